@@ -3,6 +3,7 @@ use std::path::Path;
 use anyhow::Result;
 
 use crate::config::types::WorkspaceConfig;
+use crate::hcl::variables::VariableOverrides;
 
 /// Detection result for config format.
 #[derive(Debug, PartialEq)]
@@ -31,22 +32,37 @@ pub fn detect_mode(path: &Path) -> ConfigMode {
 /// - If .yaml/.yml files exist → YAML mode (parse YAML into WorkspaceConfig)
 /// - If both exist → merge both (HCL resources + YAML orchestration)
 pub fn load_workspace(path: &Path) -> Result<WorkspaceConfig> {
+    load_workspace_with_overrides(path, &VariableOverrides::default())
+}
+
+/// Load a workspace like [`load_workspace`], applying `--var`/`--var-file` values
+/// on top of the HCL variables.
+pub fn load_workspace_with_overrides(
+    path: &Path,
+    overrides: &VariableOverrides,
+) -> Result<WorkspaceConfig> {
     let mode = detect_mode(path);
 
-    match mode {
+    let mut workspace = match mode {
         ConfigMode::Hcl => {
             tracing::info!("Detected HCL mode (.tf files)");
-            crate::hcl::parse_directory(path)
+            let mut workspace = crate::hcl::parse_directory_with_overrides(path, overrides)?;
+            crate::config::modules::expand_modules(&mut workspace, module_base_dir(path))?;
+            Ok(workspace)
         }
         ConfigMode::Yaml => {
             tracing::info!("Detected YAML mode (.yaml files)");
+            if !overrides.is_empty() {
+                tracing::warn!("--var and --var-file only apply to HCL variables; ignoring them");
+            }
             let yaml_config = crate::config::parser::load_config(&path.to_string_lossy())?;
             crate::config::yaml_converter::yaml_to_workspace(&yaml_config)
         }
         ConfigMode::Both => {
             tracing::info!("Detected mixed mode (both .tf and .yaml files)");
             // Parse HCL first (resources, providers), then overlay YAML (orchestration)
-            let mut workspace = crate::hcl::parse_directory(path)?;
+            let mut workspace = crate::hcl::parse_directory_with_overrides(path, overrides)?;
+            crate::config::modules::expand_modules(&mut workspace, module_base_dir(path))?;
 
             let yaml_config = crate::config::parser::load_config(&path.to_string_lossy())?;
             let yaml_workspace = crate::config::yaml_converter::yaml_to_workspace(&yaml_config)?;
@@ -57,6 +73,18 @@ pub fn load_workspace(path: &Path) -> Result<WorkspaceConfig> {
 
             Ok(workspace)
         }
+    }?;
+    workspace.root_dir = Some(module_base_dir(path).to_path_buf());
+    workspace.workspace_name = overrides.workspace.clone();
+    Ok(workspace)
+}
+
+/// Directory that local module sources are resolved against.
+fn module_base_dir(path: &Path) -> &Path {
+    if path.is_file() {
+        path.parent().unwrap_or(Path::new("."))
+    } else {
+        path
     }
 }
 
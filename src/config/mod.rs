@@ -1,4 +1,5 @@
 pub mod loader;
+pub mod modules;
 pub mod parser;
 pub mod types;
 pub mod validator;
@@ -0,0 +1,755 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::types::{
+    Expression, ModuleRef, ResourceConfig, TemplatePart, Value, WorkspaceConfig,
+};
+
+/// Maximum module nesting depth, guarding against self-referencing sources.
+const MAX_MODULE_DEPTH: usize = 16;
+
+/// Expand `module` blocks with local-path sources into the workspace.
+///
+/// Each module's resources and data sources are loaded from its source
+/// directory, namespaced under `module.<name>.`, and merged into the root
+/// workspace. References are rewritten so the resource DAG sees plain
+/// cross-resource references:
+///
+/// - `var.x` inside a module becomes the expression passed by the caller
+///   (or the variable's default).
+/// - `local.x` inside a module becomes the local's expression, itself
+///   rewritten in the module's scope.
+/// - `TYPE.NAME` inside a module becomes `module.<name>.TYPE.NAME`.
+/// - `module.<name>.<output>` in the caller becomes the output's expression.
+///
+/// Registry and git sources are left untouched for now.
+pub fn expand_modules(workspace: &mut WorkspaceConfig, base_dir: &Path) -> Result<()> {
+    if workspace.modules.is_empty() {
+        return Ok(());
+    }
+
+    let mut root_scope = Scope::new(Vec::new(), None, workspace);
+    let mut expanded = Expanded::default();
+    expand_calls(
+        &workspace.modules,
+        base_dir,
+        &mut root_scope,
+        &mut expanded,
+        0,
+    )?;
+
+    // Rewrite root-level references to module outputs
+    for resource in workspace
+        .resources
+        .iter_mut()
+        .chain(workspace.data_sources.iter_mut())
+    {
+        rewrite_resource(resource, &root_scope)?;
+    }
+    for output in &mut workspace.outputs {
+        output.value = rewrite_expression(&output.value, &root_scope)?;
+        output.depends_on = rewrite_depends_on(&output.depends_on, &root_scope);
+    }
+    for value in workspace.locals.values_mut() {
+        *value = rewrite_expression(value, &root_scope)?;
+    }
+    for provider in &mut workspace.providers {
+        for value in provider.config.values_mut() {
+            *value = rewrite_expression(value, &root_scope)?;
+        }
+        provider.depends_on = rewrite_depends_on(&provider.depends_on, &root_scope);
+    }
+
+    workspace.resources.extend(expanded.resources);
+    workspace.data_sources.extend(expanded.data_sources);
+
+    // Providers are process-wide; modules inherit the root configuration and only
+    // contribute provider blocks or requirements the root doesn't declare.
+    for provider in expanded.providers {
+        if !workspace
+            .providers
+            .iter()
+            .any(|p| p.name == provider.name && p.alias == provider.alias)
+        {
+            workspace.providers.push(provider);
+        }
+    }
+    if !expanded.required_providers.is_empty() {
+        let settings = workspace
+            .terraform_settings
+            .get_or_insert_with(Default::default);
+        for (name, req) in expanded.required_providers {
+            settings.required_providers.entry(name).or_insert(req);
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a module source refers to a local directory.
+pub fn is_local_source(source: &str) -> bool {
+    source.starts_with("./") || source.starts_with("../")
+}
+
+/// Resources and provider requirements collected from all expanded modules.
+#[derive(Default)]
+struct Expanded {
+    resources: Vec<ResourceConfig>,
+    data_sources: Vec<ResourceConfig>,
+    providers: Vec<crate::config::types::ProviderConfig>,
+    required_providers: HashMap<String, crate::config::types::RequiredProvider>,
+}
+
+/// Name resolution context for one module instance.
+struct Scope {
+    /// Reference prefix for this module, e.g. ["module", "vpc"]. Empty at the root.
+    prefix: Vec<String>,
+    /// Input variable substitutions. `None` at the root, where `var.*` is resolved
+    /// from variable defaults at evaluation time.
+    vars: Option<HashMap<String, Expression>>,
+    /// Local values substituted like inputs. `None` at the root.
+    locals: Option<HashMap<String, Expression>>,
+    /// Locals being substituted, innermost last, to report cycles.
+    resolving: RefCell<Vec<String>>,
+    /// "TYPE.NAME" of resources declared in this module.
+    resources: HashSet<String>,
+    /// "TYPE.NAME" of data sources declared in this module.
+    data_sources: HashSet<String>,
+    /// Outputs of already-expanded child modules, by module name.
+    module_outputs: HashMap<String, HashMap<String, Expression>>,
+    /// Fully-qualified base addresses of every resource in each child module.
+    module_addresses: HashMap<String, Vec<String>>,
+}
+
+impl Scope {
+    fn new(
+        prefix: Vec<String>,
+        vars: Option<HashMap<String, Expression>>,
+        ws: &WorkspaceConfig,
+    ) -> Self {
+        Self {
+            locals: vars.as_ref().map(|_| ws.locals.clone()),
+            resolving: RefCell::new(Vec::new()),
+            prefix,
+            vars,
+            resources: ws
+                .resources
+                .iter()
+                .map(|r| format!("{}.{}", r.resource_type, r.name))
+                .collect(),
+            data_sources: ws
+                .data_sources
+                .iter()
+                .map(|r| format!("{}.{}", r.resource_type, r.name))
+                .collect(),
+            module_outputs: HashMap::new(),
+            module_addresses: HashMap::new(),
+        }
+    }
+
+    fn address_prefix(&self) -> String {
+        self.prefix
+            .chunks(2)
+            .map(|c| format!("{}.{}.", c[0], c[1]))
+            .collect()
+    }
+}
+
+/// Expand a list of module calls declared in `dir`, in dependency order.
+fn expand_calls(
+    calls: &[ModuleRef],
+    dir: &Path,
+    scope: &mut Scope,
+    out: &mut Expanded,
+    depth: usize,
+) -> Result<()> {
+    if depth >= MAX_MODULE_DEPTH {
+        bail!(
+            "Module nesting exceeds {} levels (recursive module source?)",
+            MAX_MODULE_DEPTH
+        );
+    }
+
+    for call in order_module_calls(calls)? {
+        if !is_local_source(&call.source) {
+            tracing::debug!(
+                "Skipping module '{}': only local sources are supported (got '{}')",
+                call.name,
+                call.source
+            );
+            continue;
+        }
+
+        let module_dir: PathBuf = dir.join(&call.source);
+        let child = crate::hcl::parse_module_directory(&module_dir).context(format!(
+            "Failed to load module '{}' from {}",
+            call.name,
+            module_dir.display()
+        ))?;
+
+        // Caller-side expressions are evaluated in the caller's scope
+        let mut inputs = HashMap::new();
+        for (name, expr) in &call.variables {
+            if name == "count" || name == "for_each" {
+                bail!(
+                    "Module '{}': {} on module blocks is not supported yet",
+                    call.name,
+                    name
+                );
+            }
+            inputs.insert(name.clone(), rewrite_expression(expr, scope)?);
+        }
+
+        let mut vars = HashMap::new();
+        for var in &child.variables {
+            match inputs.remove(&var.name).or_else(|| var.default.clone()) {
+                Some(expr) => {
+                    vars.insert(var.name.clone(), expr);
+                }
+                None => bail!(
+                    "Module '{}' requires variable '{}', which was not set",
+                    call.name,
+                    var.name
+                ),
+            }
+        }
+        if let Some(name) = inputs.keys().next() {
+            bail!("Module '{}' has no variable named '{}'", call.name, name);
+        }
+
+        let mut prefix = scope.prefix.clone();
+        prefix.push("module".to_string());
+        prefix.push(call.name.clone());
+        let mut child_scope = Scope::new(prefix, Some(vars), &child);
+
+        expand_calls(
+            &child.modules,
+            &module_dir,
+            &mut child_scope,
+            out,
+            depth + 1,
+        )?;
+
+        let module_path: Vec<String> = child_scope.prefix.chunks(2).map(|c| c[1].clone()).collect();
+        // Module-level depends_on applies to every resource inside the module
+        let call_depends_on = rewrite_depends_on(&call.depends_on, scope);
+
+        let mut addresses = Vec::new();
+        for (resource, is_data) in child
+            .resources
+            .iter()
+            .map(|r| (r, false))
+            .chain(child.data_sources.iter().map(|r| (r, true)))
+        {
+            let mut resource = resource.clone();
+            rewrite_resource(&mut resource, &child_scope)?;
+            resource.module_path = module_path.clone();
+            resource.depends_on.extend(call_depends_on.iter().cloned());
+
+            let base = if is_data {
+                format!(
+                    "{}data.{}.{}",
+                    child_scope.address_prefix(),
+                    resource.resource_type,
+                    resource.name
+                )
+            } else {
+                format!(
+                    "{}{}.{}",
+                    child_scope.address_prefix(),
+                    resource.resource_type,
+                    resource.name
+                )
+            };
+            addresses.push(base);
+
+            if is_data {
+                out.data_sources.push(resource);
+            } else {
+                out.resources.push(resource);
+            }
+        }
+        // Nested modules' resources belong to this module too
+        for nested in child_scope.module_addresses.values() {
+            addresses.extend(nested.iter().cloned());
+        }
+
+        let mut outputs = HashMap::new();
+        for output in &child.outputs {
+            outputs.insert(
+                output.name.clone(),
+                rewrite_expression(&output.value, &child_scope)?,
+            );
+        }
+
+        out.providers.extend(child.providers);
+        if let Some(settings) = child.terraform_settings {
+            for (name, req) in settings.required_providers {
+                out.required_providers.entry(name).or_insert(req);
+            }
+        }
+
+        scope.module_outputs.insert(call.name.clone(), outputs);
+        scope.module_addresses.insert(call.name.clone(), addresses);
+    }
+
+    Ok(())
+}
+
+/// Order module calls so that a module is expanded after every sibling it
+/// references (through its inputs or depends_on).
+fn order_module_calls(calls: &[ModuleRef]) -> Result<Vec<&ModuleRef>> {
+    let names: HashSet<&str> = calls.iter().map(|c| c.name.as_str()).collect();
+    let deps: HashMap<&str, Vec<String>> = calls
+        .iter()
+        .map(|c| {
+            let mut refs = Vec::new();
+            for expr in c.variables.values() {
+                collect_module_refs(expr, &mut refs);
+            }
+            for dep in &c.depends_on {
+                if let Some(name) = dep.strip_prefix("module.") {
+                    refs.push(name.split('.').next().unwrap_or(name).to_string());
+                }
+            }
+            refs.retain(|r| names.contains(r.as_str()) && *r != c.name);
+            (c.name.as_str(), refs)
+        })
+        .collect();
+
+    fn visit<'a>(
+        call: &'a ModuleRef,
+        calls: &'a [ModuleRef],
+        deps: &HashMap<&str, Vec<String>>,
+        visiting: &mut HashSet<String>,
+        done: &mut HashSet<String>,
+        order: &mut Vec<&'a ModuleRef>,
+    ) -> Result<()> {
+        if done.contains(&call.name) {
+            return Ok(());
+        }
+        if !visiting.insert(call.name.clone()) {
+            bail!(
+                "Circular dependency between modules involving '{}'",
+                call.name
+            );
+        }
+        for dep in &deps[call.name.as_str()] {
+            if let Some(dep_call) = calls.iter().find(|c| &c.name == dep) {
+                visit(dep_call, calls, deps, visiting, done, order)?;
+            }
+        }
+        visiting.remove(&call.name);
+        done.insert(call.name.clone());
+        order.push(call);
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    let mut visiting = HashSet::new();
+    let mut done = HashSet::new();
+    for call in calls {
+        visit(call, calls, &deps, &mut visiting, &mut done, &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Collect the names of modules referenced as `module.NAME...` in an expression.
+fn collect_module_refs(expr: &Expression, refs: &mut Vec<String>) {
+    let mut visit = |parts: &[String]| {
+        if parts.len() >= 2 && parts[0] == "module" {
+            refs.push(parts[1].clone());
+        }
+    };
+    match expr {
+        Expression::Reference(parts) => visit(parts),
+        Expression::Literal(val) => {
+            for parts in interpolated_references(val) {
+                visit(&parts);
+            }
+        }
+        other => {
+            for child in sub_expressions(other) {
+                collect_module_refs(child, refs);
+            }
+        }
+    }
+}
+
+/// Reference paths embedded as `${...}` in literal strings (from nested blocks).
+fn interpolated_references(val: &Value) -> Vec<Vec<String>> {
+    let mut found = Vec::new();
+    match val {
+        Value::String(s) => {
+            let mut remaining = s.as_str();
+            while let Some(start) = remaining.find("${") {
+                let Some(end) = remaining[start + 2..].find('}') else {
+                    break;
+                };
+                let ref_str = &remaining[start + 2..start + 2 + end];
+                found.push(ref_str.split('.').map(|p| p.trim().to_string()).collect());
+                remaining = &remaining[start + 2 + end + 1..];
+            }
+        }
+        Value::List(items) => {
+            for item in items {
+                found.extend(interpolated_references(item));
+            }
+        }
+        Value::Map(entries) => {
+            for (_, v) in entries {
+                found.extend(interpolated_references(v));
+            }
+        }
+        _ => {}
+    }
+    found
+}
+
+/// Direct child expressions of a compound expression.
+fn sub_expressions(expr: &Expression) -> Vec<&Expression> {
+    match expr {
+        Expression::Literal(_) | Expression::Reference(_) => vec![],
+        Expression::FunctionCall { args, .. } => args.iter().collect(),
+        Expression::Conditional {
+            condition,
+            true_val,
+            false_val,
+        } => vec![condition, true_val, false_val],
+        Expression::ForExpr {
+            collection,
+            key_expr,
+            value_expr,
+            condition,
+            ..
+        } => {
+            let mut v: Vec<&Expression> = vec![collection, value_expr];
+            v.extend(key_expr.as_deref());
+            v.extend(condition.as_deref());
+            v
+        }
+        Expression::Template(parts) => parts
+            .iter()
+            .filter_map(|p| match p {
+                TemplatePart::Interpolation(e) | TemplatePart::Directive(e) => Some(e.as_ref()),
+                TemplatePart::Literal(_) => None,
+            })
+            .collect(),
+        Expression::Index { collection, key } => vec![collection, key],
+        Expression::GetAttr { object, .. } => vec![object],
+        Expression::BinaryOp { left, right, .. } => vec![left, right],
+        Expression::UnaryOp { operand, .. } => vec![operand],
+        Expression::Splat { source, each } => vec![source, each],
+    }
+}
+
+// ─── Rewriting ──────────────────────────────────────────────────────────────
+
+fn rewrite_resource(resource: &mut ResourceConfig, scope: &Scope) -> Result<()> {
+    for value in resource.attributes.values_mut() {
+        *value = rewrite_expression(value, scope)?;
+    }
+    if let Some(ref count) = resource.count {
+        resource.count = Some(rewrite_expression(count, scope)?);
+    }
+    if let Some(ref for_each) = resource.for_each {
+        resource.for_each = Some(rewrite_expression(for_each, scope)?);
+    }
+    for provisioner in &mut resource.provisioners {
+        for value in provisioner.config.values_mut() {
+            *value = rewrite_expression(value, scope)?;
+        }
+    }
+    resource.depends_on = rewrite_depends_on(&resource.depends_on, scope);
+    Ok(())
+}
+
+/// Namespace `depends_on` entries and expand `module.NAME` into the module's resources.
+fn rewrite_depends_on(depends_on: &[String], scope: &Scope) -> Vec<String> {
+    let mut result = Vec::new();
+    for dep in depends_on {
+        if let Some(name) = dep.strip_prefix("module.") {
+            if let Some(addresses) = scope.module_addresses.get(name) {
+                result.extend(addresses.iter().cloned());
+                continue;
+            }
+        }
+        let local = dep.split('[').next().unwrap_or(dep);
+        let is_local = match local.strip_prefix("data.") {
+            Some(rest) => scope.data_sources.contains(rest),
+            None => scope.resources.contains(local),
+        };
+        if is_local {
+            result.push(format!("{}{}", scope.address_prefix(), dep));
+        } else {
+            result.push(dep.clone());
+        }
+    }
+    result
+}
+
+fn rewrite_expression(expr: &Expression, scope: &Scope) -> Result<Expression> {
+    let rewrite_box =
+        |e: &Expression| -> Result<Box<Expression>> { Ok(Box::new(rewrite_expression(e, scope)?)) };
+
+    Ok(match expr {
+        Expression::Reference(parts) => {
+            rewrite_reference(parts, scope)?.unwrap_or_else(|| expr.clone())
+        }
+        Expression::Literal(val) => Expression::Literal(rewrite_value(val, scope)?),
+        Expression::FunctionCall { name, args } => Expression::FunctionCall {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|a| rewrite_expression(a, scope))
+                .collect::<Result<_>>()?,
+        },
+        Expression::Conditional {
+            condition,
+            true_val,
+            false_val,
+        } => Expression::Conditional {
+            condition: rewrite_box(condition)?,
+            true_val: rewrite_box(true_val)?,
+            false_val: rewrite_box(false_val)?,
+        },
+        Expression::ForExpr {
+            collection,
+            key_var,
+            val_var,
+            key_expr,
+            value_expr,
+            condition,
+            grouping,
+        } => Expression::ForExpr {
+            collection: rewrite_box(collection)?,
+            key_var: key_var.clone(),
+            val_var: val_var.clone(),
+            key_expr: key_expr.as_deref().map(rewrite_box).transpose()?,
+            value_expr: rewrite_box(value_expr)?,
+            condition: condition.as_deref().map(rewrite_box).transpose()?,
+            grouping: *grouping,
+        },
+        Expression::Template(parts) => Expression::Template(
+            parts
+                .iter()
+                .map(|p| {
+                    Ok(match p {
+                        TemplatePart::Literal(s) => TemplatePart::Literal(s.clone()),
+                        TemplatePart::Interpolation(e) => {
+                            TemplatePart::Interpolation(rewrite_box(e)?)
+                        }
+                        TemplatePart::Directive(e) => TemplatePart::Directive(rewrite_box(e)?),
+                    })
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Expression::Index { collection, key } => Expression::Index {
+            collection: rewrite_box(collection)?,
+            key: rewrite_box(key)?,
+        },
+        Expression::GetAttr { object, name } => Expression::GetAttr {
+            object: rewrite_box(object)?,
+            name: name.clone(),
+        },
+        Expression::BinaryOp { op, left, right } => Expression::BinaryOp {
+            op: *op,
+            left: rewrite_box(left)?,
+            right: rewrite_box(right)?,
+        },
+        Expression::UnaryOp { op, operand } => Expression::UnaryOp {
+            op: *op,
+            operand: rewrite_box(operand)?,
+        },
+        Expression::Splat { source, each } => Expression::Splat {
+            source: rewrite_box(source)?,
+            each: rewrite_box(each)?,
+        },
+    })
+}
+
+/// Rewrite a single reference path. Returns `None` when it stays unchanged.
+fn rewrite_reference(parts: &[String], scope: &Scope) -> Result<Option<Expression>> {
+    if parts.len() < 2 {
+        return Ok(None);
+    }
+
+    match parts[0].as_str() {
+        "var" => {
+            let Some(ref vars) = scope.vars else {
+                return Ok(None);
+            };
+            match vars.get(&parts[1]) {
+                Some(value) => Ok(Some(append_path(value, &parts[2..]))),
+                None => bail!(
+                    "Reference to undeclared input variable 'var.{}' in {}",
+                    parts[1],
+                    scope.address_prefix().trim_end_matches('.')
+                ),
+            }
+        }
+        "local" => {
+            let Some(ref locals) = scope.locals else {
+                return Ok(None);
+            };
+            let Some(value) = locals.get(&parts[1]) else {
+                bail!(
+                    "Reference to undeclared local value 'local.{}' in {}",
+                    parts[1],
+                    scope.address_prefix().trim_end_matches('.')
+                );
+            };
+            if scope.resolving.borrow().contains(&parts[1]) {
+                bail!(
+                    "Circular reference between local values in {}: {}",
+                    scope.address_prefix().trim_end_matches('.'),
+                    scope
+                        .resolving
+                        .borrow()
+                        .iter()
+                        .chain([&parts[1]])
+                        .map(|name| format!("local.{}", name))
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                );
+            }
+            scope.resolving.borrow_mut().push(parts[1].clone());
+            let value = rewrite_expression(value, scope);
+            scope.resolving.borrow_mut().pop();
+            Ok(Some(append_path(&value?, &parts[2..])))
+        }
+        "module" if parts.len() >= 3 => {
+            let Some(outputs) = scope.module_outputs.get(&parts[1]) else {
+                return Ok(None);
+            };
+            match outputs.get(&parts[2]) {
+                Some(value) => Ok(Some(append_path(value, &parts[3..]))),
+                None => bail!("Module '{}' has no output named '{}'", parts[1], parts[2]),
+            }
+        }
+        "data" if parts.len() >= 3 && !scope.prefix.is_empty() => {
+            if scope
+                .data_sources
+                .contains(&format!("{}.{}", parts[1], parts[2]))
+            {
+                Ok(Some(Expression::Reference(
+                    scope.prefix.iter().chain(parts).cloned().collect(),
+                )))
+            } else {
+                Ok(None)
+            }
+        }
+        _ if !scope.prefix.is_empty()
+            && scope
+                .resources
+                .contains(&format!("{}.{}", parts[0], parts[1])) =>
+        {
+            Ok(Some(Expression::Reference(
+                scope.prefix.iter().chain(parts).cloned().collect(),
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Apply a trailing attribute path to a substituted expression.
+fn append_path(value: &Expression, rest: &[String]) -> Expression {
+    if rest.is_empty() {
+        return value.clone();
+    }
+    match value {
+        Expression::Reference(parts) => {
+            Expression::Reference(parts.iter().chain(rest).cloned().collect())
+        }
+        _ => {
+            tracing::warn!(
+                "Cannot traverse .{} on a computed module value; using the whole value",
+                rest.join(".")
+            );
+            value.clone()
+        }
+    }
+}
+
+/// Evaluate an expression that references nothing (`"${var.env}-net"` once
+/// the input is substituted) to a literal, so it can be inlined into a
+/// string.
+fn fold_constant(expr: Expression) -> Expression {
+    fn is_constant(expr: &Expression) -> bool {
+        match expr {
+            Expression::Reference(_) => false,
+            Expression::Literal(val) => interpolated_references(val).is_empty(),
+            other => sub_expressions(other).into_iter().all(is_constant),
+        }
+    }
+    if matches!(expr, Expression::Literal(_)) || !is_constant(&expr) {
+        return expr;
+    }
+    let ctx = crate::executor::engine::EvalContext::plan_only(HashMap::new());
+    match crate::executor::engine::eval_expression(&expr, &ctx) {
+        Ok(serde_json::Value::String(s)) => Expression::Literal(Value::String(s)),
+        Ok(serde_json::Value::Bool(b)) => Expression::Literal(Value::Bool(b)),
+        Ok(serde_json::Value::Number(n)) => match n.as_i64() {
+            Some(i) => Expression::Literal(Value::Int(i)),
+            None => n
+                .as_f64()
+                .map_or(expr, |f| Expression::Literal(Value::Float(f))),
+        },
+        _ => expr,
+    }
+}
+
+/// Rewrite `${...}` references embedded in literal strings (from nested blocks).
+fn rewrite_value(val: &Value, scope: &Scope) -> Result<Value> {
+    Ok(match val {
+        Value::String(s) if s.contains("${") => {
+            let mut result = String::new();
+            let mut remaining = s.as_str();
+            while let Some(start) = remaining.find("${") {
+                result.push_str(&remaining[..start]);
+                let Some(end) = remaining[start + 2..].find('}') else {
+                    break;
+                };
+                let ref_str = &remaining[start + 2..start + 2 + end];
+                let parts: Vec<String> = ref_str.split('.').map(|p| p.trim().to_string()).collect();
+                let rewritten = rewrite_reference(&parts, scope)?.map(fold_constant);
+                match rewritten {
+                    Some(Expression::Reference(new_parts)) => {
+                        result.push_str(&format!("${{{}}}", new_parts.join(".")));
+                    }
+                    Some(Expression::Literal(Value::String(s))) => result.push_str(&s),
+                    Some(Expression::Literal(Value::Int(i))) => result.push_str(&i.to_string()),
+                    Some(Expression::Literal(Value::Float(f))) => result.push_str(&f.to_string()),
+                    Some(Expression::Literal(Value::Bool(b))) => result.push_str(&b.to_string()),
+                    Some(_) => {
+                        tracing::warn!(
+                            "Cannot inline complex value for ${{{}}} in a nested block",
+                            ref_str
+                        );
+                        result.push_str(&remaining[start..start + 2 + end + 1]);
+                    }
+                    None => result.push_str(&remaining[start..start + 2 + end + 1]),
+                }
+                remaining = &remaining[start + 2 + end + 1..];
+            }
+            result.push_str(remaining);
+            Value::String(result)
+        }
+        Value::List(items) => Value::List(
+            items
+                .iter()
+                .map(|v| rewrite_value(v, scope))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Map(entries) => Value::Map(
+            entries
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), rewrite_value(v, scope)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
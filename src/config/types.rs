@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
+use std::path::PathBuf;
 use std::time::Duration;
 
 // ─── Top-Level Config ───────────────────────────────────────────────────────
@@ -67,7 +68,16 @@ pub struct WorkspaceConfig {
     pub variables: Vec<VariableConfig>,
     pub outputs: Vec<OutputConfig>,
     pub locals: HashMap<String, Expression>,
+    /// `moved {}` blocks, in declaration order.
+    pub moved: Vec<MovedBlock>,
+    /// `removed {}` blocks.
+    pub removed: Vec<RemovedBlock>,
     pub terraform_settings: Option<TerraformSettings>,
+    /// Directory the configuration was loaded from.
+    pub root_dir: Option<PathBuf>,
+    /// State workspace the configuration is evaluated for, as seen by
+    /// `terraform.workspace`. `None` means the default workspace.
+    pub workspace_name: Option<String>,
 }
 
 /// terraform {} block settings (required_providers, backend, etc.)
@@ -75,6 +85,35 @@ pub struct WorkspaceConfig {
 pub struct TerraformSettings {
     pub required_providers: HashMap<String, RequiredProvider>,
     pub required_version: Option<String>,
+    pub backend: Option<BackendConfig>,
+}
+
+/// A `backend "TYPE" { ... }` block inside `terraform {}`.
+#[derive(Debug, Clone)]
+pub struct BackendConfig {
+    pub backend_type: String,
+    pub attributes: HashMap<String, Expression>,
+}
+
+impl BackendConfig {
+    /// Get a string attribute. Backend blocks only allow literal values.
+    pub fn get_str(&self, key: &str) -> Option<String> {
+        match self.attributes.get(key)? {
+            Expression::Literal(Value::String(s)) => Some(s.clone()),
+            Expression::Literal(Value::Int(n)) => Some(n.to_string()),
+            Expression::Literal(Value::Bool(b)) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Get a boolean attribute, accepting `true`/`false` strings.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.attributes.get(key)? {
+            Expression::Literal(Value::Bool(b)) => Some(*b),
+            Expression::Literal(Value::String(s)) => s.parse().ok(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -92,7 +131,13 @@ pub struct ProviderConfig {
     pub source: String,
     pub version_constraint: Option<String>,
     pub alias: Option<String>,
+    /// Per-provider concurrency cap (`max_parallel = N`). Falls back to the global
+    /// parallelism when unset.
+    pub max_parallel: Option<usize>,
     pub config: HashMap<String, Expression>,
+    /// Resources the provider must wait for (`depends_on`), on top of those
+    /// its configuration references.
+    pub depends_on: Vec<String>,
 }
 
 // ─── Resource ───────────────────────────────────────────────────────────────
@@ -107,9 +152,22 @@ pub struct ResourceConfig {
     pub for_each: Option<Expression>,
     pub depends_on: Vec<String>,
     pub lifecycle: LifecycleConfig,
+    pub timeouts: TimeoutsConfig,
     pub attributes: HashMap<String, Expression>,
     pub provisioners: Vec<ProvisionerConfig>,
     pub source_location: Option<SourceLocation>,
+    /// Names of the enclosing module calls, outermost first. Empty for the root module.
+    pub module_path: Vec<String>,
+}
+
+impl ResourceConfig {
+    /// Address prefix for the enclosing module, e.g. "module.vpc." (empty at the root).
+    pub fn module_prefix(&self) -> String {
+        self.module_path
+            .iter()
+            .map(|m| format!("module.{}.", m))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -120,20 +178,86 @@ pub struct LifecycleConfig {
     pub replace_triggered_by: Vec<String>,
 }
 
+/// How long an apply may take before oxid gives up on it, when the resource
+/// doesn't say otherwise. Matches Terraform's default operation timeout.
+pub const DEFAULT_OPERATION_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Per-operation limits from a resource's `timeouts {}` block. The block is
+/// also passed to the provider, which uses it for its own internal waiters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeoutsConfig {
+    pub create: Option<Duration>,
+    pub read: Option<Duration>,
+    pub update: Option<Duration>,
+    pub delete: Option<Duration>,
+}
+
+impl TimeoutsConfig {
+    pub fn create_timeout(&self) -> Duration {
+        self.create.unwrap_or(DEFAULT_OPERATION_TIMEOUT)
+    }
+
+    pub fn update_timeout(&self) -> Duration {
+        self.update.unwrap_or(DEFAULT_OPERATION_TIMEOUT)
+    }
+
+    pub fn delete_timeout(&self) -> Duration {
+        self.delete.unwrap_or(DEFAULT_OPERATION_TIMEOUT)
+    }
+}
+
+/// Parse a Go-style duration string as used in `timeouts` blocks,
+/// e.g. "20m", "1h30m", "90s" or "1.5h".
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut rest = input.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += number * seconds;
+    }
+    Some(Duration::from_secs_f64(total))
+}
+
 #[derive(Debug, Clone)]
 pub struct ProvisionerConfig {
     pub provisioner_type: String,
     pub config: HashMap<String, Expression>,
     pub when: ProvisionerWhen,
+    pub on_failure: ProvisionerOnFailure,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum ProvisionerWhen {
     #[default]
     Create,
     Destroy,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProvisionerOnFailure {
+    #[default]
+    Fail,
+    Continue,
+}
+
 // ─── Module Reference ───────────────────────────────────────────────────────
 
 /// A module block from HCL or a module definition from YAML.
@@ -173,6 +297,24 @@ pub struct OutputConfig {
     pub description: Option<String>,
     pub sensitive: bool,
     pub depends_on: Vec<String>,
+    pub source_location: Option<SourceLocation>,
+}
+
+/// `moved { from = ADDRESS  to = ADDRESS }` — the resource recorded in state
+/// at `from` is now declared at `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovedBlock {
+    pub from: String,
+    pub to: String,
+}
+
+/// `removed { from = ADDRESS }` — stop managing the resource at `from`. Its
+/// object is destroyed unless the block sets `lifecycle { destroy = false }`,
+/// in which case it is only dropped from state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovedBlock {
+    pub from: String,
+    pub destroy: bool,
 }
 
 // ─── Expression (the core value type) ───────────────────────────────────────
@@ -352,6 +494,17 @@ pub struct SourceLocation {
     pub config_type: ConfigType,
 }
 
+impl std::fmt::Display for SourceLocation {
+    /// Renders as `file:line:column`, or just `file` when the position is unknown.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.file)
+        } else {
+            write!(f, "{}:{}:{}", self.file, self.line, self.column)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfigType {
     Hcl,
@@ -3,6 +3,7 @@ use petgraph::graph::{DiGraph, NodeIndex};
 use std::collections::HashMap;
 
 use crate::config::types::YamlConfig;
+use crate::error::OxidError;
 
 /// A dependency graph where nodes are module names and edges represent dependencies.
 pub type ModuleGraph = DiGraph<String, ()>;
@@ -35,8 +36,13 @@ pub fn build_dag(config: &YamlConfig) -> Result<ModuleGraph> {
     }
 
     // Verify it's a DAG (no cycles)
-    if petgraph::algo::is_cyclic_directed(&graph) {
-        bail!("Circular dependency detected in module graph");
+    if let Some(cycle) = petgraph::algo::tarjan_scc(&graph)
+        .into_iter()
+        .find(|scc| scc.len() > 1 || graph.contains_edge(scc[0], scc[0]))
+    {
+        let mut names: Vec<String> = cycle.into_iter().map(|idx| graph[idx].clone()).collect();
+        names.sort();
+        return Err(OxidError::CycleDetected(names).into());
     }
 
     Ok(graph)
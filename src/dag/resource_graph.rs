@@ -1,10 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use petgraph::algo::has_path_connecting;
 use petgraph::graph::{DiGraph, NodeIndex};
 
-use crate::config::types::{Expression, ResourceConfig, ResourceIndex, WorkspaceConfig};
-use crate::executor::engine::{eval_expression, EvalContext};
+use crate::config::types::{
+    Expression, ProviderConfig, ResourceConfig, ResourceIndex, SourceLocation, WorkspaceConfig,
+};
+use crate::error::OxidError;
+use crate::executor::engine::{eval_expression, EvalContext, ResourceAction};
+use crate::provider::manager::provider_address;
 
 /// A node in the resource-level dependency graph.
 #[derive(Debug, Clone)]
@@ -14,6 +19,7 @@ pub enum DagNode {
         base_address: String,
         resource_type: String,
         name: String,
+        /// Provider address, including any alias (`hashicorp/aws.west`).
         provider_source: String,
         config: ResourceConfig,
         index: Option<ResourceIndex>,
@@ -23,6 +29,7 @@ pub enum DagNode {
         base_address: String,
         resource_type: String,
         name: String,
+        /// Provider address, including any alias (`hashicorp/aws.west`).
         provider_source: String,
         config: ResourceConfig,
         index: Option<ResourceIndex>,
@@ -31,6 +38,26 @@ pub enum DagNode {
         name: String,
         module_path: String,
     },
+    /// A provider configured from other nodes (through references in its
+    /// config or `depends_on`), so it can only be configured once they are
+    /// applied. Providers without such dependencies have no node.
+    Provider {
+        /// `provider.NAME`, or `provider.NAME.ALIAS` for an aliased block.
+        address: String,
+        /// Provider address, including any alias (`hashicorp/aws.west`).
+        provider_source: String,
+        config: ProviderConfig,
+    },
+    /// An object in state that no configured instance stands for: one
+    /// `count`/`for_each` no longer expands to, or a resource dropped with a
+    /// `removed` block. Applying it destroys the object.
+    Orphan {
+        address: String,
+        base_address: String,
+        resource_type: String,
+        /// Provider address, including any alias (`hashicorp/aws.west`).
+        provider_source: String,
+    },
 }
 
 impl DagNode {
@@ -39,6 +66,8 @@ impl DagNode {
             DagNode::Resource { address, .. } => address,
             DagNode::DataSource { address, .. } => address,
             DagNode::Output { name, .. } => name,
+            DagNode::Provider { address, .. } => address,
+            DagNode::Orphan { address, .. } => address,
         }
     }
 
@@ -47,6 +76,8 @@ impl DagNode {
             DagNode::Resource { base_address, .. } => base_address,
             DagNode::DataSource { base_address, .. } => base_address,
             DagNode::Output { name, .. } => name,
+            DagNode::Provider { address, .. } => address,
+            DagNode::Orphan { base_address, .. } => base_address,
         }
     }
 
@@ -54,9 +85,72 @@ impl DagNode {
         match self {
             DagNode::Resource { index, .. } => index.as_ref(),
             DagNode::DataSource { index, .. } => index.as_ref(),
+            DagNode::Output { .. } | DagNode::Provider { .. } | DagNode::Orphan { .. } => None,
+        }
+    }
+
+    /// Node kind as shown in graph output: `resource`, `data`, `output`, or
+    /// `provider`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DagNode::Resource { .. } | DagNode::Orphan { .. } => "resource",
+            DagNode::DataSource { .. } => "data",
+            DagNode::Output { .. } => "output",
+            DagNode::Provider { .. } => "provider",
+        }
+    }
+
+    /// Whether the node is a resource or data source instance, rather than
+    /// an output or provider.
+    pub fn is_object(&self) -> bool {
+        matches!(
+            self,
+            DagNode::Resource { .. } | DagNode::DataSource { .. } | DagNode::Orphan { .. }
+        )
+    }
+
+    /// The provider address the node runs against; outputs have none.
+    pub fn provider_source(&self) -> Option<&str> {
+        match self {
+            DagNode::Resource {
+                provider_source, ..
+            }
+            | DagNode::DataSource {
+                provider_source, ..
+            }
+            | DagNode::Provider {
+                provider_source, ..
+            }
+            | DagNode::Orphan {
+                provider_source, ..
+            } => Some(provider_source),
             DagNode::Output { .. } => None,
         }
     }
+
+    /// Address used in graph output; outputs are prefixed with `output.`.
+    pub fn graph_address(&self) -> String {
+        match self {
+            DagNode::Output { name, .. } => format!("output.{}", name),
+            _ => self.address().to_string(),
+        }
+    }
+
+    pub fn source_location(&self) -> Option<&SourceLocation> {
+        match self {
+            DagNode::Resource { config, .. } | DagNode::DataSource { config, .. } => {
+                config.source_location.as_ref()
+            }
+            DagNode::Output { .. } | DagNode::Provider { .. } | DagNode::Orphan { .. } => None,
+        }
+    }
+}
+
+/// " (at file:line:col)" suffix for error messages, empty when unknown.
+fn declared_at(location: Option<&SourceLocation>) -> String {
+    location
+        .map(|loc| format!(" (at {})", loc))
+        .unwrap_or_default()
 }
 
 /// The type of dependency between nodes.
@@ -68,10 +162,31 @@ pub enum DependencyEdge {
     Implicit,
     /// Data source dependency (data sources run during planning).
     DataDependency,
-    /// Provider dependency (provider must be configured first).
+    /// Provider dependency: a provider waits for the nodes its config
+    /// references, and the provider's resources wait for the provider.
     ProviderDep,
 }
 
+impl DependencyEdge {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyEdge::Explicit => "explicit",
+            DependencyEdge::Implicit => "implicit",
+            DependencyEdge::DataDependency => "data",
+            DependencyEdge::ProviderDep => "provider",
+        }
+    }
+
+    /// Which kind is kept when two kinds of edge join the same pair of nodes:
+    /// a declared `depends_on` outranks anything inferred.
+    fn precedence(&self) -> u8 {
+        match self {
+            DependencyEdge::Explicit => 1,
+            _ => 0,
+        }
+    }
+}
+
 /// A resource-level dependency graph.
 pub type ResourceGraph = DiGraph<DagNode, DependencyEdge>;
 
@@ -89,62 +204,32 @@ pub fn build_resource_dag(
     let mut node_map: HashMap<String, NodeIndex> = HashMap::new();
     // Maps base_address -> Vec<NodeIndex> for expanded resources
     let mut base_to_indices: HashMap<String, Vec<NodeIndex>> = HashMap::new();
+    // count and for_each see no resource states
+    let ctx = EvalContext::for_workspace(var_defaults.clone(), Default::default(), workspace);
 
     // Add all resources as nodes (expanding count/for_each)
     for resource in &workspace.resources {
-        let base_address = format!("{}.{}", resource.resource_type, resource.name);
+        let base_address = format!(
+            "{}{}.{}",
+            resource.module_prefix(),
+            resource.resource_type,
+            resource.name
+        );
         let provider_source = resolve_provider_source(resource, provider_map);
 
-        if let Some(count) = evaluate_count(resource, var_defaults)? {
-            for i in 0..count {
-                let address = format!("{}[{}]", base_address, i);
-                let node = DagNode::Resource {
-                    address: address.clone(),
-                    base_address: base_address.clone(),
-                    resource_type: resource.resource_type.clone(),
-                    name: resource.name.clone(),
-                    provider_source: provider_source.clone(),
-                    config: resource.clone(),
-                    index: Some(ResourceIndex::Count(i)),
-                };
-                let idx = graph.add_node(node);
-                node_map.insert(address, idx);
-                base_to_indices
-                    .entry(base_address.clone())
-                    .or_default()
-                    .push(idx);
-            }
-        } else if let Some(keys) = evaluate_for_each(resource, var_defaults)? {
-            for (key, _value) in &keys {
-                let address = format!("{}[\"{}\"]", base_address, key);
-                let node = DagNode::Resource {
-                    address: address.clone(),
-                    base_address: base_address.clone(),
-                    resource_type: resource.resource_type.clone(),
-                    name: resource.name.clone(),
-                    provider_source: provider_source.clone(),
-                    config: resource.clone(),
-                    index: Some(ResourceIndex::ForEach(key.clone())),
-                };
-                let idx = graph.add_node(node);
-                node_map.insert(address, idx);
-                base_to_indices
-                    .entry(base_address.clone())
-                    .or_default()
-                    .push(idx);
-            }
-        } else {
+        for (suffix, index) in expand_instances(resource, &ctx)? {
+            let address = format!("{}{}", base_address, suffix);
             let node = DagNode::Resource {
-                address: base_address.clone(),
+                address: address.clone(),
                 base_address: base_address.clone(),
                 resource_type: resource.resource_type.clone(),
                 name: resource.name.clone(),
                 provider_source: provider_source.clone(),
                 config: resource.clone(),
-                index: None,
+                index,
             };
             let idx = graph.add_node(node);
-            node_map.insert(base_address.clone(), idx);
+            node_map.insert(address, idx);
             base_to_indices
                 .entry(base_address.clone())
                 .or_default()
@@ -154,25 +239,32 @@ pub fn build_resource_dag(
 
     // Add all data sources as nodes (expanding count/for_each)
     for data_source in &workspace.data_sources {
-        let base_address = format!("data.{}.{}", data_source.resource_type, data_source.name);
+        let base_address = format!(
+            "{}data.{}.{}",
+            data_source.module_prefix(),
+            data_source.resource_type,
+            data_source.name
+        );
         let provider_source = resolve_provider_source(data_source, provider_map);
 
-        // Data sources rarely use count, but support it
-        let node = DagNode::DataSource {
-            address: base_address.clone(),
-            base_address: base_address.clone(),
-            resource_type: data_source.resource_type.clone(),
-            name: data_source.name.clone(),
-            provider_source,
-            config: data_source.clone(),
-            index: None,
-        };
-        let idx = graph.add_node(node);
-        node_map.insert(base_address.clone(), idx);
-        base_to_indices
-            .entry(base_address.clone())
-            .or_default()
-            .push(idx);
+        for (suffix, index) in expand_instances(data_source, &ctx)? {
+            let address = format!("{}{}", base_address, suffix);
+            let node = DagNode::DataSource {
+                address: address.clone(),
+                base_address: base_address.clone(),
+                resource_type: data_source.resource_type.clone(),
+                name: data_source.name.clone(),
+                provider_source: provider_source.clone(),
+                config: data_source.clone(),
+                index,
+            };
+            let idx = graph.add_node(node);
+            node_map.insert(address, idx);
+            base_to_indices
+                .entry(base_address.clone())
+                .or_default()
+                .push(idx);
+        }
     }
 
     // Add output nodes
@@ -191,14 +283,25 @@ pub fn build_resource_dag(
         .iter()
         .chain(workspace.data_sources.iter())
     {
-        let is_data = workspace
-            .data_sources
-            .iter()
-            .any(|d| d.resource_type == resource.resource_type && d.name == resource.name);
+        let is_data = workspace.data_sources.iter().any(|d| {
+            d.resource_type == resource.resource_type
+                && d.name == resource.name
+                && d.module_path == resource.module_path
+        });
         let base_address = if is_data {
-            format!("data.{}.{}", resource.resource_type, resource.name)
+            format!(
+                "{}data.{}.{}",
+                resource.module_prefix(),
+                resource.resource_type,
+                resource.name
+            )
         } else {
-            format!("{}.{}", resource.resource_type, resource.name)
+            format!(
+                "{}{}.{}",
+                resource.module_prefix(),
+                resource.resource_type,
+                resource.name
+            )
         };
 
         // Get all node indices for this resource (may be multiple if count/for_each expanded)
@@ -216,9 +319,7 @@ pub fn build_resource_dag(
             let from_indices = resolve_dep_indices(dep, &node_map, &base_to_indices);
             for &from_idx in &from_indices {
                 for &to_idx in &to_indices {
-                    if from_idx != to_idx {
-                        graph.add_edge(from_idx, to_idx, DependencyEdge::Explicit);
-                    }
+                    add_dependency(&mut graph, from_idx, to_idx, DependencyEdge::Explicit);
                 }
             }
         }
@@ -229,9 +330,19 @@ pub fn build_resource_dag(
             let from_indices = resolve_dep_indices(ref_address, &node_map, &base_to_indices);
             for &from_idx in &from_indices {
                 for &to_idx in &to_indices {
-                    if from_idx != to_idx {
-                        graph.add_edge(from_idx, to_idx, DependencyEdge::Implicit);
-                    }
+                    add_dependency(&mut graph, from_idx, to_idx, DependencyEdge::Implicit);
+                }
+            }
+        }
+
+        // A replace_triggered_by source is planned and applied first, so
+        // its change is known when the trigger is checked
+        for reference in &resource.lifecycle.replace_triggered_by {
+            let target: Vec<&str> = reference.split(['.', '[']).take(2).collect();
+            let target = format!("{}{}", resource.module_prefix(), target.join("."));
+            for from_idx in resolve_dep_indices(&target, &node_map, &base_to_indices) {
+                for &to_idx in &to_indices {
+                    add_dependency(&mut graph, from_idx, to_idx, DependencyEdge::Implicit);
                 }
             }
         }
@@ -244,7 +355,7 @@ pub fn build_resource_dag(
             for dep in &output.depends_on {
                 let from_indices = resolve_dep_indices(dep, &node_map, &base_to_indices);
                 for &from_idx in &from_indices {
-                    graph.add_edge(from_idx, to_idx, DependencyEdge::Explicit);
+                    add_dependency(&mut graph, from_idx, to_idx, DependencyEdge::Explicit);
                 }
             }
 
@@ -252,27 +363,177 @@ pub fn build_resource_dag(
             for ref_address in &refs {
                 let from_indices = resolve_dep_indices(ref_address, &node_map, &base_to_indices);
                 for &from_idx in &from_indices {
-                    graph.add_edge(from_idx, to_idx, DependencyEdge::Implicit);
+                    add_dependency(&mut graph, from_idx, to_idx, DependencyEdge::Implicit);
                 }
             }
         }
     }
 
-    // Verify no cycles
-    if petgraph::algo::is_cyclic_directed(&graph) {
-        bail!("Circular dependency detected in resource graph");
+    // Providers configured from other nodes are ordered after them, and
+    // everything using such a provider after it
+    for provider in &workspace.providers {
+        let mut dep_indices: Vec<NodeIndex> = Vec::new();
+        for dep in extract_references_from_attributes(&provider.config)
+            .iter()
+            .chain(&provider.depends_on)
+        {
+            dep_indices.extend(resolve_dep_indices(dep, &node_map, &base_to_indices));
+        }
+        if dep_indices.is_empty() {
+            continue;
+        }
+
+        let source = provider_map
+            .get(&provider.name)
+            .cloned()
+            .unwrap_or_else(|| provider.source.clone());
+        let provider_source = provider_address(&source, provider.alias.as_deref());
+        let address = match &provider.alias {
+            Some(alias) => format!("provider.{}.{}", provider.name, alias),
+            None => format!("provider.{}", provider.name),
+        };
+        let users: Vec<NodeIndex> = graph
+            .node_indices()
+            .filter(|&idx| graph[idx].is_object())
+            .filter(|&idx| graph[idx].provider_source() == Some(provider_source.as_str()))
+            .collect();
+
+        let idx = graph.add_node(DagNode::Provider {
+            address: address.clone(),
+            provider_source,
+            config: provider.clone(),
+        });
+        node_map.insert(address, idx);
+        for from_idx in dep_indices {
+            add_dependency(&mut graph, from_idx, idx, DependencyEdge::ProviderDep);
+        }
+        for to_idx in users {
+            add_dependency(&mut graph, idx, to_idx, DependencyEdge::ProviderDep);
+        }
     }
 
+    // Verify no cycles
+    topological_order(&graph)?;
+
     Ok((graph, node_map))
 }
 
-/// Resolve a dependency address to node indices. Tries exact match first, then base_address.
+/// Add a node destroying each of `orphans` to `graph`, ordered as a destroy
+/// is: whatever references an orphan is applied first, so it no longer does
+/// when the orphan goes, and where that leaves no cycle an orphan is
+/// destroyed before anything it references is changed. Orphans also wait for
+/// their provider's node, when it has one.
+pub fn add_orphans(
+    graph: &mut ResourceGraph,
+    workspace: &WorkspaceConfig,
+    orphans: Vec<DagNode>,
+) -> Result<()> {
+    if orphans.is_empty() {
+        return Ok(());
+    }
+    let configured: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|&idx| graph[idx].is_object())
+        .collect();
+    let added: Vec<NodeIndex> = orphans.into_iter().map(|o| graph.add_node(o)).collect();
+
+    // What each instance references: its block's `depends_on` and the
+    // references in its attributes. Orphans use the block they were
+    // expanded from, when the configuration still has it.
+    let references: HashMap<NodeIndex, Vec<String>> = configured
+        .iter()
+        .chain(&added)
+        .map(|&idx| {
+            let config = match &graph[idx] {
+                DagNode::Resource { config, .. } | DagNode::DataSource { config, .. } => {
+                    Some(config)
+                }
+                node => workspace.resources.iter().find(|r| {
+                    format!("{}{}.{}", r.module_prefix(), r.resource_type, r.name)
+                        == node.base_address()
+                }),
+            };
+            let mut refs = Vec::new();
+            if let Some(config) = config {
+                refs.extend(config.depends_on.iter().cloned());
+                refs.extend(extract_references_from_attributes(&config.attributes));
+            }
+            (idx, refs)
+        })
+        .collect();
+    let refers_to = |from: NodeIndex, to: &DagNode| {
+        references[&from].iter().any(|reference| {
+            reference == to.address()
+                || reference == to.base_address()
+                || (reference.starts_with("module.")
+                    && to.address().starts_with(&format!("{}.", reference)))
+        })
+    };
+
+    for &orphan in &added {
+        for &other in configured.iter().chain(&added) {
+            if other != orphan && refers_to(other, &graph[orphan]) {
+                add_dependency(graph, other, orphan, DependencyEdge::Implicit);
+            }
+        }
+    }
+    // Ordering an orphan before what it references is only possible when
+    // that does not already wait for the orphan through its dependents
+    for &orphan in &added {
+        for &other in &configured {
+            if matches!(graph[other], DagNode::Resource { .. })
+                && refers_to(orphan, &graph[other])
+                && !has_path_connecting(&*graph, other, orphan, None)
+            {
+                add_dependency(graph, orphan, other, DependencyEdge::Implicit);
+            }
+        }
+
+        let providers: Vec<NodeIndex> = graph
+            .node_indices()
+            .filter(|&idx| matches!(graph[idx], DagNode::Provider { .. }))
+            .filter(|&idx| graph[idx].provider_source() == graph[orphan].provider_source())
+            .collect();
+        for provider in providers {
+            // A provider configured from something the orphan is destroyed
+            // before keeps the configuration planning gave it
+            if !has_path_connecting(&*graph, orphan, provider, None) {
+                add_dependency(graph, provider, orphan, DependencyEdge::ProviderDep);
+            }
+        }
+    }
+
+    topological_order(graph)?;
+    Ok(())
+}
+
+/// Add an edge making `to` depend on `from`. Each pair of nodes gets at most
+/// one edge, however many times one references the other; the kind with the
+/// highest precedence wins.
+fn add_dependency(graph: &mut ResourceGraph, from: NodeIndex, to: NodeIndex, kind: DependencyEdge) {
+    if from == to {
+        return;
+    }
+    match graph.find_edge(from, to) {
+        Some(edge) => {
+            if kind.precedence() > graph[edge].precedence() {
+                graph[edge] = kind;
+            }
+        }
+        None => {
+            graph.add_edge(from, to, kind);
+        }
+    }
+}
+
+/// Resolve a dependency address to node indices. Tries exact match first, then
+/// base_address, then a module prefix.
 fn resolve_dep_indices(
     dep: &str,
     node_map: &HashMap<String, NodeIndex>,
     base_to_indices: &HashMap<String, Vec<NodeIndex>>,
 ) -> Vec<NodeIndex> {
-    // Exact match (e.g. "aws_vpc.main" or "aws_instance.main[0]")
+    // Exact match (e.g. "aws_vpc.main", "aws_instance.main[0]" or "data.aws_ami.ubuntu")
     if let Some(&idx) = node_map.get(dep) {
         return vec![idx];
     }
@@ -280,26 +541,62 @@ fn resolve_dep_indices(
     if let Some(indices) = base_to_indices.get(dep) {
         return indices.clone();
     }
+    // Module match (e.g. "module.network" resolves to every node inside the module)
+    if dep.starts_with("module.") {
+        let prefix = format!("{}.", dep);
+        let mut indices: Vec<NodeIndex> = node_map
+            .iter()
+            .filter(|(address, _)| address.starts_with(&prefix))
+            .map(|(_, &idx)| idx)
+            .collect();
+        indices.sort();
+        return indices;
+    }
     vec![]
 }
 
-/// Evaluate the count expression and return the count, or None if no count is set.
-fn evaluate_count(
+/// The instances a resource or data block expands to, as (address suffix,
+/// index) pairs: one per `count`/`for_each` instance, or a single unindexed
+/// instance when neither is set.
+fn expand_instances(
     resource: &ResourceConfig,
-    var_defaults: &HashMap<String, serde_json::Value>,
-) -> Result<Option<usize>> {
+    ctx: &EvalContext,
+) -> Result<Vec<(String, Option<ResourceIndex>)>> {
+    if let Some(count) = evaluate_count(resource, ctx)? {
+        return Ok((0..count)
+            .map(|i| (format!("[{}]", i), Some(ResourceIndex::Count(i))))
+            .collect());
+    }
+    if let Some(keys) = evaluate_for_each(resource, ctx)? {
+        return Ok(keys
+            .into_iter()
+            .map(|(key, _value)| (format!("[\"{}\"]", key), Some(ResourceIndex::ForEach(key))))
+            .collect());
+    }
+    Ok(vec![(String::new(), None)])
+}
+
+/// Evaluate the count expression and return the count, or None if no count is set.
+fn evaluate_count(resource: &ResourceConfig, ctx: &EvalContext) -> Result<Option<usize>> {
     let Some(ref count_expr) = resource.count else {
         return Ok(None);
     };
-    let ctx = EvalContext::plan_only(var_defaults.clone());
-    let val = eval_expression(count_expr, &ctx);
+    let val = eval_expression(count_expr, ctx).with_context(|| {
+        format!(
+            "Invalid count for {}.{}{}",
+            resource.resource_type,
+            resource.name,
+            declared_at(resource.source_location.as_ref())
+        )
+    })?;
     match val {
         serde_json::Value::Number(n) => {
             let count = n.as_u64().ok_or_else(|| {
                 anyhow::anyhow!(
-                    "count must be a non-negative integer for {}.{}, got {}",
+                    "count must be a non-negative integer for {}.{}{}, got {}",
                     resource.resource_type,
                     resource.name,
+                    declared_at(resource.source_location.as_ref()),
                     n
                 )
             })?;
@@ -307,14 +604,17 @@ fn evaluate_count(
         }
         serde_json::Value::Null => {
             bail!(
-                "Cannot determine count for {}.{}: count expression resolved to null (missing variable?)",
-                resource.resource_type, resource.name
+                "Cannot determine count for {}.{}{}: count expression resolved to null (missing variable?)",
+                resource.resource_type,
+                resource.name,
+                declared_at(resource.source_location.as_ref())
             );
         }
         _ => bail!(
-            "count for {}.{} must evaluate to a number, got {:?}",
+            "count for {}.{}{} must evaluate to a number, got {:?}",
             resource.resource_type,
             resource.name,
+            declared_at(resource.source_location.as_ref()),
             val
         ),
     }
@@ -323,30 +623,46 @@ fn evaluate_count(
 /// Evaluate the for_each expression and return key-value pairs, or None if not set.
 fn evaluate_for_each(
     resource: &ResourceConfig,
-    var_defaults: &HashMap<String, serde_json::Value>,
+    ctx: &EvalContext,
 ) -> Result<Option<Vec<(String, serde_json::Value)>>> {
     let Some(ref for_each_expr) = resource.for_each else {
         return Ok(None);
     };
-    let ctx = EvalContext::plan_only(var_defaults.clone());
-    let val = eval_expression(for_each_expr, &ctx);
+    let val = eval_expression(for_each_expr, ctx).with_context(|| {
+        format!(
+            "Invalid for_each for {}.{}{}",
+            resource.resource_type,
+            resource.name,
+            declared_at(resource.source_location.as_ref())
+        )
+    })?;
     match val {
         serde_json::Value::Object(map) => Ok(Some(map.into_iter().collect())),
-        serde_json::Value::Array(arr) => Ok(Some(
-            arr.into_iter()
-                .map(|v| {
-                    let key = match &v {
-                        serde_json::Value::String(s) => s.clone(),
-                        other => other.to_string(),
-                    };
-                    (key, v)
-                })
-                .collect(),
-        )),
+        serde_json::Value::Array(arr) => {
+            let mut instances: Vec<(String, serde_json::Value)> = Vec::with_capacity(arr.len());
+            for v in arr {
+                let key = match &v {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                if instances.iter().any(|(k, _)| *k == key) {
+                    bail!(
+                        "for_each for {}.{}{} has the duplicate key \"{}\"; wrap the list in toset() to remove duplicates",
+                        resource.resource_type,
+                        resource.name,
+                        declared_at(resource.source_location.as_ref()),
+                        key
+                    );
+                }
+                instances.push((key, v));
+            }
+            Ok(Some(instances))
+        }
         _ => bail!(
-            "for_each for {}.{} must evaluate to a map or set, got {:?}",
+            "for_each for {}.{}{} must evaluate to a map or set, got {:?}",
             resource.resource_type,
             resource.name,
+            declared_at(resource.source_location.as_ref()),
             val
         ),
     }
@@ -380,9 +696,15 @@ fn collect_references(expr: &Expression, refs: &mut Vec<String>) {
                     "data" if parts.len() >= 3 => {
                         refs.push(format!("data.{}.{}", parts[1], parts[2]));
                     }
-                    "module" if parts.len() >= 2 => {
-                        // Module references are tracked but don't resolve to
-                        // individual resource addresses (modules are opaque).
+                    "module" => {
+                        // After module expansion, remaining module references point
+                        // at namespaced resources: module.NAME[.module.NAME].TYPE.NAME
+                        let (prefix, rest) = split_module_prefix(parts);
+                        if rest.len() >= 3 && rest[0] == "data" {
+                            refs.push(format!("{}data.{}.{}", prefix, rest[1], rest[2]));
+                        } else if rest.len() >= 2 {
+                            refs.push(format!("{}{}.{}", prefix, rest[0], rest[1]));
+                        }
                     }
                     _ => {
                         // resource_type.name pattern
@@ -471,13 +793,21 @@ fn collect_references_from_value(val: &crate::config::types::Value, refs: &mut V
             while let Some(start) = remaining.find("${") {
                 if let Some(end) = remaining[start + 2..].find('}') {
                     let ref_str = &remaining[start + 2..start + 2 + end];
-                    let parts: Vec<&str> = ref_str.split('.').collect();
+                    let parts: Vec<String> = ref_str.split('.').map(|p| p.to_string()).collect();
                     if parts.len() >= 2 {
-                        match parts[0] {
+                        match parts[0].as_str() {
                             "var" | "local" | "each" | "count" | "path" | "terraform" | "self" => {}
                             "data" if parts.len() >= 3 => {
                                 refs.push(format!("data.{}.{}", parts[1], parts[2]));
                             }
+                            "module" => {
+                                let (prefix, rest) = split_module_prefix(&parts);
+                                if rest.len() >= 3 && rest[0] == "data" {
+                                    refs.push(format!("{}data.{}.{}", prefix, rest[1], rest[2]));
+                                } else if rest.len() >= 2 {
+                                    refs.push(format!("{}{}.{}", prefix, rest[0], rest[1]));
+                                }
+                            }
                             _ => {
                                 refs.push(format!("{}.{}", parts[0], parts[1]));
                             }
@@ -503,19 +833,36 @@ fn collect_references_from_value(val: &crate::config::types::Value, refs: &mut V
     }
 }
 
-/// Resolve the provider source for a resource.
+/// Split a reference like `module.a.module.b.aws_vpc.main.id` into its module
+/// address prefix ("module.a.module.b.") and the remaining parts.
+pub fn split_module_prefix(parts: &[String]) -> (String, &[String]) {
+    let mut prefix = String::new();
+    let mut rest = parts;
+    while rest.len() >= 2 && rest[0] == "module" {
+        prefix.push_str(&format!("module.{}.", rest[1]));
+        rest = &rest[2..];
+    }
+    (prefix, rest)
+}
+
+/// Resolve the provider address for a resource.
 /// Uses `provider_ref` if set, otherwise derives from resource type prefix.
-fn resolve_provider_source(
+/// An aliased reference (`aws.west`) yields an aliased address (`hashicorp/aws.west`)
+/// so the resource is routed to that provider configuration.
+pub fn resolve_provider_source(
     resource: &ResourceConfig,
     provider_map: &HashMap<String, String>,
 ) -> String {
     if let Some(ref provider_ref) = resource.provider_ref {
-        // Strip alias: "aws.west" → "aws"
-        let base = provider_ref.split('.').next().unwrap_or(provider_ref);
-        provider_map
+        let (base, alias) = match provider_ref.split_once('.') {
+            Some((base, alias)) => (base, Some(alias)),
+            None => (provider_ref.as_str(), None),
+        };
+        let source = provider_map
             .get(base)
             .cloned()
-            .unwrap_or_else(|| format!("hashicorp/{}", base))
+            .unwrap_or_else(|| format!("hashicorp/{}", base));
+        provider_address(&source, alias)
     } else {
         // Derive from resource type: "aws_vpc" → "aws"
         let prefix = resource
@@ -532,12 +879,23 @@ fn resolve_provider_source(
 
 /// Get a topological ordering of the graph (dependencies before dependents).
 pub fn topological_order(graph: &ResourceGraph) -> Result<Vec<NodeIndex>> {
-    petgraph::algo::toposort(graph, None).map_err(|cycle| {
-        anyhow::anyhow!(
-            "Cycle detected involving {:?}",
-            graph[cycle.node_id()].address()
-        )
-    })
+    petgraph::algo::toposort(graph, None)
+        .map_err(|cycle| OxidError::CycleDetected(cycle_addresses(graph, cycle.node_id())).into())
+}
+
+/// Addresses of the strongly connected component containing `start`, which
+/// toposort reported as part of a cycle. Sorted so the error is stable.
+fn cycle_addresses(graph: &ResourceGraph, start: NodeIndex) -> Vec<String> {
+    let component = petgraph::algo::tarjan_scc(graph)
+        .into_iter()
+        .find(|scc| scc.contains(&start))
+        .unwrap_or_else(|| vec![start]);
+    let mut addresses: Vec<String> = component
+        .into_iter()
+        .map(|idx| graph[idx].address().to_string())
+        .collect();
+    addresses.sort();
+    addresses
 }
 
 /// Get the reverse topological ordering (for destroy operations).
@@ -549,25 +907,101 @@ pub fn reverse_topological_order(graph: &ResourceGraph) -> Result<Vec<NodeIndex>
 
 /// Generate DOT representation of the resource graph.
 pub fn to_dot(graph: &ResourceGraph) -> String {
+    to_dot_with_actions(graph, &HashMap::new())
+}
+
+/// Fill color for a node with a planned action; `None` keeps the color of
+/// the node's kind.
+fn action_color(action: &ResourceAction) -> Option<&'static str> {
+    match action {
+        ResourceAction::Create => Some("#8fd98f"),
+        ResourceAction::Update => Some("#f2e27a"),
+        ResourceAction::Replace => Some("#f5b041"),
+        ResourceAction::Delete => Some("#f08080"),
+        ResourceAction::NoOp => Some("#e8e8e8"),
+        ResourceAction::Read => None,
+    }
+}
+
+/// Like [`to_dot`], but nodes found in `actions` (keyed by address) are
+/// filled by their planned action: create green, update yellow, replace
+/// orange, delete red, and unchanged grey.
+pub fn to_dot_with_actions(
+    graph: &ResourceGraph,
+    actions: &HashMap<String, ResourceAction>,
+) -> String {
     let mut dot = String::from("digraph resources {\n");
     dot.push_str("  rankdir=TB;\n");
     dot.push_str("  node [shape=box, style=filled];\n\n");
 
     for idx in graph.node_indices() {
         let node = &graph[idx];
-        let (label, color) = match node {
+        let (label, color) = dot_label(node);
+        let color = actions
+            .get(node.address())
+            .and_then(action_color)
+            .unwrap_or(color);
+        dot.push_str(&format!(
+            "  n{} [label=\"{}\", fillcolor=\"{}\"];\n",
+            idx.index(),
+            label,
+            color
+        ));
+    }
+
+    dot.push('\n');
+    push_dot_edges(&mut dot, graph);
+    dot.push_str("}\n");
+    dot
+}
+
+/// Like [`to_dot`], but resources, data sources and provider nodes are
+/// grouped into one `subgraph cluster_N` per provider address, so each
+/// provider's footprint is visible at a glance. Outputs stay outside any
+/// cluster, and edges are drawn across clusters as usual.
+pub fn to_dot_by_provider(graph: &ResourceGraph) -> String {
+    let mut clusters: BTreeMap<&str, Vec<NodeIndex>> = BTreeMap::new();
+    let mut unclustered = Vec::new();
+    for idx in graph.node_indices() {
+        match &graph[idx] {
             DagNode::Resource {
-                address,
-                resource_type,
-                ..
-            } => (format!("{}\\n{}", address, resource_type), "#a8d8a8"),
-            DagNode::DataSource {
-                address,
-                resource_type,
-                ..
-            } => (format!("data.{}\\n{}", address, resource_type), "#a8c8d8"),
-            DagNode::Output { name, .. } => (format!("output.{}", name), "#d8d8a8"),
-        };
+                provider_source, ..
+            }
+            | DagNode::DataSource {
+                provider_source, ..
+            }
+            | DagNode::Provider {
+                provider_source, ..
+            }
+            | DagNode::Orphan {
+                provider_source, ..
+            } => clusters.entry(provider_source).or_default().push(idx),
+            DagNode::Output { .. } => unclustered.push(idx),
+        }
+    }
+
+    let mut dot = String::from("digraph resources {\n");
+    dot.push_str("  rankdir=TB;\n");
+    dot.push_str("  node [shape=box, style=filled];\n\n");
+
+    for (i, (provider, nodes)) in clusters.iter().enumerate() {
+        dot.push_str(&format!("  subgraph cluster_{} {{\n", i));
+        dot.push_str(&format!("    label=\"{}\";\n", provider));
+        dot.push_str("    style=rounded;\n");
+        for idx in nodes {
+            let (label, color) = dot_label(&graph[*idx]);
+            dot.push_str(&format!(
+                "    n{} [label=\"{}\", fillcolor=\"{}\"];\n",
+                idx.index(),
+                label,
+                color
+            ));
+        }
+        dot.push_str("  }\n\n");
+    }
+
+    for idx in unclustered {
+        let (label, color) = dot_label(&graph[idx]);
         dot.push_str(&format!(
             "  n{} [label=\"{}\", fillcolor=\"{}\"];\n",
             idx.index(),
@@ -577,7 +1011,36 @@ pub fn to_dot(graph: &ResourceGraph) -> String {
     }
 
     dot.push('\n');
+    push_dot_edges(&mut dot, graph);
+    dot.push_str("}\n");
+    dot
+}
+
+/// DOT label and default fill color for a node, by kind.
+fn dot_label(node: &DagNode) -> (String, &'static str) {
+    match node {
+        DagNode::Resource {
+            address,
+            resource_type,
+            ..
+        }
+        | DagNode::Orphan {
+            address,
+            resource_type,
+            ..
+        } => (format!("{}\\n{}", address, resource_type), "#a8d8a8"),
+        DagNode::DataSource {
+            address,
+            resource_type,
+            ..
+        } => (format!("data.{}\\n{}", address, resource_type), "#a8c8d8"),
+        DagNode::Output { name, .. } => (format!("output.{}", name), "#d8d8a8"),
+        DagNode::Provider { address, .. } => (address.clone(), "#d8b8d8"),
+    }
+}
 
+/// Append every edge of `graph`, styled by dependency kind.
+fn push_dot_edges(dot: &mut String, graph: &ResourceGraph) {
     for edge in graph.edge_indices() {
         if let Some((from, to)) = graph.edge_endpoints(edge) {
             let style = match &graph[edge] {
@@ -594,7 +1057,87 @@ pub fn to_dot(graph: &ResourceGraph) -> String {
             ));
         }
     }
+}
 
-    dot.push_str("}\n");
-    dot
+/// Generate a JSON representation of the resource graph.
+pub fn to_json(graph: &ResourceGraph) -> serde_json::Value {
+    let nodes: Vec<serde_json::Value> = graph
+        .node_indices()
+        .map(|idx| {
+            let node = &graph[idx];
+            serde_json::json!({
+                "address": node.graph_address(),
+                "kind": node.kind(),
+                "provider_source": node.provider_source(),
+            })
+        })
+        .collect();
+
+    let edges: Vec<serde_json::Value> = graph
+        .edge_indices()
+        .filter_map(|edge| {
+            let (from, to) = graph.edge_endpoints(edge)?;
+            Some(serde_json::json!({
+                "from": graph[from].graph_address(),
+                "to": graph[to].graph_address(),
+                "kind": graph[edge].as_str(),
+            }))
+        })
+        .collect();
+
+    serde_json::json!({ "nodes": nodes, "edges": edges })
+}
+
+/// Generate a Mermaid flowchart of the resource graph.
+///
+/// Uses the same fill colors as [`to_dot`]; edge styles map to Mermaid's
+/// arrow types (solid, dotted, thick).
+pub fn to_mermaid(graph: &ResourceGraph) -> String {
+    let mut out = String::from("graph TD\n");
+    out.push_str("  classDef resource fill:#a8d8a8,stroke:#333\n");
+    out.push_str("  classDef data fill:#a8c8d8,stroke:#333\n");
+    out.push_str("  classDef output fill:#d8d8a8,stroke:#333\n");
+    out.push_str("  classDef provider fill:#d8b8d8,stroke:#333\n");
+
+    for idx in graph.node_indices() {
+        let node = &graph[idx];
+        let label = match node {
+            DagNode::Resource {
+                address,
+                resource_type,
+                ..
+            }
+            | DagNode::DataSource {
+                address,
+                resource_type,
+                ..
+            }
+            | DagNode::Orphan {
+                address,
+                resource_type,
+                ..
+            } => format!("{}<br/>{}", address, resource_type),
+            DagNode::Output { name, .. } => format!("output.{}", name),
+            DagNode::Provider { address, .. } => address.clone(),
+        };
+        out.push_str(&format!(
+            "  n{}[\"{}\"]:::{}\n",
+            idx.index(),
+            label.replace('"', "#quot;"),
+            node.kind()
+        ));
+    }
+
+    for edge in graph.edge_indices() {
+        if let Some((from, to)) = graph.edge_endpoints(edge) {
+            let arrow = match &graph[edge] {
+                DependencyEdge::Explicit => "-->",
+                DependencyEdge::Implicit | DependencyEdge::DataDependency => "-.->",
+                DependencyEdge::ProviderDep => "==>",
+            };
+            out.push_str(&format!("  n{} {} n{}\n", from.index(), arrow, to.index()));
+        }
+    }
+
+    out
 }
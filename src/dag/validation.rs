@@ -1,8 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use anyhow::Result;
 use colored::Colorize;
 
 use crate::config::types::*;
+use crate::error::OxidError;
+use crate::executor::engine::{eval_expression, EvalContext};
 
 /// Validation error for count/for_each reference issues.
 #[derive(Debug)]
@@ -10,6 +13,8 @@ pub struct ValidationError {
     pub source: String,
     pub ref_address: String,
     pub attr_accessed: String,
+    /// Where the referring block is declared, when known.
+    pub location: Option<SourceLocation>,
 }
 
 /// Print validation errors with colored, formatted output.
@@ -24,7 +29,15 @@ pub fn print_validation_errors(errors: &[ValidationError]) {
             "Missing resource instance key".bold()
         );
         eprintln!();
-        eprintln!("  {} {}", "on".dimmed(), err.source.yellow());
+        match &err.location {
+            Some(loc) => eprintln!(
+                "  {} {}: {}",
+                "on".dimmed(),
+                loc.to_string().bold(),
+                err.source.yellow()
+            ),
+            None => eprintln!("  {} {}", "on".dimmed(), err.source.yellow()),
+        }
         eprintln!();
         eprintln!(
             "  Because {} has {} set, its attributes must be",
@@ -56,6 +69,33 @@ pub fn print_validation_errors(errors: &[ValidationError]) {
     );
 }
 
+/// Validate count/for_each references, printing any errors and failing with
+/// [`OxidError::ValidationFailed`].
+pub fn check_count_references(workspace: &WorkspaceConfig) -> Result<()> {
+    let errors = validate_count_references(workspace);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    print_validation_errors(&errors);
+    Err(OxidError::ValidationFailed(
+        errors
+            .iter()
+            .map(|e| {
+                format!(
+                    "{}: {} has count or for_each set; use {}[count.index].{} or {}[*].{}",
+                    e.source,
+                    e.ref_address,
+                    e.ref_address,
+                    e.attr_accessed,
+                    e.ref_address,
+                    e.attr_accessed
+                )
+            })
+            .collect(),
+    )
+    .into())
+}
+
 /// Validate that references to resources with count/for_each include an index or splat.
 ///
 /// Terraform requires that when a resource has `count` or `for_each`, any reference to it
@@ -87,6 +127,7 @@ pub fn validate_count_references(workspace: &WorkspaceConfig) -> Vec<ValidationE
     // Check resource attributes
     for resource in &workspace.resources {
         let source_addr = format!("{}.{}", resource.resource_type, resource.name);
+        let first_error = errors.len();
         for (attr_name, expr) in &resource.attributes {
             check_expression(
                 expr,
@@ -114,11 +155,13 @@ pub fn validate_count_references(workspace: &WorkspaceConfig) -> Vec<ValidationE
                 &mut errors,
             );
         }
+        set_location(&mut errors[first_error..], &resource.source_location);
     }
 
     // Check data source attributes
     for data_source in &workspace.data_sources {
         let source_addr = format!("data.{}.{}", data_source.resource_type, data_source.name);
+        let first_error = errors.len();
         for (attr_name, expr) in &data_source.attributes {
             check_expression(
                 expr,
@@ -128,11 +171,13 @@ pub fn validate_count_references(workspace: &WorkspaceConfig) -> Vec<ValidationE
                 &mut errors,
             );
         }
+        set_location(&mut errors[first_error..], &data_source.source_location);
     }
 
     // Check output values
     for output in &workspace.outputs {
         let source_addr = format!("output.{}", output.name);
+        let first_error = errors.len();
         check_expression(
             &output.value,
             &multi_instance,
@@ -140,6 +185,7 @@ pub fn validate_count_references(workspace: &WorkspaceConfig) -> Vec<ValidationE
             None,
             &mut errors,
         );
+        set_location(&mut errors[first_error..], &output.source_location);
     }
 
     // Check locals
@@ -151,6 +197,12 @@ pub fn validate_count_references(workspace: &WorkspaceConfig) -> Vec<ValidationE
     errors
 }
 
+fn set_location(errors: &mut [ValidationError], location: &Option<SourceLocation>) {
+    for err in errors {
+        err.location = location.clone();
+    }
+}
+
 fn check_expression(
     expr: &Expression,
     multi_instance: &HashSet<String>,
@@ -270,6 +322,7 @@ fn push_missing_key_error(
         source,
         ref_address: ref_address.to_string(),
         attr_accessed: attr_accessed.to_string(),
+        location: None,
     });
 }
 
@@ -310,6 +363,384 @@ fn check_value(
     }
 }
 
+// ─── Diagnostics ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One finding of `oxid validate`, in the shape of its `--json` report.
+#[derive(Debug, serde::Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub summary: String,
+    pub detail: String,
+    /// The block the finding is about (`aws_instance.web`, `var.region`).
+    pub address: Option<String>,
+    /// Where that block is declared, as `file:line:column`, when known.
+    pub source: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(summary: &str, detail: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            summary: summary.to_string(),
+            detail,
+            address: None,
+            source: None,
+        }
+    }
+
+    fn at(mut self, address: String, location: Option<&SourceLocation>) -> Self {
+        self.address = Some(address);
+        self.source = location.map(|l| l.to_string());
+        self
+    }
+}
+
+/// Every problem `oxid validate` checks for, as diagnostics. `values` are the
+/// effective variable values.
+pub fn diagnose(
+    workspace: &WorkspaceConfig,
+    values: &HashMap<String, serde_json::Value>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for provider in &workspace.providers {
+        if provider.source.is_empty() {
+            diagnostics.push(
+                Diagnostic::error(
+                    "Empty provider source",
+                    format!("Provider '{}' has empty source.", provider.name),
+                )
+                .at(format!("provider.{}", provider.name), None),
+            );
+        }
+    }
+    for resource in &workspace.resources {
+        if resource.resource_type.is_empty() {
+            diagnostics.push(
+                Diagnostic::error(
+                    "Empty resource type",
+                    format!("Resource '{}' has empty type.", resource.name),
+                )
+                .at(resource.name.clone(), resource.source_location.as_ref()),
+            );
+        }
+    }
+
+    diagnostics.extend(unknown_depends_on(workspace));
+
+    for e in validate_count_references(workspace) {
+        // `source` reads "aws_eip.ip, in attribute \"instance\""
+        let (address, context) = e.source.split_once(", ").unwrap_or((&e.source, ""));
+        let mut detail = format!(
+            "Because {} has \"count\" or \"for_each\" set, its attributes must be accessed \
+             on specific instances: use {}[count.index].{} or {}[*].{}.",
+            e.ref_address, e.ref_address, e.attr_accessed, e.ref_address, e.attr_accessed
+        );
+        if !context.is_empty() {
+            detail = format!("{} ({})", detail, context);
+        }
+        diagnostics.push(
+            Diagnostic::error("Missing resource instance key", detail)
+                .at(address.to_string(), e.location.as_ref()),
+        );
+    }
+
+    for var in workspace.variables.iter().filter(|v| v.default.is_none()) {
+        diagnostics.push(
+            Diagnostic::error(
+                "No value for required variable",
+                format!(
+                    "The variable \"{}\" has no default and no value was given.",
+                    var.name
+                ),
+            )
+            .at(format!("var.{}", var.name), None),
+        );
+    }
+    for e in validate_variables(workspace, values) {
+        diagnostics.push(
+            Diagnostic::error("Invalid value for variable", e.message)
+                .at(format!("var.{}", e.variable), None),
+        );
+    }
+
+    diagnostics
+}
+
+/// `depends_on` entries naming no resource, data source, or module, as warnings.
+pub fn unknown_depends_on(workspace: &WorkspaceConfig) -> Vec<Diagnostic> {
+    let addresses: HashSet<String> = workspace
+        .resources
+        .iter()
+        .map(|r| format!("{}{}.{}", r.module_prefix(), r.resource_type, r.name))
+        .chain(
+            workspace
+                .data_sources
+                .iter()
+                .map(|d| format!("{}data.{}.{}", d.module_prefix(), d.resource_type, d.name)),
+        )
+        .collect();
+    let is_known = |dep: &str| {
+        let base = dep.split('[').next().unwrap_or(dep);
+        addresses.contains(base)
+            || match dep.strip_prefix("module.") {
+                Some(name) => {
+                    workspace.modules.iter().any(|m| m.name == name)
+                        || addresses
+                            .iter()
+                            .any(|a| a.starts_with(&format!("{}.", dep)))
+                }
+                None => false,
+            }
+    };
+
+    let mut diagnostics = Vec::new();
+    for (resource, is_data) in workspace
+        .resources
+        .iter()
+        .map(|r| (r, false))
+        .chain(workspace.data_sources.iter().map(|d| (d, true)))
+    {
+        let address = format!(
+            "{}{}{}.{}",
+            resource.module_prefix(),
+            if is_data { "data." } else { "" },
+            resource.resource_type,
+            resource.name
+        );
+        for dep in resource.depends_on.iter().filter(|d| !is_known(d)) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                summary: "Unknown depends_on reference".to_string(),
+                detail: format!(
+                    "depends_on references {}, which is not a declared resource, data source, \
+                     or module.",
+                    dep
+                ),
+                address: Some(address.clone()),
+                source: resource.source_location.as_ref().map(|l| l.to_string()),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// The `oxid validate --json` report: `valid` is false when any diagnostic
+/// is an error.
+pub fn validation_report(diagnostics: &[Diagnostic]) -> serde_json::Value {
+    serde_json::json!({
+        "valid": !diagnostics.iter().any(|d| d.severity == Severity::Error),
+        "error_count": diagnostics.iter().filter(|d| d.severity == Severity::Error).count(),
+        "warning_count": diagnostics.iter().filter(|d| d.severity == Severity::Warning).count(),
+        "diagnostics": diagnostics,
+    })
+}
+
+// ─── Variable Validation ────────────────────────────────────────────────────
+
+/// A variable value rejected by one of its `validation` blocks.
+#[derive(Debug)]
+pub struct VariableValidationError {
+    pub variable: String,
+    pub message: String,
+}
+
+/// Print variable validation errors with colored, formatted output.
+pub fn print_variable_validation_errors(errors: &[VariableValidationError]) {
+    for (i, err) in errors.iter().enumerate() {
+        if i > 0 {
+            eprintln!();
+        }
+        eprintln!(
+            "{} {}",
+            "Error:".red().bold(),
+            "Invalid value for variable".bold()
+        );
+        eprintln!();
+        eprintln!(
+            "  {} {}",
+            "on".dimmed(),
+            format!("var.{}", err.variable).yellow()
+        );
+        eprintln!();
+        eprintln!("  {}", err.message);
+    }
+    eprintln!();
+    eprintln!(
+        "{} Configuration contains {} error(s). Fix the errors above to continue.",
+        "Error:".red().bold(),
+        errors.len().to_string().red().bold()
+    );
+}
+
+/// Check variable values against their `validation` blocks, printing any
+/// failures and failing with [`OxidError::ValidationFailed`].
+pub fn check_variables(
+    workspace: &WorkspaceConfig,
+    values: &HashMap<String, serde_json::Value>,
+) -> Result<()> {
+    let errors = validate_variables(workspace, values);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    print_variable_validation_errors(&errors);
+    Err(OxidError::ValidationFailed(
+        errors
+            .iter()
+            .map(|e| format!("var.{}: {}", e.variable, e.message))
+            .collect(),
+    )
+    .into())
+}
+
+/// Check each variable's effective value against its `validation` conditions.
+///
+/// Conditions are evaluated with only `var.<name>` bound, so a condition that
+/// refers to anything other than the variable itself is reported as an error.
+/// Variables without a value are skipped.
+pub fn validate_variables(
+    workspace: &WorkspaceConfig,
+    values: &HashMap<String, serde_json::Value>,
+) -> Vec<VariableValidationError> {
+    let mut errors = Vec::new();
+
+    for var in &workspace.variables {
+        let Some(value) = values.get(&var.name) else {
+            continue;
+        };
+
+        for rule in &var.validation {
+            let mut foreign = Vec::new();
+            collect_foreign_references(&rule.condition, &var.name, &HashSet::new(), &mut foreign);
+            if let Some(reference) = foreign.first() {
+                errors.push(VariableValidationError {
+                    variable: var.name.clone(),
+                    message: format!(
+                        "The condition for variable \"{}\" can only refer to the variable itself, \
+                         not {}.",
+                        var.name, reference
+                    ),
+                });
+                continue;
+            }
+
+            let ctx = EvalContext::plan_only(HashMap::from([(var.name.clone(), value.clone())]));
+            match eval_expression(&rule.condition, &ctx) {
+                Ok(serde_json::Value::Bool(true)) => {}
+                Ok(serde_json::Value::Bool(false)) => errors.push(VariableValidationError {
+                    variable: var.name.clone(),
+                    message: rule.error_message.clone(),
+                }),
+                Err(e) => errors.push(VariableValidationError {
+                    variable: var.name.clone(),
+                    message: format!(
+                        "The condition for variable \"{}\" could not be evaluated: {:#}",
+                        var.name, e
+                    ),
+                }),
+                Ok(other) => errors.push(VariableValidationError {
+                    variable: var.name.clone(),
+                    message: format!(
+                        "The condition for variable \"{}\" must be a bool, got {}.",
+                        var.name, other
+                    ),
+                }),
+            }
+        }
+    }
+
+    errors
+}
+
+/// Collect references in a validation condition other than `var.<name>` and
+/// the iteration variables of enclosing `for` expressions.
+fn collect_foreign_references(
+    expr: &Expression,
+    name: &str,
+    bound: &HashSet<String>,
+    foreign: &mut Vec<String>,
+) {
+    match expr {
+        Expression::Reference(parts) => {
+            let is_self = parts.len() >= 2 && parts[0] == "var" && parts[1] == name;
+            let is_bound = parts.first().map(|p| bound.contains(p)).unwrap_or(false);
+            if !is_self && !is_bound {
+                foreign.push(parts.join("."));
+            }
+        }
+        Expression::Literal(_) => {}
+        Expression::FunctionCall { args, .. } => {
+            for arg in args {
+                collect_foreign_references(arg, name, bound, foreign);
+            }
+        }
+        Expression::Conditional {
+            condition,
+            true_val,
+            false_val,
+        } => {
+            collect_foreign_references(condition, name, bound, foreign);
+            collect_foreign_references(true_val, name, bound, foreign);
+            collect_foreign_references(false_val, name, bound, foreign);
+        }
+        Expression::ForExpr {
+            collection,
+            key_var,
+            val_var,
+            key_expr,
+            value_expr,
+            condition,
+            ..
+        } => {
+            collect_foreign_references(collection, name, bound, foreign);
+            let mut inner = bound.clone();
+            inner.insert(val_var.clone());
+            if let Some(k) = key_var {
+                inner.insert(k.clone());
+            }
+            if let Some(k) = key_expr {
+                collect_foreign_references(k, name, &inner, foreign);
+            }
+            collect_foreign_references(value_expr, name, &inner, foreign);
+            if let Some(c) = condition {
+                collect_foreign_references(c, name, &inner, foreign);
+            }
+        }
+        Expression::Template(parts) => {
+            for part in parts {
+                if let TemplatePart::Interpolation(e) | TemplatePart::Directive(e) = part {
+                    collect_foreign_references(e, name, bound, foreign);
+                }
+            }
+        }
+        Expression::Index { collection, key } => {
+            collect_foreign_references(collection, name, bound, foreign);
+            collect_foreign_references(key, name, bound, foreign);
+        }
+        Expression::GetAttr { object, .. } => {
+            collect_foreign_references(object, name, bound, foreign);
+        }
+        Expression::BinaryOp { left, right, .. } => {
+            collect_foreign_references(left, name, bound, foreign);
+            collect_foreign_references(right, name, bound, foreign);
+        }
+        Expression::UnaryOp { operand, .. } => {
+            collect_foreign_references(operand, name, bound, foreign);
+        }
+        Expression::Splat { source, each } => {
+            collect_foreign_references(source, name, bound, foreign);
+            collect_foreign_references(each, name, bound, foreign);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,9 +756,11 @@ mod tests {
                 for_each: None,
                 depends_on: vec![],
                 lifecycle: LifecycleConfig::default(),
+                timeouts: TimeoutsConfig::default(),
                 attributes: HashMap::new(),
                 provisioners: vec![],
                 source_location: None,
+                module_path: vec![],
             }],
             outputs: vec![OutputConfig {
                 name: "instance_id".to_string(),
@@ -339,6 +772,7 @@ mod tests {
                 description: None,
                 sensitive: false,
                 depends_on: vec![],
+                source_location: None,
             }],
             ..Default::default()
         }
@@ -409,9 +843,11 @@ mod tests {
                 for_each: None,
                 depends_on: vec![],
                 lifecycle: LifecycleConfig::default(),
+                timeouts: TimeoutsConfig::default(),
                 attributes: HashMap::new(),
                 provisioners: vec![],
                 source_location: None,
+                module_path: vec![],
             }],
             outputs: vec![OutputConfig {
                 name: "vpc_id".to_string(),
@@ -423,6 +859,7 @@ mod tests {
                 description: None,
                 sensitive: false,
                 depends_on: vec![],
+                source_location: None,
             }],
             ..Default::default()
         };
@@ -441,9 +878,11 @@ mod tests {
                 for_each: None,
                 depends_on: vec![],
                 lifecycle: LifecycleConfig::default(),
+                timeouts: TimeoutsConfig::default(),
                 attributes: HashMap::new(),
                 provisioners: vec![],
                 source_location: None,
+                module_path: vec![],
             }],
             outputs: vec![OutputConfig {
                 name: "region".to_string(),
@@ -451,6 +890,7 @@ mod tests {
                 description: None,
                 sensitive: false,
                 depends_on: vec![],
+                source_location: None,
             }],
             ..Default::default()
         };
@@ -1,16 +1,18 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use colored::Colorize;
 use dashmap::DashMap;
 use petgraph::graph::NodeIndex;
 use tokio::sync::{mpsc, Semaphore};
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
 use super::resource_graph::{DagNode, ResourceGraph};
+use crate::error::OxidError;
 
 /// Status of a node during execution.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,9 +21,22 @@ pub enum NodeStatus {
     Running,
     Succeeded,
     Failed(String),
+    /// A destroy-then-create replacement destroyed the old object but
+    /// could not create the new one.
+    ReplaceFailedAfterDestroy(String),
     Skipped(String),
 }
 
+impl NodeStatus {
+    /// Whether the node ran and failed, however it failed.
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            NodeStatus::Failed(_) | NodeStatus::ReplaceFailedAfterDestroy(_)
+        )
+    }
+}
+
 /// Result of executing a single node.
 #[derive(Debug)]
 pub struct NodeResult {
@@ -29,6 +44,11 @@ pub struct NodeResult {
     pub address: String,
     pub status: NodeStatus,
     pub outputs: Option<serde_json::Value>,
+    /// How long the executor ran, not counting time queued for a slot;
+    /// zero for nodes that never ran.
+    pub duration: Duration,
+    /// Provider source of a resource or data source.
+    pub provider: Option<String>,
 }
 
 /// Operation mode for the walker — controls progress messages.
@@ -38,6 +58,9 @@ pub enum WalkMode {
     Destroy,
 }
 
+/// Skip reason for nodes that never started because the walk was cancelled.
+pub const CANCELLED: &str = "Cancelled";
+
 /// Message sent back from worker tasks to the walker.
 enum WalkerMessage {
     NodeCompleted(NodeResult),
@@ -63,11 +86,34 @@ struct RunningNode {
 /// Event-driven DAG walker that executes nodes as their dependencies are satisfied.
 pub struct DagWalker {
     max_parallelism: usize,
+    /// Optional concurrency caps keyed by provider source (e.g. "hashicorp/aws").
+    provider_limits: HashMap<String, usize>,
+    /// Cancelling stops new nodes from starting; running ones finish.
+    cancel: CancellationToken,
 }
 
 impl DagWalker {
     pub fn new(max_parallelism: usize) -> Self {
-        Self { max_parallelism }
+        Self {
+            max_parallelism,
+            provider_limits: HashMap::new(),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Stop the walk gracefully when `cancel` fires: nodes already executing
+    /// run to completion, every node not yet started is skipped as
+    /// [`CANCELLED`], and `walk` returns once the running ones are done.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Limit how many nodes of a given provider may run at once, on top of the
+    /// global parallelism. Providers without an entry only use the global limit.
+    pub fn with_provider_limits(mut self, provider_limits: HashMap<String, usize>) -> Self {
+        self.provider_limits = provider_limits;
+        self
     }
 
     /// Walk the DAG, executing nodes via the provided executor function.
@@ -82,16 +128,23 @@ impl DagWalker {
             return Ok(Vec::new());
         }
 
-        // Count only resource/data nodes for progress display (skip outputs)
+        // Count only resource/data nodes for progress display (skip outputs
+        // and providers)
         let resource_count = graph
             .node_indices()
-            .filter(|&idx| !matches!(graph[idx], DagNode::Output { .. }))
+            .filter(|&idx| graph[idx].is_object())
             .count();
 
         // Wall clock for the entire operation — shows parallelism in timestamps
         let wall_clock = Arc::new(Instant::now());
 
         let semaphore = Arc::new(Semaphore::new(self.max_parallelism));
+        let provider_semaphores: Arc<HashMap<String, Arc<Semaphore>>> = Arc::new(
+            self.provider_limits
+                .iter()
+                .map(|(source, &limit)| (source.clone(), Arc::new(Semaphore::new(limit.max(1)))))
+                .collect(),
+        );
         let statuses: Arc<DashMap<NodeIndex, NodeStatus>> = Arc::new(DashMap::new());
         let (tx, mut rx) = mpsc::channel::<WalkerMessage>(node_count);
 
@@ -167,23 +220,46 @@ impl DagWalker {
                 graph,
                 &executor,
                 &semaphore,
+                &provider_semaphores,
                 &statuses,
                 &tx,
                 mode,
                 &start_times,
                 &running_info,
                 &wall_clock,
+                &self.cancel,
             );
         }
 
-        // Process completions until all nodes are done
+        // Process completions until all nodes are done, or until nothing is
+        // running after a cancellation
+        let mut cancelled = false;
         while completed_count < node_count {
-            let msg = rx.recv().await;
+            if cancelled
+                && !statuses
+                    .iter()
+                    .any(|entry| *entry.value() == NodeStatus::Running)
+            {
+                break;
+            }
+            let msg = tokio::select! {
+                msg = rx.recv() => msg,
+                _ = self.cancel.cancelled(), if !cancelled => {
+                    cancelled = true;
+                    println!(
+                        "{}",
+                        "Cancelling: no new operations will start; waiting for running ones to finish..."
+                            .yellow()
+                            .bold()
+                    );
+                    continue;
+                }
+            };
             match msg {
                 Some(WalkerMessage::NodeCompleted(result)) => {
                     let node_idx = result.node_index;
                     let succeeded = result.status == NodeStatus::Succeeded;
-                    let is_output = matches!(graph[node_idx], DagNode::Output { .. });
+                    let quiet = !graph[node_idx].is_object();
 
                     // Calculate elapsed time for this node
                     let elapsed_secs = start_times
@@ -197,7 +273,7 @@ impl DagWalker {
 
                     statuses.insert(node_idx, result.status.clone());
                     completed_count += 1;
-                    if !is_output {
+                    if !quiet {
                         resource_completed += 1;
                     }
 
@@ -210,7 +286,7 @@ impl DagWalker {
                         .map(|s| s.to_string());
 
                     // User-facing progress (skip outputs)
-                    if !is_output {
+                    if !quiet {
                         match &result.status {
                             NodeStatus::Succeeded => {
                                 let verb_past = node_info
@@ -240,8 +316,29 @@ impl DagWalker {
                                     err.red(),
                                 );
                             }
+                            NodeStatus::ReplaceFailedAfterDestroy(err) => {
+                                println!(
+                                    "{}: {} after {} — {}",
+                                    result.address.bold(),
+                                    "DESTROYED, NOT RECREATED".red().bold(),
+                                    format_duration(elapsed_secs),
+                                    err.red(),
+                                );
+                            }
+                            NodeStatus::Skipped(reason) => {
+                                println!("{}: {}", result.address.bold(), reason.yellow());
+                            }
                             _ => {}
                         }
+                    } else if let (DagNode::Provider { .. }, NodeStatus::Failed(err)) =
+                        (&graph[node_idx], &result.status)
+                    {
+                        println!(
+                            "{}: {} — {}",
+                            result.address.bold(),
+                            "Configuration FAILED".red().bold(),
+                            err.red(),
+                        );
                     }
 
                     debug!(
@@ -252,7 +349,7 @@ impl DagWalker {
                         "Node completed"
                     );
 
-                    if succeeded {
+                    if succeeded && !cancelled {
                         if let Some(deps) = dependents.get(&node_idx) {
                             for &dependent_idx in deps {
                                 let all_deps_met = dependencies
@@ -273,24 +370,26 @@ impl DagWalker {
                                         graph,
                                         &executor,
                                         &semaphore,
+                                        &provider_semaphores,
                                         &statuses,
                                         &tx,
                                         mode,
                                         &start_times,
                                         &running_info,
                                         &wall_clock,
+                                        &self.cancel,
                                     );
                                 }
                             }
                         }
-                    } else {
+                    } else if result.status.is_failure() {
                         let skipped = collect_transitive_dependents(node_idx, &dependents);
                         for &skip_idx in &skipped {
                             let skip_address = graph[skip_idx].address().to_string();
-                            let skip_is_output = matches!(graph[skip_idx], DagNode::Output { .. });
+                            let skip_quiet = !graph[skip_idx].is_object();
                             let reason = format!("Dependency '{}' failed", result.address);
 
-                            if !skip_is_output {
+                            if !skip_quiet {
                                 resource_completed += 1;
                                 println!(
                                     "{}: {} — {}",
@@ -308,6 +407,8 @@ impl DagWalker {
                                 address: skip_address,
                                 status: NodeStatus::Skipped(reason),
                                 outputs: None,
+                                duration: Duration::ZERO,
+                                provider: None,
                             });
                         }
                     }
@@ -318,6 +419,38 @@ impl DagWalker {
             }
         }
 
+        // Nodes that never started were cancelled
+        if cancelled {
+            let mut never_started: Vec<NodeIndex> = statuses
+                .iter()
+                .filter(|entry| *entry.value() == NodeStatus::Pending)
+                .map(|entry| *entry.key())
+                .collect();
+            never_started.sort();
+            let cancelled_resources = never_started
+                .iter()
+                .filter(|&&idx| graph[idx].is_object())
+                .count();
+            if cancelled_resources > 0 {
+                println!(
+                    "{}",
+                    format!("{} resource(s) not started: cancelled", cancelled_resources).yellow()
+                );
+            }
+            for idx in never_started {
+                let status = NodeStatus::Skipped(CANCELLED.to_string());
+                statuses.insert(idx, status.clone());
+                results.push(NodeResult {
+                    node_index: idx,
+                    address: graph[idx].address().to_string(),
+                    status,
+                    outputs: None,
+                    duration: Duration::ZERO,
+                    provider: None,
+                });
+            }
+        }
+
         // Stop the heartbeat timer
         all_done.store(true, Ordering::Relaxed);
         heartbeat_handle.abort();
@@ -333,29 +466,49 @@ fn spawn_node(
     graph: &ResourceGraph,
     executor: &Arc<NodeExecutor>,
     semaphore: &Arc<Semaphore>,
+    provider_semaphores: &Arc<HashMap<String, Arc<Semaphore>>>,
     statuses: &Arc<DashMap<NodeIndex, NodeStatus>>,
     tx: &mpsc::Sender<WalkerMessage>,
     mode: WalkMode,
     start_times: &Arc<DashMap<NodeIndex, Instant>>,
     running_info: &Arc<DashMap<NodeIndex, RunningNode>>,
     _wall_clock: &Arc<Instant>,
+    cancel: &CancellationToken,
 ) {
     let node = graph[idx].clone();
     let address = node.address().to_string();
-    let is_output = matches!(node, DagNode::Output { .. });
+    let quiet = !node.is_object();
     let is_data = matches!(node, DagNode::DataSource { .. });
+    let is_orphan = matches!(node, DagNode::Orphan { .. });
     let executor = Arc::clone(executor);
     let semaphore = Arc::clone(semaphore);
+    let provider = match &node {
+        DagNode::Resource {
+            provider_source, ..
+        }
+        | DagNode::DataSource {
+            provider_source, ..
+        }
+        | DagNode::Orphan {
+            provider_source, ..
+        } => Some(provider_source.clone()),
+        DagNode::Output { .. } | DagNode::Provider { .. } => None,
+    };
+    let provider_semaphore = provider
+        .as_ref()
+        .and_then(|source| provider_semaphores.get(source).cloned());
     let statuses = Arc::clone(statuses);
     let tx = tx.clone();
+    let cancel = cancel.clone();
 
     statuses.insert(idx, NodeStatus::Running);
     start_times.insert(idx, Instant::now());
 
-    // Show progress for resources only (not outputs)
-    if !is_output {
+    // Show progress for resources only (not outputs or providers)
+    if !quiet {
         let (verb_progress, verb_past) = match mode {
             WalkMode::Destroy => ("Destroying", "Destruction"),
+            WalkMode::Apply if is_orphan => ("Destroying", "Destruction"),
             WalkMode::Apply if is_data => ("Reading", "Read"),
             WalkMode::Apply => ("Creating", "Creation"),
         };
@@ -373,9 +526,30 @@ fn spawn_node(
     }
 
     tokio::spawn(async move {
+        // Take the provider slot first so throttled providers don't hold global slots
+        let _provider_permit = match provider_semaphore {
+            Some(ref sem) => Some(sem.acquire().await.unwrap()),
+            None => None,
+        };
         let _permit = semaphore.acquire().await.unwrap();
 
+        // Queued behind the semaphores when the walk was cancelled
+        if cancel.is_cancelled() {
+            let node_result = NodeResult {
+                node_index: idx,
+                address,
+                status: NodeStatus::Skipped(CANCELLED.to_string()),
+                outputs: None,
+                duration: Duration::ZERO,
+                provider,
+            };
+            let _ = tx.send(WalkerMessage::NodeCompleted(node_result)).await;
+            return;
+        }
+
+        let started = Instant::now();
         let result = executor(idx, node).await;
+        let duration = started.elapsed();
 
         let node_result = match result {
             Ok(outputs) => NodeResult {
@@ -383,12 +557,21 @@ fn spawn_node(
                 address,
                 status: NodeStatus::Succeeded,
                 outputs,
+                duration,
+                provider,
             },
             Err(e) => NodeResult {
                 node_index: idx,
                 address,
-                status: NodeStatus::Failed(e.to_string()),
+                status: match OxidError::find(&e) {
+                    Some(OxidError::ReplaceFailedAfterDestroy { source, .. }) => {
+                        NodeStatus::ReplaceFailedAfterDestroy(source.to_string())
+                    }
+                    _ => NodeStatus::Failed(e.to_string()),
+                },
                 outputs: None,
+                duration,
+                provider,
             },
         };
 
@@ -396,18 +579,12 @@ fn spawn_node(
     });
 }
 
-/// Format seconds into a human-readable duration string.
-fn format_duration(secs: u64) -> String {
-    if secs < 60 {
-        format!("{}s", secs)
-    } else {
-        let mins = secs / 60;
-        let remaining = secs % 60;
-        if remaining == 0 {
-            format!("{}m", mins)
-        } else {
-            format!("{}m{}s", mins, remaining)
-        }
+/// Format seconds into a human-readable duration string (`42s`, `3m`, `3m12s`).
+pub fn format_duration(secs: u64) -> String {
+    match (secs / 60, secs % 60) {
+        (0, secs) => format!("{}s", secs),
+        (mins, 0) => format!("{}m", mins),
+        (mins, remaining) => format!("{}m{}s", mins, remaining),
     }
 }
 
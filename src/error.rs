@@ -0,0 +1,67 @@
+use crate::state::models::Lock;
+
+/// Errors callers may want to tell apart, e.g. to retry on a held lock or
+/// to report a cycle differently from a provider failure.
+///
+/// Functions keep returning `anyhow::Result`; these travel inside the
+/// `anyhow::Error` and can be recovered with [`OxidError::find`] or
+/// `downcast_ref`, even after `.context(...)` has been added on top.
+#[derive(Debug, thiserror::Error)]
+pub enum OxidError {
+    /// A configuration file isn't valid HCL or JSON.
+    #[error("Failed to parse {language} in: {location}")]
+    ConfigParse {
+        language: &'static str,
+        /// `path`, or `path:line:column` when the parser reports a position.
+        location: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// A provider binary could not be started or didn't complete the
+    /// plugin handshake.
+    #[error("Failed to start provider {provider}")]
+    ProviderStart {
+        provider: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// A provider RPC returned error diagnostics.
+    #[error("Provider errors:\n{}", .0.join("\n"))]
+    ProviderDiagnostics(Vec<String>),
+
+    /// Another operation holds the workspace lock.
+    #[error(
+        "State is locked by another operation.\n\n  Lock ID:   {}\n  Locked by: {}\n  Operation: {}\n  Locked at: {}\n\n\
+         If no other oxid process is running, release it with: oxid force-unlock {}",
+        .0.lock_id, .0.locked_by, .0.operation, .0.locked_at, .0.lock_id
+    )]
+    StateLocked(Lock),
+
+    /// Dependencies form a cycle through the listed addresses or module
+    /// names.
+    #[error("Circular dependency detected between {}", .0.join(", "))]
+    CycleDetected(Vec<String>),
+
+    /// A replacement destroyed the old object but failed to create the new
+    /// one, so the resource is gone from both infrastructure and state.
+    #[error("{address} was destroyed, but creating its replacement failed")]
+    ReplaceFailedAfterDestroy {
+        address: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// Configuration or variable values failed validation. The individual
+    /// messages have already been printed.
+    #[error("Validation failed.")]
+    ValidationFailed(Vec<String>),
+}
+
+impl OxidError {
+    /// The first `OxidError` in an error's chain, if any.
+    pub fn find(err: &anyhow::Error) -> Option<&OxidError> {
+        err.chain().find_map(|e| e.downcast_ref::<OxidError>())
+    }
+}
@@ -0,0 +1,145 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use anyhow::{bail, Context, Result};
+
+/// A parsed CIDR prefix, with the address widened to 128 bits.
+#[derive(Debug, Clone, Copy)]
+struct Prefix {
+    network: u128,
+    len: u32,
+    /// 32 for IPv4, 128 for IPv6.
+    bits: u32,
+}
+
+impl Prefix {
+    fn parse(prefix: &str) -> Result<Self> {
+        let (addr, len) = prefix
+            .split_once('/')
+            .with_context(|| format!("invalid CIDR address: {}", prefix))?;
+        let addr: IpAddr = addr
+            .parse()
+            .with_context(|| format!("invalid CIDR address: {}", prefix))?;
+        let len: u32 = len
+            .parse()
+            .with_context(|| format!("invalid CIDR address: {}", prefix))?;
+        let (value, bits) = match addr {
+            IpAddr::V4(v4) => (u32::from(v4) as u128, 32),
+            IpAddr::V6(v6) => (u128::from(v6), 128),
+        };
+        if len > bits {
+            bail!("invalid CIDR address: {}", prefix);
+        }
+        Ok(Self {
+            network: value & mask(len, bits),
+            len,
+            bits,
+        })
+    }
+
+    fn host_bits(&self) -> u32 {
+        self.bits - self.len
+    }
+
+    fn format_addr(&self, value: u128) -> String {
+        if self.bits == 32 {
+            Ipv4Addr::from(value as u32).to_string()
+        } else {
+            Ipv6Addr::from(value).to_string()
+        }
+    }
+}
+
+/// Netmask with the top `len` of `bits` bits set.
+fn mask(len: u32, bits: u32) -> u128 {
+    let full = if bits == 128 {
+        u128::MAX
+    } else {
+        (1u128 << bits) - 1
+    };
+    if len == 0 {
+        0
+    } else {
+        (u128::MAX << (128 - len)) >> (128 - bits) & full
+    }
+}
+
+/// `cidrhost(prefix, hostnum)`: the address of host number `hostnum` within
+/// `prefix`. Negative numbers count back from the end of the range.
+pub fn cidr_host(prefix: &str, hostnum: i64) -> Result<String> {
+    let p = Prefix::parse(prefix)?;
+    let host_bits = p.host_bits();
+    let fits = |n: u128| host_bits >= 128 || n < (1u128 << host_bits);
+    let offset = if hostnum >= 0 {
+        let n = hostnum as u128;
+        if !fits(n) {
+            bail!(
+                "prefix of {} does not accommodate a host numbered {}",
+                p.len,
+                hostnum
+            );
+        }
+        n
+    } else {
+        let back = hostnum.unsigned_abs() as u128;
+        if host_bits < 128 && back > (1u128 << host_bits) {
+            bail!(
+                "prefix of {} does not accommodate a host numbered {}",
+                p.len,
+                hostnum
+            );
+        }
+        if host_bits >= 128 {
+            // The range size 2^128 wraps to 0 for ::/0
+            0u128.wrapping_sub(back)
+        } else {
+            (1u128 << host_bits) - back
+        }
+    };
+    Ok(p.format_addr(p.network | offset))
+}
+
+/// `cidrsubnet(prefix, newbits, netnum)`: subnet number `netnum` of `prefix`
+/// extended by `newbits` bits.
+pub fn cidr_subnet(prefix: &str, newbits: i64, netnum: i64) -> Result<String> {
+    let p = Prefix::parse(prefix)?;
+    if newbits < 0 {
+        bail!("newbits must not be negative");
+    }
+    if netnum < 0 {
+        bail!("netnum must not be negative");
+    }
+    let new_len = p.len as i64 + newbits;
+    if new_len > p.bits as i64 {
+        bail!(
+            "insufficient address space to extend prefix of {} by {}",
+            p.len,
+            newbits
+        );
+    }
+    let new_len = new_len as u32;
+    let newbits = newbits as u32;
+    let netnum = netnum as u128;
+    if newbits < 128 && netnum >= (1u128 << newbits) {
+        bail!(
+            "prefix extension of {} does not accommodate a subnet numbered {}",
+            newbits,
+            netnum
+        );
+    }
+    let shift = p.bits - new_len;
+    let network = if shift >= 128 { 0 } else { netnum << shift };
+    Ok(format!(
+        "{}/{}",
+        p.format_addr(p.network | network),
+        new_len
+    ))
+}
+
+/// `cidrnetmask(prefix)`: the dotted-decimal netmask of an IPv4 prefix.
+pub fn cidr_netmask(prefix: &str) -> Result<String> {
+    let p = Prefix::parse(prefix)?;
+    if p.bits != 32 {
+        bail!("only IPv4 networks are supported");
+    }
+    Ok(p.format_addr(mask(p.len, p.bits)))
+}
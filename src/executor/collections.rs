@@ -0,0 +1,223 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+/// `setproduct(lists...)`: every combination of one element from each list,
+/// as a list of tuples. Any empty input makes the product empty.
+pub fn set_product(lists: &[Value]) -> Result<Value> {
+    if lists.len() < 2 {
+        bail!("at least two arguments are required");
+    }
+    let mut product: Vec<Vec<Value>> = vec![vec![]];
+    for (i, list) in lists.iter().enumerate() {
+        let items = as_list(list, &format!("argument {}", i + 1))?;
+        product = product
+            .into_iter()
+            .flat_map(|prefix| {
+                items.iter().map(move |item| {
+                    let mut tuple = prefix.clone();
+                    tuple.push(item.clone());
+                    tuple
+                })
+            })
+            .collect();
+    }
+    Ok(Value::Array(
+        product.into_iter().map(Value::Array).collect(),
+    ))
+}
+
+/// `zipmap(keys, values)`: an object pairing each key with the value at the
+/// same index. Later duplicates of a key win.
+pub fn zipmap(keys: &Value, values: &Value) -> Result<Value> {
+    let keys = as_list(keys, "keys")?;
+    let values = as_list(values, "values")?;
+    if keys.len() != values.len() {
+        bail!(
+            "number of keys ({}) does not match number of values ({})",
+            keys.len(),
+            values.len()
+        );
+    }
+    let mut map = serde_json::Map::new();
+    for (i, (key, value)) in keys.iter().zip(values).enumerate() {
+        let key = match key {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => bail!("element {} of keys must be a string", i),
+        };
+        map.insert(key, value.clone());
+    }
+    Ok(Value::Object(map))
+}
+
+/// `chunklist(list, size)`: `list` split into lists of at most `size`
+/// elements. A size of zero yields the whole list as a single chunk.
+pub fn chunklist(list: &Value, size: i64) -> Result<Value> {
+    let items = as_list(list, "list")?;
+    if size < 0 {
+        bail!("the size argument must be positive");
+    }
+    if items.is_empty() {
+        return Ok(Value::Array(vec![]));
+    }
+    if size == 0 {
+        return Ok(Value::Array(vec![Value::Array(items.to_vec())]));
+    }
+    Ok(Value::Array(
+        items
+            .chunks(size as usize)
+            .map(|chunk| Value::Array(chunk.to_vec()))
+            .collect(),
+    ))
+}
+
+/// `slice(list, start, end)`: the elements from index `start` up to but not
+/// including `end`. Unlike string slicing there is no counting from the end;
+/// a negative or out-of-range index is an error.
+pub fn slice(list: &Value, start: i64, end: i64) -> Result<Value> {
+    let items = as_list(list, "list")?;
+    if start < 0 {
+        bail!(
+            "start index must be greater than or equal to 0, got {}",
+            start
+        );
+    }
+    if end > items.len() as i64 {
+        bail!(
+            "end index must be less than or equal to the length of the list ({}), got {}",
+            items.len(),
+            end
+        );
+    }
+    if start > end {
+        bail!(
+            "start index ({}) must be less than or equal to end index ({})",
+            start,
+            end
+        );
+    }
+    Ok(Value::Array(items[start as usize..end as usize].to_vec()))
+}
+
+/// `contains(list, value)`: whether any element of `list` equals `value`.
+pub fn contains(list: &Value, value: &Value) -> Result<Value> {
+    let items = as_list(list, "list")?;
+    Ok(Value::Bool(
+        items.iter().any(|item| values_equal(item, value)),
+    ))
+}
+
+/// `index(list, value)`: the position of the first element equal to
+/// `value`. A value that isn't in the list is an error.
+pub fn index(list: &Value, value: &Value) -> Result<Value> {
+    let items = as_list(list, "list")?;
+    match items.iter().position(|item| values_equal(item, value)) {
+        Some(i) => Ok(Value::from(i)),
+        None => bail!("item {} not found in list", value),
+    }
+}
+
+/// `sort(list)`: the strings of `list` in lexical order. Every element must
+/// be a string.
+pub fn sort(list: &Value) -> Result<Value> {
+    let items = as_list(list, "list")?;
+    let mut strings = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        match item {
+            Value::String(s) => strings.push(s.clone()),
+            other => bail!(
+                "sort requires a list of strings, but element {} is {}",
+                i,
+                type_name(other)
+            ),
+        }
+    }
+    strings.sort();
+    Ok(Value::Array(
+        strings.into_iter().map(Value::String).collect(),
+    ))
+}
+
+/// `reverse(list)`: the elements of a list, or the characters of a string,
+/// in reverse order.
+pub fn reverse(value: &Value) -> Result<Value> {
+    match value {
+        Value::Array(items) => Ok(Value::Array(items.iter().rev().cloned().collect())),
+        Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+        Value::Null => bail!("argument must not be null"),
+        other => bail!(
+            "argument must be a list or string, got {}",
+            type_name(other)
+        ),
+    }
+}
+
+/// `toset(list)`: the distinct elements of `list`. Strings come out in
+/// lexical order, as Terraform iterates string sets, so `for_each` over the
+/// result gets stable, unique keys; other elements keep their first position.
+/// Null and values known only after apply pass through.
+pub fn to_set(value: &Value) -> Result<Value> {
+    if value.is_null() || crate::provider::protocol::is_unknown(value) {
+        return Ok(value.clone());
+    }
+    let items = as_list(value, "argument")?;
+    let mut distinct: Vec<Value> = Vec::with_capacity(items.len());
+    for item in items {
+        if item.is_null() {
+            bail!("set elements must not be null");
+        }
+        if !distinct.iter().any(|seen| values_equal(seen, item)) {
+            distinct.push(item.clone());
+        }
+    }
+    if distinct.iter().all(Value::is_string) {
+        distinct.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+    }
+    Ok(Value::Array(distinct))
+}
+
+/// `tolist(value)`: a list or set unchanged, or the values of an object
+/// ordered by key. Null and values known only after apply pass through.
+pub fn to_list(value: &Value) -> Result<Value> {
+    match value {
+        Value::Null | Value::Array(_) => Ok(value.clone()),
+        _ if crate::provider::protocol::is_unknown(value) => Ok(value.clone()),
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            Ok(Value::Array(
+                entries.into_iter().map(|(_, v)| v.clone()).collect(),
+            ))
+        }
+        other => bail!("cannot convert {} to a list", type_name(other)),
+    }
+}
+
+/// Equality as the `==` operator sees it: numbers compare by value, so `1`
+/// and `1.0` are equal.
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn as_list<'a>(value: &'a Value, what: &str) -> Result<&'a [Value]> {
+    match value {
+        Value::Array(items) => Ok(items),
+        Value::Null => bail!("{} must not be null", what),
+        other => bail!("{} must be a list, got {}", what, type_name(other)),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,31 @@
+use sha2::Digest;
+
+/// Lowercase hex MD5 digest of `text`.
+pub fn md5_hex(text: &str) -> String {
+    hex::encode(md5::Md5::digest(text.as_bytes()))
+}
+
+/// Lowercase hex SHA-1 digest of `text`.
+pub fn sha1_hex(text: &str) -> String {
+    hex::encode(sha1::Sha1::digest(text.as_bytes()))
+}
+
+/// Lowercase hex SHA-256 digest of `text`.
+pub fn sha256_hex(text: &str) -> String {
+    hex::encode(sha2::Sha256::digest(text.as_bytes()))
+}
+
+/// Lowercase hex SHA-512 digest of `text`.
+pub fn sha512_hex(text: &str) -> String {
+    hex::encode(sha2::Sha512::digest(text.as_bytes()))
+}
+
+/// A random (v4) UUID.
+///
+/// A new value is generated on every evaluation, so an attribute set from
+/// `uuid()` differs on every plan and shows a perpetual diff. As in
+/// Terraform, pair it with `lifecycle { ignore_changes = [...] }` or use it
+/// only where the value is read once.
+pub fn uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
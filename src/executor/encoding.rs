@@ -0,0 +1,24 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+
+/// Standard, padded base64 of `data`.
+pub fn base64_encode(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+/// Decode standard base64 into a UTF-8 string.
+pub fn base64_decode(encoded: &str) -> Result<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .with_context(|| format!("invalid base64 data: {:?}", encoded))?;
+    String::from_utf8(bytes).context("the decoded bytes are not valid UTF-8")
+}
+
+/// Gzip-compress `text`, then base64-encode the result.
+pub fn base64_gzip(text: &str) -> Result<String> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(text.as_bytes())?;
+    Ok(base64_encode(&encoder.finish()?))
+}
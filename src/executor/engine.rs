@@ -1,20 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
 use dashmap::DashMap;
 use petgraph::graph::NodeIndex;
-use tracing::{debug, info};
-
-use crate::config::types::WorkspaceConfig;
-use crate::dag::resource_graph::{self, DagNode};
-use crate::dag::walker::{DagWalker, NodeExecutor, NodeResult, NodeStatus};
-use crate::provider::manager::ProviderManager;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::types::{
+    ProviderConfig, ProvisionerConfig, ProvisionerWhen, WorkspaceConfig, DEFAULT_OPERATION_TIMEOUT,
+};
+use crate::dag::resource_graph::{self, DagNode, ResourceGraph};
+use crate::dag::walker::{format_duration, DagWalker, NodeExecutor, NodeResult, NodeStatus};
+use crate::executor::provisioner::run_provisioners;
+use crate::provider::manager::{provider_address, split_provider_alias, ProviderManager};
+use crate::provider::protocol::{
+    contains_unknown, is_unknown, matches_known, unknown_value, ImportedResource,
+};
 use crate::state::backend::StateBackend;
+use crate::state::models::ResourceState;
 
 /// The action to take for a resource.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ResourceAction {
     Create,
     Update,
@@ -38,7 +50,7 @@ impl std::fmt::Display for ResourceAction {
 }
 
 /// A planned change for a single resource.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PlannedChange {
     pub address: String,
     pub action: ResourceAction,
@@ -48,9 +60,51 @@ pub struct PlannedChange {
     pub prior_state: Option<serde_json::Value>,
     pub user_config: Option<serde_json::Value>,
     pub requires_replace: Vec<String>,
+    /// Why the object is replaced when the provider didn't ask for it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace_reason: Option<String>,
+    /// Opaque provider data that must reach ApplyResourceChange unchanged.
+    #[serde(default, with = "crate::state::models::base64_bytes")]
     pub planned_private: Vec<u8>,
 }
 
+/// A step of an apply, sent to the channel given to
+/// [`ResourceEngine::with_events`] so embedders can render progress
+/// themselves. The CLI prints its own output and doesn't listen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ApplyEvent {
+    /// A resource or data source started applying (or reading).
+    ResourceStarted {
+        address: String,
+        action: ResourceAction,
+    },
+    /// Still running, sent every [`PROGRESS_INTERVAL`].
+    ResourceProgress {
+        address: String,
+        elapsed_secs: u64,
+    },
+    ResourceCompleted {
+        address: String,
+        action: ResourceAction,
+        duration_ms: u64,
+    },
+    ResourceFailed {
+        address: String,
+        error: String,
+    },
+    /// A root module output evaluated against the applied state. Sensitive
+    /// values are withheld.
+    OutputSet {
+        name: String,
+        value: Option<serde_json::Value>,
+        sensitive: bool,
+    },
+}
+
+/// How often [`ApplyEvent::ResourceProgress`] is sent for a running resource.
+pub const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// A planned output change.
 #[derive(Debug)]
 pub struct PlannedOutput {
@@ -59,11 +113,82 @@ pub struct PlannedOutput {
     pub value_known: bool,
 }
 
+/// Changes a plan makes to state records alone, before any resource is
+/// applied. Nothing is written while planning; [`StateUpdates::persist`]
+/// writes them once the plan is approved.
+#[derive(Debug, Clone, Default)]
+pub struct StateUpdates {
+    /// Records whose objects changed outside oxid, as read back from their
+    /// providers.
+    pub refreshed: Vec<ResourceState>,
+    /// Addresses of records whose objects no longer exist.
+    pub gone: Vec<String>,
+    /// Renames of `moved` blocks: the old address and the moved record.
+    pub moves: Vec<(String, ResourceState)>,
+    /// Addresses dropped from state by `removed` blocks with
+    /// `destroy = false`; their objects are left alone.
+    pub forgotten: Vec<String>,
+}
+
+impl StateUpdates {
+    /// The updates that bring `stored` records in line with their
+    /// `refreshed` versions (as returned by [`refreshed_records`]).
+    pub fn from_refresh(stored: &[ResourceState], refreshed: &[ResourceState]) -> Self {
+        let mut current: HashMap<&str, &ResourceState> =
+            refreshed.iter().map(|r| (r.address.as_str(), r)).collect();
+        let mut updates = Self::default();
+        for record in stored {
+            match current.remove(record.address.as_str()) {
+                Some(updated) => {
+                    if updated.attributes_json != record.attributes_json
+                        || updated.private_data != record.private_data
+                    {
+                        updates.refreshed.push(updated.clone());
+                    }
+                }
+                None => updates.gone.push(record.address.clone()),
+            }
+        }
+        updates
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of records the updates change.
+    pub fn len(&self) -> usize {
+        self.refreshed.len() + self.gone.len() + self.moves.len() + self.forgotten.len()
+    }
+
+    /// Write the updates to state.
+    pub async fn persist(&self, backend: &dyn StateBackend, workspace_id: &str) -> Result<()> {
+        for record in &self.refreshed {
+            backend.upsert_resource(record).await?;
+        }
+        for address in &self.gone {
+            info!(address = %address, "Object no longer exists; removing from state");
+            backend.delete_resource(workspace_id, address).await?;
+        }
+        for (from, moved) in &self.moves {
+            info!(from = %from, to = %moved.address, "Moving resource in state");
+            backend.move_resource(workspace_id, from, moved).await?;
+        }
+        for address in &self.forgotten {
+            info!(address = %address, "Removing from state; the object is not destroyed");
+            backend.delete_resource(workspace_id, address).await?;
+        }
+        Ok(())
+    }
+}
+
 /// Summary of a plan operation.
 #[derive(Debug)]
 pub struct PlanSummary {
     pub changes: Vec<PlannedChange>,
     pub outputs: Vec<PlannedOutput>,
+    /// State-only changes to write before applying `changes`.
+    pub state_updates: StateUpdates,
     pub creates: usize,
     pub updates: usize,
     pub deletes: usize,
@@ -71,6 +196,31 @@ pub struct PlanSummary {
     pub no_ops: usize,
 }
 
+impl PlanSummary {
+    /// A summary of `changes` and `outputs`, counting the changes by action.
+    pub fn from_changes(changes: Vec<PlannedChange>, outputs: Vec<PlannedOutput>) -> Self {
+        let count = |action: ResourceAction| changes.iter().filter(|c| c.action == action).count();
+        PlanSummary {
+            creates: count(ResourceAction::Create),
+            updates: count(ResourceAction::Update),
+            deletes: count(ResourceAction::Delete),
+            replaces: count(ResourceAction::Replace),
+            no_ops: count(ResourceAction::NoOp),
+            changes,
+            outputs,
+            state_updates: StateUpdates::default(),
+        }
+    }
+
+    /// True if applying the plan would create, update, replace or delete any
+    /// resource, or move or forget a record in state.
+    pub fn has_changes(&self) -> bool {
+        self.creates + self.updates + self.deletes + self.replaces > 0
+            || !self.state_updates.moves.is_empty()
+            || !self.state_updates.forgotten.is_empty()
+    }
+}
+
 impl std::fmt::Display for PlanSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut parts = Vec::new();
@@ -86,6 +236,12 @@ impl std::fmt::Display for PlanSummary {
         if self.deletes > 0 {
             parts.push(format!("{} to destroy", self.deletes));
         }
+        if !self.state_updates.moves.is_empty() {
+            parts.push(format!("{} to move", self.state_updates.moves.len()));
+        }
+        if !self.state_updates.forgotten.is_empty() {
+            parts.push(format!("{} to forget", self.state_updates.forgotten.len()));
+        }
         if parts.is_empty() {
             write!(f, "No changes.")
         } else {
@@ -94,6 +250,72 @@ impl std::fmt::Display for PlanSummary {
     }
 }
 
+/// One resource of a destroy plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestroyStep {
+    pub address: String,
+    pub resource_type: String,
+    /// Resources in state that depend on this one; they are destroyed first.
+    pub dependents: Vec<String>,
+    /// Why the provider would refuse to destroy the resource, if it would.
+    pub error: Option<String>,
+}
+
+/// Preview of `oxid destroy`: the resources in state, in the order they
+/// would be destroyed.
+#[derive(Debug, Clone, Default)]
+pub struct DestroyPlan {
+    pub steps: Vec<DestroyStep>,
+    /// State-only changes (`moved` renames and `removed` forgets) to write
+    /// before destroying.
+    pub state_updates: StateUpdates,
+}
+
+impl DestroyPlan {
+    /// True if the provider rejected the destroy of any resource.
+    pub fn has_errors(&self) -> bool {
+        self.steps.iter().any(|s| s.error.is_some())
+    }
+
+    /// The destroy plan as a regular plan (`oxid plan --destroy`): one delete
+    /// per step, in destroy order, with the prior state taken from `records`.
+    pub fn to_plan_summary(&self, records: &[ResourceState]) -> PlanSummary {
+        let records: HashMap<&str, &ResourceState> =
+            records.iter().map(|r| (r.address.as_str(), r)).collect();
+        let changes: Vec<PlannedChange> = self
+            .steps
+            .iter()
+            .map(|step| {
+                let record = records.get(step.address.as_str());
+                PlannedChange {
+                    address: step.address.clone(),
+                    action: ResourceAction::Delete,
+                    resource_type: step.resource_type.clone(),
+                    provider_source: record
+                        .map(|r| r.provider_source.clone())
+                        .unwrap_or_default(),
+                    planned_state: None,
+                    prior_state: record.and_then(|r| serde_json::from_str(&r.attributes_json).ok()),
+                    user_config: None,
+                    requires_replace: vec![],
+                    replace_reason: None,
+                    planned_private: vec![],
+                }
+            })
+            .collect();
+        PlanSummary {
+            deletes: changes.len(),
+            changes,
+            outputs: vec![],
+            state_updates: StateUpdates::default(),
+            creates: 0,
+            updates: 0,
+            replaces: 0,
+            no_ops: 0,
+        }
+    }
+}
+
 /// Summary of an apply operation.
 #[derive(Debug)]
 pub struct ApplySummary {
@@ -110,7 +332,7 @@ pub struct ApplySummary {
 impl std::fmt::Display for ApplySummary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let action = if self.is_destroy { "Destroy" } else { "Apply" };
-        let time = format_elapsed(self.elapsed_secs);
+        let time = format_duration(self.elapsed_secs);
         if self.is_destroy {
             write!(
                 f,
@@ -127,21 +349,35 @@ impl std::fmt::Display for ApplySummary {
         if self.failed > 0 {
             write!(f, ", {} failed", self.failed)?;
         }
-        write!(f, ". Total time: {}.", time)
+        write!(f, ". Total time: {}.", time)?;
+
+        let lost = self.destroyed_not_recreated();
+        if !lost.is_empty() {
+            write!(
+                f,
+                "\n\n{} {}",
+                format!(
+                    "Warning: {} resource(s) were destroyed but failed to recreate:",
+                    lost.len()
+                )
+                .red()
+                .bold(),
+                lost.join(", ")
+            )?;
+        }
+        Ok(())
     }
 }
 
-fn format_elapsed(secs: u64) -> String {
-    if secs < 60 {
-        format!("{}s", secs)
-    } else {
-        let mins = secs / 60;
-        let remaining = secs % 60;
-        if remaining == 0 {
-            format!("{}m", mins)
-        } else {
-            format!("{}m{}s", mins, remaining)
-        }
+impl ApplySummary {
+    /// Addresses whose replacement destroyed the old object and then failed
+    /// to create the new one: gone from both infrastructure and state.
+    pub fn destroyed_not_recreated(&self) -> Vec<&str> {
+        self.results
+            .iter()
+            .filter(|r| matches!(r.status, NodeStatus::ReplaceFailedAfterDestroy(_)))
+            .map(|r| r.address.as_str())
+            .collect()
     }
 }
 
@@ -153,6 +389,104 @@ fn format_elapsed(secs: u64) -> String {
 pub struct ResourceEngine {
     provider_manager: Arc<ProviderManager>,
     parallelism: usize,
+    /// Cancels apply/destroy walks gracefully (e.g. on Ctrl-C).
+    cancel: CancellationToken,
+    /// Which resources plan and apply act on (`--target`/`--exclude`).
+    selection: Selection,
+    /// Where apply reports its progress, if anywhere.
+    events: Option<mpsc::UnboundedSender<ApplyEvent>>,
+    /// Addresses planned as replacements whatever the provider plans
+    /// (`--replace`).
+    replace: Vec<String>,
+}
+
+/// The resources a plan or apply is limited to. Addresses name a resource,
+/// one instance of it, or a whole module call (`module.vpc`).
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// When non-empty, only these resources and what they depend on.
+    pub targets: Vec<String>,
+    /// Resources left out, applied after `targets`.
+    pub excludes: Vec<String>,
+    /// Also leave out everything that depends on an excluded resource.
+    pub exclude_dependents: bool,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty() && self.excludes.is_empty()
+    }
+
+    /// Whether a change to `address` that has no node in the graph (a
+    /// delete of an orphaned or removed instance) falls inside the selection.
+    pub fn includes(&self, address: &str) -> bool {
+        (self.targets.is_empty() || self.targets.iter().any(|t| address_matches(t, address)))
+            && !self.excludes.iter().any(|e| address_matches(e, address))
+    }
+
+    /// The part of `graph` this selection acts on. Targeted nodes keep
+    /// everything they depend on; excluded nodes are then removed, with
+    /// their dependents when `exclude_dependents` is set. Outputs reading an
+    /// excluded node are always removed, since its value isn't refreshed.
+    pub fn prune(&self, graph: ResourceGraph) -> ResourceGraph {
+        if self.is_empty() {
+            return graph;
+        }
+        let matching = |patterns: &[String]| -> Vec<NodeIndex> {
+            graph
+                .node_indices()
+                .filter(|&idx| {
+                    patterns
+                        .iter()
+                        .any(|p| address_matches(p, graph[idx].address()))
+                })
+                .collect()
+        };
+
+        let mut keep: HashSet<NodeIndex> = if self.targets.is_empty() {
+            graph.node_indices().collect()
+        } else {
+            let mut keep = HashSet::new();
+            let mut stack = matching(&self.targets);
+            while let Some(idx) = stack.pop() {
+                if keep.insert(idx) {
+                    stack.extend(graph.neighbors_directed(idx, petgraph::Direction::Incoming));
+                }
+            }
+            keep
+        };
+
+        let mut dropped = HashSet::new();
+        let mut stack = matching(&self.excludes);
+        while let Some(idx) = stack.pop() {
+            if dropped.insert(idx) {
+                stack.extend(
+                    graph
+                        .neighbors_directed(idx, petgraph::Direction::Outgoing)
+                        .filter(|&next| {
+                            self.exclude_dependents || matches!(graph[next], DagNode::Output { .. })
+                        }),
+                );
+            }
+        }
+        keep.retain(|idx| !dropped.contains(idx));
+
+        graph.filter_map(
+            |idx, node| keep.contains(&idx).then(|| node.clone()),
+            |_, edge| Some(edge.clone()),
+        )
+    }
+}
+
+/// Whether the address `pattern` names `address`: the address itself, the
+/// resource it is an instance of, or a module it is declared in, including
+/// every instance of that module.
+pub fn address_matches(pattern: &str, address: &str) -> bool {
+    address == pattern
+        || instance_base_address(address) == pattern
+        || address
+            .strip_prefix(pattern)
+            .is_some_and(|rest| pattern.starts_with("module.") && rest.starts_with(['.', '[']))
 }
 
 impl ResourceEngine {
@@ -160,9 +494,42 @@ impl ResourceEngine {
         Self {
             provider_manager,
             parallelism,
+            cancel: CancellationToken::new(),
+            selection: Selection::default(),
+            events: None,
+            replace: Vec::new(),
         }
     }
 
+    /// Send an [`ApplyEvent`] to `events` for each step of an apply, on top
+    /// of the printed progress. Events are dropped once the receiver is.
+    pub fn with_events(mut self, events: mpsc::UnboundedSender<ApplyEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Limit plan and apply to `selection`. Everything else is left as it is
+    /// in state, neither planned nor applied.
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Plan the resources at `addresses` (and every instance of them) as
+    /// replacements, even when the provider finds nothing to change. Each
+    /// address must be in state.
+    pub fn with_replace(mut self, addresses: Vec<String>) -> Self {
+        self.replace = addresses;
+        self
+    }
+
+    /// Once `cancel` fires, apply and destroy start no new resources and
+    /// return after the running ones finish.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     /// Get a reference to the provider manager.
     pub fn provider_manager(&self) -> &ProviderManager {
         &self.provider_manager
@@ -170,41 +537,78 @@ impl ResourceEngine {
 
     /// Plan all resources in the workspace.
     /// Returns a summary of what would change.
+    ///
+    /// With `refresh`, every object in state inside the selection is first
+    /// read back from its provider and planned against what exists now. Without it the plan
+    /// trusts the state recorded at the last apply, which is faster but
+    /// can't see changes made outside oxid.
     pub async fn plan(
         &self,
         workspace: &WorkspaceConfig,
         backend: &dyn StateBackend,
         workspace_id: &str,
+        refresh: bool,
     ) -> Result<PlanSummary> {
         let provider_map = build_provider_map(workspace);
         let var_defaults = build_variable_defaults(workspace);
-        let (graph, _node_map) =
+        let (full_graph, _node_map) =
             resource_graph::build_resource_dag(workspace, &provider_map, &var_defaults)?;
-
-        // Ensure all providers are started and configured
-        self.initialize_providers(workspace).await?;
+        let graph = self.selection.prune(full_graph.clone());
 
         let pm = Arc::clone(&self.provider_manager);
         let ws_id = workspace_id.to_string();
+        let stored = backend
+            .list_resources(&ws_id, &crate::state::models::ResourceFilter::default())
+            .await?;
+
+        // Ensure all providers are started and configured
+        self.initialize_providers(workspace, stored_states(&stored))
+            .await?;
+
+        // Only records inside the selection are refreshed, moved or forgotten
+        let selected: HashSet<&str> = graph
+            .node_indices()
+            .map(|idx| graph[idx].address())
+            .collect();
+        let in_scope = |address: &str| {
+            self.selection.is_empty()
+                || selected.contains(address)
+                || self.selection.includes(address)
+        };
+        let (scoped, unselected): (Vec<ResourceState>, Vec<ResourceState>) =
+            stored.iter().cloned().partition(|r| in_scope(&r.address));
 
         // Pre-load existing resource states so cross-resource references resolve during plan
         let resource_states = Arc::new(DashMap::new());
-        {
-            let existing = backend
-                .list_resources(
-                    &ws_id,
-                    &crate::state::models::ResourceFilter {
-                        resource_type: None,
-                        module_path: None,
-                        status: None,
-                        address_pattern: None,
-                    },
-                )
-                .await?;
-            for res in existing {
-                if let Ok(attrs) = serde_json::from_str::<serde_json::Value>(&res.attributes_json) {
-                    resource_states.insert(res.address.clone(), attrs);
-                }
+        let mut existing = refreshed_records(&scoped, self.parallelism, refresh, |resource| {
+            self.read_from_provider(resource)
+        })
+        .await;
+        let mut state_updates = StateUpdates::from_refresh(&scoped, &existing);
+        existing.extend(unselected);
+        state_updates.moves = resolve_moves(&workspace.moved, &mut existing)?;
+        state_updates
+            .moves
+            .retain(|(from, moved)| in_scope(from) || in_scope(&moved.address));
+        for warning in index_scheme_migrations(workspace, &existing) {
+            println!("{} {}", "Warning:".yellow().bold(), warning);
+        }
+        let mut removed_changes = Vec::new();
+        for (res, destroy) in removed_records(&workspace.removed, &existing) {
+            if destroy {
+                removed_changes.push(delete_change(res));
+            } else {
+                state_updates.forgotten.push(res.address.clone());
+            }
+        }
+        existing.retain(|r| !state_updates.forgotten.contains(&r.address));
+        state_updates.forgotten.retain(|address| in_scope(address));
+        check_replace_addresses(&self.replace, &existing, in_scope)?;
+        let records: HashMap<&str, &ResourceState> =
+            existing.iter().map(|r| (r.address.as_str(), r)).collect();
+        for res in &existing {
+            if let Ok(attrs) = serde_json::from_str::<serde_json::Value>(&res.attributes_json) {
+                resource_states.insert(res.address.clone(), attrs);
             }
         }
 
@@ -214,12 +618,13 @@ impl ResourceEngine {
         // Count resources for progress
         let total_resources = graph
             .node_indices()
-            .filter(|&idx| !matches!(graph[idx], DagNode::Output { .. }))
+            .filter(|&idx| graph[idx].is_object())
             .count();
         let mut planned_count = 0;
 
-        // Walk the graph to plan each resource
-        for idx in graph.node_indices() {
+        // Walk the graph dependencies-first, so each resource's planned state
+        // (including values only known after apply) is visible to dependents
+        for idx in resource_graph::topological_order(&graph)? {
             let node = &graph[idx];
             match node {
                 DagNode::Resource {
@@ -234,15 +639,21 @@ impl ResourceEngine {
                     println!(
                         "{}: {} [{}/{}]",
                         address,
-                        "Refreshing state...".dimmed(),
+                        if refresh {
+                            "Refreshing state..."
+                        } else {
+                            "Planning..."
+                        }
+                        .dimmed(),
                         planned_count,
                         total_resources,
                     );
 
                     // Build eval context with count.index / each.key + existing resource states
-                    let mut eval_ctx = EvalContext::with_states(
+                    let mut eval_ctx = EvalContext::for_workspace(
                         var_defaults.clone(),
                         Arc::clone(&resource_states),
+                        workspace,
                     );
                     match index {
                         Some(crate::config::types::ResourceIndex::Count(i)) => {
@@ -256,21 +667,27 @@ impl ResourceEngine {
                     }
 
                     // Build the proposed config as JSON
-                    let user_config = attributes_to_json(&config.attributes, &eval_ctx);
+                    let user_config = attributes_to_json(&config.attributes, &eval_ctx)
+                        .with_context(|| {
+                            format!("Failed to evaluate the configuration of {}", address)
+                        })?;
 
                     // Build full config with all schema attributes for msgpack encoding
                     let config_json = if let Ok(Some(schema)) =
                         pm.get_resource_schema(provider_source, resource_type).await
                     {
-                        build_full_resource_config(&user_config, &schema)
+                        let full = build_full_resource_config(&user_config, &schema);
+                        validate_block_items(&full, &schema)
+                            .with_context(|| format!("Invalid configuration for {}", address))?;
+                        full
                     } else {
                         user_config.clone()
                     };
 
                     // Check if resource exists in state
-                    let prior_state = backend
-                        .get_resource(&ws_id, address)
-                        .await?
+                    let prior = records.get(address.as_str());
+                    let prior_private = prior.map(|r| r.private_data.clone()).unwrap_or_default();
+                    let prior_state = prior
                         .map(|r| serde_json::from_str::<serde_json::Value>(&r.attributes_json))
                         .transpose()?;
 
@@ -281,6 +698,7 @@ impl ResourceEngine {
                             prior_state.as_ref(),
                             Some(&config_json),
                             &config_json,
+                            &prior_private,
                         )
                         .await
                     {
@@ -291,21 +709,36 @@ impl ResourceEngine {
                         }
                     };
 
-                    let action = determine_action(
+                    let mut planned_state = plan_result.planned_state;
+                    let config_unknown = planned_state
+                        .as_mut()
+                        .is_some_and(|planned| propagate_config_unknowns(&user_config, planned));
+                    let mut action = determine_action(
                         prior_state.as_ref(),
-                        plan_result.planned_state.as_ref(),
+                        planned_state.as_ref(),
                         &plan_result.requires_replace,
                     );
+                    // Config that depends on a value known only after apply
+                    // changes the resource, whatever the provider planned
+                    if action == ResourceAction::NoOp && prior_state.is_some() && config_unknown {
+                        action = ResourceAction::Update;
+                    }
+                    if action != ResourceAction::NoOp {
+                        if let Some(planned) = &planned_state {
+                            resource_states.insert(address.clone(), planned.clone());
+                        }
+                    }
 
                     changes.push(PlannedChange {
                         address: address.clone(),
                         action,
                         resource_type: resource_type.clone(),
                         provider_source: provider_source.clone(),
-                        planned_state: plan_result.planned_state,
+                        planned_state,
                         prior_state,
                         user_config: Some(user_config),
                         requires_replace: plan_result.requires_replace,
+                        replace_reason: None,
                         planned_private: plan_result.planned_private,
                     });
                 }
@@ -325,9 +758,10 @@ impl ResourceEngine {
                         planned_count,
                         total_resources,
                     );
-                    let mut ds_eval_ctx = EvalContext::with_states(
+                    let mut ds_eval_ctx = EvalContext::for_workspace(
                         var_defaults.clone(),
                         Arc::clone(&resource_states),
+                        workspace,
                     );
                     match index {
                         Some(crate::config::types::ResourceIndex::Count(i)) => {
@@ -339,14 +773,20 @@ impl ResourceEngine {
                         }
                         None => {}
                     }
-                    let user_config = attributes_to_json(&config.attributes, &ds_eval_ctx);
+                    let user_config = attributes_to_json(&config.attributes, &ds_eval_ctx)
+                        .with_context(|| {
+                            format!("Failed to evaluate the configuration of {}", address)
+                        })?;
 
                     // Build full config with all schema attributes
                     let config_json = if let Ok(Some(schema)) = pm
                         .get_data_source_schema(provider_source, resource_type)
                         .await
                     {
-                        build_full_resource_config(&user_config, &schema)
+                        let full = build_full_resource_config(&user_config, &schema);
+                        validate_block_items(&full, &schema)
+                            .with_context(|| format!("Invalid configuration for {}", address))?;
+                        full
                     } else {
                         user_config.clone()
                     };
@@ -370,6 +810,8 @@ impl ResourceEngine {
                                 elapsed,
                                 id_str,
                             );
+                            // Later reads may reference this one, possibly by instance key
+                            resource_states.insert(address.clone(), state.clone());
                             state
                         }
                         Err(e) => {
@@ -387,6 +829,7 @@ impl ResourceEngine {
                         prior_state: None,
                         user_config: Some(user_config),
                         requires_replace: vec![],
+                        replace_reason: None,
                         planned_private: vec![],
                     });
                 }
@@ -397,53 +840,62 @@ impl ResourceEngine {
                         value_known: false,
                     });
                 }
+                // Configured from stored state above; apply reconfigures it
+                // once its dependencies are applied.
+                DagNode::Provider { .. } => {}
+                // Only apply adds orphans, from the plan's deletes
+                DagNode::Orphan { .. } => {}
             }
         }
 
-        let creates = changes
-            .iter()
-            .filter(|c| c.action == ResourceAction::Create)
-            .count();
-        let updates = changes
-            .iter()
-            .filter(|c| c.action == ResourceAction::Update)
-            .count();
-        let deletes = changes
-            .iter()
-            .filter(|c| c.action == ResourceAction::Delete)
-            .count();
-        let replaces = changes
-            .iter()
-            .filter(|c| c.action == ResourceAction::Replace)
-            .count();
-        let no_ops = changes
-            .iter()
-            .filter(|c| c.action == ResourceAction::NoOp)
-            .count();
+        changes.extend(
+            plan_orphaned_instances(workspace, &full_graph, &existing)
+                .into_iter()
+                .chain(removed_changes)
+                .filter(|c| self.selection.includes(&c.address)),
+        );
+
+        apply_replace_triggers(&graph, &mut changes);
+        apply_forced_replacements(&self.replace, &mut changes);
 
         Ok(PlanSummary {
-            changes,
-            outputs,
-            creates,
-            updates,
-            deletes,
-            replaces,
-            no_ops,
+            state_updates,
+            ..PlanSummary::from_changes(changes, outputs)
         })
     }
 
     /// Apply all planned changes using the event-driven DAG walker.
+    /// Each node's outcome is recorded against `run_id`.
     pub async fn apply(
         &self,
         workspace: &WorkspaceConfig,
         backend: Arc<dyn StateBackend>,
         workspace_id: &str,
         plan: &PlanSummary,
+        run_id: &str,
     ) -> Result<ApplySummary> {
         let provider_map = build_provider_map(workspace);
         let var_defaults = build_variable_defaults(workspace);
         let (graph, _node_map) =
             resource_graph::build_resource_dag(workspace, &provider_map, &var_defaults)?;
+        let mut graph = self.selection.prune(graph);
+
+        // Instances that count/for_each no longer expand to, and resources
+        // destroyed by `removed` blocks, have no node in the graph yet
+        let in_graph: HashSet<&str> = graph.node_indices().map(|i| graph[i].address()).collect();
+        let orphans: Vec<DagNode> = plan
+            .changes
+            .iter()
+            .filter(|c| c.action == ResourceAction::Delete)
+            .filter(|c| !in_graph.contains(c.address.as_str()))
+            .map(|c| DagNode::Orphan {
+                address: c.address.clone(),
+                base_address: instance_base_address(&c.address).to_string(),
+                resource_type: c.resource_type.clone(),
+                provider_source: c.provider_source.clone(),
+            })
+            .collect();
+        resource_graph::add_orphans(&mut graph, workspace, orphans)?;
 
         let pm = Arc::clone(&self.provider_manager);
         let ws_id = workspace_id.to_string();
@@ -452,14 +904,66 @@ impl ResourceEngine {
         // As each resource completes, its new state is inserted here so dependents can
         // resolve references like `aws_s3_bucket.public_scripts.id`.
         let resource_states: Arc<DashMap<String, serde_json::Value>> = Arc::new(DashMap::new());
-
-        // Build a map of planned changes for the executor to reference
-        let _planned_changes: Arc<HashMap<String, &PlannedChange>> = Arc::new(
+        let eval_base =
+            EvalContext::for_workspace(var_defaults, Arc::clone(&resource_states), workspace);
+        let outputs: NodeOutputs = Arc::new(DashMap::new());
+        let outputs_clone = Arc::clone(&outputs);
+
+        // Planned action and diff per address, for the run record
+        let planned: HashMap<String, (String, Option<String>)> = plan
+            .changes
+            .iter()
+            .map(|c| {
+                let diff = serde_json::json!({
+                    "before": c.prior_state,
+                    "after": c.planned_state,
+                });
+                (
+                    c.address.clone(),
+                    (action_name(&c.action).to_string(), Some(diff.to_string())),
+                )
+            })
+            .collect();
+        // Planned replacements, including those no attribute of the resource
+        // itself forces (`replace_triggered_by`)
+        let replacements: Arc<HashSet<String>> = Arc::new(
             plan.changes
                 .iter()
-                .map(|c| (c.address.clone(), c))
+                .filter(|c| c.action == ResourceAction::Replace)
+                .map(|c| c.address.clone())
                 .collect(),
         );
+        let events = self.events.clone();
+        let root_outputs: HashMap<String, crate::config::types::OutputConfig> = workspace
+            .outputs
+            .iter()
+            .map(|o| (o.name.clone(), o.clone()))
+            .collect();
+        let output_config = events.as_ref().map(|_| {
+            Arc::new(move |name: &str, module_path: &str| {
+                module_path
+                    .is_empty()
+                    .then(|| root_outputs.get(name).cloned())
+                    .flatten()
+            })
+        });
+        let actions: HashMap<String, ResourceAction> = plan
+            .changes
+            .iter()
+            .map(|c| (c.address.clone(), c.action.clone()))
+            .collect();
+        let record_action: RecordAction = Arc::new(move |node: &DagNode| match node {
+            DagNode::Resource { address, .. } | DagNode::Orphan { address, .. } => Some(
+                planned
+                    .get(address)
+                    .cloned()
+                    .unwrap_or_else(|| (crate::state::models::action::NOOP.to_string(), None)),
+            ),
+            DagNode::DataSource { .. } => {
+                Some((crate::state::models::action::READ.to_string(), None))
+            }
+            DagNode::Output { .. } | DagNode::Provider { .. } => None,
+        });
 
         // Create the node executor closure
         let executor: NodeExecutor = Box::new(move |_idx: NodeIndex, node: DagNode| {
@@ -467,7 +971,11 @@ impl ResourceEngine {
             let ws_id = ws_id.clone();
             let backend = Arc::clone(&backend_clone);
             let resource_states = Arc::clone(&resource_states);
-            let var_defaults = var_defaults.clone();
+            let eval_base = eval_base.clone();
+            let outputs = Arc::clone(&outputs_clone);
+            let replacements = Arc::clone(&replacements);
+            let events = events.clone();
+            let output_config = output_config.clone();
 
             Box::pin(async move {
                 match node {
@@ -479,10 +987,7 @@ impl ResourceEngine {
                         ref index,
                         ..
                     } => {
-                        let mut eval_ctx = EvalContext::with_states(
-                            var_defaults.clone(),
-                            Arc::clone(&resource_states),
-                        );
+                        let mut eval_ctx = eval_base.clone();
                         match index {
                             Some(crate::config::types::ResourceIndex::Count(i)) => {
                                 eval_ctx.count_index = Some(*i);
@@ -493,21 +998,33 @@ impl ResourceEngine {
                             }
                             None => {}
                         }
-                        let user_config = attributes_to_json(&config.attributes, &eval_ctx);
+                        let user_config = attributes_to_json(&config.attributes, &eval_ctx)
+                            .with_context(|| {
+                                format!("Failed to evaluate the configuration of {}", address)
+                            })?;
 
                         // Build full config with all schema attributes for msgpack encoding
-                        let config_json = if let Ok(Some(schema)) =
-                            pm.get_resource_schema(provider_source, resource_type).await
-                        {
-                            build_full_resource_config(&user_config, &schema)
-                        } else {
-                            user_config
+                        let schema = pm
+                            .get_resource_schema(provider_source, resource_type)
+                            .await
+                            .ok()
+                            .flatten();
+                        let config_json = match &schema {
+                            Some(schema) => build_full_resource_config(&user_config, schema),
+                            None => user_config,
                         };
+                        let sensitive_attrs = schema
+                            .as_ref()
+                            .map(sensitive_attributes)
+                            .unwrap_or_default();
 
                         // Get prior state from database
-                        let prior_state = backend
-                            .get_resource(&ws_id, address)
-                            .await?
+                        let prior = backend.get_resource(&ws_id, address).await?;
+                        let prior_private = prior
+                            .as_ref()
+                            .map(|r| r.private_data.clone())
+                            .unwrap_or_default();
+                        let prior_state = prior
                             .map(|r| serde_json::from_str::<serde_json::Value>(&r.attributes_json))
                             .transpose()?;
 
@@ -519,82 +1036,176 @@ impl ResourceEngine {
                                 prior_state.as_ref(),
                                 Some(&config_json),
                                 &config_json,
+                                &prior_private,
                             )
                             .await?;
 
-                        // If requires_replace is non-empty AND there's a prior state,
-                        // we need to destroy the old resource first, then create new.
-                        let apply_result =
-                            if !plan_result.requires_replace.is_empty() && prior_state.is_some() {
-                                info!(
-                                    address = %address,
-                                    replace_fields = ?plan_result.requires_replace,
-                                    "Resource requires replacement — destroying old, creating new"
-                                );
-
-                                // Step 1: Destroy the old resource
-                                // Plan a destroy (prior → null)
-                                let destroy_plan = pm
-                                    .plan_resource(
-                                        provider_source,
-                                        resource_type,
-                                        prior_state.as_ref(),
-                                        None, // proposed_new = null means destroy
-                                        &config_json,
-                                    )
-                                    .await?;
-
-                                // Apply the destroy
-                                let _destroy_result = pm
-                                    .apply_resource(
-                                        provider_source,
-                                        resource_type,
-                                        prior_state.as_ref(),
-                                        None, // planned_state = null means destroy
-                                        &config_json,
-                                        &destroy_plan.planned_private,
-                                    )
-                                    .await?;
-
-                                info!(address = %address, "Old resource destroyed");
-
-                                // Remove from state database
-                                backend.delete_resource(&ws_id, address).await.ok();
-
-                                // Step 2: Create the new resource
-                                // Plan a create (null → new)
-                                let create_plan = pm
-                                    .plan_resource(
-                                        provider_source,
-                                        resource_type,
-                                        None, // no prior state
-                                        Some(&config_json),
-                                        &config_json,
-                                    )
-                                    .await?;
-
-                                // Apply the create
-                                pm.apply_resource(
-                                    provider_source,
-                                    resource_type,
-                                    None, // no prior state
-                                    create_plan.planned_state.as_ref(),
-                                    &config_json,
-                                    &create_plan.planned_private,
-                                )
-                                .await?
+                        // If requires_replace is non-empty (or the plan replaces the
+                        // resource anyway) AND there's a prior state, the old object is
+                        // destroyed and a new one created. The order of the
+                        // two steps follows `lifecycle.create_before_destroy`.
+                        let replacing = (!plan_result.requires_replace.is_empty()
+                            || replacements.contains(address))
+                            && prior_state.is_some();
+                        let created_new = replacing || prior_state.is_none();
+                        let apply_result = if replacing {
+                            let cbd = config.lifecycle.create_before_destroy;
+                            info!(
+                                address = %address,
+                                replace_fields = ?plan_result.requires_replace,
+                                create_before_destroy = cbd,
+                                "Resource requires replacement"
+                            );
+
+                            let mut created = None;
+                            for step in replace_steps(&config.lifecycle) {
+                                match step {
+                                    ReplaceStep::DestroyPrior => {
+                                        if cbd {
+                                            // The replacement is already live; retire the
+                                            // deposed object and drop its record.
+                                            destroy_deposed(
+                                                &pm,
+                                                backend.as_ref(),
+                                                &ws_id,
+                                                address,
+                                                provider_source,
+                                                resource_type,
+                                                &config_json,
+                                                config.timeouts.delete_timeout(),
+                                            )
+                                            .await?;
+                                        } else {
+                                            let destroy_plan = pm
+                                                .plan_resource(
+                                                    provider_source,
+                                                    resource_type,
+                                                    prior_state.as_ref(),
+                                                    None, // proposed_new = null means destroy
+                                                    &config_json,
+                                                    &prior_private,
+                                                )
+                                                .await?;
+                                            pm.apply_resource(
+                                                provider_source,
+                                                resource_type,
+                                                prior_state.as_ref(),
+                                                None, // planned_state = null means destroy
+                                                &config_json,
+                                                &destroy_plan.planned_private,
+                                                config.timeouts.delete_timeout(),
+                                            )
+                                            .await?;
+                                            info!(address = %address, "Old resource destroyed");
+                                            backend.delete_resource(&ws_id, address).await.ok();
+                                        }
+                                    }
+                                    ReplaceStep::CreateNew => {
+                                        if cbd {
+                                            // Record the old object as deposed before creating
+                                            // its replacement, so a crash between the two
+                                            // steps leaves it tracked for cleanup.
+                                            let mut deposed =
+                                                crate::state::models::ResourceState::new(
+                                                    &ws_id,
+                                                    resource_type,
+                                                    &config.name,
+                                                    &deposed_address(address),
+                                                );
+                                            deposed.provider_source = provider_source.to_string();
+                                            deposed.status =
+                                                crate::state::models::status::DEPOSED.to_string();
+                                            deposed.attributes_json =
+                                                serde_json::to_string(&prior_state)?;
+                                            deposed.private_data = prior_private.clone();
+                                            deposed.sensitive_attrs = sensitive_attrs.clone();
+                                            deposed.index_key = index_key_string(index);
+                                            deposed.module_path = config
+                                                .module_prefix()
+                                                .trim_end_matches('.')
+                                                .to_string();
+                                            backend.upsert_resource(&deposed).await?;
+                                        }
+
+                                        // Without create_before_destroy the old object
+                                        // is already gone if this fails
+                                        let create_plan = pm
+                                            .plan_resource(
+                                                provider_source,
+                                                resource_type,
+                                                None, // no prior state
+                                                Some(&config_json),
+                                                &config_json,
+                                                &[],
+                                            )
+                                            .await
+                                            .map_err(|e| replace_failure(e, address, !cbd))?;
+                                        let result = pm
+                                            .apply_resource(
+                                                provider_source,
+                                                resource_type,
+                                                None, // no prior state
+                                                create_plan.planned_state.as_ref(),
+                                                &config_json,
+                                                &create_plan.planned_private,
+                                                config.timeouts.create_timeout(),
+                                            )
+                                            .await
+                                            .map_err(|e| replace_failure(e, address, !cbd))?;
+
+                                        if cbd {
+                                            // Persist the replacement before touching the
+                                            // deposed object.
+                                            if let Some(ref new_state) = result.new_state {
+                                                let mut resource_state =
+                                                    crate::state::models::ResourceState::new(
+                                                        &ws_id,
+                                                        resource_type,
+                                                        &config.name,
+                                                        address,
+                                                    );
+                                                resource_state.provider_source =
+                                                    provider_source.to_string();
+                                                resource_state.status =
+                                                    crate::state::models::status::CREATED
+                                                        .to_string();
+                                                resource_state.attributes_json =
+                                                    serde_json::to_string(new_state)?;
+                                                resource_state.private_data =
+                                                    result.private_data.clone();
+                                                resource_state.sensitive_attrs =
+                                                    sensitive_attrs.clone();
+                                                resource_state.index_key = index_key_string(index);
+                                                resource_state.module_path = config
+                                                    .module_prefix()
+                                                    .trim_end_matches('.')
+                                                    .to_string();
+                                                backend.upsert_resource(&resource_state).await?;
+                                            }
+                                        }
+                                        created = Some(result);
+                                    }
+                                }
+                            }
+                            created.context("Replacement produced no new object")?
+                        } else {
+                            // Normal apply (create or in-place update)
+                            let timeout = if prior_state.is_some() {
+                                config.timeouts.update_timeout()
                             } else {
-                                // Normal apply (create or in-place update)
-                                pm.apply_resource(
-                                    provider_source,
-                                    resource_type,
-                                    prior_state.as_ref(),
-                                    plan_result.planned_state.as_ref(),
-                                    &config_json,
-                                    &plan_result.planned_private,
-                                )
-                                .await?
+                                config.timeouts.create_timeout()
                             };
+                            pm.apply_resource(
+                                provider_source,
+                                resource_type,
+                                prior_state.as_ref(),
+                                plan_result.planned_state.as_ref(),
+                                &config_json,
+                                &plan_result.planned_private,
+                                timeout,
+                            )
+                            .await?
+                        };
 
                         // Store the new state in both the database and the shared map
                         if let Some(ref new_state) = apply_result.new_state {
@@ -608,23 +1219,60 @@ impl ResourceEngine {
                                 address,
                             );
                             resource_state.provider_source = provider_source.to_string();
-                            resource_state.status = "created".to_string();
+                            resource_state.status =
+                                crate::state::models::status::CREATED.to_string();
                             resource_state.attributes_json = serde_json::to_string(new_state)?;
-                            resource_state.index_key = match index {
-                                Some(crate::config::types::ResourceIndex::Count(i)) => {
-                                    Some(i.to_string())
-                                }
-                                Some(crate::config::types::ResourceIndex::ForEach(k)) => {
-                                    Some(k.clone())
-                                }
-                                None => None,
-                            };
+                            resource_state.private_data = apply_result.private_data.clone();
+                            resource_state.sensitive_attrs = sensitive_attrs;
+                            resource_state.index_key = index_key_string(index);
+                            resource_state.module_path =
+                                config.module_prefix().trim_end_matches('.').to_string();
 
                             backend.upsert_resource(&resource_state).await?;
 
                             info!(address = %address, "Resource applied successfully");
+
+                            // Create-time provisioners run only when the object is new
+                            if created_new {
+                                let provisioner_ctx = eval_ctx.with_self(Some(new_state.clone()));
+                                let mut log = String::new();
+                                let provisioned = match resolve_provisioners(
+                                    &config.provisioners,
+                                    ProvisionerWhen::Create,
+                                    &provisioner_ctx,
+                                ) {
+                                    Ok(provisioners) => {
+                                        run_provisioners(address, &provisioners, &mut log).await
+                                    }
+                                    Err(e) => Err(e),
+                                };
+                                if !log.is_empty() {
+                                    outputs.insert(address.clone(), log);
+                                }
+                                if let Err(e) = provisioned {
+                                    // A failed provisioner leaves the object tainted, as in Terraform
+                                    resource_state.status =
+                                        crate::state::models::status::TAINTED.to_string();
+                                    backend.upsert_resource(&resource_state).await?;
+                                    return Err(e);
+                                }
+                            }
                         }
 
+                        // Clean up an object left deposed by an interrupted
+                        // create_before_destroy replacement.
+                        destroy_deposed(
+                            &pm,
+                            backend.as_ref(),
+                            &ws_id,
+                            address,
+                            provider_source,
+                            resource_type,
+                            &config_json,
+                            config.timeouts.delete_timeout(),
+                        )
+                        .await?;
+
                         Ok(apply_result.new_state)
                     }
                     DagNode::DataSource {
@@ -635,10 +1283,7 @@ impl ResourceEngine {
                         ref index,
                         ..
                     } => {
-                        let mut eval_ctx = EvalContext::with_states(
-                            var_defaults.clone(),
-                            Arc::clone(&resource_states),
-                        );
+                        let mut eval_ctx = eval_base.clone();
                         match index {
                             Some(crate::config::types::ResourceIndex::Count(i)) => {
                                 eval_ctx.count_index = Some(*i);
@@ -649,7 +1294,10 @@ impl ResourceEngine {
                             }
                             None => {}
                         }
-                        let user_config = attributes_to_json(&config.attributes, &eval_ctx);
+                        let user_config = attributes_to_json(&config.attributes, &eval_ctx)
+                            .with_context(|| {
+                                format!("Failed to evaluate the configuration of {}", address)
+                            })?;
 
                         // Build full config with all schema attributes
                         let config_json = if let Ok(Some(schema)) = pm
@@ -668,16 +1316,96 @@ impl ResourceEngine {
                         resource_states.insert(address.clone(), state.clone());
                         Ok(Some(state))
                     }
-                    DagNode::Output { .. } => {
-                        // Outputs are evaluated after all resources
+                    DagNode::Output {
+                        ref name,
+                        ref module_path,
+                    } => {
+                        // Outputs are evaluated after all resources; only
+                        // listeners see them now
+                        let (Some(events), Some(output)) = (&events, &output_config) else {
+                            return Ok(None);
+                        };
+                        let Some(output) = output(name, module_path) else {
+                            return Ok(None);
+                        };
+                        let eval_ctx = eval_base.clone();
+                        let value = match output.sensitive {
+                            true => None,
+                            false => Some(
+                                eval_expression(&output.value, &eval_ctx)
+                                    .with_context(|| format!("output \"{}\"", name))?,
+                            ),
+                        };
+                        let _ = events.send(ApplyEvent::OutputSet {
+                            name: name.clone(),
+                            value,
+                            sensitive: output.sensitive,
+                        });
+                        Ok(None)
+                    }
+                    DagNode::Provider {
+                        ref provider_source,
+                        ref config,
+                        ..
+                    } => {
+                        // Everything the config references has been applied
+                        let eval_ctx = eval_base.clone();
+                        // Planning configured this instance from stored
+                        // state, and providers may reject a second
+                        // ConfigureProvider; configure a fresh process.
+                        let version = config.version_constraint.as_deref().unwrap_or(">= 0.0.0");
+                        pm.stop_provider(provider_source).await?;
+                        pm.get_connection(provider_source, version)
+                            .await
+                            .context(format!(
+                                "Failed to initialize provider {}",
+                                provider_source
+                            ))?;
+                        configure_provider(&pm, provider_source, config, &eval_ctx).await?;
                         Ok(None)
                     }
+                    DagNode::Orphan {
+                        ref address,
+                        ref resource_type,
+                        ref provider_source,
+                        ..
+                    } => {
+                        destroy_orphan(
+                            &pm,
+                            backend.as_ref(),
+                            &ws_id,
+                            address,
+                            provider_source,
+                            resource_type,
+                        )
+                        .await
+                    }
                 }
             })
         });
 
-        let walker = DagWalker::new(self.parallelism);
+        let walker = DagWalker::new(self.parallelism)
+            .with_provider_limits(build_provider_limits(workspace))
+            .with_cancellation(self.cancel.clone());
         let start = std::time::Instant::now();
+
+        let executor = track_resource_status(
+            executor,
+            Arc::clone(&backend),
+            workspace_id,
+            Arc::clone(&record_action),
+        );
+        let executor = record_node_results(
+            executor,
+            Arc::clone(&backend),
+            run_id,
+            Arc::clone(&record_action),
+            outputs,
+        );
+        let executor = match &self.events {
+            Some(events) => emit_apply_events(executor, events.clone(), actions),
+            None => executor,
+        };
         let results = walker
             .walk(
                 &graph,
@@ -685,12 +1413,10 @@ impl ResourceEngine {
                 crate::dag::walker::WalkMode::Apply,
             )
             .await?;
+        record_skipped_nodes(backend.as_ref(), run_id, &graph, &results, &record_action).await;
         let elapsed_secs = start.elapsed().as_secs();
 
-        let failed = results
-            .iter()
-            .filter(|r| matches!(r.status, NodeStatus::Failed(_)))
-            .count();
+        let failed = results.iter().filter(|r| r.status.is_failure()).count();
         let skipped = results
             .iter()
             .filter(|r| matches!(r.status, NodeStatus::Skipped(_)))
@@ -713,49 +1439,172 @@ impl ResourceEngine {
         })
     }
 
-    /// Destroy resources in reverse dependency order.
+    /// Preview a destroy without changing anything: ask the provider to plan
+    /// the destroy of every resource in state, in reverse dependency order,
+    /// and collect the errors it reports. With `refresh`, each object is read
+    /// back from its provider first, and those that no longer exist are left
+    /// out.
+    pub async fn plan_destroy(
+        &self,
+        workspace: &WorkspaceConfig,
+        backend: &dyn StateBackend,
+        workspace_id: &str,
+        refresh: bool,
+    ) -> Result<DestroyPlan> {
+        let var_defaults = build_variable_defaults(workspace);
+        let (graph, _node_map) = resource_graph::build_resource_dag(
+            workspace,
+            &build_provider_map(workspace),
+            &var_defaults,
+        )?;
+        let mut stored = backend
+            .list_resources(
+                workspace_id,
+                &crate::state::models::ResourceFilter::default(),
+            )
+            .await?;
+        let moves = resolve_moves(&workspace.moved, &mut stored)?;
+        let forgotten = forgotten_addresses(&workspace.removed, &stored);
+        stored.retain(|r| !forgotten.contains(&r.address));
+        let state_updates = StateUpdates {
+            moves,
+            forgotten,
+            ..Default::default()
+        };
+        if stored.is_empty() {
+            return Ok(DestroyPlan {
+                state_updates,
+                ..Default::default()
+            });
+        }
+
+        self.initialize_providers(workspace, stored_states(&stored))
+            .await?;
+        let stored = refreshed_records(&stored, self.parallelism, refresh, |resource| {
+            self.read_from_provider(resource)
+        })
+        .await;
+        let graph = destroy_graph(&graph, workspace, &stored)?;
+        let records: HashMap<String, ResourceState> =
+            stored.into_iter().map(|r| (r.address.clone(), r)).collect();
+        let mut steps = destroy_steps(&graph, &records.keys().cloned().collect())?;
+        let nodes: HashMap<&str, NodeIndex> = graph
+            .node_indices()
+            .map(|idx| (graph[idx].address(), idx))
+            .collect();
+        for step in &mut steps {
+            let record = &records[&step.address];
+            let prior: serde_json::Value = serde_json::from_str(&record.attributes_json)?;
+            // An orphan has no configuration left to evaluate
+            let DagNode::Resource {
+                ref provider_source,
+                ref config,
+                ref index,
+                ..
+            } = graph[nodes[step.address.as_str()]]
+            else {
+                if let Err(e) = self
+                    .provider_manager
+                    .plan_resource(
+                        &record.provider_source,
+                        &step.resource_type,
+                        Some(&prior),
+                        None,
+                        &serde_json::Value::Null,
+                        &record.private_data,
+                    )
+                    .await
+                {
+                    step.error = Some(format!("{:#}", e));
+                }
+                continue;
+            };
+
+            let mut eval_ctx =
+                EvalContext::for_workspace(var_defaults.clone(), Default::default(), workspace);
+            match index {
+                Some(crate::config::types::ResourceIndex::Count(i)) => {
+                    eval_ctx.count_index = Some(*i);
+                }
+                Some(crate::config::types::ResourceIndex::ForEach(k)) => {
+                    eval_ctx.each_key = Some(k.clone());
+                    eval_ctx.each_value = Some(serde_json::Value::String(k.clone()));
+                }
+                None => {}
+            }
+            let user_config =
+                attributes_to_json(&config.attributes, &eval_ctx).with_context(|| {
+                    format!("Failed to evaluate the configuration of {}", step.address)
+                })?;
+            let config_json = match self
+                .provider_manager
+                .get_resource_schema(provider_source, &step.resource_type)
+                .await
+            {
+                Ok(Some(schema)) => build_full_resource_config(&user_config, &schema),
+                _ => user_config,
+            };
+
+            if let Err(e) = self
+                .provider_manager
+                .plan_resource(
+                    provider_source,
+                    &step.resource_type,
+                    Some(&prior),
+                    None, // null planned state = destroy
+                    &config_json,
+                    &record.private_data,
+                )
+                .await
+            {
+                step.error = Some(format!("{:#}", e));
+            }
+        }
+
+        Ok(DestroyPlan {
+            steps,
+            state_updates,
+        })
+    }
+
+    /// Destroy resources in reverse dependency order, recording each
+    /// resource's outcome against `run_id`.
     pub async fn destroy(
         &self,
         workspace: &WorkspaceConfig,
         backend: Arc<dyn StateBackend>,
         workspace_id: &str,
+        run_id: &str,
     ) -> Result<ApplySummary> {
         let provider_map = build_provider_map(workspace);
         let var_defaults = build_variable_defaults(workspace);
         let (graph, _node_map) =
             resource_graph::build_resource_dag(workspace, &provider_map, &var_defaults)?;
-
-        // For destroy, we reverse the graph edges so dependents are destroyed first
-        let mut reverse_graph = petgraph::graph::DiGraph::new();
-        let mut idx_map: HashMap<NodeIndex, NodeIndex> = HashMap::new();
-
-        for idx in graph.node_indices() {
-            let new_idx = reverse_graph.add_node(graph[idx].clone());
-            idx_map.insert(idx, new_idx);
-        }
-
-        for edge in graph.edge_indices() {
-            if let Some((from, to)) = graph.edge_endpoints(edge) {
-                // Reverse the edge direction
-                reverse_graph.add_edge(
-                    idx_map[&to],
-                    idx_map[&from],
-                    crate::dag::resource_graph::DependencyEdge::Explicit,
-                );
-            }
-        }
+        // Destroy evaluates config without resource states
+        let eval_base = EvalContext::for_workspace(var_defaults, Default::default(), workspace);
 
         let pm = Arc::clone(&self.provider_manager);
         let ws_id = workspace_id.to_string();
         let backend_clone = Arc::clone(&backend);
+        let outputs: NodeOutputs = Arc::new(DashMap::new());
+        let outputs_clone = Arc::clone(&outputs);
 
-        self.initialize_providers(workspace).await?;
+        let stored = backend
+            .list_resources(
+                workspace_id,
+                &crate::state::models::ResourceFilter::default(),
+            )
+            .await?;
+        self.initialize_providers(workspace, stored_states(&stored))
+            .await?;
+        let reverse_graph = destroy_graph(&graph, workspace, &stored)?;
 
         let executor: NodeExecutor = Box::new(move |_idx: NodeIndex, node: DagNode| {
             let pm = Arc::clone(&pm);
             let ws_id = ws_id.clone();
             let backend = Arc::clone(&backend_clone);
-            let var_defaults = var_defaults.clone();
+            let eval_base = eval_base.clone();
+            let outputs = Arc::clone(&outputs_clone);
 
             Box::pin(async move {
                 match node {
@@ -767,7 +1616,7 @@ impl ResourceEngine {
                         ref index,
                         ..
                     } => {
-                        let mut eval_ctx = EvalContext::plan_only(var_defaults.clone());
+                        let mut eval_ctx = eval_base.clone();
                         match index {
                             Some(crate::config::types::ResourceIndex::Count(i)) => {
                                 eval_ctx.count_index = Some(*i);
@@ -779,9 +1628,12 @@ impl ResourceEngine {
                             None => {}
                         }
                         // Get current state
-                        let current_state = backend
-                            .get_resource(&ws_id, address)
-                            .await?
+                        let current = backend.get_resource(&ws_id, address).await?;
+                        let current_private = current
+                            .as_ref()
+                            .map(|r| r.private_data.clone())
+                            .unwrap_or_default();
+                        let current_state = current
                             .map(|r| serde_json::from_str::<serde_json::Value>(&r.attributes_json))
                             .transpose()?;
 
@@ -790,7 +1642,10 @@ impl ResourceEngine {
                             return Ok(None);
                         }
 
-                        let user_config = attributes_to_json(&config.attributes, &eval_ctx);
+                        let user_config = attributes_to_json(&config.attributes, &eval_ctx)
+                            .with_context(|| {
+                                format!("Failed to evaluate the configuration of {}", address)
+                            })?;
 
                         // Build full config with all schema attributes for msgpack encoding
                         let config_json = if let Ok(Some(schema)) =
@@ -801,6 +1656,21 @@ impl ResourceEngine {
                             user_config
                         };
 
+                        // Destroy-time provisioners run before the object is destroyed;
+                        // a failure keeps it in place.
+                        let provisioner_ctx = eval_ctx.with_self(current_state.clone());
+                        let provisioners = resolve_provisioners(
+                            &config.provisioners,
+                            ProvisionerWhen::Destroy,
+                            &provisioner_ctx,
+                        )?;
+                        let mut log = String::new();
+                        let provisioned = run_provisioners(address, &provisioners, &mut log).await;
+                        if !log.is_empty() {
+                            outputs.insert(address.clone(), log);
+                        }
+                        provisioned?;
+
                         // Plan destroy (proposed_new_state = null)
                         let plan_result = pm
                             .plan_resource(
@@ -809,6 +1679,7 @@ impl ResourceEngine {
                                 current_state.as_ref(),
                                 None, // null planned state = destroy
                                 &config_json,
+                                &current_private,
                             )
                             .await?;
 
@@ -821,6 +1692,7 @@ impl ResourceEngine {
                                 None, // null planned state = destroy
                                 &config_json,
                                 &plan_result.planned_private,
+                                config.timeouts.delete_timeout(),
                             )
                             .await?;
 
@@ -828,6 +1700,18 @@ impl ResourceEngine {
                         backend.delete_resource(&ws_id, address).await?;
                         info!(address = %address, "Resource destroyed");
 
+                        destroy_deposed(
+                            &pm,
+                            backend.as_ref(),
+                            &ws_id,
+                            address,
+                            provider_source,
+                            resource_type,
+                            &config_json,
+                            config.timeouts.delete_timeout(),
+                        )
+                        .await?;
+
                         // Return the prior state's ID so the walker can display it
                         let resource_id = current_state
                             .as_ref()
@@ -836,13 +1720,50 @@ impl ResourceEngine {
                             .map(|id| serde_json::json!({"id": id}));
                         Ok(resource_id)
                     }
+                    DagNode::Orphan {
+                        ref address,
+                        ref resource_type,
+                        ref provider_source,
+                        ..
+                    } => {
+                        destroy_orphan(
+                            &pm,
+                            backend.as_ref(),
+                            &ws_id,
+                            address,
+                            provider_source,
+                            resource_type,
+                        )
+                        .await
+                    }
                     _ => Ok(None),
                 }
             })
         });
 
-        let walker = DagWalker::new(self.parallelism);
+        let walker = DagWalker::new(self.parallelism)
+            .with_provider_limits(build_provider_limits(workspace))
+            .with_cancellation(self.cancel.clone());
         let start = std::time::Instant::now();
+        let record_action: RecordAction = Arc::new(|node: &DagNode| match node {
+            DagNode::Resource { .. } | DagNode::Orphan { .. } => {
+                Some((crate::state::models::action::DELETE.to_string(), None))
+            }
+            _ => None,
+        });
+        let executor = track_resource_status(
+            executor,
+            Arc::clone(&backend),
+            workspace_id,
+            Arc::clone(&record_action),
+        );
+        let executor = record_node_results(
+            executor,
+            Arc::clone(&backend),
+            run_id,
+            Arc::clone(&record_action),
+            outputs,
+        );
         let results = walker
             .walk(
                 &reverse_graph,
@@ -850,16 +1771,21 @@ impl ResourceEngine {
                 crate::dag::walker::WalkMode::Destroy,
             )
             .await?;
+        record_skipped_nodes(
+            backend.as_ref(),
+            run_id,
+            &reverse_graph,
+            &results,
+            &record_action,
+        )
+        .await;
         let elapsed_secs = start.elapsed().as_secs();
 
         let destroyed = results
             .iter()
             .filter(|r| r.status == NodeStatus::Succeeded)
             .count();
-        let failed = results
-            .iter()
-            .filter(|r| matches!(r.status, NodeStatus::Failed(_)))
-            .count();
+        let failed = results.iter().filter(|r| r.status.is_failure()).count();
         let skipped = results
             .iter()
             .filter(|r| matches!(r.status, NodeStatus::Skipped(_)))
@@ -877,60 +1803,1150 @@ impl ResourceEngine {
         })
     }
 
+    /// Import an existing resource via the provider's ImportResourceState RPC.
+    ///
+    /// Each imported object is refreshed with ReadResource so computed attributes
+    /// are populated. Returns one entry per imported object; providers may return
+    /// several (e.g. `aws_security_group` plus its rules).
+    pub async fn import_resource(
+        &self,
+        workspace: &WorkspaceConfig,
+        provider_source: &str,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Vec<ImportedResource>> {
+        // Nothing is read from state here, so providers configured from
+        // resources see those references as null.
+        self.initialize_providers(workspace, Arc::new(DashMap::new()))
+            .await?;
+
+        let imported = self
+            .provider_manager
+            .import_resource(provider_source, resource_type, id)
+            .await
+            .context(format!(
+                "Failed to import {} with id '{}'",
+                resource_type, id
+            ))?;
+
+        if imported.is_empty() {
+            anyhow::bail!(
+                "Provider returned no resources when importing {} with id '{}'",
+                resource_type,
+                id
+            );
+        }
+
+        let mut results = Vec::with_capacity(imported.len());
+        for mut resource in imported {
+            let refreshed = self
+                .provider_manager
+                .read_resource(
+                    provider_source,
+                    &resource.type_name,
+                    &resource.state,
+                    &resource.private_data,
+                )
+                .await
+                .context(format!("Failed to read imported {}", resource.type_name))?;
+
+            match refreshed.new_state {
+                Some(state) => {
+                    resource.state = state;
+                    resource.private_data = refreshed.private_data;
+                }
+                None => anyhow::bail!(
+                    "Cannot import non-existent remote object: {} with id '{}'",
+                    resource.type_name,
+                    id
+                ),
+            }
+            results.push(resource);
+        }
+
+        Ok(results)
+    }
+
     /// Initialize all providers referenced in the workspace.
-    async fn initialize_providers(&self, workspace: &WorkspaceConfig) -> Result<()> {
-        // Build variable defaults map for resolving var.xxx references
-        let var_defaults = build_variable_defaults(workspace);
+    async fn initialize_providers(
+        &self,
+        workspace: &WorkspaceConfig,
+        resource_states: Arc<DashMap<String, serde_json::Value>>,
+    ) -> Result<()> {
+        // Config that references resources resolves against their stored state
+        let ctx = EvalContext::for_workspace(
+            build_variable_defaults(workspace),
+            resource_states,
+            workspace,
+        );
 
-        for provider in &workspace.providers {
+        // Each aliased block gets its own connection, configured from its own body
+        for (address, provider) in provider_instances(workspace)? {
             let version = provider.version_constraint.as_deref().unwrap_or(">= 0.0.0");
 
             info!(
-                provider = %provider.source,
+                provider = %address,
                 version = %version,
                 "Initializing provider"
             );
 
             self.provider_manager
-                .get_connection(&provider.source, version)
+                .get_connection(&address, version)
                 .await
-                .context(format!("Failed to initialize provider {}", provider.source))?;
+                .context(format!("Failed to initialize provider {}", address))?;
 
-            // Get schema so we know all provider config attributes (required for cty msgpack)
-            let schema = self
-                .provider_manager
-                .get_schema(&provider.source, version)
-                .await
-                .context(format!(
-                    "Failed to get schema for provider {}",
-                    provider.source
-                ))?;
-
-            // Build full provider config with all attributes (unset ones as null)
-            let user_config = resolve_attributes(&provider.config, &var_defaults);
-            let full_config = build_full_provider_config(&user_config, &schema);
-            info!(
-                "Configuring provider with {} attributes",
-                full_config.as_object().map(|m| m.len()).unwrap_or(0)
+            configure_provider(&self.provider_manager, &address, &provider, &ctx).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the current state of `resources` from their providers, up to
+    /// `parallelism` reads at a time. Resources without a provider are skipped.
+    pub async fn refresh_resources(
+        &self,
+        resources: &[ResourceState],
+    ) -> HashMap<String, RefreshOutcome> {
+        refresh_concurrently(resources, self.parallelism, |resource| {
+            self.read_from_provider(resource)
+        })
+        .await
+    }
+
+    /// Plan a refresh-only run: read every object in state back from its
+    /// provider and report how state would change to match, without looking
+    /// at the configuration. Nothing is written; the plan's `state_updates`
+    /// record what was read once it is approved.
+    pub async fn plan_refresh_only(
+        &self,
+        workspace: &WorkspaceConfig,
+        backend: &dyn StateBackend,
+        workspace_id: &str,
+    ) -> Result<PlanSummary> {
+        let stored = backend
+            .list_resources(
+                workspace_id,
+                &crate::state::models::ResourceFilter::default(),
+            )
+            .await?;
+        self.initialize_providers(workspace, stored_states(&stored))
+            .await?;
+        let refreshed = refreshed_records(&stored, self.parallelism, true, |resource| {
+            self.read_from_provider(resource)
+        })
+        .await;
+        Ok(PlanSummary {
+            state_updates: StateUpdates::from_refresh(&stored, &refreshed),
+            ..PlanSummary::from_changes(refresh_only_changes(&stored, &refreshed), Vec::new())
+        })
+    }
+
+    /// Read one object in state back from its provider.
+    async fn read_from_provider(
+        &self,
+        resource: &ResourceState,
+    ) -> Result<Option<(serde_json::Value, Vec<u8>)>> {
+        let current: serde_json::Value =
+            serde_json::from_str(&resource.attributes_json).unwrap_or_default();
+        let read = self
+            .provider_manager
+            .read_resource(
+                &resource.provider_source,
+                &resource.resource_type,
+                &current,
+                &resource.private_data,
+            )
+            .await?;
+        Ok(read.new_state.map(|state| (state, read.private_data)))
+    }
+
+    /// Stop all running providers.
+    pub async fn shutdown(&self) -> Result<()> {
+        self.provider_manager.stop_all().await
+    }
+}
+
+// ─── Run Records ─────────────────────────────────────────────────────────────
+
+/// Action name and optional diff JSON to record for a node, or `None` to not
+/// record it.
+pub type RecordAction = Arc<dyn Fn(&DagNode) -> Option<(String, Option<String>)> + Send + Sync>;
+
+/// Output captured while executing a node (provisioner stdout/stderr), keyed
+/// by address. The executor fills it; `record_node_results` drains it.
+pub type NodeOutputs = Arc<DashMap<String, String>>;
+
+/// The `run_resources.action` value for a planned action.
+pub fn action_name(action: &ResourceAction) -> &'static str {
+    use crate::state::models::action;
+    match action {
+        ResourceAction::Create => action::CREATE,
+        ResourceAction::Update => action::UPDATE,
+        ResourceAction::Delete => action::DELETE,
+        ResourceAction::Replace => action::REPLACE,
+        ResourceAction::Read => action::READ,
+        ResourceAction::NoOp => action::NOOP,
+    }
+}
+
+/// Wrap `executor` so each recorded node writes a `ResourceResult` for
+/// `run_id` as soon as it finishes, including any output it left in `outputs`.
+/// Recording failures are logged, not fatal.
+pub fn record_node_results(
+    executor: NodeExecutor,
+    backend: Arc<dyn StateBackend>,
+    run_id: &str,
+    record_action: RecordAction,
+    outputs: NodeOutputs,
+) -> NodeExecutor {
+    let run_id = run_id.to_string();
+    Box::new(move |idx: NodeIndex, node: DagNode| {
+        let Some((action, diff_json)) = record_action(&node) else {
+            return executor(idx, node);
+        };
+        let address = node.address().to_string();
+        let started_at = chrono::Utc::now().to_rfc3339();
+        let run = executor(idx, node);
+        let backend = Arc::clone(&backend);
+        let run_id = run_id.clone();
+        let outputs = Arc::clone(&outputs);
+
+        Box::pin(async move {
+            let result = run.await;
+            let output = outputs.remove(&address).map(|(_, output)| output);
+            let (status, error_message) = match &result {
+                Ok(_) => (crate::state::models::run_status::SUCCEEDED, None),
+                Err(e) => (
+                    crate::state::models::run_status::FAILED,
+                    Some(format!("{:#}", e)),
+                ),
+            };
+            let record = crate::state::models::ResourceResult {
+                address,
+                action,
+                status: status.to_string(),
+                started_at: Some(started_at),
+                completed_at: Some(chrono::Utc::now().to_rfc3339()),
+                error_message,
+                diff_json,
+                output,
+            };
+            if let Err(e) = backend.record_resource_result(&run_id, &record).await {
+                tracing::warn!(address = %record.address, error = %e, "Failed to record resource result");
+            }
+            result
+        })
+    })
+}
+
+/// Wrap `executor` so each resource and data source it runs sends
+/// [`ApplyEvent`]s to `events`: started, progress every
+/// [`PROGRESS_INTERVAL`], then completed or failed. `actions` holds the
+/// planned action per address; resources without one are no-ops and data
+/// sources are reads.
+pub fn emit_apply_events(
+    executor: NodeExecutor,
+    events: mpsc::UnboundedSender<ApplyEvent>,
+    actions: HashMap<String, ResourceAction>,
+) -> NodeExecutor {
+    Box::new(move |idx: NodeIndex, node: DagNode| {
+        if !node.is_object() {
+            return executor(idx, node);
+        }
+        let address = node.address().to_string();
+        let action = match node {
+            DagNode::DataSource { .. } => ResourceAction::Read,
+            _ => actions
+                .get(&address)
+                .cloned()
+                .unwrap_or(ResourceAction::NoOp),
+        };
+        let _ = events.send(ApplyEvent::ResourceStarted {
+            address: address.clone(),
+            action: action.clone(),
+        });
+        let run = executor(idx, node);
+        let events = events.clone();
+
+        Box::pin(async move {
+            let start = std::time::Instant::now();
+            let progress = {
+                let events = events.clone();
+                let address = address.clone();
+                tokio::spawn(async move {
+                    let mut ticks = tokio::time::interval_at(
+                        tokio::time::Instant::now() + PROGRESS_INTERVAL,
+                        PROGRESS_INTERVAL,
+                    );
+                    loop {
+                        ticks.tick().await;
+                        let event = ApplyEvent::ResourceProgress {
+                            address: address.clone(),
+                            elapsed_secs: start.elapsed().as_secs(),
+                        };
+                        if events.send(event).is_err() {
+                            break;
+                        }
+                    }
+                })
+            };
+            let result = run.await;
+            progress.abort();
+            let _ = events.send(match &result {
+                Ok(_) => ApplyEvent::ResourceCompleted {
+                    address,
+                    action,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                },
+                Err(e) => ApplyEvent::ResourceFailed {
+                    address,
+                    error: format!("{:#}", e),
+                },
+            });
+            result
+        })
+    })
+}
+
+/// Wrap `executor` so the state record of each resource it changes shows
+/// what is happening to it: `creating`, `updating` or `deleting` while the
+/// operation runs, then `failed` if it fails. A successful operation writes
+/// the record's final status itself, and a resource already marked `tainted`
+/// (by a failed provisioner) stays tainted. Resources with no record yet have
+/// nothing to mark until their object exists. `record_action` names the
+/// planned action, as for [`record_node_results`]; no-ops aren't marked.
+pub fn track_resource_status(
+    executor: NodeExecutor,
+    backend: Arc<dyn StateBackend>,
+    workspace_id: &str,
+    record_action: RecordAction,
+) -> NodeExecutor {
+    use crate::state::models::{action, status};
+    let workspace_id = workspace_id.to_string();
+    Box::new(move |idx: NodeIndex, node: DagNode| {
+        let transient = match (&node, record_action(&node)) {
+            (DagNode::Resource { .. }, Some((name, _))) => match name.as_str() {
+                action::CREATE | action::REPLACE => Some(status::CREATING),
+                action::UPDATE => Some(status::UPDATING),
+                action::DELETE => Some(status::DELETING),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(transient) = transient else {
+            return executor(idx, node);
+        };
+        let address = node.address().to_string();
+        let run = executor(idx, node);
+        let backend = Arc::clone(&backend);
+        let workspace_id = workspace_id.clone();
+
+        Box::pin(async move {
+            let mark = |status: &'static str, unless: Option<&'static str>| {
+                let backend = Arc::clone(&backend);
+                let workspace_id = workspace_id.clone();
+                let address = address.clone();
+                async move {
+                    let Some(mut record) = backend.get_resource(&workspace_id, &address).await?
+                    else {
+                        return Ok::<_, anyhow::Error>(());
+                    };
+                    if unless == Some(record.status.as_str()) {
+                        return Ok(());
+                    }
+                    record.status = status.to_string();
+                    record.updated_at = chrono::Utc::now().to_rfc3339();
+                    backend.upsert_resource(&record).await
+                }
+            };
+            mark(transient, None).await?;
+            let result = run.await;
+            if result.is_err() {
+                if let Err(e) = mark(status::FAILED, Some(status::TAINTED)).await {
+                    warn!(address = %address, error = %e, "Failed to mark resource as failed");
+                }
+            }
+            result
+        })
+    })
+}
+
+/// Record nodes the walker skipped (because a dependency failed) as cancelled.
+async fn record_skipped_nodes(
+    backend: &dyn StateBackend,
+    run_id: &str,
+    graph: &resource_graph::ResourceGraph,
+    results: &[NodeResult],
+    record_action: &RecordAction,
+) {
+    for result in results {
+        let NodeStatus::Skipped(ref reason) = result.status else {
+            continue;
+        };
+        let Some((action, diff_json)) = record_action(&graph[result.node_index]) else {
+            continue;
+        };
+        let record = crate::state::models::ResourceResult {
+            address: result.address.clone(),
+            action,
+            status: crate::state::models::run_status::CANCELLED.to_string(),
+            started_at: None,
+            completed_at: None,
+            error_message: Some(reason.clone()),
+            diff_json,
+            output: None,
+        };
+        if let Err(e) = backend.record_resource_result(run_id, &record).await {
+            tracing::warn!(address = %record.address, error = %e, "Failed to record resource result");
+        }
+    }
+}
+
+// ─── Refresh ─────────────────────────────────────────────────────────────────
+
+/// Result of reading one resource back from its provider.
+#[derive(Debug)]
+pub enum RefreshOutcome {
+    /// The provider returned the current object and its private data.
+    Refreshed {
+        state: serde_json::Value,
+        private_data: Vec<u8>,
+    },
+    /// The object no longer exists.
+    Gone,
+    /// The read failed.
+    Failed(anyhow::Error),
+}
+
+/// The state changes a refresh-only plan proposes: an update for each managed
+/// object whose attributes changed outside oxid and a delete for each that no
+/// longer exists, comparing `stored` records with their `refreshed` versions
+/// (as returned by [`refreshed_records`]). Unchanged objects are left out.
+pub fn refresh_only_changes(
+    stored: &[ResourceState],
+    refreshed: &[ResourceState],
+) -> Vec<PlannedChange> {
+    let current: HashMap<&str, &ResourceState> =
+        refreshed.iter().map(|r| (r.address.as_str(), r)).collect();
+    stored
+        .iter()
+        .filter(|res| res.resource_mode == "managed")
+        .filter_map(|res| match current.get(res.address.as_str()) {
+            None => Some(delete_change(res)),
+            Some(now) if now.attributes_json != res.attributes_json => Some(PlannedChange {
+                action: ResourceAction::Update,
+                planned_state: serde_json::from_str(&now.attributes_json).ok(),
+                ..delete_change(res)
+            }),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+/// Run `read` for every resource with a provider, at most `parallelism` at a
+/// time, and collect the outcomes keyed by address. Nothing is written to
+/// state here, so callers can persist the results sequentially.
+pub async fn refresh_concurrently<'a, F, Fut>(
+    resources: &'a [ResourceState],
+    parallelism: usize,
+    read: F,
+) -> HashMap<String, RefreshOutcome>
+where
+    F: Fn(&'a ResourceState) -> Fut,
+    Fut: std::future::Future<Output = Result<Option<(serde_json::Value, Vec<u8>)>>>,
+{
+    let semaphore = tokio::sync::Semaphore::new(parallelism.max(1));
+    let reads = resources
+        .iter()
+        .filter(|r| !r.provider_source.is_empty())
+        .map(|resource| {
+            let semaphore = &semaphore;
+            let read = &read;
+            async move {
+                let _permit = semaphore.acquire().await.expect("semaphore closed");
+                let outcome = match read(resource).await {
+                    Ok(Some((state, private_data))) => RefreshOutcome::Refreshed {
+                        state,
+                        private_data,
+                    },
+                    Ok(None) => RefreshOutcome::Gone,
+                    Err(e) => RefreshOutcome::Failed(e),
+                };
+                (resource.address.clone(), outcome)
+            }
+        });
+    futures::future::join_all(reads).await.into_iter().collect()
+}
+
+/// The state records to plan against. With `refresh`, each record is read
+/// back through `read` first: refreshed objects replace the stored
+/// attributes, objects that no longer exist are dropped (so they plan as
+/// creates), and records whose read fails are kept as stored. Without it the
+/// stored records are returned as-is and `read` is never called.
+pub async fn refreshed_records<'a, F, Fut>(
+    resources: &'a [ResourceState],
+    parallelism: usize,
+    refresh: bool,
+    read: F,
+) -> Vec<ResourceState>
+where
+    F: Fn(&'a ResourceState) -> Fut,
+    Fut: std::future::Future<Output = Result<Option<(serde_json::Value, Vec<u8>)>>>,
+{
+    if !refresh {
+        return resources.to_vec();
+    }
+    let mut outcomes = refresh_concurrently(resources, parallelism, read).await;
+    resources
+        .iter()
+        .filter_map(|resource| match outcomes.remove(&resource.address) {
+            Some(RefreshOutcome::Refreshed {
+                state,
+                private_data,
+            }) => {
+                let mut updated = resource.clone();
+                updated.attributes_json = state.to_string();
+                updated.private_data = private_data;
+                updated.updated_at = chrono::Utc::now().to_rfc3339();
+                Some(updated)
+            }
+            Some(RefreshOutcome::Gone) => None,
+            Some(RefreshOutcome::Failed(e)) => {
+                warn!(address = %resource.address, error = %e, "Failed to refresh resource; using stored state");
+                Some(resource.clone())
+            }
+            None => Some(resource.clone()),
+        })
+        .collect()
+}
+
+// ─── Helper Functions ────────────────────────────────────────────────────────
+
+/// One step of a resource replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReplaceStep {
+    DestroyPrior,
+    CreateNew,
+}
+
+/// Order of the replace steps for a resource. Destroy-then-create by default;
+/// `create_before_destroy` brings the replacement up before retiring the old object.
+fn replace_steps(lifecycle: &crate::config::types::LifecycleConfig) -> [ReplaceStep; 2] {
+    if lifecycle.create_before_destroy {
+        [ReplaceStep::CreateNew, ReplaceStep::DestroyPrior]
+    } else {
+        [ReplaceStep::DestroyPrior, ReplaceStep::CreateNew]
+    }
+}
+
+/// The error for a failed replacement create. Once the prior object has been
+/// destroyed it is tagged, so the user hears that the resource is gone rather
+/// than just that it failed.
+pub fn replace_failure(err: anyhow::Error, address: &str, prior_destroyed: bool) -> anyhow::Error {
+    if prior_destroyed {
+        crate::error::OxidError::ReplaceFailedAfterDestroy {
+            address: address.to_string(),
+            source: err.into(),
+        }
+        .into()
+    } else {
+        err
+    }
+}
+
+/// State address under which a deposed object is tracked during a
+/// create_before_destroy replacement.
+pub fn deposed_address(address: &str) -> String {
+    format!("{} (deposed)", address)
+}
+
+fn index_key_string(index: &Option<crate::config::types::ResourceIndex>) -> Option<String> {
+    match index {
+        Some(crate::config::types::ResourceIndex::Count(i)) => Some(i.to_string()),
+        Some(crate::config::types::ResourceIndex::ForEach(k)) => Some(k.clone()),
+        None => None,
+    }
+}
+
+/// Destroy the deposed object recorded for `address`, if any, and drop its record.
+#[allow(clippy::too_many_arguments)]
+async fn destroy_deposed(
+    pm: &ProviderManager,
+    backend: &dyn StateBackend,
+    ws_id: &str,
+    address: &str,
+    provider_source: &str,
+    resource_type: &str,
+    config_json: &serde_json::Value,
+    timeout: std::time::Duration,
+) -> Result<()> {
+    let deposed_addr = deposed_address(address);
+    let Some(record) = backend.get_resource(ws_id, &deposed_addr).await? else {
+        return Ok(());
+    };
+    let deposed_state: serde_json::Value = serde_json::from_str(&record.attributes_json)?;
+
+    let destroy_plan = pm
+        .plan_resource(
+            provider_source,
+            resource_type,
+            Some(&deposed_state),
+            None,
+            config_json,
+            &record.private_data,
+        )
+        .await?;
+    pm.apply_resource(
+        provider_source,
+        resource_type,
+        Some(&deposed_state),
+        None,
+        config_json,
+        &destroy_plan.planned_private,
+        timeout,
+    )
+    .await
+    .context(format!("Failed to destroy deposed object for {}", address))?;
+
+    backend.delete_resource(ws_id, &deposed_addr).await?;
+    info!(address = %address, "Deposed object destroyed");
+    Ok(())
+}
+
+/// The graph a destroy walks: `graph` with its edges reversed, so dependents
+/// are destroyed first, plus an orphan for each managed object in `records`
+/// that no configured instance stands for.
+pub fn destroy_graph(
+    graph: &ResourceGraph,
+    workspace: &WorkspaceConfig,
+    records: &[ResourceState],
+) -> Result<ResourceGraph> {
+    let mut reversed = graph.clone();
+    reversed.reverse();
+    let configured: HashSet<&str> = graph.node_indices().map(|i| graph[i].address()).collect();
+    let orphans: Vec<DagNode> = records
+        .iter()
+        .filter(|r| {
+            r.resource_mode == "managed"
+                && r.status != crate::state::models::status::DEPOSED
+                && !configured.contains(r.address.as_str())
+        })
+        .map(|r| DagNode::Orphan {
+            address: r.address.clone(),
+            base_address: instance_base_address(&r.address).to_string(),
+            resource_type: r.resource_type.clone(),
+            provider_source: r.provider_source.clone(),
+        })
+        .collect();
+    resource_graph::add_orphans(&mut reversed, workspace, orphans)?;
+    Ok(reversed)
+}
+
+/// The resources of a [`destroy_graph`] that are in state (`in_state` holds
+/// their addresses), in the order a destroy removes them: dependents before
+/// their dependencies. Ties keep the graph's declaration order.
+pub fn destroy_steps(
+    graph: &ResourceGraph,
+    in_state: &HashSet<String>,
+) -> Result<Vec<DestroyStep>> {
+    let order = resource_graph::topological_order(graph)?;
+    Ok(order
+        .into_iter()
+        .filter_map(|idx| match &graph[idx] {
+            DagNode::Resource {
+                address,
+                resource_type,
+                ..
+            }
+            | DagNode::Orphan {
+                address,
+                resource_type,
+                ..
+            } if in_state.contains(address) => {
+                let mut dependents: Vec<String> = graph
+                    .neighbors_directed(idx, petgraph::Direction::Incoming)
+                    .map(|dep| graph[dep].address().to_string())
+                    .filter(|dep| in_state.contains(dep))
+                    .collect();
+                dependents.sort();
+                dependents.dedup();
+                Some(DestroyStep {
+                    address: address.clone(),
+                    resource_type: resource_type.clone(),
+                    dependents,
+                    error: None,
+                })
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Apply `moved` blocks to the records in state, renaming them in place so
+/// the plan diffs a moved resource against its new address instead of
+/// destroying and recreating it.
+///
+/// Chains (`a` → `b`, `b` → `c`) are followed whatever order they are
+/// declared in. A block whose source isn't in state has already been applied
+/// (or never needed to be) and is skipped; one whose destination is already in
+/// state is skipped with a warning. Returns `(old_address, moved_record)`
+/// pairs in the order they were applied, ready for
+/// [`StateBackend::move_resource`].
+pub fn resolve_moves(
+    moved: &[crate::config::types::MovedBlock],
+    records: &mut [ResourceState],
+) -> Result<Vec<(String, ResourceState)>> {
+    let targets: HashMap<&str, &str> = moved
+        .iter()
+        .map(|m| (m.from.as_str(), m.to.as_str()))
+        .collect();
+    for block in moved {
+        let mut chain = vec![block.from.as_str()];
+        let mut next = block.to.as_str();
+        while let Some(&to) = targets.get(next) {
+            chain.push(next);
+            if chain.contains(&to) {
+                chain.push(to);
+                bail!("moved blocks form a cycle: {}", chain.join(" → "));
+            }
+            next = to;
+        }
+    }
+
+    let in_state = |records: &[ResourceState], address: &str| {
+        let instance_prefix = format!("{}[", address);
+        records
+            .iter()
+            .any(|r| r.address == address || r.address.starts_with(&instance_prefix))
+    };
+    // A single instance only collides with the exact destination, so
+    // `x[0]` → `x` works while `x[1]` is still in state.
+    let taken = |records: &[ResourceState], block: &crate::config::types::MovedBlock| {
+        if instance_base_address(&block.from) == block.from {
+            in_state(records, &block.to)
+        } else {
+            records.iter().any(|r| r.address == block.to)
+        }
+    };
+
+    let mut applied = Vec::new();
+    // Each pass advances every resource at least one link along its chain
+    for _ in 0..=moved.len() {
+        let mut changed = false;
+        for block in moved {
+            if !in_state(records, &block.from) {
+                continue;
+            }
+            if taken(records, block) {
+                warn!(
+                    "Not moving {} to {}: both addresses are in state",
+                    block.from, block.to
+                );
+                continue;
+            }
+            for (from, record) in crate::state::address::plan_move(records, &block.from, &block.to)
+                .with_context(|| format!("moved block from {} to {}", block.from, block.to))?
+            {
+                if let Some(slot) = records.iter_mut().find(|r| r.address == from) {
+                    *slot = record.clone();
+                }
+                applied.push((from, record));
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    Ok(applied)
+}
+
+/// Turn planned updates and no-ops into replacements where a reference in the
+/// resource's `lifecycle.replace_triggered_by` changes. A reference to a
+/// resource (or one instance) fires when it is planned for update or
+/// replacement; a reference to an attribute (`aws_launch_template.app.id`)
+/// fires only when that attribute's planned value differs from its prior one
+/// or is unknown until apply. `count.index` and `each.key` in a reference
+/// stand for the instance being planned.
+///
+/// Triggers are judged against the actions planned by the provider, so one
+/// forced replacement does not in turn trigger others. Returns the replaced
+/// addresses with the reference that fired.
+pub fn apply_replace_triggers(
+    graph: &ResourceGraph,
+    changes: &mut [PlannedChange],
+) -> Vec<(String, String)> {
+    let mut fired = Vec::new();
+    for idx in graph.node_indices() {
+        let DagNode::Resource {
+            address,
+            config,
+            index,
+            ..
+        } = &graph[idx]
+        else {
+            continue;
+        };
+        let Some(change) = changes.iter().find(|c| &c.address == address) else {
+            continue;
+        };
+        if change.prior_state.is_none()
+            || !matches!(change.action, ResourceAction::NoOp | ResourceAction::Update)
+        {
+            continue;
+        }
+        let trigger = config
+            .lifecycle
+            .replace_triggered_by
+            .iter()
+            .find(|reference| {
+                let reference = instance_reference(reference, index);
+                let (target, path) = split_trigger_reference(&reference);
+                let target = format!("{}{}", config.module_prefix(), target);
+                changes
+                    .iter()
+                    .filter(|c| c.address == target || instance_base_address(&c.address) == target)
+                    .filter(|c| {
+                        matches!(c.action, ResourceAction::Update | ResourceAction::Replace)
+                    })
+                    .any(|c| {
+                        path.is_empty() || {
+                            let prior = c.prior_state.as_ref().and_then(|s| value_at(s, &path));
+                            let planned = c.planned_state.as_ref().and_then(|s| value_at(s, &path));
+                            planned.is_some_and(is_unknown) || prior != planned
+                        }
+                    })
+            });
+        if let Some(trigger) = trigger {
+            fired.push((address.clone(), trigger.clone()));
+        }
+    }
+    for (address, trigger) in &fired {
+        if let Some(change) = changes.iter_mut().find(|c| &c.address == address) {
+            change.action = ResourceAction::Replace;
+            change.replace_reason = Some(format!("replace_triggered_by {}", trigger));
+        }
+    }
+    fired
+}
+
+/// Fail unless every `--replace` address names a resource in state that
+/// `in_scope` (the `--target`/`--exclude` selection) plans.
+pub fn check_replace_addresses(
+    replace: &[String],
+    existing: &[ResourceState],
+    in_scope: impl Fn(&str) -> bool,
+) -> Result<()> {
+    for pattern in replace {
+        let mut matching = existing
+            .iter()
+            .filter(|r| address_matches(pattern, &r.address))
+            .peekable();
+        if matching.peek().is_none() {
+            bail!(
+                "Cannot replace {}: no resource with that address is in state",
+                pattern
+            );
+        }
+        if !matching.any(|r| in_scope(&r.address)) {
+            bail!(
+                "Cannot replace {}: it is excluded by --target/--exclude",
+                pattern
             );
+        }
+    }
+    Ok(())
+}
 
-            self.provider_manager
-                .configure_provider(&provider.source, &full_config)
-                .await
-                .context(format!("Failed to configure provider {}", provider.source))?;
+/// Plan every existing object named by `replace` (a resource address
+/// covers all its instances) as a replacement, whatever the provider
+/// planned. Returns the addresses whose action changed.
+pub fn apply_forced_replacements(replace: &[String], changes: &mut [PlannedChange]) -> Vec<String> {
+    let mut replaced = Vec::new();
+    for change in changes.iter_mut() {
+        if change.prior_state.is_none()
+            || !matches!(change.action, ResourceAction::NoOp | ResourceAction::Update)
+            || !replace.iter().any(|p| address_matches(p, &change.address))
+        {
+            continue;
         }
+        change.action = ResourceAction::Replace;
+        change.replace_reason = Some("requested with --replace".to_string());
+        replaced.push(change.address.clone());
+    }
+    replaced
+}
 
-        Ok(())
+/// `reference` with `count.index` and `each.key` replaced by `index`.
+fn instance_reference(
+    reference: &str,
+    index: &Option<crate::config::types::ResourceIndex>,
+) -> String {
+    match index {
+        Some(crate::config::types::ResourceIndex::Count(i)) => {
+            reference.replace("[count.index]", &format!("[{}]", i))
+        }
+        Some(crate::config::types::ResourceIndex::ForEach(key)) => {
+            reference.replace("[each.key]", &format!("[\"{}\"]", key))
+        }
+        None => reference.to_string(),
+    }
+}
+
+/// Split `aws_instance.web[0].tags.Name` into the resource address
+/// `aws_instance.web[0]` and the attribute path `["tags", "Name"]`.
+fn split_trigger_reference(reference: &str) -> (&str, Vec<&str>) {
+    let name_start = reference
+        .find('.')
+        .map(|i| i + 1)
+        .unwrap_or(reference.len());
+    let mut end = reference[name_start..]
+        .find(['.', '['])
+        .map(|i| name_start + i)
+        .unwrap_or(reference.len());
+    if reference[end..].starts_with('[') {
+        end = reference[end..]
+            .find(']')
+            .map(|i| end + i + 1)
+            .unwrap_or(reference.len());
+    }
+    let path = reference[end..]
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    (&reference[..end], path)
+}
+
+/// The value at `path` in a state object, indexing lists by number.
+fn value_at<'a>(value: &'a serde_json::Value, path: &[&str]) -> Option<&'a serde_json::Value> {
+    path.iter().try_fold(value, |value, segment| match value {
+        serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+        _ => value.get(segment),
+    })
+}
+
+/// Warnings for configured resources whose instances in state are indexed the
+/// other way: numbered `[0]` instances of a `for_each` resource, or keyed
+/// `["a"]` instances of a `count` resource. Run after [`resolve_moves`], so
+/// only instances no `moved` block mapped across are reported; each of them
+/// would be destroyed and recreated under its new address.
+pub fn index_scheme_migrations(
+    workspace: &WorkspaceConfig,
+    existing: &[ResourceState],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for resource in &workspace.resources {
+        let (stale_keyed, from, to) = match (&resource.count, &resource.for_each) {
+            (_, Some(_)) => (false, "count", "for_each"),
+            (Some(_), None) => (true, "for_each", "count"),
+            (None, None) => continue,
+        };
+        let base = format!(
+            "{}{}.{}",
+            resource.module_prefix(),
+            resource.resource_type,
+            resource.name
+        );
+        let stale: Vec<&str> = existing
+            .iter()
+            .filter(|r| {
+                r.resource_mode == "managed"
+                    && instance_base_address(&r.address) == base
+                    && r.address != base
+                    && r.address.ends_with("\"]") == stale_keyed
+            })
+            .map(|r| r.address.as_str())
+            .collect();
+        if !stale.is_empty() {
+            warnings.push(format!(
+                "{} switched from {} to {}; {} will be destroyed and recreated. \
+                 Add moved blocks to keep them.",
+                base,
+                from,
+                to,
+                stale.join(", ")
+            ));
+        }
     }
+    warnings
+}
+
+/// Delete changes for instances still in state that their configured resource
+/// no longer expands to: `count` lowered (down to 0) or a `for_each` key
+/// removed. Resources no longer in the configuration at all are left alone.
+pub fn plan_orphaned_instances(
+    workspace: &WorkspaceConfig,
+    graph: &ResourceGraph,
+    existing: &[ResourceState],
+) -> Vec<PlannedChange> {
+    let configured: HashSet<String> = workspace
+        .resources
+        .iter()
+        .map(|r| format!("{}{}.{}", r.module_prefix(), r.resource_type, r.name))
+        .collect();
+    let planned: HashSet<&str> = graph.node_indices().map(|i| graph[i].address()).collect();
+
+    let mut orphans: Vec<PlannedChange> = existing
+        .iter()
+        .filter(|res| {
+            res.resource_mode == "managed"
+                && res.status != crate::state::models::status::DEPOSED
+                && !planned.contains(res.address.as_str())
+                && configured.contains(instance_base_address(&res.address))
+        })
+        .map(delete_change)
+        .collect();
+    orphans.sort_by(|a, b| a.address.cmp(&b.address));
+    orphans
+}
+
+/// The records in state that `removed` blocks name, whole resources or single
+/// instances, each paired with whether its block destroys the object (the
+/// default) or only drops it from state.
+pub fn removed_records<'a>(
+    removed: &[crate::config::types::RemovedBlock],
+    existing: &'a [ResourceState],
+) -> Vec<(&'a ResourceState, bool)> {
+    existing
+        .iter()
+        .filter_map(|res| {
+            removed
+                .iter()
+                .find(|b| res.address == b.from || instance_base_address(&res.address) == b.from)
+                .map(|b| (res, b.destroy))
+        })
+        .collect()
+}
+
+/// The addresses that `removed` blocks with `destroy = false` drop from
+/// state without destroying their objects.
+pub fn forgotten_addresses(
+    removed: &[crate::config::types::RemovedBlock],
+    existing: &[ResourceState],
+) -> Vec<String> {
+    removed_records(removed, existing)
+        .into_iter()
+        .filter(|(_, destroy)| !destroy)
+        .map(|(res, _)| res.address.clone())
+        .collect()
+}
+
+/// A planned delete of the object recorded in `res`.
+fn delete_change(res: &ResourceState) -> PlannedChange {
+    PlannedChange {
+        address: res.address.clone(),
+        action: ResourceAction::Delete,
+        resource_type: res.resource_type.clone(),
+        provider_source: res.provider_source.clone(),
+        planned_state: None,
+        prior_state: serde_json::from_str(&res.attributes_json).ok(),
+        user_config: None,
+        requires_replace: vec![],
+        replace_reason: None,
+        planned_private: vec![],
+    }
+}
+
+/// `aws_instance.web` for `aws_instance.web[0]` or `aws_instance.web["a"]`.
+fn instance_base_address(address: &str) -> &str {
+    match address.strip_suffix(']').and_then(|a| a.rfind('[')) {
+        Some(open) => &address[..open],
+        None => address,
+    }
+}
+
+/// Destroy the object recorded at `address` and drop its state record,
+/// returning its ID for the walker to show.
+async fn destroy_orphan(
+    pm: &ProviderManager,
+    backend: &dyn StateBackend,
+    ws_id: &str,
+    address: &str,
+    provider_source: &str,
+    resource_type: &str,
+) -> Result<Option<serde_json::Value>> {
+    let Some(record) = backend.get_resource(ws_id, address).await? else {
+        return Ok(None);
+    };
+    let prior: serde_json::Value = serde_json::from_str(&record.attributes_json)?;
+
+    let destroy_plan = pm
+        .plan_resource(
+            provider_source,
+            resource_type,
+            Some(&prior),
+            None,
+            &serde_json::Value::Null,
+            &record.private_data,
+        )
+        .await?;
+    pm.apply_resource(
+        provider_source,
+        resource_type,
+        Some(&prior),
+        None,
+        &serde_json::Value::Null,
+        &destroy_plan.planned_private,
+        DEFAULT_OPERATION_TIMEOUT,
+    )
+    .await
+    .with_context(|| format!("Failed to destroy {}", address))?;
+
+    backend.delete_resource(ws_id, address).await?;
+    Ok(prior
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(|id| serde_json::json!({ "id": id })))
+}
+
+/// Configure the provider instance at `address` from `provider`'s block, with
+/// references resolved against `ctx`.
+async fn configure_provider(
+    pm: &ProviderManager,
+    address: &str,
+    provider: &ProviderConfig,
+    ctx: &EvalContext,
+) -> Result<()> {
+    let version = provider.version_constraint.as_deref().unwrap_or(">= 0.0.0");
+
+    // Get schema so we know all provider config attributes (required for cty msgpack)
+    let schema = pm
+        .get_schema(address, version)
+        .await
+        .context(format!("Failed to get schema for provider {}", address))?;
+
+    // Build full provider config with all attributes (unset ones as null)
+    let user_config = attributes_to_json(&provider.config, ctx).with_context(|| {
+        format!(
+            "Failed to evaluate the configuration of provider {}",
+            address
+        )
+    })?;
+    let full_config = build_full_provider_config(&user_config, &schema);
+    info!(
+        "Configuring provider with {} attributes",
+        full_config.as_object().map(|m| m.len()).unwrap_or(0)
+    );
+
+    pm.configure_provider(address, &full_config)
+        .await
+        .context(format!("Failed to configure provider {}", address))
+}
 
-    /// Stop all running providers.
-    pub async fn shutdown(&self) -> Result<()> {
-        self.provider_manager.stop_all().await
+/// Attributes of each stored record keyed by address, for resolving
+/// references to resources that are not being applied.
+fn stored_states<'a>(
+    records: impl IntoIterator<Item = &'a ResourceState>,
+) -> Arc<DashMap<String, serde_json::Value>> {
+    let states = Arc::new(DashMap::new());
+    for res in records {
+        if let Ok(attrs) = serde_json::from_str::<serde_json::Value>(&res.attributes_json) {
+            states.insert(res.address.clone(), attrs);
+        }
     }
+    states
 }
 
-// ─── Helper Functions ────────────────────────────────────────────────────────
-
 /// Build a map from provider local name to source string.
 pub fn build_provider_map(workspace: &WorkspaceConfig) -> HashMap<String, String> {
     let mut map = HashMap::new();
@@ -948,8 +2964,77 @@ pub fn build_provider_map(workspace: &WorkspaceConfig) -> HashMap<String, String
     map
 }
 
+/// The provider instances to start and configure, keyed by provider address.
+///
+/// Every declared block is an instance (`hashicorp/aws`, `hashicorp/aws.west`).
+/// When a provider is only declared with aliases, resources that don't pick an
+/// alias use an implicit empty default configuration, as in Terraform.
+/// References to undeclared aliases are rejected.
+pub fn provider_instances(workspace: &WorkspaceConfig) -> Result<Vec<(String, ProviderConfig)>> {
+    let mut instances: Vec<(String, ProviderConfig)> = workspace
+        .providers
+        .iter()
+        .map(|p| (provider_address(&p.source, p.alias.as_deref()), p.clone()))
+        .collect();
+
+    let provider_map = build_provider_map(workspace);
+    for resource in workspace.resources.iter().chain(&workspace.data_sources) {
+        let address = resource_graph::resolve_provider_source(resource, &provider_map);
+        if instances.iter().any(|(a, _)| *a == address) {
+            continue;
+        }
+        let (source, alias) = split_provider_alias(&address);
+        let aliased = workspace.providers.iter().find(|p| {
+            p.alias.is_some()
+                && (p.source == source || provider_map.get(&p.name).is_some_and(|s| s == source))
+        });
+        match (alias, aliased) {
+            (Some(alias), _) => bail!(
+                "{}{}.{}: provider {} has no configuration with alias \"{}\"",
+                resource.module_prefix(),
+                resource.resource_type,
+                resource.name,
+                source,
+                alias
+            ),
+            (None, Some(declared)) => {
+                let default = ProviderConfig {
+                    alias: None,
+                    max_parallel: None,
+                    config: HashMap::new(),
+                    depends_on: Vec::new(),
+                    ..declared.clone()
+                };
+                instances.push((address, default));
+            }
+            // No block at all: nothing to configure
+            (None, None) => {}
+        }
+    }
+    Ok(instances)
+}
+
+/// Build a map from provider address to its `max_parallel` cap.
+/// Aliased configurations run as separate provider processes, so each alias
+/// has its own cap (keyed `source.alias`).
+pub fn build_provider_limits(workspace: &WorkspaceConfig) -> HashMap<String, usize> {
+    let provider_map = build_provider_map(workspace);
+    let mut limits: HashMap<String, usize> = HashMap::new();
+    for provider in &workspace.providers {
+        if let Some(limit) = provider.max_parallel {
+            let source = provider_map
+                .get(&provider.name)
+                .cloned()
+                .unwrap_or_else(|| provider.source.clone());
+            limits.insert(provider_address(&source, provider.alias.as_deref()), limit);
+        }
+    }
+    limits
+}
+
 /// Evaluation context for resolving expressions.
 /// Contains variable defaults and completed resource states for cross-resource references.
+#[derive(Clone)]
 pub struct EvalContext {
     pub var_defaults: HashMap<String, serde_json::Value>,
     /// Completed resource states keyed by address (e.g. "aws_s3_bucket.public_scripts").
@@ -961,6 +3046,13 @@ pub struct EvalContext {
     pub each_key: Option<String>,
     /// Current for_each value.
     pub each_value: Option<serde_json::Value>,
+    /// Root for `file()`/`templatefile()` paths. Defaults to the current directory.
+    pub config_dir: Option<PathBuf>,
+    /// The current resource's own post-apply state, for `self.ATTR` in provisioners.
+    pub self_state: Option<serde_json::Value>,
+    /// Name of the active state workspace, for `terraform.workspace`.
+    /// Defaults to `default`.
+    pub workspace_name: Option<String>,
 }
 
 impl EvalContext {
@@ -971,6 +3063,9 @@ impl EvalContext {
             count_index: None,
             each_key: None,
             each_value: None,
+            config_dir: None,
+            self_state: None,
+            workspace_name: None,
         }
     }
 
@@ -984,38 +3079,197 @@ impl EvalContext {
             count_index: None,
             each_key: None,
             each_value: None,
+            config_dir: None,
+            self_state: None,
+            workspace_name: None,
+        }
+    }
+
+    /// Context for expressions of `workspace`: `file()` paths resolve against
+    /// its root directory and `terraform.workspace` is its state workspace.
+    pub fn for_workspace(
+        var_defaults: HashMap<String, serde_json::Value>,
+        resource_states: Arc<DashMap<String, serde_json::Value>>,
+        workspace: &WorkspaceConfig,
+    ) -> Self {
+        Self::with_states(var_defaults, resource_states)
+            .with_config_dir(workspace.root_dir.as_deref())
+            .with_workspace_name(workspace.workspace_name.as_deref())
+    }
+
+    /// Resolve `file()` and `templatefile()` paths against `dir`.
+    pub fn with_config_dir(mut self, dir: Option<&Path>) -> Self {
+        self.config_dir = dir.map(Path::to_path_buf);
+        self
+    }
+
+    /// Report `name` as `terraform.workspace`.
+    pub fn with_workspace_name(mut self, name: Option<&str>) -> Self {
+        self.workspace_name = name.map(str::to_string);
+        self
+    }
+
+    /// Bind `self` to the current resource's state.
+    pub fn with_self(mut self, state: Option<serde_json::Value>) -> Self {
+        self.self_state = state;
+        self
+    }
+}
+
+/// Read a file for `file()`/`templatefile()`. Relative paths resolve against
+/// the config directory; the result must stay inside it.
+pub fn read_config_file(path: &str, ctx: &EvalContext) -> Result<String> {
+    let root = ctx.config_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("Config directory {} not found", root.display()))?;
+    let resolved = root
+        .join(path)
+        .canonicalize()
+        .with_context(|| format!("No file exists at {}", path))?;
+    if !resolved.starts_with(&root) {
+        bail!(
+            "{} is outside the configuration directory {}",
+            path,
+            root.display()
+        );
+    }
+    std::fs::read_to_string(&resolved).with_context(|| format!("Failed to read {}", path))
+}
+
+/// Render `${...}` references in template `content` against the `vars` object.
+fn render_template(content: &str, vars: &serde_json::Value) -> serde_json::Value {
+    use crate::config::types::{Expression, TemplatePart};
+
+    let lookup = |expr: &Expression| match expr {
+        Expression::Reference(parts) => traverse_json_value(vars, parts).unwrap_or_default(),
+        _ => serde_json::Value::Null,
+    };
+    match crate::hcl::parser::parse_template_string(content) {
+        Expression::Template(parts) => {
+            let mut result = String::new();
+            for part in &parts {
+                match part {
+                    TemplatePart::Literal(s) => result.push_str(s),
+                    TemplatePart::Interpolation(expr) | TemplatePart::Directive(expr) => {
+                        match lookup(expr) {
+                            serde_json::Value::String(s) => result.push_str(&s),
+                            serde_json::Value::Null => {}
+                            val => result.push_str(&val.to_string()),
+                        }
+                    }
+                }
+            }
+            serde_json::Value::String(result)
+        }
+        // The whole file is a single interpolation: keep the value's type
+        expr @ Expression::Reference(_) => lookup(&expr),
+        _ => serde_json::Value::String(content.to_string()),
+    }
+}
+
+/// Whole-number function argument; numeric strings are accepted as in Terraform.
+fn json_to_i64(value: &serde_json::Value) -> Option<i64> {
+    match value {
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64)),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Name a failed function call in its error, e.g. `cidrsubnet: ...`.
+fn function_result<T: Into<serde_json::Value>>(
+    function: &str,
+    result: Result<T>,
+) -> Result<serde_json::Value> {
+    result
+        .map(Into::into)
+        .with_context(|| format!("{}()", function))
+}
+
+/// Copy of `value` with object keys in sorted order at every level, so encoded
+/// output (e.g. `yamlencode`) is stable across plans.
+fn sort_object_keys(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.clone(), sort_object_keys(v)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(sort_object_keys).collect())
         }
+        other => other.clone(),
     }
 }
 
 /// Convert attribute expressions to a JSON object, resolving variable and resource references.
+/// Attributes are evaluated and inserted in key order, not the `HashMap`'s
+/// arbitrary one.
 pub fn attributes_to_json(
     attrs: &HashMap<String, crate::config::types::Expression>,
     ctx: &EvalContext,
-) -> serde_json::Value {
+) -> Result<serde_json::Value> {
+    let mut keys: Vec<&String> = attrs.keys().collect();
+    keys.sort();
     let mut map = serde_json::Map::new();
-    for (key, expr) in attrs {
-        map.insert(key.clone(), eval_expression(expr, ctx));
+    for key in keys {
+        let value = eval_expression(&attrs[key], ctx)
+            .with_context(|| format!("Invalid value for \"{}\"", key))?;
+        map.insert(key.clone(), value);
     }
-    serde_json::Value::Object(map)
+    Ok(serde_json::Value::Object(map))
+}
+
+/// Evaluate the configs of the `when`-time provisioners of a resource.
+/// Returns each provisioner with its resolved config, in declaration order;
+/// bind `self` on `ctx` first so `self.ATTR` references resolve.
+pub fn resolve_provisioners<'a>(
+    provisioners: &'a [ProvisionerConfig],
+    when: ProvisionerWhen,
+    ctx: &EvalContext,
+) -> Result<Vec<(&'a ProvisionerConfig, serde_json::Value)>> {
+    provisioners
+        .iter()
+        .filter(|p| p.when == when)
+        .map(|p| {
+            let config = attributes_to_json(&p.config, ctx)
+                .with_context(|| format!("provisioner \"{}\"", p.provisioner_type))?;
+            Ok((p, config))
+        })
+        .collect()
 }
 
-/// Evaluate an expression to a JSON value, resolving variable and resource references.
+/// Evaluate an expression to a JSON value, resolving variable and resource
+/// references. Fails on genuine errors (an attribute a known object doesn't
+/// have, an index out of range, a function rejecting its arguments) rather
+/// than folding them into null; null stays a legitimate result for unset and
+/// not-yet-known values. Only `try` and `can` consume the errors.
 pub fn eval_expression(
     expr: &crate::config::types::Expression,
     ctx: &EvalContext,
-) -> serde_json::Value {
+) -> Result<serde_json::Value> {
     use crate::config::types::{Expression, TemplatePart};
-    match expr {
-        Expression::Literal(val) => resolve_value_json(val, ctx),
-        Expression::Reference(parts) => resolve_reference(parts, ctx),
+    Ok(match expr {
+        Expression::Literal(val) => resolve_value_json(val, ctx)?,
+        Expression::Reference(parts) => resolve_reference(parts, ctx)?,
         Expression::Template(parts) => {
             let mut result = String::new();
             for part in parts {
                 match part {
                     TemplatePart::Literal(s) => result.push_str(s),
                     TemplatePart::Interpolation(expr) => {
-                        let val = eval_expression(expr, ctx);
+                        let val = eval_expression(expr, ctx)?;
+                        if is_unknown(&val) {
+                            return Ok(val);
+                        }
                         match val {
                             serde_json::Value::String(s) => result.push_str(&s),
                             serde_json::Value::Number(n) => result.push_str(&n.to_string()),
@@ -1025,7 +3279,7 @@ pub fn eval_expression(
                         }
                     }
                     TemplatePart::Directive(expr) => {
-                        let val = eval_expression(expr, ctx);
+                        let val = eval_expression(expr, ctx)?;
                         if let serde_json::Value::String(s) = val {
                             result.push_str(&s);
                         }
@@ -1034,24 +3288,64 @@ pub fn eval_expression(
             }
             serde_json::Value::String(result)
         }
+        // try and can see their arguments' errors, so they evaluate lazily
+        Expression::FunctionCall { name, args } if name == "try" => {
+            let mut last_error = None;
+            for arg in args {
+                match eval_expression(arg, ctx) {
+                    Ok(value) => return Ok(value),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+            return Err(match last_error {
+                Some(e) => e.context("try(): no argument could be evaluated"),
+                None => anyhow::anyhow!("try(): at least one argument is required"),
+            });
+        }
+        Expression::FunctionCall { name, args } if name == "can" => match args.as_slice() {
+            [arg] => serde_json::Value::Bool(eval_expression(arg, ctx).is_ok()),
+            _ => bail!("can(): exactly one argument is required"),
+        },
         Expression::FunctionCall { name, args } => {
-            let evaluated_args: Vec<serde_json::Value> =
-                args.iter().map(|a| eval_expression(a, ctx)).collect();
+            let evaluated_args = args
+                .iter()
+                .map(|a| eval_expression(a, ctx))
+                .collect::<Result<Vec<_>>>()?;
+            // A function of a value known only after apply is too, wherever
+            // in its arguments the unknown sits. Only length and the
+            // sensitivity markers see past unknowns nested in a collection.
+            let sees_past_nested = matches!(name.as_str(), "length" | "sensitive" | "nonsensitive");
+            if evaluated_args
+                .iter()
+                .any(|a| is_unknown(a) || (!sees_past_nested && contains_unknown(a)))
+            {
+                return Ok(unknown_value());
+            }
             match name.as_str() {
-                "tolist" | "toset" => evaluated_args
-                    .into_iter()
-                    .next()
-                    .unwrap_or(serde_json::Value::Null),
+                // Sensitivity is tracked by the `sensitivity` taint analysis;
+                // the values themselves pass through unchanged.
+                "sensitive" | "nonsensitive" => match evaluated_args.as_slice() {
+                    [value] => value.clone(),
+                    _ => bail!("{}(): exactly one argument is required", name),
+                },
+                "toset" => match evaluated_args.first() {
+                    Some(value) => function_result("toset", super::collections::to_set(value))?,
+                    None => serde_json::Value::Null,
+                },
+                "tolist" => match evaluated_args.first() {
+                    Some(value) => function_result("tolist", super::collections::to_list(value))?,
+                    None => serde_json::Value::Null,
+                },
                 "tostring" => match evaluated_args.into_iter().next() {
                     Some(serde_json::Value::String(s)) => serde_json::Value::String(s),
                     Some(v) => serde_json::Value::String(v.to_string()),
                     None => serde_json::Value::Null,
                 },
                 "tonumber" => match evaluated_args.first() {
-                    Some(serde_json::Value::String(s)) => s
-                        .parse::<f64>()
-                        .map(|n| serde_json::json!(n))
-                        .unwrap_or(serde_json::Value::Null),
+                    Some(serde_json::Value::String(s)) => match s.parse::<f64>() {
+                        Ok(n) => serde_json::json!(n),
+                        Err(_) => bail!("tonumber(): cannot convert \"{}\" to a number", s),
+                    },
                     Some(v @ serde_json::Value::Number(_)) => v.clone(),
                     _ => serde_json::Value::Null,
                 },
@@ -1059,7 +3353,7 @@ pub fn eval_expression(
                     Some(serde_json::Value::String(s)) => match s.as_str() {
                         "true" => serde_json::Value::Bool(true),
                         "false" => serde_json::Value::Bool(false),
-                        _ => serde_json::Value::Null,
+                        _ => bail!("tobool(): cannot convert \"{}\" to a bool", s),
                     },
                     Some(v @ serde_json::Value::Bool(_)) => v.clone(),
                     _ => serde_json::Value::Null,
@@ -1080,7 +3374,101 @@ pub fn eval_expression(
                 }
                 "jsondecode" => {
                     if let Some(serde_json::Value::String(s)) = evaluated_args.first() {
-                        serde_json::from_str(s).unwrap_or(serde_json::Value::Null)
+                        serde_json::from_str(s).context("jsondecode()")?
+                    } else {
+                        serde_json::Value::Null
+                    }
+                }
+                "file" => match evaluated_args.first() {
+                    Some(serde_json::Value::String(path)) => serde_json::Value::String(
+                        read_config_file(path, ctx).with_context(|| format!("file({:?})", path))?,
+                    ),
+                    _ => serde_json::Value::Null,
+                },
+                "templatefile" => match evaluated_args.first() {
+                    Some(serde_json::Value::String(path)) => {
+                        let vars = evaluated_args
+                            .get(1)
+                            .cloned()
+                            .unwrap_or_else(|| serde_json::json!({}));
+                        let content = read_config_file(path, ctx)
+                            .with_context(|| format!("templatefile({:?})", path))?;
+                        render_template(&content, &vars)
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "cidrhost" => match (evaluated_args.first(), evaluated_args.get(1)) {
+                    (Some(serde_json::Value::String(prefix)), Some(hostnum)) => {
+                        match json_to_i64(hostnum) {
+                            Some(hostnum) => function_result(
+                                "cidrhost",
+                                super::cidr::cidr_host(prefix, hostnum),
+                            )?,
+                            None => serde_json::Value::Null,
+                        }
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "cidrsubnet" => match (
+                    evaluated_args.first(),
+                    evaluated_args.get(1).and_then(json_to_i64),
+                    evaluated_args.get(2).and_then(json_to_i64),
+                ) {
+                    (Some(serde_json::Value::String(prefix)), Some(newbits), Some(netnum)) => {
+                        function_result(
+                            "cidrsubnet",
+                            super::cidr::cidr_subnet(prefix, newbits, netnum),
+                        )?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "cidrnetmask" => match evaluated_args.first() {
+                    Some(serde_json::Value::String(prefix)) => {
+                        function_result("cidrnetmask", super::cidr::cidr_netmask(prefix))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "md5" | "sha1" | "sha256" | "sha512" => match evaluated_args.first() {
+                    Some(serde_json::Value::String(s)) => {
+                        let digest = match name.as_str() {
+                            "md5" => super::crypto::md5_hex(s),
+                            "sha1" => super::crypto::sha1_hex(s),
+                            "sha256" => super::crypto::sha256_hex(s),
+                            _ => super::crypto::sha512_hex(s),
+                        };
+                        serde_json::Value::String(digest)
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                // Non-deterministic: a fresh value on every evaluation
+                "uuid" => serde_json::Value::String(super::crypto::uuid()),
+                "base64encode" => match evaluated_args.first() {
+                    Some(serde_json::Value::String(s)) => {
+                        serde_json::Value::String(super::encoding::base64_encode(s.as_bytes()))
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "base64decode" => match evaluated_args.first() {
+                    Some(serde_json::Value::String(s)) => {
+                        function_result("base64decode", super::encoding::base64_decode(s))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "base64gzip" => match evaluated_args.first() {
+                    Some(serde_json::Value::String(s)) => {
+                        function_result("base64gzip", super::encoding::base64_gzip(s))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "yamlencode" => match evaluated_args.first() {
+                    Some(val) => serde_yaml::to_string(&sort_object_keys(val))
+                        .map(serde_json::Value::String)
+                        .unwrap_or(serde_json::Value::Null),
+                    None => serde_json::Value::Null,
+                },
+                "yamldecode" => {
+                    if let Some(serde_json::Value::String(s)) = evaluated_args.first() {
+                        serde_yaml::from_str(s).context("yamldecode()")?
                     } else {
                         serde_json::Value::Null
                     }
@@ -1259,10 +3647,20 @@ pub fn eval_expression(
                         serde_json::Value::Null
                     }
                 }
-                "try" => evaluated_args
-                    .into_iter()
-                    .find(|v| !v.is_null())
-                    .unwrap_or(serde_json::Value::Null),
+                "regex" => match (evaluated_args.first(), evaluated_args.get(1)) {
+                    (
+                        Some(serde_json::Value::String(pattern)),
+                        Some(serde_json::Value::String(s)),
+                    ) => {
+                        let re = regex::Regex::new(pattern)
+                            .with_context(|| format!("regex(): invalid pattern '{}'", pattern))?;
+                        match re.find(s) {
+                            Some(m) => serde_json::Value::String(m.as_str().to_string()),
+                            None => bail!("regex(): pattern '{}' did not match \"{}\"", pattern, s),
+                        }
+                    }
+                    _ => serde_json::Value::Null,
+                },
                 "compact" => {
                     if let Some(serde_json::Value::Array(arr)) = evaluated_args.into_iter().next() {
                         serde_json::Value::Array(
@@ -1308,10 +3706,56 @@ pub fn eval_expression(
                         serde_json::Value::Array(vec![])
                     }
                 }
-                other => {
-                    tracing::warn!("Unsupported function: {}()", other);
-                    serde_json::Value::Null
-                }
+                "setproduct" => function_result(
+                    "setproduct",
+                    super::collections::set_product(&evaluated_args),
+                )?,
+                "zipmap" => match (evaluated_args.first(), evaluated_args.get(1)) {
+                    (Some(keys), Some(values)) => {
+                        function_result("zipmap", super::collections::zipmap(keys, values))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "chunklist" => match (
+                    evaluated_args.first(),
+                    evaluated_args.get(1).and_then(json_to_i64),
+                ) {
+                    (Some(list), Some(size)) => {
+                        function_result("chunklist", super::collections::chunklist(list, size))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "slice" => match (
+                    evaluated_args.first(),
+                    evaluated_args.get(1).and_then(json_to_i64),
+                    evaluated_args.get(2).and_then(json_to_i64),
+                ) {
+                    (Some(list), Some(start), Some(end)) => {
+                        function_result("slice", super::collections::slice(list, start, end))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "contains" => match (evaluated_args.first(), evaluated_args.get(1)) {
+                    (Some(list), Some(value)) => {
+                        function_result("contains", super::collections::contains(list, value))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "index" => match (evaluated_args.first(), evaluated_args.get(1)) {
+                    (Some(list), Some(value)) => {
+                        function_result("index", super::collections::index(list, value))?
+                    }
+                    _ => serde_json::Value::Null,
+                },
+                "sort" => match evaluated_args.first() {
+                    Some(list) => function_result("sort", super::collections::sort(list))?,
+                    None => serde_json::Value::Null,
+                },
+                "reverse" => match evaluated_args.first() {
+                    Some(value) => function_result("reverse", super::collections::reverse(value))?,
+                    None => serde_json::Value::Null,
+                },
+                other => bail!("Unsupported function: {}()", other),
             }
         }
         Expression::Conditional {
@@ -1319,163 +3763,329 @@ pub fn eval_expression(
             true_val,
             false_val,
         } => {
-            let cond = eval_expression(condition, ctx);
+            let cond = eval_expression(condition, ctx)?;
+            if is_unknown(&cond) {
+                return Ok(cond);
+            }
             let is_true = match &cond {
                 serde_json::Value::Bool(b) => *b,
                 serde_json::Value::Null => false,
                 _ => true,
             };
             if is_true {
-                eval_expression(true_val, ctx)
+                eval_expression(true_val, ctx)?
             } else {
-                eval_expression(false_val, ctx)
+                eval_expression(false_val, ctx)?
+            }
+        }
+        Expression::BinaryOp { op, left, right } => {
+            let (left, right) = (eval_expression(left, ctx)?, eval_expression(right, ctx)?);
+            if is_unknown(&left) || is_unknown(&right) {
+                unknown_value()
+            } else {
+                eval_binary_op(*op, left, right)
+            }
+        }
+        Expression::UnaryOp { op, operand } => {
+            use crate::config::types::UnaryOp;
+            match (op, eval_expression(operand, ctx)?) {
+                (UnaryOp::Not, serde_json::Value::Bool(b)) => serde_json::Value::Bool(!b),
+                _ => serde_json::Value::Null,
             }
         }
         _ => serde_json::Value::Null,
+    })
+}
+
+/// Evaluate a comparison or logical operator, as used by `validation`
+/// conditions. Other operators evaluate to null.
+fn eval_binary_op(
+    op: crate::config::types::BinOp,
+    left: serde_json::Value,
+    right: serde_json::Value,
+) -> serde_json::Value {
+    use crate::config::types::BinOp;
+    use serde_json::Value as J;
+
+    let as_num = |v: &J| match v {
+        J::Number(n) => n.as_f64(),
+        J::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+    let equal = |l: &J, r: &J| match (as_num(l), as_num(r)) {
+        (Some(a), Some(b)) if matches!(l, J::Number(_)) && matches!(r, J::Number(_)) => a == b,
+        _ => l == r,
+    };
+
+    match op {
+        BinOp::Eq => J::Bool(equal(&left, &right)),
+        BinOp::NotEq => J::Bool(!equal(&left, &right)),
+        BinOp::And | BinOp::Or => match (&left, &right) {
+            (J::Bool(l), J::Bool(r)) => J::Bool(if op == BinOp::And { *l && *r } else { *l || *r }),
+            _ => J::Null,
+        },
+        BinOp::Lt | BinOp::Lte | BinOp::Gt | BinOp::Gte => {
+            let (Some(l), Some(r)) = (as_num(&left), as_num(&right)) else {
+                return J::Null;
+            };
+            J::Bool(match op {
+                BinOp::Lt => l < r,
+                BinOp::Lte => l <= r,
+                BinOp::Gt => l > r,
+                _ => l >= r,
+            })
+        }
+        _ => J::Null,
     }
 }
 
 /// Resolve a reference expression (var.xxx, aws_vpc.main.id, data.aws_ami.xxx.id, etc.)
-fn resolve_reference(parts: &[String], ctx: &EvalContext) -> serde_json::Value {
+fn resolve_reference(parts: &[String], ctx: &EvalContext) -> Result<serde_json::Value> {
     if parts.len() >= 2 && parts[0] == "var" {
-        if let Some(val) = ctx.var_defaults.get(&parts[1]) {
-            return val.clone();
-        }
-        return serde_json::Value::Null;
+        return match ctx.var_defaults.get(&parts[1]) {
+            Some(val) => traverse_json_value(val, &parts[2..]),
+            None => Ok(serde_json::Value::Null),
+        };
     }
 
     // count.index
     if parts.len() >= 2 && parts[0] == "count" && parts[1] == "index" {
-        if let Some(idx) = ctx.count_index {
-            return serde_json::json!(idx);
-        }
-        return serde_json::Value::Null;
+        return Ok(ctx
+            .count_index
+            .map(|idx| serde_json::json!(idx))
+            .unwrap_or(serde_json::Value::Null));
     }
 
     // each.key / each.value
     if parts.len() >= 2 && parts[0] == "each" {
-        match parts[1].as_str() {
-            "key" => {
-                return ctx
-                    .each_key
-                    .as_ref()
-                    .map(|k| serde_json::Value::String(k.clone()))
-                    .unwrap_or(serde_json::Value::Null);
-            }
-            "value" => {
-                return ctx.each_value.clone().unwrap_or(serde_json::Value::Null);
-            }
-            _ => return serde_json::Value::Null,
-        }
+        return match parts[1].as_str() {
+            "key" => Ok(ctx
+                .each_key
+                .as_ref()
+                .map(|k| serde_json::Value::String(k.clone()))
+                .unwrap_or(serde_json::Value::Null)),
+            "value" => match &ctx.each_value {
+                Some(value) => traverse_json_value(value, &parts[2..]),
+                None => Ok(serde_json::Value::Null),
+            },
+            other => bail!("each has no attribute \"{}\"", other),
+        };
+    }
+
+    // terraform.workspace
+    if parts.len() == 2 && parts[0] == "terraform" && parts[1] == "workspace" {
+        return Ok(serde_json::Value::String(
+            ctx.workspace_name
+                .clone()
+                .unwrap_or_else(|| "default".to_string()),
+        ));
+    }
+
+    // self.ATTR — only bound while evaluating provisioners
+    if parts.len() >= 2 && parts[0] == "self" {
+        return match &ctx.self_state {
+            Some(state) => traverse_json_value(state, &parts[1..]),
+            None => Ok(serde_json::Value::Null),
+        };
     }
 
-    // data.TYPE.NAME.ATTR
+    // Resources inside expanded modules: module.NAME.TYPE.NAME.ATTR
+    let (module_prefix, parts) = resource_graph::split_module_prefix(parts);
+
+    // data.TYPE.NAME.ATTR, data.TYPE.NAME[KEY].ATTR
     if parts.len() >= 4 && parts[0] == "data" {
-        let address = format!("data.{}.{}", parts[1], parts[2]);
-        if let Some(state) = ctx.resource_states.get(&address) {
-            return traverse_json_value(state.value(), &parts[3..]);
-        }
-        return serde_json::Value::Null;
+        let address = format!("{}data.{}.{}", module_prefix, parts[1], parts[2]);
+        return resolve_instance_reference(&address, &parts[3..], ctx);
     }
 
     // resource references: TYPE.NAME.ATTR (e.g. aws_s3_bucket.public_scripts.id)
-    // Also handles splat: TYPE.NAME.[*].ATTR → collects from all indexed instances
     if parts.len() >= 3 {
-        let address = format!("{}.{}", parts[0], parts[1]);
+        let address = format!("{}{}.{}", module_prefix, parts[0], parts[1]);
+        return resolve_instance_reference(&address, &parts[2..], ctx);
+    }
+
+    // A whole resource, TYPE.NAME. Resource types are always PROVIDER_KIND, so
+    // a root without an underscore that isn't local, path and friends names
+    // nothing at all.
+    if let [root, name] = parts {
+        if root.contains('_') {
+            let address = format!("{}{}.{}", module_prefix, root, name);
+            return Ok(ctx
+                .resource_states
+                .get(&address)
+                .map(|state| state.value().clone())
+                .unwrap_or(serde_json::Value::Null));
+        }
+        if !matches!(
+            root.as_str(),
+            "local" | "path" | "terraform" | "module" | "data"
+        ) {
+            bail!("Reference to undeclared object \"{}.{}\"", root, name);
+        }
+    }
 
+    Ok(serde_json::Value::Null)
+}
+
+/// Look up `path` in the state stored for `address`. A leading `[KEY]`
+/// selects one count/for_each instance; a leading `[*]` (splat) collects the
+/// rest of the path from every instance. A resource with no state yet is
+/// unknown and resolves to null.
+fn resolve_instance_reference(
+    address: &str,
+    path: &[String],
+    ctx: &EvalContext,
+) -> Result<serde_json::Value> {
+    match path.first().map(String::as_str) {
         // Splat: aws_instance.main[*].id → collect attr from all indexed instances
-        if parts.len() >= 4 && parts[2] == "[*]" {
-            let attr_path = &parts[3..];
+        Some("[*]") => {
+            let attr_path = &path[1..];
             let prefix = format!("{}[", address);
             let mut values: Vec<(String, serde_json::Value)> = Vec::new();
             for entry in ctx.resource_states.iter() {
                 if entry.key().starts_with(&prefix) || *entry.key() == address {
-                    let val = traverse_json_value(entry.value(), attr_path);
+                    let val = traverse_json_value(entry.value(), attr_path)
+                        .with_context(|| format!("in {}", entry.key()))?;
                     values.push((entry.key().clone(), val));
                 }
             }
             // Sort by key to get consistent ordering (e.g. [0], [1], [2], ...)
             values.sort_by(|a, b| a.0.cmp(&b.0));
-            return serde_json::Value::Array(values.into_iter().map(|(_, v)| v).collect());
+            Ok(serde_json::Value::Array(
+                values.into_iter().map(|(_, v)| v).collect(),
+            ))
         }
-
-        if let Some(state) = ctx.resource_states.get(&address) {
-            return traverse_json_value(state.value(), &parts[2..]);
+        Some(part) if part.len() >= 2 && part.starts_with('[') && part.ends_with(']') => {
+            let key = instance_key(&part[1..part.len() - 1], ctx);
+            // count instances are stored as NAME[0], for_each ones as NAME["key"]
+            match [
+                format!("{}[{}]", address, key),
+                format!("{}[\"{}\"]", address, key),
+            ]
+            .iter()
+            .find_map(|instance| ctx.resource_states.get(instance))
+            {
+                Some(state) => traverse_json_value(state.value(), &path[1..])
+                    .with_context(|| format!("in {}", state.key())),
+                None => Ok(serde_json::Value::Null),
+            }
         }
+        _ => match ctx.resource_states.get(address) {
+            Some(state) => {
+                traverse_json_value(state.value(), path).with_context(|| format!("in {}", address))
+            }
+            None => Ok(serde_json::Value::Null),
+        },
     }
+}
 
-    serde_json::Value::Null
+/// Resolve the key inside an instance index: a literal (`0`, `"a"`) or a
+/// reference such as `each.key` or `count.index`.
+fn instance_key(inner: &str, ctx: &EvalContext) -> String {
+    let inner = inner.trim();
+    if let Some(quoted) = inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return quoted.to_string();
+    }
+    if inner.contains('.') {
+        let parts: Vec<String> = inner.split('.').map(str::to_string).collect();
+        match resolve_reference(&parts, ctx) {
+            Ok(serde_json::Value::String(s)) => return s,
+            Ok(serde_json::Value::Number(n)) => return n.to_string(),
+            _ => {}
+        }
+    }
+    inner.to_string()
 }
 
 /// Traverse a JSON value by attribute path.
-/// e.g. ["id"] looks up state["id"], ["tags", "Name"] looks up state["tags"]["Name"]
-fn traverse_json_value(value: &serde_json::Value, path: &[String]) -> serde_json::Value {
+/// e.g. ["id"] looks up state["id"], ["tags", "Name"] looks up state["tags"]["Name"].
+/// Path steps may also be written as indexes: `[0]`, `["Name"]`.
+///
+/// Null anywhere along the way is unknown and yields null. Asking a known
+/// value for something it doesn't have is an error.
+fn traverse_json_value(value: &serde_json::Value, path: &[String]) -> Result<serde_json::Value> {
     let mut current = value;
-    for key in path {
-        match current {
-            serde_json::Value::Object(map) => {
-                if let Some(v) = map.get(key.as_str()) {
-                    current = v;
-                } else {
-                    return serde_json::Value::Null;
-                }
-            }
+    for step in path {
+        let key = step
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .map(|s| s.trim().trim_matches('"'))
+            .unwrap_or(step);
+        current = match current {
+            serde_json::Value::Null => return Ok(serde_json::Value::Null),
+            // Anything inside a value known only after apply is unknown too
+            unknown if is_unknown(unknown) => return Ok(unknown.clone()),
+            serde_json::Value::Object(map) => match map.get(key) {
+                Some(v) => v,
+                None => bail!("Unsupported attribute: object has no attribute \"{}\"", key),
+            },
             serde_json::Value::Array(arr) => {
-                if let Ok(idx) = key.parse::<usize>() {
-                    if let Some(v) = arr.get(idx) {
-                        current = v;
-                    } else {
-                        return serde_json::Value::Null;
-                    }
-                } else {
-                    return serde_json::Value::Null;
+                let Ok(idx) = key.parse::<usize>() else {
+                    bail!("Invalid index: a list can't be indexed by \"{}\"", key);
+                };
+                match arr.get(idx) {
+                    Some(v) => v,
+                    None => bail!(
+                        "Invalid index: index {} is out of range for a list of {} element(s)",
+                        idx,
+                        arr.len()
+                    ),
                 }
             }
-            _ => return serde_json::Value::Null,
-        }
+            other => bail!(
+                "Unsupported attribute: can't access \"{}\" on {}",
+                key,
+                other
+            ),
+        };
     }
-    current.clone()
+    Ok(current.clone())
 }
 
 /// Resolve a literal Value to JSON, handling string interpolation in nested values.
-fn resolve_value_json(val: &crate::config::types::Value, ctx: &EvalContext) -> serde_json::Value {
+fn resolve_value_json(
+    val: &crate::config::types::Value,
+    ctx: &EvalContext,
+) -> Result<serde_json::Value> {
     use crate::config::types::Value;
-    match val {
+    Ok(match val {
         Value::Null => serde_json::Value::Null,
         Value::Bool(b) => serde_json::Value::Bool(*b),
         Value::Int(i) => serde_json::json!(*i),
         Value::Float(f) => serde_json::json!(*f),
         Value::String(s) => {
             if s.contains("${") {
-                resolve_interpolated_string(s, ctx)
+                resolve_interpolated_string(s, ctx)?
             } else {
                 serde_json::Value::String(s.clone())
             }
         }
-        Value::List(items) => {
-            serde_json::Value::Array(items.iter().map(|v| resolve_value_json(v, ctx)).collect())
-        }
-        Value::Map(entries) => {
-            let map: serde_json::Map<String, serde_json::Value> = entries
+        Value::List(items) => serde_json::Value::Array(
+            items
                 .iter()
-                .map(|(k, v)| (k.clone(), resolve_value_json(v, ctx)))
-                .collect();
-            serde_json::Value::Object(map)
-        }
-    }
+                .map(|v| resolve_value_json(v, ctx))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Map(entries) => serde_json::Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), resolve_value_json(v, ctx)?)))
+                .collect::<Result<_>>()?,
+        ),
+    })
 }
 
 /// Resolve `${...}` interpolations in a string value.
 /// Handles both variable refs (${var.xxx}) and resource refs (${aws_s3_bucket.xxx.id}).
-fn resolve_interpolated_string(s: &str, ctx: &EvalContext) -> serde_json::Value {
+fn resolve_interpolated_string(s: &str, ctx: &EvalContext) -> Result<serde_json::Value> {
     // If the string is a single interpolation like "${aws_s3_bucket.xxx.id}",
     // return the raw value (could be non-string)
     if s.starts_with("${") && s.ends_with('}') && s.matches("${").count() == 1 {
         let ref_str = &s[2..s.len() - 1];
         let ref_parts: Vec<String> = ref_str.split('.').map(|p| p.trim().to_string()).collect();
-        let resolved = resolve_reference(&ref_parts, ctx);
+        let resolved = resolve_reference(&ref_parts, ctx)?;
         if !resolved.is_null() {
-            return resolved;
+            return Ok(resolved);
         }
     }
 
@@ -1488,7 +4098,10 @@ fn resolve_interpolated_string(s: &str, ctx: &EvalContext) -> serde_json::Value
         if let Some(end) = remaining[start + 2..].find('}') {
             let ref_str = &remaining[start + 2..start + 2 + end];
             let ref_parts: Vec<String> = ref_str.split('.').map(|p| p.trim().to_string()).collect();
-            let resolved = resolve_reference(&ref_parts, ctx);
+            let resolved = resolve_reference(&ref_parts, ctx)?;
+            if is_unknown(&resolved) {
+                return Ok(resolved);
+            }
             match resolved {
                 serde_json::Value::String(s) => result.push_str(&s),
                 serde_json::Value::Number(n) => result.push_str(&n.to_string()),
@@ -1504,7 +4117,7 @@ fn resolve_interpolated_string(s: &str, ctx: &EvalContext) -> serde_json::Value
     }
     result.push_str(remaining);
 
-    serde_json::Value::String(result)
+    Ok(serde_json::Value::String(result))
 }
 
 /// Build a map of variable name -> default JSON value from workspace variables.
@@ -1513,52 +4126,34 @@ pub fn build_variable_defaults(workspace: &WorkspaceConfig) -> HashMap<String, s
     let mut defaults = HashMap::new();
     for var in &workspace.variables {
         if let Some(ref default) = var.default {
-            defaults.insert(var.name.clone(), eval_expression(default, &empty_ctx));
+            // Loading the workspace already rejects defaults that fail to
+            // evaluate
+            let value = eval_expression(default, &empty_ctx).unwrap_or_else(|e| {
+                tracing::warn!(variable = %var.name, "{:#}", e);
+                serde_json::Value::Null
+            });
+            defaults.insert(var.name.clone(), value);
         }
     }
     defaults
 }
 
-/// Resolve attribute expressions to JSON, substituting variable references.
-fn resolve_attributes(
-    attrs: &HashMap<String, crate::config::types::Expression>,
-    var_defaults: &HashMap<String, serde_json::Value>,
-) -> serde_json::Value {
-    let ctx = EvalContext::plan_only(var_defaults.clone());
-    attributes_to_json(attrs, &ctx)
-}
-
 /// Build the full provider config object with all schema attributes.
-/// cty msgpack requires ALL attributes to be present (null for unset ones).
-fn build_full_provider_config(
+///
+/// cty msgpack requires ALL attributes to be present, so unset ones are sent
+/// as null, which providers read as "not configured" and fill from their
+/// environment (`AWS_REGION`, `GOOGLE_PROJECT`, ...) or their own defaults.
+/// Nothing else is synthesized for them: nested blocks follow the same
+/// nesting rules as resource config, so an unset single block is null rather
+/// than an empty list.
+pub fn build_full_provider_config(
     user_config: &serde_json::Value,
     schema: &serde_json::Value,
 ) -> serde_json::Value {
     let mut full = serde_json::Map::new();
 
-    if let Some(provider_schema) = schema.get("provider") {
-        if let Some(block) = provider_schema.get("block") {
-            if let Some(attrs) = block.get("attributes").and_then(|a| a.as_array()) {
-                for attr in attrs {
-                    if let Some(name) = attr.get("name").and_then(|n| n.as_str()) {
-                        let value = user_config
-                            .get(name)
-                            .cloned()
-                            .unwrap_or(serde_json::Value::Null);
-                        full.insert(name.to_string(), value);
-                    }
-                }
-            }
-            if let Some(block_types) = block.get("block_types").and_then(|b| b.as_array()) {
-                for bt in block_types {
-                    if let Some(name) = bt.get("type_name").and_then(|n| n.as_str()) {
-                        if !full.contains_key(name) {
-                            full.insert(name.to_string(), serde_json::json!([]));
-                        }
-                    }
-                }
-            }
-        }
+    if let Some(block) = schema.pointer("/provider/block") {
+        populate_block_attributes(&mut full, block, user_config);
     }
 
     if full.is_empty() {
@@ -1569,7 +4164,7 @@ fn build_full_provider_config(
 }
 
 /// Build a full resource config with all schema attributes.
-/// Similar to `build_full_provider_config`, but for resource types.
+/// Like `build_full_provider_config`, but for resource types.
 /// cty msgpack requires ALL attributes to be present (null for unset/computed).
 fn build_full_resource_config(
     user_config: &serde_json::Value,
@@ -1588,6 +4183,88 @@ fn build_full_resource_config(
     serde_json::Value::Object(full)
 }
 
+/// Names of the top-level attributes a resource schema marks `sensitive`.
+pub fn sensitive_attributes(schema: &serde_json::Value) -> Vec<String> {
+    schema
+        .pointer("/block/attributes")
+        .and_then(|a| a.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|a| a.get("sensitive").and_then(|s| s.as_bool()) == Some(true))
+        .filter_map(|a| a.get("name").and_then(|n| n.as_str()).map(String::from))
+        .collect()
+}
+
+/// Check every nested block's item count against the schema's `min_items`
+/// and `max_items`, so a missing required block fails with a clear message
+/// instead of an opaque provider error.
+///
+/// `config` is the output of `build_full_resource_config`. Unknown (null)
+/// collections are skipped since their size is not known until apply.
+pub fn validate_block_items(config: &serde_json::Value, schema: &serde_json::Value) -> Result<()> {
+    match schema.get("block") {
+        Some(block) => validate_block_items_in(config, block, ""),
+        None => Ok(()),
+    }
+}
+
+fn validate_block_items_in(
+    config: &serde_json::Value,
+    block: &serde_json::Value,
+    prefix: &str,
+) -> Result<()> {
+    let Some(block_types) = block.get("block_types").and_then(|b| b.as_array()) else {
+        return Ok(());
+    };
+    for bt in block_types {
+        let Some(name) = bt.get("type_name").and_then(|n| n.as_str()) else {
+            continue;
+        };
+        let path = format!("{}{}", prefix, name);
+        let nesting = bt.get("nesting").and_then(|n| n.as_i64()).unwrap_or(2);
+        let value = config.get(name).unwrap_or(&serde_json::Value::Null);
+
+        // (from tfplugin5.proto): SINGLE=1, LIST=2, SET=3, MAP=4, GROUP=5
+        let items: Vec<&serde_json::Value> = match (nesting, value) {
+            (2 | 3, serde_json::Value::Null) => continue,
+            (2 | 3, serde_json::Value::Array(arr)) => arr.iter().collect(),
+            (4, serde_json::Value::Object(map)) => map.values().collect(),
+            (_, serde_json::Value::Null) => vec![],
+            (_, other) => vec![other],
+        };
+
+        let min_items = bt.get("min_items").and_then(|n| n.as_i64()).unwrap_or(0);
+        let max_items = bt.get("max_items").and_then(|n| n.as_i64()).unwrap_or(0);
+        let count = items.len() as i64;
+        if min_items > 0 && count < min_items {
+            bail!(
+                "block `{}` requires at least {} item{}, got {}",
+                path,
+                min_items,
+                if min_items == 1 { "" } else { "s" },
+                count
+            );
+        }
+        if max_items > 0 && count > max_items {
+            bail!(
+                "block `{}` allows at most {} item{}, got {}",
+                path,
+                max_items,
+                if max_items == 1 { "" } else { "s" },
+                count
+            );
+        }
+
+        if let Some(nested) = bt.get("block") {
+            let nested_prefix = format!("{}.", path);
+            for item in items {
+                validate_block_items_in(item, nested, &nested_prefix)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Recursively populate all attributes from a schema block.
 fn populate_block_attributes(
     full: &mut serde_json::Map<String, serde_json::Value>,
@@ -1752,8 +4429,41 @@ fn populate_object_from_cty(
     value
 }
 
+/// Mark each attribute of `planned` whose configured value in `config` is
+/// unknown (it references a value only known after apply) as unknown too.
+/// The provider was sent null in its place, so whatever it planned there is
+/// not what apply will produce. Returns whether anything was unknown.
+pub fn propagate_config_unknowns(
+    config: &serde_json::Value,
+    planned: &mut serde_json::Value,
+) -> bool {
+    use serde_json::Value;
+    if is_unknown(config) {
+        *planned = unknown_value();
+        return true;
+    }
+    match (config, planned) {
+        (Value::Object(config), Value::Object(planned)) => {
+            let mut any = false;
+            for (key, value) in config {
+                let slot = planned.entry(key.clone()).or_insert(Value::Null);
+                any |= propagate_config_unknowns(value, slot);
+            }
+            any
+        }
+        (Value::Array(config), Value::Array(planned)) => config
+            .iter()
+            .zip(planned.iter_mut())
+            .fold(false, |any, (value, slot)| {
+                propagate_config_unknowns(value, slot) | any
+            }),
+        _ => false,
+    }
+}
+
 /// Determine what action to take based on prior and planned state.
-fn determine_action(
+/// Unknown planned values ("known after apply") never count as a change.
+pub fn determine_action(
     prior: Option<&serde_json::Value>,
     planned: Option<&serde_json::Value>,
     requires_replace: &[String],
@@ -1762,7 +4472,7 @@ fn determine_action(
         (None, Some(_)) => ResourceAction::Create,
         (Some(_), None) => ResourceAction::Delete,
         (Some(prior), Some(planned)) => {
-            if prior == planned {
+            if matches_known(prior, planned) {
                 ResourceAction::NoOp
             } else if !requires_replace.is_empty() {
                 ResourceAction::Replace
@@ -1,5 +1,11 @@
+pub mod cidr;
+pub mod collections;
+pub mod crypto;
+pub mod encoding;
 pub mod engine;
 pub mod output_parser;
 pub mod parallel;
+pub mod provisioner;
 pub mod retry;
+pub mod sensitivity;
 pub mod terraform;
@@ -0,0 +1,105 @@
+use anyhow::{bail, Context, Result};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::config::types::{ProvisionerConfig, ProvisionerOnFailure};
+
+/// The result of one `local-exec` command.
+#[derive(Debug, Clone)]
+pub struct LocalExecRun {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    /// Captured stdout followed by stderr.
+    pub output: String,
+}
+
+/// Run a `local-exec` provisioner: `command` through `interpreter` (default
+/// `/bin/sh -c`), in `working_dir`, with extra `environment` variables.
+pub async fn local_exec(config: &serde_json::Value) -> Result<LocalExecRun> {
+    let command = config
+        .get("command")
+        .and_then(|v| v.as_str())
+        .context("local-exec provisioner requires a \"command\" string")?;
+
+    let interpreter: Vec<String> = match config.get("interpreter") {
+        Some(serde_json::Value::Array(items)) if !items.is_empty() => items
+            .iter()
+            .map(|v| match v {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect(),
+        _ => vec!["/bin/sh".to_string(), "-c".to_string()],
+    };
+
+    let mut cmd = Command::new(&interpreter[0]);
+    cmd.args(&interpreter[1..]).arg(command);
+    if let Some(dir) = config.get("working_dir").and_then(|v| v.as_str()) {
+        cmd.current_dir(dir);
+    }
+    if let Some(env) = config.get("environment").and_then(|v| v.as_object()) {
+        for (key, value) in env {
+            match value {
+                serde_json::Value::String(s) => cmd.env(key, s),
+                serde_json::Value::Null => continue,
+                other => cmd.env(key, other.to_string()),
+            };
+        }
+    }
+
+    let output = cmd
+        .output()
+        .await
+        .with_context(|| format!("Failed to run local-exec command with {}", interpreter[0]))?;
+
+    let mut captured = String::from_utf8_lossy(&output.stdout).into_owned();
+    captured.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(LocalExecRun {
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        output: captured,
+    })
+}
+
+/// Run resolved provisioners in declaration order, appending their output to
+/// `log`. Stops at the first failure unless that provisioner sets
+/// `on_failure = continue`.
+pub async fn run_provisioners(
+    address: &str,
+    provisioners: &[(&ProvisionerConfig, serde_json::Value)],
+    log: &mut String,
+) -> Result<()> {
+    for (provisioner, config) in provisioners {
+        if provisioner.provisioner_type != "local-exec" {
+            warn!(
+                address = %address,
+                provisioner = %provisioner.provisioner_type,
+                "Unsupported provisioner type, skipping"
+            );
+            continue;
+        }
+
+        info!(address = %address, "Running local-exec provisioner");
+        let failure = match local_exec(config).await {
+            Ok(run) => {
+                log.push_str(&run.output);
+                if run.success {
+                    continue;
+                }
+                match run.exit_code {
+                    Some(code) => format!("local-exec provisioner exited with status {}", code),
+                    None => "local-exec provisioner was terminated by a signal".to_string(),
+                }
+            }
+            Err(e) => format!("{:#}", e),
+        };
+
+        match provisioner.on_failure {
+            ProvisionerOnFailure::Continue => {
+                warn!(address = %address, error = %failure, "Provisioner failed, continuing");
+            }
+            ProvisionerOnFailure::Fail => bail!("{}: {}", address, failure),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::config::types::{Expression, TemplatePart, WorkspaceConfig};
+use crate::state::models::ResourceState;
+
+/// Where sensitive values come from: variables declared `sensitive`, and
+/// resource attributes the provider schema marks sensitive.
+///
+/// Values are tracked by taint analysis of the expressions that use them
+/// rather than alongside evaluated values, so anything derived from a
+/// sensitive source is sensitive unless wrapped in `nonsensitive()`.
+#[derive(Debug, Default)]
+pub struct Sensitivity<'a> {
+    variables: HashSet<&'a str>,
+    locals: HashMap<&'a str, &'a Expression>,
+    /// Sensitive attribute names by resource address, without instance keys.
+    attributes: HashMap<String, HashSet<String>>,
+}
+
+impl<'a> Sensitivity<'a> {
+    pub fn new(workspace: &'a WorkspaceConfig, resources: &[ResourceState]) -> Self {
+        let mut attributes: HashMap<String, HashSet<String>> = HashMap::new();
+        for resource in resources {
+            if resource.sensitive_attrs.is_empty() {
+                continue;
+            }
+            let base = resource
+                .address
+                .split('[')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            attributes
+                .entry(base)
+                .or_default()
+                .extend(resource.sensitive_attrs.iter().cloned());
+        }
+        Self {
+            variables: workspace
+                .variables
+                .iter()
+                .filter(|v| v.sensitive)
+                .map(|v| v.name.as_str())
+                .collect(),
+            locals: workspace
+                .locals
+                .iter()
+                .map(|(name, expr)| (name.as_str(), expr))
+                .collect(),
+            attributes,
+        }
+    }
+
+    /// Whether the value of `expr` derives from a sensitive source.
+    pub fn is_sensitive(&self, expr: &Expression) -> bool {
+        self.tainted(expr, &mut Vec::new())
+    }
+
+    /// `locals` holds the locals being expanded, to stop at reference cycles.
+    fn tainted(&self, expr: &Expression, locals: &mut Vec<&'a str>) -> bool {
+        match expr {
+            Expression::FunctionCall { name, .. } if name == "nonsensitive" => false,
+            Expression::FunctionCall { name, .. } if name == "sensitive" => true,
+            Expression::FunctionCall { args, .. } => args.iter().any(|a| self.tainted(a, locals)),
+            Expression::Reference(parts) => self.reference(parts, locals),
+            Expression::GetAttr { .. } | Expression::Index { .. } => {
+                let mut path = Vec::new();
+                self.traversal(expr, &mut path, locals) || self.reference(&path, locals)
+            }
+            Expression::Literal(_) => false,
+            Expression::Conditional {
+                condition,
+                true_val,
+                false_val,
+            } => [condition, true_val, false_val]
+                .iter()
+                .any(|e| self.tainted(e, locals)),
+            Expression::ForExpr {
+                collection,
+                key_expr,
+                value_expr,
+                condition,
+                ..
+            } => {
+                self.tainted(collection, locals)
+                    || key_expr.as_ref().is_some_and(|k| self.tainted(k, locals))
+                    || self.tainted(value_expr, locals)
+                    || condition.as_ref().is_some_and(|c| self.tainted(c, locals))
+            }
+            Expression::Template(parts) => parts.iter().any(|part| match part {
+                TemplatePart::Interpolation(e) | TemplatePart::Directive(e) => {
+                    self.tainted(e, locals)
+                }
+                TemplatePart::Literal(_) => false,
+            }),
+            Expression::BinaryOp { left, right, .. } => {
+                self.tainted(left, locals) || self.tainted(right, locals)
+            }
+            Expression::UnaryOp { operand, .. } => self.tainted(operand, locals),
+            Expression::Splat { source, each } => {
+                self.tainted(source, locals) || self.tainted(each, locals)
+            }
+        }
+    }
+
+    /// Flatten an attribute/index chain into the names it traverses, e.g.
+    /// `aws_db_instance.main[0].password` → `[aws_db_instance, main, password]`.
+    /// Returns whether an index key or a non-reference root is itself sensitive.
+    fn traversal(
+        &self,
+        expr: &Expression,
+        path: &mut Vec<String>,
+        locals: &mut Vec<&'a str>,
+    ) -> bool {
+        match expr {
+            Expression::Reference(parts) => {
+                path.extend(parts.iter().cloned());
+                false
+            }
+            Expression::GetAttr { object, name } => {
+                let tainted = self.traversal(object, path, locals);
+                path.push(name.clone());
+                tainted
+            }
+            Expression::Index { collection, key } => {
+                self.traversal(collection, path, locals) || self.tainted(key, locals)
+            }
+            other => self.tainted(other, locals),
+        }
+    }
+
+    fn reference(&self, parts: &[String], locals: &mut Vec<&'a str>) -> bool {
+        // Instance keys (`[0]`) don't change which attribute is reached
+        let parts: Vec<&str> = parts
+            .iter()
+            .map(String::as_str)
+            .filter(|p| !p.starts_with('['))
+            .collect();
+        let name = |i: usize| parts.get(i).copied();
+        match name(0) {
+            Some("var") => name(1).is_some_and(|n| self.variables.contains(n)),
+            Some("local") => {
+                let Some((local, expr)) = name(1).and_then(|n| self.locals.get_key_value(n)) else {
+                    return false;
+                };
+                if locals.contains(local) {
+                    return false;
+                }
+                locals.push(*local);
+                let tainted = self.tainted(expr, locals);
+                locals.pop();
+                tainted
+            }
+            Some("each" | "count" | "path" | "terraform" | "self" | "module") | None => false,
+            Some("data") if parts.len() >= 3 => {
+                self.attribute(format!("data.{}.{}", parts[1], parts[2]), name(3))
+            }
+            Some(_) if parts.len() >= 2 => {
+                self.attribute(format!("{}.{}", parts[0], parts[1]), name(2))
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// A resource at `address` is sensitive as a whole if any of its
+    /// attributes is, and through `attribute` if that attribute is.
+    fn attribute(&self, address: String, attribute: Option<&str>) -> bool {
+        match (self.attributes.get(&address), attribute) {
+            (Some(sensitive), Some(attribute)) => sensitive.contains(attribute),
+            (Some(sensitive), None) => !sensitive.is_empty(),
+            (None, _) => false,
+        }
+    }
+}
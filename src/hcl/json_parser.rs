@@ -5,6 +5,7 @@ use serde_json::Value as JsonValue;
 
 use super::parser::parse_hcl_body;
 use crate::config::types::WorkspaceConfig;
+use crate::error::OxidError;
 
 /// Known block types and their expected label counts, matching Terraform's configFileSchema.
 const BLOCK_SCHEMA: &[(&str, usize)] = &[
@@ -22,8 +23,11 @@ const BLOCK_SCHEMA: &[(&str, usize)] = &[
 ///
 /// Converts JSON → hcl::Body → reuses the existing parse_hcl_body() logic.
 pub fn parse_tf_json(content: &str, file_path: &Path) -> Result<WorkspaceConfig> {
-    let root: JsonValue = serde_json::from_str(content)
-        .with_context(|| format!("Failed to parse JSON in: {}", file_path.display()))?;
+    let root: JsonValue = serde_json::from_str(content).map_err(|err| OxidError::ConfigParse {
+        language: "JSON",
+        location: format!("{}:{}:{}", file_path.display(), err.line(), err.column()),
+        source: Box::new(err),
+    })?;
 
     let root_obj = root
         .as_object()
@@ -229,7 +233,9 @@ fn build_single_block(
 fn is_nested_block(parent_block_type: &str, key: &str, _value: &JsonValue) -> bool {
     // Known nested blocks by parent type
     let is_known_nested = match parent_block_type {
-        "resource" | "data" => matches!(key, "lifecycle" | "provisioner" | "connection"),
+        "resource" | "data" => {
+            matches!(key, "lifecycle" | "provisioner" | "connection" | "timeouts")
+        }
         "terraform" => matches!(key, "backend" | "required_providers" | "cloud"),
         _ => false,
     };
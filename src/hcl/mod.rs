@@ -1,93 +1,203 @@
 pub mod json_parser;
 pub mod parser;
+pub mod variables;
 
 use std::collections::HashMap;
 use std::path::Path;
 
 use anyhow::{Context, Result};
 
-use crate::config::types::{Expression, Value, WorkspaceConfig};
+use crate::config::types::{Expression, SourceLocation, Value, WorkspaceConfig};
+use variables::VariableOverrides;
 
 /// Parse all .tf and .tf.json files in a directory into a unified WorkspaceConfig.
 pub fn parse_directory(dir: &Path) -> Result<WorkspaceConfig> {
+    parse_directory_with_overrides(dir, &VariableOverrides::default())
+}
+
+/// Parse a directory like [`parse_directory`], then apply command-line variable values.
+pub fn parse_directory_with_overrides(
+    dir: &Path,
+    overrides: &VariableOverrides,
+) -> Result<WorkspaceConfig> {
+    let mut workspace = parse_module_directory(dir)?;
+
+    // Load .tfvars files and apply them to variable defaults.
+    // Precedence (highest to lowest):
+    //   1. --var and --var-file command-line arguments
+    //   2. TF_VAR_xxx environment variables
+    //   3. workspaces/<workspace>.tfvars, then <workspace>.tfvars
+    //   4. *.auto.tfvars (alphabetical)
+    //   5. terraform.tfvars (if present)
+    //   6. Variable defaults from .tf files
+    let tfvars = load_tfvars(dir, overrides.workspace.as_deref())?;
+    apply_tfvars(&mut workspace, &tfvars);
+
+    // Apply TF_VAR_xxx environment variables
+    apply_env_vars(&mut workspace);
+
+    // Apply --var-file and --var values (highest precedence)
+    variables::apply_overrides(&mut workspace, overrides)?;
+
+    // Convert the resulting values to each variable's declared type
+    variables::coerce_variables(&mut workspace)?;
+
+    Ok(workspace)
+}
+
+/// Parse the .tf and .tf.json files of a module directory without applying
+/// .tfvars or TF_VAR_ overrides, which only ever target the root module.
+pub fn parse_module_directory(dir: &Path) -> Result<WorkspaceConfig> {
     let mut workspace = WorkspaceConfig::default();
 
-    let all_entries: Vec<std::path::PathBuf> = std::fs::read_dir(dir)?
+    // .tf and .tf.json files are read in one lexical order by file name, so
+    // the merged result doesn't depend on which format a file happens to use.
+    let mut config_files: Vec<std::path::PathBuf> = std::fs::read_dir(dir)?
         .filter_map(|e| e.ok())
         .map(|e| e.path())
+        .filter(|p| p.is_file() && config_format(p).is_some())
         .collect();
+    config_files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
 
-    // Collect .tf files (native HCL)
-    let mut tf_files: Vec<std::path::PathBuf> = all_entries
-        .iter()
-        .filter(|p| p.extension().map(|e| e == "tf").unwrap_or(false))
-        .cloned()
-        .collect();
-    tf_files.sort();
-
-    // Collect .tf.json files
-    let mut tf_json_files: Vec<std::path::PathBuf> = all_entries
-        .iter()
-        .filter(|p| {
-            p.file_name()
-                .and_then(|n| n.to_str())
-                .map(|n| n.ends_with(".tf.json"))
-                .unwrap_or(false)
-        })
-        .cloned()
-        .collect();
-    tf_json_files.sort();
-
-    if tf_files.is_empty() && tf_json_files.is_empty() {
+    if config_files.is_empty() {
         anyhow::bail!(
             "No .tf or .tf.json files found in directory: {}",
             dir.display()
         );
     }
 
-    // Parse .tf files first, then .tf.json files (matches Terraform behavior)
-    for file in &tf_files {
-        tracing::debug!("Parsing HCL file: {}", file.display());
-        let content = std::fs::read_to_string(file)?;
-        let partial = parser::parse_hcl(&content, file)?;
+    let mut declarations = Declarations::default();
+    for file in &config_files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let partial = match config_format(file) {
+            Some(ConfigFormat::Json) => {
+                tracing::debug!("Parsing tf.json file: {}", file.display());
+                json_parser::parse_tf_json(&content, file)?
+            }
+            _ => {
+                tracing::debug!("Parsing HCL file: {}", file.display());
+                parser::parse_hcl(&content, file)?
+            }
+        };
+        declarations.check(&partial, file)?;
         merge_workspace(&mut workspace, partial);
     }
+    check_removed(&workspace)?;
 
-    for file in &tf_json_files {
-        tracing::debug!("Parsing tf.json file: {}", file.display());
-        let content = std::fs::read_to_string(file)?;
-        let partial = json_parser::parse_tf_json(&content, file)?;
-        merge_workspace(&mut workspace, partial);
+    Ok(workspace)
+}
+
+/// A `removed` block may only name a resource the configuration no longer
+/// declares.
+fn check_removed(workspace: &WorkspaceConfig) -> Result<()> {
+    for block in &workspace.removed {
+        let base = block.from.split('[').next().unwrap_or_default();
+        if let Some(resource) = workspace
+            .resources
+            .iter()
+            .find(|r| format!("{}{}.{}", r.module_prefix(), r.resource_type, r.name) == base)
+        {
+            let at = resource
+                .source_location
+                .as_ref()
+                .map_or_else(String::new, |l| format!(" (declared at {})", l));
+            anyhow::bail!(
+                "removed block names {}, which is still declared{}. Delete the resource block to stop managing it.",
+                block.from,
+                at
+            );
+        }
     }
+    Ok(())
+}
 
-    // Load .tfvars files and apply them to variable defaults.
-    // Precedence (highest to lowest):
-    //   1. TF_VAR_xxx environment variables
-    //   2. terraform.tfvars (if present)
-    //   3. *.auto.tfvars (alphabetical)
-    //   4. Variable defaults from .tf files
-    let tfvars = load_tfvars(dir)?;
-    apply_tfvars(&mut workspace, &tfvars);
+/// Where each block of a module directory was first declared, so a second
+/// declaration with the same name is rejected instead of silently merged.
+#[derive(Default)]
+struct Declarations(HashMap<String, String>);
 
-    // Apply TF_VAR_xxx environment variables (highest precedence)
-    apply_env_vars(&mut workspace);
+impl Declarations {
+    /// Record every named block of `partial`, parsed from `file`.
+    fn check(&mut self, partial: &WorkspaceConfig, file: &Path) -> Result<()> {
+        let at = |location: Option<&SourceLocation>| {
+            location.map_or_else(|| file.display().to_string(), |l| l.to_string())
+        };
+        for r in &partial.resources {
+            let what = format!("resource \"{}.{}\"", r.resource_type, r.name);
+            self.declare(what, at(r.source_location.as_ref()))?;
+        }
+        for d in &partial.data_sources {
+            let what = format!("data source \"{}.{}\"", d.resource_type, d.name);
+            self.declare(what, at(d.source_location.as_ref()))?;
+        }
+        for p in &partial.providers {
+            let what = match &p.alias {
+                Some(alias) => format!("provider \"{}\" with alias \"{}\"", p.name, alias),
+                None => format!("provider \"{}\"", p.name),
+            };
+            self.declare(what, at(None))?;
+        }
+        for v in &partial.variables {
+            self.declare(format!("variable \"{}\"", v.name), at(None))?;
+        }
+        for o in &partial.outputs {
+            let what = format!("output \"{}\"", o.name);
+            self.declare(what, at(o.source_location.as_ref()))?;
+        }
+        for m in &partial.modules {
+            self.declare(format!("module \"{}\"", m.name), at(None))?;
+        }
+        Ok(())
+    }
 
-    Ok(workspace)
+    fn declare(&mut self, what: String, at: String) -> Result<()> {
+        if let Some(first) = self.0.get(&what) {
+            anyhow::bail!(
+                "Duplicate {}: declared in {} and again in {}",
+                what,
+                first,
+                at
+            );
+        }
+        self.0.insert(what, at);
+        Ok(())
+    }
+}
+
+enum ConfigFormat {
+    Hcl,
+    Json,
+}
+
+/// The syntax of a configuration file, or `None` if it isn't one.
+fn config_format(path: &Path) -> Option<ConfigFormat> {
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(".tf.json") {
+        Some(ConfigFormat::Json)
+    } else if name.ends_with(".tf") {
+        Some(ConfigFormat::Hcl)
+    } else {
+        None
+    }
 }
 
-/// Load variable values from .tfvars files in the directory.
-fn load_tfvars(dir: &Path) -> Result<HashMap<String, Expression>> {
+/// Load variable values from .tfvars files in the directory, including those
+/// of the selected `workspace`.
+fn load_tfvars(dir: &Path, workspace: Option<&str>) -> Result<HashMap<String, Expression>> {
     let mut values = HashMap::new();
 
-    // Load terraform.tfvars if present
-    let default_tfvars = dir.join("terraform.tfvars");
-    if default_tfvars.exists() {
-        tracing::info!("Loading {}", default_tfvars.display());
-        let parsed = parse_tfvars_file(&default_tfvars)?;
-        values.extend(parsed);
+    // Load terraform.tfvars, then terraform.tfvars.json, if present
+    for name in ["terraform.tfvars", "terraform.tfvars.json"] {
+        let default_tfvars = dir.join(name);
+        if default_tfvars.exists() {
+            tracing::info!("Loading {}", default_tfvars.display());
+            let parsed = parse_tfvars_file(&default_tfvars)?;
+            values.extend(parsed);
+        }
     }
 
-    // Load *.auto.tfvars files (alphabetical order)
+    // Load *.auto.tfvars and *.auto.tfvars.json files (alphabetical order)
     let entries = std::fs::read_dir(dir)?;
     let mut auto_files: Vec<std::path::PathBuf> = entries
         .filter_map(|e| e.ok())
@@ -95,7 +205,7 @@ fn load_tfvars(dir: &Path) -> Result<HashMap<String, Expression>> {
         .filter(|p| {
             p.file_name()
                 .and_then(|n| n.to_str())
-                .map(|n| n.ends_with(".auto.tfvars"))
+                .map(|n| n.ends_with(".auto.tfvars") || n.ends_with(".auto.tfvars.json"))
                 .unwrap_or(false)
         })
         .collect();
@@ -107,14 +217,33 @@ fn load_tfvars(dir: &Path) -> Result<HashMap<String, Expression>> {
         values.extend(parsed);
     }
 
+    // Load the selected workspace's <name>.tfvars and workspaces/<name>.tfvars
+    if let Some(name) = workspace {
+        let file_name = format!("{}.tfvars", name);
+        for file in [
+            dir.join(&file_name),
+            dir.join("workspaces").join(&file_name),
+        ] {
+            if file.exists() {
+                tracing::info!("Loading {}", file.display());
+                let parsed = parse_tfvars_file(&file)?;
+                values.extend(parsed);
+            }
+        }
+    }
+
     Ok(values)
 }
 
 /// Parse a single .tfvars file into a map of variable name → Expression.
-/// .tfvars files are HCL-formatted key-value assignments.
+/// .tfvars files are HCL-formatted key-value assignments; .tfvars.json files
+/// are a JSON object whose values are taken literally.
 fn parse_tfvars_file(path: &Path) -> Result<HashMap<String, Expression>> {
     let content =
         std::fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        return parse_tfvars_json(&content).context(format!("Failed to parse {}", path.display()));
+    }
     let body: hcl::Body =
         hcl::from_str(&content).context(format!("Failed to parse {}", path.display()))?;
 
@@ -128,6 +257,22 @@ fn parse_tfvars_file(path: &Path) -> Result<HashMap<String, Expression>> {
     Ok(values)
 }
 
+fn parse_tfvars_json(content: &str) -> Result<HashMap<String, Expression>> {
+    let json: serde_json::Value = serde_json::from_str(content)?;
+    let Some(entries) = json.as_object() else {
+        anyhow::bail!("expected a JSON object of variable values");
+    };
+    Ok(entries
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.clone(),
+                Expression::Literal(variables::json_to_value(value)),
+            )
+        })
+        .collect())
+}
+
 /// Apply tfvars values to workspace variables by overriding their defaults.
 fn apply_tfvars(workspace: &mut WorkspaceConfig, tfvars: &HashMap<String, Expression>) {
     for var in &mut workspace.variables {
@@ -156,6 +301,8 @@ fn merge_workspace(main: &mut WorkspaceConfig, partial: WorkspaceConfig) {
     main.variables.extend(partial.variables);
     main.outputs.extend(partial.outputs);
     main.locals.extend(partial.locals);
+    main.moved.extend(partial.moved);
+    main.removed.extend(partial.removed);
 
     if main.terraform_settings.is_none() {
         main.terraform_settings = partial.terraform_settings;
@@ -167,6 +314,9 @@ fn merge_workspace(main: &mut WorkspaceConfig, partial: WorkspaceConfig) {
             if main_tf.required_version.is_none() {
                 main_tf.required_version = partial_tf.required_version;
             }
+            if main_tf.backend.is_none() {
+                main_tf.backend = partial_tf.backend;
+            }
         }
     }
 }
@@ -1,15 +1,87 @@
 use std::collections::HashMap;
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Result};
 
 use crate::config::types::*;
+use crate::error::OxidError;
 
 /// Parse a single HCL file into a partial WorkspaceConfig.
 pub fn parse_hcl(content: &str, file_path: &Path) -> Result<WorkspaceConfig> {
-    let body: hcl::Body = hcl::from_str(content)
-        .with_context(|| format!("Failed to parse HCL in: {}", file_path.display()))?;
-    parse_hcl_body(body, file_path)
+    let body: hcl::Body = hcl::from_str(content).map_err(|err| {
+        let location = match &err {
+            hcl::Error::Parse(parse_err) => format!(
+                "{}:{}:{}",
+                file_path.display(),
+                parse_err.location().line(),
+                parse_err.location().column()
+            ),
+            _ => file_path.display().to_string(),
+        };
+        OxidError::ConfigParse {
+            language: "HCL",
+            location,
+            source: Box::new(err),
+        }
+    })?;
+    let mut workspace = parse_hcl_body(body, file_path)?;
+    annotate_source_locations(&mut workspace, content);
+    Ok(workspace)
+}
+
+/// Fill in line/column of resources, data sources and outputs.
+///
+/// `hcl::Body` carries no spans, so the source is re-parsed with `hcl-edit`
+/// and each top-level block is matched by its identifier and labels.
+fn annotate_source_locations(workspace: &mut WorkspaceConfig, content: &str) {
+    use hcl_edit::Span;
+
+    let Ok(body) = hcl_edit::parser::parse_body(content) else {
+        return;
+    };
+    let mut positions: HashMap<(String, Vec<String>), (usize, usize)> = HashMap::new();
+    for block in body.blocks() {
+        let Some(span) = block.ident.span() else {
+            continue;
+        };
+        let labels = block
+            .labels
+            .iter()
+            .map(|l| l.as_str().to_string())
+            .collect();
+        positions
+            .entry((block.ident.as_str().to_string(), labels))
+            .or_insert_with(|| line_column(content, span.start));
+    }
+
+    let apply = |kind: &str, labels: Vec<String>, location: &mut Option<SourceLocation>| {
+        if let (Some(loc), Some(&(line, column))) = (
+            location.as_mut(),
+            positions.get(&(kind.to_string(), labels)),
+        ) {
+            loc.line = line;
+            loc.column = column;
+        }
+    };
+    for r in &mut workspace.resources {
+        let labels = vec![r.resource_type.clone(), r.name.clone()];
+        apply("resource", labels, &mut r.source_location);
+    }
+    for d in &mut workspace.data_sources {
+        let labels = vec![d.resource_type.clone(), d.name.clone()];
+        apply("data", labels, &mut d.source_location);
+    }
+    for o in &mut workspace.outputs {
+        apply("output", vec![o.name.clone()], &mut o.source_location);
+    }
+}
+
+/// Convert a byte offset into a 1-based (line, column) pair.
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
 }
 
 /// Parse an hcl::Body into a partial WorkspaceConfig.
@@ -49,7 +121,7 @@ pub(crate) fn parse_hcl_body(body: hcl::Body, file_path: &Path) -> Result<Worksp
                         }
                     }
                     "output" => {
-                        if let Some(out) = parse_output_block(&block)? {
+                        if let Some(out) = parse_output_block(&block, &file_str)? {
                             workspace.outputs.push(out);
                         }
                     }
@@ -62,6 +134,12 @@ pub(crate) fn parse_hcl_body(body: hcl::Body, file_path: &Path) -> Result<Worksp
                         let locals = parse_locals_block(&block)?;
                         workspace.locals.extend(locals);
                     }
+                    "moved" => {
+                        workspace.moved.push(parse_moved_block(&block)?);
+                    }
+                    "removed" => {
+                        workspace.removed.push(parse_removed_block(&block)?);
+                    }
                     _ => {
                         tracing::debug!("Ignoring unknown block type: {}", ident);
                     }
@@ -92,6 +170,19 @@ fn parse_terraform_block(block: &hcl::Block) -> Result<TerraformSettings> {
                             settings.required_providers.insert(name, req);
                         }
                     }
+                } else if inner_block.identifier() == "backend" {
+                    let Some(backend_type) = inner_block.labels().first() else {
+                        bail!("backend block requires a type label, e.g. backend \"s3\" {{}}");
+                    };
+                    let attributes = inner_block
+                        .body()
+                        .attributes()
+                        .map(|attr| (attr.key.to_string(), hcl_expr_to_expression(&attr.expr)))
+                        .collect();
+                    settings.backend = Some(BackendConfig {
+                        backend_type: backend_type.as_str().to_string(),
+                        attributes,
+                    });
                 }
             }
             hcl::Structure::Attribute(attr) => {
@@ -136,6 +227,8 @@ fn parse_provider_block(block: &hcl::Block) -> Result<Option<ProviderConfig>> {
 
     let name = labels[0].clone();
     let mut alias = None;
+    let mut max_parallel = None;
+    let mut depends_on = Vec::new();
     let mut config = HashMap::new();
 
     for structure in block.body().iter() {
@@ -143,6 +236,20 @@ fn parse_provider_block(block: &hcl::Block) -> Result<Option<ProviderConfig>> {
             let key: &str = &attr.key;
             if key == "alias" {
                 alias = Some(expr_to_string(&attr.expr));
+            } else if key == "max_parallel" {
+                // oxid-specific meta-argument; never forwarded to the provider
+                max_parallel = match &attr.expr {
+                    hcl::Expression::Number(n) => n.as_u64().map(|v| v as usize),
+                    _ => None,
+                };
+                if max_parallel.is_none_or(|v| v == 0) {
+                    bail!(
+                        "provider \"{}\": max_parallel must be a positive integer",
+                        name
+                    );
+                }
+            } else if key == "depends_on" {
+                depends_on = expr_to_string_list(&attr.expr);
             } else {
                 config.insert(key.to_string(), hcl_expr_to_expression(&attr.expr));
             }
@@ -154,7 +261,9 @@ fn parse_provider_block(block: &hcl::Block) -> Result<Option<ProviderConfig>> {
         source: format!("hashicorp/{}", name),
         version_constraint: None,
         alias,
+        max_parallel,
         config,
+        depends_on,
     }))
 }
 
@@ -195,6 +304,7 @@ fn parse_resource_body(
     let mut for_each = None;
     let mut depends_on = Vec::new();
     let mut lifecycle = LifecycleConfig::default();
+    let mut timeouts = TimeoutsConfig::default();
     let mut attributes = HashMap::new();
     let mut provisioners = Vec::new();
 
@@ -218,6 +328,14 @@ fn parse_resource_body(
                     "lifecycle" => {
                         lifecycle = parse_lifecycle_block(inner_block);
                     }
+                    "timeouts" => {
+                        timeouts = parse_timeouts_block(inner_block, &resource_type, &name)?;
+                        // Providers read their own timeouts, so it stays in the config too
+                        attributes.insert(
+                            ident.to_string(),
+                            parse_nested_block_as_attribute(inner_block),
+                        );
+                    }
                     "provisioner" => {
                         let prov_labels: Vec<String> = inner_block
                             .labels()
@@ -227,6 +345,7 @@ fn parse_resource_body(
                         let prov_type = prov_labels.first().cloned().unwrap_or_default();
                         let mut prov_config = HashMap::new();
                         let mut when = ProvisionerWhen::Create;
+                        let mut on_failure = ProvisionerOnFailure::Fail;
 
                         for s in inner_block.body().iter() {
                             if let hcl::Structure::Attribute(a) = s {
@@ -235,10 +354,20 @@ fn parse_resource_body(
                                     if expr_to_string(&a.expr) == "destroy" {
                                         when = ProvisionerWhen::Destroy;
                                     }
+                                } else if k == "on_failure" {
+                                    if expr_to_string(&a.expr) == "continue" {
+                                        on_failure = ProvisionerOnFailure::Continue;
+                                    }
                                 } else {
                                     prov_config
                                         .insert(k.to_string(), hcl_expr_to_expression(&a.expr));
                                 }
+                            } else if let hcl::Structure::Block(b) = s {
+                                // e.g. `connection { host = self.public_ip }`
+                                prov_config.insert(
+                                    b.identifier().to_string(),
+                                    parse_nested_block_as_attribute(b),
+                                );
                             }
                         }
 
@@ -246,6 +375,7 @@ fn parse_resource_body(
                             provisioner_type: prov_type,
                             config: prov_config,
                             when,
+                            on_failure,
                         });
                     }
                     _ => {
@@ -290,6 +420,7 @@ fn parse_resource_body(
         for_each,
         depends_on,
         lifecycle,
+        timeouts,
         attributes,
         provisioners,
         source_location: Some(SourceLocation {
@@ -298,6 +429,7 @@ fn parse_resource_body(
             column: 0,
             config_type: ConfigType::Hcl,
         }),
+        module_path: vec![],
     }))
 }
 
@@ -311,7 +443,12 @@ fn parse_lifecycle_block(block: &hcl::Block) -> LifecycleConfig {
                 "create_before_destroy" => lc.create_before_destroy = expr_to_bool(&attr.expr),
                 "prevent_destroy" => lc.prevent_destroy = expr_to_bool(&attr.expr),
                 "ignore_changes" => lc.ignore_changes = expr_to_string_list(&attr.expr),
-                "replace_triggered_by" => lc.replace_triggered_by = expr_to_string_list(&attr.expr),
+                "replace_triggered_by" => {
+                    lc.replace_triggered_by = match &attr.expr {
+                        hcl::Expression::Array(refs) => refs.iter().map(expr_to_address).collect(),
+                        _ => vec![],
+                    }
+                }
                 _ => {}
             }
         }
@@ -320,6 +457,40 @@ fn parse_lifecycle_block(block: &hcl::Block) -> LifecycleConfig {
     lc
 }
 
+fn parse_timeouts_block(
+    block: &hcl::Block,
+    resource_type: &str,
+    name: &str,
+) -> Result<TimeoutsConfig> {
+    let mut timeouts = TimeoutsConfig::default();
+
+    for structure in block.body().iter() {
+        if let hcl::Structure::Attribute(attr) = structure {
+            let key: &str = &attr.key;
+            let slot = match key {
+                "create" => &mut timeouts.create,
+                "read" => &mut timeouts.read,
+                "update" => &mut timeouts.update,
+                "delete" => &mut timeouts.delete,
+                _ => continue,
+            };
+            let value = expr_to_string(&attr.expr);
+            match parse_duration(&value) {
+                Some(duration) => *slot = Some(duration),
+                None => bail!(
+                    "Invalid {} timeout {:?} for {}.{}: expected a duration such as \"20m\" or \"1h30m\"",
+                    key,
+                    value,
+                    resource_type,
+                    name
+                ),
+            }
+        }
+    }
+
+    Ok(timeouts)
+}
+
 fn parse_variable_block(block: &hcl::Block) -> Result<Option<VariableConfig>> {
     let labels: Vec<String> = block
         .labels()
@@ -342,7 +513,7 @@ fn parse_variable_block(block: &hcl::Block) -> Result<Option<VariableConfig>> {
             hcl::Structure::Attribute(attr) => {
                 let key: &str = &attr.key;
                 match key {
-                    "type" => var_type = Some(expr_to_string(&attr.expr)),
+                    "type" => var_type = Some(type_expr_to_string(&attr.expr)),
                     "default" => default = Some(hcl_expr_to_expression(&attr.expr)),
                     "description" => description = Some(expr_to_string(&attr.expr)),
                     "sensitive" => sensitive = expr_to_bool(&attr.expr),
@@ -384,7 +555,7 @@ fn parse_variable_block(block: &hcl::Block) -> Result<Option<VariableConfig>> {
     }))
 }
 
-fn parse_output_block(block: &hcl::Block) -> Result<Option<OutputConfig>> {
+fn parse_output_block(block: &hcl::Block, file: &str) -> Result<Option<OutputConfig>> {
     let labels: Vec<String> = block
         .labels()
         .iter()
@@ -419,6 +590,12 @@ fn parse_output_block(block: &hcl::Block) -> Result<Option<OutputConfig>> {
         description,
         sensitive,
         depends_on,
+        source_location: Some(SourceLocation {
+            file: file.to_string(),
+            line: 0,
+            column: 0,
+            config_type: ConfigType::Hcl,
+        }),
     }))
 }
 
@@ -483,6 +660,82 @@ fn parse_locals_block(block: &hcl::Block) -> Result<HashMap<String, Expression>>
     Ok(locals)
 }
 
+fn parse_moved_block(block: &hcl::Block) -> Result<MovedBlock> {
+    let mut from = None;
+    let mut to = None;
+
+    for structure in block.body().iter() {
+        if let hcl::Structure::Attribute(attr) = structure {
+            match attr.key.as_str() {
+                "from" => from = Some(expr_to_address(&attr.expr)),
+                "to" => to = Some(expr_to_address(&attr.expr)),
+                _ => {}
+            }
+        }
+    }
+
+    match (from, to) {
+        (Some(from), Some(to)) => Ok(MovedBlock { from, to }),
+        _ => bail!("moved block requires both \"from\" and \"to\" addresses"),
+    }
+}
+
+fn parse_removed_block(block: &hcl::Block) -> Result<RemovedBlock> {
+    let mut from = None;
+    let mut destroy = true;
+
+    for structure in block.body().iter() {
+        match structure {
+            hcl::Structure::Attribute(attr) if attr.key.as_str() == "from" => {
+                from = Some(expr_to_address(&attr.expr));
+            }
+            hcl::Structure::Block(inner_block) if inner_block.identifier() == "lifecycle" => {
+                for s in inner_block.body().iter() {
+                    if let hcl::Structure::Attribute(a) = s {
+                        if a.key.as_str() == "destroy" {
+                            destroy = !matches!(a.expr, hcl::Expression::Bool(false));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match from {
+        Some(from) => Ok(RemovedBlock { from, destroy }),
+        None => bail!("removed block requires a \"from\" address"),
+    }
+}
+
+/// Render a resource address written as a traversal (`aws_instance.web[0]`)
+/// the way state records it. JSON configs give the address as a string.
+fn expr_to_address(expr: &hcl::Expression) -> String {
+    let hcl::Expression::Traversal(traversal) = expr else {
+        return expr_to_string(expr);
+    };
+    let mut address = expr_to_string(&traversal.expr);
+    for operator in &traversal.operators {
+        match operator {
+            hcl::expr::TraversalOperator::GetAttr(ident) => {
+                address.push('.');
+                address.push_str(ident);
+            }
+            hcl::expr::TraversalOperator::Index(hcl::Expression::String(key)) => {
+                address.push_str(&format!("[\"{}\"]", key));
+            }
+            hcl::expr::TraversalOperator::Index(idx) => {
+                address.push_str(&format!("[{}]", expr_to_string(idx)));
+            }
+            hcl::expr::TraversalOperator::LegacyIndex(n) => {
+                address.push_str(&format!("[{}]", n));
+            }
+            _ => {}
+        }
+    }
+    address
+}
+
 // ─── Expression Conversion ──────────────────────────────────────────────────
 
 /// Convert an hcl::Expression into our unified Expression type.
@@ -577,28 +830,7 @@ pub fn hcl_expr_to_expression(expr: &hcl::Expression) -> Expression {
                     operand: Box::new(hcl_expr_to_expression(&unary.expr)),
                 }
             }
-            hcl::expr::Operation::Binary(binary) => {
-                let oxid_op = match binary.operator {
-                    hcl::expr::BinaryOperator::Eq => BinOp::Eq,
-                    hcl::expr::BinaryOperator::NotEq => BinOp::NotEq,
-                    hcl::expr::BinaryOperator::Less => BinOp::Lt,
-                    hcl::expr::BinaryOperator::LessEq => BinOp::Lte,
-                    hcl::expr::BinaryOperator::Greater => BinOp::Gt,
-                    hcl::expr::BinaryOperator::GreaterEq => BinOp::Gte,
-                    hcl::expr::BinaryOperator::Plus => BinOp::Add,
-                    hcl::expr::BinaryOperator::Minus => BinOp::Sub,
-                    hcl::expr::BinaryOperator::Mul => BinOp::Mul,
-                    hcl::expr::BinaryOperator::Div => BinOp::Div,
-                    hcl::expr::BinaryOperator::Mod => BinOp::Mod,
-                    hcl::expr::BinaryOperator::And => BinOp::And,
-                    hcl::expr::BinaryOperator::Or => BinOp::Or,
-                };
-                Expression::BinaryOp {
-                    op: oxid_op,
-                    left: Box::new(hcl_expr_to_expression(&binary.lhs_expr)),
-                    right: Box::new(hcl_expr_to_expression(&binary.rhs_expr)),
-                }
-            }
+            hcl::expr::Operation::Binary(binary) => binary_chain_to_expression(binary),
         },
         hcl::Expression::ForExpr(for_expr) => Expression::ForExpr {
             collection: Box::new(hcl_expr_to_expression(&for_expr.collection_expr)),
@@ -620,6 +852,86 @@ pub fn hcl_expr_to_expression(expr: &hcl::Expression) -> Expression {
     }
 }
 
+/// Convert a chain of binary operations, applying HCL operator precedence.
+///
+/// hcl-rs keeps operators in the order it read them without precedence, so
+/// `a >= 3 && b <= 16` arrives as `a >= (3 && (b <= 16))`. The chain is
+/// flattened up to any parentheses and rebuilt so higher-precedence operators
+/// bind first and equal ones group from the left.
+fn binary_chain_to_expression(binary: &hcl::expr::BinaryOp) -> Expression {
+    fn flatten<'a>(
+        binary: &'a hcl::expr::BinaryOp,
+        operands: &mut Vec<&'a hcl::Expression>,
+        operators: &mut Vec<hcl::expr::BinaryOperator>,
+    ) {
+        for (i, expr) in [&binary.lhs_expr, &binary.rhs_expr].into_iter().enumerate() {
+            if i == 1 {
+                operators.push(binary.operator);
+            }
+            match expr {
+                hcl::Expression::Operation(op) => match op.as_ref() {
+                    hcl::expr::Operation::Binary(inner) => flatten(inner, operands, operators),
+                    hcl::expr::Operation::Unary(_) => operands.push(expr),
+                },
+                _ => operands.push(expr),
+            }
+        }
+    }
+
+    fn reduce(output: &mut Vec<Expression>, pending: &mut Vec<hcl::expr::BinaryOperator>) {
+        let (Some(op), Some(right), Some(left)) = (pending.pop(), output.pop(), output.pop())
+        else {
+            unreachable!("a binary chain has one more operand than operators");
+        };
+        output.push(Expression::BinaryOp {
+            op: bin_op(op),
+            left: Box::new(left),
+            right: Box::new(right),
+        });
+    }
+
+    let mut operands = Vec::new();
+    let mut operators = Vec::new();
+    flatten(binary, &mut operands, &mut operators);
+
+    let mut output = vec![hcl_expr_to_expression(operands[0])];
+    let mut pending: Vec<hcl::expr::BinaryOperator> = Vec::new();
+    for (op, operand) in operators.into_iter().zip(&operands[1..]) {
+        while pending
+            .last()
+            .is_some_and(|top| top.precedence() >= op.precedence())
+        {
+            reduce(&mut output, &mut pending);
+        }
+        pending.push(op);
+        output.push(hcl_expr_to_expression(operand));
+    }
+    while !pending.is_empty() {
+        reduce(&mut output, &mut pending);
+    }
+    output
+        .pop()
+        .expect("a binary chain has at least one operand")
+}
+
+fn bin_op(op: hcl::expr::BinaryOperator) -> BinOp {
+    match op {
+        hcl::expr::BinaryOperator::Eq => BinOp::Eq,
+        hcl::expr::BinaryOperator::NotEq => BinOp::NotEq,
+        hcl::expr::BinaryOperator::Less => BinOp::Lt,
+        hcl::expr::BinaryOperator::LessEq => BinOp::Lte,
+        hcl::expr::BinaryOperator::Greater => BinOp::Gt,
+        hcl::expr::BinaryOperator::GreaterEq => BinOp::Gte,
+        hcl::expr::BinaryOperator::Plus => BinOp::Add,
+        hcl::expr::BinaryOperator::Minus => BinOp::Sub,
+        hcl::expr::BinaryOperator::Mul => BinOp::Mul,
+        hcl::expr::BinaryOperator::Div => BinOp::Div,
+        hcl::expr::BinaryOperator::Mod => BinOp::Mod,
+        hcl::expr::BinaryOperator::And => BinOp::And,
+        hcl::expr::BinaryOperator::Or => BinOp::Or,
+    }
+}
+
 fn parse_nested_block_as_attribute(block: &hcl::Block) -> Expression {
     let mut entries = Vec::new();
 
@@ -661,6 +973,14 @@ fn parse_nested_block_as_attribute(block: &hcl::Block) -> Expression {
 
 // ─── Helper Functions ────────────────────────────────────────────────────────
 
+/// Render a type constraint (e.g. `list(string)`) back to its source form.
+fn type_expr_to_string(expr: &hcl::Expression) -> String {
+    match expr {
+        hcl::Expression::String(s) => s.clone(),
+        other => hcl::format::to_string(other).unwrap_or_else(|_| expr_to_string(other)),
+    }
+}
+
 fn expr_to_string(expr: &hcl::Expression) -> String {
     match expr {
         hcl::Expression::String(s) => s.clone(),
@@ -763,7 +1083,8 @@ fn expr_to_value(expr: &hcl::Expression) -> Option<Value> {
     }
 }
 
-fn parse_template_string(s: &str) -> Expression {
+/// Split a string containing `${...}` references into template parts.
+pub fn parse_template_string(s: &str) -> Expression {
     let mut parts = Vec::new();
     let mut remaining = s;
 
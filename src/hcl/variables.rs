@@ -0,0 +1,494 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::types::{Expression, Value, WorkspaceConfig};
+use crate::executor::engine::{eval_expression, EvalContext};
+
+/// A variable type constraint, parsed from the `type` attribute of a variable block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VarType {
+    Any,
+    String,
+    Number,
+    Bool,
+    List(Box<VarType>),
+    Set(Box<VarType>),
+    Map(Box<VarType>),
+    Tuple(Vec<VarType>),
+    /// Object attributes as (name, type, optional).
+    Object(Vec<(String, VarType, bool)>),
+}
+
+impl VarType {
+    /// Parse a type constraint such as `list(string)` or `object({ name = string })`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input);
+        let mut pos = 0;
+        let ty = parse_type(&tokens, &mut pos)?;
+        if pos != tokens.len() {
+            bail!(
+                "Unexpected '{}' in type constraint '{}'",
+                tokens[pos],
+                input
+            );
+        }
+        Ok(ty)
+    }
+
+    fn is_collection(&self) -> bool {
+        matches!(
+            self,
+            VarType::List(_)
+                | VarType::Set(_)
+                | VarType::Map(_)
+                | VarType::Tuple(_)
+                | VarType::Object(_)
+        )
+    }
+}
+
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in input.chars() {
+        if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '"' {
+            current.push(c);
+            continue;
+        }
+        if !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+        if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn expect(tokens: &[String], pos: &mut usize, token: &str) -> Result<()> {
+    match tokens.get(*pos) {
+        Some(t) if t == token => {
+            *pos += 1;
+            Ok(())
+        }
+        Some(t) => bail!("Expected '{}' in type constraint, found '{}'", token, t),
+        None => bail!(
+            "Expected '{}' in type constraint, found end of input",
+            token
+        ),
+    }
+}
+
+fn parse_type(tokens: &[String], pos: &mut usize) -> Result<VarType> {
+    let Some(name) = tokens.get(*pos) else {
+        bail!("Empty type constraint");
+    };
+    *pos += 1;
+
+    match name.as_str() {
+        "any" => Ok(VarType::Any),
+        "string" => Ok(VarType::String),
+        "number" => Ok(VarType::Number),
+        "bool" => Ok(VarType::Bool),
+        "list" | "set" | "map" => {
+            expect(tokens, pos, "(")?;
+            let inner = Box::new(parse_type(tokens, pos)?);
+            expect(tokens, pos, ")")?;
+            Ok(match name.as_str() {
+                "list" => VarType::List(inner),
+                "set" => VarType::Set(inner),
+                _ => VarType::Map(inner),
+            })
+        }
+        "tuple" => {
+            expect(tokens, pos, "(")?;
+            expect(tokens, pos, "[")?;
+            let mut elems = Vec::new();
+            while tokens.get(*pos).map(|t| t != "]").unwrap_or(false) {
+                elems.push(parse_type(tokens, pos)?);
+                if tokens.get(*pos).map(|t| t == ",").unwrap_or(false) {
+                    *pos += 1;
+                }
+            }
+            expect(tokens, pos, "]")?;
+            expect(tokens, pos, ")")?;
+            Ok(VarType::Tuple(elems))
+        }
+        "object" => {
+            expect(tokens, pos, "(")?;
+            expect(tokens, pos, "{")?;
+            let mut attrs = Vec::new();
+            while tokens.get(*pos).map(|t| t != "}").unwrap_or(false) {
+                let attr = tokens[*pos].trim_matches('"').to_string();
+                *pos += 1;
+                match tokens.get(*pos).map(String::as_str) {
+                    Some("=") | Some(":") => *pos += 1,
+                    _ => bail!("Expected '=' after object attribute '{}'", attr),
+                }
+                let (ty, optional) = parse_attribute_type(tokens, pos)?;
+                attrs.push((attr, ty, optional));
+                if tokens.get(*pos).map(|t| t == ",").unwrap_or(false) {
+                    *pos += 1;
+                }
+            }
+            expect(tokens, pos, "}")?;
+            expect(tokens, pos, ")")?;
+            Ok(VarType::Object(attrs))
+        }
+        other => bail!("Unknown type '{}' in type constraint", other),
+    }
+}
+
+/// Parse an object attribute type, unwrapping `optional(T)` / `optional(T, default)`.
+/// Optional defaults are skipped; missing optional attributes become null.
+fn parse_attribute_type(tokens: &[String], pos: &mut usize) -> Result<(VarType, bool)> {
+    if tokens.get(*pos).map(|t| t != "optional").unwrap_or(true) {
+        return Ok((parse_type(tokens, pos)?, false));
+    }
+    *pos += 1;
+    expect(tokens, pos, "(")?;
+    let ty = parse_type(tokens, pos)?;
+    let mut depth = 0usize;
+    while let Some(t) = tokens.get(*pos) {
+        match t.as_str() {
+            "(" | "[" | "{" => depth += 1,
+            ")" if depth == 0 => break,
+            ")" | "]" | "}" => depth -= 1,
+            _ => {}
+        }
+        *pos += 1;
+    }
+    expect(tokens, pos, ")")?;
+    Ok((ty, true))
+}
+
+// ─── Coercion ───────────────────────────────────────────────────────────────
+
+/// Convert a value to the given type, following Terraform's conversion rules:
+/// primitives convert through their string form, and a scalar given for a
+/// `list(...)` or `set(...)` is wrapped into a single-element collection.
+pub fn coerce_value(value: serde_json::Value, ty: &VarType) -> Result<serde_json::Value> {
+    use serde_json::Value as J;
+
+    if value.is_null() {
+        return Ok(value);
+    }
+
+    // Complex values from TF_VAR_ environment variables arrive as HCL source text
+    let value = match (&value, ty.is_collection()) {
+        (J::String(s), true) if s.trim_start().starts_with(['[', '{']) => {
+            parse_hcl_value(s).unwrap_or(value)
+        }
+        _ => value,
+    };
+
+    Ok(match ty {
+        VarType::Any => value,
+        VarType::String => match value {
+            J::String(_) => value,
+            J::Number(n) => J::String(n.to_string()),
+            J::Bool(b) => J::String(b.to_string()),
+            other => bail!("a string is required, got {}", type_name(&other)),
+        },
+        VarType::Number => match value {
+            J::Number(_) => value,
+            J::String(ref s) => match s.trim().parse::<f64>() {
+                Ok(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                    serde_json::json!(n as i64)
+                }
+                Ok(n) => serde_json::json!(n),
+                Err(_) => bail!("a number is required, got string \"{}\"", s),
+            },
+            other => bail!("a number is required, got {}", type_name(&other)),
+        },
+        VarType::Bool => match value {
+            J::Bool(_) => value,
+            J::String(ref s) if s == "true" => J::Bool(true),
+            J::String(ref s) if s == "false" => J::Bool(false),
+            other => bail!("a bool is required, got {}", type_name(&other)),
+        },
+        VarType::List(elem) | VarType::Set(elem) => {
+            let items = match value {
+                J::Array(items) => items,
+                J::Object(_) => bail!("a list is required, got object"),
+                scalar => vec![scalar],
+            };
+            let mut result = Vec::with_capacity(items.len());
+            for (i, item) in items.into_iter().enumerate() {
+                let item = coerce_value(item, elem)
+                    .map_err(|e| anyhow::anyhow!("element {}: {}", i, e))?;
+                if matches!(ty, VarType::Set(_)) && result.contains(&item) {
+                    continue;
+                }
+                result.push(item);
+            }
+            J::Array(result)
+        }
+        VarType::Map(elem) => match value {
+            J::Object(entries) => {
+                let mut result = serde_json::Map::new();
+                for (k, v) in entries {
+                    let v = coerce_value(v, elem)
+                        .map_err(|e| anyhow::anyhow!("element \"{}\": {}", k, e))?;
+                    result.insert(k, v);
+                }
+                J::Object(result)
+            }
+            other => bail!("a map is required, got {}", type_name(&other)),
+        },
+        VarType::Tuple(elems) => match value {
+            J::Array(items) if items.len() == elems.len() => J::Array(
+                items
+                    .into_iter()
+                    .zip(elems)
+                    .enumerate()
+                    .map(|(i, (item, elem))| {
+                        coerce_value(item, elem)
+                            .map_err(|e| anyhow::anyhow!("element {}: {}", i, e))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            J::Array(items) => bail!(
+                "a tuple of {} elements is required, got {}",
+                elems.len(),
+                items.len()
+            ),
+            other => bail!("a tuple is required, got {}", type_name(&other)),
+        },
+        VarType::Object(attrs) => match value {
+            J::Object(mut entries) => {
+                let mut result = serde_json::Map::new();
+                for (name, attr_ty, optional) in attrs {
+                    match entries.remove(name) {
+                        Some(v) => {
+                            let v = coerce_value(v, attr_ty)
+                                .map_err(|e| anyhow::anyhow!("attribute \"{}\": {}", name, e))?;
+                            result.insert(name.clone(), v);
+                        }
+                        None if *optional => {
+                            result.insert(name.clone(), J::Null);
+                        }
+                        None => bail!("attribute \"{}\" is required", name),
+                    }
+                }
+                J::Object(result)
+            }
+            other => bail!("an object is required, got {}", type_name(&other)),
+        },
+    })
+}
+
+fn type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "bool",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "list",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Parse an HCL value literal (e.g. `["a", "b"]`) into JSON.
+fn parse_hcl_value(source: &str) -> Option<serde_json::Value> {
+    let body: hcl::Body = hcl::from_str(&format!("value = {}", source)).ok()?;
+    let attr = body.attributes().next()?;
+    let expr = super::parser::hcl_expr_to_expression(attr.expr());
+    eval_expression(&expr, &EvalContext::plan_only(Default::default())).ok()
+}
+
+pub(super) fn json_to_value(json: &serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Bool(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::Float(n.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(s) => Value::String(s.clone()),
+        serde_json::Value::Array(items) => Value::List(items.iter().map(json_to_value).collect()),
+        serde_json::Value::Object(entries) => Value::Map(
+            entries
+                .iter()
+                .map(|(k, v)| (k.clone(), json_to_value(v)))
+                .collect(),
+        ),
+    }
+}
+
+// ─── Command-line Values ────────────────────────────────────────────────────
+
+/// A `--var` or `--var-file` argument, as given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VarArg {
+    /// `--var name=value`
+    Var(String),
+    /// `--var-file path`
+    File(String),
+}
+
+/// One parsed command-line value source.
+#[derive(Debug, Clone)]
+pub enum VariableOverride {
+    /// A `name = value` assignment.
+    Value(String, Expression),
+    /// A .tfvars file.
+    File(PathBuf),
+}
+
+/// Variable values given on the command line with `--var-file` and `--var`.
+/// These take precedence over .tfvars files and TF_VAR_ environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct VariableOverrides {
+    /// Assignments and .tfvars files, applied in command-line order so a
+    /// later one wins whichever flag set it.
+    pub values: Vec<VariableOverride>,
+    /// The selected workspace, whose `<name>.tfvars` is loaded with the
+    /// directory's other .tfvars files.
+    pub workspace: Option<String>,
+}
+
+impl VariableOverrides {
+    /// Build overrides from raw `--var name=value` and `--var-file path`
+    /// arguments, in command-line order.
+    pub fn from_args(args: &[VarArg]) -> Result<Self> {
+        Ok(Self {
+            values: args
+                .iter()
+                .map(|arg| match arg {
+                    VarArg::Var(assignment) => parse_var_assignment(assignment)
+                        .map(|(name, value)| VariableOverride::Value(name, value)),
+                    VarArg::File(path) => Ok(VariableOverride::File(PathBuf::from(path))),
+                })
+                .collect::<Result<_>>()?,
+            workspace: None,
+        })
+    }
+
+    /// Load the .tfvars of workspace `name` as well.
+    pub fn with_workspace(mut self, name: impl Into<String>) -> Self {
+        self.workspace = Some(name.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Parse a `name=value` argument. Quoted strings, numbers, bools, null and
+/// `[...]`/`{...}` are read as HCL literals; anything else is taken verbatim
+/// as a string, so `--var env=prod` works without extra quoting.
+pub fn parse_var_assignment(arg: &str) -> Result<(String, Expression)> {
+    let Some((name, raw)) = arg.split_once('=') else {
+        bail!("Invalid --var '{}': expected NAME=VALUE", arg);
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Invalid --var '{}': variable name is empty", arg);
+    }
+
+    let literal = hcl::from_str::<hcl::Body>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|body| body.attributes().next().map(|a| a.expr().clone()))
+        .filter(is_literal);
+    let expr = match literal {
+        Some(expr) => super::parser::hcl_expr_to_expression(&expr),
+        None => Expression::Literal(Value::String(raw.to_string())),
+    };
+    Ok((name.to_string(), expr))
+}
+
+fn is_literal(expr: &hcl::Expression) -> bool {
+    match expr {
+        hcl::Expression::Null
+        | hcl::Expression::Bool(_)
+        | hcl::Expression::Number(_)
+        | hcl::Expression::String(_) => true,
+        hcl::Expression::Array(items) => items.iter().all(is_literal),
+        hcl::Expression::Object(entries) => entries.values().all(is_literal),
+        hcl::Expression::TemplateExpr(t) => !t.to_string().contains("${"),
+        _ => false,
+    }
+}
+
+/// Apply command-line overrides on top of the workspace's variable values.
+pub fn apply_overrides(
+    workspace: &mut WorkspaceConfig,
+    overrides: &VariableOverrides,
+) -> Result<()> {
+    let mut values = std::collections::HashMap::new();
+    for value in &overrides.values {
+        match value {
+            VariableOverride::Value(name, expr) => {
+                values.insert(name.clone(), expr.clone());
+            }
+            VariableOverride::File(file) => {
+                tracing::info!("Loading {}", file.display());
+                let parsed = super::parse_tfvars_file(file)
+                    .context(format!("Failed to load --var-file {}", file.display()))?;
+                values.extend(parsed);
+            }
+        }
+    }
+
+    for name in values.keys() {
+        if !workspace.variables.iter().any(|v| &v.name == name) {
+            tracing::warn!("Value given for undeclared variable \"{}\"", name);
+        }
+    }
+    for var in &mut workspace.variables {
+        if let Some(value) = values.remove(&var.name) {
+            var.default = Some(value);
+        }
+    }
+    Ok(())
+}
+
+// ─── Workspace Passes ───────────────────────────────────────────────────────
+
+/// Coerce every variable's value (default, tfvars or TF_VAR_) to its declared type.
+pub fn coerce_variables(workspace: &mut WorkspaceConfig) -> Result<()> {
+    let ctx = EvalContext::plan_only(Default::default());
+
+    for var in &mut workspace.variables {
+        let Some(ref default) = var.default else {
+            continue;
+        };
+        let value = eval_expression(default, &ctx)
+            .with_context(|| format!("Invalid value for variable \"{}\"", var.name))?;
+        let Some(ref type_str) = var.var_type else {
+            continue;
+        };
+        let ty = VarType::parse(type_str)
+            .map_err(|e| anyhow::anyhow!("Invalid type for variable \"{}\": {}", var.name, e))?;
+        if ty == VarType::Any {
+            continue;
+        }
+
+        let coerced = coerce_value(value, &ty)
+            .map_err(|e| anyhow::anyhow!("Invalid value for variable \"{}\": {}", var.name, e))?;
+        var.default = Some(Expression::Literal(json_to_value(&coerced)));
+    }
+
+    Ok(())
+}
+
+/// Fail if any variable has no default and was not set through tfvars or TF_VAR_.
+pub fn check_required_variables(workspace: &WorkspaceConfig) -> Result<()> {
+    let missing: Vec<String> = workspace
+        .variables
+        .iter()
+        .filter(|v| v.default.is_none())
+        .map(|v| format!("No value for required variable {}", v.name))
+        .collect();
+
+    if !missing.is_empty() {
+        bail!("{}", missing.join("\n"));
+    }
+    Ok(())
+}
@@ -2,6 +2,7 @@
 
 pub mod config;
 pub mod dag;
+pub mod error;
 pub mod executor;
 pub mod hcl;
 pub mod output;
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use std::path::Path;
+use std::process::ExitCode;
 use std::sync::Arc;
 
 /// Reset SIGPIPE to default behavior so piping (e.g. `oxid graph | dot`) exits cleanly
@@ -15,10 +16,12 @@ fn reset_sigpipe() {
 use anyhow::{bail, Context, Result};
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use tracing_subscriber::EnvFilter;
+use futures::StreamExt;
+use indicatif::MultiProgress;
 
 mod config;
 mod dag;
+mod error;
 mod executor;
 mod hcl;
 mod output;
@@ -27,11 +30,18 @@ mod provider;
 mod state;
 
 use config::loader;
-use executor::engine::ResourceEngine;
-use provider::manager::ProviderManager;
+use executor::engine::{PlanSummary, RefreshOutcome, ResourceEngine, Selection};
+use provider::lockfile::LockFile;
+use provider::manager::{ProviderManager, ProviderResolution};
+use provider::pids::ReapOutcome;
+use provider::protocol::ProviderStartOptions;
+use provider::registry::Platform;
 use state::backend::StateBackend;
-use state::models::{ResourceFilter, ResourceState};
-use state::query::{execute_query, QueryFormat};
+use state::http::HttpBackend;
+use state::location::{resolve_state_location, StateLocation, STATE_DSN_ENV};
+use state::models::{ResourceFilter, ResourceSort, ResourceState};
+use state::query::{execute_query, stream_query, QueryFormat};
+use state::s3::S3Backend;
 use state::sqlite::SqliteBackend;
 
 /// oxid - Standalone infrastructure engine
@@ -54,14 +64,144 @@ struct Cli {
     #[arg(short, long, default_value = "10")]
     parallelism: usize,
 
+    /// Maximum number of provider schemas fetched at once; each large
+    /// schema is held in memory while it is decoded
+    #[arg(long, global = true, default_value_t = provider::manager::DEFAULT_SCHEMA_PARALLELISM)]
+    schema_parallelism: usize,
+
+    /// Seconds a provider may take to start before oxid gives up
+    /// (default 30, or OXID_PROVIDER_START_TIMEOUT); raise for large
+    /// providers on a cold cache
+    #[arg(long, global = true, value_name = "SECONDS")]
+    provider_start_timeout: Option<u64>,
+
+    /// Lowest port providers may listen on (default 10000, or
+    /// OXID_PLUGIN_MIN_PORT)
+    #[arg(long, global = true, value_name = "PORT")]
+    plugin_min_port: Option<u16>,
+
+    /// Highest port providers may listen on (default 25000, or
+    /// OXID_PLUGIN_MAX_PORT)
+    #[arg(long, global = true, value_name = "PORT")]
+    plugin_max_port: Option<u16>,
+
+    /// Log filter directives (e.g. "info" or "oxid=debug,provider_stderr=off");
+    /// overrides --verbose
+    #[arg(long, global = true, value_name = "FILTER")]
+    log_level: Option<String>,
+
+    /// Log output format: "pretty" for humans, "json" for one JSON object
+    /// per line on stderr
+    #[arg(long, global = true, default_value = "pretty", value_parser = ["pretty", "json"])]
+    log_format: String,
+
+    /// Print warnings on a single line without timestamps
+    #[arg(long, global = true)]
+    compact_warnings: bool,
+
+    /// Disable colored output, e.g. for CI logs detected as a terminal
+    /// (also disabled when NO_COLOR is set)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// State backend: "sqlite" (persisted per OXID_STATE_DSN, the configured
+    /// `backend` block, or the working directory) or "memory" (ephemeral,
+    /// discarded when the command exits)
+    #[arg(long, global = true, default_value = "sqlite", value_parser = ["sqlite", "memory"])]
+    state: String,
+
+    /// Workspace to operate on, instead of the one chosen with
+    /// `oxid workspace select`
+    #[arg(long, global = true, value_name = "NAME")]
+    workspace: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Variable values passed on the command line (plan/apply/destroy). `--var`
+/// and `--var-file` are kept in one list in command-line order, as Terraform
+/// applies them, which the derive macros can't express.
+#[derive(Debug, Default)]
+struct VarArgs {
+    args: Vec<hcl::variables::VarArg>,
+}
+
+impl clap::Args for VarArgs {
+    fn augment_args(cmd: clap::Command) -> clap::Command {
+        cmd.arg(
+            clap::Arg::new("var")
+                .long("var")
+                .value_name("NAME=VALUE")
+                .action(clap::ArgAction::Append)
+                .help("Set a variable value (NAME=VALUE), may be repeated"),
+        )
+        .arg(
+            clap::Arg::new("var_file")
+                .long("var-file")
+                .value_name("FILE")
+                .action(clap::ArgAction::Append)
+                .help("Load variable values from a .tfvars file, may be repeated"),
+        )
+    }
+
+    fn augment_args_for_update(cmd: clap::Command) -> clap::Command {
+        Self::augment_args(cmd)
+    }
+}
+
+impl clap::FromArgMatches for VarArgs {
+    fn from_arg_matches(matches: &clap::ArgMatches) -> Result<Self, clap::Error> {
+        use hcl::variables::VarArg;
+
+        let mut args = Vec::new();
+        for (id, make) in [
+            ("var", VarArg::Var as fn(String) -> VarArg),
+            ("var_file", VarArg::File),
+        ] {
+            if let (Some(values), Some(indices)) =
+                (matches.get_many::<String>(id), matches.indices_of(id))
+            {
+                args.extend(indices.zip(values.cloned().map(make)));
+            }
+        }
+        args.sort_by_key(|(index, _)| *index);
+        Ok(Self {
+            args: args.into_iter().map(|(_, arg)| arg).collect(),
+        })
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &clap::ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+impl Cli {
+    /// The parallelism for one command: its own flag if given, else the global one.
+    fn parallelism_for(&self, command: Option<usize>) -> usize {
+        command.unwrap_or(self.parallelism)
+    }
+}
+
+impl VarArgs {
+    /// The command-line values, plus the active workspace so its .tfvars are
+    /// loaded.
+    fn overrides(&self, cli: &Cli) -> Result<hcl::variables::VariableOverrides> {
+        Ok(hcl::variables::VariableOverrides::from_args(&self.args)?
+            .with_workspace(active_workspace_name(cli)))
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize project — download providers, create state database
-    Init,
+    Init {
+        /// Only check that the config loads and providers resolve; report
+        /// what would be downloaded without writing anything
+        #[arg(long)]
+        check: bool,
+    },
 
     /// Show execution plan (resource-level create/update/delete)
     Plan {
@@ -69,9 +209,62 @@ enum Commands {
         #[arg(short, long)]
         target: Vec<String>,
 
+        /// Leave out specific resource address(es); applied after --target
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Also leave out every resource that depends on an excluded one
+        #[arg(long, requires = "exclude")]
+        exclude_dependents: bool,
+
+        #[command(flatten)]
+        vars: VarArgs,
+
         /// Output as JSON (machine-parseable)
         #[arg(long)]
         json: bool,
+
+        /// Exit with 0 when there are no changes, 2 when changes are
+        /// present, and 1 on error
+        #[arg(long)]
+        detailed_exitcode: bool,
+
+        /// Show which changes can be applied in parallel, wave by wave
+        #[arg(long, conflicts_with = "json")]
+        show_waves: bool,
+
+        /// Read every object back from its provider before planning
+        /// (--refresh=false plans against the recorded state instead)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        refresh: bool,
+
+        /// Plan the destroy of every resource in state instead of an apply
+        #[arg(long, conflicts_with = "show_waves")]
+        destroy: bool,
+
+        /// Print a DOT graph of the changing resources and their
+        /// dependencies, colored by action, instead of the plan
+        #[arg(long, conflicts_with_all = ["json", "show_waves", "destroy"])]
+        graph: bool,
+
+        /// Only compare state with the real objects, ignoring the
+        /// configuration: show how `apply --refresh-only` would update state
+        #[arg(long, conflicts_with_all = ["show_waves", "destroy", "graph", "target", "exclude"])]
+        refresh_only: bool,
+
+        /// Print one line per changing resource instead of full attribute
+        /// diffs (plans with fewer than 20 changes are printed in full)
+        #[arg(long, conflicts_with_all = ["json", "graph", "refresh_only"])]
+        compact: bool,
+
+        /// With --compact, still print the full diff of these address(es)
+        #[arg(long, requires = "compact")]
+        expand: Vec<String>,
+
+        /// Plan the resource at this address (every instance of a resource
+        /// address) as a replacement even if nothing changed; may be repeated
+        #[arg(long, conflicts_with_all = ["destroy", "refresh_only"])]
+        replace: Vec<String>,
     },
 
     /// Apply infrastructure changes with resource-level parallelism
@@ -80,9 +273,50 @@ enum Commands {
         #[arg(short, long)]
         target: Vec<String>,
 
+        /// Leave out specific resource address(es); applied after --target
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Also leave out every resource that depends on an excluded one
+        #[arg(long, requires = "exclude")]
+        exclude_dependents: bool,
+
+        #[command(flatten)]
+        vars: VarArgs,
+
         /// Skip confirmation prompt
         #[arg(long)]
         auto_approve: bool,
+
+        /// Hold the state lock while applying (--lock=false to skip)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        lock: bool,
+
+        /// Read every object back from its provider before planning
+        /// (--refresh=false plans against the recorded state instead)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        refresh: bool,
+
+        /// Only update state to match the real objects; nothing the
+        /// configuration proposes is applied
+        #[arg(long, conflicts_with_all = ["target", "exclude"])]
+        refresh_only: bool,
+
+        /// Replace the resource at this address (every instance of a
+        /// resource address) even if nothing changed; may be repeated
+        #[arg(long, conflicts_with = "refresh_only")]
+        replace: Vec<String>,
+
+        /// After the summary, list the slowest resources and the time
+        /// spent per provider
+        #[arg(long)]
+        timings: bool,
+
+        /// Resource operations to run at once for this apply, overriding
+        /// the global --parallelism. Cloud APIs throttle writes: 5-10 is
+        /// safe for AWS, and 1-4 helps when applies hit rate limits
+        #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        parallelism: Option<usize>,
     },
 
     /// Destroy infrastructure in reverse dependency order
@@ -91,9 +325,37 @@ enum Commands {
         #[arg(short, long)]
         target: Vec<String>,
 
+        #[command(flatten)]
+        vars: VarArgs,
+
         /// Skip confirmation prompt
         #[arg(long)]
         auto_approve: bool,
+
+        /// Hold the state lock while destroying (--lock=false to skip)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        lock: bool,
+
+        /// Show the destroy plan and exit without destroying anything
+        #[arg(long)]
+        plan: bool,
+    },
+
+    /// Release a state lock left behind by an interrupted apply or destroy
+    ForceUnlock {
+        /// Lock ID reported by the command that found the state locked
+        lock_id: String,
+
+        /// Skip confirmation prompt
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show the current state: every resource and output
+    Show {
+        /// Print the state as a .tfstate JSON document
+        #[arg(long)]
+        json: bool,
     },
 
     /// Manage state
@@ -113,9 +375,29 @@ enum Commands {
         /// SQL query to execute (SELECT only)
         sql: String,
 
-        /// Output format: table, json, csv
-        #[arg(short, long, default_value = "table")]
+        /// Value bound to the next `?` placeholder (repeatable)
+        #[arg(long = "param", value_name = "VALUE")]
+        params: Vec<String>,
+
+        /// Output format: table, json, csv, jsonl (streamed one row per line)
+        #[arg(short, long, visible_alias = "output", default_value = "table")]
         format: String,
+
+        /// Keep only these dotted paths of each result row, e.g.
+        /// address,attributes.tags.Name
+        #[arg(long, value_delimiter = ',')]
+        select: Vec<String>,
+    },
+
+    /// List recent apply/destroy runs
+    Runs {
+        /// Maximum number of runs to show
+        #[arg(long, default_value = "10")]
+        limit: usize,
+
+        /// Show only the latest run, with its per-resource results
+        #[arg(long)]
+        latest: bool,
     },
 
     /// Manage workspaces
@@ -124,15 +406,22 @@ enum Commands {
         command: WorkspaceCommands,
     },
 
-    /// Show dependency graph as DOT
+    /// Show dependency graph (DOT, JSON, or Mermaid)
     Graph {
-        /// Graph type: resource or module
+        /// Graph type: resource, provider or module
         #[arg(short = 'T', long, default_value = "resource")]
         graph_type: String,
+
+        /// Output format
+        #[arg(long, default_value = "dot", value_parser = ["dot", "json", "mermaid"])]
+        format: String,
     },
 
     /// List providers and their versions
-    Providers,
+    Providers {
+        #[command(subcommand)]
+        command: Option<ProvidersCommands>,
+    },
 
     /// Detect drift between state and real infrastructure
     Drift {
@@ -142,7 +431,11 @@ enum Commands {
     },
 
     /// Validate configuration without running anything
-    Validate,
+    Validate {
+        /// Print a machine-readable report of diagnostics
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -152,18 +445,74 @@ enum StateCommands {
         /// Filter by resource type (e.g. aws_vpc)
         #[arg(long)]
         filter: Option<String>,
+
+        /// Only list resources in this module and the modules nested under it
+        /// (e.g. module.network)
+        #[arg(long)]
+        module: Option<String>,
+
+        /// Maximum number of resources to show
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Number of resources to skip before listing
+        #[arg(long)]
+        offset: Option<usize>,
+
+        /// Sort order
+        #[arg(long, default_value = "address", value_parser = ResourceSort::NAMES)]
+        sort: String,
+
+        /// Print the resources' metadata as a JSON array
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+
+        /// Output format: table, csv or tsv
+        #[arg(long, default_value = "table", value_parser = ["table", "csv", "tsv"])]
+        format: String,
     },
 
     /// Show details for a specific resource
     Show {
         /// Resource address (e.g. aws_instance.web)
         address: String,
+
+        /// Print the resource's metadata and attributes as one JSON object
+        #[arg(long, conflicts_with = "raw")]
+        json: bool,
+
+        /// Print only the stored attributes JSON, e.g. for piping into jq
+        #[arg(long)]
+        raw: bool,
+
+        /// Show sensitive attribute values instead of masking them
+        #[arg(long)]
+        show_sensitive: bool,
     },
 
-    /// Remove a resource from state without destroying it
+    /// Show a resource's results from past apply/destroy runs
+    History {
+        /// Resource address (e.g. aws_instance.web)
+        address: String,
+
+        /// Maximum number of runs to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Remove resources from state without destroying them
     Rm {
-        /// Resource address to remove
+        /// Resource address to remove, or a glob matching several
+        /// (e.g. 'aws_instance.web*')
         address: String,
+
+        /// List the resources that would be removed without removing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip the confirmation prompt when a glob matches several resources
+        #[arg(long)]
+        auto_approve: bool,
     },
 
     /// Move a resource to a new address in state
@@ -192,6 +541,19 @@ enum ImportCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum ProvidersCommands {
+    /// Write the dependency lock file (.oxid.lock.hcl) for the configured providers
+    Lock {
+        /// Platforms to record hashes for (OS_ARCH, comma-separated);
+        /// defaults to the current platform
+        #[arg(long = "platform", value_delimiter = ',')]
+        platforms: Vec<String>,
+    },
+    /// Stop provider processes left running by a crashed or killed run
+    Reap,
+}
+
 #[derive(Subcommand)]
 enum WorkspaceCommands {
     /// List all workspaces
@@ -215,63 +577,396 @@ enum WorkspaceCommands {
 
 const DEFAULT_WORKSPACE: &str = "default";
 
+/// How many providers `oxid init` installs at once.
+const PROVIDER_DOWNLOAD_CONCURRENCY: usize = 4;
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     #[cfg(unix)]
     reset_sigpipe();
 
     let cli = Cli::parse();
-
-    let filter = if cli.verbose {
-        EnvFilter::new("debug")
-    } else {
-        EnvFilter::new("warn")
-    };
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_target(false)
-        .init();
-
-    match cli.command {
-        Commands::Init => cmd_init(&cli).await,
-        Commands::Plan { ref target, json } => cmd_plan(&cli, target, json).await,
+    let no_color = output::formatter::init_color(cli.no_color);
+
+    output::log::init(&output::log::LogOptions {
+        verbose: cli.verbose,
+        level: cli.log_level.clone(),
+        format: cli.log_format.clone(),
+        compact_warnings: cli.compact_warnings,
+        no_color,
+    })?;
+
+    let outcome = match cli.command {
+        Commands::Init { check } => cmd_init(&cli, check).await.map(CommandOutcome::from),
+        Commands::Plan {
+            ref target,
+            ref exclude,
+            exclude_dependents,
+            ref vars,
+            json,
+            detailed_exitcode,
+            show_waves,
+            refresh,
+            destroy,
+            graph,
+            refresh_only,
+            compact,
+            ref expand,
+            ref replace,
+        } => {
+            cmd_plan(
+                &cli,
+                selection(target, exclude, exclude_dependents),
+                vars,
+                json,
+                detailed_exitcode,
+                show_waves,
+                refresh,
+                destroy,
+                graph,
+                refresh_only,
+                compact.then_some(expand.as_slice()),
+                replace,
+            )
+            .await
+        }
         Commands::Apply {
             ref target,
+            ref exclude,
+            exclude_dependents,
+            ref vars,
             auto_approve,
-        } => cmd_apply(&cli, target, auto_approve).await,
+            lock,
+            refresh,
+            refresh_only,
+            ref replace,
+            timings,
+            parallelism,
+        } => {
+            cmd_apply(
+                &cli,
+                selection(target, exclude, exclude_dependents),
+                vars,
+                auto_approve,
+                lock,
+                refresh,
+                refresh_only,
+                replace,
+                timings,
+                cli.parallelism_for(parallelism),
+            )
+            .await
+        }
         Commands::Destroy {
             ref target,
+            ref vars,
             auto_approve,
-        } => cmd_destroy(&cli, target, auto_approve).await,
-        Commands::State { ref command } => cmd_state(&cli, command).await,
-        Commands::Import { ref command } => cmd_import(&cli, command).await,
+            lock,
+            plan,
+        } => cmd_destroy(&cli, target, vars, auto_approve, lock, plan).await,
+        Commands::ForceUnlock { ref lock_id, force } => cmd_force_unlock(&cli, lock_id, force)
+            .await
+            .map(CommandOutcome::from),
+        Commands::Show { json } => cmd_show(&cli, json).await.map(CommandOutcome::from),
+        Commands::State { ref command } => cmd_state(&cli, command).await.map(CommandOutcome::from),
+        Commands::Import { ref command } => {
+            cmd_import(&cli, command).await.map(CommandOutcome::from)
+        }
         Commands::Query {
             ref sql,
+            ref params,
+            ref format,
+            ref select,
+        } => cmd_query(&cli, sql, params, select, format)
+            .await
+            .map(CommandOutcome::from),
+        Commands::Runs { limit, latest } => cmd_runs(&cli, limit, latest)
+            .await
+            .map(CommandOutcome::from),
+        Commands::Workspace { ref command } => {
+            cmd_workspace(&cli, command).await.map(CommandOutcome::from)
+        }
+        Commands::Graph {
+            ref graph_type,
             ref format,
-        } => cmd_query(&cli, sql, format).await,
-        Commands::Workspace { ref command } => cmd_workspace(&cli, command).await,
-        Commands::Graph { ref graph_type } => cmd_graph(&cli, graph_type).await,
-        Commands::Providers => cmd_providers(&cli).await,
-        Commands::Drift { refresh } => cmd_drift(&cli, refresh).await,
-        Commands::Validate => cmd_validate(&cli).await,
+        } => cmd_graph(&cli, graph_type, format)
+            .await
+            .map(CommandOutcome::from),
+        Commands::Providers { ref command } => match command {
+            None => cmd_providers(&cli).await,
+            Some(ProvidersCommands::Lock { platforms }) => {
+                cmd_providers_lock(&cli, platforms).await
+            }
+            Some(ProvidersCommands::Reap) => cmd_providers_reap(&cli),
+        }
+        .map(CommandOutcome::from),
+        Commands::Drift { refresh } => cmd_drift(&cli, refresh).await.map(CommandOutcome::from),
+        Commands::Validate { json } => cmd_validate(&cli, json).await.map(CommandOutcome::from),
+    }?;
+    Ok(outcome.exit_code())
+}
+
+/// Result of a successful command, mapped to the process exit code by `main`.
+/// Errors propagate out of `main` and exit with 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandOutcome {
+    /// Exit 0.
+    Success,
+    /// Exit 2: `plan --detailed-exitcode` found changes.
+    ChangesPresent,
+    /// Exit 130: an interrupt cancelled apply/destroy before it finished.
+    Cancelled,
+}
+
+impl CommandOutcome {
+    fn exit_code(self) -> ExitCode {
+        match self {
+            CommandOutcome::Success => ExitCode::SUCCESS,
+            CommandOutcome::ChangesPresent => ExitCode::from(2),
+            CommandOutcome::Cancelled => ExitCode::from(130),
+        }
+    }
+}
+
+impl From<()> for CommandOutcome {
+    fn from(_: ()) -> Self {
+        CommandOutcome::Success
     }
 }
 
 // ─── Helpers ─────────────────────────────────────────────────────────────────
 
-fn open_backend(working_dir: &str) -> Result<SqliteBackend> {
-    let db_path = format!("{}/oxid.db", working_dir);
-    SqliteBackend::open(&db_path)
+/// Open the state backend selected with `--state`.
+///
+/// Each command opens the backend exactly once and threads that instance
+/// through, which matters for `memory`: every in-memory connection is a fresh
+/// database, so it is initialized and seeded with the default workspace here.
+async fn open_backend(cli: &Cli) -> Result<Box<dyn StateBackend>> {
+    match cli.state.as_str() {
+        "memory" => {
+            let backend = SqliteBackend::open_memory()?;
+            backend.initialize().await?;
+            backend.create_workspace(DEFAULT_WORKSPACE).await?;
+            Ok(Box::new(backend))
+        }
+        _ => {
+            let dsn = std::env::var(STATE_DSN_ENV).ok();
+            let backend = match dsn {
+                Some(_) => None,
+                None => configured_backend(cli)?,
+            };
+            match resolve_state_location(dsn.as_deref(), backend.as_ref(), &cli.working_dir)? {
+                StateLocation::Sqlite(path) => {
+                    Ok(Box::new(SqliteBackend::open(&path.to_string_lossy())?))
+                }
+                StateLocation::S3(config) => Ok(Box::new(S3Backend::from_config(&config).await?)),
+                StateLocation::Http(config) => {
+                    Ok(Box::new(HttpBackend::from_config(&config).await?))
+                }
+                StateLocation::Postgres(_) => {
+                    bail!("PostgreSQL state is not supported by this build of oxid")
+                }
+            }
+        }
+    }
+}
+
+/// The `terraform { backend ... }` block of the configuration, if any.
+///
+/// Only `.tf` files declare a backend. When they fail to parse, the error is
+/// returned rather than falling back to local state the team does not use.
+fn configured_backend(cli: &Cli) -> Result<Option<config::types::BackendConfig>> {
+    let config_path = Path::new(&cli.config);
+    if loader::detect_mode(config_path) == loader::ConfigMode::Yaml {
+        return Ok(None);
+    }
+    let workspace =
+        loader::load_workspace(config_path).context("Failed to read the backend configuration")?;
+    Ok(workspace.terraform_settings.and_then(|t| t.backend))
+}
+
+/// The workspace lock held by apply/destroy.
+struct HeldStateLock {
+    backend: Arc<dyn StateBackend>,
+    lock_id: String,
+}
+
+/// Take the workspace lock for `operation`, unless locking is disabled.
+async fn lock_state(
+    backend: &Arc<dyn StateBackend>,
+    workspace_id: &str,
+    operation: &str,
+    enabled: bool,
+) -> Result<Option<HeldStateLock>> {
+    if !enabled {
+        return Ok(None);
+    }
+    let lock =
+        state::lock::acquire_workspace_lock(backend.as_ref(), workspace_id, operation).await?;
+    Ok(Some(HeldStateLock {
+        backend: Arc::clone(backend),
+        lock_id: lock.lock_id,
+    }))
+}
+
+async fn unlock_state(lock: Option<HeldStateLock>) -> Result<()> {
+    if let Some(held) = lock {
+        held.backend
+            .release_lock(&held.lock_id)
+            .await
+            .context("Failed to release the state lock")?;
+    }
+    Ok(())
 }
 
-fn provider_manager(working_dir: &str) -> ProviderManager {
-    let cache_dir = std::path::PathBuf::from(format!("{}/providers", working_dir));
-    ProviderManager::new(cache_dir)
+/// Ctrl-C handling for apply and destroy.
+///
+/// Once the walk has started, the first interrupt cancels it: no new
+/// resources start, running ones finish, and the command then records the
+/// run, stops providers and releases the lock as usual. A second interrupt,
+/// or one before the walk starts (e.g. at the confirmation prompt), stops
+/// providers, releases the lock and exits immediately.
+struct Interrupts {
+    cancel: tokio_util::sync::CancellationToken,
+    walking: Arc<std::sync::atomic::AtomicBool>,
+    handler: tokio::task::JoinHandle<()>,
+}
+
+impl Interrupts {
+    fn install(pm: Arc<ProviderManager>, lock: Option<&HeldStateLock>) -> Self {
+        use std::sync::atomic::Ordering;
+
+        let cancel = tokio_util::sync::CancellationToken::new();
+        let walking = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let lock = lock.map(|l| (Arc::clone(&l.backend), l.lock_id.clone()));
+        let handler = {
+            let cancel = cancel.clone();
+            let walking = Arc::clone(&walking);
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_err() {
+                    return;
+                }
+                if walking.load(Ordering::SeqCst) {
+                    cancel.cancel();
+                    eprintln!(
+                        "\n{}",
+                        "Interrupt received. Press Ctrl-C again to exit immediately.".yellow()
+                    );
+                    if tokio::signal::ctrl_c().await.is_err() {
+                        return;
+                    }
+                }
+                if let Err(e) = pm.stop_all().await {
+                    eprintln!("Failed to stop providers: {}", e);
+                }
+                if let Some((backend, lock_id)) = lock {
+                    if let Err(e) = backend.release_lock(&lock_id).await {
+                        eprintln!("Failed to release the state lock: {}", e);
+                    }
+                }
+                eprintln!("\n{}", "Interrupted.".yellow());
+                std::process::exit(130);
+            })
+        };
+        Self {
+            cancel,
+            walking,
+            handler,
+        }
+    }
+
+    fn token(&self) -> tokio_util::sync::CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// From now on the first interrupt cancels gracefully.
+    fn begin_walk(&self) {
+        self.walking
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+impl Drop for Interrupts {
+    fn drop(&mut self) {
+        self.handler.abort();
+    }
+}
+
+/// Run status recorded for a finished apply/destroy walk.
+fn run_status(interrupts: &Interrupts, failed: usize) -> &'static str {
+    if interrupts.cancelled() {
+        "cancelled"
+    } else if failed == 0 {
+        "succeeded"
+    } else {
+        "failed"
+    }
+}
+
+/// Exit status for an apply/destroy that returned normally.
+fn command_outcome(interrupts: &Interrupts) -> CommandOutcome {
+    if interrupts.cancelled() {
+        CommandOutcome::Cancelled
+    } else {
+        CommandOutcome::Success
+    }
+}
+
+/// Where providers are installed and their processes recorded.
+fn provider_cache_dir(cli: &Cli) -> std::path::PathBuf {
+    std::path::PathBuf::from(format!("{}/providers", cli.working_dir))
+}
+
+fn provider_manager(cli: &Cli) -> Result<ProviderManager> {
+    let cache_dir = provider_cache_dir(cli);
+    let installation = provider::mirror::ProviderInstallation::load()?;
+    let pm = ProviderManager::new(cache_dir)
+        .with_installation(installation)
+        .with_schema_parallelism(cli.schema_parallelism)
+        .with_start_options(provider_start_options(cli)?);
+    Ok(match LockFile::load(&config_dir(cli))? {
+        Some(lock) => pm.with_lock_file(lock),
+        None => pm,
+    })
+}
+
+/// Provider start options from the environment, overridden by the flags.
+fn provider_start_options(cli: &Cli) -> Result<ProviderStartOptions> {
+    let mut options = ProviderStartOptions::from_env()?;
+    if let Some(secs) = cli.provider_start_timeout {
+        options.handshake_timeout = std::time::Duration::from_secs(secs);
+    }
+    if let Some(port) = cli.plugin_min_port {
+        options.min_port = port;
+    }
+    if let Some(port) = cli.plugin_max_port {
+        options.max_port = port;
+    }
+    options.validate()?;
+    Ok(options)
+}
+
+/// Directory holding the configuration (and its lock file).
+fn config_dir(cli: &Cli) -> std::path::PathBuf {
+    let config = Path::new(&cli.config);
+    if config.is_file() {
+        config.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else {
+        config.to_path_buf()
+    }
 }
 
 // ─── Commands ────────────────────────────────────────────────────────────────
 
-async fn cmd_init(cli: &Cli) -> Result<()> {
+async fn cmd_init(cli: &Cli, check: bool) -> Result<()> {
+    if check {
+        return init_check(cli).await;
+    }
+
     let config_path = Path::new(&cli.config);
     let working_dir = &cli.working_dir;
 
@@ -280,7 +975,7 @@ async fn cmd_init(cli: &Cli) -> Result<()> {
     std::fs::create_dir_all(format!("{}/providers", working_dir))?;
 
     // Initialize state database
-    let backend = open_backend(working_dir)?;
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
     // Create default workspace
@@ -294,18 +989,42 @@ async fn cmd_init(cli: &Cli) -> Result<()> {
     // Load config and download providers if config exists
     let mode = loader::detect_mode(config_path);
     if mode != loader::ConfigMode::Yaml || config_path.exists() {
-        match loader::load_workspace(config_path) {
+        match load_active_workspace(cli, config_path) {
             Ok(workspace) => {
-                let pm = provider_manager(working_dir);
+                let pm = provider_manager(cli)?.with_progress(MultiProgress::new());
+                // Aliased blocks share a source and would install into the
+                // same directory at once; install each requirement once
+                let mut seen = std::collections::HashSet::new();
+                let providers: Vec<_> = workspace
+                    .providers
+                    .iter()
+                    .filter(|p| seen.insert((&p.source, &p.version_constraint)))
+                    .collect();
+                // Install in parallel, but report in configuration order
+                let results: Vec<_> = futures::stream::iter(providers)
+                    .map(|provider| {
+                        let pm = &pm;
+                        async move {
+                            let version =
+                                provider.version_constraint.as_deref().unwrap_or(">= 0.0.0");
+                            tracing::info!(
+                                provider = %provider.source,
+                                version = %version,
+                                "Downloading provider"
+                            );
+                            (
+                                provider,
+                                pm.ensure_provider(&provider.source, version).await,
+                            )
+                        }
+                    })
+                    .buffered(PROVIDER_DOWNLOAD_CONCURRENCY)
+                    .collect()
+                    .await;
+
                 let mut downloaded = 0;
-                for provider in &workspace.providers {
-                    let version = provider.version_constraint.as_deref().unwrap_or(">= 0.0.0");
-                    tracing::info!(
-                        provider = %provider.source,
-                        version = %version,
-                        "Downloading provider"
-                    );
-                    match pm.ensure_provider(&provider.source, version).await {
+                for (provider, result) in results {
+                    match result {
                         Ok(path) => {
                             println!(
                                 "  {} {} ({})",
@@ -339,64 +1058,338 @@ async fn cmd_init(cli: &Cli) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_plan(cli: &Cli, targets: &[String], json: bool) -> Result<()> {
-    let workspace = loader::load_workspace(Path::new(&cli.config))?;
+/// `oxid init --check`: load the config and resolve every provider, without
+/// creating the working directory, the state database or any provider files.
+async fn init_check(cli: &Cli) -> Result<()> {
+    let workspace = load_active_workspace(cli, Path::new(&cli.config))?;
+    let pm = provider_manager(cli)?;
 
-    // Validate count/for_each references before planning
-    let validation_errors = dag::validation::validate_count_references(&workspace);
-    if !validation_errors.is_empty() {
-        dag::validation::print_validation_errors(&validation_errors);
-        bail!("Validation failed.");
+    let mut to_download = 0;
+    let mut failed = 0;
+    for provider in &workspace.providers {
+        let version = provider.version_constraint.as_deref().unwrap_or(">= 0.0.0");
+        match pm.resolve_provider(&provider.source, version).await {
+            Ok(ProviderResolution::DevOverride(path)) => {
+                println!(
+                    "  {} {} (dev override: {})",
+                    "~".cyan(),
+                    provider.source.bold(),
+                    path.display()
+                );
+            }
+            Ok(ProviderResolution::Cached(path)) => {
+                println!(
+                    "  {} {} (installed: {})",
+                    "✓".green(),
+                    provider.source.bold(),
+                    path.display()
+                );
+            }
+            Ok(ProviderResolution::Mirror { version, .. }) => {
+                println!(
+                    "  {} {} {} (would install from filesystem mirror)",
+                    "+".green(),
+                    provider.source.bold(),
+                    version
+                );
+                to_download += 1;
+            }
+            Ok(ProviderResolution::Registry(download)) => {
+                println!(
+                    "  {} {} {} (would download {})",
+                    "+".green(),
+                    provider.source.bold(),
+                    download.version,
+                    download.filename
+                );
+                to_download += 1;
+            }
+            Err(e) => {
+                println!("  {} {} — {:#}", "!".red(), provider.source.bold(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!();
+    if failed > 0 {
+        bail!("{} provider(s) could not be resolved", failed);
+    }
+    output::formatter::print_success(&format!(
+        "Configuration is valid; {} provider(s) would be installed.",
+        to_download
+    ));
+    Ok(())
+}
+
+/// The `--target`/`--exclude` flags of plan and apply.
+fn selection(targets: &[String], excludes: &[String], exclude_dependents: bool) -> Selection {
+    Selection {
+        targets: targets.to_vec(),
+        excludes: excludes.to_vec(),
+        exclude_dependents,
     }
+}
 
-    let backend = open_backend(&cli.working_dir)?;
+#[allow(clippy::too_many_arguments)]
+async fn cmd_plan(
+    cli: &Cli,
+    selection: Selection,
+    vars: &VarArgs,
+    json: bool,
+    detailed_exitcode: bool,
+    show_waves: bool,
+    refresh: bool,
+    destroy: bool,
+    graph: bool,
+    refresh_only: bool,
+    compact: Option<&[String]>,
+    replace: &[String],
+) -> Result<CommandOutcome> {
+    let workspace =
+        loader::load_workspace_with_overrides(Path::new(&cli.config), &vars.overrides(cli)?)?;
+
+    // Validate count/for_each references before planning
+    dag::validation::check_count_references(&workspace)?;
+    validate_variables(&workspace)?;
+
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
-    let ws = backend
-        .get_workspace(DEFAULT_WORKSPACE)
-        .await?
-        .context("No default workspace. Run 'oxid init' first.")?;
+    let ws = active_workspace(cli, backend.as_ref()).await?;
 
-    let pm = Arc::new(provider_manager(&cli.working_dir));
-    let engine = ResourceEngine::new(pm, cli.parallelism);
+    let pm = Arc::new(provider_manager(cli)?);
+    let engine = ResourceEngine::new(pm, cli.parallelism)
+        .with_selection(selection)
+        .with_replace(replace.to_vec());
 
-    let plan = engine.plan(&workspace, &backend, &ws.id).await?;
+    if refresh_only {
+        let plan = engine
+            .plan_refresh_only(&workspace, backend.as_ref(), &ws.id)
+            .await;
+        engine.shutdown().await?;
+        let plan = plan?;
+        if json {
+            output::formatter::print_plan_json(&plan);
+        } else {
+            output::formatter::print_refresh_only_plan(&plan);
+        }
+        return Ok(if detailed_exitcode && plan.has_changes() {
+            CommandOutcome::ChangesPresent
+        } else {
+            CommandOutcome::Success
+        });
+    }
+
+    if destroy {
+        let plan = engine
+            .plan_destroy(&workspace, backend.as_ref(), &ws.id, refresh)
+            .await;
+        engine.shutdown().await?;
+        let plan = plan?;
+        // Provider refusals are reported as `destroy --plan` reports them
+        if plan.has_errors() {
+            return report_destroy_plan(&plan).map(CommandOutcome::from);
+        }
+        let mut records = backend
+            .list_resources(&ws.id, &ResourceFilter::default())
+            .await?;
+        executor::engine::resolve_moves(&workspace.moved, &mut records)?;
+        let plan = plan.to_plan_summary(&records);
+        if json {
+            output::formatter::print_plan_json(&plan);
+        } else {
+            print_plan(&plan, compact);
+        }
+        return Ok(if detailed_exitcode && plan.has_changes() {
+            CommandOutcome::ChangesPresent
+        } else {
+            CommandOutcome::Success
+        });
+    }
+
+    let plan = engine
+        .plan(&workspace, backend.as_ref(), &ws.id, refresh)
+        .await?;
     engine.shutdown().await?;
 
     if json {
         output::formatter::print_plan_json(&plan);
+    } else if graph {
+        let (graph, _) = dag::resource_graph::build_resource_dag(
+            &workspace,
+            &executor::engine::build_provider_map(&workspace),
+            &executor::engine::build_variable_defaults(&workspace),
+        )?;
+        let (changing, actions) = planner::graph::plan_graph(&graph, &plan);
+        println!(
+            "{}",
+            dag::resource_graph::to_dot_with_actions(&changing, &actions)
+        );
     } else {
-        output::formatter::print_resource_plan(&plan, targets);
+        if !refresh {
+            output::formatter::print_refresh_skipped_note();
+        }
+        print_plan(&plan, compact);
+        if show_waves {
+            let (graph, _) = dag::resource_graph::build_resource_dag(
+                &workspace,
+                &executor::engine::build_provider_map(&workspace),
+                &executor::engine::build_variable_defaults(&workspace),
+            )?;
+            let waves = planner::waves::plan_waves(&graph, &plan)?;
+            output::formatter::print_plan_waves(&waves);
+        }
+    }
+
+    if detailed_exitcode && plan.has_changes() {
+        Ok(CommandOutcome::ChangesPresent)
+    } else {
+        Ok(CommandOutcome::Success)
     }
-    Ok(())
 }
 
-async fn cmd_apply(cli: &Cli, targets: &[String], auto_approve: bool) -> Result<()> {
-    let workspace = loader::load_workspace(Path::new(&cli.config))?;
+/// Print `plan` in full, or compactly expanding only the given addresses.
+fn print_plan(plan: &PlanSummary, compact: Option<&[String]>) {
+    match compact {
+        Some(expand) => output::formatter::print_compact_plan(plan, expand),
+        None => output::formatter::print_resource_plan(plan),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn cmd_apply(
+    cli: &Cli,
+    selection: Selection,
+    vars: &VarArgs,
+    auto_approve: bool,
+    lock: bool,
+    refresh: bool,
+    refresh_only: bool,
+    replace: &[String],
+    timings: bool,
+    parallelism: usize,
+) -> Result<CommandOutcome> {
+    let workspace =
+        loader::load_workspace_with_overrides(Path::new(&cli.config), &vars.overrides(cli)?)?;
 
     // Validate count/for_each references before applying
-    let validation_errors = dag::validation::validate_count_references(&workspace);
-    if !validation_errors.is_empty() {
-        dag::validation::print_validation_errors(&validation_errors);
-        bail!("Validation failed.");
-    }
+    dag::validation::check_count_references(&workspace)?;
+    validate_variables(&workspace)?;
 
-    let backend = open_backend(&cli.working_dir)?;
+    let backend: Arc<dyn StateBackend> = Arc::from(open_backend(cli).await?);
     backend.initialize().await?;
 
-    let ws = backend
-        .get_workspace(DEFAULT_WORKSPACE)
-        .await?
-        .context("No default workspace. Run 'oxid init' first.")?;
+    let ws = active_workspace(cli, backend.as_ref()).await?;
+
+    let pm = Arc::new(provider_manager(cli)?);
+    let lock = lock_state(&backend, &ws.id, "apply", lock).await?;
+    let interrupts = Interrupts::install(Arc::clone(&pm), lock.as_ref());
+    let engine = ResourceEngine::new(pm, parallelism)
+        .with_cancellation(interrupts.token())
+        .with_selection(selection)
+        .with_replace(replace.to_vec());
+    let result = if refresh_only {
+        apply_refresh_only(&engine, &workspace, auto_approve, backend.as_ref(), &ws.id).await
+    } else {
+        apply_locked(
+            &engine,
+            &workspace,
+            auto_approve,
+            refresh,
+            timings,
+            &backend,
+            &ws.id,
+            &interrupts,
+        )
+        .await
+    };
+    let outcome = command_outcome(&interrupts);
+    drop(interrupts);
+    let unlocked = unlock_state(lock).await;
+    result?;
+    unlocked?;
+    Ok(outcome)
+}
+
+/// Show a refresh-only plan, confirm, and record the real objects in state
+/// while the caller holds the state lock. No infrastructure changes.
+async fn apply_refresh_only(
+    engine: &ResourceEngine,
+    workspace: &config::types::WorkspaceConfig,
+    auto_approve: bool,
+    backend: &dyn StateBackend,
+    workspace_id: &str,
+) -> Result<()> {
+    let plan = engine
+        .plan_refresh_only(workspace, backend, workspace_id)
+        .await;
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(e) => {
+            engine.shutdown().await?;
+            return Err(e);
+        }
+    };
+    output::formatter::print_refresh_only_plan(&plan);
+    if !plan.has_changes() {
+        engine.shutdown().await?;
+        return Ok(());
+    }
+
+    if !auto_approve {
+        println!(
+            "\nWould you like to update the state to reflect these detected changes? Only '{}' will be accepted.",
+            "yes".bold()
+        );
+        print!("  Enter a value: ");
+        use std::io::Write;
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if input.trim() != "yes" {
+            println!("\n{}", "Apply cancelled.".yellow());
+            engine.shutdown().await?;
+            return Ok(());
+        }
+    }
 
-    let pm = Arc::new(provider_manager(&cli.working_dir));
-    let engine = ResourceEngine::new(pm, cli.parallelism);
+    // Record exactly what the approved plan read
+    engine.shutdown().await?;
+    plan.state_updates.persist(backend, workspace_id).await?;
+    output::formatter::print_success(&format!(
+        "Refresh complete! {} resource(s) updated in state.",
+        plan.state_updates.len()
+    ));
+    Ok(())
+}
 
-    // Plan first
-    let plan = engine.plan(&workspace, &backend, &ws.id).await?;
-    output::formatter::print_resource_plan(&plan, targets);
+/// Plan, confirm and apply while the caller holds the state lock.
+#[allow(clippy::too_many_arguments)]
+async fn apply_locked(
+    engine: &ResourceEngine,
+    workspace: &config::types::WorkspaceConfig,
+    auto_approve: bool,
+    refresh: bool,
+    timings: bool,
+    backend: &Arc<dyn StateBackend>,
+    workspace_id: &str,
+    interrupts: &Interrupts,
+) -> Result<()> {
+    // Plan first, against refreshed state that is only written once approved
+    let plan = engine
+        .plan(workspace, backend.as_ref(), workspace_id, refresh)
+        .await?;
+    if !refresh {
+        output::formatter::print_refresh_skipped_note();
+    }
+    output::formatter::print_resource_plan(&plan);
 
-    if plan.creates == 0 && plan.updates == 0 && plan.deletes == 0 && plan.replaces == 0 {
+    if !plan.has_changes() {
+        // Nothing to confirm; keep what the refresh found
+        plan.state_updates
+            .persist(backend.as_ref(), workspace_id)
+            .await?;
         println!("\n{}", "No changes. Infrastructure is up-to-date.".green());
         engine.shutdown().await?;
         return Ok(());
@@ -420,29 +1413,30 @@ async fn cmd_apply(cli: &Cli, targets: &[String], auto_approve: bool) -> Result<
         }
     }
 
+    // Apply and the plan both start from the refreshed state
+    plan.state_updates
+        .persist(backend.as_ref(), workspace_id)
+        .await?;
+
     // Record run
     let run_id = backend
         .start_run(
-            &ws.id,
+            workspace_id,
             "apply",
             (plan.creates + plan.updates + plan.deletes) as i32,
         )
         .await?;
 
     // Apply
-    let backend_arc: Arc<dyn StateBackend> = Arc::new(backend);
+    interrupts.begin_walk();
     let summary = engine
-        .apply(&workspace, Arc::clone(&backend_arc), &ws.id, &plan)
+        .apply(workspace, Arc::clone(backend), workspace_id, &plan, &run_id)
         .await?;
 
     // Complete run
-    let status = if summary.failed == 0 {
-        "succeeded"
-    } else {
-        "failed"
-    };
+    let status = run_status(interrupts, summary.failed);
     let total_succeeded = (summary.added + summary.changed + summary.destroyed) as i32;
-    backend_arc
+    backend
         .complete_run(&run_id, status, total_succeeded, summary.failed as i32)
         .await?;
 
@@ -451,14 +1445,27 @@ async fn cmd_apply(cli: &Cli, targets: &[String], auto_approve: bool) -> Result<
     // Print summary
     println!();
     println!("{}", summary);
+    if timings {
+        output::formatter::print_apply_timings(&summary.results);
+    }
+    if interrupts.cancelled() {
+        println!(
+            "{}",
+            "Apply was cancelled before all resources were applied.".yellow()
+        );
+        return Ok(());
+    }
 
     // Evaluate and print outputs
     if !workspace.outputs.is_empty() && summary.failed == 0 {
         // Load all resource states from the backend into a DashMap for expression evaluation
         let resource_states: Arc<dashmap::DashMap<String, serde_json::Value>> =
             Arc::new(dashmap::DashMap::new());
-        let all_resources = backend_arc
-            .list_resources(&ws.id, &crate::state::models::ResourceFilter::default())
+        let all_resources = backend
+            .list_resources(
+                workspace_id,
+                &crate::state::models::ResourceFilter::default(),
+            )
             .await?;
         for r in &all_resources {
             if let Ok(attrs) = serde_json::from_str::<serde_json::Value>(&r.attributes_json) {
@@ -466,9 +1473,12 @@ async fn cmd_apply(cli: &Cli, targets: &[String], auto_approve: bool) -> Result<
             }
         }
 
-        let var_defaults = executor::engine::build_variable_defaults(&workspace);
-        let eval_ctx =
-            executor::engine::EvalContext::with_states(var_defaults, Arc::clone(&resource_states));
+        let var_defaults = executor::engine::build_variable_defaults(workspace);
+        let eval_ctx = executor::engine::EvalContext::for_workspace(
+            var_defaults,
+            Arc::clone(&resource_states),
+            workspace,
+        );
 
         println!();
         println!("{}:", "Outputs".bold());
@@ -480,53 +1490,111 @@ async fn cmd_apply(cli: &Cli, targets: &[String], auto_approve: bool) -> Result<
             .max()
             .unwrap_or(10);
 
+        let sensitivity = executor::sensitivity::Sensitivity::new(workspace, &all_resources);
+        backend.clear_outputs(workspace_id, "").await?;
         for output in &workspace.outputs {
-            let value = executor::engine::eval_expression(&output.value, &eval_ctx);
-            let display = if output.sensitive {
+            let value = executor::engine::eval_expression(&output.value, &eval_ctx)
+                .with_context(|| format!("output \"{}\"", output.name))?;
+            // Outputs derived from sensitive values are sensitive themselves
+            let sensitive = output.sensitive || sensitivity.is_sensitive(&output.value);
+            let display = if sensitive {
                 "<sensitive>".to_string()
             } else {
                 output::formatter::format_output_value(&value, 0)
             };
             println!("{:<width$} = {}", output.name, display, width = name_width);
+            // Kept in state for `oxid show`
+            backend
+                .set_output(
+                    workspace_id,
+                    "",
+                    &output.name,
+                    &serde_json::to_string(&value)?,
+                    sensitive,
+                )
+                .await?;
         }
     }
 
     Ok(())
 }
 
-async fn cmd_destroy(cli: &Cli, _targets: &[String], auto_approve: bool) -> Result<()> {
-    let workspace = loader::load_workspace(Path::new(&cli.config))?;
-    let backend = open_backend(&cli.working_dir)?;
+async fn cmd_destroy(
+    cli: &Cli,
+    _targets: &[String],
+    vars: &VarArgs,
+    auto_approve: bool,
+    lock: bool,
+    plan_only: bool,
+) -> Result<CommandOutcome> {
+    let workspace =
+        loader::load_workspace_with_overrides(Path::new(&cli.config), &vars.overrides(cli)?)?;
+    let backend: Arc<dyn StateBackend> = Arc::from(open_backend(cli).await?);
     backend.initialize().await?;
 
-    let ws = backend
-        .get_workspace(DEFAULT_WORKSPACE)
-        .await?
-        .context("No default workspace. Run 'oxid init' first.")?;
+    let ws = active_workspace(cli, backend.as_ref()).await?;
+
+    let pm = Arc::new(provider_manager(cli)?);
+    if plan_only {
+        let engine = ResourceEngine::new(pm, cli.parallelism);
+        let plan = engine
+            .plan_destroy(&workspace, backend.as_ref(), &ws.id, false)
+            .await;
+        engine.shutdown().await?;
+        return report_destroy_plan(&plan?).map(CommandOutcome::from);
+    }
+
+    let lock = lock_state(&backend, &ws.id, "destroy", lock).await?;
+    let interrupts = Interrupts::install(Arc::clone(&pm), lock.as_ref());
+    let engine = ResourceEngine::new(pm, cli.parallelism).with_cancellation(interrupts.token());
+    let result = destroy_locked(
+        &engine,
+        &workspace,
+        auto_approve,
+        &backend,
+        &ws.id,
+        &interrupts,
+    )
+    .await;
+    let outcome = command_outcome(&interrupts);
+    drop(interrupts);
+    let unlocked = unlock_state(lock).await;
+    result?;
+    unlocked?;
+    Ok(outcome)
+}
 
-    // Show what will be destroyed
-    let resource_count = backend.count_resources(&ws.id).await?;
-    if resource_count == 0 {
+/// Print the destroy preview; fails if a provider would refuse a destroy.
+fn report_destroy_plan(plan: &executor::engine::DestroyPlan) -> Result<()> {
+    if plan.steps.is_empty() {
         println!("{}", "No resources in state. Nothing to destroy.".dimmed());
         return Ok(());
     }
+    output::formatter::print_destroy_plan(plan);
+    if plan.has_errors() {
+        bail!("Destroy plan failed. Nothing was destroyed.");
+    }
+    Ok(())
+}
 
-    // List resources that will be destroyed
-    let resources = backend
-        .list_resources(&ws.id, &crate::state::models::ResourceFilter::default())
+/// Confirm and destroy while the caller holds the state lock.
+async fn destroy_locked(
+    engine: &ResourceEngine,
+    workspace: &config::types::WorkspaceConfig,
+    auto_approve: bool,
+    backend: &Arc<dyn StateBackend>,
+    workspace_id: &str,
+    interrupts: &Interrupts,
+) -> Result<()> {
+    // Ask the providers for a destroy plan so refusals surface before any change
+    let plan = engine
+        .plan_destroy(workspace, backend.as_ref(), workspace_id, false)
         .await?;
-
-    println!("\nDestruction Plan");
-    println!("{}", "─".repeat(60));
-    for r in &resources {
-        println!("  {} {}", "-".red().bold(), r.address.red());
+    report_destroy_plan(&plan)?;
+    if plan.steps.is_empty() {
+        return Ok(());
     }
-    println!("{}", "─".repeat(60));
-    println!(
-        "\n{} This will destroy {} resource(s).",
-        "⚠".yellow().bold(),
-        resource_count.to_string().red().bold()
-    );
+    let resource_count = plan.steps.len();
 
     if !auto_approve {
         println!(
@@ -544,24 +1612,22 @@ async fn cmd_destroy(cli: &Cli, _targets: &[String], auto_approve: bool) -> Resu
         }
     }
 
-    let pm = Arc::new(provider_manager(&cli.working_dir));
-    let engine = ResourceEngine::new(pm, cli.parallelism);
+    // Destroy finds the records under their `moved` addresses
+    plan.state_updates
+        .persist(backend.as_ref(), workspace_id)
+        .await?;
 
     let run_id = backend
-        .start_run(&ws.id, "destroy", resource_count as i32)
+        .start_run(workspace_id, "destroy", resource_count as i32)
         .await?;
 
-    let backend_arc: Arc<dyn StateBackend> = Arc::new(backend);
+    interrupts.begin_walk();
     let summary = engine
-        .destroy(&workspace, Arc::clone(&backend_arc), &ws.id)
+        .destroy(workspace, Arc::clone(backend), workspace_id, &run_id)
         .await?;
 
-    let status = if summary.failed == 0 {
-        "succeeded"
-    } else {
-        "failed"
-    };
-    backend_arc
+    let status = run_status(interrupts, summary.failed);
+    backend
         .complete_run(
             &run_id,
             status,
@@ -571,94 +1637,266 @@ async fn cmd_destroy(cli: &Cli, _targets: &[String], auto_approve: bool) -> Resu
         .await?;
 
     engine.shutdown().await?;
+    if summary.failed == 0 && !interrupts.cancelled() {
+        backend.clear_outputs(workspace_id, "").await?;
+    }
 
     // Print summary
     println!();
     println!("{}", summary);
+    if interrupts.cancelled() {
+        println!(
+            "{}",
+            "Destroy was cancelled before all resources were destroyed.".yellow()
+        );
+    }
+
+    Ok(())
+}
+
+async fn cmd_force_unlock(cli: &Cli, lock_id: &str, force: bool) -> Result<()> {
+    let backend = open_backend(cli).await?;
+    backend.initialize().await?;
+
+    let ws = active_workspace(cli, backend.as_ref()).await?;
+
+    let address = state::lock::WORKSPACE_LOCK_ADDRESS;
+    let Some(held) = backend.is_locked(address, &ws.id).await? else {
+        println!("{}", "State is not locked.".dimmed());
+        return Ok(());
+    };
+    if held.lock_id != lock_id {
+        bail!(
+            "Lock ID {} does not match the current lock {} (held by {} for {})",
+            lock_id,
+            held.lock_id,
+            held.locked_by,
+            held.operation
+        );
+    }
+
+    if !force {
+        println!(
+            "\nThe state was locked by {} for {} at {}.",
+            held.locked_by, held.operation, held.locked_at
+        );
+        println!(
+            "Removing a lock held by a running process can corrupt the state. Only '{}' will be accepted.",
+            "yes".bold()
+        );
+        print!("  Enter a value: ");
+        use std::io::Write;
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if input.trim() != "yes" {
+            println!("\n{}", "Force-unlock cancelled.".yellow());
+            return Ok(());
+        }
+    }
+
+    backend.force_unlock(address, &ws.id).await?;
+    output::formatter::print_success("State unlocked.");
+    Ok(())
+}
+
+async fn cmd_show(cli: &Cli, json: bool) -> Result<()> {
+    let backend = open_backend(cli).await?;
+    backend.initialize().await?;
+
+    let ws = active_workspace(cli, backend.as_ref()).await?;
+    let resources = backend
+        .list_resources(&ws.id, &ResourceFilter::default())
+        .await?;
+    let outputs = backend.list_outputs(&ws.id, Some("")).await?;
+
+    if json {
+        let state = output::formatter::state_json(&ws.id, ws.serial, &resources, &outputs);
+        println!("{}", serde_json::to_string_pretty(&state)?);
+    } else {
+        let resources: Vec<_> = resources
+            .into_iter()
+            .filter(|r| r.status != state::models::status::DEPOSED)
+            .collect();
+        output::formatter::print_state(&resources, &outputs);
+    }
 
     Ok(())
 }
 
 async fn cmd_state(cli: &Cli, command: &StateCommands) -> Result<()> {
-    let backend = open_backend(&cli.working_dir)?;
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
-    let ws = backend
-        .get_workspace(DEFAULT_WORKSPACE)
-        .await?
-        .context("No default workspace. Run 'oxid init' first.")?;
+    let ws = active_workspace(cli, backend.as_ref()).await?;
 
     match command {
-        StateCommands::List { filter } => {
-            let resource_filter = if let Some(f) = filter {
-                // Parse filter like "type=aws_vpc" or "status=created"
-                let mut rf = ResourceFilter::default();
-                for part in f.split(',') {
-                    let kv: Vec<&str> = part.splitn(2, '=').collect();
-                    if kv.len() == 2 {
-                        match kv[0].trim() {
-                            "type" => rf.resource_type = Some(kv[1].trim().to_string()),
-                            "module" => rf.module_path = Some(kv[1].trim().to_string()),
-                            "status" => rf.status = Some(kv[1].trim().to_string()),
-                            _ => {}
-                        }
-                    }
-                }
-                rf
-            } else {
-                ResourceFilter::default()
-            };
+        StateCommands::List {
+            filter,
+            module,
+            limit,
+            offset,
+            sort,
+            json,
+            format,
+        } => {
+            // Parse filter like "type=aws_vpc" or "status=created"
+            let mut resource_filter = filter
+                .as_deref()
+                .map(ResourceFilter::parse)
+                .unwrap_or_default();
+
+            if let Some(module) = module {
+                resource_filter.module_path = Some(module.trim_end_matches('.').to_string());
+            }
+            resource_filter.limit = *limit;
+            resource_filter.offset = *offset;
+            resource_filter.sort = sort.parse()?;
+            resource_filter.exclude_deposed = true;
 
             let resources = backend.list_resources(&ws.id, &resource_filter).await?;
-            output::formatter::print_resource_list(&resources);
+            if *json {
+                let list = output::formatter::resource_list_json(&resources);
+                println!("{}", serde_json::to_string_pretty(&list)?);
+            } else {
+                match format.as_str() {
+                    "csv" => print!(
+                        "{}",
+                        output::formatter::resource_list_delimited(&resources, ',')
+                    ),
+                    "tsv" => print!(
+                        "{}",
+                        output::formatter::resource_list_delimited(&resources, '\t')
+                    ),
+                    _ => output::formatter::print_resource_list(&resources),
+                }
+            }
         }
 
-        StateCommands::Show { address } => {
+        StateCommands::Show {
+            address,
+            json,
+            raw,
+            show_sensitive,
+        } => {
             let resource = backend
                 .get_resource(&ws.id, address)
                 .await?
+                .filter(|r| r.status != state::models::status::DEPOSED)
                 .context(format!("Resource '{}' not found in state.", address))?;
-            output::formatter::print_resource_detail(&resource);
+            if *json {
+                let detail = output::formatter::resource_detail_json(&resource, *show_sensitive);
+                println!("{}", serde_json::to_string_pretty(&detail)?);
+            } else if *raw {
+                println!(
+                    "{}",
+                    output::formatter::resource_attributes_raw(&resource, *show_sensitive)
+                );
+            } else {
+                output::formatter::print_resource_detail(&resource, *show_sensitive);
+            }
+        }
+
+        StateCommands::History { address, limit } => {
+            let history = backend
+                .list_resource_history(&ws.id, address, *limit)
+                .await?;
+            output::formatter::print_resource_history(address, &history);
         }
 
-        StateCommands::Rm { address } => {
-            let resource = backend.get_resource(&ws.id, address).await?;
-            if resource.is_none() {
+        StateCommands::Rm {
+            address,
+            dry_run,
+            auto_approve,
+        } => {
+            let addresses: Vec<String> = if ResourceFilter::is_glob(address) {
+                backend
+                    .list_resources(&ws.id, &ResourceFilter::address_glob(address))
+                    .await?
+                    .into_iter()
+                    .map(|r| r.address)
+                    .collect()
+            } else {
+                backend
+                    .get_resource(&ws.id, address)
+                    .await?
+                    .map(|r| r.address)
+                    .into_iter()
+                    .collect()
+            };
+            if addresses.is_empty() {
                 bail!("Resource '{}' not found in state.", address);
             }
-            backend.delete_resource(&ws.id, address).await?;
-            output::formatter::print_success(&format!(
-                "Removed {} from state (infrastructure unchanged).",
-                address
-            ));
+
+            if *dry_run || addresses.len() > 1 {
+                for matched in &addresses {
+                    println!("  {} {}", "-".red(), matched);
+                }
+            }
+            if *dry_run {
+                println!(
+                    "\n{} resource(s) would be removed from state.",
+                    addresses.len()
+                );
+                return Ok(());
+            }
+            if addresses.len() > 1 && !*auto_approve {
+                println!(
+                    "\nRemove these {} resources from state? Only '{}' will be accepted.",
+                    addresses.len(),
+                    "yes".bold()
+                );
+                print!("  Enter a value: ");
+                use std::io::Write;
+                std::io::stdout().flush()?;
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+                if input.trim() != "yes" {
+                    println!("\n{}", "Removal cancelled.".yellow());
+                    return Ok(());
+                }
+            }
+
+            for matched in &addresses {
+                backend.delete_resource(&ws.id, matched).await?;
+            }
+            output::formatter::print_success(&match addresses.as_slice() {
+                [single] => format!("Removed {} from state (infrastructure unchanged).", single),
+                _ => format!(
+                    "Removed {} resources from state (infrastructure unchanged).",
+                    addresses.len()
+                ),
+            });
         }
 
         StateCommands::Mv {
             source,
             destination,
         } => {
-            let resource = backend
-                .get_resource(&ws.id, source)
-                .await?
-                .context(format!("Source resource '{}' not found in state.", source))?;
+            let resources = backend
+                .list_resources(&ws.id, &ResourceFilter::default())
+                .await?;
+            let moves = state::address::plan_move(&resources, source, destination)?;
+            for (from, moved) in &moves {
+                backend.move_resource(&ws.id, from, moved).await?;
+            }
 
-            // Check destination doesn't exist
-            if backend.get_resource(&ws.id, destination).await?.is_some() {
-                bail!(
-                    "Destination resource '{}' already exists in state.",
+            if moves.len() == 1 {
+                output::formatter::print_success(&format!(
+                    "Moved {} → {}",
+                    moves[0].0, moves[0].1.address
+                ));
+            } else {
+                for (from, moved) in &moves {
+                    println!("  {} {} → {}", "→".blue(), from, moved.address);
+                }
+                output::formatter::print_success(&format!(
+                    "Moved {} instance(s) of {} → {}",
+                    moves.len(),
+                    source,
                     destination
-                );
+                ));
             }
-
-            // Create at new address, delete old
-            let mut moved = resource.clone();
-            moved.address = destination.clone();
-            moved.id = uuid::Uuid::new_v4().to_string();
-            moved.updated_at = chrono::Utc::now().to_rfc3339();
-            backend.upsert_resource(&moved).await?;
-            backend.delete_resource(&ws.id, source).await?;
-
-            output::formatter::print_success(&format!("Moved {} → {}", source, destination));
         }
     }
 
@@ -666,13 +1904,10 @@ async fn cmd_state(cli: &Cli, command: &StateCommands) -> Result<()> {
 }
 
 async fn cmd_import(cli: &Cli, command: &ImportCommands) -> Result<()> {
-    let backend = open_backend(&cli.working_dir)?;
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
-    let ws = backend
-        .get_workspace(DEFAULT_WORKSPACE)
-        .await?
-        .context("No default workspace. Run 'oxid init' first.")?;
+    let ws = active_workspace(cli, backend.as_ref()).await?;
 
     match command {
         ImportCommands::Tfstate { path } => {
@@ -704,63 +1939,162 @@ async fn cmd_import(cli: &Cli, command: &ImportCommands) -> Result<()> {
         }
 
         ImportCommands::Resource { address, id } => {
-            // Parse address to get resource type
-            let parts: Vec<&str> = address.splitn(2, '.').collect();
-            if parts.len() != 2 {
+            let target = state::address::ResourceAddress::parse(address)?;
+            if target.mode == "data" {
                 bail!(
-                    "Invalid resource address '{}'. Expected format: type.name",
+                    "Cannot import {}: data sources are read, not imported",
                     address
                 );
             }
-            let resource_type = parts[0];
-            let resource_name = parts[1];
+            let resource_type = target.resource_type.as_str();
+            let resource_name = target.name.as_str();
 
-            let workspace = loader::load_workspace(Path::new(&cli.config))?;
+            let workspace = load_active_workspace(cli, Path::new(&cli.config))?;
 
-            // Find the provider for this resource type
-            let provider_prefix = resource_type.split('_').next().unwrap_or(resource_type);
-            let provider_source = workspace
-                .providers
+            // Resolve the provider the same way the resource DAG does, honoring
+            // an explicit `provider = ...` on the matching resource block.
+            let provider_map = executor::engine::build_provider_map(&workspace);
+            let provider_source = match workspace
+                .resources
                 .iter()
-                .find(|p| p.name == provider_prefix || p.source.contains(provider_prefix))
-                .map(|p| p.source.clone())
-                .context(format!(
-                    "No provider found for resource type '{}'",
-                    resource_type
-                ))?;
-
-            let pm = Arc::new(provider_manager(&cli.working_dir));
-            let engine = ResourceEngine::new(pm, cli.parallelism);
+                .find(|r| r.resource_type == resource_type && r.name == resource_name)
+            {
+                Some(res) => dag::resource_graph::resolve_provider_source(res, &provider_map),
+                None => {
+                    let provider_prefix = resource_type.split('_').next().unwrap_or(resource_type);
+                    provider_map.get(provider_prefix).cloned().context(format!(
+                        "No provider found for resource type '{}'",
+                        resource_type
+                    ))?
+                }
+            };
 
-            // Use the provider's ImportResourceState RPC
-            // For now, create a resource state entry with the provider ID
-            let mut resource = ResourceState::new(&ws.id, resource_type, resource_name, address);
-            resource.provider_source = provider_source;
-            resource.status = "created".to_string();
-            resource.attributes_json = serde_json::json!({ "id": id }).to_string();
+            let pm = Arc::new(provider_manager(cli)?);
+            let engine = ResourceEngine::new(pm, cli.parallelism);
 
-            backend.upsert_resource(&resource).await?;
+            let result = engine
+                .import_resource(&workspace, &provider_source, resource_type, id)
+                .await;
             engine.shutdown().await?;
+            let imported = result?;
 
-            output::formatter::print_success(&format!("Imported {} (id: {}).", address, id));
+            // The object matching the requested type takes the given address;
+            // extra objects (e.g. security group rules) get numbered names.
+            let taken: std::collections::HashSet<String> = backend
+                .list_resources(&ws.id, &ResourceFilter::default())
+                .await?
+                .into_iter()
+                .map(|r| r.address)
+                .collect();
+            let types: Vec<&str> = imported.iter().map(|r| r.type_name.as_str()).collect();
+            let addresses = state::address::import_addresses(&target, &types, &taken);
+            for (obj, obj_address) in imported.iter().zip(addresses) {
+                let mut resource = ResourceState::new(
+                    &ws.id,
+                    &obj.type_name,
+                    &obj_address.name,
+                    &obj_address.to_string(),
+                );
+                resource.module_path = obj_address.module_path.clone();
+                resource.index_key = obj_address.index_key.clone();
+                resource.provider_source = provider_source.clone();
+                resource.status = state::models::status::CREATED.to_string();
+                resource.attributes_json = serde_json::to_string(&obj.state)?;
+                resource.private_data = obj.private_data.clone();
+
+                backend.upsert_resource(&resource).await?;
+                output::formatter::print_success(&format!(
+                    "Imported {} (id: {}).",
+                    obj_address,
+                    obj.state.get("id").and_then(|v| v.as_str()).unwrap_or(id)
+                ));
+            }
         }
     }
 
     Ok(())
 }
 
-async fn cmd_query(cli: &Cli, sql: &str, format: &str) -> Result<()> {
-    let backend = open_backend(&cli.working_dir)?;
+async fn cmd_query(
+    cli: &Cli,
+    sql: &str,
+    params: &[String],
+    select: &[String],
+    format: &str,
+) -> Result<()> {
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
     let fmt = QueryFormat::parse(format);
-    let result = execute_query(&backend, sql, fmt).await?;
+    if let QueryFormat::Jsonl = fmt {
+        // Written row by row so `oxid query ... | head` stops early; the
+        // default SIGPIPE disposition ends the process once head exits
+        stream_query(
+            backend.as_ref(),
+            sql,
+            params,
+            select,
+            &mut std::io::stdout(),
+        )
+        .await?;
+        return Ok(());
+    }
+    let result = execute_query(backend.as_ref(), sql, params, select, fmt).await?;
     println!("{}", result);
     Ok(())
 }
 
+/// The file recording the workspace chosen with `oxid workspace select`.
+fn workspace_file(working_dir: &str) -> String {
+    format!("{}/.workspace", working_dir)
+}
+
+/// The selected workspace, or the default one if none was ever selected.
+fn selected_workspace(working_dir: &str) -> String {
+    std::fs::read_to_string(workspace_file(working_dir))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string())
+}
+
+/// The workspace commands operate on: `--workspace`, else the selected one.
+fn active_workspace_name(cli: &Cli) -> String {
+    cli.workspace
+        .clone()
+        .unwrap_or_else(|| selected_workspace(&cli.working_dir))
+}
+
+/// Load the configuration at `path` for the active workspace, so
+/// `terraform.workspace` and the workspace's .tfvars match what `plan` sees.
+fn load_active_workspace(cli: &Cli, path: &Path) -> Result<config::types::WorkspaceConfig> {
+    loader::load_workspace_with_overrides(
+        path,
+        &hcl::variables::VariableOverrides::default().with_workspace(active_workspace_name(cli)),
+    )
+}
+
+/// Look up the active workspace in state.
+async fn active_workspace(
+    cli: &Cli,
+    backend: &dyn StateBackend,
+) -> Result<state::models::Workspace> {
+    let name = active_workspace_name(cli);
+    match backend.get_workspace(&name).await? {
+        Some(ws) => Ok(ws),
+        None if name == DEFAULT_WORKSPACE => {
+            bail!("No default workspace. Run 'oxid init' first.")
+        }
+        None => bail!(
+            "Workspace '{}' not found. Create it with 'oxid workspace new {}'.",
+            name,
+            name
+        ),
+    }
+}
+
 async fn cmd_workspace(cli: &Cli, command: &WorkspaceCommands) -> Result<()> {
-    let backend = open_backend(&cli.working_dir)?;
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
     match command {
@@ -773,8 +2107,9 @@ async fn cmd_workspace(cli: &Cli, command: &WorkspaceCommands) -> Result<()> {
             println!();
             println!("{}", "Workspaces".bold().cyan());
             println!("{}", "─".repeat(40));
+            let active = active_workspace_name(cli);
             for ws in &workspaces {
-                let marker = if ws.name == DEFAULT_WORKSPACE {
+                let marker = if ws.name == active {
                     "*".green().to_string()
                 } else {
                     " ".to_string()
@@ -798,8 +2133,7 @@ async fn cmd_workspace(cli: &Cli, command: &WorkspaceCommands) -> Result<()> {
                 .await?
                 .context(format!("Workspace '{}' not found.", name))?;
             // Write selected workspace to a file
-            let ws_file = format!("{}/.workspace", cli.working_dir);
-            std::fs::write(&ws_file, name)?;
+            std::fs::write(workspace_file(&cli.working_dir), name)?;
             output::formatter::print_success(&format!("Switched to workspace '{}'.", name));
         }
 
@@ -807,6 +2141,12 @@ async fn cmd_workspace(cli: &Cli, command: &WorkspaceCommands) -> Result<()> {
             if name == DEFAULT_WORKSPACE {
                 bail!("Cannot delete the default workspace.");
             }
+            if *name == selected_workspace(&cli.working_dir) {
+                bail!(
+                    "Cannot delete the selected workspace '{}'. Select another one first.",
+                    name
+                );
+            }
             backend
                 .get_workspace(name)
                 .await?
@@ -819,8 +2159,8 @@ async fn cmd_workspace(cli: &Cli, command: &WorkspaceCommands) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_graph(cli: &Cli, graph_type: &str) -> Result<()> {
-    let workspace = loader::load_workspace(Path::new(&cli.config))?;
+async fn cmd_graph(cli: &Cli, graph_type: &str, format: &str) -> Result<()> {
+    let workspace = load_active_workspace(cli, Path::new(&cli.config))?;
 
     match graph_type {
         "resource" => {
@@ -828,10 +2168,29 @@ async fn cmd_graph(cli: &Cli, graph_type: &str) -> Result<()> {
             let var_defaults = executor::engine::build_variable_defaults(&workspace);
             let (graph, _) =
                 dag::resource_graph::build_resource_dag(&workspace, &provider_map, &var_defaults)?;
-            let dot = dag::resource_graph::to_dot(&graph);
-            println!("{}", dot);
+            match format {
+                "json" => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&dag::resource_graph::to_json(&graph))?
+                ),
+                "mermaid" => print!("{}", dag::resource_graph::to_mermaid(&graph)),
+                _ => println!("{}", dag::resource_graph::to_dot(&graph)),
+            }
+        }
+        "provider" => {
+            if format != "dot" {
+                bail!("The provider graph only supports --format dot");
+            }
+            let provider_map = executor::engine::build_provider_map(&workspace);
+            let var_defaults = executor::engine::build_variable_defaults(&workspace);
+            let (graph, _) =
+                dag::resource_graph::build_resource_dag(&workspace, &provider_map, &var_defaults)?;
+            println!("{}", dag::resource_graph::to_dot_by_provider(&graph));
         }
         "module" => {
+            if format != "dot" {
+                bail!("The module graph only supports --format dot");
+            }
             // Fall back to the legacy module-level DAG for YAML configs
             let cfg = config::parser::load_config(&cli.config)?;
             let graph = dag::builder::build_dag(&cfg)?;
@@ -839,7 +2198,7 @@ async fn cmd_graph(cli: &Cli, graph_type: &str) -> Result<()> {
             println!("{}", dot);
         }
         _ => bail!(
-            "Unknown graph type '{}'. Use 'resource' or 'module'.",
+            "Unknown graph type '{}'. Use 'resource', 'provider' or 'module'.",
             graph_type
         ),
     }
@@ -847,20 +2206,39 @@ async fn cmd_graph(cli: &Cli, graph_type: &str) -> Result<()> {
     Ok(())
 }
 
+async fn cmd_runs(cli: &Cli, limit: usize, latest: bool) -> Result<()> {
+    let backend = open_backend(cli).await?;
+    backend.initialize().await?;
+
+    let ws = active_workspace(cli, backend.as_ref()).await?;
+
+    if latest {
+        match backend.get_latest_run(&ws.id).await? {
+            Some(run) => {
+                let results = backend.list_run_resources(&run.id).await?;
+                output::formatter::print_run_detail(&run, &results);
+            }
+            None => println!("{}", "No runs recorded.".dimmed()),
+        }
+    } else {
+        let runs = backend.list_runs(&ws.id, limit).await?;
+        output::formatter::print_run_list(&runs);
+    }
+
+    Ok(())
+}
+
 async fn cmd_providers(cli: &Cli) -> Result<()> {
-    let backend = open_backend(&cli.working_dir)?;
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
-    let ws = backend
-        .get_workspace(DEFAULT_WORKSPACE)
-        .await?
-        .context("No default workspace. Run 'oxid init' first.")?;
+    let ws = active_workspace(cli, backend.as_ref()).await?;
 
     let providers = backend.list_providers(&ws.id).await?;
 
     if providers.is_empty() {
         // Try loading from config
-        match loader::load_workspace(Path::new(&cli.config)) {
+        match load_active_workspace(cli, Path::new(&cli.config)) {
             Ok(workspace) if !workspace.providers.is_empty() => {
                 println!();
                 println!("{}", "Configured Providers".bold().cyan());
@@ -900,19 +2278,103 @@ async fn cmd_providers(cli: &Cli) -> Result<()> {
     Ok(())
 }
 
+async fn cmd_providers_lock(cli: &Cli, platforms: &[String]) -> Result<()> {
+    let workspace = load_active_workspace(cli, Path::new(&cli.config))?;
+    let required = workspace
+        .terraform_settings
+        .as_ref()
+        .is_some_and(|tf| !tf.required_providers.is_empty());
+    if workspace.providers.is_empty() && !required {
+        println!("{}", "No providers configured.".dimmed());
+        return Ok(());
+    }
+    let platforms = if platforms.is_empty() {
+        vec![Platform::current()]
+    } else {
+        platforms
+            .iter()
+            .map(|p| Platform::parse(p))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let pm = provider_manager(cli)?;
+    let lock = pm.lock_providers(&workspace, &platforms).await?;
+
+    println!();
+    for (source, entry) in &lock.providers {
+        println!(
+            "  {} {} {}",
+            "+".green(),
+            source.bold(),
+            format!("v{} ({} hashes)", entry.version, entry.hashes.len()).dimmed()
+        );
+    }
+    let path = lock.write(&config_dir(cli))?;
+    println!();
+    output::formatter::print_success(&format!(
+        "Locked {} provider(s) for {} in {}",
+        lock.providers.len(),
+        platforms
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        path.display()
+    ));
+    Ok(())
+}
+
+fn cmd_providers_reap(cli: &Cli) -> Result<()> {
+    let registry = provider::pids::PidRegistry::new(&provider_cache_dir(cli));
+    let processes = registry.reap()?;
+    if processes.is_empty() {
+        println!("{}", "No provider processes recorded.".dimmed());
+        return Ok(());
+    }
+
+    println!();
+    let mut terminated = 0;
+    for (process, outcome) in &processes {
+        let (icon, note) = match outcome {
+            ReapOutcome::Terminated => {
+                terminated += 1;
+                ("-".red(), "terminated".to_string())
+            }
+            ReapOutcome::Exited => (" ".normal(), "already exited".to_string()),
+            ReapOutcome::Reused => (
+                " ".normal(),
+                "pid now belongs to another process; left alone".to_string(),
+            ),
+            ReapOutcome::InUse => (
+                " ".normal(),
+                format!("in use by running oxid process {}", process.owner),
+            ),
+        };
+        println!(
+            "  {} {} {}",
+            icon,
+            process.provider.bold(),
+            format!("(pid {}) {}", process.pid, note).dimmed()
+        );
+    }
+    println!();
+    output::formatter::print_success(&format!(
+        "Terminated {} orphaned provider process(es)",
+        terminated
+    ));
+    Ok(())
+}
+
 async fn cmd_drift(cli: &Cli, refresh: bool) -> Result<()> {
-    let workspace = loader::load_workspace(Path::new(&cli.config))?;
-    let backend = open_backend(&cli.working_dir)?;
+    let workspace = load_active_workspace(cli, Path::new(&cli.config))?;
+    let backend = open_backend(cli).await?;
     backend.initialize().await?;
 
-    let ws = backend
-        .get_workspace(DEFAULT_WORKSPACE)
-        .await?
-        .context("No default workspace. Run 'oxid init' first.")?;
+    let ws = active_workspace(cli, backend.as_ref()).await?;
 
     if refresh {
         println!("{}", "Refreshing state from providers...".dimmed());
-        let pm = Arc::new(provider_manager(&cli.working_dir));
+        let pm = Arc::new(provider_manager(cli)?);
         let engine = ResourceEngine::new(pm, cli.parallelism);
 
         // Initialize providers
@@ -924,30 +2386,27 @@ async fn cmd_drift(cli: &Cli, refresh: bool) -> Result<()> {
                 .await;
         }
 
-        // Read each resource from the provider and update state
+        // Read resources from their providers concurrently, then persist
+        // the results one at a time
         let resources = backend
             .list_resources(&ws.id, &ResourceFilter::default())
             .await?;
+        let mut outcomes = engine.refresh_resources(&resources).await;
         let mut refreshed = 0;
         for resource in &resources {
-            if resource.provider_source.is_empty() {
-                continue;
-            }
-            let current: serde_json::Value =
-                serde_json::from_str(&resource.attributes_json).unwrap_or_default();
-            match engine
-                .provider_manager()
-                .read_resource(&resource.provider_source, &resource.resource_type, &current)
-                .await
-            {
-                Ok(Some(refreshed_state)) => {
+            match outcomes.remove(&resource.address) {
+                Some(RefreshOutcome::Refreshed {
+                    state,
+                    private_data,
+                }) => {
                     let mut updated = resource.clone();
-                    updated.attributes_json = serde_json::to_string(&refreshed_state)?;
+                    updated.attributes_json = serde_json::to_string(&state)?;
+                    updated.private_data = private_data;
                     updated.updated_at = chrono::Utc::now().to_rfc3339();
                     backend.upsert_resource(&updated).await?;
                     refreshed += 1;
                 }
-                Ok(None) => {
+                Some(RefreshOutcome::Gone) => {
                     // Resource no longer exists
                     println!(
                         "  {} {} — {}",
@@ -956,13 +2415,14 @@ async fn cmd_drift(cli: &Cli, refresh: bool) -> Result<()> {
                         "resource no longer exists".red()
                     );
                 }
-                Err(e) => {
+                Some(RefreshOutcome::Failed(e)) => {
                     tracing::warn!(
                         address = %resource.address,
                         error = %e,
                         "Failed to refresh resource"
                     );
                 }
+                None => {}
             }
         }
 
@@ -1027,13 +2487,17 @@ async fn cmd_drift(cli: &Cli, refresh: bool) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_validate(cli: &Cli) -> Result<()> {
+async fn cmd_validate(cli: &Cli, json: bool) -> Result<()> {
+    if json {
+        return validate_json(cli);
+    }
+
     let config_path = Path::new(&cli.config);
     let mode = loader::detect_mode(config_path);
 
     println!("  {} Config format: {:?}", "→".blue(), mode);
 
-    let workspace = loader::load_workspace(config_path)?;
+    let workspace = load_active_workspace(cli, config_path)?;
 
     println!(
         "  {} {} provider(s), {} resource(s), {} data source(s), {} module(s), {} variable(s), {} output(s)",
@@ -1060,32 +2524,183 @@ async fn cmd_validate(cli: &Cli) -> Result<()> {
         }
     }
 
-    // Validate depends_on references
-    let all_addresses: std::collections::HashSet<String> = workspace
-        .resources
-        .iter()
-        .map(|r| format!("{}.{}", r.resource_type, r.name))
-        .collect();
-
-    for resource in &workspace.resources {
-        for dep in &resource.depends_on {
-            if !all_addresses.contains(dep) {
-                tracing::warn!(
-                    resource = format!("{}.{}", resource.resource_type, resource.name),
-                    depends_on = %dep,
-                    "depends_on references unknown resource"
-                );
-            }
-        }
+    // Validate depends_on references: resources, data sources, and modules
+    for diagnostic in dag::validation::unknown_depends_on(&workspace) {
+        tracing::warn!(
+            resource = diagnostic.address.as_deref().unwrap_or_default(),
+            "{}",
+            diagnostic.detail
+        );
     }
 
     // Validate count/for_each references
-    let validation_errors = dag::validation::validate_count_references(&workspace);
-    if !validation_errors.is_empty() {
-        dag::validation::print_validation_errors(&validation_errors);
-        bail!("Validation failed.");
-    }
+    dag::validation::check_count_references(&workspace)?;
+
+    // Validate variable values against their validation blocks
+    validate_variables(&workspace)?;
 
     output::formatter::print_success("Configuration is valid.");
     Ok(())
 }
+
+/// `oxid validate --json`: print every diagnostic as one JSON report, failing
+/// when any of them is an error.
+fn validate_json(cli: &Cli) -> Result<()> {
+    use dag::validation::{diagnose, validation_report, Diagnostic};
+
+    let diagnostics = match load_active_workspace(cli, Path::new(&cli.config)) {
+        Ok(workspace) => {
+            let values = executor::engine::build_variable_defaults(&workspace);
+            diagnose(&workspace, &values)
+        }
+        Err(e) => vec![Diagnostic::error(
+            "Failed to load configuration",
+            format!("{:#}", e),
+        )],
+    };
+    let report = validation_report(&diagnostics);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if report["valid"] == false {
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == dag::validation::Severity::Error)
+            .map(|d| d.detail.clone())
+            .collect();
+        return Err(error::OxidError::ValidationFailed(errors).into());
+    }
+    Ok(())
+}
+
+/// Check that required variables are set and that values pass their `validation`
+/// blocks, printing any failures.
+fn validate_variables(workspace: &config::types::WorkspaceConfig) -> Result<()> {
+    hcl::variables::check_required_variables(workspace)?;
+    let values = executor::engine::build_variable_defaults(workspace);
+    dag::validation::check_variables(workspace, &values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply_parallelism(args: &[&str]) -> usize {
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Apply { parallelism, .. } => cli.parallelism_for(parallelism),
+            _ => unreachable!("not an apply"),
+        }
+    }
+
+    #[test]
+    fn test_apply_parallelism_overrides_global() {
+        assert_eq!(apply_parallelism(&["oxid", "apply"]), 10);
+        assert_eq!(apply_parallelism(&["oxid", "-p", "20", "apply"]), 20);
+        assert_eq!(
+            apply_parallelism(&["oxid", "-p", "20", "apply", "--parallelism", "4"]),
+            4
+        );
+        assert!(Cli::try_parse_from(["oxid", "apply", "--parallelism", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_var_flags_keep_command_line_order() {
+        use crate::hcl::variables::VarArg;
+
+        let cli = Cli::try_parse_from([
+            "oxid",
+            "plan",
+            "--var",
+            "a=1",
+            "--var-file",
+            "prod.tfvars",
+            "--var=b=2",
+        ])
+        .unwrap();
+        let Commands::Plan { vars, .. } = cli.command else {
+            unreachable!("not a plan");
+        };
+        assert_eq!(
+            vars.args,
+            [
+                VarArg::Var("a=1".to_string()),
+                VarArg::File("prod.tfvars".to_string()),
+                VarArg::Var("b=2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_graph_config_uses_the_active_workspace() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("main.tf"),
+            "resource \"null_resource\" \"web\" {\n  count = terraform.workspace == \"prod\" ? 3 : 1\n}\n",
+        )
+        .unwrap();
+        let config = dir.path().display().to_string();
+        let instances = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).unwrap();
+            let workspace = load_active_workspace(&cli, Path::new(&cli.config)).unwrap();
+            let (_, nodes) = dag::resource_graph::build_resource_dag(
+                &workspace,
+                &executor::engine::build_provider_map(&workspace),
+                &executor::engine::build_variable_defaults(&workspace),
+            )
+            .unwrap();
+            nodes
+                .keys()
+                .filter(|address| address.starts_with("null_resource.web"))
+                .count()
+        };
+
+        let working_dir = dir.path().join(".oxid").display().to_string();
+        let base = ["oxid", "-c", &config, "-w", &working_dir];
+        assert_eq!(
+            instances(&[&base[..], &["--workspace", "prod", "graph"]].concat()),
+            3
+        );
+        assert_eq!(instances(&[&base[..], &["graph"]].concat()), 1);
+    }
+
+    #[test]
+    fn test_validate_checks_the_active_workspace_tfvars() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("main.tf"),
+            "variable \"size\" {\n  default = \"small\"\n  validation {\n    condition     = contains([\"small\", \"large\"], var.size)\n    error_message = \"size must be small or large.\"\n  }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("prod.tfvars"), "size = \"huge\"\n").unwrap();
+        let config = dir.path().display().to_string();
+        let working_dir = dir.path().join(".oxid").display().to_string();
+        let errors = |workspace: &str| {
+            let cli = Cli::try_parse_from([
+                "oxid",
+                "-c",
+                &config,
+                "-w",
+                &working_dir,
+                "--workspace",
+                workspace,
+                "validate",
+            ])
+            .unwrap();
+            let workspace = load_active_workspace(&cli, Path::new(&cli.config)).unwrap();
+            let values = executor::engine::build_variable_defaults(&workspace);
+            dag::validation::diagnose(&workspace, &values)
+                .into_iter()
+                .map(|d| d.detail)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(errors("default").is_empty());
+        assert_eq!(errors("prod"), ["size must be small or large."]);
+    }
+
+    #[test]
+    fn test_cancelled_run_exits_non_zero() {
+        assert_eq!(CommandOutcome::Cancelled.exit_code(), ExitCode::from(130));
+        assert_ne!(CommandOutcome::Cancelled.exit_code(), ExitCode::SUCCESS);
+    }
+}
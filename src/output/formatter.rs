@@ -1,7 +1,34 @@
+use base64::Engine;
 use colored::Colorize;
 
-use crate::executor::engine::{PlanSummary, PlannedChange, ResourceAction};
-use crate::state::models::ResourceState;
+use crate::dag::walker::{format_duration, NodeResult, NodeStatus};
+use crate::executor::engine::{
+    address_matches, DestroyPlan, PlanSummary, PlannedChange, ResourceAction,
+};
+use crate::planner::waves::Wave;
+use crate::provider::protocol::{is_unknown, matches_known};
+use crate::state::models::{
+    status, OutputValue, ResourceHistoryEntry, ResourceResult, ResourceState, RunRecord,
+};
+
+/// Shown in place of a sensitive value.
+pub const SENSITIVE_PLACEHOLDER: &str = "(sensitive value)";
+
+/// Whether output should be plain text: `--no-color`, or `NO_COLOR` set to
+/// any non-empty value (see https://no-color.org).
+pub fn color_disabled(no_color: bool, no_color_env: Option<&str>) -> bool {
+    no_color || no_color_env.is_some_and(|value| !value.is_empty())
+}
+
+/// Turn colored output off for the whole process when [`color_disabled`],
+/// even if stdout looks like a terminal. Returns whether it did.
+pub fn init_color(no_color: bool) -> bool {
+    let disabled = color_disabled(no_color, std::env::var("NO_COLOR").ok().as_deref());
+    if disabled {
+        colored::control::set_override(false);
+    }
+    disabled
+}
 
 /// Print a success message.
 pub fn print_success(msg: &str) {
@@ -13,79 +40,207 @@ pub fn print_error(msg: &str) {
     println!("{} {}", "✗".red().bold(), msg.red());
 }
 
-/// Print a resource-level execution plan in a Terraform-like format.
-pub fn print_resource_plan(plan: &PlanSummary, targets: &[String]) {
-    println!();
+/// Print a refresh-only plan: drift between state and the real objects.
+pub fn print_refresh_only_plan(plan: &PlanSummary) {
+    print!("{}", format_refresh_only_plan(plan));
+}
 
-    if plan.changes.is_empty() {
-        println!("{}", "No changes. Infrastructure is up-to-date.".green());
-        return;
+/// Render a refresh-only plan: each object that changed or was deleted
+/// outside oxid, with what state would record for it.
+pub fn format_refresh_only_plan(plan: &PlanSummary) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::from("\n");
+    if !plan.has_changes() {
+        let _ = writeln!(
+            out,
+            "{}",
+            "No changes. The state matches the remote objects.".green()
+        );
+        return out;
     }
+    let _ = writeln!(
+        out,
+        "{}\n",
+        "Oxid detected the following changes made outside of oxid since the last apply:".bold()
+    );
+    for change in &plan.changes {
+        let description = match change.action {
+            ResourceAction::Delete => "has been deleted outside oxid",
+            _ => "has changed outside oxid",
+        };
+        out.push_str(&format_change(change, description));
+    }
+    let _ = writeln!(
+        out,
+        "{}",
+        "This is a refresh-only plan: applying it with --refresh-only updates the state to match the remote objects without changing any infrastructure."
+            .dimmed()
+    );
+    out
+}
+
+/// Print a resource-level execution plan in a Terraform-like format.
+pub fn print_resource_plan(plan: &PlanSummary) {
+    println!();
 
     // Check if there are any actionable changes
-    let actionable: Vec<&PlannedChange> = plan
-        .changes
-        .iter()
-        .filter(|c| c.action != ResourceAction::NoOp)
-        .filter(|c| targets.is_empty() || targets.iter().any(|t| c.address.contains(t)))
-        .collect();
+    let actionable = actionable_changes(plan);
 
-    if actionable.is_empty() {
+    if actionable.is_empty()
+        && plan.state_updates.moves.is_empty()
+        && plan.state_updates.forgotten.is_empty()
+    {
         println!("{}", "No changes. Infrastructure is up-to-date.".green());
         return;
     }
 
     // Legend
-    println!("Oxid used the selected providers to generate the following execution plan.");
-    println!("Resource actions are indicated with the following symbols:");
+    if !actionable.is_empty() {
+        println!("Oxid used the selected providers to generate the following execution plan.");
+        println!("Resource actions are indicated with the following symbols:");
 
-    let has_creates = actionable
-        .iter()
-        .any(|c| c.action == ResourceAction::Create);
-    let has_updates = actionable
-        .iter()
-        .any(|c| c.action == ResourceAction::Update);
-    let has_deletes = actionable
-        .iter()
-        .any(|c| c.action == ResourceAction::Delete);
-    let has_replaces = actionable
-        .iter()
-        .any(|c| c.action == ResourceAction::Replace);
-    let has_reads = actionable.iter().any(|c| c.action == ResourceAction::Read);
+        let has_creates = actionable
+            .iter()
+            .any(|c| c.action == ResourceAction::Create);
+        let has_updates = actionable
+            .iter()
+            .any(|c| c.action == ResourceAction::Update);
+        let has_deletes = actionable
+            .iter()
+            .any(|c| c.action == ResourceAction::Delete);
+        let has_replaces = actionable
+            .iter()
+            .any(|c| c.action == ResourceAction::Replace);
+        let has_reads = actionable.iter().any(|c| c.action == ResourceAction::Read);
 
-    if has_creates {
-        println!("  {} create", "+".green().bold());
+        if has_creates {
+            println!("  {} create", "+".green().bold());
+        }
+        if has_updates {
+            println!("  {} update in-place", "~".yellow().bold());
+        }
+        if has_replaces {
+            println!(
+                "  {} destroy and then create replacement",
+                "-/+".magenta().bold()
+            );
+        }
+        if has_deletes {
+            println!("  {} destroy", "-".red().bold());
+        }
+        if has_reads {
+            println!(" {} read (data resources)", "<=".cyan().bold());
+        }
+        println!();
     }
-    if has_updates {
-        println!("  {} update in-place", "~".yellow().bold());
+
+    println!("Oxid will perform the following actions:");
+    println!();
+
+    // Print each resource
+    for change in &actionable {
+        print_resource_change(change);
     }
-    if has_replaces {
-        println!(
-            "  {} destroy and then create replacement",
-            "-/+".magenta().bold()
+    print!("{}", format_state_updates(plan));
+
+    // Print summary
+    println!("{}", plan);
+    println!();
+
+    print_output_changes(plan);
+}
+
+/// The records a plan moves to new addresses or drops from state, e.g.
+/// `  # aws_instance.a has moved to aws_instance.b`.
+pub fn format_state_updates(plan: &PlanSummary) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    for (from, moved) in &plan.state_updates.moves {
+        let _ = writeln!(
+            out,
+            "  {} {} {}\n",
+            "#".dimmed(),
+            from.bold(),
+            format!("has moved to {}", moved.address).dimmed()
         );
     }
-    if has_deletes {
-        println!("  {} destroy", "-".red().bold());
+    for address in &plan.state_updates.forgotten {
+        let _ = writeln!(
+            out,
+            "  {} {} {}\n",
+            "#".dimmed(),
+            address.bold(),
+            "will no longer be managed by oxid (the object is not destroyed)".dimmed()
+        );
     }
-    if has_reads {
-        println!(" {} read (data resources)", "<=".cyan().bold());
+    out
+}
+
+/// Plans with fewer changes than this are printed in full even with
+/// `--compact`.
+pub const COMPACT_PLAN_THRESHOLD: usize = 20;
+
+/// The changes a plan shows: everything but NoOps. A targeted plan is
+/// already limited to the selection and what it depends on.
+fn actionable_changes(plan: &PlanSummary) -> Vec<&PlannedChange> {
+    plan.changes
+        .iter()
+        .filter(|c| c.action != ResourceAction::NoOp)
+        .collect()
+}
+
+/// One line per changing resource, e.g. `  +   aws_instance.web` or
+/// `  -/+ aws_db_instance.main`.
+pub fn format_compact_plan(plan: &PlanSummary) -> String {
+    let mut out = String::new();
+    for change in actionable_changes(plan) {
+        let icon = match change.action {
+            ResourceAction::Create => "+".green(),
+            ResourceAction::Update => "~".yellow(),
+            ResourceAction::Delete => "-".red(),
+            ResourceAction::Replace => "-/+".magenta(),
+            ResourceAction::Read => "<=".cyan(),
+            ResourceAction::NoOp => continue,
+        };
+        let pad = " ".repeat(3 - icon.chars().count());
+        out.push_str(&format!("  {}{} {}\n", icon.bold(), pad, change.address));
+    }
+    out
+}
+
+/// Print a plan with one line per changing resource, expanding the full
+/// attribute diff only for resources named in `expand`. Plans with fewer
+/// than [`COMPACT_PLAN_THRESHOLD`] changes are printed in full.
+pub fn print_compact_plan(plan: &PlanSummary, expand: &[String]) {
+    let actionable = actionable_changes(plan);
+    if actionable.len() < COMPACT_PLAN_THRESHOLD {
+        print_resource_plan(plan);
+        return;
     }
 
     println!();
     println!("Oxid will perform the following actions:");
     println!();
+    print!("{}", format_compact_plan(plan));
+    println!();
 
-    // Print each resource
-    for change in &actionable {
+    for change in actionable
+        .iter()
+        .filter(|c| expand.iter().any(|e| address_matches(e, &c.address)))
+    {
         print_resource_change(change);
     }
 
-    // Print summary
     println!("{}", plan);
     println!();
 
-    // Print output changes
+    print_output_changes(plan);
+}
+
+/// Print the outputs a plan will set.
+fn print_output_changes(plan: &PlanSummary) {
     if !plan.outputs.is_empty() {
         println!("Changes to Outputs:");
         let name_width = plan
@@ -111,31 +266,49 @@ pub fn print_resource_plan(plan: &PlanSummary, targets: &[String]) {
 
 /// Print a single resource change with its attributes.
 fn print_resource_change(change: &PlannedChange) {
-    let (icon, color_fn): (&str, fn(&str) -> colored::ColoredString) = match change.action {
-        ResourceAction::Create => ("+", |s: &str| s.green()),
-        ResourceAction::Update => ("~", |s: &str| s.yellow()),
-        ResourceAction::Delete => ("-", |s: &str| s.red()),
-        ResourceAction::Replace => ("-/+", |s: &str| s.magenta()),
-        ResourceAction::Read => ("<=", |s: &str| s.cyan()),
-        ResourceAction::NoOp => return,
-    };
+    print!("{}", format_resource_change(change));
+}
 
+/// Render a single resource change with its attributes. Attributes that
+/// force a replacement are marked `# forces replacement`.
+pub fn format_resource_change(change: &PlannedChange) -> String {
     let action_desc = match change.action {
         ResourceAction::Create => "will be created",
         ResourceAction::Update => "will be updated in-place",
         ResourceAction::Delete => "will be destroyed",
         ResourceAction::Replace => "must be replaced",
         ResourceAction::Read => "will be read during apply",
-        ResourceAction::NoOp => return,
+        ResourceAction::NoOp => return String::new(),
+    };
+    format_change(change, action_desc)
+}
+
+/// Render a single resource change under the header
+/// `# <address> <action_desc>`.
+fn format_change(change: &PlannedChange, action_desc: &str) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    let (icon, color_fn): (&str, fn(&str) -> colored::ColoredString) = match change.action {
+        ResourceAction::Create => ("+", |s: &str| s.green()),
+        ResourceAction::Update => ("~", |s: &str| s.yellow()),
+        ResourceAction::Delete => ("-", |s: &str| s.red()),
+        ResourceAction::Replace => ("-/+", |s: &str| s.magenta()),
+        ResourceAction::Read => ("<=", |s: &str| s.cyan()),
+        ResourceAction::NoOp => return out,
     };
 
     // Header: # aws_vpc.main will be created
-    println!(
+    let _ = writeln!(
+        out,
         "  {} {} {}",
         "#".dimmed(),
         change.address.bold(),
         action_desc.dimmed()
     );
+    if let Some(ref reason) = change.replace_reason {
+        let _ = writeln!(out, "  {}", format!("# ({})", reason).dimmed());
+    }
 
     // Resource block: + resource "aws_vpc" "main" {
     let is_data = change.address.starts_with("data.");
@@ -167,7 +340,7 @@ fn print_resource_change(change: &PlannedChange) {
         "  {} {} \"{}\" \"{}\" {{",
         icon, block_type, res_type, res_name
     );
-    println!("{}", color_fn(&header));
+    let _ = writeln!(out, "{}", color_fn(&header));
 
     // Collect user-specified keys for identification
     let user_keys: std::collections::HashSet<String> = change
@@ -207,14 +380,15 @@ fn print_resource_change(change: &PlannedChange) {
 
             for key in &keys {
                 let value = &obj[key.as_str()];
+                let forces = forces_replacement(change, key);
 
                 // Skip null values that aren't user-specified (reduce noise)
-                if value.is_null() && !user_keys.contains(key.as_str()) {
+                if value.is_null() && !user_keys.contains(key.as_str()) && !forces {
                     continue;
                 }
 
                 // Skip very large nested objects/arrays unless user-specified
-                if !user_keys.contains(key.as_str()) {
+                if !user_keys.contains(key.as_str()) && !forces {
                     match value {
                         serde_json::Value::Object(m) if m.len() > 8 => continue,
                         serde_json::Value::Array(a) if a.len() > 10 => continue,
@@ -230,19 +404,18 @@ fn print_resource_change(change: &PlannedChange) {
                 // Show change marker for updates
                 let attr_icon = match change.action {
                     ResourceAction::Update => {
-                        if prior_value.map(|p| p != value).unwrap_or(true) && is_user_set {
+                        if prior_value
+                            .map(|p| !matches_known(p, value))
+                            .unwrap_or(true)
+                            && is_user_set
+                        {
                             "~"
                         } else {
                             " "
                         }
                     }
-                    ResourceAction::Replace => {
-                        if change.requires_replace.contains(&key.to_string()) {
-                            "#" // forces replacement
-                        } else {
-                            " "
-                        }
-                    }
+                    ResourceAction::Replace if forces => "~",
+                    ResourceAction::Replace => " ",
                     _ => "+",
                 };
 
@@ -253,7 +426,11 @@ fn print_resource_change(change: &PlannedChange) {
                     display_val,
                     width = max_key_len
                 );
-                println!("{}", color_fn(&line));
+                if forces {
+                    let _ = writeln!(out, "{} {}", color_fn(&line), "# forces replacement".red());
+                } else {
+                    let _ = writeln!(out, "{}", color_fn(&line));
+                }
             }
         }
     } else if change.action == ResourceAction::Create || change.action == ResourceAction::Replace {
@@ -272,7 +449,7 @@ fn print_resource_change(change: &PlannedChange) {
                         display_val,
                         width = max_key_len
                     );
-                    println!("{}", color_fn(&line));
+                    let _ = writeln!(out, "{}", color_fn(&line));
                 }
             }
         }
@@ -286,8 +463,46 @@ fn print_resource_change(change: &PlannedChange) {
             " "
         }
     );
-    println!("{}", color_fn(&closing));
-    println!();
+    let _ = writeln!(out, "{}", color_fn(&closing));
+    if let Some(reason) = replacement_reason(change) {
+        let _ = writeln!(out, "  {}", reason.dimmed());
+    }
+    let _ = writeln!(out);
+    out
+}
+
+/// Whether the provider reported that a change to top-level attribute
+/// `key` (or something nested in it) forces `change` to be a replacement.
+fn forces_replacement(change: &PlannedChange, key: &str) -> bool {
+    change.action == ResourceAction::Replace
+        && change
+            .requires_replace
+            .iter()
+            .any(|path| path.split(['.', '[']).next() == Some(key))
+}
+
+/// "Resource X must be replaced because attributes [a, b] changed.", for a
+/// replacement the provider forced.
+pub fn replacement_reason(change: &PlannedChange) -> Option<String> {
+    if change.action != ResourceAction::Replace || change.requires_replace.is_empty() {
+        return None;
+    }
+    let mut paths: Vec<&str> = Vec::new();
+    for path in &change.requires_replace {
+        if !paths.contains(&path.as_str()) {
+            paths.push(path);
+        }
+    }
+    Some(format!(
+        "Resource {} must be replaced because {} [{}] changed.",
+        change.address,
+        if paths.len() == 1 {
+            "attribute"
+        } else {
+            "attributes"
+        },
+        paths.join(", ")
+    ))
 }
 
 /// Format a value for the plan display.
@@ -298,7 +513,7 @@ fn format_plan_value(
 ) -> String {
     if is_user_set {
         format_value_short(value)
-    } else if value.is_null() {
+    } else if value.is_null() || is_unknown(value) {
         "(known after apply)".dimmed().to_string()
     } else {
         format_value_short(value)
@@ -307,6 +522,9 @@ fn format_plan_value(
 
 /// Format a JSON value for short inline display.
 fn format_value_short(value: &serde_json::Value) -> String {
+    if is_unknown(value) {
+        return "(known after apply)".dimmed().to_string();
+    }
     match value {
         serde_json::Value::String(s) => format!("\"{}\"", s),
         serde_json::Value::Null => "(known after apply)".dimmed().to_string(),
@@ -408,6 +626,8 @@ pub fn print_plan_json(plan: &PlanSummary) {
                 "prior_state": c.prior_state,
                 "user_config": c.user_config,
                 "requires_replace": c.requires_replace,
+                "replace_reason": c.replace_reason,
+                "planned_private": base64::engine::general_purpose::STANDARD.encode(&c.planned_private),
             })
         })
         .collect();
@@ -424,8 +644,17 @@ pub fn print_plan_json(plan: &PlanSummary) {
         })
         .collect();
 
+    let moved: Vec<serde_json::Value> = plan
+        .state_updates
+        .moves
+        .iter()
+        .map(|(from, moved)| serde_json::json!({ "from": from, "to": moved.address }))
+        .collect();
+
     let json = serde_json::json!({
         "changes": changes,
+        "moved": moved,
+        "forgotten": plan.state_updates.forgotten,
         "outputs": outputs,
         "summary": {
             "add": plan.creates,
@@ -441,6 +670,163 @@ pub fn print_plan_json(plan: &PlanSummary) {
     );
 }
 
+/// Note printed above a plan made with `--refresh=false`.
+pub fn format_refresh_skipped_note() -> String {
+    format!(
+        "\n{} Objects were not refreshed (--refresh=false). This plan compares the\n\
+         configuration against the last recorded state, so changes made outside oxid\n\
+         are not detected.\n",
+        "Note:".yellow().bold()
+    )
+}
+
+/// Print the `--refresh=false` note.
+pub fn print_refresh_skipped_note() {
+    print!("{}", format_refresh_skipped_note());
+}
+
+/// Render the plan's execution waves (`oxid plan --show-waves`).
+pub fn format_plan_waves(waves: &[Wave]) -> String {
+    use std::fmt::Write;
+
+    if waves.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", "Execution waves:".bold());
+    for wave in waves {
+        let _ = writeln!(
+            out,
+            "  {} ({} in parallel)",
+            format!("Wave {}", wave.number).cyan().bold(),
+            wave.addresses.len()
+        );
+        for address in &wave.addresses {
+            let _ = writeln!(out, "    {}", address);
+        }
+    }
+    let widest = waves.iter().map(|w| w.addresses.len()).max().unwrap_or(0);
+    let _ = writeln!(
+        out,
+        "\n{} wave{}, at most {} change{} at once.",
+        waves.len(),
+        if waves.len() == 1 { "" } else { "s" },
+        widest,
+        if widest == 1 { "" } else { "s" }
+    );
+    out
+}
+
+/// Print the plan's execution waves.
+pub fn print_plan_waves(waves: &[Wave]) {
+    print!("{}", format_plan_waves(waves));
+}
+
+/// Render the destroy preview (`oxid destroy`), in destroy order.
+pub fn format_destroy_plan(plan: &DestroyPlan) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    let _ = writeln!(out, "\nDestruction Plan");
+    let _ = writeln!(out, "{}", "─".repeat(60));
+    for step in &plan.steps {
+        let _ = write!(out, "  {} {}", "-".red().bold(), step.address.red());
+        if !step.dependents.is_empty() {
+            let _ = write!(
+                out,
+                " {}",
+                format!("(after {})", step.dependents.join(", ")).dimmed()
+            );
+        }
+        let _ = writeln!(out);
+        if let Some(error) = &step.error {
+            let _ = writeln!(out, "      {} {}", "Error:".red().bold(), error);
+        }
+    }
+    for address in &plan.state_updates.forgotten {
+        let _ = writeln!(
+            out,
+            "  {} {} {}",
+            "#".dimmed(),
+            address,
+            "will no longer be managed by oxid".dimmed()
+        );
+    }
+    let _ = writeln!(out, "{}", "─".repeat(60));
+
+    let failed = plan.steps.iter().filter(|s| s.error.is_some()).count();
+    if failed > 0 {
+        let _ = writeln!(
+            out,
+            "\n{} The provider would refuse to destroy {} of {} resource(s).",
+            "✗".red().bold(),
+            failed.to_string().red().bold(),
+            plan.steps.len()
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "\n{} This will destroy {} resource(s).",
+            "⚠".yellow().bold(),
+            plan.steps.len().to_string().red().bold()
+        );
+    }
+    out
+}
+
+/// Print the destroy preview.
+pub fn print_destroy_plan(plan: &DestroyPlan) {
+    print!("{}", format_destroy_plan(plan));
+}
+
+/// `oxid state list --json`: each resource's metadata, without attributes.
+pub fn resource_list_json(resources: &[ResourceState]) -> serde_json::Value {
+    resources
+        .iter()
+        .map(|resource| {
+            serde_json::json!({
+                "address": resource.address,
+                "resource_type": resource.resource_type,
+                "resource_mode": resource.resource_mode,
+                "provider_source": resource.provider_source,
+                "status": resource.status,
+                "module_path": resource.module_path,
+                "index_key": resource.index_key,
+                "updated_at": resource.updated_at,
+            })
+        })
+        .collect()
+}
+
+/// `oxid state list --format csv|tsv`: one row per resource, for
+/// spreadsheets. `delimiter` is `,` for CSV and `\t` for TSV.
+pub fn resource_list_delimited(resources: &[ResourceState], delimiter: char) -> String {
+    let records: Vec<Vec<String>> = resources
+        .iter()
+        .map(|resource| {
+            vec![
+                resource.address.clone(),
+                resource.resource_type.clone(),
+                resource.provider_source.clone(),
+                resource.status.clone(),
+                resource.updated_at.clone(),
+            ]
+        })
+        .collect();
+    crate::state::query::format_delimited(
+        &[
+            "address",
+            "resource_type",
+            "provider_source",
+            "status",
+            "updated_at",
+        ],
+        &records,
+        delimiter,
+    )
+}
+
 /// Print a list of resources from state.
 pub fn print_resource_list(resources: &[ResourceState]) {
     if resources.is_empty() {
@@ -490,8 +876,452 @@ pub fn print_resource_list(resources: &[ResourceState]) {
     println!();
 }
 
+/// Render the whole state, every resource instance with its attributes and
+/// then the outputs, for `oxid show`. Sensitive values are masked.
+pub fn format_state(resources: &[ResourceState], outputs: &[OutputValue]) -> String {
+    if resources.is_empty() && outputs.is_empty() {
+        return format!("{}\n", "The state is empty.".dimmed());
+    }
+
+    let mut out = String::new();
+    for resource in resources {
+        out.push_str(&format!("{}\n", format!("# {}:", resource.address).bold()));
+        let keyword = if resource.resource_mode == "data" {
+            "data"
+        } else {
+            "resource"
+        };
+        out.push_str(&format!(
+            "{} \"{}\" \"{}\" {{\n",
+            keyword, resource.resource_type, resource.resource_name
+        ));
+        let attrs = serde_json::from_str::<serde_json::Value>(&resource.attributes_json)
+            .unwrap_or(serde_json::Value::Null);
+        if let Some(obj) = attrs.as_object() {
+            let width = obj.keys().map(|k| k.len()).max().unwrap_or(0);
+            for (key, value) in obj {
+                let display = if resource.sensitive_attrs.contains(key) {
+                    SENSITIVE_PLACEHOLDER.dimmed().to_string()
+                } else {
+                    format_output_value(value, 1)
+                };
+                out.push_str(&format!("    {:<width$} = {}\n", key, display));
+            }
+        }
+        out.push_str("}\n\n");
+    }
+
+    if !outputs.is_empty() {
+        out.push_str(&format!("{}:\n\n", "Outputs".bold()));
+        let width = outputs
+            .iter()
+            .map(|o| o.output_name.len())
+            .max()
+            .unwrap_or(0);
+        for output in outputs {
+            let display = if output.sensitive {
+                SENSITIVE_PLACEHOLDER.dimmed().to_string()
+            } else {
+                let value =
+                    serde_json::from_str(&output.output_value).unwrap_or(serde_json::Value::Null);
+                format_output_value(&value, 0)
+            };
+            out.push_str(&format!("{:<width$} = {}\n", output.output_name, display));
+        }
+    }
+    out
+}
+
+/// Print the whole state (`oxid show`).
+pub fn print_state(resources: &[ResourceState], outputs: &[OutputValue]) {
+    print!("{}", format_state(resources, outputs));
+}
+
+/// The state as a version 4 `.tfstate` document, the format `oxid import
+/// tfstate` reads, for `oxid show --json`. `lineage` identifies the workspace
+/// and `serial` is its state serial.
+pub fn state_json(
+    lineage: &str,
+    serial: u64,
+    resources: &[ResourceState],
+    outputs: &[OutputValue],
+) -> serde_json::Value {
+    // Instances of one resource are grouped under a single entry, in the
+    // order the resource first appears.
+    let mut entries: Vec<serde_json::Value> = Vec::new();
+    let mut positions: std::collections::HashMap<(&str, &str, &str, &str), usize> =
+        std::collections::HashMap::new();
+    for resource in resources {
+        let key = (
+            resource.module_path.as_str(),
+            resource.resource_mode.as_str(),
+            resource.resource_type.as_str(),
+            resource.resource_name.as_str(),
+        );
+        let position = *positions.entry(key).or_insert_with(|| {
+            let mut entry = serde_json::json!({
+                "mode": resource.resource_mode,
+                "type": resource.resource_type,
+                "name": resource.resource_name,
+                "provider": resource.provider_source,
+                "instances": [],
+            });
+            if !resource.module_path.is_empty() {
+                entry["module"] = serde_json::json!(resource.module_path);
+            }
+            entries.push(entry);
+            entries.len() - 1
+        });
+
+        let mut instance = serde_json::json!({
+            "schema_version": resource.schema_version,
+            "attributes": serde_json::from_str::<serde_json::Value>(&resource.attributes_json)
+                .unwrap_or_else(|_| serde_json::json!({})),
+            "sensitive_attributes": resource
+                .sensitive_attrs
+                .iter()
+                .map(|name| serde_json::json!([{ "type": "get_attr", "value": name }]))
+                .collect::<Vec<_>>(),
+        });
+        if let Some(ref key) = resource.index_key {
+            instance["index_key"] = serde_json::json!(key);
+        }
+        match resource.status.as_str() {
+            status::TAINTED => instance["status"] = serde_json::json!("tainted"),
+            status::DEPOSED => instance["deposed"] = serde_json::json!(deposed_key(resource)),
+            _ => {}
+        }
+        if !resource.private_data.is_empty() {
+            instance["private"] = serde_json::json!(
+                base64::engine::general_purpose::STANDARD.encode(&resource.private_data)
+            );
+        }
+        entries[position]["instances"]
+            .as_array_mut()
+            .expect("instances is an array")
+            .push(instance);
+    }
+
+    let outputs: serde_json::Map<String, serde_json::Value> = outputs
+        .iter()
+        .map(|o| {
+            let value = serde_json::from_str(&o.output_value).unwrap_or(serde_json::Value::Null);
+            (
+                o.output_name.clone(),
+                serde_json::json!({ "value": value, "sensitive": o.sensitive }),
+            )
+        })
+        .collect();
+
+    serde_json::json!({
+        "version": 4,
+        "terraform_version": env!("CARGO_PKG_VERSION"),
+        "serial": serial,
+        "lineage": lineage,
+        "outputs": outputs,
+        "resources": entries,
+    })
+}
+
+/// The key a deposed object is exported under: eight hex digits, as
+/// Terraform generates, derived from the record so it is stable.
+fn deposed_key(resource: &ResourceState) -> String {
+    use sha2::Digest;
+    hex::encode(&sha2::Sha256::digest(resource.id.as_bytes())[..4])
+}
+
+/// Resources listed by `apply --timings`.
+pub const SLOWEST_RESOURCES: usize = 10;
+
+/// Render the `--timings` breakdown of an apply: the `top` slowest resources
+/// and the time spent per provider. Only resources and data sources that
+/// actually ran are counted.
+pub fn format_apply_timings(results: &[NodeResult], top: usize) -> String {
+    use std::fmt::Write;
+
+    let mut ran: Vec<&NodeResult> = results
+        .iter()
+        .filter(|r| r.provider.is_some())
+        .filter(|r| r.status == NodeStatus::Succeeded || r.status.is_failure())
+        .collect();
+    if ran.is_empty() {
+        return String::new();
+    }
+    ran.sort_by(|a, b| b.duration.cmp(&a.duration).then(a.address.cmp(&b.address)));
+
+    let mut by_provider: Vec<(&str, std::time::Duration, usize)> = Vec::new();
+    for result in &ran {
+        let provider = result.provider.as_deref().unwrap_or_default();
+        match by_provider.iter_mut().find(|(p, _, _)| *p == provider) {
+            Some((_, total, count)) => {
+                *total += result.duration;
+                *count += 1;
+            }
+            None => by_provider.push((provider, result.duration, 1)),
+        }
+    }
+    by_provider.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let width = ran
+        .iter()
+        .take(top)
+        .map(|r| r.address.len())
+        .chain(by_provider.iter().map(|(p, _, _)| p.len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}", "Slowest resources:".bold());
+    for result in ran.iter().take(top) {
+        let _ = writeln!(
+            out,
+            "  {:<width$}  {}",
+            result.address,
+            format_timing(result.duration)
+        );
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}", "Time by provider:".bold());
+    for (provider, total, count) in by_provider {
+        let _ = writeln!(
+            out,
+            "  {:<width$}  {} ({} resource{})",
+            provider,
+            format_timing(total),
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+    }
+    out
+}
+
+/// Print the `--timings` breakdown of an apply.
+pub fn print_apply_timings(results: &[NodeResult]) {
+    print!("{}", format_apply_timings(results, SLOWEST_RESOURCES));
+}
+
+/// A duration as `4.2s`, or `3m12s` from a minute up.
+fn format_timing(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        return format!("{:.1}s", duration.as_secs_f64());
+    }
+    format_duration(secs)
+}
+
+/// Render recent runs as a table (`oxid runs`).
+pub fn format_run_list(runs: &[RunRecord]) -> String {
+    use std::fmt::Write;
+
+    if runs.is_empty() {
+        return format!("{}\n", "No runs recorded.".dimmed());
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}", "Runs".bold().cyan());
+    let _ = writeln!(out, "{}", "─".repeat(110));
+    let _ = writeln!(
+        out,
+        "  {:<9} {:<9} {:<11} {:<27} {:<27} {}",
+        "ID".bold(),
+        "OPERATION".bold(),
+        "STATUS".bold(),
+        "STARTED".bold(),
+        "COMPLETED".bold(),
+        "PLANNED/OK/FAILED".bold()
+    );
+    let _ = writeln!(out, "{}", "─".repeat(110));
+    for run in runs {
+        let _ = writeln!(
+            out,
+            "  {:<9} {:<9} {:<11} {:<27} {:<27} {}/{}/{}",
+            short_id(&run.id),
+            run.operation,
+            colored_run_status(&run.status),
+            run.started_at,
+            run.completed_at.as_deref().unwrap_or("-"),
+            run.resources_planned,
+            run.resources_succeeded,
+            run.resources_failed
+        );
+    }
+    let _ = writeln!(out);
+    out
+}
+
+/// Print recent runs as a table.
+pub fn print_run_list(runs: &[RunRecord]) {
+    print!("{}", format_run_list(runs));
+}
+
+/// Render a single run with its per-resource results (`oxid runs --latest`).
+pub fn format_run_detail(run: &RunRecord, results: &[ResourceResult]) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{} {}", "Run".bold().cyan(), run.id.bold());
+    let _ = writeln!(out, "{}", "─".repeat(80));
+    let _ = writeln!(out, "  {:<12} {}", "Operation:".bold(), run.operation);
+    let _ = writeln!(
+        out,
+        "  {:<12} {}",
+        "Status:".bold(),
+        colored_run_status(&run.status)
+    );
+    let _ = writeln!(out, "  {:<12} {}", "Started:".bold(), run.started_at);
+    let _ = writeln!(
+        out,
+        "  {:<12} {}",
+        "Completed:".bold(),
+        run.completed_at.as_deref().unwrap_or("-")
+    );
+    let _ = writeln!(
+        out,
+        "  {:<12} {} planned, {} succeeded, {} failed",
+        "Resources:".bold(),
+        run.resources_planned,
+        run.resources_succeeded,
+        run.resources_failed
+    );
+    if let Some(ref error) = run.error_message {
+        let _ = writeln!(out, "  {:<12} {}", "Error:".bold(), error.red());
+    }
+
+    if !results.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "  {:<40} {:<9} {:<11} {}",
+            "ADDRESS".bold(),
+            "ACTION".bold(),
+            "STATUS".bold(),
+            "ERROR".bold()
+        );
+        let _ = writeln!(out, "{}", "─".repeat(80));
+        for result in results {
+            let _ = writeln!(
+                out,
+                "  {:<40} {:<9} {:<11} {}",
+                result.address,
+                result.action,
+                colored_run_status(&result.status),
+                result.error_message.as_deref().unwrap_or("").dimmed()
+            );
+            // Provisioner output, indented under its resource
+            for line in result.output.iter().flat_map(|o| o.lines()) {
+                let _ = writeln!(out, "      {}", line.dimmed());
+            }
+        }
+    }
+    let _ = writeln!(out);
+    out
+}
+
+/// Print a single run with its per-resource results.
+pub fn print_run_detail(run: &RunRecord, results: &[ResourceResult]) {
+    print!("{}", format_run_detail(run, results));
+}
+
+fn short_id(id: &str) -> String {
+    id.chars().take(8).collect()
+}
+
+fn colored_run_status(status: &str) -> String {
+    match status {
+        "succeeded" => status.green().to_string(),
+        "failed" => status.red().to_string(),
+        "running" => status.blue().to_string(),
+        "cancelled" => status.yellow().to_string(),
+        _ => status.to_string(),
+    }
+}
+
+/// Print a resource's results across past runs (`oxid state history`).
+pub fn print_resource_history(address: &str, history: &[ResourceHistoryEntry]) {
+    if history.is_empty() {
+        println!("{}", format!("No run history for {}.", address).dimmed());
+        return;
+    }
+
+    println!();
+    println!("{} {}", "History".bold().cyan(), address.bold());
+    println!("{}", "─".repeat(80));
+    println!(
+        "  {:<27} {:<9} {:<9} {:<11} {}",
+        "STARTED".bold(),
+        "RUN".bold(),
+        "ACTION".bold(),
+        "STATUS".bold(),
+        "ERROR".bold()
+    );
+    println!("{}", "─".repeat(80));
+
+    for entry in history {
+        let result = &entry.result;
+        println!(
+            "  {:<27} {:<9} {:<9} {:<11} {}",
+            result.started_at.as_deref().unwrap_or("-"),
+            short_id(&entry.run_id),
+            result.action,
+            colored_run_status(&result.status),
+            result.error_message.as_deref().unwrap_or("").dimmed()
+        );
+    }
+
+    println!();
+}
+
+/// A resource's stored attributes, with sensitive ones replaced by a
+/// placeholder unless `show_sensitive` is set.
+fn resource_attributes(resource: &ResourceState, show_sensitive: bool) -> serde_json::Value {
+    let mut attributes = serde_json::from_str::<serde_json::Value>(&resource.attributes_json)
+        .unwrap_or_else(|_| serde_json::json!({}));
+    if !show_sensitive {
+        if let Some(obj) = attributes.as_object_mut() {
+            for key in &resource.sensitive_attrs {
+                if let Some(value) = obj.get_mut(key) {
+                    *value = serde_json::json!(SENSITIVE_PLACEHOLDER);
+                }
+            }
+        }
+    }
+    attributes
+}
+
+/// `oxid state show --json`: one resource's metadata and attributes.
+pub fn resource_detail_json(resource: &ResourceState, show_sensitive: bool) -> serde_json::Value {
+    serde_json::json!({
+        "address": resource.address,
+        "mode": resource.resource_mode,
+        "type": resource.resource_type,
+        "name": resource.resource_name,
+        "module": resource.module_path,
+        "index_key": resource.index_key,
+        "status": resource.status,
+        "provider_source": resource.provider_source,
+        "schema_version": resource.schema_version,
+        "created_at": resource.created_at,
+        "updated_at": resource.updated_at,
+        "sensitive_attributes": resource.sensitive_attrs,
+        "attributes": resource_attributes(resource, show_sensitive),
+    })
+}
+
+/// `oxid state show --raw`: the attributes exactly as stored, unless some
+/// need masking.
+pub fn resource_attributes_raw(resource: &ResourceState, show_sensitive: bool) -> String {
+    if show_sensitive || resource.sensitive_attrs.is_empty() {
+        resource.attributes_json.clone()
+    } else {
+        resource_attributes(resource, false).to_string()
+    }
+}
+
 /// Print detailed resource state.
-pub fn print_resource_detail(resource: &ResourceState) {
+pub fn print_resource_detail(resource: &ResourceState, show_sensitive: bool) {
     println!();
     println!("{} {}", "Resource:".bold().cyan(), resource.address.bold());
     println!("{}", "─".repeat(60));
@@ -538,8 +1368,8 @@ pub fn print_resource_detail(resource: &ResourceState) {
                     .collect();
 
                 for (key, value) in obj {
-                    let display_value = if sensitive.contains(key.as_str()) {
-                        "(sensitive)".dimmed().to_string()
+                    let display_value = if !show_sensitive && sensitive.contains(key.as_str()) {
+                        SENSITIVE_PLACEHOLDER.dimmed().to_string()
                     } else {
                         format_value_short(value)
                     };
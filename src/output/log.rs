@@ -0,0 +1,130 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Log output options from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    pub verbose: bool,
+    /// `EnvFilter` directives (e.g. `info` or `oxid=debug,provider_stderr=off`).
+    pub level: Option<String>,
+    /// `pretty` or `json`.
+    pub format: String,
+    /// Single-line warnings without timestamps (pretty format only).
+    pub compact_warnings: bool,
+    /// Plain text without ANSI colors (pretty format only).
+    pub no_color: bool,
+}
+
+impl LogOptions {
+    /// The filter to apply: `--log-level` if given, otherwise debug/warn by verbosity.
+    pub fn filter(&self) -> Result<EnvFilter> {
+        match &self.level {
+            Some(level) => EnvFilter::try_new(level)
+                .with_context(|| format!("Invalid --log-level '{}'", level)),
+            None if self.verbose => Ok(EnvFilter::new("debug")),
+            None => Ok(EnvFilter::new("warn")),
+        }
+    }
+}
+
+/// Install the global tracing subscriber.
+pub fn init(options: &LogOptions) -> Result<()> {
+    let filter = options.filter()?;
+    match options.format.as_str() {
+        "json" => {
+            let subscriber = json_subscriber(filter, std::io::stderr);
+            tracing::subscriber::set_global_default(subscriber)
+                .context("Failed to install log subscriber")?;
+        }
+        _ if options.compact_warnings => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_target(false)
+            .with_ansi(!options.no_color)
+            .without_time()
+            .compact()
+            .init(),
+        _ => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_target(false)
+            .with_ansi(!options.no_color)
+            .init(),
+    }
+    Ok(())
+}
+
+/// A subscriber writing one JSON object per event to `writer`.
+pub fn json_subscriber<W>(filter: EnvFilter, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .event_format(JsonLines)
+        .finish()
+}
+
+/// Formats events as JSON lines:
+/// `{"timestamp":..,"level":"WARN","target":"provider_stderr","fields":{"message":..}}`.
+pub struct JsonLines;
+
+impl<S, N> FormatEvent<S, N> for JsonLines
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let meta = event.metadata();
+        let mut fields = serde_json::Map::new();
+        event.record(&mut JsonVisitor(&mut fields));
+
+        let line = serde_json::json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "level": meta.level().as_str(),
+            "target": meta.target(),
+            "fields": fields,
+        });
+        writeln!(writer, "{}", line)
+    }
+}
+
+struct JsonVisitor<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::Direction;
+
+use crate::dag::resource_graph::ResourceGraph;
+use crate::executor::engine::{PlanSummary, ResourceAction};
+
+/// The part of `graph` a plan touches: every resource with a change, plus
+/// everything those resources depend on, directly or not, so the changes can
+/// be read in context. Returns the subgraph and the planned action of each
+/// resource in it, for [`crate::dag::resource_graph::to_dot_with_actions`].
+///
+/// Unchanged resources that nothing changing depends on are left out, as are
+/// deletes of resources no longer in the configuration, which have no node.
+pub fn plan_graph(
+    graph: &ResourceGraph,
+    plan: &PlanSummary,
+) -> (ResourceGraph, HashMap<String, ResourceAction>) {
+    let actions: HashMap<&str, &ResourceAction> = plan
+        .changes
+        .iter()
+        .map(|c| (c.address.as_str(), &c.action))
+        .collect();
+
+    let mut keep = HashSet::new();
+    let mut stack: Vec<_> = graph
+        .node_indices()
+        .filter(|&idx| {
+            actions.get(graph[idx].address()).is_some_and(|action| {
+                !matches!(action, ResourceAction::NoOp | ResourceAction::Read)
+            })
+        })
+        .collect();
+    while let Some(idx) = stack.pop() {
+        if keep.insert(idx) {
+            stack.extend(graph.neighbors_directed(idx, Direction::Incoming));
+        }
+    }
+
+    let subgraph = graph.filter_map(
+        |idx, node| keep.contains(&idx).then(|| node.clone()),
+        |_, edge| Some(edge.clone()),
+    );
+    let kept_actions = subgraph
+        .node_weights()
+        .filter_map(|node| {
+            let action = actions.get(node.address())?;
+            Some((node.address().to_string(), (*action).clone()))
+        })
+        .collect();
+    (subgraph, kept_actions)
+}
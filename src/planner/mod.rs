@@ -1,2 +1,4 @@
 pub mod diff;
+pub mod graph;
 pub mod plan;
+pub mod waves;
@@ -0,0 +1,71 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+use petgraph::Direction;
+
+use crate::dag::resource_graph::{topological_order, ResourceGraph};
+use crate::executor::engine::{PlanSummary, ResourceAction};
+
+/// A set of resource changes that can be applied concurrently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wave {
+    pub number: usize,
+    pub addresses: Vec<String>,
+}
+
+/// Group the plan's resource changes into waves: every change in a wave
+/// depends only on changes in earlier waves, so a wave can run in parallel
+/// once the previous one has finished.
+///
+/// Resources without changes don't start a wave of their own but still pass
+/// ordering through, so `a -> (no-op) b -> c` puts `c` after `a`. Changes
+/// that aren't in the graph (deletes of resources removed from config) have
+/// no dependencies and land in the first wave.
+pub fn plan_waves(graph: &ResourceGraph, plan: &PlanSummary) -> Result<Vec<Wave>> {
+    let changed: HashSet<&str> = plan
+        .changes
+        .iter()
+        .filter(|c| !matches!(c.action, ResourceAction::NoOp | ResourceAction::Read))
+        .map(|c| c.address.as_str())
+        .collect();
+
+    // Wave number of each node; 0 means nothing before it changes
+    let mut levels = HashMap::new();
+    let mut waves: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    let mut in_graph = HashSet::new();
+    for idx in topological_order(graph)? {
+        let address = graph[idx].address();
+        let upstream = graph
+            .neighbors_directed(idx, Direction::Incoming)
+            .filter_map(|dep| levels.get(&dep).copied())
+            .max()
+            .unwrap_or(0);
+        let level = if changed.contains(address) {
+            in_graph.insert(address);
+            waves
+                .entry(upstream + 1)
+                .or_default()
+                .push(address.to_string());
+            upstream + 1
+        } else {
+            upstream
+        };
+        levels.insert(idx, level);
+    }
+
+    for address in changed.difference(&in_graph) {
+        waves.entry(1).or_default().push(address.to_string());
+    }
+
+    Ok(waves
+        .into_values()
+        .enumerate()
+        .map(|(i, mut addresses)| {
+            addresses.sort();
+            Wave {
+                number: i + 1,
+                addresses,
+            }
+        })
+        .collect())
+}
@@ -3,6 +3,8 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 use tracing::debug;
 
+use super::version::VersionConstraint;
+
 /// Manages a local cache of downloaded provider binaries.
 ///
 /// Cache layout:
@@ -32,13 +34,12 @@ impl ProviderCache {
             .join(version)
     }
 
-    /// Find a cached provider binary matching the given constraint.
-    /// For now, supports exact version match or "latest cached".
+    /// Find the highest cached provider binary matching the given constraint.
     pub fn find(
         &self,
         namespace: &str,
         provider_type: &str,
-        version_constraint: &str,
+        constraint: &VersionConstraint,
     ) -> Result<Option<PathBuf>> {
         let provider_dir = self
             .root
@@ -50,23 +51,8 @@ impl ProviderCache {
             return Ok(None);
         }
 
-        // Exact version match
-        let version_constraint = version_constraint.trim();
-        if !version_constraint.starts_with('~')
-            && !version_constraint.starts_with('>')
-            && !version_constraint.starts_with('<')
-            && !version_constraint.starts_with('=')
-        {
-            return self.find_exact(namespace, provider_type, version_constraint);
-        }
-
-        // For constraint-based lookups, find any cached version.
-        // The actual version resolution happens in the registry client;
-        // here we just check if we already have any matching version.
-        let entries = std::fs::read_dir(&provider_dir)?;
         let mut versions: Vec<(String, PathBuf)> = Vec::new();
-
-        for entry in entries {
+        for entry in std::fs::read_dir(&provider_dir)? {
             let entry = entry?;
             if entry.file_type()?.is_dir() {
                 let version = entry.file_name().to_string_lossy().to_string();
@@ -76,13 +62,13 @@ impl ProviderCache {
             }
         }
 
-        if versions.is_empty() {
+        let Some(best) = constraint.highest(versions.iter().map(|(v, _)| v.as_str())) else {
             return Ok(None);
-        }
-
-        // Sort by version descending and return the latest
-        versions.sort_by(|a, b| compare_versions(&b.0, &a.0));
-        Ok(Some(versions[0].1.clone()))
+        };
+        Ok(versions
+            .iter()
+            .find(|(v, _)| v == best)
+            .map(|(_, binary)| binary.clone()))
     }
 
     /// Find a cached provider binary for an exact version.
@@ -203,7 +189,7 @@ impl std::fmt::Display for CachedProvider {
 }
 
 /// Find a provider binary in a directory.
-fn find_binary_in_dir(dir: &Path) -> Option<PathBuf> {
+pub(crate) fn find_binary_in_dir(dir: &Path) -> Option<PathBuf> {
     let entries = std::fs::read_dir(dir).ok()?;
     for entry in entries {
         let entry = entry.ok()?;
@@ -231,18 +217,3 @@ fn dir_size(path: &Path) -> Result<u64> {
     }
     Ok(total)
 }
-
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
-    let a_parts: Vec<u64> = a.split('.').filter_map(|p| p.parse().ok()).collect();
-    let b_parts: Vec<u64> = b.split('.').filter_map(|p| p.parse().ok()).collect();
-    let max_len = a_parts.len().max(b_parts.len());
-    for i in 0..max_len {
-        let av = a_parts.get(i).copied().unwrap_or(0);
-        let bv = b_parts.get(i).copied().unwrap_or(0);
-        match av.cmp(&bv) {
-            std::cmp::Ordering::Equal => continue,
-            other => return other,
-        }
-    }
-    std::cmp::Ordering::Equal
-}
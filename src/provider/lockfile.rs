@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+use super::mirror::qualified_source;
+
+/// Name of the dependency lock file, kept next to the configuration.
+pub const LOCK_FILE_NAME: &str = ".oxid.lock.hcl";
+
+/// Prefix of a hash over a provider's zip archive, as listed in the
+/// registry's SHA256SUMS (the same `zh:` scheme Terraform uses).
+const ZIP_HASH_PREFIX: &str = "zh:";
+
+/// Prefix of a hash over a provider package's unpacked files (Terraform's
+/// `h1:` scheme). Unlike `zh:` it can be checked against a cached or
+/// unpacked-mirror copy, where no archive is left.
+const PACKAGE_HASH_PREFIX: &str = "h1:";
+
+/// The providers a configuration was locked to by `oxid providers lock`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockFile {
+    /// Entries keyed by lowercase qualified source
+    /// (`registry.terraform.io/hashicorp/aws`).
+    pub providers: BTreeMap<String, LockedProvider>,
+}
+
+/// One `provider "SOURCE" { ... }` entry of the lock file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockedProvider {
+    pub version: String,
+    /// The configured version constraints the version was selected for.
+    pub constraints: Option<String>,
+    /// Archive and package hashes for every locked platform, e.g.
+    /// `zh:4b8a...` and `h1:q0Yb...`.
+    pub hashes: Vec<String>,
+}
+
+impl LockedProvider {
+    /// The `zh:` hash entry for an archive with the given hex SHA-256.
+    pub fn zip_hash(shasum: &str) -> String {
+        format!("{}{}", ZIP_HASH_PREFIX, shasum.trim().to_lowercase())
+    }
+
+    /// Whether an archive with the given hex SHA-256 is one of the locked
+    /// builds.
+    pub fn allows_archive(&self, shasum: &str) -> bool {
+        let hash = Self::zip_hash(shasum);
+        self.hashes.iter().any(|h| h.eq_ignore_ascii_case(&hash))
+    }
+
+    /// Whether an unpacked package with the given `h1:` hash is one of the
+    /// locked builds.
+    pub fn allows_package(&self, hash: &str) -> bool {
+        self.hashes.iter().any(|h| h == hash)
+    }
+}
+
+/// The `h1:` hash of an unpacked provider package directory.
+pub fn package_hash(dir: &Path) -> Result<String> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<(String, String)>) -> Result<()> {
+        for entry in
+            std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+                continue;
+            }
+            let bytes = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let name = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((name, hex::encode(Sha256::digest(&bytes))));
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files)?;
+    Ok(hash1(files))
+}
+
+/// The `h1:` hash a provider zip archive will have once unpacked.
+pub fn archive_package_hash(archive: &[u8]) -> Result<String> {
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive))
+        .context("Failed to open provider archive")?;
+    let mut files = Vec::new();
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        files.push((
+            entry.name().to_string(),
+            hex::encode(Sha256::digest(&bytes)),
+        ));
+    }
+    Ok(hash1(files))
+}
+
+/// Terraform's dirhash "Hash1": the SHA-256 of one `SUM  NAME` line per file,
+/// sorted by name.
+fn hash1(mut files: Vec<(String, String)>) -> String {
+    files.sort();
+    let mut summary = Sha256::new();
+    for (name, sum) in &files {
+        summary.update(format!("{}  {}\n", sum, name));
+    }
+    format!(
+        "{}{}",
+        PACKAGE_HASH_PREFIX,
+        base64::engine::general_purpose::STANDARD.encode(summary.finalize())
+    )
+}
+
+impl LockFile {
+    /// Path of the lock file for a configuration directory.
+    pub fn path_in(dir: &Path) -> PathBuf {
+        dir.join(LOCK_FILE_NAME)
+    }
+
+    /// Load the lock file in `dir`, if there is one.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path_in(dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Invalid lock file {}", path.display()))
+            .map(Some)
+    }
+
+    /// Parse lock file HCL.
+    pub fn parse(content: &str) -> Result<Self> {
+        let body: hcl::Body = hcl::from_str(content).context("Failed to parse HCL")?;
+        let mut lock = LockFile::default();
+        for block in body.blocks() {
+            if block.identifier() != "provider" {
+                bail!("Unexpected block '{}' in lock file", block.identifier());
+            }
+            let [label] = block.labels() else {
+                bail!("provider blocks in the lock file take exactly one label");
+            };
+            let source = label.as_str();
+
+            let mut entry = LockedProvider::default();
+            for attr in block.body().attributes() {
+                match (attr.key(), attr.expr()) {
+                    ("version", hcl::Expression::String(v)) => entry.version = v.clone(),
+                    ("constraints", hcl::Expression::String(c)) => {
+                        entry.constraints = Some(c.clone())
+                    }
+                    ("hashes", hcl::Expression::Array(items)) => {
+                        for item in items {
+                            let hcl::Expression::String(hash) = item else {
+                                bail!("hashes for {} must be strings", source);
+                            };
+                            entry.hashes.push(hash.clone());
+                        }
+                    }
+                    (key, _) => bail!("Unexpected attribute '{}' for {}", key, source),
+                }
+            }
+            if entry.version.is_empty() {
+                bail!("Lock entry for {} has no version", source);
+            }
+            lock.providers.insert(lock_key(source), entry);
+        }
+        Ok(lock)
+    }
+
+    /// The entry for `source` (`hashicorp/aws` or fully qualified).
+    pub fn get(&self, source: &str) -> Option<&LockedProvider> {
+        self.providers.get(&lock_key(source))
+    }
+
+    /// Add or replace the entry for `source`.
+    pub fn insert(&mut self, source: &str, entry: LockedProvider) {
+        self.providers.insert(lock_key(source), entry);
+    }
+
+    /// Render the lock file, with entries and hashes in a stable order so
+    /// regenerating an unchanged lock produces no diff.
+    pub fn to_hcl(&self) -> String {
+        let mut out = String::from(
+            "# This file is maintained automatically by \"oxid providers lock\".\n\
+             # Manual edits may be lost in future updates.\n",
+        );
+        for (source, entry) in &self.providers {
+            out.push_str(&format!("\nprovider {} {{\n", quoted(source)));
+            out.push_str(&format!("  version     = {}\n", quoted(&entry.version)));
+            if let Some(constraints) = &entry.constraints {
+                out.push_str(&format!("  constraints = {}\n", quoted(constraints)));
+            }
+            let mut hashes = entry.hashes.clone();
+            hashes.sort();
+            hashes.dedup();
+            out.push_str("  hashes = [\n");
+            for hash in &hashes {
+                out.push_str(&format!("    {},\n", quoted(hash)));
+            }
+            out.push_str("  ]\n}\n");
+        }
+        out
+    }
+
+    /// Write the lock file into `dir`, returning its path.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        let path = Self::path_in(dir);
+        std::fs::write(&path, self.to_hcl())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+fn lock_key(source: &str) -> String {
+    qualified_source(source).to_lowercase()
+}
+
+fn quoted(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_default()
+}
@@ -1,111 +1,426 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{Context, Result};
-use tokio::sync::{Mutex, RwLock};
-use tracing::{debug, info};
-
-use super::cache::ProviderCache;
-use super::protocol::ProviderConnection;
-use super::registry::RegistryClient;
+use anyhow::{bail, Context, Result};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use tokio::sync::{Mutex, RwLock, Semaphore};
+use tracing::{debug, info, warn};
+
+use super::cache::{find_binary_in_dir, ProviderCache};
+use super::lockfile::{archive_package_hash, package_hash, LockFile, LockedProvider};
+use super::mirror::{qualified_source, MirrorPackage, ProviderInstallation};
+use super::pids::{start_time, PidRegistry, ProviderProcess};
+use super::protocol::{is_transport_error, ProviderConnection, ProviderStartOptions};
+use super::registry::{
+    extract_provider_archive, shasum, Platform, ProviderSource, RegistryClient,
+    DEFAULT_REGISTRY_HOST,
+};
+use super::version::{parse_version, VersionConstraint};
+use crate::config::types::WorkspaceConfig;
+use crate::error::OxidError;
+
+/// How many provider schemas are fetched at once unless configured otherwise.
+pub const DEFAULT_SCHEMA_PARALLELISM: usize = 2;
+
+/// Where a provider binary comes from, as decided by [`ProviderManager::resolve_provider`].
+#[derive(Debug, Clone)]
+pub enum ProviderResolution {
+    /// A `dev_overrides` binary, used as-is.
+    DevOverride(PathBuf),
+    /// Already installed in the provider cache.
+    Cached(PathBuf),
+    /// Installed from a filesystem mirror.
+    Mirror {
+        version: String,
+        package: MirrorPackage,
+    },
+    /// Downloaded from the registry.
+    Registry(ProviderSource),
+}
 
 /// Manages provider lifecycles: discovery, download, startup, and connection pooling.
 pub struct ProviderManager {
     cache: ProviderCache,
     registry: RegistryClient,
-    /// Running provider connections keyed by "namespace/type".
-    /// Uses RwLock: gRPC calls take read lock (concurrent), startup/configure take write lock.
-    connections: Arc<RwLock<HashMap<String, ProviderConnection>>>,
-    /// Cached schemas keyed by "namespace/type".
+    /// Running provider connections keyed by "namespace/type", or
+    /// "namespace/type.alias" for aliased provider configurations.
+    /// RPCs clone the `Arc` under a brief read lock and release it before
+    /// awaiting; only starting and stopping providers take the write lock.
+    connections: Arc<RwLock<HashMap<String, Arc<ProviderConnection>>>>,
+    /// Cached schemas keyed by "namespace/type" (shared by all aliases).
     schemas: Arc<Mutex<HashMap<String, serde_json::Value>>>,
+    /// Caps concurrent GetSchema calls: large schemas (AWS is ~256MB) are
+    /// held in memory whole while they are decoded.
+    schema_permits: Arc<Semaphore>,
+    /// Filesystem mirrors and network policy for installing providers.
+    installation: ProviderInstallation,
+    /// Versions and archive hashes pinned by `oxid providers lock`.
+    lock: Option<LockFile>,
+    /// Where registry downloads draw their progress bars, if anywhere.
+    progress: Option<MultiProgress>,
+    /// How each running provider was started and configured, keyed like
+    /// `connections`, so a crashed process can be brought back the same way.
+    launches: Arc<Mutex<HashMap<String, ProviderLaunch>>>,
+    /// Serializes restarts so concurrent failures respawn a provider once.
+    restart_lock: Arc<Mutex<()>>,
+    /// Handshake timeout and port range for provider processes.
+    start_options: ProviderStartOptions,
+    /// Running provider processes, recorded in the cache directory so
+    /// `oxid providers reap` can stop those left behind by a crash.
+    pids: PidRegistry,
+}
+
+/// The source, version and configuration a provider instance was started with.
+#[derive(Debug, Clone, Default)]
+struct ProviderLaunch {
+    source: String,
+    version_constraint: String,
+    config: Option<serde_json::Value>,
 }
 
 impl ProviderManager {
     pub fn new(cache_dir: PathBuf) -> Self {
         Self {
+            pids: PidRegistry::new(&cache_dir),
             cache: ProviderCache::new(cache_dir),
             registry: RegistryClient::new(),
             connections: Arc::new(RwLock::new(HashMap::new())),
             schemas: Arc::new(Mutex::new(HashMap::new())),
+            schema_permits: Arc::new(Semaphore::new(DEFAULT_SCHEMA_PARALLELISM)),
+            installation: ProviderInstallation::default(),
+            lock: None,
+            progress: None,
+            launches: Arc::new(Mutex::new(HashMap::new())),
+            restart_lock: Arc::new(Mutex::new(())),
+            start_options: ProviderStartOptions::default(),
         }
     }
 
     pub fn with_registry(cache_dir: PathBuf, registry_url: &str) -> Self {
         Self {
+            pids: PidRegistry::new(&cache_dir),
             cache: ProviderCache::new(cache_dir),
             registry: RegistryClient::with_base_url(registry_url),
             connections: Arc::new(RwLock::new(HashMap::new())),
             schemas: Arc::new(Mutex::new(HashMap::new())),
+            schema_permits: Arc::new(Semaphore::new(DEFAULT_SCHEMA_PARALLELISM)),
+            installation: ProviderInstallation::default(),
+            lock: None,
+            progress: None,
+            launches: Arc::new(Mutex::new(HashMap::new())),
+            restart_lock: Arc::new(Mutex::new(())),
+            start_options: ProviderStartOptions::default(),
         }
     }
 
+    /// Install providers according to `installation` (mirrors, network policy).
+    pub fn with_installation(mut self, installation: ProviderInstallation) -> Self {
+        self.installation = installation;
+        self
+    }
+
+    /// Only install the versions and archives recorded in `lock`.
+    pub fn with_lock_file(mut self, lock: LockFile) -> Self {
+        self.lock = Some(lock);
+        self
+    }
+
+    /// Fetch at most `limit` provider schemas at once.
+    pub fn with_schema_parallelism(mut self, limit: usize) -> Self {
+        self.schema_permits = Arc::new(Semaphore::new(limit.max(1)));
+        self
+    }
+
+    /// Start provider processes with `options`.
+    pub fn with_start_options(mut self, options: ProviderStartOptions) -> Self {
+        self.start_options = options;
+        self
+    }
+
+    /// Show a progress bar on `progress` for each provider downloaded from
+    /// the registry.
+    pub fn with_progress(mut self, progress: MultiProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
     /// Ensure a provider is available (downloaded + cached).
+    /// A `dev_overrides` binary is used as-is, with no version check.
+    /// Otherwise looks in the local cache, then filesystem mirrors, then the
+    /// registry if the installation config allows network access for `source`.
     /// Returns the path to the provider binary.
     pub async fn ensure_provider(&self, source: &str, version_constraint: &str) -> Result<PathBuf> {
+        let resolution = self.resolve_provider(source, version_constraint).await?;
+        let (source, _) = split_provider_alias(source);
+        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
+        let key = format!("{}/{}", namespace, provider_type);
+        let locked = self.lock.as_ref().and_then(|lock| lock.get(source));
+
+        match resolution {
+            ProviderResolution::DevOverride(binary) => Ok(binary),
+            ProviderResolution::Cached(binary) => {
+                if let (Some(entry), Some(dir)) = (locked, binary.parent()) {
+                    check_locked_package(entry, &key, dir)?;
+                }
+                Ok(binary)
+            }
+            ProviderResolution::Mirror { version, package } => {
+                info!(
+                    "Installing provider {}@{} from filesystem mirror",
+                    key, version
+                );
+                match package {
+                    MirrorPackage::Packed(archive) => {
+                        if let Some(entry) = locked {
+                            let sum = shasum(&std::fs::read(&archive)?);
+                            check_locked_archive(
+                                entry,
+                                &key,
+                                &sum,
+                                &archive.display().to_string(),
+                            )?;
+                        }
+                        let dest_dir = self.cache.version_dir(&namespace, &provider_type, &version);
+                        std::fs::create_dir_all(&dest_dir)?;
+                        extract_provider_archive(&archive, &dest_dir)
+                            .with_context(|| format!("Failed to unpack {}", archive.display()))
+                    }
+                    MirrorPackage::Unpacked(dir) => {
+                        if let Some(entry) = locked {
+                            check_locked_package(entry, &key, &dir)?;
+                        }
+                        find_binary_in_dir(&dir).with_context(|| {
+                            format!("No provider binary in mirror directory {}", dir.display())
+                        })
+                    }
+                }
+            }
+            ProviderResolution::Registry(download_info) => {
+                let version = download_info.version.clone();
+                info!(
+                    "Downloading provider {}/{}@{}",
+                    namespace, provider_type, version
+                );
+                let dest_dir = self.cache.version_dir(&namespace, &provider_type, &version);
+
+                let bar = match &self.progress {
+                    Some(progress) => progress.add(
+                        ProgressBar::new(0)
+                            .with_style(download_style())
+                            .with_prefix(format!("{}@{}", key, version)),
+                    ),
+                    None => ProgressBar::hidden(),
+                };
+                let host_registry = host_registry(source);
+                let registry = host_registry.as_ref().unwrap_or(&self.registry);
+                let downloaded = registry
+                    .download_provider(&download_info, &dest_dir, &bar)
+                    .await;
+                bar.finish_and_clear();
+                let binary_path = downloaded?;
+
+                info!(
+                    "Provider {}/{}@{} downloaded to {}",
+                    namespace,
+                    provider_type,
+                    version,
+                    binary_path.display()
+                );
+
+                Ok(binary_path)
+            }
+        }
+    }
+
+    /// Work out where `ensure_provider` would get a provider from, without
+    /// writing anything. Only a registry lookup touches the network, and it
+    /// resolves the version and archive without downloading it.
+    pub async fn resolve_provider(
+        &self,
+        source: &str,
+        version_constraint: &str,
+    ) -> Result<ProviderResolution> {
+        let (source, _) = split_provider_alias(source);
         let (namespace, provider_type) = RegistryClient::parse_source(source)?;
         let key = format!("{}/{}", namespace, provider_type);
 
+        if let Some(binary) = self.installation.dev_override(source) {
+            warn!(
+                "Provider development overrides are in effect: using {} for {}. \
+                 Version constraints are not checked.",
+                binary.display(),
+                key
+            );
+            if !binary.is_file() {
+                bail!(
+                    "dev_overrides binary for {} does not exist: {}",
+                    key,
+                    binary.display()
+                );
+            }
+            return Ok(ProviderResolution::DevOverride(binary.to_path_buf()));
+        }
+
+        let mut constraint = VersionConstraint::parse(version_constraint)
+            .with_context(|| format!("Provider {}", key))?;
+
+        // A lock file pins the version and the archives that may be installed
+        let locked = self.lock.as_ref().and_then(|lock| lock.get(source));
+        match locked {
+            Some(entry) => {
+                if !constraint.matches(&parse_version(&entry.version)?) {
+                    bail!(
+                        "Locked version {} of {} does not match '{}'. Run 'oxid providers lock' to update the lock file.",
+                        entry.version,
+                        key,
+                        constraint
+                    );
+                }
+                constraint = VersionConstraint::parse(&format!("= {}", entry.version))?;
+            }
+            None if self.lock.is_some() => {
+                warn!(
+                    "Provider {} is not in the lock file. Run 'oxid providers lock' to add it.",
+                    key
+                );
+            }
+            None => {}
+        }
+
         // Check cache first
-        if let Some(cached) = self
-            .cache
-            .find(&namespace, &provider_type, version_constraint)?
-        {
+        if let Some(cached) = self.cache.find(&namespace, &provider_type, &constraint)? {
             debug!("Provider {} found in cache: {}", key, cached.display());
-            return Ok(cached);
+            return Ok(ProviderResolution::Cached(cached));
         }
 
-        // Resolve version from registry
-        info!(
-            "Resolving provider {}/{} version {}",
-            namespace, provider_type, version_constraint
-        );
-        let version = self
-            .registry
-            .resolve_version(&namespace, &provider_type, version_constraint)
-            .await?;
-
-        // Check cache with resolved version
-        if let Some(cached) = self
-            .cache
-            .find_exact(&namespace, &provider_type, &version)?
+        // Filesystem mirrors take precedence over the network
+        if let Some((version, package)) =
+            self.installation
+                .find(source, &constraint, &Platform::current())?
         {
-            debug!("Provider {}@{} found in cache", key, version);
-            return Ok(cached);
+            return Ok(ProviderResolution::Mirror { version, package });
+        }
+        if !self.installation.allows_network(source) {
+            bail!(
+                "Provider {} ({}) is not in any filesystem mirror, and network installation is disabled for it",
+                key,
+                constraint
+            );
         }
 
-        // Download from registry
-        info!(
-            "Downloading provider {}/{}@{}",
-            namespace, provider_type, version
-        );
-        let download_info = self
-            .registry
-            .get_download_info(&namespace, &provider_type, &version)
+        let host_registry = host_registry(source);
+        let registry = host_registry.as_ref().unwrap_or(&self.registry);
+
+        // Resolve version and download location from the registry
+        info!("Resolving provider {} version {}", key, constraint);
+        let download_info = registry
+            .resolve(&namespace, &provider_type, &constraint)
             .await?;
+        if let Some(entry) = locked {
+            check_locked_archive(entry, &key, &download_info.shasum, &download_info.filename)?;
+        }
 
-        let dest_dir = self.cache.version_dir(&namespace, &provider_type, &version);
+        Ok(ProviderResolution::Registry(download_info))
+    }
 
-        let binary_path = self
-            .registry
-            .download_provider(&download_info, &dest_dir)
+    /// Resolve the version of `source` to lock for `platforms`: the highest
+    /// version matching `version_constraint` built for all of them, with the
+    /// archive (`zh:`) and package (`h1:`) hash of each build. Every build is
+    /// downloaded, since the registry only publishes archive checksums.
+    pub async fn lock_provider(
+        &self,
+        source: &str,
+        version_constraint: &str,
+        platforms: &[Platform],
+    ) -> Result<LockedProvider> {
+        let (source, _) = split_provider_alias(source);
+        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
+        let constraint = match version_constraint.trim() {
+            "" => VersionConstraint::parse(">= 0.0.0")?,
+            c => VersionConstraint::parse(c)
+                .with_context(|| format!("Provider {}/{}", namespace, provider_type))?,
+        };
+
+        let host_registry = host_registry(source);
+        let registry = host_registry.as_ref().unwrap_or(&self.registry);
+        let version = registry
+            .resolve_version_for(&namespace, &provider_type, &constraint, platforms)
             .await?;
 
-        info!(
-            "Provider {}/{}@{} downloaded to {}",
-            namespace,
-            provider_type,
+        let mut hashes = Vec::new();
+        for platform in platforms {
+            let download = registry
+                .get_download_info_for(&namespace, &provider_type, &version, platform)
+                .await?;
+            let archive = registry
+                .fetch_archive(&download, &ProgressBar::hidden())
+                .await?;
+            hashes.push(LockedProvider::zip_hash(&download.shasum));
+            hashes.push(archive_package_hash(&archive)?);
+        }
+        hashes.sort();
+        hashes.dedup();
+
+        Ok(LockedProvider {
             version,
-            binary_path.display()
-        );
+            constraints: Some(version_constraint.trim())
+                .filter(|c| !c.is_empty())
+                .map(str::to_string),
+            hashes,
+        })
+    }
+
+    /// Lock every provider the workspace configures for `platforms`: each
+    /// `provider` block and each `required_providers` entry. Blocks sharing a
+    /// source (aliases) get one entry covering all their constraints.
+    pub async fn lock_providers(
+        &self,
+        workspace: &WorkspaceConfig,
+        platforms: &[Platform],
+    ) -> Result<LockFile> {
+        let required = workspace
+            .terraform_settings
+            .as_ref()
+            .map(|tf| &tf.required_providers);
+
+        let mut constraints: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut add = |source: &str, constraint: Option<&str>| {
+            let entry = constraints
+                .entry(qualified_source(source).to_lowercase())
+                .or_default();
+            if let Some(c) = constraint.map(str::trim).filter(|c| !c.is_empty()) {
+                if !entry.iter().any(|e| e == c) {
+                    entry.push(c.to_string());
+                }
+            }
+        };
+        for provider in &workspace.providers {
+            match required.and_then(|r| r.get(&provider.name)) {
+                Some(req) => add(&req.source, provider.version_constraint.as_deref()),
+                None => add(&provider.source, provider.version_constraint.as_deref()),
+            }
+        }
+        for req in required.into_iter().flat_map(|r| r.values()) {
+            add(&req.source, req.version.as_deref());
+        }
 
-        Ok(binary_path)
+        let mut lock = LockFile::default();
+        for (source, constraint) in constraints {
+            let entry = self
+                .lock_provider(&source, &constraint.join(", "), platforms)
+                .await
+                .with_context(|| format!("Failed to lock provider {}", source))?;
+            lock.insert(&source, entry);
+        }
+        Ok(lock)
     }
 
     /// Get or start a provider connection. Reuses existing connections.
+    /// `source` may carry an alias (`hashicorp/aws.west`); each alias runs its
+    /// own provider process so it can be configured independently.
     pub async fn get_connection(&self, source: &str, version_constraint: &str) -> Result<()> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
+        let key = connection_key(source)?;
 
         // Check with read lock first (fast path)
         {
@@ -117,28 +432,57 @@ impl ProviderManager {
 
         let binary_path = self.ensure_provider(source, version_constraint).await?;
 
-        let conn = ProviderConnection::start(&binary_path)
+        let conn = ProviderConnection::start_with(&binary_path, &self.start_options)
             .await
-            .context(format!("Failed to start provider {}", key))?;
+            .map_err(|err| OxidError::ProviderStart {
+                provider: key.clone(),
+                source: err.into(),
+            })?;
 
         let mut conns = self.connections.write().await;
-        conns.insert(key, conn);
+        if conns.contains_key(&key) {
+            // Another task started the same provider while this one was
+            // starting; keep the first so every caller shares one process.
+            drop(conns);
+            debug!("Provider {} already started concurrently", key);
+            conn.stop().await?;
+            return Ok(());
+        }
+        let conn = Arc::new(conn);
+        conns.insert(key.clone(), conn.clone());
+        drop(conns);
+        self.track_pid(&key, &conn).await;
+
+        let mut launches = self.launches.lock().await;
+        let launch = launches.entry(key).or_default();
+        launch.source = source.to_string();
+        launch.version_constraint = version_constraint.to_string();
         Ok(())
     }
 
+    /// The running connection for `address`, cloned out of the pool so the
+    /// map lock is not held while an RPC is in flight.
+    async fn connection(&self, address: &str) -> Result<Arc<ProviderConnection>> {
+        let key = connection_key(address)?;
+        let conns = self.connections.read().await;
+        conns
+            .get(&key)
+            .cloned()
+            .context(format!("Provider {} not connected", key))
+    }
+
     /// Get the schema for a provider. Starts the provider if not running.
     pub async fn get_schema(
         &self,
         source: &str,
         version_constraint: &str,
     ) -> Result<serde_json::Value> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
+        let schema_key = connection_key(split_provider_alias(source).0)?;
 
         // Check schema cache
         {
             let schemas = self.schemas.lock().await;
-            if let Some(schema) = schemas.get(&key) {
+            if let Some(schema) = schemas.get(&schema_key) {
                 return Ok(schema.clone());
             }
         }
@@ -146,25 +490,160 @@ impl ProviderManager {
         // Ensure connection exists
         self.get_connection(source, version_constraint).await?;
 
-        // Get schema from provider (returns JSON directly) — needs write lock for caching
-        let mut conns = self.connections.write().await;
-        let conn = conns
-            .get_mut(&key)
-            .context(format!("Provider {} not connected", key))?;
-
-        let schema_json = conn.get_schema().await?;
+        let conn = self.connection(source).await?;
+        let schema_json = self
+            .limit_schema_fetch(async {
+                // Another caller may have fetched it while this one waited
+                if let Some(schema) = self.schemas.lock().await.get(&schema_key) {
+                    return Ok(schema.clone());
+                }
+                conn.get_schema().await
+            })
+            .await?;
 
         // Cache it
         {
             let mut schemas = self.schemas.lock().await;
-            schemas.insert(key, schema_json.clone());
+            schemas.insert(schema_key, schema_json.clone());
         }
 
         Ok(schema_json)
     }
 
+    /// Run a schema fetch once fewer than the configured number of other
+    /// fetches are in flight (see [`ProviderManager::with_schema_parallelism`]).
+    pub async fn limit_schema_fetch<T>(&self, fetch: impl std::future::Future<Output = T>) -> T {
+        let _permit = self
+            .schema_permits
+            .acquire()
+            .await
+            .expect("schema semaphore is never closed");
+        fetch.await
+    }
+
+    /// Run an RPC against the provider at `address`, restarting it once if
+    /// its process has died. A provider found dead before the call is always
+    /// restarted and the call made on the new process; one that dies during
+    /// the call is restarted, and the call is only replayed when `replay` is
+    /// set, since a crashed apply may have partly changed real infrastructure.
+    async fn call<T, F, Fut>(&self, address: &str, replay: bool, rpc: F) -> Result<T>
+    where
+        F: Fn(Arc<ProviderConnection>) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut conn = self.connection(address).await?;
+        if conn.has_exited().await {
+            conn = self.restart(address, &conn).await?;
+        }
+        match rpc(conn.clone()).await {
+            Ok(result) => Ok(result),
+            Err(err) if is_transport_error(&err) && exited_after_error(&conn).await => {
+                warn!("Provider {} exited unexpectedly: {:#}", address, err);
+                let conn = self.restart(address, &conn).await?;
+                if replay {
+                    rpc(conn).await
+                } else {
+                    Err(err.context(format!(
+                        "Provider {} crashed during the call and was restarted",
+                        address
+                    )))
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replace the dead process behind `address` with a fresh one, started
+    /// from the same binary and configured as the old one was.
+    async fn restart(
+        &self,
+        address: &str,
+        dead: &Arc<ProviderConnection>,
+    ) -> Result<Arc<ProviderConnection>> {
+        let key = connection_key(address)?;
+        let _restarting = self.restart_lock.lock().await;
+
+        match self.connections.read().await.get(&key) {
+            // Another caller already restarted it.
+            Some(current) if !Arc::ptr_eq(current, dead) => return Ok(current.clone()),
+            Some(_) => {}
+            None => bail!("Provider {} not connected", key),
+        }
+
+        let launch = self
+            .launches
+            .lock()
+            .await
+            .get(&key)
+            .cloned()
+            .context(format!("Provider {} has no recorded launch", key))?;
+        info!("Restarting provider {}", key);
+
+        let binary_path = self
+            .ensure_provider(&launch.source, &launch.version_constraint)
+            .await?;
+        let conn = ProviderConnection::start_with(&binary_path, &self.start_options)
+            .await
+            .map_err(|err| OxidError::ProviderStart {
+                provider: key.clone(),
+                source: err.into(),
+            })?;
+        // Schemas are cached per process, and providers must see GetSchema
+        // before they are configured.
+        self.limit_schema_fetch(conn.get_schema()).await?;
+        if let Some(config) = &launch.config {
+            conn.configure("oxid", config)
+                .await
+                .with_context(|| format!("Failed to reconfigure restarted provider {}", key))?;
+        }
+
+        let conn = Arc::new(conn);
+        let mut conns = self.connections.write().await;
+        match conns.get(&key) {
+            Some(current) if Arc::ptr_eq(current, dead) => {
+                conns.insert(key.clone(), conn.clone());
+                drop(conns);
+                self.track_pid(&key, &conn).await;
+                Ok(conn)
+            }
+            _ => {
+                // Stopped while restarting; don't bring it back.
+                drop(conns);
+                conn.stop().await?;
+                bail!("Provider {} was stopped while restarting", key)
+            }
+        }
+    }
+
+    /// Record the process of the provider started under `key`. Failing to
+    /// write the registry only costs `oxid providers reap` its entry.
+    async fn track_pid(&self, key: &str, conn: &ProviderConnection) {
+        let Some(pid) = conn.pid().await else {
+            return;
+        };
+        let process = ProviderProcess {
+            pid,
+            provider: key.to_string(),
+            owner: std::process::id(),
+            started: start_time(pid),
+        };
+        if let Err(e) = self.pids.add(process) {
+            warn!("Failed to record provider {} (pid {}): {:#}", key, pid, e);
+        }
+    }
+
+    /// The registry of provider processes started from this cache directory.
+    pub fn pid_registry(&self) -> &PidRegistry {
+        &self.pids
+    }
+
+    /// OS process id of the running provider at `address`, if any.
+    pub async fn provider_pid(&self, address: &str) -> Result<Option<u32>> {
+        Ok(self.connection(address).await?.pid().await)
+    }
+
     /// Execute a plan for a single resource.
-    /// Uses read lock — multiple plans can run concurrently.
+    /// Multiple plans can run concurrently against the same provider.
     pub async fn plan_resource(
         &self,
         source: &str,
@@ -172,22 +651,24 @@ impl ProviderManager {
         prior_state: Option<&serde_json::Value>,
         proposed_new_state: Option<&serde_json::Value>,
         config: &serde_json::Value,
+        prior_private: &[u8],
     ) -> Result<super::protocol::PlanResult> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
-
-        let conns = self.connections.read().await;
-        let conn = conns.get(&key).context(format!(
-            "Provider {} not connected. Call get_connection first.",
-            key
-        ))?;
-
-        conn.plan_resource_change(type_name, prior_state, proposed_new_state, config)
+        self.call(source, true, |conn| async move {
+            conn.plan_resource_change(
+                type_name,
+                prior_state,
+                proposed_new_state,
+                config,
+                prior_private,
+            )
             .await
+        })
+        .await
     }
 
     /// Execute an apply for a single resource.
-    /// Uses read lock — multiple applies can run concurrently.
+    /// Multiple applies can run concurrently against the same provider.
+    #[allow(clippy::too_many_arguments)]
     pub async fn apply_resource(
         &self,
         source: &str,
@@ -196,22 +677,19 @@ impl ProviderManager {
         planned_state: Option<&serde_json::Value>,
         config: &serde_json::Value,
         planned_private: &[u8],
+        timeout: std::time::Duration,
     ) -> Result<super::protocol::ApplyResult> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
-
-        let conns = self.connections.read().await;
-        let conn = conns
-            .get(&key)
-            .context(format!("Provider {} not connected", key))?;
-
-        conn.apply_resource_change(
-            type_name,
-            prior_state,
-            planned_state,
-            config,
-            planned_private,
-        )
+        self.call(source, false, |conn| async move {
+            conn.apply_resource_change(
+                type_name,
+                prior_state,
+                planned_state,
+                config,
+                planned_private,
+                timeout,
+            )
+            .await
+        })
         .await
     }
 
@@ -221,16 +699,12 @@ impl ProviderManager {
         source: &str,
         type_name: &str,
         current_state: &serde_json::Value,
-    ) -> Result<Option<serde_json::Value>> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
-
-        let conns = self.connections.read().await;
-        let conn = conns
-            .get(&key)
-            .context(format!("Provider {} not connected", key))?;
-
-        conn.read_resource(type_name, current_state).await
+        private: &[u8],
+    ) -> Result<super::protocol::ReadResult> {
+        self.call(source, true, |conn| async move {
+            conn.read_resource(type_name, current_state, private).await
+        })
+        .await
     }
 
     /// Read a data source.
@@ -240,15 +714,24 @@ impl ProviderManager {
         type_name: &str,
         config: &serde_json::Value,
     ) -> Result<serde_json::Value> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
-
-        let conns = self.connections.read().await;
-        let conn = conns
-            .get(&key)
-            .context(format!("Provider {} not connected", key))?;
+        self.call(source, true, |conn| async move {
+            conn.read_data_source(type_name, config).await
+        })
+        .await
+    }
 
-        conn.read_data_source(type_name, config).await
+    /// Import an existing resource by its provider-specific ID.
+    /// A single ID may yield several resources (e.g. a security group and its rules).
+    pub async fn import_resource(
+        &self,
+        source: &str,
+        type_name: &str,
+        id: &str,
+    ) -> Result<Vec<super::protocol::ImportedResource>> {
+        self.call(source, true, |conn| async move {
+            conn.import_resource(type_name, id).await
+        })
+        .await
     }
 
     /// Get the schema for a specific resource type.
@@ -257,13 +740,7 @@ impl ProviderManager {
         source: &str,
         type_name: &str,
     ) -> Result<Option<serde_json::Value>> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
-
-        let conns = self.connections.read().await;
-        let conn = conns
-            .get(&key)
-            .context(format!("Provider {} not connected", key))?;
+        let conn = self.connection(source).await?;
 
         Ok(conn.get_resource_schema(type_name))
     }
@@ -274,50 +751,59 @@ impl ProviderManager {
         source: &str,
         type_name: &str,
     ) -> Result<Option<serde_json::Value>> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
-
-        let conns = self.connections.read().await;
-        let conn = conns
-            .get(&key)
-            .context(format!("Provider {} not connected", key))?;
+        let conn = self.connection(source).await?;
 
         Ok(conn.get_data_source_schema(type_name))
     }
 
-    /// Configure a running provider. Needs write lock (mutates connection state).
+    /// Configure a running provider. The pool lock is not held during the
+    /// RPC, so configuring one provider does not stall calls to others.
+    /// The config is kept so the provider can be reconfigured if it has to
+    /// be restarted after a crash.
     pub async fn configure_provider(&self, source: &str, config: &serde_json::Value) -> Result<()> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
-
-        let mut conns = self.connections.write().await;
-        let conn = conns
-            .get_mut(&key)
-            .context(format!("Provider {} not connected", key))?;
-
-        conn.configure("oxid", config).await
+        self.call(source, true, |conn| async move {
+            conn.configure("oxid", config).await
+        })
+        .await?;
+        let key = connection_key(source)?;
+        self.launches.lock().await.entry(key).or_default().config = Some(config.clone());
+        Ok(())
     }
 
-    /// Stop all running providers.
+    /// Stop all running providers. The pool is drained first, so no new
+    /// RPCs reach a provider while it is shutting down.
     pub async fn stop_all(&self) -> Result<()> {
-        let mut conns = self.connections.write().await;
-        for (key, mut conn) in conns.drain() {
+        let drained: Vec<_> = self.connections.write().await.drain().collect();
+        self.launches.lock().await.clear();
+        for (key, conn) in drained {
             info!("Stopping provider {}", key);
             if let Err(e) = conn.stop().await {
                 tracing::error!("Failed to stop provider {}: {}", key, e);
             }
         }
+        if let Err(e) = self.pids.clear_owner(std::process::id()) {
+            warn!("Failed to clear the provider process registry: {:#}", e);
+        }
         Ok(())
     }
 
     /// Stop a specific provider.
     pub async fn stop_provider(&self, source: &str) -> Result<()> {
-        let (namespace, provider_type) = RegistryClient::parse_source(source)?;
-        let key = format!("{}/{}", namespace, provider_type);
+        let key = connection_key(source)?;
 
-        let mut conns = self.connections.write().await;
-        if let Some(mut conn) = conns.remove(&key) {
+        let conn = self.connections.write().await.remove(&key);
+        self.launches.lock().await.remove(&key);
+        if let Some(conn) = conn {
+            let pid = conn.pid().await;
             conn.stop().await?;
+            if let Some(pid) = pid {
+                if let Err(e) = self.pids.remove(pid) {
+                    warn!(
+                        "Failed to unregister provider {} (pid {}): {:#}",
+                        key, pid, e
+                    );
+                }
+            }
         }
         Ok(())
     }
@@ -329,6 +815,99 @@ impl ProviderManager {
     }
 }
 
+/// Whether an RPC that failed in transport was caused by the provider process
+/// dying. The broken channel can surface before the exit does, so the exit
+/// gets a moment.
+async fn exited_after_error(conn: &ProviderConnection) -> bool {
+    for _ in 0..5 {
+        if conn.has_exited().await {
+            return true;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    false
+}
+
+/// Progress bar style for registry downloads: bytes received of the total.
+fn download_style() -> ProgressStyle {
+    ProgressStyle::with_template("  {prefix:.bold} [{bar:30}] {bytes}/{total_bytes}")
+        .expect("valid progress template")
+        .progress_chars("=> ")
+}
+
+/// A hostname-qualified source is served by that host's registry; `None`
+/// means the manager's own registry.
+fn host_registry(source: &str) -> Option<RegistryClient> {
+    match RegistryClient::source_host(source) {
+        Some(host) if host != DEFAULT_REGISTRY_HOST => {
+            Some(RegistryClient::with_base_url(&format!("https://{}", host)))
+        }
+        _ => None,
+    }
+}
+
+/// Refuse to install an archive whose checksum the lock file doesn't list.
+fn check_locked_archive(entry: &LockedProvider, key: &str, sum: &str, archive: &str) -> Result<()> {
+    if !entry.allows_archive(sum) {
+        bail!(
+            "Checksum of {} ({}) for {} {} is not in the lock file. Run 'oxid providers lock' if the provider was intentionally changed.",
+            archive,
+            LockedProvider::zip_hash(sum),
+            key,
+            entry.version
+        );
+    }
+    Ok(())
+}
+
+/// Refuse to use an installed or unpacked package whose `h1:` hash the lock
+/// file doesn't list.
+fn check_locked_package(entry: &LockedProvider, key: &str, dir: &Path) -> Result<()> {
+    let hash = package_hash(dir)?;
+    if !entry.allows_package(&hash) {
+        bail!(
+            "Checksum of {} ({}) for {} {} is not in the lock file. Run 'oxid providers lock' if the provider was intentionally changed.",
+            dir.display(),
+            hash,
+            key,
+            entry.version
+        );
+    }
+    Ok(())
+}
+
+/// The address of a provider instance: `source` for the default configuration,
+/// `source.alias` for an aliased one (e.g. `hashicorp/aws.west`).
+pub fn provider_address(source: &str, alias: Option<&str>) -> String {
+    match alias {
+        Some(alias) => format!("{}.{}", source, alias),
+        None => source.to_string(),
+    }
+}
+
+/// Split a provider address into its source and alias. Provider types never
+/// contain dots, so only a dot in the last path segment starts an alias.
+pub fn split_provider_alias(address: &str) -> (&str, Option<&str>) {
+    let type_start = address.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match address[type_start..].find('.') {
+        Some(dot) => (
+            &address[..type_start + dot],
+            Some(&address[type_start + dot + 1..]),
+        ),
+        None => (address, None),
+    }
+}
+
+/// Connection map key for a provider address: "namespace/type[.alias]".
+fn connection_key(address: &str) -> Result<String> {
+    let (source, alias) = split_provider_alias(address);
+    let (namespace, provider_type) = RegistryClient::parse_source(source)?;
+    Ok(provider_address(
+        &format!("{}/{}", namespace, provider_type),
+        alias,
+    ))
+}
+
 impl Drop for ProviderManager {
     fn drop(&mut self) {
         // Best-effort cleanup — child processes are killed on drop anyway
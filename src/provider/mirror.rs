@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use super::registry::{Platform, DEFAULT_REGISTRY_HOST};
+use super::version::VersionConstraint;
+
+/// Path of the CLI configuration file, overriding `~/.oxid.hcl`.
+pub const CLI_CONFIG_ENV: &str = "OXID_CLI_CONFIG_FILE";
+/// A filesystem mirror directory, used before any configured mirrors.
+pub const PROVIDER_MIRROR_ENV: &str = "OXID_PROVIDER_MIRROR";
+/// Comma-separated `SOURCE=BINARY` pairs, added to the configured
+/// `dev_overrides`.
+pub const DEV_OVERRIDES_ENV: &str = "OXID_DEV_OVERRIDES";
+
+/// How providers are installed, from a `provider_installation` block:
+///
+/// ```hcl
+/// provider_installation {
+///   filesystem_mirror {
+///     path    = "/opt/oxid/providers"
+///     include = ["registry.terraform.io/hashicorp/*"]
+///   }
+///   direct {
+///     exclude = ["registry.terraform.io/hashicorp/*"]
+///   }
+/// }
+/// ```
+///
+/// Without the block every provider is downloaded from its registry. With it,
+/// mirrors are searched in order and the network is only used for providers a
+/// `direct` block allows.
+///
+/// A top-level `dev_overrides` map points sources at locally built binaries,
+/// bypassing installation and version checks entirely:
+///
+/// ```hcl
+/// dev_overrides = {
+///   "registry.terraform.io/hashicorp/foo" = "/home/me/go/bin/terraform-provider-foo"
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProviderInstallation {
+    pub mirrors: Vec<FilesystemMirror>,
+    /// `None` when network installation is disabled.
+    pub direct: Option<InstallMethodFilter>,
+    /// Provider binaries keyed by fully qualified source.
+    pub dev_overrides: HashMap<String, PathBuf>,
+}
+
+/// `include`/`exclude` patterns of an installation method. Patterns are
+/// `hostname/namespace/type`, each part a name or `*`.
+#[derive(Debug, Clone, Default)]
+pub struct InstallMethodFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FilesystemMirror {
+    pub path: PathBuf,
+    pub filter: InstallMethodFilter,
+}
+
+/// A provider package found in a mirror.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MirrorPackage {
+    /// `HOST/NS/TYPE/terraform-provider-TYPE_VERSION_OS_ARCH.zip`
+    Packed(PathBuf),
+    /// `HOST/NS/TYPE/VERSION/OS_ARCH/terraform-provider-TYPE...`
+    Unpacked(PathBuf),
+}
+
+impl Default for ProviderInstallation {
+    fn default() -> Self {
+        Self {
+            mirrors: Vec::new(),
+            direct: Some(InstallMethodFilter::default()),
+            dev_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl ProviderInstallation {
+    /// Load from `$OXID_CLI_CONFIG_FILE` or `~/.oxid.hcl`, then put
+    /// `$OXID_PROVIDER_MIRROR` (if set) in front of the configured mirrors
+    /// and add `$OXID_DEV_OVERRIDES`.
+    pub fn load() -> Result<Self> {
+        let config_path = std::env::var_os(CLI_CONFIG_ENV)
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".oxid.hcl")));
+        let mut installation = match config_path {
+            Some(path) if path.exists() => {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                Self::parse(&content).with_context(|| format!("Invalid {}", path.display()))?
+            }
+            _ => Self::default(),
+        };
+        if let Some(dir) = std::env::var_os(PROVIDER_MIRROR_ENV) {
+            installation.mirrors.insert(
+                0,
+                FilesystemMirror {
+                    path: PathBuf::from(dir),
+                    filter: InstallMethodFilter::default(),
+                },
+            );
+        }
+        if let Ok(overrides) = std::env::var(DEV_OVERRIDES_ENV) {
+            for pair in overrides.split(',').filter(|p| !p.trim().is_empty()) {
+                let (source, binary) = pair.split_once('=').with_context(|| {
+                    format!(
+                        "{} entries must be SOURCE=BINARY, got \"{}\"",
+                        DEV_OVERRIDES_ENV, pair
+                    )
+                })?;
+                installation.add_dev_override(source.trim(), PathBuf::from(binary.trim()));
+            }
+        }
+        Ok(installation)
+    }
+
+    /// Parse the `provider_installation` block and `dev_overrides` map of a
+    /// CLI configuration file.
+    pub fn parse(content: &str) -> Result<Self> {
+        let body: hcl::Body = hcl::from_str(content).context("Failed to parse HCL")?;
+        let dev_overrides = parse_dev_overrides(&body)?;
+        let Some(block) = body
+            .blocks()
+            .find(|b| b.identifier() == "provider_installation")
+        else {
+            return Ok(Self {
+                dev_overrides,
+                ..Self::default()
+            });
+        };
+
+        let mut installation = Self {
+            mirrors: Vec::new(),
+            direct: None,
+            dev_overrides,
+        };
+        for method in block.body().blocks() {
+            let filter = InstallMethodFilter {
+                include: string_list(method.body(), "include")?,
+                exclude: string_list(method.body(), "exclude")?,
+            };
+            match method.identifier() {
+                "filesystem_mirror" => {
+                    let path = method
+                        .body()
+                        .attributes()
+                        .find(|a| a.key() == "path")
+                        .and_then(|a| match a.expr() {
+                            hcl::Expression::String(s) => Some(s.clone()),
+                            _ => None,
+                        })
+                        .context("filesystem_mirror requires a \"path\" string")?;
+                    installation.mirrors.push(FilesystemMirror {
+                        path: PathBuf::from(path),
+                        filter,
+                    });
+                }
+                "direct" => installation.direct = Some(filter),
+                other => bail!("Unsupported provider installation method \"{}\"", other),
+            }
+        }
+        Ok(installation)
+    }
+
+    pub fn add_dev_override(&mut self, source: &str, binary: PathBuf) {
+        self.dev_overrides
+            .insert(qualified_source(source).to_ascii_lowercase(), binary);
+    }
+
+    /// The locally built binary configured for `source`, if any.
+    pub fn dev_override(&self, source: &str) -> Option<&Path> {
+        self.dev_overrides
+            .get(&qualified_source(source).to_ascii_lowercase())
+            .map(PathBuf::as_path)
+    }
+
+    /// Whether `source` may be downloaded from its registry.
+    pub fn allows_network(&self, source: &str) -> bool {
+        self.direct.as_ref().is_some_and(|d| d.allows(source))
+    }
+
+    /// The highest version of `source` satisfying `constraint` for `platform`
+    /// across all mirrors that include it.
+    pub fn find(
+        &self,
+        source: &str,
+        constraint: &VersionConstraint,
+        platform: &Platform,
+    ) -> Result<Option<(String, MirrorPackage)>> {
+        let mut found: Vec<(String, MirrorPackage)> = Vec::new();
+        for mirror in self.mirrors.iter().filter(|m| m.filter.allows(source)) {
+            found.extend(mirror.packages(source, platform)?);
+        }
+        let Some(best) = constraint
+            .highest(found.iter().map(|(v, _)| v.as_str()))
+            .map(str::to_string)
+        else {
+            return Ok(None);
+        };
+        Ok(found.into_iter().find(|(v, _)| *v == best))
+    }
+}
+
+impl InstallMethodFilter {
+    /// Whether `source` passes the filter. An empty `include` list includes
+    /// everything; `exclude` wins over `include`.
+    pub fn allows(&self, source: &str) -> bool {
+        let address = qualified_source(source);
+        let matches = |pattern: &String| pattern_matches(pattern, &address);
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+impl FilesystemMirror {
+    /// Packages of `source` in this mirror built for `platform`, in either
+    /// the packed or unpacked layout.
+    pub fn packages(
+        &self,
+        source: &str,
+        platform: &Platform,
+    ) -> Result<Vec<(String, MirrorPackage)>> {
+        let address = qualified_source(source);
+        let provider_dir = self.path.join(&address);
+        if !provider_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let provider_type = address.rsplit('/').next().unwrap_or_default();
+        let zip_prefix = format!("terraform-provider-{}_", provider_type);
+        let zip_suffix = format!("_{}.zip", platform);
+
+        let mut packages = Vec::new();
+        for entry in std::fs::read_dir(&provider_dir)
+            .with_context(|| format!("Failed to read mirror {}", provider_dir.display()))?
+        {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            if path.is_dir() {
+                let target = path.join(platform.to_string());
+                if target.is_dir() {
+                    packages.push((name, MirrorPackage::Unpacked(target)));
+                }
+            } else if let Some(version) = name
+                .strip_prefix(&zip_prefix)
+                .and_then(|rest| rest.strip_suffix(&zip_suffix))
+            {
+                packages.push((version.to_string(), MirrorPackage::Packed(path)));
+            }
+        }
+        Ok(packages)
+    }
+}
+
+/// `hashicorp/aws` → `registry.terraform.io/hashicorp/aws`.
+pub fn qualified_source(source: &str) -> String {
+    if source.split('/').count() == 2 {
+        format!("{}/{}", DEFAULT_REGISTRY_HOST, source)
+    } else {
+        source.to_string()
+    }
+}
+
+fn pattern_matches(pattern: &str, address: &str) -> bool {
+    let pattern = qualified_source(pattern);
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let address: Vec<&str> = address.split('/').collect();
+    pattern.len() == address.len()
+        && pattern
+            .iter()
+            .zip(&address)
+            .all(|(p, a)| *p == "*" || p.eq_ignore_ascii_case(a))
+}
+
+fn parse_dev_overrides(body: &hcl::Body) -> Result<HashMap<String, PathBuf>> {
+    let mut overrides = HashMap::new();
+    let Some(attr) = body.attributes().find(|a| a.key() == "dev_overrides") else {
+        return Ok(overrides);
+    };
+    let hcl::Expression::Object(entries) = attr.expr() else {
+        bail!("\"dev_overrides\" must be a map of provider source to binary path");
+    };
+    for (key, value) in entries {
+        let source = match key {
+            hcl::ObjectKey::Identifier(id) => id.to_string(),
+            hcl::ObjectKey::Expression(hcl::Expression::String(s)) => s.clone(),
+            _ => bail!("\"dev_overrides\" keys must be provider sources"),
+        };
+        let hcl::Expression::String(binary) = value else {
+            bail!("dev_overrides[\"{}\"] must be a binary path string", source);
+        };
+        overrides.insert(
+            qualified_source(&source).to_ascii_lowercase(),
+            PathBuf::from(binary),
+        );
+    }
+    Ok(overrides)
+}
+
+fn string_list(body: &hcl::Body, key: &str) -> Result<Vec<String>> {
+    let Some(attr) = body.attributes().find(|a| a.key() == key) else {
+        return Ok(Vec::new());
+    };
+    match attr.expr() {
+        hcl::Expression::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                hcl::Expression::String(s) => Ok(s.clone()),
+                _ => bail!("\"{}\" must be a list of strings", key),
+            })
+            .collect(),
+        _ => bail!("\"{}\" must be a list of strings", key),
+    }
+}
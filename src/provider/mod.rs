@@ -1,7 +1,12 @@
 pub mod cache;
+pub mod lockfile;
 pub mod manager;
+pub mod mirror;
+pub mod mtls;
+pub mod pids;
 pub mod protocol;
 pub mod registry;
+pub mod version;
 
 /// Generated gRPC types from OpenTofu plugin protocol.
 #[allow(clippy::all)]
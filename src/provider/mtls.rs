@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    KeyUsagePurpose,
+};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use tokio_rustls::TlsConnector;
+
+/// The environment variable go-plugin reads the client certificate from.
+/// Setting it asks the provider to serve gRPC over mutual TLS (AutoMTLS).
+pub const CLIENT_CERT_ENV: &str = "PLUGIN_CLIENT_CERT";
+
+/// The name go-plugin certificates are issued for.
+const CERT_NAME: &str = "localhost";
+
+/// A freshly generated, self-signed client certificate for one provider process.
+pub struct ClientCertificate {
+    /// PEM encoding, handed to the provider through [`CLIENT_CERT_ENV`].
+    pub cert_pem: String,
+    cert_der: CertificateDer<'static>,
+    key_der: Vec<u8>,
+}
+
+impl ClientCertificate {
+    /// Generate a certificate shaped like go-plugin's own: a self-signed CA
+    /// for `localhost`, usable for both client and server authentication.
+    pub fn generate() -> Result<Self> {
+        let key = KeyPair::generate().context("Failed to generate provider client key")?;
+        let mut params = CertificateParams::new(vec![CERT_NAME.to_string()])
+            .context("Invalid provider client certificate parameters")?;
+        params
+            .distinguished_name
+            .push(DnType::CommonName, CERT_NAME);
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "HashiCorp");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.key_usages = vec![
+            KeyUsagePurpose::DigitalSignature,
+            KeyUsagePurpose::KeyEncipherment,
+            KeyUsagePurpose::KeyCertSign,
+        ];
+        params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ClientAuth,
+            ExtendedKeyUsagePurpose::ServerAuth,
+        ];
+        let cert = params
+            .self_signed(&key)
+            .context("Failed to self-sign provider client certificate")?;
+
+        Ok(Self {
+            cert_pem: cert.pem(),
+            cert_der: cert.der().clone(),
+            key_der: key.serialize_der(),
+        })
+    }
+
+    /// A TLS connector that presents this certificate and only accepts the
+    /// provider's own certificate, as advertised in its handshake.
+    pub fn connector(&self, server_cert: Vec<u8>) -> Result<TlsConnector> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = PinnedServerCert {
+            cert: CertificateDer::from(server_cert),
+            provider: provider.clone(),
+        };
+        let mut config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .context("Failed to configure provider TLS")?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_client_auth_cert(
+                vec![self.cert_der.clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(self.key_der.clone())),
+            )
+            .context("Failed to configure provider client certificate")?;
+        // gRPC servers require HTTP/2 to be negotiated over TLS
+        config.alpn_protocols = vec![b"h2".to_vec()];
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+}
+
+/// The server name to present when connecting to a provider over TLS.
+pub fn server_name() -> ServerName<'static> {
+    ServerName::try_from(CERT_NAME).expect("localhost is a valid server name")
+}
+
+/// Decode the server certificate from the sixth handshake field: the DER
+/// encoding in unpadded standard base64.
+pub fn decode_server_cert(field: &str) -> Result<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD_NO_PAD
+        .decode(field.trim_end_matches('='))
+        .context("Invalid provider certificate in handshake")
+}
+
+/// Accepts exactly the certificate the provider advertised in its handshake.
+///
+/// go-plugin certificates are self-signed CAs, which regular path validation
+/// refuses as end-entity certificates, so the certificate is pinned instead.
+#[derive(Debug)]
+struct PinnedServerCert {
+    cert: CertificateDer<'static>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedServerCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if end_entity.as_ref() == self.cert.as_ref() {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "provider presented a certificate other than the one in its handshake".into(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
@@ -0,0 +1,257 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Name of the registry file, kept in the provider cache directory.
+pub const PID_REGISTRY_FILE: &str = "pids.json";
+
+/// How long a terminated provider gets to exit before it is killed.
+const REAP_GRACE: Duration = Duration::from_secs(2);
+
+/// A provider process started by an oxid run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderProcess {
+    pub pid: u32,
+    /// Connection key of the provider (`hashicorp/aws`, `hashicorp/aws.west`).
+    pub provider: String,
+    /// Process id of the oxid run that started the provider.
+    pub owner: u32,
+    /// Start time of the process (see [`start_time`]), so a reused pid is
+    /// never mistaken for the provider.
+    #[serde(default)]
+    pub started: Option<u64>,
+}
+
+/// What `oxid providers reap` did with a registered process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReapOutcome {
+    /// Still running after its oxid run was gone, so it was terminated.
+    Terminated,
+    /// The process had already exited.
+    Exited,
+    /// The pid now belongs to a different process; left alone.
+    Reused,
+    /// The oxid run that started it is still running; left alone.
+    InUse,
+}
+
+/// Provider processes started by oxid runs, recorded in
+/// `<cache>/pids.json` so processes left behind by a crashed run can be
+/// found and stopped by a later `oxid providers reap`.
+///
+/// Entries are removed when their provider is stopped normally. Process ids
+/// can be reused by the OS, so an entry whose oxid run is still alive is
+/// never touched, and a process is only signalled if its start time still
+/// matches the entry.
+///
+/// Read-modify-write cycles hold an exclusive lock on `pids.json.lock`, so
+/// concurrent oxid runs don't lose each other's entries, and the registry is
+/// replaced atomically, so a reader never sees a half-written file.
+pub struct PidRegistry {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles within this process.
+    lock: Mutex<()>,
+}
+
+impl PidRegistry {
+    /// The registry in provider cache directory `dir`.
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            path: dir.join(PID_REGISTRY_FILE),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every registered process; a missing registry is empty.
+    pub fn load(&self) -> Result<Vec<ProviderProcess>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", self.path.display()))
+    }
+
+    fn save(&self, processes: &[ProviderProcess]) -> Result<()> {
+        if processes.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(e).with_context(|| format!("Failed to remove {}", self.path.display()))
+                }
+                _ => Ok(()),
+            };
+        }
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let mut file = tempfile::NamedTempFile::new_in(dir)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        serde_json::to_writer_pretty(&mut file, processes)?;
+        file.persist(&self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Hold the registry lock, across processes, for the duration of `f`.
+    fn locked<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let _guard = self.lock.lock().unwrap();
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let lock_path = self.path.with_extension("json.lock");
+        let lock_file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        lock_exclusive(&lock_file)
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+        // The lock is released when `lock_file` is closed
+        f()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Vec<ProviderProcess>)) -> Result<()> {
+        self.locked(|| {
+            let mut processes = self.load()?;
+            f(&mut processes);
+            self.save(&processes)
+        })
+    }
+    /// Record `process`, replacing an earlier process of the same provider
+    /// started by the same run (one that was restarted).
+    pub fn add(&self, process: ProviderProcess) -> Result<()> {
+        self.update(|processes| {
+            processes.retain(|p| !(p.owner == process.owner && p.provider == process.provider));
+            processes.push(process);
+        })
+    }
+
+    /// Forget process `pid`.
+    pub fn remove(&self, pid: u32) -> Result<()> {
+        self.update(|processes| processes.retain(|p| p.pid != pid))
+    }
+
+    /// Forget every process started by run `owner`.
+    pub fn clear_owner(&self, owner: u32) -> Result<()> {
+        self.update(|processes| processes.retain(|p| p.owner != owner))
+    }
+
+    /// Terminate every registered process whose oxid run is gone, and
+    /// forget it. Processes of runs still alive are kept.
+    pub fn reap(&self) -> Result<Vec<(ProviderProcess, ReapOutcome)>> {
+        self.locked(|| {
+            let current = std::process::id();
+            let mut reaped = Vec::new();
+            let mut kept = Vec::new();
+            for process in self.load()? {
+                let outcome = if process.owner != current && is_running(process.owner) {
+                    kept.push(process.clone());
+                    ReapOutcome::InUse
+                } else if !is_running(process.pid) {
+                    ReapOutcome::Exited
+                } else if start_time(process.pid) != process.started {
+                    ReapOutcome::Reused
+                } else {
+                    terminate(process.pid);
+                    ReapOutcome::Terminated
+                };
+                reaped.push((process, outcome));
+            }
+            self.save(&kept)?;
+            Ok(reaped)
+        })
+    }
+}
+
+/// Process ids that can safely be signalled: `kill` treats 0, -1 and other
+/// negative ids as process groups, and 1 is init.
+#[cfg(unix)]
+fn signallable(pid: u32) -> Option<libc::pid_t> {
+    libc::pid_t::try_from(pid).ok().filter(|&pid| pid > 1)
+}
+
+/// Whether process `pid` exists and has not exited.
+#[cfg(unix)]
+pub fn is_running(pid: u32) -> bool {
+    let Some(pid) = signallable(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks that the process exists.
+    let exists = unsafe { libc::kill(pid, 0) } == 0
+        || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+    exists && !is_zombie(pid)
+}
+
+#[cfg(not(unix))]
+pub fn is_running(_pid: u32) -> bool {
+    false
+}
+
+/// Start time of process `pid` in clock ticks since boot (field 22 of
+/// `/proc/<pid>/stat`), or `None` where that is unavailable.
+pub fn start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // Fields after the parenthesized command name start at field 3
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(22 - 3)?.parse().ok()
+}
+
+#[cfg(unix)]
+fn lock_exclusive(file: &std::fs::File) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: `file` is an open descriptor for the duration of the call.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn lock_exclusive(_file: &std::fs::File) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Whether `pid` has exited and only waits for its parent to collect it.
+#[cfg(unix)]
+fn is_zombie(pid: libc::pid_t) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| {
+            let (_, rest) = stat.rsplit_once(')')?;
+            Some(rest.trim_start().starts_with('Z'))
+        })
+        .unwrap_or(false)
+}
+
+/// Send SIGTERM, then SIGKILL if the process outlives [`REAP_GRACE`].
+#[cfg(unix)]
+fn terminate(pid: u32) {
+    let Some(target) = signallable(pid) else {
+        return;
+    };
+    // SAFETY: `target` is a single process id, never a process group.
+    unsafe { libc::kill(target, libc::SIGTERM) };
+    let deadline = Instant::now() + REAP_GRACE;
+    while Instant::now() < deadline {
+        if !is_running(pid) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    // SAFETY: as above.
+    unsafe { libc::kill(target, libc::SIGKILL) };
+}
+
+#[cfg(not(unix))]
+fn terminate(_pid: u32) {}
@@ -1,31 +1,135 @@
 use std::path::Path;
 use std::process::Stdio;
+use std::sync::OnceLock;
 
 use anyhow::{bail, Context, Result};
-use tokio::io::AsyncBufReadExt;
-use tokio::net::{TcpListener, UnixStream};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, UnixStream};
 use tokio::process::{Child, Command};
-use tonic::transport::Channel;
+use tokio::sync::Mutex;
+use tokio_rustls::TlsConnector;
+use tonic::transport::{Channel, Endpoint};
 use tracing::{debug, info, warn};
 
+use super::mtls::{self, ClientCertificate, CLIENT_CERT_ENV};
 use super::tfplugin5::provider_client::ProviderClient as V5Client;
 use super::tfplugin6::provider_client::ProviderClient as V6Client;
 use super::ProtocolVersion;
+use crate::error::OxidError;
 
 /// The go-plugin handshake magic cookie.
 const MAGIC_COOKIE_KEY: &str = "TF_PLUGIN_MAGIC_COOKIE";
 const MAGIC_COOKIE_VALUE: &str = "d602bf8f470bc67ca7faa0386276bbdd4330efaf76d1a219cb4d6991ca9872b2";
 
+/// Seconds to wait for a provider's handshake line, overriding the default.
+pub const START_TIMEOUT_ENV: &str = "OXID_PROVIDER_START_TIMEOUT";
+/// Lowest port a provider may listen on.
+pub const MIN_PORT_ENV: &str = "OXID_PLUGIN_MIN_PORT";
+/// Highest port a provider may listen on.
+pub const MAX_PORT_ENV: &str = "OXID_PLUGIN_MAX_PORT";
+
+/// How provider processes are started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderStartOptions {
+    /// How long a provider may take to print its handshake. Large providers
+    /// on a cold filesystem cache can take well over the default.
+    pub handshake_timeout: std::time::Duration,
+    /// The port range passed to providers that listen on TCP.
+    pub min_port: u16,
+    pub max_port: u16,
+    /// How often to send an HTTP/2 ping on the provider channel, even while
+    /// no RPC is in flight, so proxies and idle timeouts don't silently drop
+    /// the connection during a long apply.
+    pub keep_alive_interval: std::time::Duration,
+    /// How long to wait for a ping acknowledgement before treating the
+    /// connection as dead.
+    pub keep_alive_timeout: std::time::Duration,
+}
+
+impl Default for ProviderStartOptions {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: std::time::Duration::from_secs(30),
+            min_port: 10000,
+            max_port: 25000,
+            keep_alive_interval: std::time::Duration::from_secs(30),
+            keep_alive_timeout: std::time::Duration::from_secs(20),
+        }
+    }
+}
+
+impl ProviderStartOptions {
+    /// Defaults overridden by `$OXID_PROVIDER_START_TIMEOUT`,
+    /// `$OXID_PLUGIN_MIN_PORT` and `$OXID_PLUGIN_MAX_PORT`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Like [`Self::from_env`], reading variables through `lookup`.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{} must be a number, got \"{}\"", name, value))
+        }
+        let mut options = Self::default();
+        if let Some(secs) = lookup(START_TIMEOUT_ENV) {
+            options.handshake_timeout =
+                std::time::Duration::from_secs(parse(START_TIMEOUT_ENV, &secs)?);
+        }
+        if let Some(port) = lookup(MIN_PORT_ENV) {
+            options.min_port = parse(MIN_PORT_ENV, &port)?;
+        }
+        if let Some(port) = lookup(MAX_PORT_ENV) {
+            options.max_port = parse(MAX_PORT_ENV, &port)?;
+        }
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// The gRPC endpoint for a provider listening at `address`, with large
+    /// flow-control windows and HTTP/2 keep-alive pings.
+    pub fn endpoint(&self, address: &str) -> Result<Endpoint> {
+        Ok(Channel::from_shared(address.to_string())
+            .context("Invalid provider endpoint")?
+            .initial_stream_window_size((1 << 31) - 1)
+            .initial_connection_window_size((1 << 31) - 1)
+            .http2_keep_alive_interval(self.keep_alive_interval)
+            .keep_alive_timeout(self.keep_alive_timeout)
+            .keep_alive_while_idle(true))
+    }
+
+    /// Reject a zero timeout or an empty port range.
+    pub fn validate(&self) -> Result<()> {
+        if self.handshake_timeout.is_zero() {
+            bail!("The provider start timeout must be at least one second");
+        }
+        if self.min_port > self.max_port {
+            bail!(
+                "The provider port range {}-{} is empty",
+                self.min_port,
+                self.max_port
+            );
+        }
+        Ok(())
+    }
+}
+
 /// A connected provider instance wrapping the gRPC client.
+///
+/// All RPCs take `&self`: clients are cloned per call (sharing one HTTP/2
+/// channel), so a connection can be shared behind an `Arc` by concurrent
+/// callers.
 pub struct ProviderConnection {
     pub protocol_version: ProtocolVersion,
     v5_client: Option<V5Client<Channel>>,
     v6_client: Option<V6Client<Channel>>,
-    child: Child,
+    child: Mutex<Child>,
     /// Cached schema type names for resource_types()/data_source_types().
-    schemas: Option<SchemaCache>,
+    schemas: OnceLock<SchemaCache>,
     /// Full schema as JSON for external caching.
-    schema_json: Option<serde_json::Value>,
+    schema_json: OnceLock<serde_json::Value>,
 }
 
 /// Cached schema info extracted from either v5 or v6 GetSchema responses.
@@ -38,18 +142,27 @@ struct SchemaCache {
 impl ProviderConnection {
     /// Start a provider binary and establish a gRPC connection.
     pub async fn start(binary_path: &Path) -> Result<Self> {
+        Self::start_with(binary_path, &ProviderStartOptions::default()).await
+    }
+
+    /// Like [`Self::start`], with the handshake timeout and port range in
+    /// `options`.
+    pub async fn start_with(binary_path: &Path, options: &ProviderStartOptions) -> Result<Self> {
         info!("Starting provider: {}", binary_path.display());
+        check_provider_binary(binary_path)?;
+        let client_cert = ClientCertificate::generate()?;
 
         let mut child = Command::new(binary_path)
             .env(MAGIC_COOKIE_KEY, MAGIC_COOKIE_VALUE)
-            .env("PLUGIN_MIN_PORT", "10000")
-            .env("PLUGIN_MAX_PORT", "25000")
+            .env(CLIENT_CERT_ENV, &client_cert.cert_pem)
+            .env("PLUGIN_MIN_PORT", options.min_port.to_string())
+            .env("PLUGIN_MAX_PORT", options.max_port.to_string())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null())
             .kill_on_drop(true)
             .spawn()
-            .context("Failed to start provider binary")?;
+            .map_err(|e| spawn_error(binary_path, e))?;
 
         // Drain stderr in a background task to prevent the provider from blocking
         // when its log output exceeds the OS pipe buffer (typically 64KB on macOS).
@@ -110,7 +223,7 @@ impl ProviderConnection {
         let mut handshake_line = String::new();
 
         let read_result = tokio::time::timeout(
-            std::time::Duration::from_secs(30),
+            options.handshake_timeout,
             reader.read_line(&mut handshake_line),
         )
         .await;
@@ -118,7 +231,11 @@ impl ProviderConnection {
         match read_result {
             Ok(Ok(0)) => bail!("Provider exited before handshake"),
             Ok(Err(e)) => bail!("Failed to read provider handshake: {}", e),
-            Err(_) => bail!("Provider handshake timed out after 30 seconds"),
+            Err(_) => bail!(
+                "Provider handshake timed out after {:?} (raise {} or --provider-start-timeout for slow starts)",
+                options.handshake_timeout,
+                START_TIMEOUT_ENV
+            ),
             Ok(Ok(_)) => {}
         }
 
@@ -136,74 +253,10 @@ impl ProviderConnection {
             );
         };
 
-        // Connect via gRPC (supports both TCP and Unix socket)
-        // For unix sockets, we spin up a local TCP proxy because tonic's connect_with_connector
-        // doesn't properly apply h2 connection-level flow control window sizes, causing large
-        // responses (like the AWS provider's ~20MB schema) to hang indefinitely.
-        let endpoint_addr = if handshake.network_type == "unix" {
-            let socket_path = handshake.address.clone();
-            info!(
-                "Connecting to provider gRPC via unix socket: {}",
-                socket_path
-            );
+        let endpoint_addr = provider_endpoint(&handshake, &client_cert).await?;
 
-            // Bind a TCP listener on an ephemeral port
-            let tcp_listener = TcpListener::bind("127.0.0.1:0")
-                .await
-                .context("Failed to bind TCP proxy listener")?;
-            let proxy_addr = tcp_listener
-                .local_addr()
-                .context("Failed to get proxy address")?;
-            info!("TCP proxy for unix socket listening on {}", proxy_addr);
-
-            // Spawn the proxy task in the background
-            tokio::spawn(async move {
-                loop {
-                    match tcp_listener.accept().await {
-                        Ok((tcp_stream, _)) => {
-                            let path = socket_path.clone();
-                            tokio::spawn(async move {
-                                match UnixStream::connect(&path).await {
-                                    Ok(unix_stream) => {
-                                        let (mut tcp_read, mut tcp_write) =
-                                            tokio::io::split(tcp_stream);
-                                        let (mut unix_read, mut unix_write) =
-                                            tokio::io::split(unix_stream);
-
-                                        let t2u = tokio::io::copy(&mut tcp_read, &mut unix_write);
-                                        let u2t = tokio::io::copy(&mut unix_read, &mut tcp_write);
-
-                                        let _ = tokio::try_join!(t2u, u2t);
-                                    }
-                                    Err(e) => {
-                                        tracing::error!(
-                                            "Failed to connect to unix socket {}: {}",
-                                            path,
-                                            e
-                                        );
-                                    }
-                                }
-                            });
-                        }
-                        Err(e) => {
-                            tracing::error!("TCP proxy accept error: {}", e);
-                            break;
-                        }
-                    }
-                }
-            });
-
-            format!("http://{}", proxy_addr)
-        } else {
-            let endpoint = format!("http://{}", handshake.address);
-            info!("Connecting to provider gRPC at {}", endpoint);
-            endpoint
-        };
-
-        let channel = Channel::from_shared(endpoint_addr.clone())
-            .context("Invalid provider endpoint")?
-            .initial_stream_window_size((1 << 31) - 1)
-            .initial_connection_window_size((1 << 31) - 1)
+        let channel = options
+            .endpoint(&endpoint_addr)?
             .connect()
             .await
             .context("Failed to connect to provider gRPC")?;
@@ -232,16 +285,16 @@ impl ProviderConnection {
             protocol_version,
             v5_client,
             v6_client,
-            child,
-            schemas: None,
-            schema_json: None,
+            child: Mutex::new(child),
+            schemas: OnceLock::new(),
+            schema_json: OnceLock::new(),
         })
     }
 
     /// Fetch the provider schema. Returns a lightweight JSON with provider config schema
     /// and resource/data source type names.
-    pub async fn get_schema(&mut self) -> Result<serde_json::Value> {
-        if let Some(ref cached) = self.schema_json {
+    pub async fn get_schema(&self) -> Result<serde_json::Value> {
+        if let Some(cached) = self.schema_json.get() {
             return Ok(cached.clone());
         }
 
@@ -251,7 +304,7 @@ impl ProviderConnection {
 
         let schema_json = match self.protocol_version {
             ProtocolVersion::V5 => {
-                let client = self.v5_client.as_mut().context("No v5 client")?;
+                let mut client = self.v5_client.as_ref().context("No v5 client")?.clone();
                 let response = tokio::time::timeout(
                     timeout_dur,
                     client.get_schema(super::tfplugin5::get_provider_schema::Request {}),
@@ -288,7 +341,8 @@ impl ProviderConnection {
                 if provider_meta_schema.is_some() {
                     info!("Provider has provider_meta schema");
                 }
-                self.schemas = Some(SchemaCache {
+                // A concurrent fetch may have won the race; both are identical
+                let _ = self.schemas.set(SchemaCache {
                     resource_schemas,
                     data_source_schemas,
                     provider_meta_schema,
@@ -296,7 +350,7 @@ impl ProviderConnection {
                 schema_json
             }
             ProtocolVersion::V6 => {
-                let client = self.v6_client.as_mut().context("No v6 client")?;
+                let mut client = self.v6_client.as_ref().context("No v6 client")?.clone();
                 let response = tokio::time::timeout(
                     timeout_dur,
                     client.get_provider_schema(super::tfplugin6::get_provider_schema::Request {}),
@@ -330,7 +384,8 @@ impl ProviderConnection {
                     "data_source_types": data_source_types,
                 });
                 let provider_meta_schema = inner.provider_meta.as_ref().map(schema_to_json_v6);
-                self.schemas = Some(SchemaCache {
+                // A concurrent fetch may have won the race; both are identical
+                let _ = self.schemas.set(SchemaCache {
                     resource_schemas,
                     data_source_schemas,
                     provider_meta_schema,
@@ -339,13 +394,13 @@ impl ProviderConnection {
             }
         };
 
-        self.schema_json = Some(schema_json.clone());
+        let _ = self.schema_json.set(schema_json.clone());
         Ok(schema_json)
     }
 
     /// Configure the provider.
     pub async fn configure(
-        &mut self,
+        &self,
         terraform_version: &str,
         config: &serde_json::Value,
     ) -> Result<()> {
@@ -354,9 +409,9 @@ impl ProviderConnection {
 
         match self.protocol_version {
             ProtocolVersion::V5 => {
-                let client = self.v5_client.as_mut().context("No v5 client")?;
-                let config_msgpack = rmp_serde::to_vec_named(config)
-                    .context("Failed to encode config as msgpack")?;
+                let mut client = self.v5_client.as_ref().context("No v5 client")?.clone();
+                let config_msgpack =
+                    encode_msgpack(config).context("Failed to encode config as msgpack")?;
                 let request = super::tfplugin5::configure::Request {
                     terraform_version: terraform_version.to_string(),
                     config: Some(super::tfplugin5::DynamicValue {
@@ -372,9 +427,9 @@ impl ProviderConnection {
                 check_diagnostics_v5(&response.into_inner().diagnostics)?;
             }
             ProtocolVersion::V6 => {
-                let client = self.v6_client.as_mut().context("No v6 client")?;
-                let config_msgpack = rmp_serde::to_vec_named(config)
-                    .context("Failed to encode config as msgpack")?;
+                let mut client = self.v6_client.as_ref().context("No v6 client")?.clone();
+                let config_msgpack =
+                    encode_msgpack(config).context("Failed to encode config as msgpack")?;
                 let request = super::tfplugin6::configure_provider::Request {
                     terraform_version: terraform_version.to_string(),
                     config: Some(super::tfplugin6::DynamicValue {
@@ -402,6 +457,7 @@ impl ProviderConnection {
         prior_state: Option<&serde_json::Value>,
         proposed_new_state: Option<&serde_json::Value>,
         config: &serde_json::Value,
+        prior_private: &[u8],
     ) -> Result<PlanResult> {
         debug!(
             "PlanResourceChange for {}: config keys = {:?}",
@@ -426,7 +482,7 @@ impl ProviderConnection {
                         proposed_new_state.unwrap_or(&null_val),
                     )),
                     config: Some(json_to_dynamic_v5(config)),
-                    prior_private: vec![],
+                    prior_private: prior_private.to_vec(),
                     provider_meta: Some(json_to_dynamic_v5(&provider_meta_val)),
                     client_capabilities: None,
                     prior_identity: None,
@@ -441,10 +497,9 @@ impl ProviderConnection {
                             )
                         })?
                         .map_err(|e| {
-                            anyhow::anyhow!(
-                                "PlanResourceChange RPC failed for {}: {}",
-                                type_name,
-                                e
+                            rpc_error(
+                                format!("PlanResourceChange RPC failed for {}: {}", type_name, e),
+                                e,
                             )
                         })?;
                 let inner = response.into_inner();
@@ -489,7 +544,7 @@ impl ProviderConnection {
                         proposed_new_state.unwrap_or(&null_val),
                     )),
                     config: Some(json_to_dynamic_v6(config)),
-                    prior_private: vec![],
+                    prior_private: prior_private.to_vec(),
                     provider_meta: None,
                     client_capabilities: None,
                     prior_identity: None,
@@ -504,10 +559,9 @@ impl ProviderConnection {
                             )
                         })?
                         .map_err(|e| {
-                            anyhow::anyhow!(
-                                "PlanResourceChange RPC failed for {}: {}",
-                                type_name,
-                                e
+                            rpc_error(
+                                format!("PlanResourceChange RPC failed for {}: {}", type_name, e),
+                                e,
                             )
                         })?;
                 let inner = response.into_inner();
@@ -530,7 +584,11 @@ impl ProviderConnection {
         }
     }
 
-    /// Apply a resource change.
+    /// Apply a resource change, giving up after `timeout_dur`.
+    ///
+    /// Apply can take a long time — EC2 instances need ~60s to terminate, IGW detach
+    /// can take ~50s, and the provider retries operations like VPC deletion internally —
+    /// so the limit comes from the resource's `timeouts` block or the 10 minute default.
     pub async fn apply_resource_change(
         &self,
         type_name: &str,
@@ -538,12 +596,8 @@ impl ProviderConnection {
         planned_state: Option<&serde_json::Value>,
         config: &serde_json::Value,
         planned_private: &[u8],
+        timeout_dur: std::time::Duration,
     ) -> Result<ApplyResult> {
-        // Apply can take a long time — EC2 instances need ~60s to terminate, IGW detach
-        // can take ~50s, and the provider retries operations like VPC deletion internally.
-        // Use 600s (10 min) to match Terraform's default resource operation timeout.
-        let timeout_dur = std::time::Duration::from_secs(600);
-
         // Build provider_meta from schema (required by framework-based resources)
         let provider_meta_val = self.build_provider_meta();
 
@@ -566,15 +620,15 @@ impl ProviderConnection {
                         .await
                         .map_err(|_| {
                             anyhow::anyhow!(
-                                "ApplyResourceChange RPC timed out after 600s for {}",
+                                "ApplyResourceChange RPC timed out after {}s for {}",
+                                timeout_dur.as_secs(),
                                 type_name
                             )
                         })?
                         .map_err(|e| {
-                            anyhow::anyhow!(
-                                "ApplyResourceChange RPC failed for {}: {}",
-                                type_name,
-                                e
+                            rpc_error(
+                                format!("ApplyResourceChange RPC failed for {}: {}", type_name, e),
+                                e,
                             )
                         })?;
                 let inner = response.into_inner();
@@ -618,15 +672,15 @@ impl ProviderConnection {
                         .await
                         .map_err(|_| {
                             anyhow::anyhow!(
-                                "ApplyResourceChange RPC timed out after 600s for {}",
+                                "ApplyResourceChange RPC timed out after {}s for {}",
+                                timeout_dur.as_secs(),
                                 type_name
                             )
                         })?
                         .map_err(|e| {
-                            anyhow::anyhow!(
-                                "ApplyResourceChange RPC failed for {}: {}",
-                                type_name,
-                                e
+                            rpc_error(
+                                format!("ApplyResourceChange RPC failed for {}: {}", type_name, e),
+                                e,
                             )
                         })?;
                 let inner = response.into_inner();
@@ -644,11 +698,13 @@ impl ProviderConnection {
     }
 
     /// Read the current state of a resource from the provider.
+    /// `private` is the opaque data stored with the resource at its last apply.
     pub async fn read_resource(
         &self,
         type_name: &str,
         current_state: &serde_json::Value,
-    ) -> Result<Option<serde_json::Value>> {
+        private: &[u8],
+    ) -> Result<ReadResult> {
         let timeout_dur = std::time::Duration::from_secs(30);
         let provider_meta_val = self.build_provider_meta();
 
@@ -658,7 +714,7 @@ impl ProviderConnection {
                 let request = super::tfplugin5::read_resource::Request {
                     type_name: type_name.to_string(),
                     current_state: Some(json_to_dynamic_v5(current_state)),
-                    private: vec![],
+                    private: private.to_vec(),
                     provider_meta: Some(json_to_dynamic_v5(&provider_meta_val)),
                     client_capabilities: None,
                     current_identity: None,
@@ -669,21 +725,28 @@ impl ProviderConnection {
                         anyhow::anyhow!("ReadResource RPC timed out after 30s for {}", type_name)
                     })?
                     .map_err(|e| {
-                        anyhow::anyhow!("ReadResource RPC failed for {}: {}", type_name, e)
+                        rpc_error(
+                            format!("ReadResource RPC failed for {}: {}", type_name, e),
+                            e,
+                        )
                     })?;
                 let inner = response.into_inner();
                 check_diagnostics_v5(&inner.diagnostics)?;
-                inner
+                let new_state = inner
                     .new_state
                     .map(|dv| dynamic_to_json_v5(&dv))
-                    .transpose()
+                    .transpose()?;
+                Ok(ReadResult {
+                    new_state,
+                    private_data: inner.private,
+                })
             }
             ProtocolVersion::V6 => {
                 let mut client = self.v6_client.as_ref().context("No v6 client")?.clone();
                 let request = super::tfplugin6::read_resource::Request {
                     type_name: type_name.to_string(),
                     current_state: Some(json_to_dynamic_v6(current_state)),
-                    private: vec![],
+                    private: private.to_vec(),
                     provider_meta: Some(json_to_dynamic_v6(&provider_meta_val)),
                     client_capabilities: None,
                     current_identity: None,
@@ -694,14 +757,21 @@ impl ProviderConnection {
                         anyhow::anyhow!("ReadResource RPC timed out after 30s for {}", type_name)
                     })?
                     .map_err(|e| {
-                        anyhow::anyhow!("ReadResource RPC failed for {}: {}", type_name, e)
+                        rpc_error(
+                            format!("ReadResource RPC failed for {}: {}", type_name, e),
+                            e,
+                        )
                     })?;
                 let inner = response.into_inner();
                 check_diagnostics_v6(&inner.diagnostics)?;
-                inner
+                let new_state = inner
                     .new_state
                     .map(|dv| dynamic_to_json_v6(&dv))
-                    .transpose()
+                    .transpose()?;
+                Ok(ReadResult {
+                    new_state,
+                    private_data: inner.private,
+                })
             }
         }
     }
@@ -723,7 +793,10 @@ impl ProviderConnection {
                     client_capabilities: None,
                 };
                 let response = client.read_data_source(request).await.map_err(|e| {
-                    anyhow::anyhow!("ReadDataSource RPC failed for {}: {}", type_name, e)
+                    rpc_error(
+                        format!("ReadDataSource RPC failed for {}: {}", type_name, e),
+                        e,
+                    )
                 })?;
                 let inner = response.into_inner();
                 check_diagnostics_v5(&inner.diagnostics)?;
@@ -742,7 +815,10 @@ impl ProviderConnection {
                     client_capabilities: None,
                 };
                 let response = client.read_data_source(request).await.map_err(|e| {
-                    anyhow::anyhow!("ReadDataSource RPC failed for {}: {}", type_name, e)
+                    rpc_error(
+                        format!("ReadDataSource RPC failed for {}: {}", type_name, e),
+                        e,
+                    )
                 })?;
                 let inner = response.into_inner();
                 check_diagnostics_v6(&inner.diagnostics)?;
@@ -771,7 +847,10 @@ impl ProviderConnection {
                     identity: None,
                 };
                 let response = client.import_resource_state(request).await.map_err(|e| {
-                    anyhow::anyhow!("ImportResourceState RPC failed for {}: {}", type_name, e)
+                    rpc_error(
+                        format!("ImportResourceState RPC failed for {}: {}", type_name, e),
+                        e,
+                    )
                 })?;
                 let inner = response.into_inner();
                 check_diagnostics_v5(&inner.diagnostics)?;
@@ -798,7 +877,10 @@ impl ProviderConnection {
                     identity: None,
                 };
                 let response = client.import_resource_state(request).await.map_err(|e| {
-                    anyhow::anyhow!("ImportResourceState RPC failed for {}: {}", type_name, e)
+                    rpc_error(
+                        format!("ImportResourceState RPC failed for {}: {}", type_name, e),
+                        e,
+                    )
                 })?;
                 let inner = response.into_inner();
                 check_diagnostics_v6(&inner.diagnostics)?;
@@ -837,10 +919,12 @@ impl ProviderConnection {
                     .validate_resource_type_config(request)
                     .await
                     .map_err(|e| {
-                        anyhow::anyhow!(
-                            "ValidateResourceTypeConfig RPC failed for {}: {}",
-                            type_name,
-                            e
+                        rpc_error(
+                            format!(
+                                "ValidateResourceTypeConfig RPC failed for {}: {}",
+                                type_name, e
+                            ),
+                            e,
                         )
                     })?;
                 check_diagnostics_v5(&response.into_inner().diagnostics)?;
@@ -856,10 +940,9 @@ impl ProviderConnection {
                     .validate_resource_config(request)
                     .await
                     .map_err(|e| {
-                        anyhow::anyhow!(
-                            "ValidateResourceConfig RPC failed for {}: {}",
-                            type_name,
-                            e
+                        rpc_error(
+                            format!("ValidateResourceConfig RPC failed for {}: {}", type_name, e),
+                            e,
                         )
                     })?;
                 check_diagnostics_v6(&response.into_inner().diagnostics)?;
@@ -869,30 +952,44 @@ impl ProviderConnection {
     }
 
     /// Gracefully stop the provider.
-    pub async fn stop(&mut self) -> Result<()> {
-        if let Some(ref mut client) = self.v5_client {
-            let _ = client.stop(super::tfplugin5::stop::Request {}).await;
+    pub async fn stop(&self) -> Result<()> {
+        if let Some(client) = &self.v5_client {
+            let _ = client
+                .clone()
+                .stop(super::tfplugin5::stop::Request {})
+                .await;
         }
-        if let Some(ref mut client) = self.v6_client {
+        if let Some(client) = &self.v6_client {
             let _ = client
+                .clone()
                 .stop_provider(super::tfplugin6::stop_provider::Request {})
                 .await;
         }
-        let _ = self.child.kill().await;
+        let _ = self.child.lock().await.kill().await;
         Ok(())
     }
 
+    /// OS process id of the provider, or `None` once it has been reaped.
+    pub async fn pid(&self) -> Option<u32> {
+        self.child.lock().await.id()
+    }
+
+    /// Whether the provider process has exited, e.g. after a crash.
+    pub async fn has_exited(&self) -> bool {
+        matches!(self.child.lock().await.try_wait(), Ok(Some(_)))
+    }
+
     /// Get the schema for a specific resource type.
     pub fn get_resource_schema(&self, type_name: &str) -> Option<serde_json::Value> {
         self.schemas
-            .as_ref()
+            .get()
             .and_then(|s| s.resource_schemas.get(type_name).cloned())
     }
 
     /// Get the schema for a specific data source type.
     pub fn get_data_source_schema(&self, type_name: &str) -> Option<serde_json::Value> {
         self.schemas
-            .as_ref()
+            .get()
             .and_then(|s| s.data_source_schemas.get(type_name).cloned())
     }
 
@@ -901,7 +998,7 @@ impl ProviderConnection {
     fn build_provider_meta(&self) -> serde_json::Value {
         if let Some(schema) = self
             .schemas
-            .as_ref()
+            .get()
             .and_then(|s| s.provider_meta_schema.as_ref())
         {
             if let Some(block) = schema.get("block") {
@@ -924,14 +1021,14 @@ impl ProviderConnection {
     /// Get the provider_meta schema (if the provider defines one).
     pub fn get_provider_meta_schema(&self) -> Option<serde_json::Value> {
         self.schemas
-            .as_ref()
+            .get()
             .and_then(|s| s.provider_meta_schema.clone())
     }
 
     /// Get the resource types supported by this provider.
     pub fn resource_types(&self) -> Vec<String> {
         self.schemas
-            .as_ref()
+            .get()
             .map(|s| s.resource_schemas.keys().cloned().collect())
             .unwrap_or_default()
     }
@@ -939,7 +1036,7 @@ impl ProviderConnection {
     /// Get the data source types supported by this provider.
     pub fn data_source_types(&self) -> Vec<String> {
         self.schemas
-            .as_ref()
+            .get()
             .map(|s| s.data_source_schemas.keys().cloned().collect())
             .unwrap_or_default()
     }
@@ -960,6 +1057,12 @@ pub struct ApplyResult {
     pub private_data: Vec<u8>,
 }
 
+#[derive(Debug)]
+pub struct ReadResult {
+    pub new_state: Option<serde_json::Value>,
+    pub private_data: Vec<u8>,
+}
+
 #[derive(Debug)]
 pub struct ImportedResource {
     pub type_name: String,
@@ -967,18 +1070,237 @@ pub struct ImportedResource {
     pub private_data: Vec<u8>,
 }
 
-// ─── Handshake ───────────────────────────────────────────────────────────────
+// ─── RPC Errors ──────────────────────────────────────────────────────────────
 
+/// A failed provider RPC. Reads as `message`, and keeps the gRPC status so a
+/// broken connection can be told apart from an error the provider returned.
 #[derive(Debug)]
-struct Handshake {
-    _core_protocol: u32,
-    app_protocol: u32,
-    network_type: String,
+struct RpcError {
+    message: String,
+    status: tonic::Status,
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+fn rpc_error(message: String, status: tonic::Status) -> anyhow::Error {
+    RpcError { message, status }.into()
+}
+
+/// Whether `err` is an RPC that failed in transport, as when the provider
+/// process dies mid-call, rather than a status or diagnostics the provider
+/// sent back.
+pub fn is_transport_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        let status = match cause.downcast_ref::<RpcError>() {
+            Some(rpc) => &rpc.status,
+            None => match cause.downcast_ref::<tonic::Status>() {
+                Some(status) => status,
+                None => return false,
+            },
+        };
+        // Statuses decoded from a response carry no source; ones synthesized
+        // from a hyper or h2 failure do.
+        matches!(
+            status.code(),
+            tonic::Code::Unavailable | tonic::Code::Cancelled
+        ) || std::error::Error::source(status).is_some()
+    })
+}
+
+// ─── Launch ──────────────────────────────────────────────────────────────────
+
+/// Check that a provider binary exists and is executable, so a missing or
+/// broken install is reported before the OS gives an opaque spawn error.
+fn check_provider_binary(binary_path: &Path) -> Result<()> {
+    let metadata = match std::fs::metadata(binary_path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => bail!(
+            "Provider binary not found at {} — run `oxid init` to install it",
+            binary_path.display()
+        ),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!("Failed to read provider binary {}", binary_path.display())
+            })
+        }
+    };
+    if !metadata.is_file() {
+        bail!(
+            "Provider binary {} is not a file — run `oxid init` to reinstall it",
+            binary_path.display()
+        );
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            bail!(
+                "Provider binary {} is not executable — check its permissions (chmod +x)",
+                binary_path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Translate the common ways spawning a provider fails into actionable errors.
+fn spawn_error(binary_path: &Path, err: std::io::Error) -> anyhow::Error {
+    let path = binary_path.display();
+    match err.kind() {
+        std::io::ErrorKind::NotFound => anyhow::anyhow!(
+            "Provider binary not found at {} — run `oxid init` to install it",
+            path
+        ),
+        std::io::ErrorKind::PermissionDenied if cfg!(target_os = "macos") => anyhow::anyhow!(
+            "Provider binary {} is not executable — check its permissions, or clear the \
+             quarantine flag with `xattr -d com.apple.quarantine {}`",
+            path,
+            path
+        ),
+        std::io::ErrorKind::PermissionDenied => anyhow::anyhow!(
+            "Provider binary {} is not executable — check its permissions",
+            path
+        ),
+        _ => anyhow::Error::new(err).context(format!("Failed to start provider binary {}", path)),
+    }
+}
+
+/// The gRPC endpoint to dial for a provider that printed `handshake`.
+///
+/// TLS is only used when the handshake advertises a server certificate: a
+/// provider that ignores [`CLIENT_CERT_ENV`] is dialled in plain text.
+pub async fn provider_endpoint(
+    handshake: &Handshake,
+    client_cert: &ClientCertificate,
+) -> Result<String> {
+    let tls = match &handshake.server_cert {
+        Some(server_cert) => {
+            info!("Provider negotiated AutoMTLS");
+            Some(client_cert.connector(server_cert.clone())?)
+        }
+        None => None,
+    };
+
+    // Connect via gRPC (supports both TCP and Unix socket)
+    // For unix sockets, we spin up a local TCP proxy because tonic's connect_with_connector
+    // doesn't properly apply h2 connection-level flow control window sizes, causing large
+    // responses (like the AWS provider's ~20MB schema) to hang indefinitely.
+    // The same proxy terminates TLS when the provider serves AutoMTLS.
+    if handshake.network_type == "unix" || tls.is_some() {
+        info!(
+            "Connecting to provider gRPC via {} {}",
+            handshake.network_type, handshake.address
+        );
+        let proxy_addr = spawn_provider_proxy(
+            handshake.network_type.clone(),
+            handshake.address.clone(),
+            tls,
+        )
+        .await?;
+        info!("TCP proxy for provider listening on {}", proxy_addr);
+        Ok(format!("http://{}", proxy_addr))
+    } else {
+        let endpoint = format!("http://{}", handshake.address);
+        info!("Connecting to provider gRPC at {}", endpoint);
+        Ok(endpoint)
+    }
+}
+
+/// A connection to the provider's gRPC server, plain or TLS-wrapped.
+trait ProviderStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> ProviderStream for T {}
+
+/// Listen on an ephemeral local port and forward each connection to the
+/// provider's `network` `address`, wrapping it in TLS when `tls` is set.
+async fn spawn_provider_proxy(
+    network: String,
     address: String,
-    _protocol: String,
+    tls: Option<TlsConnector>,
+) -> Result<std::net::SocketAddr> {
+    let tcp_listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to bind TCP proxy listener")?;
+    let proxy_addr = tcp_listener
+        .local_addr()
+        .context("Failed to get proxy address")?;
+
+    tokio::spawn(async move {
+        loop {
+            match tcp_listener.accept().await {
+                Ok((mut tcp_stream, _)) => {
+                    let network = network.clone();
+                    let address = address.clone();
+                    let tls = tls.clone();
+                    tokio::spawn(async move {
+                        match connect_provider(&network, &address, tls.as_ref()).await {
+                            Ok(mut upstream) => {
+                                let _ =
+                                    tokio::io::copy_bidirectional(&mut tcp_stream, &mut upstream)
+                                        .await;
+                            }
+                            Err(e) => {
+                                tracing::error!(
+                                    "Failed to connect to provider at {}: {}",
+                                    address,
+                                    e
+                                );
+                            }
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::error!("TCP proxy accept error: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(proxy_addr)
+}
+
+async fn connect_provider(
+    network: &str,
+    address: &str,
+    tls: Option<&TlsConnector>,
+) -> std::io::Result<Box<dyn ProviderStream>> {
+    let stream: Box<dyn ProviderStream> = if network == "unix" {
+        Box::new(UnixStream::connect(address).await?)
+    } else {
+        Box::new(TcpStream::connect(address).await?)
+    };
+    match tls {
+        Some(tls) => Ok(Box::new(tls.connect(mtls::server_name(), stream).await?)),
+        None => Ok(stream),
+    }
 }
 
-fn parse_handshake(line: &str) -> Result<Handshake> {
+// ─── Handshake ───────────────────────────────────────────────────────────────
+
+/// The go-plugin core protocol version this client speaks.
+const CORE_PROTOCOL_VERSION: u32 = 1;
+
+/// The line a provider prints on stdout once it is ready to serve:
+/// `core|app|network|address|protocol[|server-cert]`.
+#[derive(Debug)]
+pub struct Handshake {
+    pub core_protocol: u32,
+    pub app_protocol: u32,
+    pub network_type: String,
+    pub address: String,
+    pub protocol: String,
+    /// The provider's TLS certificate (DER), present when it serves AutoMTLS.
+    pub server_cert: Option<Vec<u8>>,
+}
+
+pub fn parse_handshake(line: &str) -> Result<Handshake> {
     let parts: Vec<&str> = line.split('|').collect();
     if parts.len() < 5 {
         bail!(
@@ -987,17 +1309,117 @@ fn parse_handshake(line: &str) -> Result<Handshake> {
         );
     }
 
+    let core_protocol = parts[0].parse().context("Invalid core protocol version")?;
+    if core_protocol != CORE_PROTOCOL_VERSION {
+        bail!(
+            "Unsupported go-plugin core protocol version {} (expected {})",
+            core_protocol,
+            CORE_PROTOCOL_VERSION
+        );
+    }
+    if parts[4] != "grpc" {
+        bail!(
+            "Unsupported provider RPC protocol '{}' (only grpc is supported)",
+            parts[4]
+        );
+    }
+    let server_cert = match parts.get(5).map(|field| field.trim()) {
+        Some(field) if !field.is_empty() => Some(mtls::decode_server_cert(field)?),
+        _ => None,
+    };
+
     Ok(Handshake {
-        _core_protocol: parts[0].parse().context("Invalid core protocol version")?,
+        core_protocol,
         app_protocol: parts[1].parse().context("Invalid app protocol version")?,
         network_type: parts[2].to_string(),
         address: parts[3].to_string(),
-        _protocol: parts[4].to_string(),
+        protocol: parts[4].to_string(),
+        server_cert,
     })
 }
 
 // ─── Msgpack/cty Helpers ─────────────────────────────────────────────────────
 
+/// Key of the sentinel object standing in for a cty unknown value.
+pub const UNKNOWN_KEY: &str = "__oxid_unknown__";
+
+/// The sentinel for a value only known after apply: `{"__oxid_unknown__": true}`.
+pub fn unknown_value() -> serde_json::Value {
+    serde_json::json!({ UNKNOWN_KEY: true })
+}
+
+/// Whether `value` is the unknown sentinel.
+pub fn is_unknown(value: &serde_json::Value) -> bool {
+    value
+        .as_object()
+        .is_some_and(|obj| obj.len() == 1 && obj.get(UNKNOWN_KEY) == Some(&true.into()))
+}
+
+/// Whether `value` is the unknown sentinel or holds one at any depth.
+pub fn contains_unknown(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Array(items) => items.iter().any(contains_unknown),
+        serde_json::Value::Object(_) if is_unknown(value) => true,
+        serde_json::Value::Object(map) => map.values().any(contains_unknown),
+        _ => false,
+    }
+}
+
+/// Whether `planned` agrees with `prior`, treating an unknown anywhere in
+/// `planned` as matching whatever `prior` holds at that position.
+pub fn matches_known(prior: &serde_json::Value, planned: &serde_json::Value) -> bool {
+    use serde_json::Value;
+    if is_unknown(planned) {
+        return true;
+    }
+    match (prior, planned) {
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| matches_known(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && b.iter()
+                    .all(|(k, v)| a.get(k).is_some_and(|p| matches_known(p, v)))
+        }
+        _ => prior == planned,
+    }
+}
+
+/// Replace unknown sentinels with null, so they never reach a provider.
+/// Object keys come out sorted at every level, whatever order the map
+/// iterates in, so the same value always encodes to the same bytes.
+pub fn strip_unknowns(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        v if is_unknown(v) => Value::Null,
+        Value::Array(items) => Value::Array(items.iter().map(strip_unknowns).collect()),
+        Value::Object(obj) => {
+            let mut entries: Vec<_> = obj.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.clone(), strip_unknowns(v)))
+                    .collect(),
+            )
+        }
+        v => v.clone(),
+    }
+}
+
+/// Encode a value as msgpack for a provider, with unknowns sent as null and
+/// map keys in sorted order.
+pub fn encode_msgpack(value: &serde_json::Value) -> Result<Vec<u8>> {
+    rmp_serde::to_vec_named(&strip_unknowns(value)).context("Failed to encode msgpack")
+}
+
+/// Decode a provider's msgpack value. cty unknowns become [`unknown_value`].
+pub fn decode_msgpack(bytes: &[u8]) -> Result<serde_json::Value> {
+    let raw: rmpv::Value =
+        rmpv::decode::read_value(&mut &bytes[..]).context("Failed to decode msgpack")?;
+    Ok(rmpv_to_json(raw))
+}
+
 /// Convert rmpv::Value to serde_json::Value, handling cty extension types.
 /// cty uses msgpack extension type 0 for "unknown" values (computed at apply time).
 fn rmpv_to_json(val: rmpv::Value) -> serde_json::Value {
@@ -1022,7 +1444,9 @@ fn rmpv_to_json(val: rmpv::Value) -> serde_json::Value {
         rmpv::Value::String(s) => {
             serde_json::Value::String(s.into_str().unwrap_or_default().to_string())
         }
-        rmpv::Value::Binary(b) => serde_json::Value::String(base64_encode(&b)),
+        rmpv::Value::Binary(b) => {
+            serde_json::Value::String(crate::executor::encoding::base64_encode(&b))
+        }
         rmpv::Value::Array(arr) => {
             serde_json::Value::Array(arr.into_iter().map(rmpv_to_json).collect())
         }
@@ -1037,55 +1461,25 @@ fn rmpv_to_json(val: rmpv::Value) -> serde_json::Value {
             }
             serde_json::Value::Object(map)
         }
-        rmpv::Value::Ext(_type_id, _data) => {
-            // cty extension type 0 = unknown value (will be computed at apply time)
-            // All extension types treated as null for planning purposes
-            serde_json::Value::Null
-        }
-    }
-}
-
-/// Simple base64 encoding for binary msgpack values.
-fn base64_encode(data: &[u8]) -> String {
-    use std::fmt::Write;
-    let mut s = String::with_capacity(data.len() * 4 / 3 + 4);
-    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    for chunk in data.chunks(3) {
-        let b0 = chunk[0] as u32;
-        let b1 = if chunk.len() > 1 { chunk[1] as u32 } else { 0 };
-        let b2 = if chunk.len() > 2 { chunk[2] as u32 } else { 0 };
-        let n = (b0 << 16) | (b1 << 8) | b2;
-        let _ = write!(s, "{}", CHARS[(n >> 18 & 63) as usize] as char);
-        let _ = write!(s, "{}", CHARS[(n >> 12 & 63) as usize] as char);
-        if chunk.len() > 1 {
-            let _ = write!(s, "{}", CHARS[(n >> 6 & 63) as usize] as char);
-        } else {
-            s.push('=');
-        }
-        if chunk.len() > 2 {
-            let _ = write!(s, "{}", CHARS[(n & 63) as usize] as char);
-        } else {
-            s.push('=');
-        }
+        // cty extension type 0 = unknown value (will be computed at apply time)
+        rmpv::Value::Ext(0, _) => unknown_value(),
+        rmpv::Value::Ext(_type_id, _data) => serde_json::Value::Null,
     }
-    s
 }
 
 // ─── v5 Helpers ──────────────────────────────────────────────────────────────
 
 fn json_to_dynamic_v5(value: &serde_json::Value) -> super::tfplugin5::DynamicValue {
     super::tfplugin5::DynamicValue {
-        msgpack: rmp_serde::to_vec_named(value).unwrap_or_default(),
+        msgpack: encode_msgpack(value).unwrap_or_default(),
         json: vec![],
     }
 }
 
 fn dynamic_to_json_v5(dv: &super::tfplugin5::DynamicValue) -> Result<serde_json::Value> {
     if !dv.msgpack.is_empty() {
-        // Use rmpv to handle cty extension types (e.g., unknown values = ext type 0)
-        let raw: rmpv::Value =
-            rmpv::decode::read_value(&mut &dv.msgpack[..]).context("Failed to decode msgpack")?;
-        Ok(rmpv_to_json(raw))
+        // rmpv handles cty extension types (e.g., unknown values = ext type 0)
+        decode_msgpack(&dv.msgpack)
     } else if !dv.json.is_empty() {
         Ok(serde_json::from_slice(&dv.json)?)
     } else {
@@ -1130,7 +1524,7 @@ fn check_diagnostics_v5(diagnostics: &[super::tfplugin5::Diagnostic]) -> Result<
         }
         Ok(())
     } else {
-        bail!("Provider errors:\n{}", errors.join("\n"))
+        Err(OxidError::ProviderDiagnostics(errors).into())
     }
 }
 
@@ -1138,16 +1532,14 @@ fn check_diagnostics_v5(diagnostics: &[super::tfplugin5::Diagnostic]) -> Result<
 
 fn json_to_dynamic_v6(value: &serde_json::Value) -> super::tfplugin6::DynamicValue {
     super::tfplugin6::DynamicValue {
-        msgpack: rmp_serde::to_vec_named(value).unwrap_or_default(),
+        msgpack: encode_msgpack(value).unwrap_or_default(),
         json: vec![],
     }
 }
 
 fn dynamic_to_json_v6(dv: &super::tfplugin6::DynamicValue) -> Result<serde_json::Value> {
     if !dv.msgpack.is_empty() {
-        let raw: rmpv::Value =
-            rmpv::decode::read_value(&mut &dv.msgpack[..]).context("Failed to decode msgpack")?;
-        Ok(rmpv_to_json(raw))
+        decode_msgpack(&dv.msgpack)
     } else if !dv.json.is_empty() {
         Ok(serde_json::from_slice(&dv.json)?)
     } else {
@@ -1192,7 +1584,7 @@ fn check_diagnostics_v6(diagnostics: &[super::tfplugin6::Diagnostic]) -> Result<
         }
         Ok(())
     } else {
-        bail!("Provider errors:\n{}", errors.join("\n"))
+        Err(OxidError::ProviderDiagnostics(errors).into())
     }
 }
 
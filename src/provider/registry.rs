@@ -1,7 +1,11 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
+use indicatif::ProgressBar;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::version::VersionConstraint;
 
 /// Information about a provider resolved from the registry.
 #[derive(Debug, Clone)]
@@ -17,25 +21,85 @@ pub struct ProviderSource {
     pub protocols: Vec<String>,
 }
 
-/// Response from the registry versions API.
-#[derive(Debug, Deserialize)]
-struct VersionsResponse {
-    versions: Vec<VersionEntry>,
+/// Response from the registry versions API
+/// (`GET /v1/providers/{namespace}/{type}/versions`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryVersions {
+    pub versions: Vec<RegistryVersion>,
 }
 
-#[derive(Debug, Deserialize)]
-struct VersionEntry {
-    version: String,
-    protocols: Vec<String>,
-    #[allow(dead_code)]
-    platforms: Vec<PlatformEntry>,
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryVersion {
+    pub version: String,
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    #[serde(default)]
+    pub platforms: Vec<Platform>,
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct PlatformEntry {
-    os: String,
-    arch: String,
+/// An OS/architecture pair as named by the registry (`linux`/`amd64`).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Platform {
+    pub os: String,
+    pub arch: String,
+}
+
+impl Platform {
+    pub fn new(os: &str, arch: &str) -> Self {
+        Self {
+            os: os.to_string(),
+            arch: arch.to_string(),
+        }
+    }
+
+    /// The platform this binary runs on.
+    pub fn current() -> Self {
+        let (os, arch) = detect_platform();
+        Self { os, arch }
+    }
+
+    /// Parse the `OS_ARCH` form used on the command line (`linux_amd64`).
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().split_once('_') {
+            Some((os, arch)) if !os.is_empty() && !arch.is_empty() => Ok(Self::new(os, arch)),
+            _ => bail!(
+                "Invalid platform '{}'. Expected OS_ARCH, e.g. linux_amd64",
+                s
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}", self.os, self.arch)
+    }
+}
+
+impl RegistryVersions {
+    /// The highest version matching `constraint` that ships a build for
+    /// `platform`. Versions listing no platforms are assumed to support all.
+    pub fn select(
+        &self,
+        constraint: &VersionConstraint,
+        platform: &Platform,
+    ) -> Option<&RegistryVersion> {
+        self.select_for_all(constraint, std::slice::from_ref(platform))
+    }
+
+    /// The highest version matching `constraint` that ships a build for
+    /// every one of `platforms`.
+    pub fn select_for_all(
+        &self,
+        constraint: &VersionConstraint,
+        platforms: &[Platform],
+    ) -> Option<&RegistryVersion> {
+        let available = self.versions.iter().filter(|v| {
+            v.platforms.is_empty() || platforms.iter().all(|p| v.platforms.contains(p))
+        });
+        let best = constraint.highest(available.clone().map(|v| v.version.as_str()))?;
+        available.into_iter().find(|v| v.version == best)
+    }
 }
 
 /// Response from the registry download API.
@@ -49,6 +113,9 @@ struct DownloadResponse {
     protocols: Vec<String>,
 }
 
+/// Host of the public Terraform Registry, used for unqualified sources.
+pub const DEFAULT_REGISTRY_HOST: &str = "registry.terraform.io";
+
 /// The OpenTofu/Terraform provider registry client.
 /// Discovers and downloads provider binaries from registry.opentofu.org
 /// or registry.terraform.io.
@@ -61,7 +128,7 @@ impl Default for RegistryClient {
     fn default() -> Self {
         Self {
             http: reqwest::Client::new(),
-            base_url: "https://registry.terraform.io".to_string(),
+            base_url: format!("https://{}", DEFAULT_REGISTRY_HOST),
         }
     }
 }
@@ -88,45 +155,76 @@ impl RegistryClient {
         }
     }
 
-    /// List available versions for a provider.
-    pub async fn list_versions(
+    /// Host named by a `hostname/namespace/type` source, if any.
+    pub fn source_host(source: &str) -> Option<&str> {
+        let parts: Vec<&str> = source.split('/').collect();
+        match parts.as_slice() {
+            [host, _, _] => Some(host),
+            _ => None,
+        }
+    }
+
+    /// Fetch the versions a provider publishes.
+    pub async fn fetch_versions(
         &self,
         namespace: &str,
         provider_type: &str,
-    ) -> Result<Vec<(String, Vec<String>)>> {
+    ) -> Result<RegistryVersions> {
         let url = format!(
             "{}/v1/providers/{}/{}/versions",
             self.base_url, namespace, provider_type
         );
 
-        let resp: VersionsResponse = self
-            .http
+        self.http
             .get(&url)
             .send()
             .await
             .context("Failed to query provider registry")?
+            .error_for_status()
+            .with_context(|| format!("Registry has no provider {}/{}", namespace, provider_type))?
             .json()
             .await
-            .context("Failed to parse registry response")?;
+            .context("Failed to parse registry response")
+    }
 
-        Ok(resp
+    /// List available versions for a provider.
+    pub async fn list_versions(
+        &self,
+        namespace: &str,
+        provider_type: &str,
+    ) -> Result<Vec<(String, Vec<String>)>> {
+        Ok(self
+            .fetch_versions(namespace, provider_type)
+            .await?
             .versions
             .into_iter()
             .map(|v| (v.version, v.protocols))
             .collect())
     }
 
-    /// Resolve the best version matching a constraint.
-    /// Supports: exact ("1.2.3"), prefix ("~> 1.2"), >= (">= 1.0").
+    /// Resolve the highest version matching a constraint that is built for
+    /// the current platform.
     pub async fn resolve_version(
         &self,
         namespace: &str,
         provider_type: &str,
-        constraint: &str,
+        constraint: &VersionConstraint,
     ) -> Result<String> {
-        let versions = self.list_versions(namespace, provider_type).await?;
+        self.resolve_version_for(namespace, provider_type, constraint, &[Platform::current()])
+            .await
+    }
 
-        if versions.is_empty() {
+    /// Resolve the highest version matching a constraint that is built for
+    /// all of `platforms`.
+    pub async fn resolve_version_for(
+        &self,
+        namespace: &str,
+        provider_type: &str,
+        constraint: &VersionConstraint,
+        platforms: &[Platform],
+    ) -> Result<String> {
+        let versions = self.fetch_versions(namespace, provider_type).await?;
+        if versions.versions.is_empty() {
             bail!(
                 "No versions found for provider {}/{}",
                 namespace,
@@ -134,93 +232,35 @@ impl RegistryClient {
             );
         }
 
-        let constraint = constraint.trim();
-
-        // Exact version
-        if !constraint.starts_with('~')
-            && !constraint.starts_with('>')
-            && !constraint.starts_with('<')
-            && !constraint.starts_with('=')
-        {
-            if versions.iter().any(|(v, _)| v == constraint) {
-                return Ok(constraint.to_string());
-            }
-            bail!(
-                "Version {} not found for {}/{}",
-                constraint,
+        match versions.select_for_all(constraint, platforms) {
+            Some(v) => Ok(v.version.clone()),
+            None => bail!(
+                "No version of {}/{} matches '{}' for {}",
                 namespace,
-                provider_type
-            );
-        }
-
-        // ~> pessimistic constraint: ~> 1.2 means >= 1.2.0, < 2.0.0
-        if constraint.starts_with("~>") {
-            let version_part = constraint.trim_start_matches("~>").trim();
-            let parts: Vec<u64> = version_part
-                .split('.')
-                .filter_map(|p| p.parse().ok())
-                .collect();
-
-            let mut matching: Vec<&str> = versions
-                .iter()
-                .filter(|(v, _)| {
-                    let v_parts: Vec<u64> = v.split('.').filter_map(|p| p.parse().ok()).collect();
-                    if v_parts.len() < parts.len() {
-                        return false;
-                    }
-                    // Must match all but last constraint part, and last must be >=
-                    for (i, part) in parts[..parts.len().saturating_sub(1)].iter().enumerate() {
-                        if v_parts.get(i) != Some(part) {
-                            return false;
-                        }
-                    }
-                    let last_idx = parts.len() - 1;
-                    v_parts
-                        .get(last_idx)
-                        .map(|v| *v >= parts[last_idx])
-                        .unwrap_or(false)
-                })
-                .map(|(v, _)| v.as_str())
-                .collect();
-
-            matching.sort_by(|a, b| compare_versions(b, a));
-
-            return matching
-                .first()
-                .map(|v| v.to_string())
-                .ok_or_else(|| anyhow::anyhow!("No version matches constraint '{}'", constraint));
-        }
-
-        // >= constraint
-        if constraint.starts_with(">=") {
-            let version_part = constraint.trim_start_matches(">=").trim();
-            let min_parts: Vec<u64> = version_part
-                .split('.')
-                .filter_map(|p| p.parse().ok())
-                .collect();
-
-            let mut matching: Vec<&str> = versions
-                .iter()
-                .filter(|(v, _)| {
-                    let v_parts: Vec<u64> = v.split('.').filter_map(|p| p.parse().ok()).collect();
-                    compare_version_tuples(&v_parts, &min_parts) != std::cmp::Ordering::Less
-                })
-                .map(|(v, _)| v.as_str())
-                .collect();
-
-            matching.sort_by(|a, b| compare_versions(b, a));
-
-            return matching
-                .first()
-                .map(|v| v.to_string())
-                .ok_or_else(|| anyhow::anyhow!("No version matches constraint '{}'", constraint));
+                provider_type,
+                constraint,
+                platforms
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
+    }
 
-        // Fallback: latest version
-        versions
-            .first()
-            .map(|(v, _)| v.clone())
-            .ok_or_else(|| anyhow::anyhow!("No versions available"))
+    /// Resolve a constraint to a concrete version and its download URL and
+    /// checksum for the current platform.
+    pub async fn resolve(
+        &self,
+        namespace: &str,
+        provider_type: &str,
+        constraint: &VersionConstraint,
+    ) -> Result<ProviderSource> {
+        let version = self
+            .resolve_version(namespace, provider_type, constraint)
+            .await?;
+        self.get_download_info(namespace, provider_type, &version)
+            .await
     }
 
     /// Get the download URL and metadata for a specific provider version.
@@ -230,7 +270,20 @@ impl RegistryClient {
         provider_type: &str,
         version: &str,
     ) -> Result<ProviderSource> {
-        let (os, arch) = detect_platform();
+        self.get_download_info_for(namespace, provider_type, version, &Platform::current())
+            .await
+    }
+
+    /// Get the download URL and metadata for a provider version built for
+    /// `platform`.
+    pub async fn get_download_info_for(
+        &self,
+        namespace: &str,
+        provider_type: &str,
+        version: &str,
+        platform: &Platform,
+    ) -> Result<ProviderSource> {
+        let (os, arch) = (&platform.os, &platform.arch);
 
         let url = format!(
             "{}/v1/providers/{}/{}/{}/download/{}/{}",
@@ -243,6 +296,13 @@ impl RegistryClient {
             .send()
             .await
             .context("Failed to query download URL")?
+            .error_for_status()
+            .with_context(|| {
+                format!(
+                    "Registry has no {}_{} build of {}/{} {}",
+                    os, arch, namespace, provider_type, version
+                )
+            })?
             .json()
             .await
             .context("Failed to parse download response")?;
@@ -260,26 +320,35 @@ impl RegistryClient {
         })
     }
 
-    /// Download a provider binary to the specified directory.
-    /// Returns the path to the extracted provider binary.
+    /// Download a provider binary to the specified directory, reporting
+    /// bytes received on `progress`. Returns the path to the extracted
+    /// provider binary.
+    ///
+    /// On failure `dest_dir` is removed, so a half-written download never
+    /// looks like a cached provider and a retry starts fresh.
     pub async fn download_provider(
         &self,
         source: &ProviderSource,
         dest_dir: &Path,
+        progress: &ProgressBar,
+    ) -> Result<PathBuf> {
+        let result = self.download_into(source, dest_dir, progress).await;
+        if result.is_err() {
+            let _ = std::fs::remove_dir_all(dest_dir);
+        }
+        result
+    }
+
+    async fn download_into(
+        &self,
+        source: &ProviderSource,
+        dest_dir: &Path,
+        progress: &ProgressBar,
     ) -> Result<PathBuf> {
         std::fs::create_dir_all(dest_dir)?;
 
         let archive_path = dest_dir.join(&source.filename);
-
-        // Download the archive
-        let resp = self
-            .http
-            .get(&source.download_url)
-            .send()
-            .await
-            .context("Failed to download provider archive")?;
-
-        let bytes = resp.bytes().await?;
+        let bytes = self.fetch_archive(source, progress).await?;
         std::fs::write(&archive_path, &bytes)?;
 
         // Extract the archive (zip format for terraform providers)
@@ -290,10 +359,45 @@ impl RegistryClient {
 
         Ok(binary_path)
     }
+
+    /// Download a provider archive into memory, reporting bytes received on
+    /// `progress`, and check it against the registry's checksum.
+    pub async fn fetch_archive(
+        &self,
+        source: &ProviderSource,
+        progress: &ProgressBar,
+    ) -> Result<Vec<u8>> {
+        let mut resp = self
+            .http
+            .get(&source.download_url)
+            .send()
+            .await
+            .context("Failed to download provider archive")?
+            .error_for_status()
+            .context("Failed to download provider archive")?;
+        if let Some(total) = resp.content_length() {
+            progress.set_length(total);
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .context("Failed to download provider archive")?
+        {
+            bytes.extend_from_slice(&chunk);
+            progress.set_position(bytes.len() as u64);
+        }
+        verify_shasum(&bytes, &source.shasum)
+            .with_context(|| format!("Refusing to install {}", source.filename))?;
+        Ok(bytes)
+    }
 }
 
-/// Extract a provider binary from a zip archive.
-fn extract_provider_archive(archive_path: &Path, dest_dir: &Path) -> Result<PathBuf> {
+/// Extract a provider package from a zip archive, returning the path of its
+/// binary. Every file is unpacked so the directory keeps the package's `h1:`
+/// hash.
+pub(crate) fn extract_provider_archive(archive_path: &Path, dest_dir: &Path) -> Result<PathBuf> {
     let file = std::fs::File::open(archive_path)?;
     let mut archive = zip::ZipArchive::new(file)?;
 
@@ -301,14 +405,21 @@ fn extract_provider_archive(archive_path: &Path, dest_dir: &Path) -> Result<Path
 
     for i in 0..archive.len() {
         let mut entry = archive.by_index(i)?;
-        let name = entry.name().to_string();
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else {
+            bail!("Provider archive has an unsafe path: {}", entry.name());
+        };
+        let out_path = dest_dir.join(&name);
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut outfile = std::fs::File::create(&out_path)?;
+        std::io::copy(&mut entry, &mut outfile)?;
 
         // Provider binaries are named terraform-provider-<type>_v<version>
-        if name.starts_with("terraform-provider-") {
-            let out_path = dest_dir.join(&name);
-            let mut outfile = std::fs::File::create(&out_path)?;
-            std::io::copy(&mut entry, &mut outfile)?;
-
+        if entry.name().starts_with("terraform-provider-") {
             // Make executable on Unix
             #[cfg(unix)]
             {
@@ -323,6 +434,24 @@ fn extract_provider_archive(archive_path: &Path, dest_dir: &Path) -> Result<Path
     binary_path.ok_or_else(|| anyhow::anyhow!("No provider binary found in archive"))
 }
 
+/// Hex SHA-256 of a provider archive, as the registry reports it.
+pub fn shasum(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Check downloaded bytes against the registry's hex SHA-256 checksum.
+pub fn verify_shasum(bytes: &[u8], expected: &str) -> Result<()> {
+    let actual = shasum(bytes);
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        bail!(
+            "Checksum mismatch: expected {}, downloaded archive has {}",
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
 /// Detect the current OS and architecture for registry downloads.
 fn detect_platform() -> (String, String) {
     let os = if cfg!(target_os = "macos") {
@@ -345,22 +474,3 @@ fn detect_platform() -> (String, String) {
 
     (os.to_string(), arch.to_string())
 }
-
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
-    let a_parts: Vec<u64> = a.split('.').filter_map(|p| p.parse().ok()).collect();
-    let b_parts: Vec<u64> = b.split('.').filter_map(|p| p.parse().ok()).collect();
-    compare_version_tuples(&a_parts, &b_parts)
-}
-
-fn compare_version_tuples(a: &[u64], b: &[u64]) -> std::cmp::Ordering {
-    let max_len = a.len().max(b.len());
-    for i in 0..max_len {
-        let av = a.get(i).copied().unwrap_or(0);
-        let bv = b.get(i).copied().unwrap_or(0);
-        match av.cmp(&bv) {
-            std::cmp::Ordering::Equal => continue,
-            other => return other,
-        }
-    }
-    std::cmp::Ordering::Equal
-}
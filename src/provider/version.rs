@@ -0,0 +1,171 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use semver::Version;
+
+/// A Terraform-style version constraint such as `>= 5.0, < 6.0` or `~> 5.70`.
+///
+/// Comma-separated clauses must all hold. Supported operators are `=`, `!=`,
+/// `>`, `>=`, `<`, `<=` and `~>` (only the rightmost written component may
+/// grow). A bare version means `=`. As in Terraform, a prerelease version is
+/// only selected by an exact `=` clause naming it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint {
+    clauses: Vec<Clause>,
+    raw: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Clause {
+    op: Op,
+    version: Version,
+    /// Number of components written (`~> 1.2` has 2), for `~>`.
+    precision: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Pessimistic,
+}
+
+impl VersionConstraint {
+    /// A constraint every release version satisfies.
+    pub fn any() -> Self {
+        Self {
+            clauses: Vec::new(),
+            raw: String::new(),
+        }
+    }
+
+    pub fn parse(constraint: &str) -> Result<Self> {
+        let mut clauses = Vec::new();
+        for part in constraint.split(',').map(str::trim) {
+            if part.is_empty() {
+                continue;
+            }
+            let (op, rest) = [
+                ("~>", Op::Pessimistic),
+                (">=", Op::Ge),
+                ("<=", Op::Le),
+                ("!=", Op::Ne),
+                (">", Op::Gt),
+                ("<", Op::Lt),
+                ("=", Op::Eq),
+            ]
+            .iter()
+            .find_map(|(prefix, op)| part.strip_prefix(prefix).map(|rest| (*op, rest)))
+            .unwrap_or((Op::Eq, part));
+            let rest = rest.trim();
+            let precision = rest
+                .split(['-', '+'])
+                .next()
+                .unwrap_or("")
+                .split('.')
+                .count();
+            let version = parse_version(rest)
+                .with_context(|| format!("Invalid version constraint '{}'", constraint))?;
+            clauses.push(Clause {
+                op,
+                version,
+                precision,
+            });
+        }
+        Ok(Self {
+            clauses,
+            raw: constraint.trim().to_string(),
+        })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        if !version.pre.is_empty()
+            && !self
+                .clauses
+                .iter()
+                .any(|c| c.op == Op::Eq && c.version == *version)
+        {
+            return false;
+        }
+        self.clauses.iter().all(|c| c.matches(version))
+    }
+
+    /// The highest of `versions` satisfying the constraint. Unparseable
+    /// versions are ignored.
+    pub fn highest<'a, I>(&self, versions: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        versions
+            .into_iter()
+            .filter_map(|v| parse_version(v).ok().map(|parsed| (v, parsed)))
+            .filter(|(_, parsed)| self.matches(parsed))
+            .max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(v, _)| v)
+    }
+}
+
+impl Clause {
+    fn matches(&self, v: &Version) -> bool {
+        // Compare without build metadata, which carries no precedence
+        let v = &Version {
+            build: semver::BuildMetadata::EMPTY,
+            ..v.clone()
+        };
+        match self.op {
+            Op::Eq => *v == self.version,
+            Op::Ne => *v != self.version,
+            Op::Gt => *v > self.version,
+            Op::Ge => *v >= self.version,
+            Op::Lt => *v < self.version,
+            Op::Le => *v <= self.version,
+            Op::Pessimistic => *v >= self.version && *v < self.pessimistic_upper_bound(),
+        }
+    }
+
+    /// `~> 1.2` allows `< 2.0.0`; `~> 1.2.3` allows `< 1.3.0`.
+    fn pessimistic_upper_bound(&self) -> Version {
+        let base = &self.version;
+        match self.precision {
+            0..=2 => Version::new(base.major + 1, 0, 0),
+            _ => Version::new(base.major, base.minor + 1, 0),
+        }
+    }
+}
+
+impl FromStr for VersionConstraint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.raw.is_empty() {
+            write!(f, "any version")
+        } else {
+            write!(f, "{}", self.raw)
+        }
+    }
+}
+
+/// Parse a version, accepting a leading `v` and missing minor/patch
+/// components (`5`, `5.70`).
+pub fn parse_version(version: &str) -> Result<Version> {
+    let version = version.trim().trim_start_matches('v');
+    let split = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(split);
+    let components = core.split('.').count();
+    if core.is_empty() || components > 3 {
+        bail!("Invalid version '{}'", version);
+    }
+    let padded = format!("{}{}{}", core, ".0".repeat(3 - components), suffix);
+    Version::parse(&padded).with_context(|| format!("Invalid version '{}'", version))
+}
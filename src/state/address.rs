@@ -0,0 +1,236 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+
+use super::models::ResourceState;
+
+/// A parsed resource address such as `module.net.aws_subnet.private["a"]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceAddress {
+    /// `module.NAME` segments joined with `.`, empty for the root module.
+    pub module_path: String,
+    /// `data` for data sources, `managed` otherwise.
+    pub mode: String,
+    pub resource_type: String,
+    pub name: String,
+    /// Raw count index or for_each key, as stored in `resources.index_key`.
+    pub index_key: Option<String>,
+}
+
+impl ResourceAddress {
+    pub fn parse(address: &str) -> Result<Self> {
+        let (base, index_key) = split_index(address)?;
+
+        let segments: Vec<&str> = base.split('.').collect();
+        let mut rest = segments.as_slice();
+        let mut modules = Vec::new();
+        while rest.len() > 2 && rest[0] == "module" {
+            modules.push(format!("module.{}", rest[1]));
+            rest = &rest[2..];
+        }
+        let mode = if rest.len() == 3 && rest[0] == "data" {
+            rest = &rest[1..];
+            "data"
+        } else {
+            "managed"
+        };
+        match rest {
+            [resource_type, name] if !resource_type.is_empty() && !name.is_empty() => Ok(Self {
+                module_path: modules.join("."),
+                mode: mode.to_string(),
+                resource_type: resource_type.to_string(),
+                name: name.to_string(),
+                index_key,
+            }),
+            _ => bail!(
+                "Invalid resource address '{}': expected [module.NAME.]TYPE.NAME[INDEX]",
+                address
+            ),
+        }
+    }
+
+    /// The address without its index.
+    pub fn base(&self) -> String {
+        let mut out = String::new();
+        if !self.module_path.is_empty() {
+            out.push_str(&self.module_path);
+            out.push('.');
+        }
+        if self.mode == "data" {
+            out.push_str("data.");
+        }
+        out.push_str(&self.resource_type);
+        out.push('.');
+        out.push_str(&self.name);
+        out
+    }
+
+    /// Copy of this address carrying `index_key`.
+    pub fn with_index(&self, index_key: Option<String>) -> Self {
+        Self {
+            index_key,
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for ResourceAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base())?;
+        match &self.index_key {
+            Some(key) if key.parse::<u64>().is_ok() => write!(f, "[{}]", key),
+            Some(key) => write!(f, "[\"{}\"]", key),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Split `base[INDEX]` into the base and the raw index key.
+fn split_index(address: &str) -> Result<(&str, Option<String>)> {
+    if !address.ends_with(']') {
+        return Ok((address, None));
+    }
+    let (start, key) = if address.ends_with("\"]") {
+        let start = address.rfind("[\"").filter(|&i| i + 2 < address.len() - 1);
+        match start {
+            Some(i) => (i, &address[i + 2..address.len() - 2]),
+            None => bail!("Invalid index in resource address '{}'", address),
+        }
+    } else {
+        let Some(i) = address.rfind('[') else {
+            bail!("Invalid index in resource address '{}'", address);
+        };
+        let key = &address[i + 1..address.len() - 1];
+        if key.parse::<u64>().is_err() {
+            bail!(
+                "Invalid index in resource address '{}': use [N] or [\"KEY\"]",
+                address
+            );
+        }
+        (i, key)
+    };
+    Ok((&address[..start], Some(key.to_string())))
+}
+
+/// Work out the state records affected by `state mv SOURCE DESTINATION`.
+///
+/// A source with an index (or a non-expanded resource) moves one record; a
+/// base address with expanded instances moves every instance, keeping each
+/// instance's index. Returns `(old_address, moved_record)` pairs; moved
+/// records keep their `id` so dependency links survive.
+pub fn plan_move(
+    resources: &[ResourceState],
+    source: &str,
+    destination: &str,
+) -> Result<Vec<(String, ResourceState)>> {
+    let src = ResourceAddress::parse(source)?;
+    let dst = ResourceAddress::parse(destination)?;
+
+    if src.resource_type != dst.resource_type || src.mode != dst.mode {
+        bail!(
+            "Cannot move {} to {}: resource types differ ({} vs {})",
+            source,
+            destination,
+            src.resource_type,
+            dst.resource_type
+        );
+    }
+
+    let targets: Vec<(&ResourceState, ResourceAddress)> =
+        if let Some(exact) = resources.iter().find(|r| r.address == source) {
+            vec![(exact, dst.clone())]
+        } else if src.index_key.is_none() {
+            let instance_prefix = format!("{}[", source);
+            let instances: Vec<&ResourceState> = resources
+                .iter()
+                .filter(|r| r.address.starts_with(&instance_prefix))
+                .collect();
+            if instances.is_empty() {
+                bail!("Source resource '{}' not found in state.", source);
+            }
+            if dst.index_key.is_some() {
+                bail!(
+                    "Cannot move all instances of {} to the single instance {}",
+                    source,
+                    destination
+                );
+            }
+            instances
+                .into_iter()
+                .map(|r| {
+                    let index = ResourceAddress::parse(&r.address)
+                        .ok()
+                        .and_then(|a| a.index_key)
+                        .or_else(|| r.index_key.clone());
+                    (r, dst.with_index(index))
+                })
+                .collect()
+        } else {
+            bail!("Source resource '{}' not found in state.", source);
+        };
+
+    let moved_from: Vec<&str> = targets.iter().map(|(r, _)| r.address.as_str()).collect();
+    let mut moves = Vec::with_capacity(targets.len());
+    for (resource, new_address) in targets {
+        let new_address_str = new_address.to_string();
+        if resources
+            .iter()
+            .any(|r| r.address == new_address_str && !moved_from.contains(&r.address.as_str()))
+        {
+            bail!(
+                "Destination resource '{}' already exists in state.",
+                new_address_str
+            );
+        }
+        let mut moved = resource.clone();
+        moved.address = new_address_str;
+        moved.resource_name = new_address.name.clone();
+        moved.module_path = new_address.module_path.clone();
+        moved.index_key = new_address.index_key.clone();
+        moved.updated_at = chrono::Utc::now().to_rfc3339();
+        moves.push((resource.address.clone(), moved));
+    }
+    Ok(moves)
+}
+
+/// Addresses for the objects one `oxid import` returned, in order. The first
+/// object of the requested type takes `address`; every other object (e.g.
+/// the rules of an imported security group) becomes `TYPE.NAME_N` in the same
+/// module, numbered from 1 and skipping addresses already `taken`. Those are
+/// valid, referenceable config names, so each can be written as a block.
+pub fn import_addresses(
+    address: &ResourceAddress,
+    types: &[&str],
+    taken: &std::collections::HashSet<String>,
+) -> Vec<ResourceAddress> {
+    let primary = types
+        .iter()
+        .position(|t| *t == address.resource_type)
+        .unwrap_or(0);
+    let mut n = 0;
+    types
+        .iter()
+        .enumerate()
+        .map(|(i, resource_type)| {
+            if i == primary {
+                return ResourceAddress {
+                    resource_type: resource_type.to_string(),
+                    ..address.clone()
+                };
+            }
+            loop {
+                n += 1;
+                let extra = ResourceAddress {
+                    module_path: address.module_path.clone(),
+                    mode: "managed".to_string(),
+                    resource_type: resource_type.to_string(),
+                    name: format!("{}_{}", address.name, n),
+                    index_key: None,
+                };
+                if !taken.contains(&extra.to_string()) {
+                    return extra;
+                }
+            }
+        })
+        .collect()
+}
@@ -2,8 +2,8 @@ use anyhow::Result;
 use async_trait::async_trait;
 
 use super::models::{
-    ImportResult, Lock, LockInfo, OutputValue, ResourceFilter, ResourceResult, ResourceState,
-    RunRecord, Workspace,
+    ImportResult, Lock, LockInfo, OutputValue, ResourceFilter, ResourceHistoryEntry,
+    ResourceResult, ResourceState, RunRecord, Workspace,
 };
 
 /// Pluggable state backend trait.
@@ -44,6 +44,16 @@ pub trait StateBackend: Send + Sync {
     /// Delete a resource from state.
     async fn delete_resource(&self, workspace_id: &str, address: &str) -> Result<()>;
 
+    /// Re-address the resource at `from_address` in place, taking the address,
+    /// name, module path and index key from `moved`. The record's id (and so
+    /// its dependency edges) is kept.
+    async fn move_resource(
+        &self,
+        workspace_id: &str,
+        from_address: &str,
+        moved: &ResourceState,
+    ) -> Result<()>;
+
     /// List resources with optional filtering.
     async fn list_resources(
         &self,
@@ -140,6 +150,17 @@ pub trait StateBackend: Send + Sync {
     /// Record a per-resource result within a run.
     async fn record_resource_result(&self, run_id: &str, result: &ResourceResult) -> Result<()>;
 
+    /// List the per-resource results recorded for a run.
+    async fn list_run_resources(&self, run_id: &str) -> Result<Vec<ResourceResult>>;
+
+    /// List a resource's results across runs, most recent first.
+    async fn list_resource_history(
+        &self,
+        workspace_id: &str,
+        address: &str,
+        limit: usize,
+    ) -> Result<Vec<ResourceHistoryEntry>>;
+
     /// Get the latest run for a workspace.
     async fn get_latest_run(&self, workspace_id: &str) -> Result<Option<RunRecord>>;
 
@@ -148,9 +169,19 @@ pub trait StateBackend: Send + Sync {
 
     // ─── Query ──────────────────────────────────────────────────────────────
 
-    /// Execute a raw SQL query against the state database.
-    /// Returns rows as JSON values.
-    async fn query_raw(&self, sql: &str) -> Result<Vec<serde_json::Value>>;
+    /// Execute a single read-only SQL statement against the state database,
+    /// binding `params` to its `?` placeholders in order. Statements that
+    /// would modify the database are rejected. Returns rows as JSON values.
+    async fn query_raw(&self, sql: &str, params: &[String]) -> Result<Vec<serde_json::Value>>;
+
+    /// Like `query_raw`, but hands each row to `on_row` as it is read instead
+    /// of collecting them. Returns the number of rows visited.
+    async fn query_each(
+        &self,
+        sql: &str,
+        params: &[String],
+        on_row: &mut (dyn FnMut(serde_json::Value) -> Result<()> + Send),
+    ) -> Result<usize>;
 
     // ─── Import ─────────────────────────────────────────────────────────────
 
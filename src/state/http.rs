@@ -0,0 +1,318 @@
+//! Read-only state from a Terraform `http` backend endpoint.
+//!
+//! The `.tfstate` JSON at `address` is fetched once when the backend opens
+//! and loaded into an in-memory `SqliteBackend`, so `oxid plan` and the
+//! `state` read commands can run against live Terraform state while a team
+//! migrates. Anything that would write state fails; use `oxid import` to
+//! move the state into an oxid backend first.
+//!
+//! Configured with a backend block:
+//!
+//! ```hcl
+//! terraform {
+//!   backend "http" {
+//!     address = "https://state.example.com/prod"
+//!   }
+//! }
+//! ```
+//!
+//! A bearer token for the endpoint is read from `OXID_HTTP_STATE_TOKEN`.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+
+use super::backend::StateBackend;
+use super::models::*;
+use super::sqlite::SqliteBackend;
+use crate::config::types::BackendConfig;
+
+/// Environment variable holding the bearer token for the state endpoint.
+pub const HTTP_TOKEN_ENV: &str = "OXID_HTTP_STATE_TOKEN";
+
+/// Workspace the remote state is loaded into.
+const WORKSPACE: &str = "default";
+
+/// Read-only state backend over a Terraform `http` backend endpoint.
+pub struct HttpBackend {
+    inner: SqliteBackend,
+    address: String,
+}
+
+impl HttpBackend {
+    /// Fetch the state at `address`, sending `token` as a bearer token. An
+    /// endpoint with no state yet (404 or an empty body) reads as empty.
+    pub async fn open(address: &str, token: Option<&str>) -> Result<Self> {
+        let mut request = reqwest::Client::new().get(address);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch state from {}", address))?;
+        let status = response.status();
+        let body = if status == reqwest::StatusCode::NOT_FOUND {
+            String::new()
+        } else if status.is_success() {
+            response.text().await?
+        } else {
+            bail!("Failed to fetch state from {}: HTTP {}", address, status);
+        };
+
+        let inner = SqliteBackend::open_memory()?;
+        inner.initialize().await?;
+        let workspace_id = inner.create_workspace(WORKSPACE).await?;
+        if !body.trim().is_empty() {
+            let state: TfState = serde_json::from_str(&body)
+                .with_context(|| format!("Failed to parse Terraform state from {}", address))?;
+            inner.import_tf_state(&workspace_id, &state)?;
+            inner.set_serial(&workspace_id, state.serial)?;
+        }
+
+        Ok(Self {
+            inner,
+            address: address.to_string(),
+        })
+    }
+
+    /// Open the backend described by a `backend "http"` block.
+    pub async fn from_config(backend: &BackendConfig) -> Result<Self> {
+        let Some(address) = backend.get_str("address") else {
+            bail!("backend \"http\": 'address' is required");
+        };
+        let token = std::env::var(HTTP_TOKEN_ENV).ok();
+        Self::open(&address, token.as_deref()).await
+    }
+
+    fn read_only<T>(&self) -> Result<T> {
+        bail!(
+            "The http state backend is read-only ({}); run `oxid import` to move the state into an oxid backend",
+            self.address
+        )
+    }
+}
+
+#[async_trait]
+impl StateBackend for HttpBackend {
+    // ─── Initialization ─────────────────────────────────────────────────────
+
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    // ─── Workspace Operations ───────────────────────────────────────────────
+
+    async fn create_workspace(&self, _name: &str) -> Result<String> {
+        self.read_only()
+    }
+
+    async fn get_workspace(&self, name: &str) -> Result<Option<Workspace>> {
+        self.inner.get_workspace(name).await
+    }
+
+    async fn list_workspaces(&self) -> Result<Vec<Workspace>> {
+        self.inner.list_workspaces().await
+    }
+
+    async fn delete_workspace(&self, _name: &str) -> Result<()> {
+        self.read_only()
+    }
+
+    // ─── Resource CRUD ──────────────────────────────────────────────────────
+
+    async fn get_resource(
+        &self,
+        workspace_id: &str,
+        address: &str,
+    ) -> Result<Option<ResourceState>> {
+        self.inner.get_resource(workspace_id, address).await
+    }
+
+    async fn upsert_resource(&self, _resource: &ResourceState) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn delete_resource(&self, _workspace_id: &str, _address: &str) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn move_resource(
+        &self,
+        _workspace_id: &str,
+        _from_address: &str,
+        _moved: &ResourceState,
+    ) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn list_resources(
+        &self,
+        workspace_id: &str,
+        filter: &ResourceFilter,
+    ) -> Result<Vec<ResourceState>> {
+        self.inner.list_resources(workspace_id, filter).await
+    }
+
+    async fn count_resources(&self, workspace_id: &str) -> Result<usize> {
+        self.inner.count_resources(workspace_id).await
+    }
+
+    // ─── Dependencies ───────────────────────────────────────────────────────
+
+    async fn set_dependencies(
+        &self,
+        _resource_id: &str,
+        _depends_on: &[(String, String)],
+    ) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn get_dependencies(&self, resource_id: &str) -> Result<Vec<String>> {
+        self.inner.get_dependencies(resource_id).await
+    }
+
+    async fn get_dependents(&self, resource_id: &str) -> Result<Vec<String>> {
+        self.inner.get_dependents(resource_id).await
+    }
+
+    // ─── Locking ────────────────────────────────────────────────────────────
+
+    async fn acquire_lock(
+        &self,
+        _address: &str,
+        _workspace_id: &str,
+        _info: &LockInfo,
+    ) -> Result<Lock> {
+        self.read_only()
+    }
+
+    async fn release_lock(&self, lock_id: &str) -> Result<()> {
+        self.inner.release_lock(lock_id).await
+    }
+
+    async fn force_unlock(&self, _address: &str, _workspace_id: &str) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn is_locked(&self, address: &str, workspace_id: &str) -> Result<Option<Lock>> {
+        self.inner.is_locked(address, workspace_id).await
+    }
+
+    // ─── Outputs ────────────────────────────────────────────────────────────
+
+    async fn set_output(
+        &self,
+        _workspace_id: &str,
+        _module_path: &str,
+        _name: &str,
+        _value: &str,
+        _sensitive: bool,
+    ) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn get_output(
+        &self,
+        workspace_id: &str,
+        module_path: &str,
+        name: &str,
+    ) -> Result<Option<OutputValue>> {
+        self.inner.get_output(workspace_id, module_path, name).await
+    }
+
+    async fn list_outputs(
+        &self,
+        workspace_id: &str,
+        module_path: Option<&str>,
+    ) -> Result<Vec<OutputValue>> {
+        self.inner.list_outputs(workspace_id, module_path).await
+    }
+
+    async fn clear_outputs(&self, _workspace_id: &str, _module_path: &str) -> Result<()> {
+        self.read_only()
+    }
+
+    // ─── Runs ───────────────────────────────────────────────────────────────
+
+    async fn start_run(
+        &self,
+        _workspace_id: &str,
+        _operation: &str,
+        _resources_planned: i32,
+    ) -> Result<String> {
+        self.read_only()
+    }
+
+    async fn complete_run(
+        &self,
+        _run_id: &str,
+        _status: &str,
+        _resources_succeeded: i32,
+        _resources_failed: i32,
+    ) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn record_resource_result(&self, _run_id: &str, _result: &ResourceResult) -> Result<()> {
+        self.read_only()
+    }
+
+    async fn list_run_resources(&self, run_id: &str) -> Result<Vec<ResourceResult>> {
+        self.inner.list_run_resources(run_id).await
+    }
+
+    async fn list_resource_history(
+        &self,
+        workspace_id: &str,
+        address: &str,
+        limit: usize,
+    ) -> Result<Vec<ResourceHistoryEntry>> {
+        self.inner
+            .list_resource_history(workspace_id, address, limit)
+            .await
+    }
+
+    async fn get_latest_run(&self, workspace_id: &str) -> Result<Option<RunRecord>> {
+        self.inner.get_latest_run(workspace_id).await
+    }
+
+    async fn list_runs(&self, workspace_id: &str, limit: usize) -> Result<Vec<RunRecord>> {
+        self.inner.list_runs(workspace_id, limit).await
+    }
+
+    // ─── Query ──────────────────────────────────────────────────────────────
+
+    async fn query_raw(&self, sql: &str, params: &[String]) -> Result<Vec<serde_json::Value>> {
+        self.inner.query_raw(sql, params).await
+    }
+
+    async fn query_each(
+        &self,
+        sql: &str,
+        params: &[String],
+        on_row: &mut (dyn FnMut(serde_json::Value) -> Result<()> + Send),
+    ) -> Result<usize> {
+        self.inner.query_each(sql, params, on_row).await
+    }
+
+    // ─── Import ─────────────────────────────────────────────────────────────
+
+    async fn import_tfstate(&self, _workspace_id: &str, _state_json: &str) -> Result<ImportResult> {
+        self.read_only()
+    }
+
+    // ─── Providers ──────────────────────────────────────────────────────────
+
+    async fn register_provider(
+        &self,
+        _workspace_id: &str,
+        _source: &str,
+        _version: &str,
+    ) -> Result<String> {
+        self.read_only()
+    }
+
+    async fn list_providers(&self, workspace_id: &str) -> Result<Vec<(String, String, String)>> {
+        self.inner.list_providers(workspace_id).await
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::types::BackendConfig;
+
+/// Environment variable that overrides the configured state backend.
+pub const STATE_DSN_ENV: &str = "OXID_STATE_DSN";
+
+/// Where state is stored, resolved from `OXID_STATE_DSN`, the
+/// `terraform { backend ... }` block, or the working directory default.
+#[derive(Debug, Clone)]
+pub enum StateLocation {
+    /// Local SQLite database file.
+    Sqlite(PathBuf),
+    /// PostgreSQL connection string.
+    Postgres(String),
+    /// S3 object described by a `backend "s3"` block.
+    S3(BackendConfig),
+    /// Read-only Terraform state described by a `backend "http"` block.
+    Http(BackendConfig),
+}
+
+/// Resolve the state location.
+///
+/// Precedence: `dsn` (from `OXID_STATE_DSN`), then the configured backend
+/// block, then `<working_dir>/oxid.db`.
+pub fn resolve_state_location(
+    dsn: Option<&str>,
+    backend: Option<&BackendConfig>,
+    working_dir: &str,
+) -> Result<StateLocation> {
+    if let Some(dsn) = dsn.filter(|d| !d.is_empty()) {
+        return parse_dsn(dsn).with_context(|| format!("Invalid {}", STATE_DSN_ENV));
+    }
+
+    let Some(backend) = backend else {
+        return Ok(default_location(working_dir));
+    };
+
+    match backend.backend_type.as_str() {
+        "local" => Ok(match backend.get_str("path") {
+            // Terraform's JSON state (e.g. CDKTF output) is not an oxid database
+            Some(path) if path.ends_with(".tfstate") => {
+                tracing::warn!(
+                    "Ignoring local backend path {} (Terraform state file); use `oxid import` to migrate it",
+                    path
+                );
+                default_location(working_dir)
+            }
+            Some(path) => StateLocation::Sqlite(PathBuf::from(path)),
+            None => default_location(working_dir),
+        }),
+        "s3" => Ok(StateLocation::S3(backend.clone())),
+        "http" => Ok(StateLocation::Http(backend.clone())),
+        "pg" => match backend.get_str("conn_str") {
+            Some(conn_str) => Ok(StateLocation::Postgres(conn_str)),
+            None => bail!("backend \"pg\": 'conn_str' is required"),
+        },
+        other => bail!(
+            "Unsupported backend \"{}\" (supported: local, s3, pg, http). Set {} to override.",
+            other,
+            STATE_DSN_ENV
+        ),
+    }
+}
+
+/// Parse a DSN: `postgres://...`, `sqlite://PATH`, or a bare file path.
+fn parse_dsn(dsn: &str) -> Result<StateLocation> {
+    if dsn.starts_with("postgres://") || dsn.starts_with("postgresql://") {
+        return Ok(StateLocation::Postgres(dsn.to_string()));
+    }
+    if let Some(path) = dsn.strip_prefix("sqlite://") {
+        if path.is_empty() {
+            bail!("sqlite DSN has no path: {}", dsn);
+        }
+        return Ok(StateLocation::Sqlite(PathBuf::from(path)));
+    }
+    if let Some((scheme, _)) = dsn.split_once("://") {
+        bail!("Unsupported scheme '{}' in {}", scheme, dsn);
+    }
+    Ok(StateLocation::Sqlite(PathBuf::from(dsn)))
+}
+
+fn default_location(working_dir: &str) -> StateLocation {
+    StateLocation::Sqlite(PathBuf::from(format!("{}/oxid.db", working_dir)))
+}
@@ -1,7 +1,11 @@
-use anyhow::{bail, Result};
+use anyhow::Result;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use super::backend::StateBackend;
+use super::models::{Lock, LockInfo};
+use crate::error::OxidError;
+
 /// File-based lock for concurrent safety.
 pub struct FileLock {
     lock_path: PathBuf,
@@ -16,10 +20,7 @@ impl FileLock {
         fs::create_dir_all(lock_path.parent().unwrap())?;
 
         if lock_path.exists() {
-            bail!(
-                "Module '{}' is locked. Another process may be running.",
-                module_name
-            );
+            return Err(OxidError::StateLocked(held_file_lock(&lock_path, module_name)).into());
         }
 
         let lock_info = format!(
@@ -41,8 +42,72 @@ impl FileLock {
     }
 }
 
+/// The lock described by an existing lock file, for the lock-conflict error.
+fn held_file_lock(lock_path: &Path, module_name: &str) -> Lock {
+    let content = fs::read_to_string(lock_path).unwrap_or_default();
+    let field = |name: &str| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .unwrap_or("unknown")
+            .to_string()
+    };
+    Lock {
+        resource_address: module_name.to_string(),
+        workspace_id: String::new(),
+        locked_at: field("time"),
+        locked_by: format!("pid {}", field("pid")),
+        lock_id: module_name.to_string(),
+        operation: format!("module {}", module_name),
+        expires_at: None,
+        info: Some(lock_path.display().to_string()),
+    }
+}
+
 impl Drop for FileLock {
     fn drop(&mut self) {
         let _ = fs::remove_file(&self.lock_path);
     }
 }
+
+// ─── Workspace Lock ─────────────────────────────────────────────────────────
+
+/// Reserved resource address of the lock that state-mutating commands hold
+/// on a whole workspace.
+pub const WORKSPACE_LOCK_ADDRESS: &str = "__workspace__";
+
+/// Take the workspace lock for `operation` (e.g. "apply"). If another
+/// process holds it, the error says who, since when, and how to break it.
+pub async fn acquire_workspace_lock(
+    backend: &dyn StateBackend,
+    workspace_id: &str,
+    operation: &str,
+) -> Result<Lock> {
+    let info = LockInfo {
+        locked_by: lock_owner(),
+        operation: operation.to_string(),
+        info: None,
+        ttl_secs: None,
+    };
+    match backend
+        .acquire_lock(WORKSPACE_LOCK_ADDRESS, workspace_id, &info)
+        .await
+    {
+        Ok(lock) => Ok(lock),
+        Err(e) => match backend
+            .is_locked(WORKSPACE_LOCK_ADDRESS, workspace_id)
+            .await?
+        {
+            Some(held) => Err(OxidError::StateLocked(held).into()),
+            None => Err(e),
+        },
+    }
+}
+
+/// `user (pid N)`, identifying the process holding a lock.
+fn lock_owner() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("{} (pid {})", user, std::process::id())
+}
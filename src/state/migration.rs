@@ -57,11 +57,63 @@ fn apply_migrations(conn: &Connection, from_version: i32) -> Result<()> {
         )?;
     }
 
-    // Migration 1 -> 2 would go here when schema changes
-    // if from_version < 2 {
+    if from_version < 2 {
+        // Migration 1 -> 2: opaque provider private data per resource
+        if !has_column(conn, "resources", "private_data")? {
+            conn.execute_batch("ALTER TABLE resources ADD COLUMN private_data BLOB;")?;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO schema_version (version, applied_at, description) VALUES (?1, ?2, ?3)",
+            rusqlite::params![2, now, "Add resources.private_data"],
+        )?;
+    }
+
+    if from_version < 3 {
+        // Migration 2 -> 3: captured provisioner output per run resource
+        if has_table(conn, "run_resources")? && !has_column(conn, "run_resources", "output")? {
+            conn.execute_batch("ALTER TABLE run_resources ADD COLUMN output TEXT;")?;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO schema_version (version, applied_at, description) VALUES (?1, ?2, ?3)",
+            rusqlite::params![3, now, "Add run_resources.output"],
+        )?;
+    }
+
+    if from_version < 4 {
+        // Migration 3 -> 4: state serial per workspace
+        if !has_column(conn, "workspaces", "serial")? {
+            conn.execute_batch(
+                "ALTER TABLE workspaces ADD COLUMN serial INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO schema_version (version, applied_at, description) VALUES (?1, ?2, ?3)",
+            rusqlite::params![4, now, "Add workspaces.serial"],
+        )?;
+    }
+
+    // Migration 4 -> 5 would go here when schema changes
+    // if from_version < 5 {
     //     conn.execute_batch("ALTER TABLE resources ADD COLUMN new_col TEXT;")?;
-    //     conn.execute("INSERT INTO schema_version ...", params![2, now, "Add new_col"])?;
+    //     conn.execute("INSERT INTO schema_version ...", params![5, now, "Add new_col"])?;
     // }
 
     Ok(())
 }
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names.iter().any(|n| n == column))
+}
+
+fn has_table(conn: &Connection, table: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?1",
+        [table],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
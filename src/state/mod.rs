@@ -1,8 +1,12 @@
+pub mod address;
 pub mod backend;
+pub mod http;
+pub mod location;
 pub mod lock;
 pub mod migration;
 pub mod models;
 pub mod query;
+pub mod s3;
 pub mod schema;
 pub mod sqlite;
 pub mod store;
@@ -18,6 +18,9 @@ pub struct ResourceState {
     pub attributes_json: String,
     pub sensitive_attrs: Vec<String>,
     pub schema_version: i32,
+    /// Opaque provider data from the last apply, handed back on plan and read.
+    #[serde(default, with = "base64_bytes")]
+    pub private_data: Vec<u8>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -44,12 +47,31 @@ impl ResourceState {
             attributes_json: "{}".to_string(),
             sensitive_attrs: vec![],
             schema_version: 0,
+            private_data: vec![],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
         }
     }
 }
 
+/// Serde adapter encoding opaque provider bytes as a base64 string.
+pub mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD
+            .decode(encoded.as_bytes())
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 /// Resource status values.
 pub mod status {
     pub const PLANNED: &str = "planned";
@@ -59,6 +81,8 @@ pub mod status {
     pub const DELETING: &str = "deleting";
     pub const DELETED: &str = "deleted";
     pub const TAINTED: &str = "tainted";
+    /// Old object awaiting destruction after a create_before_destroy replacement.
+    pub const DEPOSED: &str = "deposed";
     pub const FAILED: &str = "failed";
 }
 
@@ -70,6 +94,8 @@ pub struct Workspace {
     pub name: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Incremented on every write to the workspace's resources or outputs.
+    pub serial: u64,
 }
 
 // ─── Dependencies ───────────────────────────────────────────────────────────
@@ -145,12 +171,24 @@ pub struct ResourceResult {
     pub completed_at: Option<String>,
     pub error_message: Option<String>,
     pub diff_json: Option<String>,
+    /// Captured provisioner stdout/stderr.
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+/// A resource's result within a past run, as listed by `oxid state history`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceHistoryEntry {
+    pub run_id: String,
+    pub operation: String,
+    pub result: ResourceResult,
 }
 
 pub mod action {
     pub const CREATE: &str = "create";
     pub const UPDATE: &str = "update";
     pub const DELETE: &str = "delete";
+    pub const REPLACE: &str = "replace";
     pub const READ: &str = "read";
     pub const NOOP: &str = "no-op";
     pub const IMPORT: &str = "import";
@@ -163,14 +201,122 @@ pub mod run_status {
     pub const CANCELLED: &str = "cancelled";
 }
 
+/// Escape `text` for use in a SQL `LIKE` pattern with `\\` as the escape
+/// character, so it matches only itself.
+pub fn like_literal(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 // ─── Query Results ──────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Default)]
 pub struct ResourceFilter {
     pub resource_type: Option<String>,
+    /// Module address such as `module.network`. Matches resources in that
+    /// module, in each of its instances and in modules nested under it.
     pub module_path: Option<String>,
     pub status: Option<String>,
+    /// SQL `LIKE` pattern for the address, with `\\` as the escape character.
     pub address_pattern: Option<String>,
+    /// Maximum number of resources to return.
+    pub limit: Option<usize>,
+    /// Number of matching resources to skip.
+    pub offset: Option<usize>,
+    pub sort: ResourceSort,
+    /// Leave out objects deposed by a `create_before_destroy` replacement,
+    /// which are tracked as `<address> (deposed)` until destroyed.
+    pub exclude_deposed: bool,
+}
+
+impl ResourceFilter {
+    /// Parse a `--filter` spec: comma-separated `type=`, `module=` and
+    /// `status=` terms (e.g. `type=aws_vpc,status=created`). Unknown keys
+    /// are ignored.
+    pub fn parse(spec: &str) -> Self {
+        let mut filter = ResourceFilter::default();
+        for part in spec.split(',') {
+            if let Some((key, value)) = part.split_once('=') {
+                let value = Some(value.trim().to_string());
+                match key.trim() {
+                    "type" => filter.resource_type = value,
+                    "module" => filter.module_path = value,
+                    "status" => filter.status = value,
+                    _ => {}
+                }
+            }
+        }
+        filter
+    }
+
+    /// Match addresses against a shell-style glob: `*` stands for any run of
+    /// characters and `?` for any one character, so `aws_instance.web*`
+    /// matches `aws_instance.web` and each of its instances. Everything else,
+    /// including `_` and `%`, matches literally.
+    pub fn address_glob(glob: &str) -> Self {
+        let mut pattern = String::with_capacity(glob.len());
+        for c in glob.chars() {
+            match c {
+                '*' => pattern.push('%'),
+                '?' => pattern.push('_'),
+                c => pattern.push_str(&like_literal(&c.to_string())),
+            }
+        }
+        ResourceFilter {
+            address_pattern: Some(pattern),
+            ..Default::default()
+        }
+    }
+
+    /// Whether `address` contains glob wildcards for [`Self::address_glob`].
+    pub fn is_glob(address: &str) -> bool {
+        address.contains(['*', '?'])
+    }
+}
+
+/// Ordering of `list_resources` results. Ties are broken by address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResourceSort {
+    #[default]
+    Address,
+    ResourceType,
+    UpdatedAt,
+}
+
+impl ResourceSort {
+    pub const NAMES: [&'static str; 3] = ["address", "resource_type", "updated_at"];
+
+    /// The `resources` column to order by.
+    pub fn column(self) -> &'static str {
+        match self {
+            ResourceSort::Address => "address",
+            ResourceSort::ResourceType => "resource_type",
+            ResourceSort::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+impl std::str::FromStr for ResourceSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "address" => Ok(ResourceSort::Address),
+            "resource_type" | "type" => Ok(ResourceSort::ResourceType),
+            "updated_at" => Ok(ResourceSort::UpdatedAt),
+            other => anyhow::bail!(
+                "Unknown sort key '{}' (expected one of: {})",
+                other,
+                Self::NAMES.join(", ")
+            ),
+        }
+    }
 }
 
 // ─── Import ─────────────────────────────────────────────────────────────────
@@ -182,6 +328,21 @@ pub struct ImportResult {
     pub warnings: Vec<String>,
 }
 
+// ─── Snapshots ──────────────────────────────────────────────────────────────
+
+/// A serialized copy of the whole state database, as stored by remote backends.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// Snapshot format version.
+    pub version: u32,
+    /// Incremented on every write; lets readers detect newer state.
+    pub serial: u64,
+    /// Unique ID assigned when the state was first created.
+    pub lineage: String,
+    /// Rows of each table, keyed by table name.
+    pub tables: std::collections::BTreeMap<String, Vec<serde_json::Map<String, serde_json::Value>>>,
+}
+
 // ─── Legacy types (kept for backward compatibility) ─────────────────────────
 
 #[derive(Debug, Clone)]
@@ -200,3 +361,130 @@ pub struct ModuleOutput {
     pub output_key: String,
     pub output_value: String,
 }
+
+// ─── Terraform State ────────────────────────────────────────────────────────
+
+/// A Terraform `.tfstate` file, as read by `oxid import` and the `http`
+/// backend.
+#[derive(Debug, serde::Deserialize)]
+pub struct TfState {
+    #[serde(default)]
+    pub serial: u64,
+    #[serde(default)]
+    pub resources: Vec<TfStateResource>,
+    #[serde(default)]
+    pub outputs: std::collections::HashMap<String, TfOutput>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TfStateResource {
+    /// `module.NAME` path of the resource, absent in the root module.
+    #[serde(default)]
+    pub module: String,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub name: String,
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default)]
+    pub instances: Vec<TfInstance>,
+}
+
+fn default_mode() -> String {
+    "managed".to_string()
+}
+
+impl TfStateResource {
+    /// Oxid address of one of this resource's instances, e.g.
+    /// `module.net.aws_subnet.private["a"]`.
+    pub fn address(&self, instance: &TfInstance) -> String {
+        super::address::ResourceAddress {
+            module_path: self.module.clone(),
+            mode: self.mode.clone(),
+            resource_type: self.resource_type.clone(),
+            name: self.name.clone(),
+            index_key: instance.index_key(),
+        }
+        .to_string()
+    }
+
+    /// Oxid provider address (`hashicorp/aws`, `hashicorp/aws.west`) for
+    /// Terraform's `provider["registry.terraform.io/hashicorp/aws"].west`.
+    /// Sources on other registries keep their hostname.
+    pub fn provider_address(&self) -> String {
+        let provider = match self.provider.find("provider[\"") {
+            Some(start) => &self.provider[start + "provider[\"".len()..],
+            None => return self.provider.clone(),
+        };
+        let Some((source, alias)) = provider.split_once("\"]") else {
+            return self.provider.clone();
+        };
+        let source = source
+            .strip_prefix(crate::provider::registry::DEFAULT_REGISTRY_HOST)
+            .and_then(|s| s.strip_prefix('/'))
+            .unwrap_or(source);
+        crate::provider::manager::provider_address(source, alias.strip_prefix('.'))
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TfInstance {
+    /// Count index (a number) or for_each key (a string).
+    #[serde(default)]
+    pub index_key: Option<serde_json::Value>,
+    #[serde(default)]
+    pub schema_version: Option<i32>,
+    #[serde(default)]
+    pub attributes: serde_json::Value,
+    /// Paths of sensitive values, as lists of `get_attr`/`index` steps.
+    #[serde(default)]
+    pub sensitive_attributes: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Base64-encoded provider private data.
+    #[serde(default)]
+    pub private: Option<String>,
+    /// Key of a deposed object: a prior object kept while its
+    /// create_before_destroy replacement was created.
+    #[serde(default)]
+    pub deposed: Option<String>,
+}
+
+impl TfInstance {
+    /// The index key as oxid stores it in `resources.index_key`.
+    pub fn index_key(&self) -> Option<String> {
+        match self.index_key.as_ref()? {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(key) => Some(key.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Top-level attributes holding a sensitive value, the granularity oxid
+    /// tracks sensitivity at.
+    pub fn sensitive_attribute_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for path in &self.sensitive_attributes {
+            let name = path
+                .get(0)
+                .filter(|step| step["type"] == "get_attr")
+                .and_then(|step| step["value"].as_str());
+            if let Some(name) = name {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TfOutput {
+    pub value: serde_json::Value,
+    #[serde(rename = "type")]
+    pub output_type: Option<serde_json::Value>,
+    pub sensitive: Option<bool>,
+}
@@ -1,3 +1,5 @@
+use std::io::Write;
+
 use anyhow::{bail, Result};
 
 use super::backend::StateBackend;
@@ -8,6 +10,8 @@ pub enum QueryFormat {
     Table,
     Json,
     Csv,
+    /// One JSON object per line, written as rows are read.
+    Jsonl,
 }
 
 impl QueryFormat {
@@ -15,25 +19,32 @@ impl QueryFormat {
         match s.to_lowercase().as_str() {
             "json" => QueryFormat::Json,
             "csv" => QueryFormat::Csv,
+            "jsonl" => QueryFormat::Jsonl,
             _ => QueryFormat::Table,
         }
     }
 }
 
-/// Execute a user query and format the results.
+/// Execute a user query and format the results. Only a single `SELECT`
+/// (optionally with a leading `WITH`) is accepted; `params` are bound to its
+/// `?` placeholders in order. A non-empty `select` projects each row down to
+/// those dotted attribute paths.
 pub async fn execute_query(
     backend: &dyn StateBackend,
     sql: &str,
+    params: &[String],
+    select: &[String],
     format: QueryFormat,
 ) -> Result<String> {
-    // Basic safety: only allow SELECT queries
-    let trimmed = sql.trim().to_uppercase();
-    if !trimmed.starts_with("SELECT") {
-        bail!("Only SELECT queries are allowed. Use oxid commands for mutations.");
+    let statement = validate_statement(sql)?;
+    let paths = parse_paths(select)?;
+    let mut rows = backend.query_raw(statement, params).await?;
+    if !paths.is_empty() {
+        for row in &mut rows {
+            *row = project(row, &paths);
+        }
     }
 
-    let rows = backend.query_raw(sql).await?;
-
     if rows.is_empty() {
         return Ok("No results.".to_string());
     }
@@ -42,9 +53,108 @@ pub async fn execute_query(
         QueryFormat::Table => format_table(&rows),
         QueryFormat::Json => format_json(&rows),
         QueryFormat::Csv => format_csv(&rows),
+        QueryFormat::Jsonl => format_jsonl(&rows),
     }
 }
 
+/// Execute a user query and write each row to `out` as a single line of
+/// JSON as soon as it is read. Returns the number of rows written.
+pub async fn stream_query(
+    backend: &dyn StateBackend,
+    sql: &str,
+    params: &[String],
+    select: &[String],
+    out: &mut (dyn Write + Send),
+) -> Result<usize> {
+    let statement = validate_statement(sql)?;
+    let paths = parse_paths(select)?;
+    backend
+        .query_each(statement, params, &mut |row| {
+            let row = if paths.is_empty() {
+                row
+            } else {
+                project(&row, &paths)
+            };
+            serde_json::to_writer(&mut *out, &row)?;
+            out.write_all(b"\n")?;
+            out.flush()?;
+            Ok(())
+        })
+        .await
+}
+
+/// Parse `--select` paths such as `address` or `attributes.tags.Name`.
+fn parse_paths(select: &[String]) -> Result<Vec<(&str, Vec<&str>)>> {
+    select
+        .iter()
+        .map(|path| {
+            let path = path.trim();
+            let segments: Vec<&str> = path.split('.').collect();
+            if segments.iter().any(|s| s.is_empty()) {
+                bail!("Invalid attribute path '{}' in --select", path);
+            }
+            Ok((path, segments))
+        })
+        .collect()
+}
+
+/// Project `row` down to `paths`. Each path becomes a column named after the
+/// path itself; numeric segments index into lists, and paths that don't
+/// resolve are null.
+fn project(row: &serde_json::Value, paths: &[(&str, Vec<&str>)]) -> serde_json::Value {
+    let columns = paths
+        .iter()
+        .map(|(path, segments)| {
+            let value = segments
+                .iter()
+                .try_fold(row, |value, segment| match value {
+                    serde_json::Value::Object(map) => map.get(*segment),
+                    serde_json::Value::Array(items) => {
+                        segment.parse::<usize>().ok().and_then(|i| items.get(i))
+                    }
+                    _ => None,
+                })
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+            (path.to_string(), value)
+        })
+        .collect();
+    serde_json::Value::Object(columns)
+}
+
+/// Check that `sql` is a single `SELECT`/`WITH` statement and return it
+/// without surrounding whitespace or a trailing `;`.
+fn validate_statement(sql: &str) -> Result<&str> {
+    let statement = sql.trim().trim_end_matches(';').trim_end();
+    let keyword = statement
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+    if keyword != "SELECT" && keyword != "WITH" {
+        bail!("Only SELECT queries are allowed. Use oxid commands for mutations.");
+    }
+    if has_statement_separator(statement) {
+        bail!("Only a single statement is allowed.");
+    }
+    Ok(statement)
+}
+
+/// Whether `sql` has a `;` outside string literals and quoted identifiers.
+fn has_statement_separator(sql: &str) -> bool {
+    let mut quote: Option<char> = None;
+    for c in sql.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if matches!(c, '\'' | '"' | '`') => quote = Some(c),
+            None if c == ';' => return true,
+            None => {}
+        }
+    }
+    false
+}
+
 fn format_table(rows: &[serde_json::Value]) -> Result<String> {
     let first = rows[0].as_object().unwrap();
     let columns: Vec<String> = first.keys().cloned().collect();
@@ -100,36 +210,60 @@ fn format_json(rows: &[serde_json::Value]) -> Result<String> {
     Ok(serde_json::to_string_pretty(rows)?)
 }
 
+fn format_jsonl(rows: &[serde_json::Value]) -> Result<String> {
+    let lines = rows
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(lines.join("\n"))
+}
+
 fn format_csv(rows: &[serde_json::Value]) -> Result<String> {
     let first = rows[0].as_object().unwrap();
     let columns: Vec<String> = first.keys().cloned().collect();
 
-    let mut output = String::new();
+    let records: Vec<Vec<String>> = rows
+        .iter()
+        .filter_map(|row| row.as_object())
+        .map(|obj| {
+            columns
+                .iter()
+                .map(|col| obj.get(col).map(value_to_display).unwrap_or_default())
+                .collect()
+        })
+        .collect();
 
-    // Header
-    output.push_str(&columns.join(","));
-    output.push('\n');
+    Ok(format_delimited(&columns, &records, ','))
+}
 
-    // Rows
-    for row in rows {
-        if let Some(obj) = row.as_object() {
-            let vals: Vec<String> = columns
-                .iter()
-                .map(|col| {
-                    let val = obj.get(col).map(value_to_display).unwrap_or_default();
-                    if val.contains(',') || val.contains('"') {
-                        format!("\"{}\"", val.replace('"', "\"\""))
-                    } else {
-                        val
-                    }
-                })
-                .collect();
-            output.push_str(&vals.join(","));
-            output.push('\n');
+/// Render `records` as delimiter-separated values under a header of
+/// `columns`, one record per line. Fields containing the delimiter, a quote
+/// or a line break are quoted, with quotes doubled (RFC 4180), so `,` gives
+/// CSV and `\t` TSV that spreadsheets read back intact.
+pub fn format_delimited<S: AsRef<str>>(
+    columns: &[S],
+    records: &[Vec<String>],
+    delimiter: char,
+) -> String {
+    let separator = delimiter.to_string();
+    let quote = |field: &str| {
+        if field.contains([delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
         }
-    }
+    };
 
-    Ok(output)
+    let mut output = String::new();
+    let header: Vec<String> = columns.iter().map(|c| quote(c.as_ref())).collect();
+    output.push_str(&header.join(&separator));
+    output.push('\n');
+    for record in records {
+        let fields: Vec<String> = record.iter().map(|f| quote(f)).collect();
+        output.push_str(&fields.join(&separator));
+        output.push('\n');
+    }
+    output
 }
 
 fn value_to_display(v: &serde_json::Value) -> String {
@@ -0,0 +1,940 @@
+//! Remote state stored in S3, with optional DynamoDB locking.
+//!
+//! The whole state database is kept in memory (an in-memory `SqliteBackend`)
+//! and written to a single S3 object as a JSON `StateSnapshot`. Resource locks
+//! are created with a conditional `PutItem` on a DynamoDB table so that
+//! concurrent runs on different machines exclude each other.
+//!
+//! Taking the workspace lock re-reads the object, so a locked command always
+//! works on the latest state. While the lock is held, mutations are written
+//! once, when it is released; without it, every mutation is written. Writes
+//! are conditional on the object's ETag, so state changed by another process
+//! since it was read is never overwritten.
+//!
+//! Configured with a backend block:
+//!
+//! ```hcl
+//! terraform {
+//!   backend "s3" {
+//!     bucket         = "my-oxid-state"
+//!     key            = "prod/oxid.json"
+//!     region         = "us-east-1"
+//!     dynamodb_table = "oxid-locks"   # optional, partition key "LockID" (string)
+//!     encrypt        = true           # optional, SSE-S3
+//!   }
+//! }
+//! ```
+//!
+//! Credentials are read from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+//! (optionally) `AWS_SESSION_TOKEN`. The caller needs `s3:GetObject` and
+//! `s3:PutObject` on the state object (conditional writes use `If-Match` /
+//! `If-None-Match`), `s3:ListBucket` on the bucket (so a
+//! missing object reads as 404 rather than 403), and `dynamodb:GetItem`,
+//! `dynamodb:PutItem` and `dynamodb:DeleteItem` on the lock table.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use super::backend::StateBackend;
+use super::lock::WORKSPACE_LOCK_ADDRESS;
+use super::models::*;
+use super::sqlite::SqliteBackend;
+use crate::config::types::BackendConfig;
+
+// ─── Remote Store ───────────────────────────────────────────────────────────
+
+/// The state object as read from the store.
+#[derive(Debug, Clone)]
+pub struct RemoteObject {
+    pub body: Vec<u8>,
+    /// Entity tag of this version of the object.
+    pub etag: String,
+}
+
+/// Storage operations needed by the S3 backend. Implemented against AWS by
+/// [`AwsStateStore`]; tests substitute an in-memory store.
+#[async_trait]
+pub trait RemoteStateStore: Send + Sync {
+    /// Read the state object. Returns `None` if it does not exist yet.
+    async fn read_state(&self) -> Result<Option<RemoteObject>>;
+
+    /// Overwrite the state object if its entity tag is still `expected`
+    /// (`None`: only if the object does not exist), returning the new tag.
+    async fn write_state(&self, body: &[u8], expected: Option<&str>) -> Result<String>;
+
+    /// Create a lock entry. Fails if an entry with the same key already exists.
+    async fn put_lock(&self, key: &str, lock: &Lock) -> Result<()>;
+
+    /// Delete a lock entry (no error if it does not exist).
+    async fn delete_lock(&self, key: &str) -> Result<()>;
+
+    /// Read a lock entry.
+    async fn get_lock(&self, key: &str) -> Result<Option<Lock>>;
+}
+
+// ─── Backend ────────────────────────────────────────────────────────────────
+
+/// State backend that keeps state in a remote object store.
+pub struct S3Backend {
+    inner: SqliteBackend,
+    store: Box<dyn RemoteStateStore>,
+    /// Prefix for lock keys, so several states can share one lock table.
+    lock_prefix: String,
+    serial: AtomicU64,
+    lineage: Mutex<String>,
+    /// Entity tag of the object the in-memory state was last read from or
+    /// written to; `None` if it does not exist yet.
+    etag: Mutex<Option<String>>,
+    /// Lock ID of the workspace lock, while this process holds it.
+    workspace_lock: Mutex<Option<String>>,
+    /// Mutations not yet written, deferred while the workspace lock is held.
+    dirty: AtomicBool,
+    /// Lock ID → lock key for locks held by this process.
+    held_locks: DashMap<String, String>,
+}
+
+impl S3Backend {
+    /// Load state from the store (or start empty if the object doesn't exist).
+    pub async fn open(store: Box<dyn RemoteStateStore>, lock_prefix: &str) -> Result<Self> {
+        let inner = SqliteBackend::open_memory()?;
+        inner.initialize().await?;
+
+        let backend = Self {
+            inner,
+            store,
+            lock_prefix: lock_prefix.to_string(),
+            serial: AtomicU64::new(0),
+            lineage: Mutex::new(uuid::Uuid::new_v4().to_string()),
+            etag: Mutex::new(None),
+            workspace_lock: Mutex::new(None),
+            dirty: AtomicBool::new(false),
+            held_locks: DashMap::new(),
+        };
+        backend.load().await?;
+        Ok(backend)
+    }
+
+    /// Open the backend described by a `backend "s3"` block.
+    pub async fn from_config(backend: &BackendConfig) -> Result<Self> {
+        let config = S3Config::from_backend(backend)?;
+        let lock_prefix = format!("{}/{}", config.bucket, config.key);
+        if config.dynamodb_table.is_none() {
+            tracing::warn!(
+                "S3 backend has no dynamodb_table; locks will not be shared between machines"
+            );
+        }
+        let store = AwsStateStore::new(config, AwsCredentials::from_env()?);
+        Self::open(Box::new(store), &lock_prefix).await
+    }
+
+    /// Current state serial (incremented on every write).
+    pub fn serial(&self) -> u64 {
+        self.serial.load(Ordering::SeqCst)
+    }
+
+    /// Replace the in-memory state with the remote object, unless it is the
+    /// version already loaded.
+    async fn load(&self) -> Result<()> {
+        let Some(object) = self.store.read_state().await? else {
+            return Ok(());
+        };
+        if self.etag.lock().unwrap().as_deref() == Some(object.etag.as_str()) {
+            return Ok(());
+        }
+        let snapshot: StateSnapshot =
+            serde_json::from_slice(&object.body).context("Failed to parse remote state")?;
+        self.inner.import_snapshot(&snapshot)?;
+        self.serial.store(snapshot.serial, Ordering::SeqCst);
+        *self.lineage.lock().unwrap() = snapshot.lineage;
+        *self.etag.lock().unwrap() = Some(object.etag);
+        Ok(())
+    }
+
+    /// Write the current state to the remote store, if the remote object is
+    /// still the version this state was read from.
+    async fn flush(&self) -> Result<()> {
+        let serial = self.serial() + 1;
+        let mut snapshot = self.inner.export_snapshot()?;
+        snapshot.serial = serial;
+        snapshot.lineage = self.lineage.lock().unwrap().clone();
+        let body = serde_json::to_vec(&snapshot)?;
+        let expected = self.etag.lock().unwrap().clone();
+        let etag = self
+            .store
+            .write_state(&body, expected.as_deref())
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to write remote state (serial {}); it may have been changed by \
+                     another process since it was read",
+                    serial
+                )
+            })?;
+        self.serial.store(serial, Ordering::SeqCst);
+        *self.etag.lock().unwrap() = Some(etag);
+        self.dirty.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Record a mutation: written now, or when the workspace lock is released.
+    async fn mutated(&self) -> Result<()> {
+        if self.workspace_lock.lock().unwrap().is_some() {
+            self.dirty.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+        self.flush().await
+    }
+
+    fn lock_key(&self, workspace_id: &str, address: &str) -> String {
+        format!("{}/{}/{}", self.lock_prefix, workspace_id, address)
+    }
+}
+
+#[async_trait]
+impl StateBackend for S3Backend {
+    // ─── Initialization ─────────────────────────────────────────────────────
+
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    // ─── Workspace Operations ───────────────────────────────────────────────
+
+    async fn create_workspace(&self, name: &str) -> Result<String> {
+        let id = self.inner.create_workspace(name).await?;
+        self.mutated().await?;
+        Ok(id)
+    }
+
+    async fn get_workspace(&self, name: &str) -> Result<Option<Workspace>> {
+        self.inner.get_workspace(name).await
+    }
+
+    async fn list_workspaces(&self) -> Result<Vec<Workspace>> {
+        self.inner.list_workspaces().await
+    }
+
+    async fn delete_workspace(&self, name: &str) -> Result<()> {
+        self.inner.delete_workspace(name).await?;
+        self.mutated().await
+    }
+
+    // ─── Resource CRUD ──────────────────────────────────────────────────────
+
+    async fn get_resource(
+        &self,
+        workspace_id: &str,
+        address: &str,
+    ) -> Result<Option<ResourceState>> {
+        self.inner.get_resource(workspace_id, address).await
+    }
+
+    async fn upsert_resource(&self, resource: &ResourceState) -> Result<()> {
+        self.inner.upsert_resource(resource).await?;
+        self.mutated().await
+    }
+
+    async fn delete_resource(&self, workspace_id: &str, address: &str) -> Result<()> {
+        self.inner.delete_resource(workspace_id, address).await?;
+        self.mutated().await
+    }
+
+    async fn move_resource(
+        &self,
+        workspace_id: &str,
+        from_address: &str,
+        moved: &ResourceState,
+    ) -> Result<()> {
+        self.inner
+            .move_resource(workspace_id, from_address, moved)
+            .await?;
+        self.mutated().await
+    }
+
+    async fn list_resources(
+        &self,
+        workspace_id: &str,
+        filter: &ResourceFilter,
+    ) -> Result<Vec<ResourceState>> {
+        self.inner.list_resources(workspace_id, filter).await
+    }
+
+    async fn count_resources(&self, workspace_id: &str) -> Result<usize> {
+        self.inner.count_resources(workspace_id).await
+    }
+
+    // ─── Dependencies ───────────────────────────────────────────────────────
+
+    async fn set_dependencies(
+        &self,
+        resource_id: &str,
+        depends_on: &[(String, String)],
+    ) -> Result<()> {
+        self.inner.set_dependencies(resource_id, depends_on).await?;
+        self.mutated().await
+    }
+
+    async fn get_dependencies(&self, resource_id: &str) -> Result<Vec<String>> {
+        self.inner.get_dependencies(resource_id).await
+    }
+
+    async fn get_dependents(&self, resource_id: &str) -> Result<Vec<String>> {
+        self.inner.get_dependents(resource_id).await
+    }
+
+    // ─── Locking ────────────────────────────────────────────────────────────
+
+    async fn acquire_lock(
+        &self,
+        address: &str,
+        workspace_id: &str,
+        info: &LockInfo,
+    ) -> Result<Lock> {
+        let lock = self.inner.acquire_lock(address, workspace_id, info).await?;
+        let key = self.lock_key(workspace_id, address);
+
+        if let Err(e) = self.store.put_lock(&key, &lock).await {
+            self.inner.release_lock(&lock.lock_id).await?;
+            let holder = self
+                .store
+                .get_lock(&key)
+                .await
+                .ok()
+                .flatten()
+                .map(|l| format!(" by {} ({})", l.locked_by, l.operation))
+                .unwrap_or_default();
+            return Err(e.context(format!("Resource {} is already locked{}", address, holder)));
+        }
+
+        self.held_locks.insert(lock.lock_id.clone(), key);
+        if address == WORKSPACE_LOCK_ADDRESS {
+            // Work on the state as of now, not as of when it was opened
+            if let Err(e) = self.load().await {
+                self.release_lock(&lock.lock_id).await?;
+                return Err(e);
+            }
+            *self.workspace_lock.lock().unwrap() = Some(lock.lock_id.clone());
+        }
+        Ok(lock)
+    }
+
+    async fn release_lock(&self, lock_id: &str) -> Result<()> {
+        let mut flushed = Ok(());
+        let is_workspace_lock = self.workspace_lock.lock().unwrap().as_deref() == Some(lock_id);
+        if is_workspace_lock {
+            *self.workspace_lock.lock().unwrap() = None;
+            if self.dirty.load(Ordering::SeqCst) {
+                flushed = self.flush().await;
+            }
+        }
+        if let Some((_, key)) = self.held_locks.remove(lock_id) {
+            self.store.delete_lock(&key).await?;
+        }
+        self.inner.release_lock(lock_id).await?;
+        flushed
+    }
+
+    async fn force_unlock(&self, address: &str, workspace_id: &str) -> Result<()> {
+        let key = self.lock_key(workspace_id, address);
+        self.store.delete_lock(&key).await?;
+        self.held_locks.retain(|_, k| *k != key);
+        if address == WORKSPACE_LOCK_ADDRESS && self.workspace_lock.lock().unwrap().take().is_some()
+        {
+            self.flush().await?;
+        }
+        self.inner.force_unlock(address, workspace_id).await
+    }
+
+    async fn is_locked(&self, address: &str, workspace_id: &str) -> Result<Option<Lock>> {
+        if let Some(lock) = self.inner.is_locked(address, workspace_id).await? {
+            return Ok(Some(lock));
+        }
+        self.store
+            .get_lock(&self.lock_key(workspace_id, address))
+            .await
+    }
+
+    // ─── Outputs ────────────────────────────────────────────────────────────
+
+    async fn set_output(
+        &self,
+        workspace_id: &str,
+        module_path: &str,
+        name: &str,
+        value: &str,
+        sensitive: bool,
+    ) -> Result<()> {
+        self.inner
+            .set_output(workspace_id, module_path, name, value, sensitive)
+            .await?;
+        self.mutated().await
+    }
+
+    async fn get_output(
+        &self,
+        workspace_id: &str,
+        module_path: &str,
+        name: &str,
+    ) -> Result<Option<OutputValue>> {
+        self.inner.get_output(workspace_id, module_path, name).await
+    }
+
+    async fn list_outputs(
+        &self,
+        workspace_id: &str,
+        module_path: Option<&str>,
+    ) -> Result<Vec<OutputValue>> {
+        self.inner.list_outputs(workspace_id, module_path).await
+    }
+
+    async fn clear_outputs(&self, workspace_id: &str, module_path: &str) -> Result<()> {
+        self.inner.clear_outputs(workspace_id, module_path).await?;
+        self.mutated().await
+    }
+
+    // ─── Runs ───────────────────────────────────────────────────────────────
+
+    async fn start_run(
+        &self,
+        workspace_id: &str,
+        operation: &str,
+        resources_planned: i32,
+    ) -> Result<String> {
+        let id = self
+            .inner
+            .start_run(workspace_id, operation, resources_planned)
+            .await?;
+        self.mutated().await?;
+        Ok(id)
+    }
+
+    async fn complete_run(
+        &self,
+        run_id: &str,
+        status: &str,
+        resources_succeeded: i32,
+        resources_failed: i32,
+    ) -> Result<()> {
+        self.inner
+            .complete_run(run_id, status, resources_succeeded, resources_failed)
+            .await?;
+        self.mutated().await
+    }
+
+    async fn record_resource_result(&self, run_id: &str, result: &ResourceResult) -> Result<()> {
+        self.inner.record_resource_result(run_id, result).await?;
+        self.mutated().await
+    }
+
+    async fn list_run_resources(&self, run_id: &str) -> Result<Vec<ResourceResult>> {
+        self.inner.list_run_resources(run_id).await
+    }
+
+    async fn list_resource_history(
+        &self,
+        workspace_id: &str,
+        address: &str,
+        limit: usize,
+    ) -> Result<Vec<ResourceHistoryEntry>> {
+        self.inner
+            .list_resource_history(workspace_id, address, limit)
+            .await
+    }
+
+    async fn get_latest_run(&self, workspace_id: &str) -> Result<Option<RunRecord>> {
+        self.inner.get_latest_run(workspace_id).await
+    }
+
+    async fn list_runs(&self, workspace_id: &str, limit: usize) -> Result<Vec<RunRecord>> {
+        self.inner.list_runs(workspace_id, limit).await
+    }
+
+    // ─── Query ──────────────────────────────────────────────────────────────
+
+    async fn query_raw(&self, sql: &str, params: &[String]) -> Result<Vec<serde_json::Value>> {
+        self.inner.query_raw(sql, params).await
+    }
+
+    async fn query_each(
+        &self,
+        sql: &str,
+        params: &[String],
+        on_row: &mut (dyn FnMut(serde_json::Value) -> Result<()> + Send),
+    ) -> Result<usize> {
+        self.inner.query_each(sql, params, on_row).await
+    }
+
+    // ─── Import ─────────────────────────────────────────────────────────────
+
+    async fn import_tfstate(&self, workspace_id: &str, state_json: &str) -> Result<ImportResult> {
+        let result = self.inner.import_tfstate(workspace_id, state_json).await?;
+        self.mutated().await?;
+        Ok(result)
+    }
+
+    // ─── Providers ──────────────────────────────────────────────────────────
+
+    async fn register_provider(
+        &self,
+        workspace_id: &str,
+        source: &str,
+        version: &str,
+    ) -> Result<String> {
+        let id = self
+            .inner
+            .register_provider(workspace_id, source, version)
+            .await?;
+        self.mutated().await?;
+        Ok(id)
+    }
+
+    async fn list_providers(&self, workspace_id: &str) -> Result<Vec<(String, String, String)>> {
+        self.inner.list_providers(workspace_id).await
+    }
+}
+
+// ─── Configuration ──────────────────────────────────────────────────────────
+
+/// Settings from a `backend "s3"` block.
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub bucket: String,
+    pub key: String,
+    pub region: String,
+    pub dynamodb_table: Option<String>,
+    /// Custom S3 endpoint (e.g. MinIO); uses path-style addressing.
+    pub endpoint: Option<String>,
+    pub dynamodb_endpoint: Option<String>,
+    pub encrypt: bool,
+}
+
+impl S3Config {
+    pub fn from_backend(backend: &BackendConfig) -> Result<Self> {
+        if backend.backend_type != "s3" {
+            bail!("Expected an s3 backend, got '{}'", backend.backend_type);
+        }
+        let required = |key: &str| {
+            backend
+                .get_str(key)
+                .with_context(|| format!("backend \"s3\": '{}' is required", key))
+        };
+        let region = backend
+            .get_str("region")
+            .or_else(|| std::env::var("AWS_REGION").ok())
+            .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+            .context("backend \"s3\": 'region' is required (or set AWS_REGION)")?;
+
+        Ok(Self {
+            bucket: required("bucket")?,
+            key: required("key")?,
+            region,
+            dynamodb_table: backend.get_str("dynamodb_table"),
+            endpoint: backend.get_str("endpoint"),
+            dynamodb_endpoint: backend.get_str("dynamodb_endpoint"),
+            encrypt: backend.get_bool("encrypt").unwrap_or(false),
+        })
+    }
+}
+
+/// Static AWS credentials.
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            access_key_id: std::env::var("AWS_ACCESS_KEY_ID")
+                .context("AWS_ACCESS_KEY_ID must be set to use the s3 backend")?,
+            secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY")
+                .context("AWS_SECRET_ACCESS_KEY must be set to use the s3 backend")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+// ─── AWS Store ──────────────────────────────────────────────────────────────
+
+/// [`RemoteStateStore`] backed by S3 (state object) and DynamoDB (locks).
+pub struct AwsStateStore {
+    config: S3Config,
+    credentials: AwsCredentials,
+    client: reqwest::Client,
+}
+
+impl AwsStateStore {
+    pub fn new(config: S3Config, credentials: AwsCredentials) -> Self {
+        Self {
+            config,
+            credentials,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn object_url(&self) -> String {
+        let key = uri_encode(&self.config.key, false);
+        match self.config.endpoint {
+            Some(ref endpoint) => format!(
+                "{}/{}/{}",
+                endpoint.trim_end_matches('/'),
+                self.config.bucket,
+                key
+            ),
+            None => format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                self.config.bucket, self.config.region, key
+            ),
+        }
+    }
+
+    fn dynamodb_url(&self) -> String {
+        match self.config.dynamodb_endpoint {
+            Some(ref endpoint) => format!("{}/", endpoint.trim_end_matches('/')),
+            None => format!("https://dynamodb.{}.amazonaws.com/", self.config.region),
+        }
+    }
+
+    /// Build a SigV4-signed request.
+    fn signed(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        service: &str,
+        mut headers: BTreeMap<String, String>,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder> {
+        let parsed = reqwest::Url::parse(url).with_context(|| format!("Invalid URL {}", url))?;
+        let host = match parsed.port() {
+            Some(port) => format!("{}:{}", parsed.host_str().unwrap_or_default(), port),
+            None => parsed.host_str().unwrap_or_default().to_string(),
+        };
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        headers.insert("host".to_string(), host);
+        headers.insert("x-amz-date".to_string(), amz_date.clone());
+        headers.insert("x-amz-content-sha256".to_string(), payload_hash.clone());
+        if let Some(ref token) = self.credentials.session_token {
+            headers.insert("x-amz-security-token".to_string(), token.clone());
+        }
+
+        let authorization = sign_v4(
+            &SigningRequest {
+                method: method.as_str(),
+                path: parsed.path(),
+                query: parsed.query().unwrap_or(""),
+                headers: &headers,
+                payload_hash: &payload_hash,
+                region: &self.config.region,
+                service,
+                amz_date: &amz_date,
+            },
+            &self.credentials,
+        );
+
+        let mut request = self.client.request(method, parsed).body(body);
+        for (name, value) in &headers {
+            if name != "host" {
+                request = request.header(name, value);
+            }
+        }
+        Ok(request.header("authorization", authorization))
+    }
+
+    /// Call a DynamoDB JSON API action.
+    async fn dynamodb(
+        &self,
+        action: &str,
+        payload: serde_json::Value,
+    ) -> Result<(reqwest::StatusCode, serde_json::Value)> {
+        let headers = BTreeMap::from([
+            (
+                "content-type".to_string(),
+                "application/x-amz-json-1.0".to_string(),
+            ),
+            (
+                "x-amz-target".to_string(),
+                format!("DynamoDB_20120810.{}", action),
+            ),
+        ]);
+        let response = self
+            .signed(
+                reqwest::Method::POST,
+                &self.dynamodb_url(),
+                "dynamodb",
+                headers,
+                serde_json::to_vec(&payload)?,
+            )?
+            .send()
+            .await
+            .with_context(|| format!("DynamoDB {} request failed", action))?;
+        let status = response.status();
+        let body = response.json().await.unwrap_or(serde_json::Value::Null);
+        Ok((status, body))
+    }
+}
+
+#[async_trait]
+impl RemoteStateStore for AwsStateStore {
+    async fn read_state(&self) -> Result<Option<RemoteObject>> {
+        let response = self
+            .signed(
+                reqwest::Method::GET,
+                &self.object_url(),
+                "s3",
+                BTreeMap::new(),
+                Vec::new(),
+            )?
+            .send()
+            .await
+            .context("S3 GetObject request failed")?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let etag = response_etag(&response)?;
+                Ok(Some(RemoteObject {
+                    body: response.bytes().await?.to_vec(),
+                    etag,
+                }))
+            }
+            status => bail!(
+                "S3 GetObject s3://{}/{} failed ({}): {}",
+                self.config.bucket,
+                self.config.key,
+                status,
+                response.text().await.unwrap_or_default()
+            ),
+        }
+    }
+
+    async fn write_state(&self, body: &[u8], expected: Option<&str>) -> Result<String> {
+        let mut headers =
+            BTreeMap::from([("content-type".to_string(), "application/json".to_string())]);
+        match expected {
+            Some(etag) => headers.insert("if-match".to_string(), etag.to_string()),
+            None => headers.insert("if-none-match".to_string(), "*".to_string()),
+        };
+        if self.config.encrypt {
+            headers.insert(
+                "x-amz-server-side-encryption".to_string(),
+                "AES256".to_string(),
+            );
+        }
+        let response = self
+            .signed(
+                reqwest::Method::PUT,
+                &self.object_url(),
+                "s3",
+                headers,
+                body.to_vec(),
+            )?
+            .send()
+            .await
+            .context("S3 PutObject request failed")?;
+        if matches!(
+            response.status(),
+            reqwest::StatusCode::PRECONDITION_FAILED | reqwest::StatusCode::CONFLICT
+        ) {
+            bail!(
+                "s3://{}/{} was changed by another process",
+                self.config.bucket,
+                self.config.key
+            );
+        }
+        if !response.status().is_success() {
+            bail!(
+                "S3 PutObject s3://{}/{} failed ({}): {}",
+                self.config.bucket,
+                self.config.key,
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+        }
+        response_etag(&response)
+    }
+
+    async fn put_lock(&self, key: &str, lock: &Lock) -> Result<()> {
+        let Some(ref table) = self.config.dynamodb_table else {
+            return Ok(());
+        };
+        let (status, body) = self
+            .dynamodb(
+                "PutItem",
+                serde_json::json!({
+                    "TableName": table,
+                    "Item": {
+                        "LockID": { "S": key },
+                        "Info": { "S": serde_json::to_string(lock)? },
+                    },
+                    "ConditionExpression": "attribute_not_exists(LockID)",
+                }),
+            )
+            .await?;
+        if status.is_success() {
+            return Ok(());
+        }
+        let error_type = body["__type"].as_str().unwrap_or_default();
+        if error_type.ends_with("ConditionalCheckFailedException") {
+            bail!("Lock {} is held by another process", key);
+        }
+        bail!(
+            "DynamoDB PutItem on {} failed ({}): {}",
+            table,
+            status,
+            body
+        );
+    }
+
+    async fn delete_lock(&self, key: &str) -> Result<()> {
+        let Some(ref table) = self.config.dynamodb_table else {
+            return Ok(());
+        };
+        let (status, body) = self
+            .dynamodb(
+                "DeleteItem",
+                serde_json::json!({
+                    "TableName": table,
+                    "Key": { "LockID": { "S": key } },
+                }),
+            )
+            .await?;
+        if !status.is_success() {
+            bail!(
+                "DynamoDB DeleteItem on {} failed ({}): {}",
+                table,
+                status,
+                body
+            );
+        }
+        Ok(())
+    }
+
+    async fn get_lock(&self, key: &str) -> Result<Option<Lock>> {
+        let Some(ref table) = self.config.dynamodb_table else {
+            return Ok(None);
+        };
+        let (status, body) = self
+            .dynamodb(
+                "GetItem",
+                serde_json::json!({
+                    "TableName": table,
+                    "Key": { "LockID": { "S": key } },
+                    "ConsistentRead": true,
+                }),
+            )
+            .await?;
+        if !status.is_success() {
+            bail!(
+                "DynamoDB GetItem on {} failed ({}): {}",
+                table,
+                status,
+                body
+            );
+        }
+        match body["Item"]["Info"]["S"].as_str() {
+            Some(info) => Ok(Some(serde_json::from_str(info)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The `ETag` header of an S3 response.
+fn response_etag(response: &reqwest::Response) -> Result<String> {
+    response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string)
+        .context("S3 response has no ETag")
+}
+
+// ─── SigV4 ──────────────────────────────────────────────────────────────────
+
+/// The parts of an HTTP request covered by an AWS Signature Version 4.
+pub struct SigningRequest<'a> {
+    pub method: &'a str,
+    /// URI path, already percent-encoded.
+    pub path: &'a str,
+    /// Query string, already percent-encoded and sorted.
+    pub query: &'a str,
+    /// Headers to sign, with lowercase names. Must include `host` and `x-amz-date`.
+    pub headers: &'a BTreeMap<String, String>,
+    pub payload_hash: &'a str,
+    pub region: &'a str,
+    pub service: &'a str,
+    /// Request timestamp, `YYYYMMDDTHHMMSSZ`.
+    pub amz_date: &'a str,
+}
+
+/// Compute the `Authorization` header value for a request.
+pub fn sign_v4(req: &SigningRequest, credentials: &AwsCredentials) -> String {
+    let signed_headers = req
+        .headers
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers: String = req
+        .headers
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+        .collect();
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        req.method, req.path, req.query, canonical_headers, signed_headers, req.payload_hash
+    );
+
+    let date = &req.amz_date[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, req.region, req.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        req.amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date,
+    );
+    let k_region = hmac_sha256(&k_date, req.region);
+    let k_service = hmac_sha256(&k_region, req.service);
+    let k_signing = hmac_sha256(&k_service, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id, scope, signed_headers, signature
+    )
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode per RFC 3986, as required by SigV4 canonical URIs.
+fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
@@ -4,7 +4,7 @@
 ///
 /// Compatible with both SQLite and PostgreSQL (using TEXT for timestamps
 /// and TEXT for JSON instead of JSONB to keep dialect-agnostic).
-pub const SCHEMA_VERSION: i32 = 1;
+pub const SCHEMA_VERSION: i32 = 4;
 
 pub const CREATE_TABLES_SQL: &str = "
 -- Schema version tracking
@@ -18,6 +18,7 @@ CREATE TABLE IF NOT EXISTS schema_version (
 CREATE TABLE IF NOT EXISTS workspaces (
     id TEXT PRIMARY KEY,
     name TEXT NOT NULL UNIQUE,
+    serial INTEGER NOT NULL DEFAULT 0,
     created_at TEXT NOT NULL,
     updated_at TEXT NOT NULL
 );
@@ -48,6 +49,7 @@ CREATE TABLE IF NOT EXISTS resources (
     attributes_json TEXT NOT NULL DEFAULT '{}',
     sensitive_attrs TEXT NOT NULL DEFAULT '[]',
     schema_version INTEGER DEFAULT 0,
+    private_data BLOB,
     created_at TEXT NOT NULL,
     updated_at TEXT NOT NULL,
     UNIQUE(workspace_id, address),
@@ -115,6 +117,7 @@ CREATE TABLE IF NOT EXISTS run_resources (
     completed_at TEXT,
     error_message TEXT,
     diff_json TEXT,
+    output TEXT,
     PRIMARY KEY (run_id, resource_address),
     FOREIGN KEY (run_id) REFERENCES runs(id) ON DELETE CASCADE
 );
@@ -135,6 +138,23 @@ CREATE TABLE IF NOT EXISTS modules (
 );
 ";
 
+/// SQLite triggers that bump a workspace's `serial` on every write to its
+/// resources or outputs, as Terraform does for each state it persists.
+pub const CREATE_TRIGGERS_SQL: &str = "
+CREATE TRIGGER IF NOT EXISTS serial_resource_insert AFTER INSERT ON resources
+BEGIN UPDATE workspaces SET serial = serial + 1 WHERE id = NEW.workspace_id; END;
+CREATE TRIGGER IF NOT EXISTS serial_resource_update AFTER UPDATE ON resources
+BEGIN UPDATE workspaces SET serial = serial + 1 WHERE id = NEW.workspace_id; END;
+CREATE TRIGGER IF NOT EXISTS serial_resource_delete AFTER DELETE ON resources
+BEGIN UPDATE workspaces SET serial = serial + 1 WHERE id = OLD.workspace_id; END;
+CREATE TRIGGER IF NOT EXISTS serial_output_insert AFTER INSERT ON resource_outputs
+BEGIN UPDATE workspaces SET serial = serial + 1 WHERE id = NEW.workspace_id; END;
+CREATE TRIGGER IF NOT EXISTS serial_output_update AFTER UPDATE ON resource_outputs
+BEGIN UPDATE workspaces SET serial = serial + 1 WHERE id = NEW.workspace_id; END;
+CREATE TRIGGER IF NOT EXISTS serial_output_delete AFTER DELETE ON resource_outputs
+BEGIN UPDATE workspaces SET serial = serial + 1 WHERE id = OLD.workspace_id; END;
+";
+
 pub const CREATE_INDEXES_SQL: &str = "
 CREATE INDEX IF NOT EXISTS idx_resources_type ON resources(resource_type);
 CREATE INDEX IF NOT EXISTS idx_resources_module ON resources(module_path);
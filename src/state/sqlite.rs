@@ -1,10 +1,14 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
+use rusqlite::types::ValueRef;
 use rusqlite::{params, Connection};
 use std::path::Path;
 use std::sync::Mutex;
 
+use base64::Engine;
+
 use super::backend::StateBackend;
+use super::migration;
 use super::models::*;
 use super::schema;
 
@@ -42,6 +46,266 @@ impl SqliteBackend {
     fn now() -> String {
         chrono::Utc::now().to_rfc3339()
     }
+
+    /// Import the resources and outputs of a parsed Terraform state into
+    /// `workspace_id`. Addresses already in state are skipped.
+    ///
+    /// Each imported instance's `dependencies` are recorded as explicit
+    /// `resource_dependencies` edges; a dependency that names a resource
+    /// with instances depends on all of them.
+    pub fn import_tf_state(&self, workspace_id: &str, state: &TfState) -> Result<ImportResult> {
+        let mut imported = 0;
+        let mut skipped = 0;
+        let mut warnings = Vec::new();
+        let mut imported_dependencies: Vec<(String, String, &[String])> = Vec::new();
+        let now = Self::now();
+
+        let conn = self.conn.lock().unwrap();
+
+        for tf_resource in &state.resources {
+            let provider = tf_resource.provider_address();
+            for instance in &tf_resource.instances {
+                let address = tf_resource.address(instance);
+                if instance.deposed.is_some() {
+                    skipped += 1;
+                    warnings.push(format!("Skipped a deposed object of {}", address));
+                    continue;
+                }
+
+                let id = uuid::Uuid::new_v4().to_string();
+                let attrs_json = serde_json::to_string(&instance.attributes)
+                    .unwrap_or_else(|_| "{}".to_string());
+                let sensitive_json = serde_json::to_string(&instance.sensitive_attribute_names())
+                    .unwrap_or_else(|_| "[]".to_string());
+
+                let private_data = match instance.private {
+                    Some(ref encoded) => Some(
+                        base64::engine::general_purpose::STANDARD
+                            .decode(encoded)
+                            .with_context(|| format!("Invalid private data for {}", address))?,
+                    ),
+                    None => None,
+                };
+
+                let result = conn.execute(
+                    "INSERT INTO resources (id, workspace_id, module_path, resource_type, resource_name,
+                        resource_mode, provider_source, index_key, address, status,
+                        attributes_json, sensitive_attrs, schema_version, created_at, updated_at,
+                        private_data)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+                     ON CONFLICT(workspace_id, address) DO NOTHING",
+                    params![
+                        id,
+                        workspace_id,
+                        tf_resource.module,
+                        tf_resource.resource_type,
+                        tf_resource.name,
+                        tf_resource.mode,
+                        provider,
+                        instance.index_key(),
+                        address,
+                        "created",
+                        attrs_json,
+                        sensitive_json,
+                        instance.schema_version.unwrap_or(0),
+                        now,
+                        now,
+                        private_data,
+                    ],
+                );
+
+                match result {
+                    Ok(rows) if rows > 0 => {
+                        imported += 1;
+                        if !instance.dependencies.is_empty() {
+                            imported_dependencies.push((id, address, &instance.dependencies));
+                        }
+                    }
+                    Ok(_) => {
+                        skipped += 1;
+                        warnings.push(format!("Skipped {} (already exists)", address));
+                    }
+                    Err(e) => {
+                        skipped += 1;
+                        warnings.push(format!("Failed to import {}: {}", address, e));
+                    }
+                }
+            }
+        }
+
+        // Resolve dependency addresses once every resource is in place
+        for (resource_id, address, dependencies) in imported_dependencies {
+            for dependency in dependencies {
+                let depends_on: Vec<String> = conn
+                    .prepare(
+                        "SELECT id FROM resources WHERE workspace_id = ?1 \
+                         AND (address = ?2 OR address LIKE ?3 ESCAPE '\\')",
+                    )?
+                    .query_map(
+                        params![
+                            workspace_id,
+                            dependency,
+                            format!("{}[%", like_literal(dependency))
+                        ],
+                        |row| row.get(0),
+                    )?
+                    .collect::<rusqlite::Result<_>>()?;
+                if depends_on.is_empty() {
+                    warnings.push(format!(
+                        "{} depends on {}, which is not in state",
+                        address, dependency
+                    ));
+                }
+                for depends_on_id in depends_on {
+                    conn.execute(
+                        "INSERT OR IGNORE INTO resource_dependencies (resource_id, depends_on_id, dependency_type)
+                         VALUES (?1, ?2, ?3)",
+                        params![resource_id, depends_on_id, dep_type::EXPLICIT],
+                    )?;
+                }
+            }
+        }
+
+        // Import outputs
+        for (name, output) in &state.outputs {
+            let id = uuid::Uuid::new_v4().to_string();
+            let value_str = serde_json::to_string(&output.value).unwrap_or_default();
+            let _ = conn.execute(
+                "INSERT INTO resource_outputs (id, workspace_id, module_path, output_name, output_value, sensitive)
+                 VALUES (?1, ?2, '', ?3, ?4, ?5)
+                 ON CONFLICT(workspace_id, module_path, output_name) DO UPDATE SET
+                    output_value = excluded.output_value",
+                params![id, workspace_id, name, value_str, output.sensitive.unwrap_or(false) as i32],
+            );
+        }
+
+        Ok(ImportResult {
+            imported,
+            skipped,
+            warnings,
+        })
+    }
+
+    /// Dump every state table into a snapshot (serial and lineage left unset).
+    ///
+    /// Resource locks are process-local and are not included.
+    pub fn export_snapshot(&self) -> Result<StateSnapshot> {
+        let conn = self.conn.lock().unwrap();
+        let tables: Vec<String> = conn
+            .prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' \
+                 AND name NOT LIKE 'sqlite_%' AND name != 'resource_locks' ORDER BY name",
+            )?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut snapshot = StateSnapshot {
+            version: SNAPSHOT_VERSION,
+            ..Default::default()
+        };
+        for table in tables {
+            let mut stmt = conn.prepare(&format!("SELECT * FROM \"{}\"", table))?;
+            let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+            let rows = stmt
+                .query_map([], |row| {
+                    let mut map = serde_json::Map::new();
+                    for (i, col) in columns.iter().enumerate() {
+                        let value = match row.get_ref(i)? {
+                            ValueRef::Null => serde_json::Value::Null,
+                            ValueRef::Integer(n) => serde_json::json!(n),
+                            ValueRef::Real(f) => serde_json::json!(f),
+                            ValueRef::Text(t) => {
+                                serde_json::Value::String(String::from_utf8_lossy(t).into_owned())
+                            }
+                            ValueRef::Blob(b) => serde_json::json!({ "$blob": hex::encode(b) }),
+                        };
+                        map.insert(col.clone(), value);
+                    }
+                    Ok(map)
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            snapshot.tables.insert(table, rows);
+        }
+        Ok(snapshot)
+    }
+
+    /// Replace the contents of the state tables with a snapshot.
+    /// The schema must already exist (see `initialize`).
+    pub fn import_snapshot(&self, snapshot: &StateSnapshot) -> Result<()> {
+        if snapshot.version > SNAPSHOT_VERSION {
+            anyhow::bail!(
+                "State snapshot version {} is newer than this oxid supports ({})",
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=OFF;")?;
+        let result = (|| -> Result<()> {
+            let tx = conn.transaction()?;
+            for (table, rows) in &snapshot.tables {
+                tx.execute(&format!("DELETE FROM \"{}\"", table), [])
+                    .with_context(|| format!("Unknown table '{}' in state snapshot", table))?;
+                for row in rows {
+                    let columns: Vec<&String> = row.keys().collect();
+                    let sql = format!(
+                        "INSERT INTO \"{}\" ({}) VALUES ({})",
+                        table,
+                        columns
+                            .iter()
+                            .map(|c| format!("\"{}\"", c))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        (1..=columns.len())
+                            .map(|i| format!("?{}", i))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    let values: Vec<rusqlite::types::Value> =
+                        row.values().map(json_to_sql).collect::<Result<_>>()?;
+                    tx.execute(&sql, rusqlite::params_from_iter(values))
+                        .with_context(|| format!("Failed to restore a row of '{}'", table))?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })();
+        conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+        result
+    }
+
+    /// Set a workspace's state serial, e.g. to the serial of the Terraform
+    /// state it was loaded from.
+    pub fn set_serial(&self, workspace_id: &str, serial: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE workspaces SET serial = ?1 WHERE id = ?2",
+            params![serial, workspace_id],
+        )?;
+        Ok(())
+    }
+}
+
+/// Current state snapshot format version.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+fn json_to_sql(value: &serde_json::Value) -> Result<rusqlite::types::Value> {
+    use rusqlite::types::Value as Sql;
+    Ok(match value {
+        serde_json::Value::Null => Sql::Null,
+        serde_json::Value::Bool(b) => Sql::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Sql::Integer(i),
+            None => Sql::Real(n.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(s) => Sql::Text(s.clone()),
+        serde_json::Value::Object(obj) => match obj.get("$blob").and_then(|b| b.as_str()) {
+            Some(encoded) => Sql::Blob(hex::decode(encoded)?),
+            None => Sql::Text(value.to_string()),
+        },
+        serde_json::Value::Array(_) => Sql::Text(value.to_string()),
+    })
 }
 
 #[async_trait]
@@ -53,11 +317,10 @@ impl StateBackend for SqliteBackend {
         conn.execute_batch(schema::CREATE_TABLES_SQL)?;
         conn.execute_batch(schema::CREATE_INDEXES_SQL)?;
 
-        // Record schema version
-        conn.execute(
-            "INSERT OR IGNORE INTO schema_version (version, applied_at, description) VALUES (?1, ?2, ?3)",
-            params![schema::SCHEMA_VERSION, Self::now(), "Initial schema"],
-        )?;
+        // Bring databases created by older versions up to the current schema
+        // and record the schema version
+        migration::check_and_migrate(&conn)?;
+        conn.execute_batch(schema::CREATE_TRIGGERS_SQL)?;
         Ok(())
     }
 
@@ -76,8 +339,9 @@ impl StateBackend for SqliteBackend {
 
     async fn get_workspace(&self, name: &str) -> Result<Option<Workspace>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn
-            .prepare("SELECT id, name, created_at, updated_at FROM workspaces WHERE name = ?1")?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, created_at, updated_at, serial FROM workspaces WHERE name = ?1",
+        )?;
         let result = stmt
             .query_row(params![name], |row| {
                 Ok(Workspace {
@@ -85,6 +349,7 @@ impl StateBackend for SqliteBackend {
                     name: row.get(1)?,
                     created_at: row.get(2)?,
                     updated_at: row.get(3)?,
+                    serial: row.get(4)?,
                 })
             })
             .ok();
@@ -93,8 +358,9 @@ impl StateBackend for SqliteBackend {
 
     async fn list_workspaces(&self) -> Result<Vec<Workspace>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt =
-            conn.prepare("SELECT id, name, created_at, updated_at FROM workspaces ORDER BY name")?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, created_at, updated_at, serial FROM workspaces ORDER BY name",
+        )?;
         let rows = stmt
             .query_map([], |row| {
                 Ok(Workspace {
@@ -102,6 +368,7 @@ impl StateBackend for SqliteBackend {
                     name: row.get(1)?,
                     created_at: row.get(2)?,
                     updated_at: row.get(3)?,
+                    serial: row.get(4)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -125,7 +392,8 @@ impl StateBackend for SqliteBackend {
         let mut stmt = conn.prepare(
             "SELECT id, workspace_id, module_path, resource_type, resource_name,
                     resource_mode, provider_source, index_key, address, status,
-                    attributes_json, sensitive_attrs, schema_version, created_at, updated_at
+                    attributes_json, sensitive_attrs, schema_version, created_at, updated_at,
+                    private_data
              FROM resources WHERE workspace_id = ?1 AND address = ?2",
         )?;
         let result = stmt
@@ -142,13 +410,15 @@ impl StateBackend for SqliteBackend {
         conn.execute(
             "INSERT INTO resources (id, workspace_id, module_path, resource_type, resource_name,
                 resource_mode, provider_source, index_key, address, status,
-                attributes_json, sensitive_attrs, schema_version, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                attributes_json, sensitive_attrs, schema_version, created_at, updated_at,
+                private_data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
              ON CONFLICT(workspace_id, address) DO UPDATE SET
                 status = excluded.status,
                 attributes_json = excluded.attributes_json,
                 sensitive_attrs = excluded.sensitive_attrs,
                 schema_version = excluded.schema_version,
+                private_data = excluded.private_data,
                 updated_at = excluded.updated_at",
             params![
                 resource.id,
@@ -166,6 +436,7 @@ impl StateBackend for SqliteBackend {
                 resource.schema_version,
                 resource.created_at,
                 resource.updated_at,
+                resource.private_data,
             ],
         )?;
         Ok(())
@@ -180,6 +451,33 @@ impl StateBackend for SqliteBackend {
         Ok(())
     }
 
+    async fn move_resource(
+        &self,
+        workspace_id: &str,
+        from_address: &str,
+        moved: &ResourceState,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE resources SET address = ?3, resource_name = ?4, module_path = ?5,
+                index_key = ?6, updated_at = ?7
+             WHERE workspace_id = ?1 AND address = ?2",
+            params![
+                workspace_id,
+                from_address,
+                moved.address,
+                moved.resource_name,
+                moved.module_path,
+                moved.index_key,
+                moved.updated_at,
+            ],
+        )?;
+        if updated == 0 {
+            bail!("Resource '{}' not found in state.", from_address);
+        }
+        Ok(())
+    }
+
     async fn list_resources(
         &self,
         workspace_id: &str,
@@ -189,7 +487,8 @@ impl StateBackend for SqliteBackend {
         let mut sql = String::from(
             "SELECT id, workspace_id, module_path, resource_type, resource_name,
                     resource_mode, provider_source, index_key, address, status,
-                    attributes_json, sensitive_attrs, schema_version, created_at, updated_at
+                    attributes_json, sensitive_attrs, schema_version, created_at, updated_at,
+                    private_data
              FROM resources WHERE workspace_id = ?1",
         );
         let mut param_values: Vec<String> = vec![workspace_id.to_string()];
@@ -201,22 +500,46 @@ impl StateBackend for SqliteBackend {
             param_idx += 1;
         }
         if let Some(ref mp) = filter.module_path {
-            sql.push_str(&format!(" AND module_path = ?{}", param_idx));
+            // The module itself, its instances and its nested modules
+            sql.push_str(&format!(
+                " AND (module_path = ?{0} OR module_path LIKE ?{1} ESCAPE '\\'
+                      OR module_path LIKE ?{2} ESCAPE '\\')",
+                param_idx,
+                param_idx + 1,
+                param_idx + 2
+            ));
             param_values.push(mp.clone());
-            param_idx += 1;
+            param_values.push(format!("{}.%", like_literal(mp)));
+            param_values.push(format!("{}[%", like_literal(mp)));
+            param_idx += 3;
         }
         if let Some(ref st) = filter.status {
             sql.push_str(&format!(" AND status = ?{}", param_idx));
             param_values.push(st.clone());
             param_idx += 1;
         }
+        if filter.exclude_deposed {
+            sql.push_str(&format!(" AND status != '{}'", status::DEPOSED));
+        }
         if let Some(ref pat) = filter.address_pattern {
-            sql.push_str(&format!(" AND address LIKE ?{}", param_idx));
+            sql.push_str(&format!(" AND address LIKE ?{} ESCAPE '\\'", param_idx));
             param_values.push(pat.clone());
             // param_idx not needed after last use
         }
 
-        sql.push_str(" ORDER BY address");
+        sql.push_str(&format!(" ORDER BY {}", filter.sort.column()));
+        if filter.sort != ResourceSort::Address {
+            sql.push_str(", address");
+        }
+        // SQLite only accepts OFFSET after a LIMIT; -1 means unbounded.
+        match (filter.limit, filter.offset) {
+            (Some(limit), Some(offset)) => {
+                sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset))
+            }
+            (Some(limit), None) => sql.push_str(&format!(" LIMIT {}", limit)),
+            (None, Some(offset)) => sql.push_str(&format!(" LIMIT -1 OFFSET {}", offset)),
+            (None, None) => {}
+        }
 
         let mut stmt = conn.prepare(&sql)?;
         let param_refs: Vec<&dyn rusqlite::ToSql> = param_values
@@ -518,11 +841,12 @@ impl StateBackend for SqliteBackend {
     async fn record_resource_result(&self, run_id: &str, result: &ResourceResult) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO run_resources (run_id, resource_address, action, status, started_at, completed_at, error_message, diff_json)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "INSERT INTO run_resources (run_id, resource_address, action, status, started_at, completed_at, error_message, diff_json, output)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
              ON CONFLICT(run_id, resource_address) DO UPDATE SET
                 status = excluded.status, completed_at = excluded.completed_at,
-                error_message = excluded.error_message, diff_json = excluded.diff_json",
+                error_message = excluded.error_message, diff_json = excluded.diff_json,
+                output = excluded.output",
             params![
                 run_id,
                 result.address,
@@ -532,11 +856,72 @@ impl StateBackend for SqliteBackend {
                 result.completed_at,
                 result.error_message,
                 result.diff_json,
+                result.output,
             ],
         )?;
         Ok(())
     }
 
+    async fn list_run_resources(&self, run_id: &str) -> Result<Vec<ResourceResult>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT resource_address, action, status, started_at, completed_at, error_message, diff_json, output
+             FROM run_resources WHERE run_id = ?1
+             ORDER BY started_at IS NULL, started_at, resource_address",
+        )?;
+        let rows = stmt
+            .query_map(params![run_id], |row| {
+                Ok(ResourceResult {
+                    address: row.get(0)?,
+                    action: row.get(1)?,
+                    status: row.get(2)?,
+                    started_at: row.get(3)?,
+                    completed_at: row.get(4)?,
+                    error_message: row.get(5)?,
+                    diff_json: row.get(6)?,
+                    output: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    async fn list_resource_history(
+        &self,
+        workspace_id: &str,
+        address: &str,
+        limit: usize,
+    ) -> Result<Vec<ResourceHistoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT rr.run_id, r.operation, rr.resource_address, rr.action, rr.status,
+                    rr.started_at, rr.completed_at, rr.error_message, rr.diff_json, rr.output
+             FROM run_resources rr JOIN runs r ON r.id = rr.run_id
+             WHERE r.workspace_id = ?1 AND rr.resource_address = ?2
+             ORDER BY COALESCE(rr.started_at, r.started_at) DESC, r.started_at DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt
+            .query_map(params![workspace_id, address, limit as i64], |row| {
+                Ok(ResourceHistoryEntry {
+                    run_id: row.get(0)?,
+                    operation: row.get(1)?,
+                    result: ResourceResult {
+                        address: row.get(2)?,
+                        action: row.get(3)?,
+                        status: row.get(4)?,
+                        started_at: row.get(5)?,
+                        completed_at: row.get(6)?,
+                        error_message: row.get(7)?,
+                        diff_json: row.get(8)?,
+                        output: row.get(9)?,
+                    },
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
     async fn get_latest_run(&self, workspace_id: &str) -> Result<Option<RunRecord>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
@@ -591,12 +976,40 @@ impl StateBackend for SqliteBackend {
 
     // ─── Query ──────────────────────────────────────────────────────────────
 
-    async fn query_raw(&self, sql: &str) -> Result<Vec<serde_json::Value>> {
+    async fn query_raw(&self, sql: &str, params: &[String]) -> Result<Vec<serde_json::Value>> {
+        let mut result = Vec::new();
+        self.query_each(sql, params, &mut |row| {
+            result.push(row);
+            Ok(())
+        })
+        .await?;
+        Ok(result)
+    }
+
+    async fn query_each(
+        &self,
+        sql: &str,
+        params: &[String],
+        on_row: &mut (dyn FnMut(serde_json::Value) -> Result<()> + Send),
+    ) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(sql)?;
+        let mut stmt = conn.prepare(sql).context("Invalid query")?;
+        // SQLite knows whether the compiled statement writes, which also
+        // catches writes hidden behind a WITH clause
+        if !stmt.readonly() {
+            anyhow::bail!("Only read-only queries are allowed. Use oxid commands for mutations.");
+        }
+        let expected = stmt.parameter_count();
+        if expected != params.len() {
+            anyhow::bail!(
+                "Query has {} parameter placeholder(s) but {} value(s) were given",
+                expected,
+                params.len()
+            );
+        }
         let column_names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
             let mut map = serde_json::Map::new();
             for (i, col_name) in column_names.iter().enumerate() {
                 let value: rusqlite::Result<String> = row.get(i);
@@ -626,8 +1039,14 @@ impl StateBackend for SqliteBackend {
             Ok(serde_json::Value::Object(map))
         })?;
 
-        let result: Vec<serde_json::Value> = rows.filter_map(|r| r.ok()).collect();
-        Ok(result)
+        // Rows are handed over as SQLite steps through them, so large
+        // results never have to be held in memory at once
+        let mut count = 0;
+        for row in rows.filter_map(|r| r.ok()) {
+            on_row(row)?;
+            count += 1;
+        }
+        Ok(count)
     }
 
     // ─── Import ─────────────────────────────────────────────────────────────
@@ -635,95 +1054,7 @@ impl StateBackend for SqliteBackend {
     async fn import_tfstate(&self, workspace_id: &str, state_json: &str) -> Result<ImportResult> {
         let state: TfState =
             serde_json::from_str(state_json).context("Failed to parse .tfstate JSON")?;
-
-        let mut imported = 0;
-        let mut skipped = 0;
-        let mut warnings = Vec::new();
-        let now = Self::now();
-
-        let conn = self.conn.lock().unwrap();
-
-        for tf_resource in &state.resources {
-            for (idx, instance) in tf_resource.instances.iter().enumerate() {
-                let address = if tf_resource.instances.len() > 1 {
-                    if let Some(ref key) = instance.index_key {
-                        format!(
-                            "{}.{}[{}]",
-                            tf_resource.resource_type, tf_resource.name, key
-                        )
-                    } else {
-                        format!(
-                            "{}.{}[{}]",
-                            tf_resource.resource_type, tf_resource.name, idx
-                        )
-                    }
-                } else {
-                    format!("{}.{}", tf_resource.resource_type, tf_resource.name)
-                };
-
-                let id = uuid::Uuid::new_v4().to_string();
-                let attrs_json = serde_json::to_string(&instance.attributes)
-                    .unwrap_or_else(|_| "{}".to_string());
-                let sensitive_json = serde_json::to_string(&instance.sensitive_attributes)
-                    .unwrap_or_else(|_| "[]".to_string());
-
-                let result = conn.execute(
-                    "INSERT INTO resources (id, workspace_id, module_path, resource_type, resource_name,
-                        resource_mode, provider_source, index_key, address, status,
-                        attributes_json, sensitive_attrs, schema_version, created_at, updated_at)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
-                     ON CONFLICT(workspace_id, address) DO NOTHING",
-                    params![
-                        id,
-                        workspace_id,
-                        "",  // module_path - would need to be extracted from resource
-                        tf_resource.resource_type,
-                        tf_resource.name,
-                        tf_resource.mode,
-                        tf_resource.provider,
-                        instance.index_key,
-                        address,
-                        "created",
-                        attrs_json,
-                        sensitive_json,
-                        instance.schema_version.unwrap_or(0),
-                        now,
-                        now,
-                    ],
-                );
-
-                match result {
-                    Ok(rows) if rows > 0 => imported += 1,
-                    Ok(_) => {
-                        skipped += 1;
-                        warnings.push(format!("Skipped {} (already exists)", address));
-                    }
-                    Err(e) => {
-                        skipped += 1;
-                        warnings.push(format!("Failed to import {}: {}", address, e));
-                    }
-                }
-            }
-        }
-
-        // Import outputs
-        for (name, output) in &state.outputs {
-            let id = uuid::Uuid::new_v4().to_string();
-            let value_str = serde_json::to_string(&output.value).unwrap_or_default();
-            let _ = conn.execute(
-                "INSERT INTO resource_outputs (id, workspace_id, module_path, output_name, output_value, sensitive)
-                 VALUES (?1, ?2, '', ?3, ?4, ?5)
-                 ON CONFLICT(workspace_id, module_path, output_name) DO UPDATE SET
-                    output_value = excluded.output_value",
-                params![id, workspace_id, name, value_str, output.sensitive.unwrap_or(false) as i32],
-            );
-        }
-
-        Ok(ImportResult {
-            imported,
-            skipped,
-            warnings,
-        })
+        self.import_tf_state(workspace_id, &state)
     }
 
     // ─── Providers ──────────────────────────────────────────────────────────
@@ -779,57 +1110,11 @@ fn resource_from_row(row: &rusqlite::Row<'_>) -> ResourceState {
         attributes_json: row.get(10).unwrap_or_default(),
         sensitive_attrs,
         schema_version: row.get(12).unwrap_or_default(),
+        private_data: row
+            .get::<_, Option<Vec<u8>>>(15)
+            .unwrap_or_default()
+            .unwrap_or_default(),
         created_at: row.get(13).unwrap_or_default(),
         updated_at: row.get(14).unwrap_or_default(),
     }
 }
-
-// ─── Terraform state file types for import ──────────────────────────────────
-
-#[derive(Debug, serde::Deserialize)]
-struct TfState {
-    #[serde(default)]
-    resources: Vec<TfStateResource>,
-    #[serde(default)]
-    outputs: std::collections::HashMap<String, TfOutput>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct TfStateResource {
-    #[serde(default = "default_mode")]
-    mode: String,
-    #[serde(rename = "type")]
-    resource_type: String,
-    name: String,
-    #[serde(default)]
-    provider: String,
-    #[serde(default)]
-    instances: Vec<TfInstance>,
-}
-
-fn default_mode() -> String {
-    "managed".to_string()
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct TfInstance {
-    #[serde(default)]
-    index_key: Option<String>,
-    #[serde(default)]
-    schema_version: Option<i32>,
-    #[serde(default)]
-    attributes: serde_json::Value,
-    #[serde(default)]
-    sensitive_attributes: Vec<String>,
-    #[serde(default)]
-    #[allow(dead_code)]
-    dependencies: Vec<String>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct TfOutput {
-    value: serde_json::Value,
-    #[serde(rename = "type")]
-    _output_type: Option<serde_json::Value>,
-    sensitive: Option<bool>,
-}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use oxid::config::types::{BackendConfig, Expression, Value};
+use oxid::hcl::json_parser::parse_tf_json;
+use oxid::hcl::parser::parse_hcl;
+use oxid::state::location::{resolve_state_location, StateLocation};
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+fn backend(backend_type: &str, attrs: &[(&str, &str)]) -> BackendConfig {
+    BackendConfig {
+        backend_type: backend_type.to_string(),
+        attributes: attrs
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    Expression::Literal(Value::String(v.to_string())),
+                )
+            })
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+fn sqlite_path(location: StateLocation) -> PathBuf {
+    match location {
+        StateLocation::Sqlite(path) => path,
+        other => panic!("expected sqlite location, got {:?}", other),
+    }
+}
+
+// ─── Parsing ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_parse_local_backend_block() {
+    let hcl = r#"
+terraform {
+  required_version = ">= 1.5"
+  backend "local" {
+    path = "state/prod.db"
+  }
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("test.tf")).unwrap();
+    let settings = ws.terraform_settings.unwrap();
+    assert_eq!(settings.required_version.as_deref(), Some(">= 1.5"));
+    let backend = settings.backend.unwrap();
+    assert_eq!(backend.backend_type, "local");
+    assert_eq!(backend.get_str("path").as_deref(), Some("state/prod.db"));
+}
+
+#[test]
+fn test_parse_local_backend_block_json() {
+    let json = r#"{
+  "terraform": {
+    "backend": {
+      "local": { "path": "state/prod.db" }
+    }
+  }
+}"#;
+    let ws = parse_tf_json(json, Path::new("main.tf.json")).unwrap();
+    let backend = ws.terraform_settings.unwrap().backend.unwrap();
+    assert_eq!(backend.backend_type, "local");
+    assert_eq!(backend.get_str("path").as_deref(), Some("state/prod.db"));
+}
+
+#[test]
+fn test_backend_block_requires_type_label() {
+    let hcl = "terraform {\n  backend {\n  }\n}\n";
+    assert!(parse_hcl(hcl, Path::new("test.tf")).is_err());
+}
+
+// ─── Resolution ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_default_location_without_backend() {
+    let location = resolve_state_location(None, None, ".oxid").unwrap();
+    assert_eq!(sqlite_path(location), PathBuf::from(".oxid/oxid.db"));
+}
+
+#[test]
+fn test_local_backend_path() {
+    let local = backend("local", &[("path", "state/prod.db")]);
+    let location = resolve_state_location(None, Some(&local), ".oxid").unwrap();
+    assert_eq!(sqlite_path(location), PathBuf::from("state/prod.db"));
+
+    // Terraform .tfstate paths (as emitted by CDKTF) are not oxid databases
+    let local = backend("local", &[("path", "terraform.main.tfstate")]);
+    let location = resolve_state_location(None, Some(&local), ".oxid").unwrap();
+    assert_eq!(sqlite_path(location), PathBuf::from(".oxid/oxid.db"));
+
+    // Without a path the working directory default is used
+    let local = backend("local", &[]);
+    let location = resolve_state_location(None, Some(&local), ".oxid").unwrap();
+    assert_eq!(sqlite_path(location), PathBuf::from(".oxid/oxid.db"));
+}
+
+#[test]
+fn test_dsn_takes_precedence_over_backend_block() {
+    let s3 = backend("s3", &[("bucket", "b"), ("key", "k")]);
+    let location = resolve_state_location(Some("sqlite:///tmp/x.db"), Some(&s3), ".oxid").unwrap();
+    assert_eq!(sqlite_path(location), PathBuf::from("/tmp/x.db"));
+
+    let location =
+        resolve_state_location(Some("postgres://u@db/oxid"), Some(&s3), ".oxid").unwrap();
+    assert!(matches!(location, StateLocation::Postgres(ref dsn) if dsn == "postgres://u@db/oxid"));
+
+    // An empty DSN is treated as unset
+    let location = resolve_state_location(Some(""), Some(&s3), ".oxid").unwrap();
+    assert!(matches!(location, StateLocation::S3(_)));
+}
+
+#[test]
+fn test_dsn_bare_path_and_bad_scheme() {
+    let location = resolve_state_location(Some("custom/state.db"), None, ".oxid").unwrap();
+    assert_eq!(sqlite_path(location), PathBuf::from("custom/state.db"));
+
+    let err = resolve_state_location(Some("mysql://db"), None, ".oxid").unwrap_err();
+    assert!(format!("{:#}", err).contains("Unsupported scheme 'mysql'"));
+}
+
+#[test]
+fn test_pg_backend_and_unknown_backend() {
+    let pg = backend("pg", &[("conn_str", "postgres://db/oxid")]);
+    let location = resolve_state_location(None, Some(&pg), ".oxid").unwrap();
+    assert!(matches!(location, StateLocation::Postgres(ref dsn) if dsn == "postgres://db/oxid"));
+
+    let err = resolve_state_location(None, Some(&backend("pg", &[])), ".oxid").unwrap_err();
+    assert!(err.to_string().contains("'conn_str' is required"));
+
+    let http = backend("http", &[("address", "https://state.example.com/prod")]);
+    let location = resolve_state_location(None, Some(&http), ".oxid").unwrap();
+    assert!(matches!(location, StateLocation::Http(_)));
+
+    let err = resolve_state_location(None, Some(&backend("gcs", &[])), ".oxid").unwrap_err();
+    assert!(err.to_string().contains("Unsupported backend \"gcs\""));
+}
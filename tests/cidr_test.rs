@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use oxid::executor::cidr::{cidr_host, cidr_netmask, cidr_subnet};
+use oxid::executor::engine::{eval_expression, EvalContext};
+use oxid::hcl::parser::parse_hcl;
+
+// ─── cidrsubnet ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_cidrsubnet_ipv4() {
+    assert_eq!(cidr_subnet("10.0.0.0/16", 8, 0).unwrap(), "10.0.0.0/24");
+    assert_eq!(cidr_subnet("10.0.0.0/16", 8, 2).unwrap(), "10.0.2.0/24");
+    assert_eq!(cidr_subnet("172.16.0.0/12", 4, 2).unwrap(), "172.18.0.0/16");
+    assert_eq!(cidr_subnet("10.1.2.0/24", 4, 15).unwrap(), "10.1.2.240/28");
+    // Host bits in the base prefix are ignored
+    assert_eq!(cidr_subnet("10.0.5.7/16", 8, 1).unwrap(), "10.0.1.0/24");
+    assert_eq!(cidr_subnet("10.0.0.0/16", 0, 0).unwrap(), "10.0.0.0/16");
+}
+
+#[test]
+fn test_cidrsubnet_ipv6() {
+    assert_eq!(
+        cidr_subnet("fd00:fd12:3456:7890::/56", 16, 162).unwrap(),
+        "fd00:fd12:3456:7800:a200::/72"
+    );
+    assert_eq!(
+        cidr_subnet("2001:db8::/32", 32, 1).unwrap(),
+        "2001:db8:0:1::/64"
+    );
+}
+
+#[test]
+fn test_cidrsubnet_errors() {
+    let err = cidr_subnet("10.0.0.0/24", 4, 16).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "prefix extension of 4 does not accommodate a subnet numbered 16"
+    );
+
+    let err = cidr_subnet("10.0.0.0/30", 4, 0).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "insufficient address space to extend prefix of 30 by 4"
+    );
+
+    assert!(cidr_subnet("10.0.0.0/16", 8, -1).is_err());
+    assert!(cidr_subnet("not-a-cidr", 8, 0).is_err());
+    assert!(cidr_subnet("10.0.0.0/33", 1, 0).is_err());
+}
+
+// ─── cidrhost ────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_cidrhost() {
+    assert_eq!(cidr_host("10.12.112.0/20", 16).unwrap(), "10.12.112.16");
+    assert_eq!(cidr_host("10.12.112.0/20", 268).unwrap(), "10.12.113.12");
+    assert_eq!(cidr_host("10.0.0.0/24", -1).unwrap(), "10.0.0.255");
+    assert_eq!(cidr_host("10.0.0.0/24", -256).unwrap(), "10.0.0.0");
+    assert_eq!(
+        cidr_host("fd00:fd12:3456:7890:00a2::/72", 34).unwrap(),
+        "fd00:fd12:3456:7890::22"
+    );
+    assert_eq!(
+        cidr_host("::/0", -1).unwrap(),
+        "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"
+    );
+}
+
+#[test]
+fn test_cidrhost_errors() {
+    let err = cidr_host("10.0.0.0/24", 256).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "prefix of 24 does not accommodate a host numbered 256"
+    );
+    assert!(cidr_host("10.0.0.0/24", -257).is_err());
+}
+
+// ─── cidrnetmask ─────────────────────────────────────────────────────────────
+
+#[test]
+fn test_cidrnetmask() {
+    assert_eq!(cidr_netmask("172.16.0.0/12").unwrap(), "255.240.0.0");
+    assert_eq!(cidr_netmask("10.0.0.0/32").unwrap(), "255.255.255.255");
+    assert_eq!(cidr_netmask("0.0.0.0/0").unwrap(), "0.0.0.0");
+    assert!(cidr_netmask("fd00::/64").is_err());
+}
+
+// ─── Evaluation ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_cidr_functions_in_expressions() {
+    let hcl = r#"
+variable "vpc_cidr" {
+  default = "10.0.0.0/16"
+}
+
+resource "aws_subnet" "private" {
+  count      = 3
+  cidr_block = cidrsubnet(var.vpc_cidr, 8, count.index)
+}
+
+output "gateway" {
+  value = cidrhost(cidrsubnet(var.vpc_cidr, 8, 1), 1)
+}
+
+output "overflow" {
+  value = cidrsubnet(var.vpc_cidr, 2, 4)
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let vars = HashMap::from([("vpc_cidr".to_string(), serde_json::json!("10.0.0.0/16"))]);
+
+    let mut ctx = EvalContext::plan_only(vars.clone());
+    ctx.count_index = Some(2);
+    let cidr_block = &ws.resources[0].attributes["cidr_block"];
+    assert_eq!(eval_expression(cidr_block, &ctx).unwrap(), "10.0.2.0/24");
+
+    let ctx = EvalContext::plan_only(vars);
+    let output = |name: &str| {
+        let o = ws.outputs.iter().find(|o| o.name == name).unwrap();
+        eval_expression(&o.value, &ctx)
+    };
+    assert_eq!(output("gateway").unwrap(), "10.0.1.1");
+    let err = format!("{:#}", output("overflow").unwrap_err());
+    assert!(err.starts_with("cidrsubnet()"), "{}", err);
+}
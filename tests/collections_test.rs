@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use oxid::dag::resource_graph::build_resource_dag;
+use oxid::executor::collections::{
+    chunklist, contains, index, reverse, set_product, slice, sort, to_list, to_set, zipmap,
+};
+use oxid::executor::engine::{
+    build_provider_map, build_variable_defaults, eval_expression, EvalContext,
+};
+use oxid::hcl::parser::parse_hcl;
+use serde_json::json;
+
+// ─── setproduct ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_setproduct() {
+    assert_eq!(
+        set_product(&[json!(["a", "b"]), json!([1, 2])]).unwrap(),
+        json!([["a", 1], ["a", 2], ["b", 1], ["b", 2]])
+    );
+    assert_eq!(
+        set_product(&[json!(["dev"]), json!(["us", "eu"]), json!([true])]).unwrap(),
+        json!([["dev", "us", true], ["dev", "eu", true]])
+    );
+    // An empty factor empties the product
+    assert_eq!(
+        set_product(&[json!(["a", "b"]), json!([])]).unwrap(),
+        json!([])
+    );
+}
+
+#[test]
+fn test_setproduct_errors() {
+    assert!(set_product(&[json!(["a"])]).is_err());
+    assert!(set_product(&[json!(["a"]), json!("b")]).is_err());
+    assert!(set_product(&[json!(["a"]), json!(null)]).is_err());
+}
+
+// ─── zipmap ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_zipmap() {
+    assert_eq!(
+        zipmap(&json!(["a", "b"]), &json!([1, {"x": true}])).unwrap(),
+        json!({"a": 1, "b": {"x": true}})
+    );
+    assert_eq!(zipmap(&json!([]), &json!([])).unwrap(), json!({}));
+    // Numeric keys become strings; the last duplicate wins
+    assert_eq!(
+        zipmap(&json!([1, "k", "k"]), &json!(["one", "first", "second"])).unwrap(),
+        json!({"1": "one", "k": "second"})
+    );
+}
+
+#[test]
+fn test_zipmap_errors() {
+    let err = zipmap(&json!(["a", "b"]), &json!([1])).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "number of keys (2) does not match number of values (1)"
+    );
+    assert!(zipmap(&json!([null]), &json!([1])).is_err());
+    assert!(zipmap(&json!({"a": 1}), &json!([1])).is_err());
+}
+
+// ─── chunklist ───────────────────────────────────────────────────────────────
+
+#[test]
+fn test_chunklist() {
+    assert_eq!(
+        chunklist(&json!(["a", "b", "c", "d", "e"]), 2).unwrap(),
+        json!([["a", "b"], ["c", "d"], ["e"]])
+    );
+    assert_eq!(chunklist(&json!([1, 2, 3]), 5).unwrap(), json!([[1, 2, 3]]));
+    assert_eq!(chunklist(&json!([1, 2, 3]), 0).unwrap(), json!([[1, 2, 3]]));
+    assert_eq!(chunklist(&json!([]), 3).unwrap(), json!([]));
+    assert!(chunklist(&json!([1]), -1).is_err());
+}
+
+// ─── slice ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_slice() {
+    let list = json!(["a", "b", "c", "d"]);
+    assert_eq!(slice(&list, 1, 3).unwrap(), json!(["b", "c"]));
+    assert_eq!(slice(&list, 0, 4).unwrap(), list);
+    assert_eq!(slice(&list, 2, 2).unwrap(), json!([]));
+}
+
+#[test]
+fn test_slice_rejects_out_of_range_indices() {
+    let list = json!(["a", "b", "c"]);
+    assert!(slice(&list, -1, 2)
+        .unwrap_err()
+        .to_string()
+        .contains("start index must be greater than or equal to 0"));
+    assert!(slice(&list, 0, 4)
+        .unwrap_err()
+        .to_string()
+        .contains("end index must be less than or equal to the length of the list (3)"));
+    assert!(slice(&list, 2, 1)
+        .unwrap_err()
+        .to_string()
+        .contains("must be less than or equal to end index"));
+}
+
+// ─── contains / index ────────────────────────────────────────────────────────
+
+#[test]
+fn test_contains() {
+    let list = json!(["a", 2, {"k": "v"}]);
+    assert_eq!(contains(&list, &json!("a")).unwrap(), json!(true));
+    assert_eq!(contains(&list, &json!(2.0)).unwrap(), json!(true));
+    assert_eq!(contains(&list, &json!({"k": "v"})).unwrap(), json!(true));
+    assert_eq!(contains(&list, &json!("2")).unwrap(), json!(false));
+    assert_eq!(contains(&json!([]), &json!("a")).unwrap(), json!(false));
+    assert!(contains(&json!("abc"), &json!("a")).is_err());
+}
+
+#[test]
+fn test_index() {
+    let list = json!(["a", "b", "c", "b"]);
+    assert_eq!(index(&list, &json!("a")).unwrap(), json!(0));
+    assert_eq!(index(&list, &json!("b")).unwrap(), json!(1));
+}
+
+#[test]
+fn test_index_of_missing_item_is_an_error() {
+    let err = index(&json!(["a", "b"]), &json!("z")).unwrap_err();
+    assert_eq!(err.to_string(), "item \"z\" not found in list");
+}
+
+// ─── sort / reverse ──────────────────────────────────────────────────────────
+
+#[test]
+fn test_sort() {
+    assert_eq!(
+        sort(&json!(["web-10", "db", "web-2", "Api"])).unwrap(),
+        json!(["Api", "db", "web-10", "web-2"])
+    );
+    assert_eq!(sort(&json!([])).unwrap(), json!([]));
+}
+
+#[test]
+fn test_sort_rejects_non_string_elements() {
+    let err = sort(&json!(["a", 1])).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "sort requires a list of strings, but element 1 is number"
+    );
+    assert!(sort(&json!([null])).is_err());
+    assert!(sort(&json!("abc")).is_err());
+}
+
+#[test]
+fn test_reverse() {
+    assert_eq!(
+        reverse(&json!([1, "b", null])).unwrap(),
+        json!([null, "b", 1])
+    );
+    assert_eq!(reverse(&json!("héllo")).unwrap(), json!("olléh"));
+    assert!(reverse(&json!(3)).is_err());
+}
+
+// ─── toset / tolist ──────────────────────────────────────────────────────────
+
+#[test]
+fn test_toset_deduplicates() {
+    assert_eq!(
+        to_set(&json!(["b", "a", "b", "a"])).unwrap(),
+        json!(["a", "b"])
+    );
+    // Numbers compare by value; non-strings keep their first position
+    assert_eq!(to_set(&json!([3, 1, 3.0, 1])).unwrap(), json!([3, 1]));
+    assert_eq!(to_set(&json!([])).unwrap(), json!([]));
+    assert!(to_set(&json!(null)).unwrap().is_null());
+    assert!(to_set(&json!(["a", null])).is_err());
+    assert!(to_set(&json!({"a": 1})).is_err());
+}
+
+#[test]
+fn test_tolist_orders_object_values_by_key() {
+    assert_eq!(
+        to_list(&json!({"b": 2, "a": 1, "c": 3})).unwrap(),
+        json!([1, 2, 3])
+    );
+    assert_eq!(to_list(&json!(["b", "a"])).unwrap(), json!(["b", "a"]));
+    assert!(to_list(&json!("a")).is_err());
+}
+
+#[test]
+fn test_for_each_over_toset_with_duplicates() {
+    let hcl = r#"
+resource "aws_subnet" "zone" {
+  for_each = toset(["a", "a", "b"])
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (_, nodes) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let mut addresses: Vec<&str> = nodes.keys().map(String::as_str).collect();
+    addresses.sort();
+    assert_eq!(
+        addresses,
+        ["aws_subnet.zone[\"a\"]", "aws_subnet.zone[\"b\"]"]
+    );
+
+    // A bare list with duplicates would give two instances one address
+    let hcl = hcl.replace("toset([\"a\", \"a\", \"b\"])", "[\"a\", \"a\", \"b\"]");
+    let ws = parse_hcl(&hcl, Path::new("main.tf")).unwrap();
+    let err = build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("duplicate key \"a\""), "{}", err);
+}
+
+// ─── Evaluation ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_collection_functions_in_expressions() {
+    let hcl = r#"
+output "matrix" {
+  value = setproduct(["dev", "prod"], ["us", "eu"])
+}
+
+output "ports" {
+  value = zipmap(["http", "https"], [80, 443])
+}
+
+output "batches" {
+  value = chunklist(["a", "b", "c"], 2)
+}
+
+output "middle" {
+  value = slice(["a", "b", "c", "d"], 1, 3)
+}
+
+output "bad_slice" {
+  value = slice(["a", "b"], 0, 5)
+}
+
+output "bad_zip" {
+  value = zipmap(["a"], [])
+}
+
+output "has_prod" {
+  value = contains(["dev", "prod"], "prod") ? "yes" : "no"
+}
+
+output "mixed" {
+  value = sort(["a", 1])
+}
+
+output "position" {
+  value = index(["dev", "prod"], "prod")
+}
+
+output "missing" {
+  value = index(["dev", "prod"], "qa")
+}
+
+output "ordered" {
+  value = reverse(sort(["b", "c", "a"]))
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let ctx = EvalContext::plan_only(HashMap::new());
+    let output = |name: &str| {
+        let o = ws.outputs.iter().find(|o| o.name == name).unwrap();
+        eval_expression(&o.value, &ctx)
+    };
+    let error = |name: &str| format!("{:#}", output(name).unwrap_err());
+    let output = |name: &str| output(name).unwrap();
+    assert_eq!(
+        output("matrix"),
+        json!([["dev", "us"], ["dev", "eu"], ["prod", "us"], ["prod", "eu"]])
+    );
+    assert_eq!(output("ports"), json!({"http": 80, "https": 443}));
+    assert_eq!(output("batches"), json!([["a", "b"], ["c"]]));
+    assert_eq!(output("middle"), json!(["b", "c"]));
+    assert!(error("bad_slice").starts_with("slice()"));
+    assert!(error("bad_zip").starts_with("zipmap()"));
+    assert_eq!(output("has_prod"), "yes");
+    assert_eq!(output("position"), 1);
+    assert!(error("missing").starts_with("index()"));
+    assert!(error("mixed").starts_with("sort()"));
+    assert_eq!(output("ordered"), json!(["c", "b", "a"]));
+}
+
+#[test]
+fn test_collection_functions_drive_for_each() {
+    let hcl = r#"
+resource "aws_security_group_rule" "ingress" {
+  for_each = zipmap(["http", "https"], [80, 443])
+  port     = each.value
+}
+
+resource "aws_instance" "matrix" {
+  for_each = zipmap(["dev-a", "dev-b", "prod-a", "prod-b"], setproduct(["dev", "prod"], ["a", "b"]))
+  tags     = { env = each.value[0] }
+}
+
+resource "aws_subnet" "tail" {
+  for_each = toset(slice(["a", "b", "c"], 1, 3))
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (_, nodes) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let mut addresses: Vec<&str> = nodes.keys().map(String::as_str).collect();
+    addresses.sort();
+    assert_eq!(
+        addresses,
+        [
+            "aws_instance.matrix[\"dev-a\"]",
+            "aws_instance.matrix[\"dev-b\"]",
+            "aws_instance.matrix[\"prod-a\"]",
+            "aws_instance.matrix[\"prod-b\"]",
+            "aws_security_group_rule.ingress[\"http\"]",
+            "aws_security_group_rule.ingress[\"https\"]",
+            "aws_subnet.tail[\"b\"]",
+            "aws_subnet.tail[\"c\"]",
+        ]
+    );
+}
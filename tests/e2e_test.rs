@@ -1,6 +1,7 @@
 //! End-to-end tests for oxid CLI using real fixtures.
 //!
-//! All tests are `#[ignore]` — plain `cargo test` skips them entirely.
+//! Tests that need provider downloads or cloud credentials are `#[ignore]`,
+//! so plain `cargo test` only runs the offline ones.
 //!
 //! ```bash
 //! cargo test -- --ignored                       # Tier 1: no-creds e2e
@@ -66,6 +67,20 @@ fn e2e_01_pure_hcl_init() {
         .stdout(predicate::str::contains("Project initialized successfully"));
 }
 
+#[test]
+#[ignore]
+fn e2e_01_pure_hcl_init_check_writes_nothing() {
+    let fixture = fixture_dir("01-pure-hcl");
+    let work = TempDir::new().unwrap();
+    let work_dir = work.path().join(".oxid");
+    oxid_cmd("init", &fixture, &work_dir)
+        .arg("--check")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Configuration is valid"));
+    assert!(!work_dir.exists());
+}
+
 #[test]
 #[ignore]
 fn e2e_01_pure_hcl_plan() {
@@ -88,6 +103,133 @@ fn e2e_01_pure_hcl_plan() {
         );
 }
 
+#[test]
+#[ignore]
+fn e2e_01_pure_hcl_import_resource() {
+    let fixture = fixture_dir("01-pure-hcl");
+    let work = TempDir::new().unwrap();
+    oxid_cmd("init", &fixture, work.path()).assert().success();
+    // random_integer import IDs are "result,min,max"
+    oxid_cmd("import", &fixture, work.path())
+        .args(["resource", "random_integer.port", "8500,8000,9000"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported random_integer.port"));
+    // The provider populates every attribute, not just the ID
+    oxid_cmd("state", &fixture, work.path())
+        .args(["show", "random_integer.port"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("result")
+                .and(predicate::str::contains("8500"))
+                .and(predicate::str::contains("min"))
+                .and(predicate::str::contains("max")),
+        );
+}
+
+#[test]
+#[ignore]
+fn e2e_01_pure_hcl_plan_memory_state() {
+    let fixture = fixture_dir("01-pure-hcl");
+    let work = TempDir::new().unwrap();
+    // No init: the in-memory backend seeds its own default workspace
+    oxid_cmd("plan", &fixture, work.path())
+        .args(["--state", "memory"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("5 to add"));
+    assert!(!work.path().join("oxid.db").exists());
+}
+
+#[test]
+#[ignore]
+fn e2e_01_pure_hcl_state_list_memory_state() {
+    let fixture = fixture_dir("01-pure-hcl");
+    let work = TempDir::new().unwrap();
+    oxid_cmd("state", &fixture, work.path())
+        .args(["list", "--state", "memory"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No resources in state."));
+    assert!(!work.path().join("oxid.db").exists());
+}
+
+#[test]
+#[ignore]
+fn e2e_01_pure_hcl_plan_detailed_exitcode_changes() {
+    let fixture = fixture_dir("01-pure-hcl");
+    let work = TempDir::new().unwrap();
+    oxid_cmd("plan", &fixture, work.path())
+        .args(["--state", "memory", "--detailed-exitcode"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("5 to add"));
+}
+
+#[test]
+fn e2e_plan_detailed_exitcode_no_changes() {
+    let config = TempDir::new().unwrap();
+    std::fs::write(
+        config.path().join("main.tf"),
+        "variable \"name\" {\n  default = \"oxid\"\n}\n",
+    )
+    .unwrap();
+    let work = TempDir::new().unwrap();
+    oxid_cmd("plan", config.path(), work.path())
+        .args(["--state", "memory", "--detailed-exitcode"])
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains("No changes"));
+
+    // Errors exit with 1
+    std::fs::write(config.path().join("main.tf"), "resource \"a\" {\n").unwrap();
+    oxid_cmd("plan", config.path(), work.path())
+        .args(["--state", "memory", "--detailed-exitcode"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn e2e_plan_refresh_only_rejects_target() {
+    let config = TempDir::new().unwrap();
+    std::fs::write(config.path().join("main.tf"), "").unwrap();
+    let work = TempDir::new().unwrap();
+    for selection in ["--target", "--exclude"] {
+        oxid_cmd("plan", config.path(), work.path())
+            .args([
+                "--state",
+                "memory",
+                "--refresh-only",
+                selection,
+                "null_resource.a",
+            ])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+}
+
+#[test]
+fn e2e_unparsable_config_does_not_fall_back_to_local_state() {
+    let config = TempDir::new().unwrap();
+    std::fs::write(
+        config.path().join("main.tf"),
+        "terraform {\n  backend \"s3\" {\n",
+    )
+    .unwrap();
+    let work = TempDir::new().unwrap();
+    oxid_cmd("state", config.path(), work.path())
+        .arg("list")
+        .env_remove("OXID_STATE_DSN")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Failed to read the backend configuration",
+        ));
+    assert!(!work.path().join("oxid.db").exists());
+}
+
 // ── 02-pure-tf-json ──────────────────────────────────────────────────────────
 
 #[test]
@@ -335,3 +477,238 @@ fn e2e_06_aws_mixed_plan() {
                 .and(predicate::str::contains("2 to add")),
         );
 }
+
+// ─── Workspaces ──────────────────────────────────────────────────────────────
+
+#[tokio::test]
+#[ignore]
+async fn e2e_apply_lands_in_selected_workspace() {
+    use oxid::state::backend::StateBackend;
+    use oxid::state::models::ResourceFilter;
+    use oxid::state::sqlite::SqliteBackend;
+
+    let config = TempDir::new().unwrap();
+    std::fs::write(
+        config.path().join("main.tf"),
+        r#"
+terraform {
+  required_providers {
+    random = {
+      source  = "hashicorp/random"
+      version = "3.7.2"
+    }
+  }
+}
+
+resource "random_integer" "port" {
+  min = 8000
+  max = 9000
+}
+"#,
+    )
+    .unwrap();
+    let work = TempDir::new().unwrap();
+    let oxid = |subcommand: &str, args: &[&str]| {
+        let mut cmd = oxid_cmd(subcommand, config.path(), work.path());
+        cmd.args(args);
+        cmd
+    };
+
+    oxid("init", &[]).assert().success();
+    oxid("workspace", &["new", "staging"]).assert().success();
+    oxid("workspace", &["select", "staging"]).assert().success();
+    oxid("apply", &["--auto-approve"]).assert().success();
+
+    let backend = SqliteBackend::open(&work.path().join("oxid.db").to_string_lossy()).unwrap();
+    let addresses = |ws_id: String| {
+        let backend = &backend;
+        async move {
+            backend
+                .list_resources(&ws_id, &ResourceFilter::default())
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.address)
+                .collect::<Vec<_>>()
+        }
+    };
+    let staging = backend.get_workspace("staging").await.unwrap().unwrap();
+    let default = backend.get_workspace("default").await.unwrap().unwrap();
+    assert_eq!(addresses(staging.id).await, ["random_integer.port"]);
+    assert!(addresses(default.id).await.is_empty());
+
+    // --workspace overrides the selection
+    oxid("state", &["list", "--workspace", "default"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No resources in state."));
+    oxid("state", &["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("random_integer.port"));
+}
+
+// ─── Replacement ─────────────────────────────────────────────────────────────
+
+/// With create_before_destroy the replacement is created while the prior
+/// object still exists, so one that fails to create leaves the prior object
+/// in place, tracked as deposed and kept out of `state list`. Without it the
+/// prior object is destroyed first. Downloads hashicorp/local.
+#[test]
+#[ignore]
+fn e2e_create_before_destroy_creates_the_replacement_first() {
+    for create_before_destroy in [true, false] {
+        let config = TempDir::new().unwrap();
+        std::fs::write(
+            config.path().join("main.tf"),
+            format!(
+                r#"
+terraform {{
+  required_providers {{
+    local = {{
+      source  = "hashicorp/local"
+      version = "2.5.2"
+    }}
+  }}
+}}
+
+variable "path" {{
+  default = "unused"
+}}
+
+resource "local_file" "out" {{
+  filename = var.path
+  content  = "hello"
+
+  lifecycle {{
+    create_before_destroy = {create_before_destroy}
+  }}
+}}
+"#
+            ),
+        )
+        .unwrap();
+        let work = TempDir::new().unwrap();
+        let oxid = |subcommand: &str, args: &[&str]| {
+            let mut cmd = oxid_cmd(subcommand, config.path(), work.path());
+            cmd.args(args);
+            cmd
+        };
+        let prior = work.path().join("prior.txt");
+        let prior_var = format!("path={}", prior.display());
+
+        oxid("init", &[]).assert().success();
+        oxid("apply", &["--auto-approve", "--var", &prior_var])
+            .assert()
+            .success();
+        assert!(prior.exists());
+
+        // Nothing can be created under /proc, so the replacement fails
+        oxid(
+            "apply",
+            &["--auto-approve", "--var", "path=/proc/oxid/out.txt"],
+        )
+        .assert()
+        .failure();
+        assert_eq!(prior.exists(), create_before_destroy);
+        oxid("state", &["list"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("(deposed)").not());
+    }
+}
+
+// ─── Provider connection pool ────────────────────────────────────────────────
+
+/// Many concurrent plans share one provider process without serializing on
+/// the connection pool, and `stop_all` drains it. Downloads hashicorp/null.
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+#[ignore]
+async fn e2e_provider_pool_concurrent_plans() {
+    use oxid::provider::manager::ProviderManager;
+    use std::sync::Arc;
+
+    let cache = TempDir::new().unwrap();
+    let pm = Arc::new(ProviderManager::new(cache.path().to_path_buf()));
+    pm.get_schema("hashicorp/null", "3.2.4").await.unwrap();
+    pm.configure_provider("hashicorp/null", &serde_json::json!({}))
+        .await
+        .unwrap();
+
+    let config = serde_json::json!({ "id": null, "triggers": null });
+    let mut tasks = tokio::task::JoinSet::new();
+    for i in 0..128 {
+        let pm = pm.clone();
+        let config = config.clone();
+        tasks.spawn(async move {
+            if i % 16 == 0 {
+                // Schema lookups and connection checks interleave with plans
+                pm.get_connection("hashicorp/null", "3.2.4").await.unwrap();
+                pm.get_schema("hashicorp/null", "3.2.4").await.unwrap();
+            }
+            pm.plan_resource(
+                "hashicorp/null",
+                "null_resource",
+                None,
+                Some(&config),
+                &config,
+                &[],
+            )
+            .await
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        let plan = result.unwrap().unwrap();
+        assert!(plan.planned_state.is_some());
+    }
+    assert_eq!(pm.list_running().await, vec!["hashicorp/null".to_string()]);
+
+    pm.stop_all().await.unwrap();
+    assert!(pm.list_running().await.is_empty());
+    let err = pm
+        .plan_resource("hashicorp/null", "null_resource", None, None, &config, &[])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not connected"), "{}", err);
+}
+
+/// A provider whose process dies is restarted and reconfigured on the next
+/// RPC instead of failing every later call. Downloads hashicorp/null.
+#[tokio::test]
+#[ignore]
+async fn e2e_provider_restarts_after_crash() {
+    use oxid::provider::manager::ProviderManager;
+
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf());
+    pm.get_schema("hashicorp/null", "3.2.4").await.unwrap();
+    pm.configure_provider("hashicorp/null", &serde_json::json!({}))
+        .await
+        .unwrap();
+
+    let pid = pm.provider_pid("hashicorp/null").await.unwrap().unwrap();
+    let killed = std::process::Command::new("kill")
+        .args(["-9", &pid.to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let config = serde_json::json!({ "id": null, "triggers": null });
+    let plan = pm
+        .plan_resource(
+            "hashicorp/null",
+            "null_resource",
+            None,
+            Some(&config),
+            &config,
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(plan.planned_state.is_some());
+
+    let restarted = pm.provider_pid("hashicorp/null").await.unwrap().unwrap();
+    assert_ne!(restarted, pid);
+    pm.stop_all().await.unwrap();
+}
@@ -0,0 +1,1788 @@
+use oxid::config::types::{MovedBlock, ProvisionerWhen, RemovedBlock, ResourceIndex};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use oxid::dag::resource_graph::{add_orphans, build_resource_dag, DagNode};
+use oxid::dag::walker::{DagWalker, NodeExecutor, WalkMode};
+use oxid::executor::engine::{
+    apply_forced_replacements, apply_replace_triggers, attributes_to_json,
+    build_full_provider_config, build_provider_limits, build_provider_map, build_variable_defaults,
+    check_replace_addresses, deposed_address, determine_action, forgotten_addresses,
+    index_scheme_migrations, plan_orphaned_instances, propagate_config_unknowns,
+    provider_instances, refresh_concurrently, refresh_only_changes, refreshed_records,
+    removed_records, resolve_moves, resolve_provisioners, track_resource_status,
+    validate_block_items, EvalContext, PlanSummary, PlannedChange, RecordAction, RefreshOutcome,
+    ResourceAction, Selection, StateUpdates,
+};
+use oxid::hcl::parser::parse_hcl;
+use oxid::provider::manager::{provider_address, split_provider_alias, ProviderManager};
+use oxid::provider::protocol::unknown_value;
+use oxid::state::backend::StateBackend;
+use oxid::state::models::ResourceFilter;
+use oxid::state::models::{action, status, ResourceState};
+use oxid::state::sqlite::SqliteBackend;
+use serde_json::json;
+
+// ─── Deposed objects ─────────────────────────────────────────────────────────
+
+#[test]
+fn test_deposed_address_is_distinct() {
+    assert_eq!(
+        deposed_address("aws_instance.web[0]"),
+        "aws_instance.web[0] (deposed)"
+    );
+    assert_ne!(deposed_address("aws_instance.web"), "aws_instance.web");
+}
+
+// ─── Nested block item limits ────────────────────────────────────────────────
+
+/// Synthetic schema: `rule` is a LIST block with 1..=2 items, each holding an
+/// optional SINGLE `filter` block that must be present.
+fn block_limits_schema() -> serde_json::Value {
+    json!({
+        "version": 0,
+        "block": {
+            "attributes": [{"name": "name", "type": "string", "optional": true}],
+            "block_types": [
+                {
+                    "type_name": "rule",
+                    "nesting": 2,
+                    "min_items": 1,
+                    "max_items": 2,
+                    "block": {
+                        "attributes": [{"name": "id", "type": "string", "required": true}],
+                        "block_types": [
+                            {"type_name": "filter", "nesting": 1, "min_items": 1, "max_items": 1,
+                             "block": {"attributes": [], "block_types": []}}
+                        ]
+                    }
+                },
+                {"type_name": "tags", "nesting": 3, "min_items": 0, "max_items": 0,
+                 "block": {"attributes": [], "block_types": []}}
+            ]
+        }
+    })
+}
+
+#[test]
+fn test_block_items_within_limits() {
+    let config = json!({
+        "name": "x",
+        "rule": [{"id": "a", "filter": {}}, {"id": "b", "filter": {}}],
+        "tags": [],
+    });
+    assert!(validate_block_items(&config, &block_limits_schema()).is_ok());
+}
+
+#[test]
+fn test_block_requires_min_items() {
+    let config = json!({"name": "x", "rule": [], "tags": []});
+    let err = validate_block_items(&config, &block_limits_schema()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "block `rule` requires at least 1 item, got 0"
+    );
+}
+
+#[test]
+fn test_block_exceeds_max_items() {
+    let rule = json!({"id": "a", "filter": {}});
+    let config = json!({"rule": [rule, rule, rule], "tags": []});
+    let err = validate_block_items(&config, &block_limits_schema()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "block `rule` allows at most 2 items, got 3"
+    );
+}
+
+#[test]
+fn test_nested_single_block_required() {
+    let config = json!({"rule": [{"id": "a", "filter": null}], "tags": []});
+    let err = validate_block_items(&config, &block_limits_schema()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "block `rule.filter` requires at least 1 item, got 0"
+    );
+}
+
+#[test]
+fn test_unknown_block_list_is_skipped() {
+    let config = json!({"rule": null, "tags": null});
+    assert!(validate_block_items(&config, &block_limits_schema()).is_ok());
+}
+
+// ─── Planned private data ────────────────────────────────────────────────────
+
+#[test]
+fn test_planned_private_round_trips_byte_for_byte() {
+    let planned_private: Vec<u8> = (0..=255).collect();
+    let change = PlannedChange {
+        address: "aws_instance.web".to_string(),
+        action: ResourceAction::Create,
+        resource_type: "aws_instance".to_string(),
+        provider_source: "hashicorp/aws".to_string(),
+        planned_state: Some(json!({"ami": "ami-123"})),
+        prior_state: None,
+        user_config: None,
+        requires_replace: vec![],
+        replace_reason: None,
+        planned_private: planned_private.clone(),
+    };
+
+    let serialized = serde_json::to_string(&change).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&serialized).unwrap();
+    assert!(value["planned_private"].is_string());
+    assert_eq!(value["action"], "create");
+
+    let restored: PlannedChange = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(restored.planned_private, planned_private);
+    assert_eq!(restored.action, ResourceAction::Create);
+}
+
+// ─── Concurrent refresh ──────────────────────────────────────────────────────
+
+fn refresh_fixture() -> Vec<ResourceState> {
+    let mut resources: Vec<ResourceState> = (0..20)
+        .map(|i| {
+            let mut r = ResourceState::new(
+                "ws",
+                "null_resource",
+                &i.to_string(),
+                &format!("null_resource.r{}", i),
+            );
+            r.provider_source = if i % 2 == 0 {
+                "hashicorp/null".to_string()
+            } else {
+                "hashicorp/random".to_string()
+            };
+            r
+        })
+        .collect();
+    resources.push(ResourceState::new(
+        "ws",
+        "null_resource",
+        "orphan",
+        "null_resource.orphan",
+    ));
+    resources
+}
+
+#[tokio::test]
+async fn test_refresh_concurrently_reads_every_resource() {
+    let resources = refresh_fixture();
+    let running = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+
+    let outcomes = refresh_concurrently(&resources, 4, |resource| {
+        let (running, peak) = (&running, &peak);
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(Some((json!({"id": resource.address}), vec![7])))
+        }
+    })
+    .await;
+
+    // Every resource with a provider is read exactly once
+    assert_eq!(outcomes.len(), 20);
+    assert!(!outcomes.contains_key("null_resource.orphan"));
+    for i in 0..20 {
+        let address = format!("null_resource.r{}", i);
+        match &outcomes[&address] {
+            RefreshOutcome::Refreshed {
+                state,
+                private_data,
+            } => {
+                assert_eq!(state["id"], address);
+                assert_eq!(private_data, &vec![7]);
+            }
+            other => panic!("{} not refreshed: {:?}", address, other),
+        }
+    }
+    assert!(peak.load(Ordering::SeqCst) <= 4);
+}
+
+#[tokio::test]
+async fn test_refresh_concurrently_reports_gone_and_failed() {
+    let resources = refresh_fixture();
+    let outcomes = refresh_concurrently(&resources, 3, |resource| async move {
+        match resource.address.as_str() {
+            "null_resource.r1" => Ok(None),
+            "null_resource.r2" => anyhow::bail!("provider crashed"),
+            _ => Ok(Some((json!({}), vec![]))),
+        }
+    })
+    .await;
+
+    assert!(matches!(outcomes["null_resource.r1"], RefreshOutcome::Gone));
+    assert!(matches!(
+        &outcomes["null_resource.r2"],
+        RefreshOutcome::Failed(e) if e.to_string() == "provider crashed"
+    ));
+    assert!(matches!(
+        outcomes["null_resource.r3"],
+        RefreshOutcome::Refreshed { .. }
+    ));
+}
+
+// ─── Refresh before plan ─────────────────────────────────────────────────────
+
+/// A stand-in provider that counts reads and reports `r1` deleted remotely.
+async fn counting_read(
+    reads: &AtomicUsize,
+    resource: &ResourceState,
+) -> anyhow::Result<Option<(serde_json::Value, Vec<u8>)>> {
+    reads.fetch_add(1, Ordering::SeqCst);
+    match resource.address.as_str() {
+        "null_resource.r1" => Ok(None),
+        "null_resource.r2" => anyhow::bail!("provider crashed"),
+        _ => Ok(Some((json!({"id": "live"}), vec![1]))),
+    }
+}
+
+#[tokio::test]
+async fn test_refresh_reads_every_record_before_planning() {
+    let resources = refresh_fixture();
+    let reads = AtomicUsize::new(0);
+    let records = refreshed_records(&resources, 4, true, |r| counting_read(&reads, r)).await;
+
+    // One read per resource that has a provider
+    assert_eq!(reads.load(Ordering::SeqCst), 20);
+    // Objects gone remotely are dropped so they plan as creates
+    assert!(!records.iter().any(|r| r.address == "null_resource.r1"));
+    assert_eq!(records.len(), resources.len() - 1);
+
+    let by_address = |address: &str| records.iter().find(|r| r.address == address).unwrap();
+    assert_eq!(
+        by_address("null_resource.r0").attributes_json,
+        r#"{"id":"live"}"#
+    );
+    assert_eq!(by_address("null_resource.r0").private_data, vec![1]);
+    // A failed read keeps the stored record
+    assert_eq!(
+        by_address("null_resource.r2").attributes_json,
+        resources[2].attributes_json
+    );
+}
+
+#[tokio::test]
+async fn test_refresh_false_never_reads() {
+    let resources = refresh_fixture();
+    let reads = AtomicUsize::new(0);
+    let records = refreshed_records(&resources, 4, false, |r| counting_read(&reads, r)).await;
+
+    assert_eq!(reads.load(Ordering::SeqCst), 0);
+    assert_eq!(records.len(), resources.len());
+    assert!(records
+        .iter()
+        .zip(&resources)
+        .all(|(a, b)| a.address == b.address && a.attributes_json == b.attributes_json));
+}
+
+// ─── Provider aliases ────────────────────────────────────────────────────────
+
+const MULTI_REGION: &str = r#"
+provider "aws" {
+  alias  = "east"
+  region = "us-east-1"
+}
+
+provider "aws" {
+  alias        = "west"
+  region       = "us-west-2"
+  max_parallel = 2
+}
+
+resource "aws_s3_bucket" "primary" {
+  provider = aws.east
+  bucket   = "logs-east"
+}
+
+resource "aws_s3_bucket" "replica" {
+  provider = aws.west
+  bucket   = "logs-west"
+}
+
+resource "aws_iam_role" "replication" {
+  name = "replication"
+}
+"#;
+
+fn node_providers(hcl: &str) -> HashMap<String, String> {
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) = build_resource_dag(&ws, &build_provider_map(&ws), &HashMap::new()).unwrap();
+    graph
+        .node_indices()
+        .filter_map(|idx| match &graph[idx] {
+            DagNode::Resource {
+                address,
+                provider_source,
+                ..
+            } => Some((address.clone(), provider_source.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_provider_address_round_trip() {
+    assert_eq!(
+        provider_address("hashicorp/aws", Some("west")),
+        "hashicorp/aws.west"
+    );
+    assert_eq!(provider_address("hashicorp/aws", None), "hashicorp/aws");
+    assert_eq!(
+        split_provider_alias("hashicorp/aws.west"),
+        ("hashicorp/aws", Some("west"))
+    );
+    assert_eq!(
+        split_provider_alias("registry.terraform.io/hashicorp/aws"),
+        ("registry.terraform.io/hashicorp/aws", None)
+    );
+}
+
+#[test]
+fn test_aliased_resources_route_to_their_provider() {
+    let providers = node_providers(MULTI_REGION);
+    assert_eq!(providers["aws_s3_bucket.primary"], "hashicorp/aws.east");
+    assert_eq!(providers["aws_s3_bucket.replica"], "hashicorp/aws.west");
+    assert_eq!(providers["aws_iam_role.replication"], "hashicorp/aws");
+}
+
+#[test]
+fn test_each_alias_is_configured_from_its_own_block() {
+    let ws = parse_hcl(MULTI_REGION, Path::new("main.tf")).unwrap();
+    let instances = provider_instances(&ws).unwrap();
+    let addresses: Vec<&str> = instances.iter().map(|(a, _)| a.as_str()).collect();
+    assert_eq!(
+        addresses,
+        ["hashicorp/aws.east", "hashicorp/aws.west", "hashicorp/aws"]
+    );
+
+    let region = |address: &str| {
+        let (_, provider) = instances.iter().find(|(a, _)| a == address).unwrap();
+        provider.config.get("region").cloned()
+    };
+    assert!(format!("{:?}", region("hashicorp/aws.east")).contains("us-east-1"));
+    assert!(format!("{:?}", region("hashicorp/aws.west")).contains("us-west-2"));
+    // The unaliased role uses an implicit, empty default configuration
+    assert!(region("hashicorp/aws").is_none());
+
+    let limits = build_provider_limits(&ws);
+    assert_eq!(limits.get("hashicorp/aws.west"), Some(&2));
+    assert!(!limits.contains_key("hashicorp/aws.east"));
+}
+
+#[test]
+fn test_reference_to_undeclared_alias_is_rejected() {
+    let hcl = r#"
+provider "aws" {
+  region = "us-east-1"
+}
+
+resource "aws_s3_bucket" "b" {
+  provider = aws.central
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let err = provider_instances(&ws).unwrap_err().to_string();
+    assert!(err.contains("aws_s3_bucket.b"), "{}", err);
+    assert!(err.contains("alias \"central\""), "{}", err);
+}
+
+// ─── Provisioner self references ─────────────────────────────────────────────
+
+const PROVISIONED: &str = r#"
+resource "aws_instance" "web" {
+  ami = "ami-123"
+
+  provisioner "local-exec" {
+    command = "echo ${self.id} >> hosts.txt"
+
+    connection {
+      host = self.public_ip
+    }
+  }
+
+  provisioner "local-exec" {
+    when    = destroy
+    command = "echo removing ${self.id}"
+  }
+}
+"#;
+
+#[test]
+fn test_provisioner_resolves_self_against_new_state() {
+    let ws = parse_hcl(PROVISIONED, Path::new("main.tf")).unwrap();
+    let ctx = EvalContext::plan_only(HashMap::new())
+        .with_self(Some(json!({"id": "i-0abc", "public_ip": "10.0.0.7"})));
+
+    let create =
+        resolve_provisioners(&ws.resources[0].provisioners, ProvisionerWhen::Create, &ctx).unwrap();
+    assert_eq!(create.len(), 1);
+    let (provisioner, config) = &create[0];
+    assert_eq!(provisioner.provisioner_type, "local-exec");
+    assert_eq!(config["command"], "echo i-0abc >> hosts.txt");
+    assert_eq!(config["connection"]["host"], "10.0.0.7");
+
+    let destroy = resolve_provisioners(
+        &ws.resources[0].provisioners,
+        ProvisionerWhen::Destroy,
+        &ctx,
+    )
+    .unwrap();
+    assert_eq!(destroy.len(), 1);
+    assert_eq!(destroy[0].1["command"], "echo removing i-0abc");
+}
+
+#[test]
+fn test_self_is_null_without_bound_state() {
+    let ws = parse_hcl(PROVISIONED, Path::new("main.tf")).unwrap();
+    let ctx = EvalContext::plan_only(HashMap::new());
+    let create =
+        resolve_provisioners(&ws.resources[0].provisioners, ProvisionerWhen::Create, &ctx).unwrap();
+    assert_eq!(create[0].1["command"], "echo  >> hosts.txt");
+}
+
+// ─── Data source instances ───────────────────────────────────────────────────
+
+const DATA_FOR_EACH: &str = r#"
+variable "zones" {
+  default = ["us-east-1a", "us-east-1b"]
+}
+
+data "aws_subnet" "zone" {
+  for_each          = toset(var.zones)
+  availability_zone = each.key
+}
+
+data "aws_ami" "pinned" {
+  count = 2
+  name  = "ami-${count.index}"
+}
+
+resource "aws_instance" "web" {
+  for_each  = toset(var.zones)
+  subnet_id = data.aws_subnet.zone[each.key].id
+  ami       = data.aws_ami.pinned[1].id
+  first     = data.aws_subnet.zone["us-east-1a"].cidr_block
+  all       = data.aws_subnet.zone[*].id
+}
+"#;
+
+#[test]
+fn test_data_source_for_each_expands_to_indexed_nodes() {
+    let ws = parse_hcl(DATA_FOR_EACH, Path::new("main.tf")).unwrap();
+    let (graph, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    for address in [
+        r#"data.aws_subnet.zone["us-east-1a"]"#,
+        r#"data.aws_subnet.zone["us-east-1b"]"#,
+        "data.aws_ami.pinned[0]",
+        "data.aws_ami.pinned[1]",
+    ] {
+        let idx = node_map[address];
+        assert!(
+            matches!(graph[idx], DagNode::DataSource { .. }),
+            "{} is not a data source node",
+            address
+        );
+        assert_eq!(
+            graph[idx].base_address(),
+            address.split('[').next().unwrap()
+        );
+    }
+    assert!(!node_map.contains_key("data.aws_subnet.zone"));
+
+    // Each instance reads with its own key
+    let idx = node_map[r#"data.aws_subnet.zone["us-east-1b"]"#];
+    let DagNode::DataSource { config, index, .. } = &graph[idx] else {
+        unreachable!()
+    };
+    let mut ctx = EvalContext::plan_only(build_variable_defaults(&ws));
+    if let Some(ResourceIndex::ForEach(key)) = index {
+        ctx.each_key = Some(key.clone());
+    }
+    assert_eq!(
+        attributes_to_json(&config.attributes, &ctx).unwrap()["availability_zone"],
+        "us-east-1b"
+    );
+
+    // Dependents wait on every instance of a referenced data source
+    let web = node_map[r#"aws_instance.web["us-east-1a"]"#];
+    let deps: std::collections::BTreeSet<&str> = graph
+        .neighbors_directed(web, petgraph::Direction::Incoming)
+        .map(|i| graph[i].address())
+        .collect();
+    assert_eq!(
+        deps.into_iter().collect::<Vec<_>>(),
+        [
+            "data.aws_ami.pinned[0]",
+            "data.aws_ami.pinned[1]",
+            r#"data.aws_subnet.zone["us-east-1a"]"#,
+            r#"data.aws_subnet.zone["us-east-1b"]"#,
+        ]
+    );
+}
+
+#[test]
+fn test_indexed_data_source_references_resolve() {
+    let ws = parse_hcl(DATA_FOR_EACH, Path::new("main.tf")).unwrap();
+    let states = Arc::new(DashMap::new());
+    states.insert(
+        r#"data.aws_subnet.zone["us-east-1a"]"#.to_string(),
+        json!({ "id": "subnet-a", "cidr_block": "10.0.1.0/24" }),
+    );
+    states.insert(
+        r#"data.aws_subnet.zone["us-east-1b"]"#.to_string(),
+        json!({ "id": "subnet-b", "cidr_block": "10.0.2.0/24" }),
+    );
+    states.insert(
+        "data.aws_ami.pinned[1]".to_string(),
+        json!({ "id": "ami-1" }),
+    );
+
+    let mut ctx = EvalContext::with_states(build_variable_defaults(&ws), states);
+    ctx.each_key = Some("us-east-1b".to_string());
+    let web = ws.resources.iter().find(|r| r.name == "web").unwrap();
+    let config = attributes_to_json(&web.attributes, &ctx).unwrap();
+    assert_eq!(config["subnet_id"], "subnet-b");
+    assert_eq!(config["ami"], "ami-1");
+    assert_eq!(config["first"], "10.0.1.0/24");
+    assert_eq!(config["all"], json!(["subnet-a", "subnet-b"]));
+}
+
+// ─── Scaling count down ──────────────────────────────────────────────────────
+
+fn counted_config(count: usize) -> String {
+    format!(
+        r#"
+resource "null_resource" "web" {{
+  count = {count}
+}}
+
+resource "null_resource" "tags" {{
+  for_each = toset([])
+}}
+"#
+    )
+}
+
+fn state_record(address: &str) -> ResourceState {
+    let mut record = ResourceState::new("ws", "null_resource", "web", address);
+    record.provider_source = "hashicorp/null".to_string();
+    record.attributes_json = json!({"id": address}).to_string();
+    record
+}
+
+fn orphaned(count: usize, state: &[ResourceState]) -> Vec<PlannedChange> {
+    let ws = parse_hcl(&counted_config(count), Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    plan_orphaned_instances(&ws, &graph, state)
+}
+
+#[test]
+fn test_scaling_count_to_zero_deletes_every_instance() {
+    let state = [
+        state_record("null_resource.web[0]"),
+        state_record("null_resource.web[1]"),
+    ];
+    let changes = orphaned(0, &state);
+    let addresses: Vec<&str> = changes.iter().map(|c| c.address.as_str()).collect();
+    assert_eq!(addresses, ["null_resource.web[0]", "null_resource.web[1]"]);
+    for change in &changes {
+        assert_eq!(change.action, ResourceAction::Delete);
+        assert_eq!(change.provider_source, "hashicorp/null");
+        assert_eq!(change.prior_state, Some(json!({"id": change.address})));
+        assert!(change.planned_state.is_none());
+    }
+}
+
+#[test]
+fn test_scaling_count_down_deletes_only_removed_instances() {
+    let state = [
+        state_record("null_resource.web[0]"),
+        state_record("null_resource.web[1]"),
+        state_record("null_resource.tags[\"a\"]"),
+    ];
+    let addresses: Vec<String> = orphaned(1, &state).into_iter().map(|c| c.address).collect();
+    assert_eq!(
+        addresses,
+        ["null_resource.tags[\"a\"]", "null_resource.web[1]"]
+    );
+    assert!(orphaned(2, &state[..2]).is_empty());
+}
+
+#[test]
+fn test_unconfigured_and_deposed_records_are_not_orphans() {
+    let mut deposed = state_record(&deposed_address("null_resource.web[0]"));
+    deposed.status = "deposed".to_string();
+    let mut data = state_record("null_resource.web[1]");
+    data.resource_mode = "data".to_string();
+    let state = [state_record("null_resource.removed"), deposed, data];
+    assert!(orphaned(0, &state).is_empty());
+}
+
+#[test]
+fn test_orphans_are_destroyed_after_dependents_and_before_dependencies() {
+    let ws = parse_hcl(
+        r#"
+resource "null_resource" "net" {}
+
+resource "null_resource" "web" {
+  count    = 1
+  triggers = { net = null_resource.net.id }
+}
+
+resource "null_resource" "db" {
+  count    = 0
+  triggers = { net = null_resource.net.id }
+}
+
+resource "null_resource" "lb" {
+  triggers = { web = null_resource.web[0].id }
+}
+"#,
+        Path::new("main.tf"),
+    )
+    .unwrap();
+    let (mut graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let orphan = |address: &str, base_address: &str| DagNode::Orphan {
+        address: address.to_string(),
+        base_address: base_address.to_string(),
+        resource_type: "null_resource".to_string(),
+        provider_source: "hashicorp/null".to_string(),
+    };
+    let orphans = vec![
+        orphan("null_resource.web[1]", "null_resource.web"),
+        orphan("null_resource.db[0]", "null_resource.db"),
+    ];
+    add_orphans(&mut graph, &ws, orphans).unwrap();
+    let node = |address: &str| {
+        graph
+            .node_indices()
+            .find(|&idx| graph[idx].address() == address)
+            .unwrap()
+    };
+    let edge = |from: &str, to: &str| graph.find_edge(node(from), node(to)).is_some();
+
+    // lb stops referencing web[1] before it goes
+    assert!(edge("null_resource.lb", "null_resource.web[1]"));
+    assert!(edge("null_resource.db[0]", "null_resource.net"));
+    // net is applied before lb, so web[1] cannot also precede it
+    assert!(!edge("null_resource.web[1]", "null_resource.net"));
+}
+
+// ─── Moved blocks ────────────────────────────────────────────────────────────
+
+fn moved(from: &str, to: &str) -> MovedBlock {
+    MovedBlock {
+        from: from.to_string(),
+        to: to.to_string(),
+    }
+}
+
+fn addresses(records: &[ResourceState]) -> Vec<&str> {
+    records.iter().map(|r| r.address.as_str()).collect()
+}
+
+#[test]
+fn test_moved_block_prevents_spurious_replacement() {
+    let hcl = r#"
+moved {
+  from = null_resource.old
+  to   = null_resource.web
+}
+
+resource "null_resource" "web" {
+  count = 2
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let mut state = vec![
+        state_record("null_resource.old[0]"),
+        state_record("null_resource.old[1]"),
+    ];
+
+    // Without the move, each instance would be created anew
+    let action_for = |state: &[ResourceState], address: &str| {
+        // The provider plans the unchanged config back to the prior state
+        let prior = state
+            .iter()
+            .find(|r| r.address == address)
+            .map(|r| serde_json::from_str::<serde_json::Value>(&r.attributes_json).unwrap());
+        let planned = prior.clone().unwrap_or_else(|| json!({"id": address}));
+        determine_action(prior.as_ref(), Some(&planned), &[])
+    };
+    assert_eq!(
+        action_for(&state, "null_resource.web[0]"),
+        ResourceAction::Create
+    );
+
+    let moves = resolve_moves(&ws.moved, &mut state).unwrap();
+    assert_eq!(moves.len(), 2);
+    assert_eq!(moves[0].0, "null_resource.old[0]");
+    assert_eq!(
+        addresses(&state),
+        ["null_resource.web[0]", "null_resource.web[1]"]
+    );
+    // The moved record keeps its id, so dependency links survive
+    assert_eq!(moves[0].1.id, state[0].id);
+
+    for idx in graph.node_indices() {
+        let address = graph[idx].address();
+        assert_eq!(
+            action_for(&state, address),
+            ResourceAction::NoOp,
+            "{}",
+            address
+        );
+    }
+    assert!(plan_orphaned_instances(&ws, &graph, &state).is_empty());
+}
+
+#[test]
+fn test_moved_chains_resolve_in_any_order() {
+    let mut state = vec![state_record("null_resource.a")];
+    let blocks = [
+        moved("null_resource.b", "null_resource.c"),
+        moved("null_resource.a", "null_resource.b"),
+    ];
+    let moves = resolve_moves(&blocks, &mut state).unwrap();
+    let steps: Vec<(&str, &str)> = moves
+        .iter()
+        .map(|(from, to)| (from.as_str(), to.address.as_str()))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            ("null_resource.a", "null_resource.b"),
+            ("null_resource.b", "null_resource.c"),
+        ]
+    );
+    assert_eq!(addresses(&state), ["null_resource.c"]);
+
+    // Already applied: nothing left to move
+    assert!(resolve_moves(&blocks, &mut state).unwrap().is_empty());
+}
+
+#[test]
+fn test_moved_blocks_skip_missing_sources_and_taken_destinations() {
+    let mut state = vec![
+        state_record("null_resource.a"),
+        state_record("null_resource.b"),
+    ];
+    let blocks = [
+        moved("null_resource.gone", "null_resource.web"),
+        moved("null_resource.a", "null_resource.b"),
+    ];
+    assert!(resolve_moves(&blocks, &mut state).unwrap().is_empty());
+    assert_eq!(addresses(&state), ["null_resource.a", "null_resource.b"]);
+}
+
+#[test]
+fn test_moved_blocks_reject_cycles_and_type_changes() {
+    let mut state = vec![state_record("null_resource.a")];
+    let err = resolve_moves(
+        &[
+            moved("null_resource.a", "null_resource.b"),
+            moved("null_resource.b", "null_resource.a"),
+        ],
+        &mut state,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("cycle"), "{}", err);
+
+    let err = resolve_moves(&[moved("null_resource.a", "aws_instance.a")], &mut state).unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("resource types differ"),
+        "{:#}",
+        err
+    );
+    assert_eq!(addresses(&state), ["null_resource.a"]);
+}
+
+#[test]
+fn test_count_to_for_each_migration_with_moved_blocks_destroys_nothing() {
+    let hcl = r#"
+moved {
+  from = null_resource.web[0]
+  to   = null_resource.web["a"]
+}
+
+moved {
+  from = null_resource.web[1]
+  to   = null_resource.web["b"]
+}
+
+resource "null_resource" "web" {
+  for_each = toset(["a", "b"])
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let mut state = vec![
+        state_record("null_resource.web[0]"),
+        state_record("null_resource.web[1]"),
+    ];
+
+    let warnings = index_scheme_migrations(&ws, &state);
+    assert_eq!(warnings.len(), 1);
+    assert!(
+        warnings[0].starts_with("null_resource.web switched from count to for_each"),
+        "{}",
+        warnings[0]
+    );
+
+    let moves = resolve_moves(&ws.moved, &mut state).unwrap();
+    assert_eq!(moves.len(), 2);
+    assert_eq!(
+        addresses(&state),
+        [r#"null_resource.web["a"]"#, r#"null_resource.web["b"]"#]
+    );
+    assert!(index_scheme_migrations(&ws, &state).is_empty());
+    assert_eq!(state[0].index_key.as_deref(), Some("a"));
+    let planned: Vec<&str> = graph.node_indices().map(|i| graph[i].address()).collect();
+    for record in &state {
+        assert!(
+            planned.contains(&record.address.as_str()),
+            "{}",
+            record.address
+        );
+    }
+    assert!(plan_orphaned_instances(&ws, &graph, &state).is_empty());
+}
+
+#[test]
+fn test_moved_instance_to_bare_address_ignores_other_instances() {
+    let mut state = vec![
+        state_record("null_resource.web[0]"),
+        state_record("null_resource.web[1]"),
+    ];
+    let moves = resolve_moves(
+        &[moved("null_resource.web[0]", "null_resource.web")],
+        &mut state,
+    )
+    .unwrap();
+    assert_eq!(moves.len(), 1);
+    assert_eq!(
+        addresses(&state),
+        ["null_resource.web", "null_resource.web[1]"]
+    );
+}
+
+// ─── Removed blocks ──────────────────────────────────────────────────────────
+
+const REMOVED_CONFIG: &str = r#"
+removed {
+  from = null_resource.old
+}
+
+removed {
+  from = null_resource.kept
+  lifecycle {
+    destroy = false
+  }
+}
+
+resource "null_resource" "web" {}
+"#;
+
+#[test]
+fn test_removed_blocks_parse_destroy_mode() {
+    let ws = parse_hcl(REMOVED_CONFIG, Path::new("main.tf")).unwrap();
+    assert_eq!(
+        ws.removed,
+        [
+            RemovedBlock {
+                from: "null_resource.old".to_string(),
+                destroy: true,
+            },
+            RemovedBlock {
+                from: "null_resource.kept".to_string(),
+                destroy: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_removed_blocks_match_records_in_state() {
+    let ws = parse_hcl(REMOVED_CONFIG, Path::new("main.tf")).unwrap();
+    let state = [
+        state_record("null_resource.web"),
+        state_record("null_resource.old[0]"),
+        state_record("null_resource.old[1]"),
+        state_record("null_resource.kept"),
+    ];
+    let removed: Vec<(&str, bool)> = removed_records(&ws.removed, &state)
+        .into_iter()
+        .map(|(r, destroy)| (r.address.as_str(), destroy))
+        .collect();
+    assert_eq!(
+        removed,
+        [
+            ("null_resource.old[0]", true),
+            ("null_resource.old[1]", true),
+            ("null_resource.kept", false),
+        ]
+    );
+    assert!(removed_records(&ws.removed, &state[..1]).is_empty());
+}
+
+#[tokio::test]
+async fn test_forgotten_records_are_dropped_only_when_persisted() {
+    let ws = parse_hcl(REMOVED_CONFIG, Path::new("main.tf")).unwrap();
+    let backend = SqliteBackend::open_memory().unwrap();
+    backend.initialize().await.unwrap();
+    let ws_id = backend.create_workspace("default").await.unwrap();
+    let mut stored = Vec::new();
+    for address in [
+        "null_resource.web",
+        "null_resource.old",
+        "null_resource.kept",
+    ] {
+        let mut record = state_record(address);
+        record.workspace_id = ws_id.clone();
+        backend.upsert_resource(&record).await.unwrap();
+        stored.push(record);
+    }
+
+    let updates = StateUpdates {
+        forgotten: forgotten_addresses(&ws.removed, &stored),
+        ..Default::default()
+    };
+    assert_eq!(updates.forgotten, ["null_resource.kept"]);
+    assert_eq!(backend.count_resources(&ws_id).await.unwrap(), 3);
+
+    updates.persist(&backend, &ws_id).await.unwrap();
+    // The destroying block's resource stays until apply destroys it
+    let mut remaining: Vec<String> = backend
+        .list_resources(&ws_id, &ResourceFilter::default())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.address)
+        .collect();
+    remaining.sort();
+    assert_eq!(remaining, ["null_resource.old", "null_resource.web"]);
+}
+
+// ─── terraform.workspace ─────────────────────────────────────────────────────
+
+#[test]
+fn test_terraform_workspace_resolves_to_the_active_workspace() {
+    let expr = oxid::config::types::Expression::Reference(vec![
+        "terraform".to_string(),
+        "workspace".to_string(),
+    ]);
+    let ctx = EvalContext::plan_only(HashMap::new());
+    assert_eq!(
+        oxid::executor::engine::eval_expression(&expr, &ctx).unwrap(),
+        json!("default")
+    );
+    let ctx = EvalContext::plan_only(HashMap::new()).with_workspace_name(Some("prod"));
+    assert_eq!(
+        oxid::executor::engine::eval_expression(&expr, &ctx).unwrap(),
+        json!("prod")
+    );
+}
+
+#[test]
+fn test_workspace_context_carries_root_dir_and_workspace_name() {
+    let mut workspace = parse_hcl("", Path::new("main.tf")).unwrap();
+    workspace.root_dir = Some(std::path::PathBuf::from("/configs/app"));
+    workspace.workspace_name = Some("prod".to_string());
+    let ctx = EvalContext::for_workspace(HashMap::new(), Default::default(), &workspace);
+    assert_eq!(
+        ctx.config_dir.as_deref(),
+        Some(std::path::Path::new("/configs/app"))
+    );
+    assert_eq!(ctx.workspace_name.as_deref(), Some("prod"));
+}
+
+#[test]
+fn test_count_depends_on_terraform_workspace() {
+    let hcl = r#"
+resource "null_resource" "web" {
+  count = terraform.workspace == "prod" ? 3 : 1
+}
+"#;
+    let mut ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (_, nodes) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    assert_eq!(nodes.len(), 1);
+
+    ws.workspace_name = Some("prod".to_string());
+    let (_, nodes) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let mut addresses: Vec<&str> = nodes.keys().map(String::as_str).collect();
+    addresses.sort();
+    assert_eq!(
+        addresses,
+        [
+            "null_resource.web[0]",
+            "null_resource.web[1]",
+            "null_resource.web[2]"
+        ]
+    );
+}
+
+// ─── Schema fetch limit ──────────────────────────────────────────────────────
+
+async fn peak_schema_fetches(pm: &ProviderManager, fetches: usize) -> usize {
+    let running = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let fetch = || async {
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        running.fetch_sub(1, Ordering::SeqCst);
+    };
+    futures::future::join_all((0..fetches).map(|_| pm.limit_schema_fetch(fetch()))).await;
+    peak.load(Ordering::SeqCst)
+}
+
+#[tokio::test]
+async fn test_schema_fetches_are_capped() {
+    let cache = tempfile::TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf());
+    assert_eq!(peak_schema_fetches(&pm, 6).await, 2);
+
+    let pm = ProviderManager::new(cache.path().to_path_buf()).with_schema_parallelism(3);
+    assert_eq!(peak_schema_fetches(&pm, 6).await, 3);
+
+    // Zero would deadlock; it means one at a time
+    let pm = ProviderManager::new(cache.path().to_path_buf()).with_schema_parallelism(0);
+    assert_eq!(peak_schema_fetches(&pm, 6).await, 1);
+}
+
+// ─── Provider configuration ──────────────────────────────────────────────────
+
+/// A provider schema shaped like the AWS provider's: every attribute optional
+/// so it can come from the environment instead.
+fn env_provider_schema() -> serde_json::Value {
+    json!({
+        "provider": {
+            "version": 0,
+            "block": {
+                "attributes": [
+                    { "name": "region", "type": "string", "optional": true },
+                    { "name": "profile", "type": "string", "optional": true },
+                    { "name": "access_key", "type": "string", "optional": true, "sensitive": true },
+                    { "name": "max_retries", "type": "number", "optional": true }
+                ],
+                "block_types": [
+                    {
+                        "type_name": "assume_role",
+                        "nesting": 2,
+                        "block": {
+                            "attributes": [
+                                { "name": "role_arn", "type": "string", "optional": true },
+                                { "name": "session_name", "type": "string", "optional": true }
+                            ]
+                        }
+                    },
+                    {
+                        "type_name": "default_tags",
+                        "nesting": 1,
+                        "block": {
+                            "attributes": [
+                                { "name": "tags", "type": ["map", "string"], "optional": true }
+                            ]
+                        }
+                    }
+                ]
+            }
+        }
+    })
+}
+
+#[test]
+fn test_empty_provider_block_leaves_everything_unset() {
+    let config = build_full_provider_config(&json!({}), &env_provider_schema());
+    // Null is "not configured": the provider falls back to its environment
+    assert_eq!(
+        config,
+        json!({
+            "region": null,
+            "profile": null,
+            "access_key": null,
+            "max_retries": null,
+            "assume_role": [],
+            "default_tags": null,
+        })
+    );
+}
+
+#[test]
+fn test_provider_config_keeps_user_values_and_populates_blocks() {
+    let user = json!({
+        "region": "eu-west-1",
+        "assume_role": { "role_arn": "arn:aws:iam::123456789012:role/deploy" },
+    });
+    let config = build_full_provider_config(&user, &env_provider_schema());
+    assert_eq!(config["region"], "eu-west-1");
+    assert_eq!(config["profile"], serde_json::Value::Null);
+    assert_eq!(
+        config["assume_role"],
+        json!([{
+            "role_arn": "arn:aws:iam::123456789012:role/deploy",
+            "session_name": null,
+        }])
+    );
+    assert_eq!(config["default_tags"], serde_json::Value::Null);
+}
+
+// ─── Target and exclude ──────────────────────────────────────────────────────
+
+const SELECTION_CONFIG: &str = r#"
+resource "null_resource" "db" {}
+
+resource "null_resource" "app" {
+  triggers = {
+    db = null_resource.db.id
+  }
+}
+
+resource "null_resource" "web" {
+  count = 2
+}
+
+output "db_id" {
+  value = null_resource.db.id
+}
+"#;
+
+fn selected(selection: Selection) -> Vec<String> {
+    let ws = parse_hcl(SELECTION_CONFIG, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let graph = selection.prune(graph);
+    let mut addresses: Vec<String> = graph
+        .node_weights()
+        .map(|n| n.address().to_string())
+        .collect();
+    addresses.sort();
+    addresses
+}
+
+#[test]
+fn test_excluded_resource_is_left_out_while_siblings_stay() {
+    let selection = Selection {
+        excludes: vec!["null_resource.db".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        selected(selection.clone()),
+        [
+            "null_resource.app",
+            "null_resource.web[0]",
+            "null_resource.web[1]"
+        ]
+    );
+    // Deletes of orphaned instances follow the same selection
+    assert!(!selection.includes("null_resource.db"));
+    assert!(selection.includes("null_resource.web[2]"));
+
+    let with_dependents = Selection {
+        exclude_dependents: true,
+        ..selection
+    };
+    assert_eq!(
+        selected(with_dependents),
+        ["null_resource.web[0]", "null_resource.web[1]"]
+    );
+}
+
+#[test]
+fn test_exclude_applies_within_targets() {
+    let targeted = Selection {
+        targets: vec!["null_resource.app".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        selected(targeted.clone()),
+        ["null_resource.app", "null_resource.db"]
+    );
+
+    let selection = Selection {
+        excludes: vec!["null_resource.db".to_string()],
+        ..targeted
+    };
+    assert_eq!(selected(selection.clone()), ["null_resource.app"]);
+    assert!(!selection.includes("null_resource.web[0]"));
+
+    // A single instance can be excluded from a counted resource
+    let instance = Selection {
+        excludes: vec!["null_resource.web[1]".to_string()],
+        ..Default::default()
+    };
+    assert!(selected(instance).contains(&"null_resource.web[0]".to_string()));
+    assert_eq!(selected(Selection::default()).len(), 5);
+}
+
+#[test]
+fn test_module_target_covers_every_resource_under_it() {
+    let selection = Selection {
+        targets: vec!["module.network".to_string()],
+        ..Default::default()
+    };
+    assert!(selection.includes("module.network.aws_vpc.main"));
+    assert!(selection.includes("module.network.module.subnets.aws_subnet.a[0]"));
+    assert!(selection.includes(r#"module.network["east"].aws_vpc.main"#));
+    assert!(!selection.includes("module.network_extra.aws_vpc.main"));
+    assert!(!selection.includes("aws_vpc.network"));
+}
+
+// ─── Replace triggered by ────────────────────────────────────────────────────
+
+const TRIGGER_CONFIG: &str = r#"
+resource "aws_launch_template" "app" {
+  image_id = "ami-2"
+}
+
+resource "aws_instance" "web" {
+  count = 2
+
+  lifecycle {
+    replace_triggered_by = [aws_launch_template.app.image_id]
+  }
+}
+
+resource "aws_instance" "worker" {
+  lifecycle {
+    replace_triggered_by = [aws_launch_template.app.description]
+  }
+}
+"#;
+
+fn change(
+    address: &str,
+    action: ResourceAction,
+    prior: serde_json::Value,
+    planned: serde_json::Value,
+) -> PlannedChange {
+    PlannedChange {
+        address: address.to_string(),
+        action,
+        resource_type: address.split('.').next().unwrap().to_string(),
+        provider_source: "hashicorp/aws".to_string(),
+        planned_state: Some(planned),
+        prior_state: Some(prior),
+        user_config: None,
+        requires_replace: vec![],
+        replace_reason: None,
+        planned_private: vec![],
+    }
+}
+
+#[test]
+fn test_changed_referenced_attribute_triggers_replace() {
+    let ws = parse_hcl(TRIGGER_CONFIG, Path::new("main.tf")).unwrap();
+    let web = ws.resources.iter().find(|r| r.name == "web").unwrap();
+    assert_eq!(
+        web.lifecycle.replace_triggered_by,
+        ["aws_launch_template.app.image_id"]
+    );
+    let (graph, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    // The trigger's source is planned and applied first
+    for instance in ["aws_instance.web[0]", "aws_instance.web[1]"] {
+        assert!(graph
+            .find_edge(node_map["aws_launch_template.app"], node_map[instance])
+            .is_some());
+    }
+
+    let instance = json!({"id": "i-1", "ami": "ami-1"});
+    let mut changes = vec![
+        change(
+            "aws_launch_template.app",
+            ResourceAction::Update,
+            json!({"id": "lt-1", "image_id": "ami-1", "description": "app"}),
+            json!({"id": "lt-1", "image_id": "ami-2", "description": "app"}),
+        ),
+        change(
+            "aws_instance.web[0]",
+            ResourceAction::NoOp,
+            instance.clone(),
+            instance.clone(),
+        ),
+        change(
+            "aws_instance.web[1]",
+            ResourceAction::NoOp,
+            instance.clone(),
+            instance.clone(),
+        ),
+        change(
+            "aws_instance.worker",
+            ResourceAction::NoOp,
+            instance.clone(),
+            instance,
+        ),
+    ];
+
+    let fired = apply_replace_triggers(&graph, &mut changes);
+    let replaced: Vec<&str> = fired.iter().map(|(address, _)| address.as_str()).collect();
+    assert_eq!(replaced, ["aws_instance.web[0]", "aws_instance.web[1]"]);
+    assert_eq!(fired[0].1, "aws_launch_template.app.image_id");
+    assert_eq!(
+        changes[1].replace_reason.as_deref(),
+        Some("replace_triggered_by aws_launch_template.app.image_id")
+    );
+    let actions: Vec<&ResourceAction> = changes.iter().map(|c| &c.action).collect();
+    assert_eq!(
+        actions,
+        [
+            &ResourceAction::Update,
+            &ResourceAction::Replace,
+            &ResourceAction::Replace,
+            // Only the description is referenced, and it is unchanged
+            &ResourceAction::NoOp,
+        ]
+    );
+}
+
+#[test]
+fn test_replace_trigger_on_resource_and_unknown_values() {
+    let hcl = r#"
+resource "aws_launch_template" "app" {
+  count = 2
+}
+
+resource "aws_instance" "web" {
+  count = 2
+
+  lifecycle {
+    replace_triggered_by = [aws_launch_template.app[count.index]]
+  }
+}
+
+resource "aws_instance" "worker" {
+  lifecycle {
+    replace_triggered_by = [aws_launch_template.app[0].latest_version]
+  }
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let template = json!({"id": "lt-1", "latest_version": 1});
+    let instance = json!({"id": "i-1"});
+    let mut changes = vec![
+        change(
+            "aws_launch_template.app[0]",
+            ResourceAction::Update,
+            template.clone(),
+            json!({"id": "lt-1", "latest_version": unknown_value()}),
+        ),
+        change(
+            "aws_launch_template.app[1]",
+            ResourceAction::NoOp,
+            template.clone(),
+            template,
+        ),
+        change(
+            "aws_instance.web[0]",
+            ResourceAction::Update,
+            instance.clone(),
+            json!({"id": "i-1", "tags": {}}),
+        ),
+        change(
+            "aws_instance.web[1]",
+            ResourceAction::NoOp,
+            instance.clone(),
+            instance.clone(),
+        ),
+        change(
+            "aws_instance.worker",
+            ResourceAction::NoOp,
+            instance.clone(),
+            instance,
+        ),
+    ];
+
+    let fired = apply_replace_triggers(&graph, &mut changes);
+    let replaced: Vec<&str> = fired.iter().map(|(address, _)| address.as_str()).collect();
+    // web[1] follows app[1], which is unchanged
+    assert_eq!(replaced, ["aws_instance.web[0]", "aws_instance.worker"]);
+    assert_eq!(changes[2].action, ResourceAction::Replace);
+    assert_eq!(changes[3].action, ResourceAction::NoOp);
+}
+
+// ─── Forced replacement ──────────────────────────────────────────────────────
+
+#[test]
+fn test_replace_flag_plans_named_resources_as_replacements() {
+    let state = serde_json::json!({"id": "1"});
+    let mut changes = vec![
+        change(
+            "aws_instance.web[0]",
+            ResourceAction::NoOp,
+            state.clone(),
+            state.clone(),
+        ),
+        change(
+            "aws_instance.web[1]",
+            ResourceAction::Update,
+            state.clone(),
+            serde_json::json!({"id": "2"}),
+        ),
+        change(
+            "aws_instance.db",
+            ResourceAction::NoOp,
+            state.clone(),
+            state.clone(),
+        ),
+        change("aws_vpc.main", ResourceAction::NoOp, state.clone(), state),
+    ];
+    let replace = vec!["aws_instance.web".to_string(), "aws_vpc.main".to_string()];
+
+    let replaced = apply_forced_replacements(&replace, &mut changes);
+    assert_eq!(
+        replaced,
+        ["aws_instance.web[0]", "aws_instance.web[1]", "aws_vpc.main"]
+    );
+    let actions: Vec<String> = changes.iter().map(|c| c.action.to_string()).collect();
+    assert_eq!(actions, ["-/+", "-/+", "(no changes)", "-/+"]);
+    assert_eq!(
+        changes[0].replace_reason.as_deref(),
+        Some("requested with --replace")
+    );
+    assert_eq!(changes[2].replace_reason, None);
+
+    let plan = PlanSummary::from_changes(changes, vec![]);
+    assert_eq!((plan.replaces, plan.updates, plan.no_ops), (3, 0, 1));
+}
+
+#[test]
+fn test_replace_flag_requires_address_in_state() {
+    let existing = vec![
+        ResourceState::new("ws", "aws_instance", "web", "aws_instance.web[0]"),
+        ResourceState::new("ws", "aws_vpc", "main", "aws_vpc.main"),
+    ];
+    let names = |addresses: &[&str]| addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+    let all = |_: &str| true;
+    check_replace_addresses(
+        &names(&["aws_instance.web", "aws_vpc.main"]),
+        &existing,
+        all,
+    )
+    .unwrap();
+    check_replace_addresses(&names(&["aws_instance.web[0]"]), &existing, all).unwrap();
+    let err =
+        check_replace_addresses(&names(&["aws_instance.web[1]"]), &existing, all).unwrap_err();
+    assert!(err.to_string().contains("aws_instance.web[1]"), "{}", err);
+    assert!(check_replace_addresses(&names(&["aws_db_instance.main"]), &existing, all).is_err());
+}
+
+#[test]
+fn test_replace_flag_rejects_addresses_outside_the_selection() {
+    let existing = vec![
+        ResourceState::new("ws", "aws_instance", "web", "aws_instance.web"),
+        ResourceState::new("ws", "aws_vpc", "main", "aws_vpc.main"),
+    ];
+    let only_vpc = |address: &str| address == "aws_vpc.main";
+
+    check_replace_addresses(&["aws_vpc.main".to_string()], &existing, only_vpc).unwrap();
+    let err = check_replace_addresses(&["aws_instance.web".to_string()], &existing, only_vpc)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot replace aws_instance.web: it is excluded by --target/--exclude"
+    );
+}
+
+// ─── Resource status ─────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_failed_apply_leaves_resource_failed() {
+    let ws = parse_hcl(
+        r#"
+resource "null_resource" "web" {}
+resource "null_resource" "db" {}
+resource "null_resource" "cache" {}
+"#,
+        Path::new("main.tf"),
+    )
+    .unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    let backend: Arc<dyn StateBackend> = Arc::new(SqliteBackend::open_memory().unwrap());
+    backend.initialize().await.unwrap();
+    let ws_id = backend.create_workspace("default").await.unwrap();
+    for (address, status) in [
+        ("null_resource.web", status::CREATED),
+        ("null_resource.db", status::CREATED),
+        ("null_resource.cache", status::CREATED),
+    ] {
+        let mut record = state_record(address);
+        record.workspace_id = ws_id.clone();
+        record.status = status.to_string();
+        backend.upsert_resource(&record).await.unwrap();
+    }
+
+    // web fails its update, db succeeds, and cache fails after a
+    // provisioner tainted it; each is seen mid-update
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let executor: NodeExecutor = {
+        let backend = Arc::clone(&backend);
+        let ws_id = ws_id.clone();
+        let seen = Arc::clone(&seen);
+        Box::new(move |_idx, node: DagNode| {
+            let backend = Arc::clone(&backend);
+            let ws_id = ws_id.clone();
+            let seen = Arc::clone(&seen);
+            let address = node.address().to_string();
+            Box::pin(async move {
+                let record = backend.get_resource(&ws_id, &address).await?.unwrap();
+                seen.lock()
+                    .unwrap()
+                    .push((address.clone(), record.status.clone()));
+                match address.as_str() {
+                    "null_resource.db" => return Ok(None),
+                    "null_resource.cache" => {
+                        let mut tainted = record.clone();
+                        tainted.status = status::TAINTED.to_string();
+                        backend.upsert_resource(&tainted).await?;
+                    }
+                    _ => {}
+                }
+                anyhow::bail!("provider said no")
+            })
+        })
+    };
+    let record_action: RecordAction =
+        Arc::new(|_: &DagNode| Some((action::UPDATE.to_string(), None)));
+    let executor = track_resource_status(executor, Arc::clone(&backend), &ws_id, record_action);
+    DagWalker::new(4)
+        .walk(&graph, Arc::new(executor), WalkMode::Apply)
+        .await
+        .unwrap();
+
+    let mut seen = seen.lock().unwrap().clone();
+    seen.sort();
+    assert!(
+        seen.iter().all(|(_, status)| status == "updating"),
+        "{:?}",
+        seen
+    );
+    let status_of = |address: &'static str| {
+        let backend = Arc::clone(&backend);
+        let ws_id = ws_id.clone();
+        async move {
+            backend
+                .get_resource(&ws_id, address)
+                .await
+                .unwrap()
+                .unwrap()
+                .status
+        }
+    };
+    assert_eq!(status_of("null_resource.web").await, status::FAILED);
+    // A successful executor writes the final status itself
+    assert_eq!(status_of("null_resource.db").await, status::UPDATING);
+    assert_eq!(status_of("null_resource.cache").await, status::TAINTED);
+
+    let failed = backend
+        .list_resources(&ws_id, &ResourceFilter::parse("status=failed"))
+        .await
+        .unwrap();
+    assert_eq!(addresses(&failed), ["null_resource.web"]);
+}
+
+// ─── Refresh ─────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_refresh_updates_are_written_only_when_persisted() {
+    let backend = SqliteBackend::open_memory().unwrap();
+    backend.initialize().await.unwrap();
+    let ws_id = backend.create_workspace("default").await.unwrap();
+    let mut stored = Vec::new();
+    for name in ["drifted", "gone", "same"] {
+        let mut record = state_record(&format!("null_resource.{}", name));
+        record.workspace_id = ws_id.clone();
+        record.provider_source = "hashicorp/null".to_string();
+        record.attributes_json = r#"{"id":"a"}"#.to_string();
+        backend.upsert_resource(&record).await.unwrap();
+        stored.push(record);
+    }
+
+    let refreshed = refreshed_records(&stored, 4, true, |r| async move {
+        match r.address.as_str() {
+            "null_resource.drifted" => Ok(Some((json!({"id": "b"}), vec![]))),
+            "null_resource.gone" => Ok(None),
+            _ => Ok(Some((json!({"id": "a"}), vec![]))),
+        }
+    })
+    .await;
+    let updates = StateUpdates::from_refresh(&stored, &refreshed);
+    assert_eq!(addresses(&updates.refreshed), ["null_resource.drifted"]);
+    assert_eq!(updates.gone, ["null_resource.gone"]);
+    // Computing the updates writes nothing
+    assert_eq!(backend.count_resources(&ws_id).await.unwrap(), 3);
+
+    updates.persist(&backend, &ws_id).await.unwrap();
+    let records = backend
+        .list_resources(&ws_id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        addresses(&records),
+        ["null_resource.drifted", "null_resource.same"]
+    );
+    assert_eq!(records[0].attributes_json, r#"{"id":"b"}"#);
+}
+
+// ─── Refresh-only plan ───────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_refresh_only_plan_reports_drift_without_planning_config() {
+    let stored: Vec<ResourceState> = ["drifted", "gone", "same"]
+        .iter()
+        .map(|name| {
+            let mut r = ResourceState::new(
+                "ws",
+                "null_resource",
+                name,
+                &format!("null_resource.{}", name),
+            );
+            r.provider_source = "hashicorp/null".to_string();
+            r.attributes_json = r#"{"id":"a"}"#.to_string();
+            r
+        })
+        .collect();
+
+    let refreshed = refreshed_records(&stored, 4, true, |r| async move {
+        match r.address.as_str() {
+            "null_resource.drifted" => Ok(Some((json!({"id": "b"}), vec![]))),
+            "null_resource.gone" => Ok(None),
+            _ => Ok(Some((json!({"id": "a"}), vec![]))),
+        }
+    })
+    .await;
+    let plan = PlanSummary::from_changes(refresh_only_changes(&stored, &refreshed), vec![]);
+
+    assert_eq!((plan.updates, plan.deletes, plan.creates), (1, 1, 0));
+    let drifted = plan
+        .changes
+        .iter()
+        .find(|c| c.address == "null_resource.drifted")
+        .unwrap();
+    assert_eq!(drifted.action, ResourceAction::Update);
+    assert_eq!(drifted.planned_state, Some(json!({"id": "b"})));
+    let gone = plan
+        .changes
+        .iter()
+        .find(|c| c.address == "null_resource.gone")
+        .unwrap();
+    assert_eq!(gone.action, ResourceAction::Delete);
+}
+
+// ─── Unknown values in plan ──────────────────────────────────────────────────
+
+const UNKNOWN_CHAIN: &str = r#"
+resource "aws_vpc" "main" {
+  cidr_block = "10.0.0.0/16"
+}
+
+resource "aws_subnet" "a" {
+  vpc_id     = aws_vpc.main.id
+  cidr_block = "10.0.1.0/24"
+  vpc_name   = upper(aws_vpc.main.id)
+  tags = {
+    Name = "subnet-${aws_vpc.main.id}"
+    Same = aws_vpc.main.cidr_block
+  }
+}
+"#;
+
+#[test]
+fn test_unknown_attribute_propagates_to_dependent_plan() {
+    let ws = parse_hcl(UNKNOWN_CHAIN, Path::new("main.tf")).unwrap();
+    // The VPC plans as a create, its id known only after apply
+    let states = Arc::new(DashMap::new());
+    states.insert(
+        "aws_vpc.main".to_string(),
+        json!({ "id": unknown_value(), "cidr_block": "10.0.0.0/16" }),
+    );
+    let ctx = EvalContext::with_states(build_variable_defaults(&ws), states);
+    let subnet = ws.resources.iter().find(|r| r.name == "a").unwrap();
+    let config = attributes_to_json(&subnet.attributes, &ctx).unwrap();
+    assert_eq!(config["vpc_id"], unknown_value());
+    assert_eq!(config["tags"]["Name"], unknown_value());
+    assert_eq!(config["vpc_name"], unknown_value());
+    assert_eq!(config["tags"]["Same"], "10.0.0.0/16");
+
+    // The provider saw null for the unknowns; the plan shows them unknown
+    let prior = json!({
+        "id": "subnet-1",
+        "vpc_id": "vpc-old",
+        "cidr_block": "10.0.1.0/24",
+        "vpc_name": "VPC-OLD",
+        "tags": {"Name": "subnet-vpc-old", "Same": "10.0.0.0/16"},
+    });
+    let mut planned = json!({
+        "id": "subnet-1",
+        "vpc_id": null,
+        "cidr_block": "10.0.1.0/24",
+        "vpc_name": null,
+        "tags": {"Name": null, "Same": "10.0.0.0/16"},
+    });
+    assert!(propagate_config_unknowns(&config, &mut planned));
+    assert_eq!(planned["vpc_id"], unknown_value());
+    assert_eq!(planned["tags"]["Name"], unknown_value());
+    assert_eq!(planned["id"], "subnet-1");
+    assert_eq!(planned["tags"]["Same"], "10.0.0.0/16");
+    // Unknowns alone never look like a change, which is why plan promotes
+    // an unchanged-looking resource with unknown config to an update
+    assert_eq!(
+        determine_action(Some(&prior), Some(&planned), &[]),
+        ResourceAction::NoOp
+    );
+
+    let mut known = prior.clone();
+    assert!(!propagate_config_unknowns(&prior, &mut known));
+    assert_eq!(known, prior);
+}
+
+#[test]
+fn test_unknown_nested_in_function_arguments_is_unknown() {
+    let hcl = r#"
+resource "aws_iam_policy" "p" {
+  policy  = jsonencode({ Resource = aws_vpc.main.arn })
+  subnets = join(",", [aws_vpc.main.id, "subnet-1"])
+  merged  = concat(["a"], [aws_vpc.main.id])
+  count_of = length([aws_vpc.main.id, "b"])
+  literal = jsonencode({ Resource = aws_vpc.main.cidr_block })
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let states = Arc::new(DashMap::new());
+    states.insert(
+        "aws_vpc.main".to_string(),
+        json!({ "id": unknown_value(), "arn": unknown_value(), "cidr_block": "10.0.0.0/16" }),
+    );
+    let ctx = EvalContext::with_states(HashMap::new(), states);
+    let config = attributes_to_json(&ws.resources[0].attributes, &ctx).unwrap();
+    assert_eq!(config["policy"], unknown_value());
+    assert_eq!(config["subnets"], unknown_value());
+    assert_eq!(config["merged"], unknown_value());
+    // The length of a list doesn't depend on its elements
+    assert_eq!(config["count_of"], 2);
+    assert_eq!(config["literal"], r#"{"Resource":"10.0.0.0/16"}"#);
+}
@@ -0,0 +1,261 @@
+use std::path::Path;
+
+use oxid::config::parser::parse_config;
+use oxid::dag::builder::build_dag;
+use oxid::dag::resource_graph::build_resource_dag;
+use oxid::dag::validation::{check_count_references, check_variables};
+use oxid::error::OxidError;
+use oxid::executor::engine::{build_provider_map, build_variable_defaults};
+use oxid::hcl::json_parser::parse_tf_json;
+use oxid::hcl::parser::parse_hcl;
+use oxid::provider::manager::ProviderManager;
+use oxid::provider::mirror::ProviderInstallation;
+use oxid::state::backend::StateBackend;
+use oxid::state::lock::{acquire_workspace_lock, FileLock};
+use oxid::state::sqlite::SqliteBackend;
+use tempfile::TempDir;
+
+// ─── Config parsing ──────────────────────────────────────────────────────────
+
+#[test]
+fn test_hcl_syntax_error_is_config_parse() {
+    let err = parse_hcl("resource \"a\" \"b\" {\n  x = \n}\n", Path::new("bad.tf")).unwrap_err();
+    match OxidError::find(&err) {
+        Some(OxidError::ConfigParse {
+            language, location, ..
+        }) => {
+            assert_eq!(*language, "HCL");
+            assert_eq!(location, "bad.tf:2:7");
+        }
+        other => panic!("expected ConfigParse, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_json_syntax_error_is_config_parse() {
+    let err = parse_tf_json("{\n  \"resource\": \n", Path::new("bad.tf.json")).unwrap_err();
+    match OxidError::find(&err) {
+        Some(OxidError::ConfigParse {
+            language, location, ..
+        }) => {
+            assert_eq!(*language, "JSON");
+            assert!(location.starts_with("bad.tf.json:"), "{}", location);
+        }
+        other => panic!("expected ConfigParse, got {:?}", other),
+    }
+}
+
+// ─── Cycles ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_resource_cycle_lists_every_member() {
+    let hcl = r#"
+resource "aws_vpc" "main" {
+  tags = { peer = aws_subnet.a.id }
+}
+
+resource "aws_subnet" "a" {
+  vpc_id = aws_vpc.main.id
+}
+
+resource "aws_instance" "web" {
+  subnet_id = aws_subnet.a.id
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let err = build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws))
+        .unwrap_err();
+    match OxidError::find(&err) {
+        // The instance depends on the cycle but isn't part of it
+        Some(OxidError::CycleDetected(members)) => {
+            assert_eq!(members, &["aws_subnet.a", "aws_vpc.main"])
+        }
+        other => panic!("expected CycleDetected, got {:?}", other),
+    }
+    assert!(err.to_string().contains("Circular dependency"), "{}", err);
+}
+
+#[test]
+fn test_module_cycle_is_cycle_detected() {
+    let yaml = r#"
+project:
+  name: "cycle"
+  version: "1.0"
+  modules:
+    a:
+      source: "./a"
+      depends_on:
+        - b
+    b:
+      source: "./b"
+      depends_on:
+        - a
+    c:
+      source: "./c"
+"#;
+    let err = build_dag(&parse_config(yaml).unwrap()).unwrap_err();
+    assert!(
+        matches!(OxidError::find(&err), Some(OxidError::CycleDetected(m)) if m == &["a", "b"]),
+        "{:?}",
+        err
+    );
+}
+
+// ─── Validation ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_bare_reference_to_counted_resource_fails_validation() {
+    let hcl = r#"
+resource "aws_instance" "web" {
+  count = 2
+}
+
+output "ip" {
+  value = aws_instance.web.private_ip
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let err = check_count_references(&ws).unwrap_err();
+    match OxidError::find(&err) {
+        Some(OxidError::ValidationFailed(messages)) => {
+            assert_eq!(messages.len(), 1, "{:?}", messages);
+            assert!(messages[0].contains("aws_instance.web[*].private_ip"));
+        }
+        other => panic!("expected ValidationFailed, got {:?}", other),
+    }
+    assert_eq!(err.to_string(), "Validation failed.");
+}
+
+#[test]
+fn test_failing_variable_validation_is_validation_failed() {
+    let hcl = r#"
+variable "env" {
+  default = "qa"
+  validation {
+    condition     = can(regex("^(dev|prod)$", var.env))
+    error_message = "env must be dev or prod."
+  }
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let err = check_variables(&ws, &build_variable_defaults(&ws)).unwrap_err();
+    assert!(
+        matches!(
+            OxidError::find(&err),
+            Some(OxidError::ValidationFailed(m)) if m == &["var.env: env must be dev or prod."]
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_failing_function_in_count_fails_the_graph() {
+    let hcl = r#"
+resource "aws_subnet" "a" {
+  count      = length(slice(["a", "b"], 0, 5))
+  cidr_block = "10.0.0.0/24"
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let err = build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws))
+        .unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(
+        message.starts_with("Invalid count for aws_subnet.a"),
+        "{}",
+        message
+    );
+    assert!(message.contains("slice()"), "{}", message);
+}
+
+// ─── Providers ───────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_provider_exiting_before_handshake_is_provider_start() {
+    let dir = TempDir::new().unwrap();
+    let binary = dir.path().join("terraform-provider-foo");
+    std::fs::write(&binary, "#!/bin/sh\nexit 1\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let mut installation = ProviderInstallation::default();
+    installation.add_dev_override("hashicorp/foo", binary);
+    let pm = ProviderManager::new(dir.path().join("cache")).with_installation(installation);
+    let err = pm.get_connection("hashicorp/foo", "").await.unwrap_err();
+    match OxidError::find(&err) {
+        Some(OxidError::ProviderStart { provider, source }) => {
+            assert!(provider.contains("hashicorp/foo"), "{}", provider);
+            assert!(
+                source.to_string().contains("exited before handshake"),
+                "{}",
+                source
+            );
+        }
+        other => panic!("expected ProviderStart, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_provider_diagnostics_keep_their_messages() {
+    let err = OxidError::ProviderDiagnostics(vec![
+        "Invalid region: us-nowhere-1".to_string(),
+        "Missing credentials".to_string(),
+    ]);
+    assert_eq!(
+        err.to_string(),
+        "Provider errors:\nInvalid region: us-nowhere-1\nMissing credentials"
+    );
+    // Still recoverable once wrapped with context
+    let wrapped = anyhow::Error::from(err).context("Failed to plan aws_instance.web");
+    assert!(matches!(
+        OxidError::find(&wrapped),
+        Some(OxidError::ProviderDiagnostics(d)) if d.len() == 2
+    ));
+}
+
+// ─── Locking ─────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_held_workspace_lock_is_state_locked() {
+    let dir = TempDir::new().unwrap();
+    let backend = SqliteBackend::open(dir.path().join("oxid.db").to_str().unwrap()).unwrap();
+    backend.initialize().await.unwrap();
+    let ws_id = backend.create_workspace("default").await.unwrap();
+
+    let held = acquire_workspace_lock(&backend, &ws_id, "apply")
+        .await
+        .unwrap();
+    let err = acquire_workspace_lock(&backend, &ws_id, "destroy")
+        .await
+        .unwrap_err();
+    match OxidError::find(&err) {
+        Some(OxidError::StateLocked(lock)) => {
+            assert_eq!(lock.lock_id, held.lock_id);
+            assert_eq!(lock.operation, "apply");
+        }
+        other => panic!("expected StateLocked, got {:?}", other),
+    }
+    assert!(err
+        .to_string()
+        .contains(&format!("oxid force-unlock {}", held.lock_id)));
+}
+
+#[test]
+fn test_held_module_file_lock_is_state_locked() {
+    let dir = TempDir::new().unwrap();
+    let work = dir.path().to_str().unwrap();
+    let _held = FileLock::acquire(work, "network").unwrap();
+
+    let err = FileLock::acquire(work, "network").err().unwrap();
+    match OxidError::find(&err) {
+        Some(OxidError::StateLocked(lock)) => {
+            assert_eq!(lock.resource_address, "network");
+            assert_eq!(lock.locked_by, format!("pid {}", std::process::id()));
+        }
+        other => panic!("expected StateLocked, got {:?}", other),
+    }
+}
@@ -0,0 +1,18 @@
+terraform {
+  required_providers {
+    null = {
+      source  = "hashicorp/null"
+      version = ">= 3.0"
+    }
+  }
+}
+
+variable "prefix" {
+  default = "dev"
+}
+
+resource "null_resource" "main" {
+  triggers = {
+    name = "${var.prefix}-main"
+  }
+}
@@ -0,0 +1,3 @@
+output "app_id" {
+  value = null_resource.app.id
+}
@@ -0,0 +1,4 @@
+module "net" {
+  source = "./modules/net"
+  count  = 2
+}
@@ -0,0 +1,16 @@
+module "pet" {
+  source = "../local/modules/pet"
+  prefix = "dev"
+}
+
+data "external" "ready" {
+  program = ["echo", "{}"]
+}
+
+resource "null_resource" "after_pet" {
+  depends_on = [module.pet]
+}
+
+resource "null_resource" "after_data" {
+  depends_on = [data.external.ready]
+}
@@ -0,0 +1,33 @@
+terraform {
+  required_providers {
+    random = {
+      source = "hashicorp/random"
+    }
+  }
+}
+
+variable "prefix" {
+  type    = string
+  default = "dev"
+}
+
+module "label" {
+  source = "./modules/label"
+  prefix = var.prefix
+}
+
+module "pet" {
+  source = "./modules/pet"
+  prefix = module.label.name
+  length = 3
+}
+
+resource "null_resource" "consumer" {
+  triggers = {
+    pet = module.pet.id
+  }
+}
+
+output "pet_id" {
+  value = module.pet.id
+}
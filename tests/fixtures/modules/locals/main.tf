@@ -0,0 +1,12 @@
+locals {
+  name = "root"
+}
+
+module "net" {
+  source = "./modules/net"
+  env    = "prod"
+}
+
+output "subnet_name" {
+  value = module.net.name
+}
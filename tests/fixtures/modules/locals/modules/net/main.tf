@@ -0,0 +1,22 @@
+variable "env" {
+  type = string
+}
+
+locals {
+  name = "${var.env}-net"
+  tags = { Name = local.name }
+  vpc  = aws_vpc.main.id
+}
+
+resource "aws_vpc" "main" {
+  cidr_block = "10.0.0.0/16"
+  tags       = local.tags
+}
+
+resource "aws_subnet" "a" {
+  vpc_id = local.vpc
+}
+
+output "name" {
+  value = local.name
+}
@@ -0,0 +1,3 @@
+module "pet" {
+  source = "./modules/pet"
+}
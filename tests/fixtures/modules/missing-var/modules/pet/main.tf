@@ -0,0 +1,23 @@
+variable "prefix" {
+  type = string
+}
+
+variable "length" {
+  type    = number
+  default = 2
+}
+
+resource "random_pet" "this" {
+  prefix = var.prefix
+  length = var.length
+}
+
+resource "null_resource" "after" {
+  triggers = {
+    pet = random_pet.this.id
+  }
+}
+
+output "id" {
+  value = random_pet.this.id
+}
@@ -0,0 +1,18 @@
+output "script" {
+  value = file("scripts/setup.sh")
+}
+
+output "cloud_init" {
+  value = templatefile("cloud-init.tftpl", {
+    hostname = "web-01"
+    config   = { port = 8080 }
+  })
+}
+
+output "missing" {
+  value = file("does-not-exist.sh")
+}
+
+output "escape" {
+  value = file("../../engine_test.rs")
+}
@@ -0,0 +1,21 @@
+variable "region" {
+  default = "us-east-1"
+}
+
+variable "size" {
+  default = "small"
+}
+
+variable "zone" {
+  default = "a"
+}
+
+variable "instance_count" {
+  type    = number
+  default = 1
+}
+
+variable "tags" {
+  type    = map(string)
+  default = {}
+}
@@ -0,0 +1,21 @@
+variable "region" {
+  type = string
+}
+
+variable "env" {
+  default = "qa"
+
+  validation {
+    condition     = contains(["dev", "prod"], var.env)
+    error_message = "env must be dev or prod."
+  }
+}
+
+resource "aws_instance" "web" {
+  count = 2
+}
+
+resource "aws_eip" "ip" {
+  instance   = aws_instance.web.id
+  depends_on = [aws_vpc.missing]
+}
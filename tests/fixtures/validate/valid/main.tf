@@ -0,0 +1,18 @@
+variable "env" {
+  default = "dev"
+
+  validation {
+    condition     = contains(["dev", "prod"], var.env)
+    error_message = "env must be dev or prod."
+  }
+}
+
+resource "aws_instance" "web" {
+  count = 2
+  tags  = { env = var.env }
+}
+
+resource "aws_eip" "ip" {
+  instance   = aws_instance.web[0].id
+  depends_on = [aws_instance.web]
+}
@@ -0,0 +1,531 @@
+use oxid::dag::walker::{NodeResult, NodeStatus};
+use oxid::executor::engine::{PlanSummary, PlannedChange, ResourceAction};
+use oxid::output::formatter::{
+    color_disabled, format_apply_timings, format_compact_plan, format_refresh_only_plan,
+    format_resource_change, format_run_detail, format_run_list, format_state, format_state_updates,
+    init_color, resource_attributes_raw, resource_detail_json, resource_list_delimited, state_json,
+};
+use oxid::state::models::{OutputValue, ResourceResult, ResourceState, RunRecord};
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+fn run(id: &str, operation: &str, status: &str, failed: i32) -> RunRecord {
+    RunRecord {
+        id: id.to_string(),
+        workspace_id: "ws".to_string(),
+        started_at: "2026-10-01T12:00:00+00:00".to_string(),
+        completed_at: Some("2026-10-01T12:01:30+00:00".to_string()),
+        status: status.to_string(),
+        operation: operation.to_string(),
+        resources_planned: 3,
+        resources_succeeded: 3 - failed,
+        resources_failed: failed,
+        error_message: None,
+    }
+}
+
+// ─── Runs ────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_format_run_list() {
+    colored::control::set_override(false);
+    let runs = vec![
+        run("0f8e7d6c-aaaa-bbbb", "destroy", "succeeded", 0),
+        run("1a2b3c4d-cccc-dddd", "apply", "failed", 1),
+    ];
+    let table = format_run_list(&runs);
+    let lines: Vec<&str> = table.lines().collect();
+
+    assert!(table.contains("OPERATION"));
+    let apply = lines.iter().find(|l| l.contains("1a2b3c4d")).unwrap();
+    assert!(apply.contains("apply"));
+    assert!(apply.contains("failed"));
+    assert!(apply.contains("2026-10-01T12:00:00+00:00"));
+    assert!(apply.contains("2026-10-01T12:01:30+00:00"));
+    assert!(apply.ends_with("3/2/1"));
+    // Ids are shortened
+    assert!(!table.contains("1a2b3c4d-cccc"));
+
+    assert!(format_run_list(&[]).contains("No runs recorded."));
+}
+
+#[test]
+fn test_format_run_detail_lists_resources() {
+    colored::control::set_override(false);
+    let mut latest = run("1a2b3c4d-cccc-dddd", "apply", "failed", 1);
+    latest.completed_at = None;
+    let results = vec![
+        ResourceResult {
+            address: "aws_vpc.main".to_string(),
+            action: "create".to_string(),
+            status: "succeeded".to_string(),
+            started_at: None,
+            completed_at: None,
+            error_message: None,
+            diff_json: None,
+            output: Some("vpc ready\n".to_string()),
+        },
+        ResourceResult {
+            address: "aws_subnet.a".to_string(),
+            action: "create".to_string(),
+            status: "failed".to_string(),
+            started_at: None,
+            completed_at: None,
+            error_message: Some("InvalidSubnet.Range".to_string()),
+            diff_json: None,
+            output: None,
+        },
+    ];
+    let detail = format_run_detail(&latest, &results);
+
+    assert!(detail.contains("1a2b3c4d-cccc-dddd"));
+    assert!(detail.contains("3 planned, 2 succeeded, 1 failed"));
+    assert!(detail.contains("Completed:   -"));
+    assert!(detail.contains("\n      vpc ready\n"));
+    let subnet = detail.lines().find(|l| l.contains("aws_subnet.a")).unwrap();
+    assert!(subnet.contains("failed"));
+    assert!(subnet.contains("InvalidSubnet.Range"));
+    assert!(detail.contains("aws_vpc.main"));
+}
+
+// ─── Show ────────────────────────────────────────────────────────────────────
+
+fn resource(address: &str, resource_type: &str, name: &str, attributes: &str) -> ResourceState {
+    let mut state = ResourceState::new("ws", resource_type, name, address);
+    state.provider_source = "hashicorp/aws".to_string();
+    state.status = "created".to_string();
+    state.attributes_json = attributes.to_string();
+    state
+}
+
+fn output(name: &str, value: &str, sensitive: bool) -> OutputValue {
+    OutputValue {
+        id: name.to_string(),
+        workspace_id: "ws".to_string(),
+        module_path: String::new(),
+        output_name: name.to_string(),
+        output_value: value.to_string(),
+        sensitive,
+    }
+}
+
+fn show_state() -> (Vec<ResourceState>, Vec<OutputValue>) {
+    let mut db = resource(
+        "aws_db_instance.main",
+        "aws_db_instance",
+        "main",
+        r#"{"id":"db-1","password":"hunter2"}"#,
+    );
+    db.sensitive_attrs = vec!["password".to_string()];
+    db.schema_version = 2;
+    db.private_data = br#"{"schema_version":"2"}"#.to_vec();
+
+    let mut web0 = resource(
+        "aws_instance.web[0]",
+        "aws_instance",
+        "web",
+        r#"{"id":"i-0"}"#,
+    );
+    web0.index_key = Some("0".to_string());
+    web0.schema_version = 1;
+    // The prior object of web[0], kept while its replacement was created
+    let mut web0_deposed = resource(
+        "aws_instance.web[0] (deposed)",
+        "aws_instance",
+        "web",
+        r#"{"id":"i-old"}"#,
+    );
+    web0_deposed.id = "web0-deposed".to_string();
+    web0_deposed.index_key = Some("0".to_string());
+    web0_deposed.schema_version = 1;
+    web0_deposed.status = "deposed".to_string();
+    let mut web1 = resource(
+        "aws_instance.web[1]",
+        "aws_instance",
+        "web",
+        r#"{"id":"i-1"}"#,
+    );
+    web1.index_key = Some("1".to_string());
+    web1.schema_version = 1;
+    web1.status = "tainted".to_string();
+
+    let mut vpc = resource(
+        "module.network.data.aws_vpc.default",
+        "aws_vpc",
+        "default",
+        r#"{"id":"vpc-1"}"#,
+    );
+    vpc.resource_mode = "data".to_string();
+    vpc.module_path = "module.network".to_string();
+
+    let outputs = vec![
+        output("db_password", r#""hunter2""#, true),
+        output("web_ids", r#"["i-0","i-1"]"#, false),
+    ];
+    (vec![db, web0, web0_deposed, web1, vpc], outputs)
+}
+
+#[test]
+fn test_state_json_matches_fixture() {
+    let (resources, outputs) = show_state();
+    let mut actual = state_json("ws", 7, &resources, &outputs);
+    assert_eq!(actual["terraform_version"], env!("CARGO_PKG_VERSION"));
+    actual.as_object_mut().unwrap().remove("terraform_version");
+
+    let fixture: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string("tests/fixtures/show/state.json").unwrap())
+            .unwrap();
+    assert_eq!(actual, fixture);
+}
+
+#[test]
+fn test_format_state_masks_sensitive_values() {
+    colored::control::set_override(false);
+    let (resources, outputs) = show_state();
+    let text = format_state(&resources, &outputs);
+    assert!(text.contains("# aws_db_instance.main:"), "{}", text);
+    assert!(
+        text.contains("resource \"aws_db_instance\" \"main\" {"),
+        "{}",
+        text
+    );
+    assert!(text.contains("password = (sensitive value)"), "{}", text);
+    assert!(text.contains("data \"aws_vpc\" \"default\" {"), "{}", text);
+    assert!(text.contains("db_password = (sensitive value)"), "{}", text);
+    assert!(!text.contains("hunter2"), "{}", text);
+    assert!(text.contains("\"i-1\""), "{}", text);
+
+    assert!(format_state(&[], &[]).contains("The state is empty."));
+}
+
+// ─── State list ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_resource_list_csv_quotes_fields() {
+    let mut tagged = resource(
+        r#"aws_instance.web["us-east-1a,b"]"#,
+        "aws_instance",
+        "web",
+        "{}",
+    );
+    tagged.updated_at = "2026-10-01T12:00:00+00:00".to_string();
+    let mut vpc = resource("aws_vpc.main", "aws_vpc", "main", "{}");
+    vpc.updated_at = "2026-10-02T08:30:00+00:00".to_string();
+
+    assert_eq!(
+        resource_list_delimited(&[tagged.clone(), vpc.clone()], ','),
+        "address,resource_type,provider_source,status,updated_at\n\
+         \"aws_instance.web[\"\"us-east-1a,b\"\"]\",aws_instance,hashicorp/aws,created,2026-10-01T12:00:00+00:00\n\
+         aws_vpc.main,aws_vpc,hashicorp/aws,created,2026-10-02T08:30:00+00:00\n"
+    );
+
+    // Tabs delimit TSV; the comma no longer needs quoting, the quotes still do
+    let tsv = resource_list_delimited(&[tagged], '\t');
+    assert_eq!(
+        tsv.lines().nth(1).unwrap(),
+        "\"aws_instance.web[\"\"us-east-1a,b\"\"]\"\taws_instance\thashicorp/aws\tcreated\t2026-10-01T12:00:00+00:00"
+    );
+}
+
+// ─── State show ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_resource_detail_json_has_metadata_and_masks_sensitive() {
+    let (resources, _) = show_state();
+    let db = &resources[0];
+
+    let detail = resource_detail_json(db, false);
+    for key in [
+        "address",
+        "type",
+        "status",
+        "provider_source",
+        "schema_version",
+        "created_at",
+        "updated_at",
+        "attributes",
+    ] {
+        assert!(detail.get(key).is_some(), "missing {}: {}", key, detail);
+    }
+    assert_eq!(detail["address"], "aws_db_instance.main");
+    assert_eq!(detail["type"], "aws_db_instance");
+    assert_eq!(detail["provider_source"], "hashicorp/aws");
+    assert_eq!(detail["schema_version"], 2);
+    assert_eq!(detail["attributes"]["id"], "db-1");
+    assert_eq!(detail["attributes"]["password"], "(sensitive value)");
+
+    let revealed = resource_detail_json(db, true);
+    assert_eq!(revealed["attributes"]["password"], "hunter2");
+}
+
+#[test]
+fn test_resource_attributes_raw() {
+    let (resources, _) = show_state();
+    let db = &resources[0];
+    assert_eq!(
+        resource_attributes_raw(db, true),
+        r#"{"id":"db-1","password":"hunter2"}"#
+    );
+    let masked: serde_json::Value =
+        serde_json::from_str(&resource_attributes_raw(db, false)).unwrap();
+    assert_eq!(
+        masked,
+        serde_json::json!({ "id": "db-1", "password": "(sensitive value)" })
+    );
+
+    // Nothing to mask: stored bytes pass through untouched
+    let web = &resources[1];
+    assert_eq!(resource_attributes_raw(web, false), web.attributes_json);
+}
+
+// ─── Color ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_no_color_flag_or_env_disables_color() {
+    assert!(color_disabled(true, None));
+    assert!(color_disabled(false, Some("1")));
+    // An empty NO_COLOR doesn't count, per the convention
+    assert!(!color_disabled(false, Some("")));
+    assert!(!color_disabled(false, None));
+
+    assert!(init_color(true));
+    let table = format_run_list(&[run("1a2b3c4d-cccc-dddd", "apply", "failed", 1)]);
+    assert!(table.contains("failed"));
+    assert!(!table.contains('\x1b'), "{:?}", table);
+}
+
+// ─── Apply timings ───────────────────────────────────────────────────────────
+
+fn timed(address: &str, provider: Option<&str>, secs: u64, status: NodeStatus) -> NodeResult {
+    NodeResult {
+        node_index: petgraph::graph::NodeIndex::new(0),
+        address: address.to_string(),
+        status,
+        outputs: None,
+        duration: std::time::Duration::from_secs(secs),
+        provider: provider.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_apply_timings_list_slowest_resource_first() {
+    colored::control::set_override(false);
+    let aws = Some("hashicorp/aws");
+    let results = vec![
+        timed("aws_vpc.main", aws, 3, NodeStatus::Succeeded),
+        timed("aws_db_instance.main", aws, 312, NodeStatus::Succeeded),
+        timed(
+            "random_pet.name",
+            Some("hashicorp/random"),
+            1,
+            NodeStatus::Succeeded,
+        ),
+        timed(
+            "aws_instance.web",
+            aws,
+            45,
+            NodeStatus::Failed("boom".into()),
+        ),
+        // Outputs and skipped resources never ran
+        timed("output.url", None, 0, NodeStatus::Succeeded),
+        timed("aws_eip.web", aws, 0, NodeStatus::Skipped("x".into())),
+    ];
+
+    let text = format_apply_timings(&results, 2);
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let slowest = lines
+        .iter()
+        .position(|l| *l == "Slowest resources:")
+        .unwrap();
+    assert!(lines[slowest + 1].starts_with("aws_db_instance.main"));
+    assert!(lines[slowest + 1].ends_with("5m12s"));
+    assert!(lines[slowest + 2].starts_with("aws_instance.web"));
+    assert!(lines[slowest + 2].ends_with("45.0s"));
+    // Only the top two are listed
+    assert!(!text.contains("aws_vpc.main"));
+    assert!(!text.contains("aws_eip.web"));
+
+    let providers = lines
+        .iter()
+        .position(|l| *l == "Time by provider:")
+        .unwrap();
+    assert!(lines[providers + 1].starts_with("hashicorp/aws"));
+    assert!(lines[providers + 1].ends_with("6m (3 resources)"));
+    assert!(lines[providers + 2].ends_with("1.0s (1 resource)"));
+
+    assert!(format_apply_timings(&[], 10).is_empty());
+}
+
+// ─── Compact plan ────────────────────────────────────────────────────────────
+
+fn change(address: &str, action: ResourceAction) -> PlannedChange {
+    PlannedChange {
+        address: address.to_string(),
+        action,
+        resource_type: address.split('.').next().unwrap().to_string(),
+        provider_source: "hashicorp/aws".to_string(),
+        planned_state: Some(serde_json::json!({"id": "x", "tags": {"a": "b"}})),
+        prior_state: None,
+        user_config: None,
+        requires_replace: vec![],
+        replace_reason: None,
+        planned_private: vec![],
+    }
+}
+
+#[test]
+fn test_compact_plan_prints_one_line_per_change() {
+    colored::control::set_override(false);
+    let mut changes: Vec<PlannedChange> = (0..30)
+        .map(|i| change(&format!("aws_instance.web[{}]", i), ResourceAction::Create))
+        .collect();
+    changes.push(change("aws_db_instance.main", ResourceAction::Replace));
+    changes.push(change("aws_vpc.main", ResourceAction::Update));
+    changes.push(change("aws_subnet.old", ResourceAction::Delete));
+    changes.extend((0..10).map(|i| change(&format!("aws_eip.ip[{}]", i), ResourceAction::NoOp)));
+    let plan = PlanSummary::from_changes(changes, vec![]);
+
+    let compact = format_compact_plan(&plan);
+    let lines: Vec<&str> = compact.lines().collect();
+    assert_eq!(lines.len(), 33);
+    assert!(lines.contains(&"  +   aws_instance.web[0]"));
+    assert!(lines.contains(&"  -/+ aws_db_instance.main"));
+    assert!(lines.contains(&"  ~   aws_vpc.main"));
+    assert!(lines.contains(&"  -   aws_subnet.old"));
+    assert!(!compact.contains("aws_eip"));
+}
+
+// ─── State-only changes ──────────────────────────────────────────────────────
+
+#[test]
+fn test_plan_with_only_moves_has_changes() {
+    colored::control::set_override(false);
+    let mut plan = PlanSummary::from_changes(
+        vec![change("aws_instance.web", ResourceAction::NoOp)],
+        vec![],
+    );
+    assert!(!plan.has_changes());
+
+    let moved = ResourceState::new("ws", "aws_instance", "app", "aws_instance.app");
+    plan.state_updates
+        .moves
+        .push(("aws_instance.web".to_string(), moved));
+    assert!(plan.has_changes());
+    assert_eq!(plan.to_string(), "Plan: 1 to move.");
+    assert_eq!(
+        format_state_updates(&plan),
+        "  # aws_instance.web has moved to aws_instance.app\n\n"
+    );
+}
+
+#[test]
+fn test_plan_with_only_forgets_has_changes() {
+    colored::control::set_override(false);
+    let mut plan = PlanSummary::from_changes(vec![], vec![]);
+    plan.state_updates
+        .forgotten
+        .push("aws_instance.legacy".to_string());
+    assert!(plan.has_changes());
+    assert_eq!(plan.to_string(), "Plan: 1 to forget.");
+    assert_eq!(
+        format_state_updates(&plan),
+        "  # aws_instance.legacy will no longer be managed by oxid (the object is not destroyed)\n\n"
+    );
+}
+
+// ─── Refresh-only plan ───────────────────────────────────────────────────────
+
+#[test]
+fn test_refresh_only_plan_describes_drift() {
+    colored::control::set_override(false);
+    let mut drifted = change("aws_instance.web", ResourceAction::Update);
+    drifted.prior_state = Some(serde_json::json!({"id": "x", "tags": {}}));
+    let mut gone = change("aws_s3_bucket.logs", ResourceAction::Delete);
+    gone.planned_state = None;
+    gone.prior_state = Some(serde_json::json!({"id": "logs"}));
+    let plan = PlanSummary::from_changes(vec![drifted, gone], vec![]);
+
+    let text = format_refresh_only_plan(&plan);
+    assert!(
+        text.contains("# aws_instance.web has changed outside oxid"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("# aws_s3_bucket.logs has been deleted outside oxid"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("will be destroyed"), "{}", text);
+    assert!(!text.contains("Plan:"), "{}", text);
+
+    let text = format_refresh_only_plan(&PlanSummary::from_changes(vec![], vec![]));
+    assert!(text.contains("No changes. The state matches the remote objects."));
+}
+
+// ─── Replacement reasons ─────────────────────────────────────────────────────
+
+#[test]
+fn test_replacement_names_forcing_attributes() {
+    colored::control::set_override(false);
+    let mut replace = change("aws_instance.web", ResourceAction::Replace);
+    replace.prior_state = Some(serde_json::json!({
+        "ami": "ami-1",
+        "instance_type": "t3.micro",
+        "root_block_device": [{"volume_size": 8}],
+    }));
+    replace.planned_state = Some(serde_json::json!({
+        "ami": "ami-2",
+        "instance_type": "t3.micro",
+        "root_block_device": [{"volume_size": 16}],
+    }));
+    replace.user_config = Some(serde_json::json!({"ami": "ami-2", "instance_type": "t3.micro"}));
+    replace.requires_replace = vec![
+        "ami".to_string(),
+        "root_block_device.[0].volume_size".to_string(),
+    ];
+
+    let rendered = format_resource_change(&replace);
+    let line = |key: &str| {
+        rendered
+            .lines()
+            .find(|l| l.contains(&format!(" {} ", key)))
+            .unwrap_or_else(|| panic!("no line for {} in:\n{}", key, rendered))
+    };
+    assert!(
+        line("ami").ends_with("# forces replacement"),
+        "{}",
+        rendered
+    );
+    assert!(
+        line("root_block_device").ends_with("# forces replacement"),
+        "{}",
+        rendered
+    );
+    assert!(
+        !line("instance_type").contains("forces replacement"),
+        "{}",
+        rendered
+    );
+    assert!(rendered.contains(
+        "Resource aws_instance.web must be replaced because attributes [ami, root_block_device.[0].volume_size] changed."
+    ));
+
+    // Replacements the provider did not force carry no reason
+    replace.requires_replace.clear();
+    let rendered = format_resource_change(&replace);
+    assert!(!rendered.contains("forces replacement"));
+    assert!(!rendered.contains("because"));
+}
+
+#[test]
+fn test_replacement_shows_why_it_was_forced() {
+    colored::control::set_override(false);
+    let mut forced = change("aws_instance.web", ResourceAction::Replace);
+    forced.replace_reason = Some("requested with --replace".to_string());
+
+    let rendered = format_resource_change(&forced);
+    let mut lines = rendered.lines();
+    assert_eq!(lines.next(), Some("  # aws_instance.web must be replaced"));
+    assert_eq!(lines.next(), Some("  # (requested with --replace)"));
+}
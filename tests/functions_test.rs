@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use base64::Engine;
+use oxid::config::loader::load_workspace;
+use oxid::executor::engine::{eval_expression, read_config_file, EvalContext};
+use oxid::hcl::parser::parse_hcl;
+use serde_json::json;
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+/// Evaluate the `value` of each output in `hcl`, keyed by output name.
+fn eval_outputs(hcl: &str) -> HashMap<String, serde_json::Value> {
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let ctx = EvalContext::plan_only(HashMap::new());
+    ws.outputs
+        .iter()
+        .map(|o| (o.name.clone(), eval_expression(&o.value, &ctx).unwrap()))
+        .collect()
+}
+
+/// Evaluate the single output in `hcl`, expecting an error, and return its
+/// message.
+fn eval_error(hcl: &str, ctx: &EvalContext) -> String {
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    format!(
+        "{:#}",
+        eval_expression(&ws.outputs[0].value, ctx).unwrap_err()
+    )
+}
+
+// ─── YAML encoding ───────────────────────────────────────────────────────────
+
+#[test]
+fn test_yamlencode_yamldecode_round_trip() {
+    let outputs = eval_outputs(
+        r#"
+output "encoded" {
+  value = yamlencode({
+    name = "policy"
+    statement = [
+      { effect = "Allow", actions = ["s3:GetObject", "s3:ListBucket"] },
+      { effect = "Deny", actions = [], enabled = false },
+    ]
+    limits = { max = 10, ratio = 0.5 }
+  })
+}
+
+output "round_trip" {
+  value = yamldecode(yamlencode({
+    name = "policy"
+    nested = { list = [1, 2, 3], flag = true, empty = null }
+  }))
+}
+"#,
+    );
+
+    let encoded = outputs["encoded"].as_str().unwrap();
+    let decoded: serde_json::Value = serde_yaml::from_str(encoded).unwrap();
+    assert_eq!(
+        decoded,
+        json!({
+            "name": "policy",
+            "statement": [
+                {"effect": "Allow", "actions": ["s3:GetObject", "s3:ListBucket"]},
+                {"effect": "Deny", "actions": [], "enabled": false},
+            ],
+            "limits": {"max": 10, "ratio": 0.5},
+        })
+    );
+
+    assert_eq!(
+        outputs["round_trip"],
+        json!({
+            "name": "policy",
+            "nested": {"list": [1, 2, 3], "flag": true, "empty": null},
+        })
+    );
+}
+
+#[test]
+fn test_yamlencode_sorts_keys() {
+    let outputs = eval_outputs(
+        r#"
+output "encoded" {
+  value = yamlencode({ zeta = 1, alpha = { delta = 2, beta = 3 } })
+}
+"#,
+    );
+    assert_eq!(
+        outputs["encoded"],
+        "alpha:\n  beta: 3\n  delta: 2\nzeta: 1\n"
+    );
+}
+
+#[test]
+fn test_yamldecode_invalid_input_is_an_error() {
+    let err = eval_error(
+        r#"
+output "bad" {
+  value = yamldecode("key: [unterminated")
+}
+"#,
+        &EvalContext::plan_only(HashMap::new()),
+    );
+    assert!(err.contains("yamldecode()"), "{}", err);
+}
+
+#[test]
+fn test_jsonencode_nested_structures() {
+    let outputs = eval_outputs(
+        r#"
+output "json" {
+  value = jsondecode(jsonencode({ a = [{ b = { c = true } }] }))
+}
+"#,
+    );
+    assert_eq!(outputs["json"], json!({"a": [{"b": {"c": true}}]}));
+}
+
+// ─── Files and templates ─────────────────────────────────────────────────────
+
+const TEMPLATES: &str = "tests/fixtures/templates";
+
+/// Evaluate the output `name` of the templates fixture.
+fn eval_fixture_output(name: &str) -> anyhow::Result<serde_json::Value> {
+    let ws = load_workspace(Path::new(TEMPLATES)).unwrap();
+    let ctx = EvalContext::plan_only(HashMap::new()).with_config_dir(ws.root_dir.as_deref());
+    let output = ws.outputs.iter().find(|o| o.name == name).unwrap();
+    eval_expression(&output.value, &ctx)
+}
+
+#[test]
+fn test_file_reads_relative_to_config_dir() {
+    assert_eq!(
+        eval_fixture_output("script").unwrap(),
+        "#!/bin/sh\necho \"hello\"\n"
+    );
+}
+
+#[test]
+fn test_templatefile_interpolates_vars() {
+    assert_eq!(
+        eval_fixture_output("cloud_init").unwrap(),
+        "#cloud-config\nhostname: web-01\nport: 8080\n"
+    );
+}
+
+#[test]
+fn test_file_missing_or_outside_root_is_an_error() {
+    let err = format!("{:#}", eval_fixture_output("missing").unwrap_err());
+    assert!(
+        err.contains("No file exists at does-not-exist.sh"),
+        "{}",
+        err
+    );
+    let err = format!("{:#}", eval_fixture_output("escape").unwrap_err());
+    assert!(
+        err.contains("is outside the configuration directory"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_read_config_file_errors() {
+    let ctx = EvalContext::plan_only(HashMap::new()).with_config_dir(Some(Path::new(TEMPLATES)));
+
+    let err = read_config_file("does-not-exist.sh", &ctx).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("No file exists at does-not-exist.sh"));
+
+    let err = read_config_file("../../engine_test.rs", &ctx).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("is outside the configuration directory"));
+
+    assert!(read_config_file("scripts/../cloud-init.tftpl", &ctx).is_ok());
+}
+
+// ─── try and can ─────────────────────────────────────────────────────────────
+
+/// A context with `settings`, `nullable` and `zones` variables bound.
+fn vars_ctx() -> EvalContext {
+    EvalContext::plan_only(HashMap::from([
+        ("settings".to_string(), json!({"name": "web", "port": 8080})),
+        ("nullable".to_string(), json!(null)),
+        ("zones".to_string(), json!(["a", "b"])),
+    ]))
+}
+
+/// Evaluate outputs with the variables of [`vars_ctx`] bound.
+fn eval_outputs_with_vars(hcl: &str) -> HashMap<String, serde_json::Value> {
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let ctx = vars_ctx();
+    ws.outputs
+        .iter()
+        .map(|o| (o.name.clone(), eval_expression(&o.value, &ctx).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_try_returns_first_argument_without_error() {
+    let outputs = eval_outputs_with_vars(
+        r#"
+output "undeclared" {
+  value = try(nonexistent.attr, "fallback")
+}
+
+output "missing_attr" {
+  value = try(var.settings.timeout, 30)
+}
+
+output "present_attr" {
+  value = try(var.settings.port, 30)
+}
+
+output "out_of_range" {
+  value = try(var.zones[5], var.zones[0])
+}
+
+output "bad_json" {
+  value = try(jsondecode("{not json"), {})
+}
+
+output "bad_number" {
+  value = try(tonumber("ten"), 10)
+}
+"#,
+    );
+    assert_eq!(outputs["undeclared"], "fallback");
+    assert_eq!(outputs["missing_attr"], 30);
+    assert_eq!(outputs["present_attr"], 8080);
+    assert_eq!(outputs["out_of_range"], "a");
+    assert_eq!(outputs["bad_json"], json!({}));
+    assert_eq!(outputs["bad_number"], 10);
+}
+
+#[test]
+fn test_try_fails_when_every_argument_fails() {
+    let err = eval_error(
+        r#"
+output "all_fail" {
+  value = try(nonexistent.attr, var.zones[9])
+}
+"#,
+        &vars_ctx(),
+    );
+    assert!(
+        err.contains("try(): no argument could be evaluated"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_try_keeps_null_results() {
+    // null is a value, not an error, so try doesn't move past it
+    let outputs = eval_outputs_with_vars(
+        r#"
+output "null" {
+  value = try(var.nullable, "fallback")
+}
+"#,
+    );
+    assert!(outputs["null"].is_null());
+}
+
+#[test]
+fn test_can_reports_whether_evaluation_errors() {
+    let outputs = eval_outputs_with_vars(
+        r#"
+output "undeclared" {
+  value = can(nonexistent.attr)
+}
+
+output "missing_attr" {
+  value = can(var.settings.timeout)
+}
+
+output "present_attr" {
+  value = can(var.settings.name)
+}
+
+output "in_range" {
+  value = can(var.zones[1])
+}
+
+output "out_of_range" {
+  value = can(var.zones[2])
+}
+
+output "null" {
+  value = can(var.nullable)
+}
+
+output "no_match" {
+  value = can(regex("^[0-9]+$", "abc"))
+}
+
+output "nested" {
+  value = can(upper(var.settings.missing))
+}
+"#,
+    );
+    assert_eq!(outputs["undeclared"], false);
+    assert_eq!(outputs["missing_attr"], false);
+    assert_eq!(outputs["present_attr"], true);
+    assert_eq!(outputs["in_range"], true);
+    assert_eq!(outputs["out_of_range"], false);
+    assert_eq!(outputs["null"], true);
+    assert_eq!(outputs["no_match"], false);
+    assert_eq!(outputs["nested"], false);
+}
+
+// ─── Base64 ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_base64encode_base64decode_round_trip() {
+    let outputs = eval_outputs(
+        r#"
+output "encoded" {
+  value = base64encode("runcmd:\n  - écho")
+}
+
+output "round_trip" {
+  value = base64decode(base64encode("hello, world"))
+}
+
+output "decoded" {
+  value = base64decode("aGVsbG8=")
+}
+
+output "invalid" {
+  value = can(base64decode("not base64!"))
+}
+"#,
+    );
+    assert_eq!(
+        outputs["encoded"],
+        base64::engine::general_purpose::STANDARD.encode("runcmd:\n  - écho")
+    );
+    assert_eq!(outputs["round_trip"], "hello, world");
+    assert_eq!(outputs["decoded"], "hello");
+    assert_eq!(outputs["invalid"], false);
+
+    let err = eval_error(
+        r#"
+output "invalid" {
+  value = base64decode("not base64!")
+}
+"#,
+        &EvalContext::plan_only(HashMap::new()),
+    );
+    assert!(err.contains("base64decode()"), "{}", err);
+}
+
+#[test]
+fn test_base64gzip_decompresses_to_input() {
+    use std::io::Read;
+
+    let outputs = eval_outputs(
+        r#"
+output "zipped" {
+  value = base64gzip("set -e\necho hello\n")
+}
+"#,
+    );
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(outputs["zipped"].as_str().unwrap())
+        .unwrap();
+    let mut text = String::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, "set -e\necho hello\n");
+}
+
+// ─── Hashes and uuid ─────────────────────────────────────────────────────────
+
+#[test]
+fn test_hash_functions_match_known_vectors() {
+    let outputs = eval_outputs(
+        r#"
+output "md5" {
+  value = md5("abc")
+}
+
+output "sha1" {
+  value = sha1("abc")
+}
+
+output "sha256" {
+  value = sha256("abc")
+}
+
+output "sha512" {
+  value = sha512("abc")
+}
+
+output "empty" {
+  value = md5("")
+}
+"#,
+    );
+    assert_eq!(outputs["md5"], "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(outputs["sha1"], "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+        outputs["sha256"],
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        outputs["sha512"],
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+         2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+    );
+    assert_eq!(outputs["empty"], "d41d8cd98f00b204e9800998ecf8427e");
+}
+
+#[test]
+fn test_uuid_is_a_fresh_v4_uuid() {
+    let outputs = eval_outputs(
+        r#"
+output "a" {
+  value = uuid()
+}
+
+output "b" {
+  value = uuid()
+}
+"#,
+    );
+    let a = outputs["a"].as_str().unwrap();
+    let parsed = uuid::Uuid::parse_str(a).unwrap();
+    assert_eq!(parsed.get_version_num(), 4);
+    assert_ne!(outputs["a"], outputs["b"]);
+}
+
+// ─── Operator precedence ─────────────────────────────────────────────────────
+
+#[test]
+fn test_operators_bind_by_precedence() {
+    let outputs = eval_outputs(
+        r#"
+output "comparison_before_and_before_or" {
+  value = 5 >= 3 && 2 <= 1 || 4 == 4
+}
+
+output "and_before_or" {
+  value = true || false && false
+}
+
+output "parentheses_win" {
+  value = (true || false) && false
+}
+
+output "comparison_before_equality" {
+  value = 1 < 2 == true
+}
+"#,
+    );
+    assert_eq!(outputs["comparison_before_and_before_or"], json!(true));
+    assert_eq!(outputs["and_before_or"], json!(true));
+    assert_eq!(outputs["parentheses_win"], json!(false));
+    assert_eq!(outputs["comparison_before_equality"], json!(true));
+}
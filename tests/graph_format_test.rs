@@ -0,0 +1,320 @@
+use std::path::Path;
+
+use oxid::dag::resource_graph::{
+    build_resource_dag, to_dot, to_dot_by_provider, to_json, to_mermaid, ResourceGraph,
+};
+use oxid::executor::engine::{build_provider_map, build_variable_defaults};
+use oxid::hcl::parser::parse_hcl;
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+fn sample_graph() -> ResourceGraph {
+    let hcl = r#"
+data "aws_ami" "ubuntu" {
+  most_recent = true
+}
+
+resource "aws_vpc" "main" {
+  cidr_block = "10.0.0.0/16"
+}
+
+resource "aws_instance" "web" {
+  ami       = data.aws_ami.ubuntu.id
+  subnet_id = aws_vpc.main.id
+}
+
+resource "null_resource" "after" {
+  depends_on = [aws_instance.web]
+}
+
+output "web_id" {
+  value = aws_instance.web.id
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    graph
+}
+
+fn graph_of(hcl: &str) -> ResourceGraph {
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws))
+        .unwrap()
+        .0
+}
+
+/// The kinds of every edge, as "from -> to (kind)".
+fn edges(graph: &ResourceGraph) -> Vec<String> {
+    let mut edges: Vec<String> = graph
+        .edge_indices()
+        .map(|e| {
+            let (from, to) = graph.edge_endpoints(e).unwrap();
+            format!(
+                "{} -> {} ({})",
+                graph[from].address(),
+                graph[to].address(),
+                graph[e].as_str()
+            )
+        })
+        .collect();
+    edges.sort();
+    edges
+}
+
+// ─── Edges ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_repeated_references_share_one_edge() {
+    let graph = graph_of(
+        r#"
+resource "aws_iam_role" "app" {}
+
+resource "aws_lambda_function" "app" {
+  role        = aws_iam_role.app.arn
+  description = "runs as ${aws_iam_role.app.name} (${aws_iam_role.app.id})"
+}
+"#,
+    );
+    assert_eq!(
+        edges(&graph),
+        ["aws_iam_role.app -> aws_lambda_function.app (implicit)"]
+    );
+}
+
+#[test]
+fn test_depends_on_outranks_reference() {
+    // Declared before and after the reference: the explicit kind wins either way
+    let graph = graph_of(
+        r#"
+resource "aws_vpc" "main" {}
+resource "aws_s3_bucket" "logs" {}
+
+resource "aws_subnet" "a" {
+  depends_on = [aws_vpc.main]
+  vpc_id     = aws_vpc.main.id
+}
+
+resource "aws_flow_log" "vpc" {
+  log_destination = aws_s3_bucket.logs.arn
+  vpc_id          = aws_vpc.main.id
+  depends_on      = [aws_s3_bucket.logs]
+}
+
+output "subnet" {
+  value      = aws_subnet.a.id
+  depends_on = [aws_subnet.a]
+}
+"#,
+    );
+    assert_eq!(
+        edges(&graph),
+        [
+            "aws_s3_bucket.logs -> aws_flow_log.vpc (explicit)",
+            "aws_subnet.a -> subnet (explicit)",
+            "aws_vpc.main -> aws_flow_log.vpc (implicit)",
+            "aws_vpc.main -> aws_subnet.a (explicit)",
+        ]
+    );
+}
+
+// ─── Providers ───────────────────────────────────────────────────────────────
+
+#[test]
+fn test_provider_configured_from_resource_is_ordered_after_it() {
+    let graph = graph_of(
+        r#"
+provider "aws" {
+  region = "us-east-1"
+}
+
+provider "kubernetes" {
+  host                   = aws_eks_cluster.main.endpoint
+  cluster_ca_certificate = base64decode(aws_eks_cluster.main.certificate_authority[0].data)
+}
+
+resource "aws_eks_cluster" "main" {}
+
+resource "kubernetes_namespace" "app" {}
+
+data "kubernetes_service" "dns" {}
+"#,
+    );
+    // Only the provider with dependencies gets a node
+    assert!(graph.node_weights().all(|n| n.address() != "provider.aws"));
+    assert_eq!(
+        edges(&graph),
+        [
+            "aws_eks_cluster.main -> provider.kubernetes (provider)",
+            "provider.kubernetes -> data.kubernetes_service.dns (provider)",
+            "provider.kubernetes -> kubernetes_namespace.app (provider)",
+        ]
+    );
+
+    let json = to_json(&graph);
+    let provider = json["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|n| n["kind"] == "provider")
+        .unwrap();
+    assert_eq!(provider["address"], "provider.kubernetes");
+    assert_eq!(provider["provider_source"], "hashicorp/kubernetes");
+}
+
+#[test]
+fn test_provider_depends_on_orders_only_its_alias() {
+    let graph = graph_of(
+        r#"
+provider "aws" {
+  region = "us-east-1"
+}
+
+provider "aws" {
+  alias      = "west"
+  region     = "us-west-2"
+  depends_on = [aws_iam_role.deployer]
+}
+
+resource "aws_iam_role" "deployer" {}
+
+resource "aws_s3_bucket" "west" {
+  provider = aws.west
+}
+"#,
+    );
+    assert_eq!(
+        edges(&graph),
+        [
+            "aws_iam_role.deployer -> provider.aws.west (provider)",
+            "provider.aws.west -> aws_s3_bucket.west (provider)",
+        ]
+    );
+}
+
+#[test]
+fn test_provider_graph_clusters_nodes_by_provider() {
+    let graph = graph_of(
+        r#"
+provider "aws" {
+  alias  = "west"
+  region = "us-west-2"
+}
+
+resource "aws_vpc" "main" {}
+
+resource "aws_s3_bucket" "logs" {
+  provider = aws.west
+  tags     = { vpc = aws_vpc.main.id }
+}
+
+resource "random_id" "suffix" {
+  keepers = { bucket = aws_s3_bucket.logs.id }
+}
+
+output "suffix" {
+  value = random_id.suffix.hex
+}
+"#,
+    );
+    let dot = to_dot_by_provider(&graph);
+
+    let clusters = dot.matches("subgraph cluster_").count();
+    assert_eq!(clusters, 3);
+    for provider in ["hashicorp/aws", "hashicorp/aws.west", "hashicorp/random"] {
+        assert!(
+            dot.contains(&format!("label=\"{}\";", provider)),
+            "missing cluster for {}:\n{}",
+            provider,
+            dot
+        );
+    }
+
+    // Outputs sit outside every cluster
+    let last_cluster_end = dot.rfind("  }\n").unwrap();
+    assert!(dot.find("output.suffix").unwrap() > last_cluster_end);
+
+    // Dependency edges survive across clusters
+    let arrows = dot.lines().filter(|l| l.contains(" -> ")).count();
+    assert_eq!(arrows, graph.edge_count());
+    assert!(arrows >= 3);
+}
+
+// ─── JSON ────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_graph_json_lists_all_nodes() {
+    let graph = sample_graph();
+    let json = to_json(&graph);
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), graph.node_count());
+
+    let address_of = |addr: &str| nodes.iter().find(|n| n["address"] == addr).cloned();
+    for addr in [
+        "data.aws_ami.ubuntu",
+        "aws_vpc.main",
+        "aws_instance.web",
+        "null_resource.after",
+        "output.web_id",
+    ] {
+        assert!(address_of(addr).is_some(), "missing node {}", addr);
+    }
+
+    let vpc = address_of("aws_vpc.main").unwrap();
+    assert_eq!(vpc["kind"], "resource");
+    assert_eq!(vpc["provider_source"], "hashicorp/aws");
+    assert_eq!(address_of("data.aws_ami.ubuntu").unwrap()["kind"], "data");
+    let output = address_of("output.web_id").unwrap();
+    assert_eq!(output["kind"], "output");
+    assert!(output["provider_source"].is_null());
+}
+
+#[test]
+fn test_graph_json_lists_all_edges() {
+    let graph = sample_graph();
+    let json = to_json(&graph);
+    let edges = json["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), graph.edge_count());
+
+    let has_edge = |from: &str, to: &str, kind: &str| {
+        edges
+            .iter()
+            .any(|e| e["from"] == from && e["to"] == to && e["kind"] == kind)
+    };
+    assert!(has_edge("aws_vpc.main", "aws_instance.web", "implicit"));
+    assert!(has_edge(
+        "data.aws_ami.ubuntu",
+        "aws_instance.web",
+        "implicit"
+    ));
+    assert!(has_edge(
+        "aws_instance.web",
+        "null_resource.after",
+        "explicit"
+    ));
+    assert!(has_edge("aws_instance.web", "output.web_id", "implicit"));
+}
+
+// ─── Mermaid ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_graph_mermaid_output() {
+    let graph = sample_graph();
+    let mermaid = to_mermaid(&graph);
+    assert!(mermaid.starts_with("graph TD\n"));
+    assert!(mermaid.contains("aws_vpc.main<br/>aws_vpc\"]:::resource"));
+    assert!(mermaid.contains("\"output.web_id\"]:::output"));
+    assert!(mermaid.contains(":::data"));
+
+    let arrows = mermaid
+        .lines()
+        .filter(|l| l.contains("-->") || l.contains("-.->") || l.contains("==>"))
+        .count();
+    assert_eq!(arrows, graph.edge_count());
+}
+
+#[test]
+fn test_graph_dot_remains_default_format() {
+    let dot = to_dot(&sample_graph());
+    assert!(dot.starts_with("digraph resources {"));
+}
@@ -0,0 +1,251 @@
+use std::path::Path;
+use std::time::Duration;
+
+use oxid::config::types::{parse_duration, DEFAULT_OPERATION_TIMEOUT};
+use oxid::dag::validation::validate_count_references;
+use oxid::hcl::parse_directory;
+use oxid::hcl::parser::parse_hcl;
+
+const CONFIG: &str = r#"# Example configuration
+
+variable "names" {
+  default = ["a", "b"]
+}
+
+resource "null_resource" "first" {
+  count = 2
+}
+
+  resource "null_resource" "second" {
+    triggers = {
+      id = null_resource.first.id
+    }
+  }
+
+data "external" "lookup" {
+  program = ["echo"]
+}
+
+output "first_ids" {
+  value = null_resource.first[*].id
+}
+"#;
+
+// ─── Source Locations ────────────────────────────────────────────────────────
+
+#[test]
+fn test_resource_source_location() {
+    let ws = parse_hcl(CONFIG, Path::new("main.tf")).unwrap();
+    let first = ws.resources.iter().find(|r| r.name == "first").unwrap();
+    let loc = first.source_location.as_ref().unwrap();
+    assert_eq!(loc.file, "main.tf");
+    assert_eq!((loc.line, loc.column), (7, 1));
+
+    let second = ws.resources.iter().find(|r| r.name == "second").unwrap();
+    let loc = second.source_location.as_ref().unwrap();
+    assert_eq!((loc.line, loc.column), (11, 3));
+    assert_eq!(loc.to_string(), "main.tf:11:3");
+}
+
+#[test]
+fn test_data_and_output_source_locations() {
+    let ws = parse_hcl(CONFIG, Path::new("main.tf")).unwrap();
+    let data = ws.data_sources[0].source_location.as_ref().unwrap();
+    assert_eq!((data.line, data.column), (17, 1));
+    let output = ws.outputs[0].source_location.as_ref().unwrap();
+    assert_eq!((output.line, output.column), (21, 1));
+}
+
+#[test]
+fn test_parse_error_reports_line_and_column() {
+    let err = parse_hcl("resource \"a\" \"b\" {\n  x = \n}\n", Path::new("bad.tf")).unwrap_err();
+    assert_eq!(err.to_string(), "Failed to parse HCL in: bad.tf:2:7");
+}
+
+#[test]
+fn test_validation_error_carries_location() {
+    let ws = parse_hcl(CONFIG, Path::new("main.tf")).unwrap();
+    let errors = validate_count_references(&ws);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].ref_address, "null_resource.first");
+    assert_eq!(
+        errors[0].location.as_ref().unwrap().to_string(),
+        "main.tf:11:3"
+    );
+}
+
+// ─── Duplicate Declarations ──────────────────────────────────────────────────
+
+fn write_config(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, content) in files {
+        std::fs::write(dir.path().join(name), content).unwrap();
+    }
+    dir
+}
+
+fn duplicate_error(files: &[(&str, &str)]) -> String {
+    let dir = write_config(files);
+    format!("{:#}", parse_directory(dir.path()).unwrap_err())
+}
+
+#[test]
+fn test_duplicate_resource_across_files_names_both() {
+    let err = parse_directory(Path::new("tests/fixtures/duplicates"))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Duplicate resource \"aws_vpc.main\""),
+        "{}",
+        err
+    );
+    assert!(err.contains("main.tf:5:1"), "{}", err);
+    assert!(err.contains("network.tf:5:1"), "{}", err);
+}
+
+#[test]
+fn test_duplicate_variable_output_and_provider() {
+    let err = duplicate_error(&[
+        ("a.tf", "variable \"region\" {}\n"),
+        ("b.tf", "variable \"region\" {}\n"),
+    ]);
+    assert!(err.contains("Duplicate variable \"region\""), "{}", err);
+    assert!(err.contains("a.tf") && err.contains("b.tf"), "{}", err);
+
+    let err = duplicate_error(&[
+        ("a.tf", "output \"id\" {\n  value = 1\n}\n"),
+        ("b.tf.json", r#"{"output": {"id": {"value": 2}}}"#),
+    ]);
+    assert!(err.contains("Duplicate output \"id\""), "{}", err);
+    assert!(err.contains("b.tf.json"), "{}", err);
+
+    let err = duplicate_error(&[("a.tf", "provider \"aws\" {}\nprovider \"aws\" {}\n")]);
+    assert!(err.contains("Duplicate provider \"aws\""), "{}", err);
+}
+
+#[test]
+fn test_same_name_in_different_namespaces_is_allowed() {
+    let dir = write_config(&[
+        (
+            "a.tf",
+            "provider \"aws\" {}\nresource \"aws_vpc\" \"main\" {}\n",
+        ),
+        (
+            "b.tf",
+            "provider \"aws\" {\n  alias = \"west\"\n}\ndata \"aws_vpc\" \"main\" {}\nresource \"aws_subnet\" \"main\" {}\n",
+        ),
+    ]);
+    let ws = parse_directory(dir.path()).unwrap();
+    assert_eq!(ws.resources.len(), 2);
+    assert_eq!(ws.data_sources.len(), 1);
+    assert_eq!(ws.providers.len(), 2);
+}
+
+#[test]
+fn test_moved_blocks_are_parsed_in_order() {
+    let hcl = r#"
+moved {
+  from = aws_instance.a
+  to   = aws_instance.b
+}
+
+moved {
+  from = module.net.aws_subnet.private["a"]
+  to   = aws_subnet.private[0]
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let moves: Vec<(&str, &str)> = ws
+        .moved
+        .iter()
+        .map(|m| (m.from.as_str(), m.to.as_str()))
+        .collect();
+    assert_eq!(
+        moves,
+        [
+            ("aws_instance.a", "aws_instance.b"),
+            (
+                "module.net.aws_subnet.private[\"a\"]",
+                "aws_subnet.private[0]"
+            ),
+        ]
+    );
+
+    let err = parse_hcl(
+        "moved {\n  from = aws_instance.a\n}\n",
+        Path::new("main.tf"),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("requires both"), "{}", err);
+}
+
+#[test]
+fn test_removed_block_for_declared_resource_is_rejected() {
+    let dir = write_config(&[(
+        "main.tf",
+        "resource \"aws_vpc\" \"main\" {}\n\nremoved {\n  from = aws_vpc.main\n}\n",
+    )]);
+    let err = format!("{:#}", parse_directory(dir.path()).unwrap_err());
+    assert!(err.contains("removed block names aws_vpc.main"), "{}", err);
+    assert!(err.contains("main.tf:1:1"), "{}", err);
+
+    let dir = write_config(&[("main.tf", "removed {\n  from = aws_vpc.main\n}\n")]);
+    assert_eq!(parse_directory(dir.path()).unwrap().removed.len(), 1);
+}
+
+// ─── Timeouts ────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_timeouts_block_sets_operation_timeouts() {
+    let ws = parse_hcl(
+        r#"
+resource "aws_db_instance" "main" {
+  engine = "postgres"
+
+  timeouts {
+    create = "40m"
+    delete = "1h30m"
+  }
+}
+"#,
+        Path::new("main.tf"),
+    )
+    .unwrap();
+    let db = &ws.resources[0];
+    assert_eq!(db.timeouts.create_timeout(), Duration::from_secs(40 * 60));
+    assert_eq!(db.timeouts.delete_timeout(), Duration::from_secs(90 * 60));
+    // Unset operations fall back to the default
+    assert_eq!(db.timeouts.update_timeout(), DEFAULT_OPERATION_TIMEOUT);
+    // The provider still sees the block
+    assert!(db.attributes.contains_key("timeouts"));
+
+    let ws = parse_hcl("resource \"aws_vpc\" \"main\" {}\n", Path::new("main.tf")).unwrap();
+    assert_eq!(
+        ws.resources[0].timeouts.create_timeout(),
+        DEFAULT_OPERATION_TIMEOUT
+    );
+}
+
+#[test]
+fn test_invalid_timeout_is_rejected() {
+    let err = parse_hcl(
+        "resource \"aws_vpc\" \"main\" {\n  timeouts {\n    create = \"soon\"\n  }\n}\n",
+        Path::new("main.tf"),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("Invalid create timeout"), "{}", err);
+    assert!(err.contains("aws_vpc.main"), "{}", err);
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+    assert_eq!(parse_duration("1.5m"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+    assert_eq!(parse_duration(""), None);
+    assert_eq!(parse_duration("20"), None);
+    assert_eq!(parse_duration("20 minutes"), None);
+}
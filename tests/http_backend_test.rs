@@ -0,0 +1,216 @@
+use oxid::state::backend::StateBackend;
+use oxid::state::http::HttpBackend;
+use oxid::state::models::{ResourceFilter, ResourceState};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const TFSTATE: &str = r#"{
+  "version": 4,
+  "terraform_version": "1.9.0",
+  "serial": 7,
+  "lineage": "3f2c",
+  "outputs": {
+    "vpc_id": {"value": "vpc-123", "type": "string"}
+  },
+  "resources": [
+    {
+      "mode": "managed",
+      "type": "aws_vpc",
+      "name": "main",
+      "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+      "instances": [
+        {"schema_version": 1, "attributes": {"id": "vpc-123", "cidr_block": "10.0.0.0/16"}}
+      ]
+    }
+  ]
+}"#;
+
+/// Instances the way Terraform writes them: numeric count keys, string
+/// for_each keys, module paths, data sources and aliased providers.
+const TFSTATE_INSTANCES: &str = r#"{
+  "version": 4,
+  "serial": 3,
+  "resources": [
+    {
+      "mode": "managed",
+      "type": "aws_subnet",
+      "name": "private",
+      "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+      "instances": [
+        {"index_key": 0, "attributes": {"id": "subnet-0"}},
+        {"index_key": 1, "attributes": {"id": "subnet-1"}}
+      ]
+    },
+    {
+      "mode": "managed",
+      "type": "aws_s3_bucket",
+      "name": "logs",
+      "provider": "provider[\"registry.terraform.io/hashicorp/aws\"].west",
+      "instances": [
+        {"index_key": "a", "attributes": {"id": "logs-a"}}
+      ]
+    },
+    {
+      "module": "module.a",
+      "mode": "managed",
+      "type": "aws_vpc",
+      "name": "main",
+      "provider": "module.a.provider[\"registry.terraform.io/hashicorp/aws\"]",
+      "instances": [{"attributes": {"id": "vpc-a"}}]
+    },
+    {
+      "module": "module.b",
+      "mode": "managed",
+      "type": "aws_vpc",
+      "name": "main",
+      "provider": "module.b.provider[\"example.com/acme/aws\"]",
+      "instances": [
+        {
+          "attributes": {"id": "vpc-b", "password": "hunter2"},
+          "sensitive_attributes": [[{"type": "get_attr", "value": "password"}]]
+        }
+      ]
+    },
+    {
+      "mode": "data",
+      "type": "aws_ami",
+      "name": "ubuntu",
+      "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+      "instances": [{"attributes": {"id": "ami-1"}}]
+    }
+  ]
+}"#;
+
+// ─── Mock Server ─────────────────────────────────────────────────────────────
+
+/// An endpoint serving `body` at `/state` to requests bearing `token`, 401
+/// to everyone else, and 404 for any other path.
+async fn mock_state_server(body: &'static str, token: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let path = request.split_whitespace().nth(1).unwrap_or("");
+            let authorized = request
+                .lines()
+                .any(|l| l.eq_ignore_ascii_case(&format!("authorization: Bearer {}", token)));
+            let (status, body) = match (path, authorized) {
+                ("/state", true) => ("200 OK", body),
+                ("/state", false) => ("401 Unauthorized", ""),
+                _ => ("404 Not Found", ""),
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    format!("http://{}", addr)
+}
+
+// ─── Reads ───────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_http_backend_reads_terraform_state() {
+    let server = mock_state_server(TFSTATE, "s3cret").await;
+    let backend = HttpBackend::open(&format!("{}/state", server), Some("s3cret"))
+        .await
+        .unwrap();
+
+    let ws = backend.get_workspace("default").await.unwrap().unwrap();
+    assert_eq!(ws.serial, 7);
+    let resources = backend
+        .list_resources(&ws.id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].address, "aws_vpc.main");
+    assert_eq!(resources[0].schema_version, 1);
+    let attrs: serde_json::Value = serde_json::from_str(&resources[0].attributes_json).unwrap();
+    assert_eq!(attrs["cidr_block"], "10.0.0.0/16");
+
+    let output = backend
+        .get_output(&ws.id, "", "vpc_id")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(output.output_value, r#""vpc-123""#);
+}
+
+#[tokio::test]
+async fn test_http_backend_is_read_only() {
+    let server = mock_state_server(TFSTATE, "s3cret").await;
+    let backend = HttpBackend::open(&format!("{}/state", server), Some("s3cret"))
+        .await
+        .unwrap();
+    let ws = backend.get_workspace("default").await.unwrap().unwrap();
+
+    let resource = ResourceState::new(&ws.id, "aws_subnet", "a", "aws_subnet.a");
+    let err = backend.upsert_resource(&resource).await.unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{}", err);
+    assert!(backend.start_run(&ws.id, "apply", 1).await.is_err());
+    assert_eq!(backend.count_resources(&ws.id).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_http_backend_auth_and_missing_state() {
+    let server = mock_state_server(TFSTATE, "s3cret").await;
+
+    let err = HttpBackend::open(&format!("{}/state", server), Some("wrong"))
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("401"), "{}", err);
+
+    // No state at the endpoint yet reads as empty
+    let backend = HttpBackend::open(&format!("{}/other", server), None)
+        .await
+        .unwrap();
+    let ws = backend.get_workspace("default").await.unwrap().unwrap();
+    assert_eq!(backend.count_resources(&ws.id).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_http_backend_reads_terraform_instance_addresses() {
+    let server = mock_state_server(TFSTATE_INSTANCES, "s3cret").await;
+    let backend = HttpBackend::open(&format!("{}/state", server), Some("s3cret"))
+        .await
+        .unwrap();
+    let ws = backend.get_workspace("default").await.unwrap().unwrap();
+    let resources = backend
+        .list_resources(&ws.id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    let find = |address: &str| {
+        resources
+            .iter()
+            .find(|r| r.address == address)
+            .unwrap_or_else(|| panic!("{} not imported", address))
+    };
+    assert_eq!(resources.len(), 6);
+
+    let subnet = find("aws_subnet.private[1]");
+    assert_eq!(subnet.index_key.as_deref(), Some("1"));
+    assert_eq!(subnet.provider_source, "hashicorp/aws");
+    find("aws_subnet.private[0]");
+
+    let bucket = find("aws_s3_bucket.logs[\"a\"]");
+    assert_eq!(bucket.index_key.as_deref(), Some("a"));
+    assert_eq!(bucket.provider_source, "hashicorp/aws.west");
+
+    assert_eq!(find("module.a.aws_vpc.main").module_path, "module.a");
+    let vpc_b = find("module.b.aws_vpc.main");
+    assert_eq!(vpc_b.provider_source, "example.com/acme/aws");
+    assert_eq!(vpc_b.sensitive_attrs, vec!["password".to_string()]);
+
+    assert_eq!(find("data.aws_ami.ubuntu").resource_mode, "data");
+}
@@ -0,0 +1,412 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use oxid::hcl::parser::parse_hcl;
+use oxid::provider::lockfile::{archive_package_hash, package_hash, LockFile, LockedProvider};
+use oxid::provider::manager::ProviderManager;
+use oxid::provider::mirror::{FilesystemMirror, InstallMethodFilter, ProviderInstallation};
+use oxid::provider::registry::{shasum, Platform};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const AWS_VERSIONS: &str = include_str!("fixtures/registry/aws_versions.json");
+const RANDOM_VERSIONS: &str = r#"{"versions": [
+  {"version": "3.5.1", "protocols": ["5.0"], "platforms": [
+    {"os": "linux", "arch": "amd64"}, {"os": "darwin", "arch": "arm64"}]},
+  {"version": "3.6.0", "protocols": ["5.0"], "platforms": [
+    {"os": "linux", "arch": "amd64"}, {"os": "darwin", "arch": "arm64"}]}
+]}"#;
+
+/// The archive the mock registry serves for a download path: a stand-in
+/// binary holding the path, so every build hashes differently.
+fn mock_archive(download_path: &str) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options =
+        zip::write::SimpleFileOptions::default().last_modified_time(zip::DateTime::default());
+    zip.start_file("terraform-provider-mock_x5", options)
+        .unwrap();
+    zip.write_all(download_path.as_bytes()).unwrap();
+    zip.start_file("LICENSE", options).unwrap();
+    zip.write_all(b"MPL-2.0\n").unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+/// A registry serving recorded version lists for hashicorp/aws and
+/// hashicorp/random. Download responses and archives are synthesized per
+/// platform by `mock_archive`.
+async fn mock_registry() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+            let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+            let (status, body) = match segments.as_slice() {
+                ["v1", "providers", "hashicorp", "aws", "versions"] => {
+                    ("200 OK", AWS_VERSIONS.as_bytes().to_vec())
+                }
+                ["v1", "providers", "hashicorp", "random", "versions"] => {
+                    ("200 OK", RANDOM_VERSIONS.as_bytes().to_vec())
+                }
+                ["archives", ..] => ("200 OK", mock_archive(&path["/archives".len()..])),
+                ["v1", "providers", "hashicorp", ptype, version, "download", os, arch] => (
+                    "200 OK",
+                    serde_json::json!({
+                        "protocols": ["5.0"],
+                        "os": os,
+                        "arch": arch,
+                        "filename": format!("terraform-provider-{}_{}_{}_{}.zip", ptype, version, os, arch),
+                        "download_url": format!("http://{}/archives{}", addr, path),
+                        "shasum": shasum(&mock_archive(&path)),
+                    })
+                    .to_string()
+                    .into_bytes(),
+                ),
+                _ => ("404 Not Found", b"{}".to_vec()),
+            };
+            let head = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            );
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(&body).await;
+        }
+    });
+    format!("http://{}", addr)
+}
+
+/// The `zh:` and `h1:` hashes of one build the mock registry serves.
+fn mock_hashes(ptype: &str, version: &str, platform: &str) -> [String; 2] {
+    let (os, arch) = platform.split_once('_').unwrap();
+    let archive = mock_archive(&format!(
+        "/v1/providers/hashicorp/{}/{}/download/{}/{}",
+        ptype, version, os, arch
+    ));
+    [
+        LockedProvider::zip_hash(&shasum(&archive)),
+        archive_package_hash(&archive).unwrap(),
+    ]
+}
+
+const CONFIG: &str = r#"
+terraform {
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws"
+      version = ">= 5.0, < 6.0"
+    }
+    random = {
+      source = "hashicorp/random"
+    }
+  }
+}
+
+provider "aws" {
+  region = "us-east-1"
+}
+
+provider "aws" {
+  alias  = "west"
+  region = "us-west-2"
+}
+
+provider "random" {}
+"#;
+
+fn fixture_mirror() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/provider-mirror")
+}
+
+fn offline(mirror: &Path) -> ProviderInstallation {
+    ProviderInstallation {
+        mirrors: vec![FilesystemMirror {
+            path: mirror.to_path_buf(),
+            filter: InstallMethodFilter::default(),
+        }],
+        direct: None,
+        dev_overrides: Default::default(),
+    }
+}
+
+fn locked(version: &str, hashes: &[String]) -> LockFile {
+    let mut lock = LockFile::default();
+    lock.insert(
+        "hashicorp/null",
+        LockedProvider {
+            version: version.to_string(),
+            constraints: None,
+            hashes: hashes.to_vec(),
+        },
+    );
+    lock
+}
+
+// ─── Lock file format ────────────────────────────────────────────────────────
+
+#[test]
+fn test_lock_file_round_trip() {
+    let mut lock = LockFile::default();
+    lock.insert(
+        "hashicorp/aws",
+        LockedProvider {
+            version: "5.70.0".to_string(),
+            constraints: Some(">= 5.0, < 6.0".to_string()),
+            hashes: vec!["zh:bbbb".to_string(), "zh:aaaa".to_string()],
+        },
+    );
+    lock.insert(
+        "registry.example.com/Acme/Widget",
+        LockedProvider {
+            version: "1.0.0".to_string(),
+            constraints: None,
+            hashes: vec![],
+        },
+    );
+
+    let text = lock.to_hcl();
+    assert!(
+        text.contains("provider \"registry.terraform.io/hashicorp/aws\" {"),
+        "{}",
+        text
+    );
+    assert!(text.contains("constraints = \">= 5.0, < 6.0\""), "{}", text);
+    // Hashes are written sorted so regenerating the file is stable
+    assert!(
+        text.find("zh:aaaa").unwrap() < text.find("zh:bbbb").unwrap(),
+        "{}",
+        text
+    );
+
+    let mut parsed = LockFile::parse(&text).unwrap();
+    parsed.providers.values_mut().for_each(|p| p.hashes.sort());
+    lock.providers.values_mut().for_each(|p| p.hashes.sort());
+    assert_eq!(parsed, lock);
+    assert_eq!(parsed.get("acme/widget"), None);
+    assert!(parsed.get("registry.example.com/acme/widget").is_some());
+    assert_eq!(parsed.get("hashicorp/aws").unwrap().version, "5.70.0");
+}
+
+#[test]
+fn test_lock_entry_requires_version() {
+    let err = LockFile::parse("provider \"hashicorp/aws\" {\n  hashes = []\n}\n").unwrap_err();
+    assert!(err.to_string().contains("has no version"), "{}", err);
+}
+
+#[test]
+fn test_package_hash_matches_the_unpacked_archive() {
+    let archive = mock_archive("/some/build");
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("terraform-provider-mock_x5"), "/some/build").unwrap();
+    std::fs::write(dir.path().join("LICENSE"), "MPL-2.0\n").unwrap();
+
+    let hash = package_hash(dir.path()).unwrap();
+    assert!(hash.starts_with("h1:"), "{}", hash);
+    assert_eq!(hash, archive_package_hash(&archive).unwrap());
+
+    std::fs::write(dir.path().join("LICENSE"), "changed").unwrap();
+    assert_ne!(package_hash(dir.path()).unwrap(), hash);
+}
+
+#[test]
+fn test_platform_parse() {
+    assert_eq!(
+        Platform::parse("darwin_arm64").unwrap(),
+        Platform::new("darwin", "arm64")
+    );
+    assert!(Platform::parse("linux").is_err());
+}
+
+// ─── providers lock ──────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_lock_has_an_entry_for_every_configured_provider() {
+    let ws = parse_hcl(CONFIG, Path::new("main.tf")).unwrap();
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::with_registry(cache.path().to_path_buf(), &mock_registry().await);
+    let platforms = [
+        Platform::new("linux", "amd64"),
+        Platform::new("darwin", "arm64"),
+    ];
+
+    let lock = pm.lock_providers(&ws, &platforms).await.unwrap();
+    assert_eq!(
+        lock.providers.keys().collect::<Vec<_>>(),
+        [
+            "registry.terraform.io/hashicorp/aws",
+            "registry.terraform.io/hashicorp/random"
+        ]
+    );
+
+    // 5.71.0 has no darwin_arm64 build, so both platforms settle on 5.70.0
+    let aws = lock.get("hashicorp/aws").unwrap();
+    assert_eq!(aws.version, "5.70.0");
+    assert_eq!(aws.constraints.as_deref(), Some(">= 5.0, < 6.0"));
+    let mut expected = [
+        mock_hashes("aws", "5.70.0", "linux_amd64"),
+        mock_hashes("aws", "5.70.0", "darwin_arm64"),
+    ]
+    .concat();
+    expected.sort();
+    assert_eq!(aws.hashes, expected);
+
+    let random = lock.get("hashicorp/random").unwrap();
+    assert_eq!(random.version, "3.6.0");
+    assert_eq!(random.constraints, None);
+    assert_eq!(random.hashes.len(), 4);
+
+    // The written file parses back to the same entries
+    let dir = TempDir::new().unwrap();
+    lock.write(dir.path()).unwrap();
+    assert_eq!(LockFile::load(dir.path()).unwrap(), Some(lock));
+}
+
+#[tokio::test]
+async fn test_lock_fails_when_no_version_fits_every_platform() {
+    let ws = parse_hcl(CONFIG, Path::new("main.tf")).unwrap();
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::with_registry(cache.path().to_path_buf(), &mock_registry().await);
+    let err = pm
+        .lock_providers(&ws, &[Platform::new("plan9", "amd64")])
+        .await
+        .unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.contains("Failed to lock provider"), "{}", message);
+    assert!(message.contains("plan9_amd64"), "{}", message);
+}
+
+// ─── Installing from a lock ──────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_locked_version_is_installed_from_mirror() {
+    if Platform::current() != Platform::new("linux", "amd64") {
+        // The fixture mirror only carries linux_amd64 packages for 3.x
+        return;
+    }
+    let archive = fixture_mirror()
+        .join("registry.terraform.io/hashicorp/null/terraform-provider-null_3.2.2_linux_amd64.zip");
+    let hash = LockedProvider::zip_hash(&shasum(&std::fs::read(&archive).unwrap()));
+
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()))
+        .with_lock_file(locked("3.2.2", &[hash]));
+    // The constraint alone would pick 3.3.0; the lock pins 3.2.2
+    let path = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap();
+    assert!(
+        path.ends_with("terraform-provider-null_v3.2.2_x5"),
+        "{}",
+        path.display()
+    );
+}
+
+#[tokio::test]
+async fn test_unlocked_archive_is_rejected() {
+    if Platform::current() != Platform::new("linux", "amd64") {
+        return;
+    }
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()))
+        .with_lock_file(locked("3.2.2", &["zh:0000".to_string()]));
+    let err = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("is not in the lock file"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_cached_provider_is_checked_against_the_lock() {
+    if Platform::current() != Platform::new("linux", "amd64") {
+        return;
+    }
+    let archive = std::fs::read(fixture_mirror().join(
+        "registry.terraform.io/hashicorp/null/terraform-provider-null_3.2.2_linux_amd64.zip",
+    ))
+    .unwrap();
+    let hashes = [
+        LockedProvider::zip_hash(&shasum(&archive)),
+        archive_package_hash(&archive).unwrap(),
+    ];
+
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()))
+        .with_lock_file(locked("3.2.2", &hashes));
+    let installed = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap();
+    // The second call is a cache hit, which matches the locked h1: hash
+    let cached = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap();
+    assert_eq!(cached, installed);
+
+    std::fs::write(&installed, "#!/bin/sh\n").unwrap();
+    let err = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("is not in the lock file"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_unpacked_mirror_is_checked_against_the_lock() {
+    if Platform::current() != Platform::new("linux", "amd64") {
+        return;
+    }
+    let package = fixture_mirror().join("registry.terraform.io/hashicorp/null/3.3.0/linux_amd64");
+    let cache = TempDir::new().unwrap();
+
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()))
+        .with_lock_file(locked("3.3.0", &["zh:0000".to_string()]));
+    let err = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("is not in the lock file"), "{}", err);
+
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()))
+        .with_lock_file(locked("3.3.0", &[package_hash(&package).unwrap()]));
+    let path = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap();
+    assert!(path.starts_with(&package), "{}", path.display());
+}
+
+#[tokio::test]
+async fn test_lock_conflicting_with_constraint_is_an_error() {
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()))
+        .with_lock_file(locked("3.2.2", &[]));
+    let err = pm
+        .ensure_provider("hashicorp/null", ">= 4.0")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Locked version 3.2.2 of hashicorp/null does not match '>= 4.0'"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,98 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use oxid::output::log::{json_subscriber, LogOptions};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+/// In-memory log sink shared between the subscriber and the test.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Buffer {
+    type Writer = Buffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn capture(filter: &str, emit: impl FnOnce()) -> Vec<serde_json::Value> {
+    let buffer = Buffer::default();
+    let subscriber = json_subscriber(EnvFilter::new(filter), buffer.clone());
+    tracing::subscriber::with_default(subscriber, emit);
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    output
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("log line is not valid JSON"))
+        .collect()
+}
+
+// ─── JSON log format ─────────────────────────────────────────────────────────
+
+#[test]
+fn test_json_log_lines_are_parseable() {
+    let lines = capture("debug", || {
+        tracing::warn!(address = "aws_vpc.main", attempt = 2, "Retrying \"apply\"");
+        tracing::warn!(target: "provider_stderr", "[WARN] deprecated argument");
+        tracing::debug!(ok = true, "done");
+    });
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["level"], "WARN");
+    assert_eq!(lines[0]["fields"]["message"], "Retrying \"apply\"");
+    assert_eq!(lines[0]["fields"]["address"], "aws_vpc.main");
+    assert_eq!(lines[0]["fields"]["attempt"], 2);
+    assert!(lines[0]["timestamp"].is_string());
+
+    assert_eq!(lines[1]["target"], "provider_stderr");
+    assert_eq!(lines[2]["level"], "DEBUG");
+    assert_eq!(lines[2]["fields"]["ok"], true);
+}
+
+#[test]
+fn test_json_log_provider_target_can_be_filtered() {
+    let lines = capture("debug,provider_stderr=off", || {
+        tracing::warn!(target: "provider_stderr", "noisy provider");
+        tracing::warn!("kept");
+    });
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["fields"]["message"], "kept");
+}
+
+// ─── Log level ───────────────────────────────────────────────────────────────
+
+#[test]
+fn test_log_level_overrides_verbose() {
+    let options = LogOptions {
+        verbose: true,
+        level: Some("error".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(options.filter().unwrap().to_string(), "error");
+
+    let options = LogOptions {
+        verbose: true,
+        ..Default::default()
+    };
+    assert_eq!(options.filter().unwrap().to_string(), "debug");
+    assert_eq!(LogOptions::default().filter().unwrap().to_string(), "warn");
+
+    let options = LogOptions {
+        level: Some("oxid=nope=x".to_string()),
+        ..Default::default()
+    };
+    assert!(options.filter().is_err());
+}
@@ -0,0 +1,284 @@
+use std::path::{Path, PathBuf};
+
+use oxid::provider::manager::{ProviderManager, ProviderResolution};
+use oxid::provider::mirror::{
+    FilesystemMirror, InstallMethodFilter, MirrorPackage, ProviderInstallation,
+};
+use oxid::provider::registry::Platform;
+use oxid::provider::version::VersionConstraint;
+use tempfile::TempDir;
+
+fn fixture_mirror() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/provider-mirror")
+}
+
+/// Mirror-only installation: no network access.
+fn offline(mirror: &Path) -> ProviderInstallation {
+    ProviderInstallation {
+        mirrors: vec![FilesystemMirror {
+            path: mirror.to_path_buf(),
+            filter: InstallMethodFilter::default(),
+        }],
+        direct: None,
+        dev_overrides: Default::default(),
+    }
+}
+
+fn constraint(s: &str) -> VersionConstraint {
+    VersionConstraint::parse(s).unwrap()
+}
+
+// ─── Configuration ───────────────────────────────────────────────────────────
+
+#[test]
+fn test_parse_provider_installation() {
+    let installation = ProviderInstallation::parse(
+        r#"
+provider_installation {
+  filesystem_mirror {
+    path    = "/opt/oxid/providers"
+    include = ["hashicorp/*"]
+  }
+  direct {
+    exclude = ["registry.terraform.io/hashicorp/*"]
+  }
+}
+"#,
+    )
+    .unwrap();
+    assert_eq!(installation.mirrors.len(), 1);
+    assert_eq!(
+        installation.mirrors[0].path,
+        Path::new("/opt/oxid/providers")
+    );
+    assert!(installation.mirrors[0].filter.allows("hashicorp/null"));
+    assert!(!installation.mirrors[0].filter.allows("integrations/github"));
+
+    assert!(!installation.allows_network("hashicorp/aws"));
+    assert!(installation.allows_network("integrations/github"));
+}
+
+#[test]
+fn test_mirror_without_direct_disables_network() {
+    let installation = ProviderInstallation::parse(
+        r#"
+provider_installation {
+  filesystem_mirror {
+    path = "/opt/oxid/providers"
+  }
+}
+"#,
+    )
+    .unwrap();
+    assert!(!installation.allows_network("hashicorp/aws"));
+}
+
+#[test]
+fn test_no_installation_block_allows_network() {
+    let installation = ProviderInstallation::parse("plugin_cache_dir = \"/tmp\"\n").unwrap();
+    assert!(installation.mirrors.is_empty());
+    assert!(installation.allows_network("hashicorp/aws"));
+}
+
+#[test]
+fn test_unknown_installation_method_is_rejected() {
+    let err = ProviderInstallation::parse(
+        r#"
+provider_installation {
+  network_mirror {
+    url = "https://mirror.example.com/"
+  }
+}
+"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("network_mirror"), "{}", err);
+}
+
+// ─── Mirror lookup ───────────────────────────────────────────────────────────
+
+#[test]
+fn test_finds_highest_matching_version_across_layouts() {
+    let installation = offline(&fixture_mirror());
+    let linux = Platform::new("linux", "amd64");
+
+    let (version, package) = installation
+        .find("hashicorp/null", &constraint(">= 3.0"), &linux)
+        .unwrap()
+        .unwrap();
+    assert_eq!(version, "3.3.0");
+    assert!(matches!(package, MirrorPackage::Unpacked(_)));
+
+    let (version, package) = installation
+        .find("hashicorp/null", &constraint("~> 3.2.0"), &linux)
+        .unwrap()
+        .unwrap();
+    assert_eq!(version, "3.2.2");
+    assert!(
+        matches!(package, MirrorPackage::Packed(ref p) if p.ends_with("terraform-provider-null_3.2.2_linux_amd64.zip"))
+    );
+}
+
+#[test]
+fn test_lookup_respects_platform_and_constraint() {
+    let installation = offline(&fixture_mirror());
+    let mac = Platform::new("darwin", "arm64");
+    let (version, _) = installation
+        .find("hashicorp/null", &VersionConstraint::any(), &mac)
+        .unwrap()
+        .unwrap();
+    assert_eq!(version, "4.0.0");
+
+    let linux = Platform::new("linux", "amd64");
+    assert!(installation
+        .find("hashicorp/null", &constraint(">= 4.0"), &linux)
+        .unwrap()
+        .is_none());
+    assert!(installation
+        .find("hashicorp/aws", &VersionConstraint::any(), &linux)
+        .unwrap()
+        .is_none());
+}
+
+// ─── ensure_provider ─────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_ensure_provider_installs_from_mirror() {
+    if Platform::current() != Platform::new("linux", "amd64") {
+        // The fixture mirror only carries linux_amd64 packages for 3.x
+        return;
+    }
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()));
+
+    let unpacked = pm
+        .ensure_provider("hashicorp/null", ">= 3.0")
+        .await
+        .unwrap();
+    assert!(unpacked.ends_with("3.3.0/linux_amd64/terraform-provider-null_v3.3.0_x5"));
+
+    // Packed archives are extracted into the provider cache
+    let packed = pm.ensure_provider("hashicorp/null", "3.2.1").await.unwrap();
+    assert!(packed.starts_with(cache.path()));
+    assert!(packed.ends_with("terraform-provider-null_v3.2.1_x5"));
+    assert!(packed.exists());
+}
+
+#[tokio::test]
+async fn test_resolve_provider_writes_nothing() {
+    if Platform::current() != Platform::new("linux", "amd64") {
+        return;
+    }
+    let dir = TempDir::new().unwrap();
+    let cache = dir.path().join("providers");
+    let pm = ProviderManager::new(cache.clone()).with_installation(offline(&fixture_mirror()));
+
+    // A packed archive would be extracted into the cache by ensure_provider
+    let resolution = pm
+        .resolve_provider("hashicorp/null", "3.2.1")
+        .await
+        .unwrap();
+    match resolution {
+        ProviderResolution::Mirror { version, package } => {
+            assert_eq!(version, "3.2.1");
+            assert_eq!(
+                package,
+                MirrorPackage::Packed(
+                    fixture_mirror().join(
+                        "registry.terraform.io/hashicorp/null/terraform-provider-null_3.2.1_linux_amd64.zip"
+                    )
+                )
+            );
+        }
+        other => panic!("expected a mirror package, got {:?}", other),
+    }
+    assert!(!cache.exists());
+
+    // Once installed, the same lookup is satisfied by the cache
+    pm.ensure_provider("hashicorp/null", "3.2.1").await.unwrap();
+    assert!(matches!(
+        pm.resolve_provider("hashicorp/null", "3.2.1")
+            .await
+            .unwrap(),
+        ProviderResolution::Cached(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_ensure_provider_fails_offline_when_not_mirrored() {
+    let cache = TempDir::new().unwrap();
+    let pm = ProviderManager::new(cache.path().to_path_buf())
+        .with_installation(offline(&fixture_mirror()));
+    let err = pm
+        .ensure_provider("hashicorp/aws", ">= 5.0")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("hashicorp/aws"), "{}", err);
+    assert!(err.contains("network installation is disabled"), "{}", err);
+}
+
+// ─── Dev overrides ───────────────────────────────────────────────────────────
+
+#[test]
+fn test_parse_dev_overrides() {
+    let installation = ProviderInstallation::parse(
+        r#"
+dev_overrides = {
+  "registry.terraform.io/hashicorp/foo" = "/home/dev/bin/terraform-provider-foo"
+  "acme/Widget"                          = "/tmp/terraform-provider-widget"
+}
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        installation.dev_override("hashicorp/foo"),
+        Some(Path::new("/home/dev/bin/terraform-provider-foo"))
+    );
+    assert_eq!(
+        installation.dev_override("registry.terraform.io/acme/widget"),
+        Some(Path::new("/tmp/terraform-provider-widget"))
+    );
+    assert_eq!(installation.dev_override("hashicorp/aws"), None);
+    // Overrides alone leave network installation enabled
+    assert!(installation.allows_network("hashicorp/aws"));
+}
+
+#[tokio::test]
+async fn test_ensure_provider_uses_dev_override_verbatim() {
+    let dir = TempDir::new().unwrap();
+    let binary = dir.path().join("terraform-provider-foo");
+    std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+
+    // Offline with an empty mirror: only the override can satisfy the lookup
+    let mut installation = offline(dir.path());
+    installation.add_dev_override("hashicorp/foo", binary.clone());
+    let pm = ProviderManager::new(dir.path().join("cache")).with_installation(installation);
+
+    let path = pm.ensure_provider("hashicorp/foo", "~> 9.9").await.unwrap();
+    assert_eq!(path, binary);
+    let aliased = pm
+        .ensure_provider("registry.terraform.io/hashicorp/foo.west", "")
+        .await
+        .unwrap();
+    assert_eq!(aliased, binary);
+}
+
+#[tokio::test]
+async fn test_missing_dev_override_binary_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let mut installation = ProviderInstallation::default();
+    installation.add_dev_override("hashicorp/foo", dir.path().join("missing"));
+    let pm = ProviderManager::new(dir.path().join("cache")).with_installation(installation);
+    let err = pm
+        .ensure_provider("hashicorp/foo", "")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("dev_overrides binary for hashicorp/foo does not exist"),
+        "{}",
+        err
+    );
+}
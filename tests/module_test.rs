@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use oxid::config::loader::load_workspace;
+use oxid::config::types::*;
+use oxid::dag::resource_graph::build_resource_dag;
+use oxid::executor::engine::{
+    attributes_to_json, build_provider_map, build_variable_defaults, eval_expression, EvalContext,
+};
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+fn load_fixture(name: &str) -> anyhow::Result<WorkspaceConfig> {
+    load_workspace(Path::new(&format!("tests/fixtures/modules/{}", name)))
+}
+
+fn find<'a>(ws: &'a WorkspaceConfig, module_path: &[&str], name: &str) -> &'a ResourceConfig {
+    ws.resources
+        .iter()
+        .find(|r| r.name == name && r.module_path == module_path)
+        .unwrap_or_else(|| panic!("resource '{}' in {:?} should exist", name, module_path))
+}
+
+// ─── Local module expansion ──────────────────────────────────────────────────
+
+#[test]
+fn test_module_resources_are_namespaced() {
+    let ws = load_fixture("local").unwrap();
+
+    // consumer (root) + random_pet.this + null_resource.after (module.pet)
+    assert_eq!(ws.resources.len(), 3);
+    let pet = find(&ws, &["pet"], "this");
+    assert_eq!(pet.resource_type, "random_pet");
+    assert_eq!(pet.module_prefix(), "module.pet.");
+
+    let (_, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    assert!(node_map.contains_key("module.pet.random_pet.this"));
+    assert!(node_map.contains_key("module.pet.null_resource.after"));
+    assert!(node_map.contains_key("null_resource.consumer"));
+}
+
+#[test]
+fn test_module_inputs_are_substituted() {
+    let ws = load_fixture("local").unwrap();
+    let pet = find(&ws, &["pet"], "this");
+
+    // Explicit input wins over the module default
+    match &pet.attributes["length"] {
+        Expression::Literal(Value::Int(n)) => assert_eq!(*n, 3),
+        other => panic!("expected literal 3, got {:?}", other),
+    }
+
+    // Input wired from another module's output, which in turn uses a root variable
+    match &pet.attributes["prefix"] {
+        Expression::Template(parts) => match &parts[0] {
+            TemplatePart::Interpolation(expr) => match expr.as_ref() {
+                Expression::Reference(parts) => assert_eq!(parts, &["var", "prefix"]),
+                other => panic!("expected var.prefix, got {:?}", other),
+            },
+            other => panic!("expected interpolation, got {:?}", other),
+        },
+        other => panic!("expected template, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_module_references_create_dag_edges() {
+    let ws = load_fixture("local").unwrap();
+    let (graph, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    // Intra-module reference
+    assert!(graph
+        .find_edge(
+            node_map["module.pet.random_pet.this"],
+            node_map["module.pet.null_resource.after"]
+        )
+        .is_some());
+    // Root resource consuming a module output
+    assert!(graph
+        .find_edge(
+            node_map["module.pet.random_pet.this"],
+            node_map["null_resource.consumer"]
+        )
+        .is_some());
+}
+
+#[test]
+fn test_module_output_reference_is_rewritten() {
+    let ws = load_fixture("local").unwrap();
+    let output = ws.outputs.iter().find(|o| o.name == "pet_id").unwrap();
+    match &output.value {
+        Expression::Reference(parts) => {
+            assert_eq!(parts, &["module", "pet", "random_pet", "this", "id"])
+        }
+        other => panic!("expected reference, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_module_missing_required_variable() {
+    let err = load_fixture("missing-var").unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("requires variable 'prefix'"),
+        "unexpected error: {:#}",
+        err
+    );
+}
+
+#[test]
+fn test_module_locals_are_scoped_to_the_module() {
+    let ws = load_fixture("locals").unwrap();
+    let ctx = EvalContext::plan_only(build_variable_defaults(&ws));
+
+    let vpc = find(&ws, &["net"], "main");
+    let config = attributes_to_json(&vpc.attributes, &ctx).unwrap();
+    assert_eq!(config["tags"], serde_json::json!({"Name": "prod-net"}));
+
+    // A local naming a module resource becomes a reference to it
+    let (graph, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    assert!(graph
+        .find_edge(
+            node_map["module.net.aws_vpc.main"],
+            node_map["module.net.aws_subnet.a"]
+        )
+        .is_some());
+
+    // The root's local of the same name is untouched
+    let output = ws.outputs.iter().find(|o| o.name == "subnet_name").unwrap();
+    assert_eq!(eval_expression(&output.value, &ctx).unwrap(), "prod-net");
+    assert!(ws.locals.contains_key("name"));
+}
+
+#[test]
+fn test_module_count_is_rejected() {
+    let err = load_fixture("count").unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("Module 'net': count on module blocks is not supported yet"),
+        "unexpected error: {:#}",
+        err
+    );
+}
+
+// ─── depends_on ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_depends_on_links_data_sources_and_modules() {
+    let ws = load_fixture("depends-on").unwrap();
+    let (graph, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    for inner in [
+        "module.pet.random_pet.this",
+        "module.pet.null_resource.after",
+    ] {
+        assert!(
+            graph
+                .find_edge(node_map[inner], node_map["null_resource.after_pet"])
+                .is_some(),
+            "{} should precede null_resource.after_pet",
+            inner
+        );
+    }
+    assert!(graph
+        .find_edge(
+            node_map["data.external.ready"],
+            node_map["null_resource.after_data"]
+        )
+        .is_some());
+}
+
+#[test]
+fn test_depends_on_module_prefix_resolves_in_the_graph() {
+    // A workspace whose module resources are already namespaced, with a
+    // depends_on that still names the module itself
+    let hcl = r#"
+resource "aws_vpc" "main" {
+  cidr_block = "10.0.0.0/16"
+}
+
+resource "aws_instance" "web" {
+  depends_on = [module.network]
+}
+"#;
+    let mut ws = oxid::hcl::parser::parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    ws.resources[0].module_path = vec!["network".to_string()];
+    let (graph, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    assert!(graph
+        .find_edge(
+            node_map["module.network.aws_vpc.main"],
+            node_map["aws_instance.web"]
+        )
+        .is_some());
+}
@@ -0,0 +1,151 @@
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command};
+
+use oxid::provider::pids::{is_running, start_time, PidRegistry, ProviderProcess, ReapOutcome};
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+fn sleeper() -> Child {
+    Command::new("sleep").arg("60").spawn().unwrap()
+}
+
+/// The id of a process that has exited and been collected.
+fn dead_pid() -> u32 {
+    let mut child = Command::new("true").spawn().unwrap();
+    child.wait().unwrap();
+    child.id()
+}
+
+fn process(pid: u32, provider: &str, owner: u32) -> ProviderProcess {
+    ProviderProcess {
+        pid,
+        provider: provider.to_string(),
+        owner,
+        started: start_time(pid),
+    }
+}
+
+// ─── Registry ────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_registry_add_replace_and_clear() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = PidRegistry::new(dir.path());
+    assert!(registry.load().unwrap().is_empty());
+
+    registry.add(process(100, "hashicorp/aws", 1)).unwrap();
+    registry.add(process(101, "hashicorp/aws.west", 1)).unwrap();
+    registry.add(process(200, "hashicorp/aws", 2)).unwrap();
+    // A restarted provider replaces the run's earlier process
+    registry.add(process(102, "hashicorp/aws", 1)).unwrap();
+    let pids: Vec<u32> = registry.load().unwrap().iter().map(|p| p.pid).collect();
+    assert_eq!(pids, [101, 200, 102]);
+
+    registry.remove(101).unwrap();
+    registry.clear_owner(1).unwrap();
+    assert_eq!(registry.load().unwrap(), [process(200, "hashicorp/aws", 2)]);
+
+    registry.clear_owner(2).unwrap();
+    assert!(!registry.path().exists());
+}
+
+#[test]
+fn test_concurrent_registries_keep_every_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    std::thread::scope(|scope| {
+        for owner in 1..=8 {
+            let path = dir.path();
+            scope.spawn(move || {
+                let registry = PidRegistry::new(path);
+                for pid in 0..10 {
+                    let provider = format!("hashicorp/p{}", pid);
+                    registry
+                        .add(process(owner * 100 + pid, &provider, owner))
+                        .unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(PidRegistry::new(dir.path()).load().unwrap().len(), 80);
+}
+
+// ─── Reap ────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_reap_terminates_processes_of_dead_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut orphan = sleeper();
+    let owner = dead_pid();
+    let exited = dead_pid();
+    std::fs::write(
+        dir.path().join("pids.json"),
+        serde_json::to_string(&[
+            process(orphan.id(), "hashicorp/aws", owner),
+            process(exited, "hashicorp/null", owner),
+        ])
+        .unwrap(),
+    )
+    .unwrap();
+
+    let reaped = PidRegistry::new(dir.path()).reap().unwrap();
+    assert_eq!(
+        reaped
+            .iter()
+            .map(|(p, outcome)| (p.provider.as_str(), *outcome))
+            .collect::<Vec<_>>(),
+        [
+            ("hashicorp/aws", ReapOutcome::Terminated),
+            ("hashicorp/null", ReapOutcome::Exited),
+        ]
+    );
+    assert_eq!(orphan.wait().unwrap().signal(), Some(libc::SIGTERM));
+    assert!(!dir.path().join("pids.json").exists());
+}
+
+#[test]
+fn test_reap_leaves_processes_of_running_oxid_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut owner = sleeper();
+    let mut provider = sleeper();
+    let registry = PidRegistry::new(dir.path());
+    registry
+        .add(process(provider.id(), "hashicorp/aws", owner.id()))
+        .unwrap();
+
+    let reaped = registry.reap().unwrap();
+    assert_eq!(reaped.len(), 1);
+    assert_eq!(reaped[0].1, ReapOutcome::InUse);
+    assert!(is_running(provider.id()));
+    assert_eq!(registry.load().unwrap().len(), 1);
+
+    for child in [&mut owner, &mut provider] {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
+
+#[test]
+fn test_reap_leaves_reused_pids_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut unrelated = sleeper();
+    let mut recorded = process(unrelated.id(), "hashicorp/aws", dead_pid());
+    // The provider that had this pid started at another time
+    recorded.started = recorded.started.map(|t| t + 1);
+    let registry = PidRegistry::new(dir.path());
+    registry.add(recorded).unwrap();
+
+    let reaped = registry.reap().unwrap();
+    assert_eq!(reaped[0].1, ReapOutcome::Reused);
+    assert!(is_running(unrelated.id()));
+    assert!(registry.load().unwrap().is_empty());
+
+    unrelated.kill().unwrap();
+    unrelated.wait().unwrap();
+}
+
+#[test]
+fn test_process_groups_are_never_signalled() {
+    assert!(!is_running(0));
+    assert!(!is_running(1));
+    assert!(!is_running(u32::MAX));
+}
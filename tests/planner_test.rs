@@ -0,0 +1,356 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use oxid::config::types::WorkspaceConfig;
+use oxid::dag::resource_graph::{build_resource_dag, to_dot_with_actions, ResourceGraph};
+use oxid::executor::engine::{
+    build_provider_map, build_variable_defaults, destroy_graph, destroy_steps, DestroyPlan,
+    DestroyStep, PlanSummary, PlannedChange, ResourceAction,
+};
+use oxid::hcl::parser::parse_hcl;
+use oxid::output::formatter::{format_destroy_plan, format_plan_waves};
+use oxid::planner::graph::plan_graph;
+use oxid::planner::waves::{plan_waves, Wave};
+use oxid::state::models::ResourceState;
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+/// `vpc` fans out to two subnets that both feed `lb`, which `dns` follows.
+fn diamond_workspace() -> WorkspaceConfig {
+    let hcl = r#"
+resource "aws_vpc" "main" {
+  cidr_block = "10.0.0.0/16"
+}
+
+resource "aws_subnet" "a" {
+  vpc_id = aws_vpc.main.id
+}
+
+resource "aws_subnet" "b" {
+  vpc_id = aws_vpc.main.id
+}
+
+resource "aws_lb" "web" {
+  subnets = [aws_subnet.a.id, aws_subnet.b.id]
+}
+
+resource "aws_route53_record" "web" {
+  alias = aws_lb.web.dns_name
+}
+
+resource "aws_s3_bucket" "logs" {
+  bucket = "logs"
+}
+
+output "lb_dns" {
+  value = aws_lb.web.dns_name
+}
+"#;
+    parse_hcl(hcl, Path::new("main.tf")).unwrap()
+}
+
+fn diamond_graph() -> ResourceGraph {
+    let ws = diamond_workspace();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+    graph
+}
+
+/// AWS records in state at `addresses`, each with its name as `id`.
+fn state_records(addresses: &[&str]) -> Vec<ResourceState> {
+    addresses
+        .iter()
+        .map(|address| {
+            let (resource_type, name) = address.split_once('.').unwrap();
+            let mut record = ResourceState::new("ws", resource_type, name, address);
+            record.provider_source = "hashicorp/aws".to_string();
+            record.attributes_json = format!(r#"{{"id":"{}"}}"#, name);
+            record
+        })
+        .collect()
+}
+
+/// The destroy order of `records` against the diamond configuration.
+fn diamond_destroy_steps(records: &[ResourceState]) -> Vec<DestroyStep> {
+    let graph = destroy_graph(&diamond_graph(), &diamond_workspace(), records).unwrap();
+    let in_state: HashSet<String> = records.iter().map(|r| r.address.clone()).collect();
+    destroy_steps(&graph, &in_state).unwrap()
+}
+
+fn plan(changes: &[(&str, ResourceAction)]) -> PlanSummary {
+    PlanSummary {
+        changes: changes
+            .iter()
+            .map(|(address, action)| PlannedChange {
+                address: address.to_string(),
+                action: action.clone(),
+                resource_type: address.split('.').next().unwrap().to_string(),
+                provider_source: "hashicorp/aws".to_string(),
+                planned_state: None,
+                prior_state: None,
+                user_config: None,
+                requires_replace: vec![],
+                replace_reason: None,
+                planned_private: vec![],
+            })
+            .collect(),
+        outputs: vec![],
+        creates: 0,
+        updates: 0,
+        deletes: 0,
+        replaces: 0,
+        no_ops: 0,
+        state_updates: Default::default(),
+    }
+}
+
+fn wave(number: usize, addresses: &[&str]) -> Wave {
+    Wave {
+        number,
+        addresses: addresses.iter().map(|a| a.to_string()).collect(),
+    }
+}
+
+// ─── Waves ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_diamond_groups_into_waves() {
+    let graph = diamond_graph();
+    let plan = plan(&[
+        ("aws_vpc.main", ResourceAction::Create),
+        ("aws_subnet.a", ResourceAction::Create),
+        ("aws_subnet.b", ResourceAction::Create),
+        ("aws_lb.web", ResourceAction::Create),
+        ("aws_route53_record.web", ResourceAction::Create),
+        ("aws_s3_bucket.logs", ResourceAction::Create),
+    ]);
+    assert_eq!(
+        plan_waves(&graph, &plan).unwrap(),
+        vec![
+            wave(1, &["aws_s3_bucket.logs", "aws_vpc.main"]),
+            wave(2, &["aws_subnet.a", "aws_subnet.b"]),
+            wave(3, &["aws_lb.web"]),
+            wave(4, &["aws_route53_record.web"]),
+        ]
+    );
+}
+
+#[test]
+fn test_unchanged_resources_do_not_start_a_wave() {
+    let graph = diamond_graph();
+    // Only the vpc and the record change; the diamond in between is a no-op
+    let plan = plan(&[
+        ("aws_vpc.main", ResourceAction::Update),
+        ("aws_subnet.a", ResourceAction::NoOp),
+        ("aws_subnet.b", ResourceAction::NoOp),
+        ("aws_lb.web", ResourceAction::NoOp),
+        ("aws_route53_record.web", ResourceAction::Replace),
+        ("aws_s3_bucket.logs", ResourceAction::NoOp),
+    ]);
+    assert_eq!(
+        plan_waves(&graph, &plan).unwrap(),
+        vec![
+            wave(1, &["aws_vpc.main"]),
+            wave(2, &["aws_route53_record.web"]),
+        ]
+    );
+}
+
+#[test]
+fn test_changes_outside_the_graph_run_first() {
+    let graph = diamond_graph();
+    let plan = plan(&[
+        ("aws_subnet.a", ResourceAction::Update),
+        ("aws_instance.removed", ResourceAction::Delete),
+    ]);
+    assert_eq!(
+        plan_waves(&graph, &plan).unwrap(),
+        vec![wave(1, &["aws_instance.removed", "aws_subnet.a"])]
+    );
+    assert!(plan_waves(&graph, &self::plan(&[])).unwrap().is_empty());
+}
+
+#[test]
+fn test_format_plan_waves() {
+    colored::control::set_override(false);
+    let text = format_plan_waves(&[
+        wave(1, &["aws_s3_bucket.logs", "aws_vpc.main"]),
+        wave(2, &["aws_subnet.a"]),
+    ]);
+    assert!(text.contains("Wave 1 (2 in parallel)"), "{}", text);
+    assert!(text.contains("    aws_vpc.main\n"), "{}", text);
+    assert!(text.contains("Wave 2 (1 in parallel)"), "{}", text);
+    assert!(
+        text.contains("2 waves, at most 2 changes at once."),
+        "{}",
+        text
+    );
+}
+
+// ─── Plan graph ──────────────────────────────────────────────────────────────
+
+#[test]
+fn test_plan_graph_keeps_changes_and_their_dependencies() {
+    let graph = diamond_graph();
+    let plan = plan(&[
+        ("aws_vpc.main", ResourceAction::NoOp),
+        ("aws_subnet.a", ResourceAction::Update),
+        ("aws_subnet.b", ResourceAction::NoOp),
+        ("aws_lb.web", ResourceAction::Replace),
+        ("aws_route53_record.web", ResourceAction::NoOp),
+        ("aws_s3_bucket.logs", ResourceAction::NoOp),
+    ]);
+    let (changing, actions) = plan_graph(&graph, &plan);
+
+    let mut addresses: Vec<&str> = changing.node_weights().map(|n| n.address()).collect();
+    addresses.sort();
+    // The record and bucket are unchanged and nothing changing depends on them
+    assert_eq!(
+        addresses,
+        ["aws_lb.web", "aws_subnet.a", "aws_subnet.b", "aws_vpc.main"]
+    );
+    assert_eq!(changing.edge_count(), 4);
+    assert_eq!(actions["aws_lb.web"], ResourceAction::Replace);
+    assert_eq!(actions["aws_vpc.main"], ResourceAction::NoOp);
+
+    let dot = to_dot_with_actions(&changing, &actions);
+    assert!(!dot.contains("aws_s3_bucket.logs"), "{}", dot);
+    assert!(!dot.contains("aws_route53_record.web"), "{}", dot);
+    assert!(
+        dot.contains("aws_lb.web\\naws_lb\", fillcolor=\"#f5b041\""),
+        "{}",
+        dot
+    );
+    assert!(
+        dot.contains("aws_subnet.a\\naws_subnet\", fillcolor=\"#f2e27a\""),
+        "{}",
+        dot
+    );
+    assert!(
+        dot.contains("aws_vpc.main\\naws_vpc\", fillcolor=\"#e8e8e8\""),
+        "{}",
+        dot
+    );
+}
+
+#[test]
+fn test_plan_graph_without_changes_is_empty() {
+    let graph = diamond_graph();
+    let plan = plan(&[
+        ("aws_vpc.main", ResourceAction::NoOp),
+        ("aws_s3_bucket.logs", ResourceAction::NoOp),
+    ]);
+    let (changing, actions) = plan_graph(&graph, &plan);
+    assert_eq!(changing.node_count(), 0);
+    assert!(actions.is_empty());
+}
+
+// ─── Destroy plan ────────────────────────────────────────────────────────────
+
+#[test]
+fn test_destroy_plan_lists_dependents_first() {
+    let steps = diamond_destroy_steps(&state_records(&[
+        "aws_vpc.main",
+        "aws_subnet.a",
+        "aws_subnet.b",
+        "aws_lb.web",
+        "aws_route53_record.web",
+    ]));
+    let order: Vec<&str> = steps.iter().map(|s| s.address.as_str()).collect();
+    assert_eq!(order.len(), 5, "{:?}", order);
+    let position = |address: &str| order.iter().position(|a| *a == address).unwrap();
+    assert!(position("aws_route53_record.web") < position("aws_lb.web"));
+    assert!(position("aws_lb.web") < position("aws_subnet.a"));
+    assert!(position("aws_lb.web") < position("aws_subnet.b"));
+    assert!(position("aws_subnet.a") < position("aws_vpc.main"));
+    assert!(position("aws_subnet.b") < position("aws_vpc.main"));
+
+    // The bucket isn't in state, so there is nothing to destroy for it
+    assert!(!order.contains(&"aws_s3_bucket.logs"));
+
+    let vpc = &steps[position("aws_vpc.main")];
+    assert_eq!(vpc.dependents, ["aws_subnet.a", "aws_subnet.b"]);
+    assert!(steps[position("aws_route53_record.web")]
+        .dependents
+        .is_empty());
+}
+
+#[test]
+fn test_destroy_plan_includes_resources_only_in_state() {
+    let steps = diamond_destroy_steps(&state_records(&["aws_vpc.main", "aws_eip.legacy"]));
+    let legacy = steps
+        .iter()
+        .find(|s| s.address == "aws_eip.legacy")
+        .expect("state-only resource is planned");
+    assert_eq!(legacy.resource_type, "aws_eip");
+    assert_eq!(steps.len(), 2);
+}
+
+#[test]
+fn test_format_destroy_plan_reports_refusals() {
+    colored::control::set_override(false);
+    let step = |address: &str, error: Option<&str>| DestroyStep {
+        address: address.to_string(),
+        resource_type: address.split('.').next().unwrap().to_string(),
+        dependents: vec![],
+        error: error.map(str::to_string),
+    };
+
+    let plan = DestroyPlan {
+        steps: vec![step("aws_lb.web", None), step("aws_vpc.main", None)],
+        ..Default::default()
+    };
+    let text = format_destroy_plan(&plan);
+    assert!(
+        text.find("- aws_lb.web").unwrap() < text.find("- aws_vpc.main").unwrap(),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("This will destroy 2 resource(s)."),
+        "{}",
+        text
+    );
+    assert!(!plan.has_errors());
+
+    let plan = DestroyPlan {
+        steps: vec![
+            step("aws_lb.web", None),
+            step("aws_s3_bucket.logs", Some("bucket is not empty")),
+        ],
+        ..Default::default()
+    };
+    let text = format_destroy_plan(&plan);
+    assert!(text.contains("Error: bucket is not empty"), "{}", text);
+    assert!(
+        text.contains("would refuse to destroy 1 of 2 resource(s)"),
+        "{}",
+        text
+    );
+    assert!(plan.has_errors());
+}
+
+#[test]
+fn test_destroy_plan_summary_deletes_every_resource_in_state() {
+    let records = state_records(&["aws_vpc.main", "aws_subnet.a", "aws_lb.web"]);
+
+    let plan = DestroyPlan {
+        steps: diamond_destroy_steps(&records),
+        ..Default::default()
+    }
+    .to_plan_summary(&records);
+
+    assert_eq!(plan.deletes, records.len());
+    assert_eq!(plan.changes.len(), records.len());
+    assert_eq!(plan.to_string(), "Plan: 3 to destroy.");
+    assert!(plan
+        .changes
+        .iter()
+        .all(|c| c.action == ResourceAction::Delete && c.planned_state.is_none()));
+    let order: Vec<&str> = plan.changes.iter().map(|c| c.address.as_str()).collect();
+    assert_eq!(order, ["aws_lb.web", "aws_subnet.a", "aws_vpc.main"]);
+    assert_eq!(
+        plan.changes[0].prior_state,
+        Some(serde_json::json!({"id": "web"}))
+    );
+}
@@ -0,0 +1,619 @@
+use std::collections::HashMap;
+
+use oxid::config::types::{Expression, Value};
+use oxid::executor::engine::{attributes_to_json, determine_action, EvalContext, ResourceAction};
+use oxid::provider::mtls::ClientCertificate;
+use oxid::provider::protocol::{
+    decode_msgpack, encode_msgpack, is_transport_error, is_unknown, matches_known, parse_handshake,
+    provider_endpoint, unknown_value, ProviderConnection, ProviderStartOptions,
+};
+use serde_json::json;
+
+/// cty's encoding of an unknown value: msgpack extension type 0.
+fn cty_unknown() -> rmpv::Value {
+    rmpv::Value::Ext(0, vec![0])
+}
+
+fn encode(value: &rmpv::Value) -> Vec<u8> {
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, value).unwrap();
+    buf
+}
+
+/// A planned `aws_instance` state as a provider would send it: `id` and
+/// `arn` are unknown, `tags.Name` is known, `tags.Owner` is unknown.
+fn planned_payload() -> Vec<u8> {
+    encode(&rmpv::Value::Map(vec![
+        ("id".into(), cty_unknown()),
+        ("arn".into(), cty_unknown()),
+        ("ami".into(), "ami-123".into()),
+        ("monitoring".into(), rmpv::Value::Nil),
+        (
+            "tags".into(),
+            rmpv::Value::Map(vec![
+                ("Name".into(), "web".into()),
+                ("Owner".into(), cty_unknown()),
+            ]),
+        ),
+    ]))
+}
+
+// ─── Decoding ────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_unknown_decodes_to_sentinel_not_null() {
+    let planned = decode_msgpack(&planned_payload()).unwrap();
+    assert!(is_unknown(&planned["id"]));
+    assert!(is_unknown(&planned["tags"]["Owner"]));
+    assert_eq!(planned["ami"], json!("ami-123"));
+    assert!(planned["monitoring"].is_null());
+    assert!(!is_unknown(&planned["monitoring"]));
+}
+
+#[test]
+fn test_other_extension_types_decode_to_null() {
+    let value = decode_msgpack(&encode(&rmpv::Value::Ext(1, vec![1, 2]))).unwrap();
+    assert!(value.is_null());
+}
+
+#[test]
+fn test_sentinel_is_stripped_before_encoding() {
+    let value = json!({ "id": unknown_value(), "list": [unknown_value(), "a"], "n": 1 });
+    let encoded = encode_msgpack(&value).unwrap();
+    let raw = rmpv::decode::read_value(&mut &encoded[..]).unwrap();
+    let text = raw.to_string();
+    assert!(!text.contains("__oxid_unknown__"), "{}", text);
+    assert_eq!(
+        decode_msgpack(&encoded).unwrap(),
+        json!({ "id": null, "list": [null, "a"], "n": 1 })
+    );
+}
+
+// ─── Encoding ────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_same_config_encodes_to_identical_bytes() {
+    let names = [
+        "tags",
+        "ami",
+        "instance_type",
+        "subnet_id",
+        "user_data",
+        "count",
+    ];
+    let attrs = |order: &[&str]| -> HashMap<String, Expression> {
+        order
+            .iter()
+            .map(|name| {
+                let value = Expression::Literal(Value::String(format!("{}-value", name)));
+                (name.to_string(), value)
+            })
+            .collect()
+    };
+    let mut reversed = names;
+    reversed.reverse();
+
+    let ctx = EvalContext::plan_only(HashMap::new());
+    let first = encode_msgpack(&attributes_to_json(&attrs(&names), &ctx).unwrap()).unwrap();
+    let second = encode_msgpack(&attributes_to_json(&attrs(&reversed), &ctx).unwrap()).unwrap();
+    assert_eq!(first, second);
+
+    // Keys are written in sorted order, nested maps included
+    let nested = json!({ "z": { "b": 1, "a": 2 }, "a": null });
+    let decoded = rmpv::decode::read_value(&mut &encode_msgpack(&nested).unwrap()[..]).unwrap();
+    let keys: Vec<&str> = decoded
+        .as_map()
+        .unwrap()
+        .iter()
+        .map(|(k, _)| k.as_str().unwrap())
+        .collect();
+    assert_eq!(keys, ["a", "z"]);
+    let inner: Vec<&str> = decoded.as_map().unwrap()[1]
+        .1
+        .as_map()
+        .unwrap()
+        .iter()
+        .map(|(k, _)| k.as_str().unwrap())
+        .collect();
+    assert_eq!(inner, ["a", "b"]);
+}
+
+// ─── Planning ────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_unknown_matches_any_prior_value() {
+    assert!(matches_known(&json!("i-abc"), &unknown_value()));
+    assert!(matches_known(&json!(null), &unknown_value()));
+    assert!(matches_known(
+        &json!({ "a": 1, "b": [1, 2] }),
+        &json!({ "a": 1, "b": [1, unknown_value()] })
+    ));
+    assert!(!matches_known(
+        &json!({ "a": 1, "b": [1, 2] }),
+        &json!({ "a": 2, "b": [1, unknown_value()] })
+    ));
+    assert!(!matches_known(&json!([1]), &json!([1, unknown_value()])));
+}
+
+#[test]
+fn test_unknown_planned_values_are_not_an_update() {
+    let prior = json!({
+        "id": "i-abc",
+        "arn": "arn:aws:ec2:us-east-1:123:instance/i-abc",
+        "ami": "ami-123",
+        "monitoring": null,
+        "tags": { "Name": "web", "Owner": "ops" },
+    });
+    let planned = decode_msgpack(&planned_payload()).unwrap();
+    assert_eq!(
+        determine_action(Some(&prior), Some(&planned), &[]),
+        ResourceAction::NoOp
+    );
+
+    let mut changed = planned.clone();
+    changed["ami"] = json!("ami-456");
+    assert_eq!(
+        determine_action(Some(&prior), Some(&changed), &[]),
+        ResourceAction::Update
+    );
+    assert_eq!(
+        determine_action(Some(&prior), Some(&changed), &["ami".to_string()]),
+        ResourceAction::Replace
+    );
+}
+
+#[test]
+fn test_genuine_null_is_still_a_change() {
+    let prior = json!({ "description": "managed" });
+    let planned = json!({ "description": null });
+    assert_eq!(
+        determine_action(Some(&prior), Some(&planned), &[]),
+        ResourceAction::Update
+    );
+}
+
+// ─── Launch errors ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_missing_provider_binary_suggests_init() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("terraform-provider-missing");
+    let err = ProviderConnection::start(&path).await.err().unwrap();
+    let message = err.to_string();
+    assert!(
+        message.contains("Provider binary not found at"),
+        "{}",
+        message
+    );
+    assert!(message.contains(&path.display().to_string()), "{}", message);
+    assert!(message.contains("oxid init"), "{}", message);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_non_executable_provider_binary_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("terraform-provider-noexec");
+    std::fs::write(&path, "#!/bin/sh\n").unwrap();
+    let err = ProviderConnection::start(&path).await.err().unwrap();
+    assert!(err.to_string().contains("is not executable"), "{}", err);
+}
+
+// ─── Start options ───────────────────────────────────────────────────────────
+
+/// A provider stub running `body`, executable.
+#[cfg(unix)]
+fn stub_provider(dir: &std::path::Path, body: &str) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+    let path = dir.join("terraform-provider-stub");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_custom_start_timeout_is_honored() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = stub_provider(dir.path(), "sleep 10");
+    let options = ProviderStartOptions {
+        handshake_timeout: std::time::Duration::from_millis(300),
+        ..Default::default()
+    };
+    let started = std::time::Instant::now();
+    let err = ProviderConnection::start_with(&path, &options)
+        .await
+        .err()
+        .unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    let message = err.to_string();
+    assert!(message.contains("timed out after 300ms"), "{}", message);
+    assert!(
+        message.contains("OXID_PROVIDER_START_TIMEOUT"),
+        "{}",
+        message
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_port_range_is_passed_to_the_provider() {
+    let dir = tempfile::tempdir().unwrap();
+    let ports = dir.path().join("ports");
+    let path = stub_provider(
+        dir.path(),
+        &format!(
+            "echo \"$PLUGIN_MIN_PORT-$PLUGIN_MAX_PORT\" > {}",
+            ports.display()
+        ),
+    );
+    let options = ProviderStartOptions {
+        min_port: 40000,
+        max_port: 40100,
+        ..Default::default()
+    };
+    let err = ProviderConnection::start_with(&path, &options)
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("exited before handshake"),
+        "{}",
+        err
+    );
+    assert_eq!(
+        std::fs::read_to_string(&ports).unwrap().trim(),
+        "40000-40100"
+    );
+}
+
+#[test]
+fn test_start_options_from_environment() {
+    let defaults = ProviderStartOptions::from_vars(|_| None).unwrap();
+    assert_eq!(defaults, ProviderStartOptions::default());
+    assert_eq!(defaults.handshake_timeout.as_secs(), 30);
+    assert_eq!((defaults.min_port, defaults.max_port), (10000, 25000));
+
+    let vars = |timeout: &str, min: &str| {
+        let (timeout, min) = (timeout.to_string(), min.to_string());
+        move |name: &str| match name {
+            "OXID_PROVIDER_START_TIMEOUT" => Some(timeout.clone()),
+            "OXID_PLUGIN_MIN_PORT" => Some(min.clone()),
+            _ => None,
+        }
+    };
+    let options = ProviderStartOptions::from_vars(vars("120", "20000")).unwrap();
+    assert_eq!(options.handshake_timeout.as_secs(), 120);
+    assert_eq!((options.min_port, options.max_port), (20000, 25000));
+
+    let err = ProviderStartOptions::from_vars(vars("120", "30000")).unwrap_err();
+    assert!(err.to_string().contains("30000-25000 is empty"), "{}", err);
+    let err = ProviderStartOptions::from_vars(vars("slow", "20000")).unwrap_err();
+    assert!(
+        err.to_string().contains("OXID_PROVIDER_START_TIMEOUT"),
+        "{}",
+        err
+    );
+}
+
+// ─── Keep-alive ──────────────────────────────────────────────────────────────
+
+/// Read HTTP/2 frames from an idle client connection until a PING arrives.
+async fn wait_for_ping(mut socket: tokio::net::TcpStream) -> std::io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let mut preface = [0u8; 24];
+    socket.read_exact(&mut preface).await?;
+    // An empty SETTINGS frame completes the server side of the handshake
+    socket.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]).await?;
+    loop {
+        let mut header = [0u8; 9];
+        socket.read_exact(&mut header).await?;
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let mut payload = vec![0u8; len];
+        socket.read_exact(&mut payload).await?;
+        match header[3] {
+            // PING
+            6 => return Ok(()),
+            // SETTINGS without ACK: acknowledge it
+            4 if header[4] & 1 == 0 => {
+                socket.write_all(&[0, 0, 0, 4, 1, 0, 0, 0, 0]).await?;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_provider_channel_pings_while_idle() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        wait_for_ping(socket).await
+    });
+
+    let options = ProviderStartOptions {
+        keep_alive_interval: std::time::Duration::from_millis(100),
+        ..Default::default()
+    };
+    let _channel = options.endpoint(&address).unwrap().connect().await.unwrap();
+
+    // No RPC is ever sent, yet the connection is pinged
+    tokio::time::timeout(std::time::Duration::from_secs(5), server)
+        .await
+        .expect("no keep-alive ping on an idle channel")
+        .unwrap()
+        .unwrap();
+}
+
+#[test]
+fn test_keep_alive_is_on_by_default() {
+    let options = ProviderStartOptions::default();
+    assert_eq!(options.keep_alive_interval.as_secs(), 30);
+    assert_eq!(options.keep_alive_timeout.as_secs(), 20);
+    assert!(options.endpoint("not a uri").is_err());
+}
+
+// ─── Crash detection ─────────────────────────────────────────────────────────
+
+/// How the fake provider server answers the first RPC on a connection.
+#[derive(Clone, Copy)]
+enum Answer {
+    /// A trailers-only response with grpc-status 3 (INVALID_ARGUMENT).
+    InvalidArgument,
+    /// Close the connection, as a crashing provider would.
+    Hangup,
+}
+
+/// Serve one HTTP/2 connection: on the first request create `stop`, which
+/// makes the stub provider exit, then answer the request.
+async fn answer_first_rpc(
+    mut socket: tokio::net::TcpStream,
+    stop: std::path::PathBuf,
+    answer: Answer,
+) -> std::io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let mut preface = [0u8; 24];
+    socket.read_exact(&mut preface).await?;
+    socket.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]).await?;
+    loop {
+        let mut header = [0u8; 9];
+        socket.read_exact(&mut header).await?;
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let mut payload = vec![0u8; len];
+        socket.read_exact(&mut payload).await?;
+        match header[3] {
+            // HEADERS: the request
+            1 => {
+                std::fs::write(&stop, "")?;
+                let Answer::InvalidArgument = answer else {
+                    return Ok(());
+                };
+                // :status 200, content-type, grpc-status and grpc-message,
+                // HPACK-encoded without indexing
+                let mut block = vec![0x88, 0x0f, 0x10, 16];
+                block.extend_from_slice(b"application/grpc");
+                for (name, value) in [("grpc-status", "3"), ("grpc-message", "bad config")] {
+                    block.extend_from_slice(&[0, name.len() as u8]);
+                    block.extend_from_slice(name.as_bytes());
+                    block.push(value.len() as u8);
+                    block.extend_from_slice(value.as_bytes());
+                }
+                let mut frame = (block.len() as u32).to_be_bytes()[1..].to_vec();
+                // HEADERS with END_STREAM | END_HEADERS on the request's stream
+                frame.extend_from_slice(&[1, 5]);
+                frame.extend_from_slice(&header[5..9]);
+                frame.extend_from_slice(&block);
+                socket.write_all(&frame).await?;
+            }
+            // SETTINGS without ACK: acknowledge it
+            4 if header[4] & 1 == 0 => {
+                socket.write_all(&[0, 0, 0, 4, 1, 0, 0, 0, 0]).await?;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Start a stub provider served by a fake gRPC server that answers its first
+/// RPC with `answer` while the stub exits. Returns the error of a
+/// ReadResource and how many times the stub was started.
+#[cfg(unix)]
+async fn read_while_provider_exits(answer: Answer) -> (anyhow::Error, usize) {
+    use oxid::provider::manager::ProviderManager;
+    use oxid::provider::mirror::ProviderInstallation;
+
+    let dir = tempfile::tempdir().unwrap();
+    let stop = dir.path().join("stop");
+    let launches = dir.path().join("launches");
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = {
+        let stop = stop.clone();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(answer_first_rpc(socket, stop.clone(), answer));
+            }
+        })
+    };
+
+    let binary = stub_provider(
+        dir.path(),
+        &format!(
+            "echo started >> {}\necho \"1|5|tcp|{}|grpc\"\nwhile [ ! -e {} ]; do sleep 0.01; done",
+            launches.display(),
+            address,
+            stop.display()
+        ),
+    );
+    let mut installation = ProviderInstallation::default();
+    installation.add_dev_override("hashicorp/stub", binary);
+    let pm = ProviderManager::new(dir.path().join("cache")).with_installation(installation);
+    pm.get_connection("hashicorp/stub", "").await.unwrap();
+    let err = pm
+        .read_resource("hashicorp/stub", "stub_thing", &json!({}), &[])
+        .await
+        .unwrap_err();
+    server.abort();
+    let started = std::fs::read_to_string(&launches).unwrap().lines().count();
+    (err, started)
+}
+
+/// A status the provider sent back is its answer, not a crash, even when the
+/// process happens to exit right after.
+#[cfg(unix)]
+#[tokio::test]
+async fn test_provider_error_status_does_not_restart_the_provider() {
+    let (err, started) = read_while_provider_exits(Answer::InvalidArgument).await;
+    assert_eq!(started, 1, "{:#}", err);
+    let message = format!("{:#}", err);
+    assert!(message.contains("ReadResource RPC failed"), "{}", message);
+    assert!(message.contains("bad config"), "{}", message);
+    assert!(!is_transport_error(&err), "{}", message);
+}
+
+/// A connection that breaks while the process exits is a crash: the provider
+/// is restarted.
+#[cfg(unix)]
+#[tokio::test]
+async fn test_broken_connection_restarts_the_exited_provider() {
+    let (err, started) = read_while_provider_exits(Answer::Hangup).await;
+    assert_eq!(started, 2, "{:#}", err);
+}
+
+// ─── Handshake ───────────────────────────────────────────────────────────────
+
+#[test]
+fn test_parse_handshake_without_tls() {
+    let handshake = parse_handshake("1|6|unix|/tmp/plugin123|grpc").unwrap();
+    assert_eq!(handshake.core_protocol, 1);
+    assert_eq!(handshake.app_protocol, 6);
+    assert_eq!(handshake.network_type, "unix");
+    assert_eq!(handshake.address, "/tmp/plugin123");
+    assert_eq!(handshake.protocol, "grpc");
+    assert!(handshake.server_cert.is_none());
+}
+
+#[test]
+fn test_parse_handshake_with_server_cert() {
+    // go-plugin sends the certificate's DER in unpadded standard base64
+    let handshake = parse_handshake("1|5|tcp|127.0.0.1:10000|grpc|MIIBkTCB+6AD").unwrap();
+    assert_eq!(handshake.app_protocol, 5);
+    assert_eq!(handshake.address, "127.0.0.1:10000");
+    assert_eq!(
+        handshake.server_cert.unwrap(),
+        [0x30, 0x82, 0x01, 0x91, 0x30, 0x81, 0xfb, 0xa0, 0x03]
+    );
+}
+
+#[test]
+fn test_parse_handshake_rejects_unsupported_protocols() {
+    let err = parse_handshake("2|6|unix|/tmp/plugin123|grpc").unwrap_err();
+    assert!(
+        err.to_string().contains("core protocol version 2"),
+        "{}",
+        err
+    );
+    let err = parse_handshake("1|6|unix|/tmp/plugin123|netrpc").unwrap_err();
+    assert!(err.to_string().contains("netrpc"), "{}", err);
+    assert!(parse_handshake("1|6|unix|/tmp/plugin123|grpc|not base64!").is_err());
+}
+
+// ─── AutoMTLS ────────────────────────────────────────────────────────────────
+
+/// A loopback TLS server presenting a fresh `localhost` certificate. Answers
+/// "ping" with "pong" on the first connection; returns the certificate DER
+/// and the address.
+async fn tls_echo_server() -> (
+    Vec<u8>,
+    std::net::SocketAddr,
+    tokio::task::JoinHandle<std::io::Result<()>>,
+) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let key = rcgen::KeyPair::generate().unwrap();
+    let cert = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+        .unwrap()
+        .self_signed(&key)
+        .unwrap();
+    let cert_der = cert.der().to_vec();
+    let config = rustls::ServerConfig::builder_with_provider(std::sync::Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_no_client_auth()
+    .with_single_cert(
+        vec![cert.der().clone()],
+        rustls::pki_types::PrivateKeyDer::Pkcs8(key.serialize_der().into()),
+    )
+    .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(config));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await?;
+        let mut stream = acceptor.accept(socket).await?;
+        let mut ping = [0u8; 4];
+        stream.read_exact(&mut ping).await?;
+        assert_eq!(&ping, b"ping");
+        stream.write_all(b"pong").await?;
+        stream.flush().await
+    });
+    (cert_der, address, server)
+}
+
+/// Send "ping" to `endpoint` and read back up to four bytes.
+async fn ping(endpoint: &str) -> Vec<u8> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let mut stream = tokio::net::TcpStream::connect(endpoint.trim_start_matches("http://"))
+        .await
+        .unwrap();
+    stream.write_all(b"ping").await.unwrap();
+    let mut reply = Vec::new();
+    let _ = stream.take(4).read_to_end(&mut reply).await;
+    reply
+}
+
+fn tls_handshake(address: std::net::SocketAddr, cert_der: &[u8]) -> String {
+    use base64::Engine;
+    format!(
+        "1|5|tcp|{}|grpc|{}",
+        address,
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(cert_der)
+    )
+}
+
+#[tokio::test]
+async fn test_provider_without_server_cert_is_dialled_in_plain_text() {
+    let handshake = parse_handshake("1|5|tcp|127.0.0.1:10000|grpc").unwrap();
+    let client_cert = ClientCertificate::generate().unwrap();
+    let endpoint = provider_endpoint(&handshake, &client_cert).await.unwrap();
+    assert_eq!(endpoint, "http://127.0.0.1:10000");
+}
+
+#[tokio::test]
+async fn test_tls_proxy_connects_to_the_advertised_certificate() {
+    let (cert_der, address, server) = tls_echo_server().await;
+    let handshake = parse_handshake(&tls_handshake(address, &cert_der)).unwrap();
+    let client_cert = ClientCertificate::generate().unwrap();
+    let endpoint = provider_endpoint(&handshake, &client_cert).await.unwrap();
+    assert_ne!(endpoint, format!("http://{}", address));
+
+    assert_eq!(ping(&endpoint).await, b"pong");
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_tls_proxy_rejects_a_different_certificate() {
+    let (_, address, server) = tls_echo_server().await;
+    let other = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let handshake = parse_handshake(&tls_handshake(address, other.cert.der())).unwrap();
+    let client_cert = ClientCertificate::generate().unwrap();
+    let endpoint = provider_endpoint(&handshake, &client_cert).await.unwrap();
+
+    assert!(ping(&endpoint).await.is_empty());
+    assert!(server.await.unwrap().is_err());
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use oxid::config::types::{ProvisionerConfig, ProvisionerOnFailure, ProvisionerWhen};
+use oxid::executor::engine::{resolve_provisioners, EvalContext};
+use oxid::executor::provisioner::{local_exec, run_provisioners};
+use oxid::hcl::parser::parse_hcl;
+use serde_json::json;
+
+fn local_exec_provisioner(on_failure: ProvisionerOnFailure) -> ProvisionerConfig {
+    ProvisionerConfig {
+        provisioner_type: "local-exec".to_string(),
+        config: HashMap::new(),
+        when: ProvisionerWhen::Create,
+        on_failure,
+    }
+}
+
+// ─── local-exec ──────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_local_exec_captures_output() {
+    let run = local_exec(&json!({"command": "echo hello; echo oops >&2"}))
+        .await
+        .unwrap();
+    assert!(run.success);
+    assert_eq!(run.exit_code, Some(0));
+    assert_eq!(run.output, "hello\noops\n");
+}
+
+#[tokio::test]
+async fn test_local_exec_passes_environment_and_working_dir() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let run = local_exec(&json!({
+        "command": "echo \"$GREETING\" > greeting.txt",
+        "working_dir": dir.path().to_string_lossy(),
+        "environment": {"GREETING": "hi there"},
+    }))
+    .await
+    .unwrap();
+    assert!(run.success, "{}", run.output);
+    let written = std::fs::read_to_string(dir.path().join("greeting.txt")).unwrap();
+    assert_eq!(written, "hi there\n");
+}
+
+#[tokio::test]
+async fn test_local_exec_requires_command() {
+    let err = local_exec(&json!({})).await.unwrap_err().to_string();
+    assert!(err.contains("command"), "{}", err);
+}
+
+// ─── Failure handling ────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_nonzero_exit_fails_the_resource() {
+    let provisioner = local_exec_provisioner(ProvisionerOnFailure::Fail);
+    let resolved = vec![(&provisioner, json!({"command": "echo before; exit 3"}))];
+    let mut log = String::new();
+    let err = run_provisioners("null_resource.a", &resolved, &mut log)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("null_resource.a"), "{}", err);
+    assert!(err.contains("exited with status 3"), "{}", err);
+    assert_eq!(log, "before\n");
+}
+
+#[tokio::test]
+async fn test_on_failure_continue_runs_remaining_provisioners() {
+    let lenient = local_exec_provisioner(ProvisionerOnFailure::Continue);
+    let strict = local_exec_provisioner(ProvisionerOnFailure::Fail);
+    let resolved = vec![
+        (&lenient, json!({"command": "false"})),
+        (&strict, json!({"command": "echo after"})),
+    ];
+    let mut log = String::new();
+    run_provisioners("null_resource.a", &resolved, &mut log)
+        .await
+        .unwrap();
+    assert_eq!(log, "after\n");
+}
+
+// ─── Parsing and self ────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_create_provisioner_echoes_self_id() {
+    let hcl = r#"
+resource "null_resource" "a" {
+  provisioner "local-exec" {
+    command    = "echo created ${self.id}"
+    on_failure = continue
+  }
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let provisioners = &ws.resources[0].provisioners;
+    assert_eq!(provisioners[0].on_failure, ProvisionerOnFailure::Continue);
+
+    let ctx = EvalContext::plan_only(HashMap::new()).with_self(Some(json!({"id": "42"})));
+    let resolved = resolve_provisioners(provisioners, ProvisionerWhen::Create, &ctx).unwrap();
+    let mut log = String::new();
+    run_provisioners("null_resource.a", &resolved, &mut log)
+        .await
+        .unwrap();
+    assert_eq!(log, "created 42\n");
+}
@@ -0,0 +1,295 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use indicatif::{MultiProgress, ProgressDrawTarget};
+use oxid::provider::manager::ProviderManager;
+use oxid::provider::registry::{shasum, verify_shasum, Platform, RegistryClient, RegistryVersions};
+use oxid::provider::version::{parse_version, VersionConstraint};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const VERSIONS: &str = include_str!("fixtures/registry/aws_versions.json");
+const DOWNLOAD: &str = include_str!("fixtures/registry/aws_5.71.0_download.json");
+
+fn constraint(s: &str) -> VersionConstraint {
+    VersionConstraint::parse(s).unwrap()
+}
+
+fn allows(c: &str, version: &str) -> bool {
+    constraint(c).matches(&parse_version(version).unwrap())
+}
+
+// ─── Version constraints ─────────────────────────────────────────────────────
+
+#[test]
+fn test_range_constraint() {
+    assert!(allows(">= 5.0, < 6.0", "5.0.0"));
+    assert!(allows(">= 5.0, < 6.0", "5.71.0"));
+    assert!(!allows(">= 5.0, < 6.0", "6.0.0"));
+    assert!(!allows(">= 5.0, < 6.0", "4.67.0"));
+}
+
+#[test]
+fn test_pessimistic_constraint() {
+    assert!(allows("~> 5.70", "5.99.0"));
+    assert!(!allows("~> 5.70", "6.0.0"));
+    assert!(!allows("~> 5.70", "5.69.0"));
+    assert!(allows("~> 5.70.1", "5.70.9"));
+    assert!(!allows("~> 5.70.1", "5.71.0"));
+}
+
+#[test]
+fn test_exact_and_excluded_versions() {
+    assert!(allows("5.70.0", "5.70.0"));
+    assert!(allows("= 5.70", "5.70.0"));
+    assert!(!allows("5.70.0", "5.70.1"));
+    assert!(!allows(">= 5.0, != 5.70.0", "5.70.0"));
+}
+
+#[test]
+fn test_prerelease_needs_exact_constraint() {
+    assert!(!allows(">= 5.0", "6.0.0-beta1"));
+    assert!(allows("6.0.0-beta1", "6.0.0-beta1"));
+}
+
+#[test]
+fn test_invalid_constraint() {
+    let err = VersionConstraint::parse(">= five").unwrap_err();
+    assert!(format!("{:#}", err).contains("Invalid version constraint '>= five'"));
+}
+
+// ─── Version selection (recorded registry JSON) ──────────────────────────────
+
+fn versions() -> RegistryVersions {
+    serde_json::from_str(VERSIONS).unwrap()
+}
+
+#[test]
+fn test_selects_highest_matching_version() {
+    let linux = Platform::new("linux", "amd64");
+    let selected = |c: &str| {
+        versions()
+            .select(&constraint(c), &linux)
+            .map(|v| v.version.clone())
+    };
+    assert_eq!(selected(">= 5.0, < 6.0").as_deref(), Some("5.71.0"));
+    assert_eq!(selected("~> 4.0").as_deref(), Some("4.67.0"));
+    assert_eq!(selected(">= 0.0.0").as_deref(), Some("6.0.0"));
+    assert_eq!(selected(">= 7.0"), None);
+}
+
+#[test]
+fn test_skips_versions_without_a_build_for_the_platform() {
+    let mac = Platform::new("darwin", "arm64");
+    let versions = versions();
+    let selected = versions.select(&constraint(">= 5.0, < 6.0"), &mac).unwrap();
+    assert_eq!(selected.version, "5.70.0");
+}
+
+// ─── Registry client ─────────────────────────────────────────────────────────
+
+/// Serve the recorded registry responses over HTTP, like a private mirror.
+async fn mock_registry() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("");
+            let (status, body) = if path == "/v1/providers/hashicorp/aws/versions" {
+                ("200 OK", VERSIONS)
+            } else if path.starts_with("/v1/providers/hashicorp/aws/5.71.0/download/") {
+                ("200 OK", DOWNLOAD)
+            } else {
+                ("404 Not Found", "{}")
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_resolve_returns_download_url_and_shasum() {
+    let registry = RegistryClient::with_base_url(&mock_registry().await);
+    let expected = versions()
+        .select(&constraint(">= 5.0, < 6.0"), &Platform::current())
+        .map(|v| v.version.clone());
+    if expected.as_deref() != Some("5.71.0") {
+        // The fixture only records a 5.71.0 download response
+        return;
+    }
+
+    let source = registry
+        .resolve("hashicorp", "aws", &constraint(">= 5.0, < 6.0"))
+        .await
+        .unwrap();
+    assert_eq!(source.version, "5.71.0");
+    assert!(source
+        .download_url
+        .ends_with("terraform-provider-aws_5.71.0_linux_amd64.zip"));
+    assert_eq!(
+        source.shasum,
+        "4b8a1f0f3d5a9e2c6b7d8e9f0a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d"
+    );
+    assert_eq!(source.protocols, ["5.0"]);
+}
+
+#[tokio::test]
+async fn test_resolve_reports_unsatisfiable_constraint() {
+    let registry = RegistryClient::with_base_url(&mock_registry().await);
+    let err = registry
+        .resolve("hashicorp", "aws", &constraint(">= 7.0"))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("No version of hashicorp/aws matches '>= 7.0'"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_provider_is_an_error() {
+    let registry = RegistryClient::with_base_url(&mock_registry().await);
+    let err = registry
+        .resolve("hashicorp", "nope", &VersionConstraint::any())
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Registry has no provider hashicorp/nope"));
+}
+
+// ─── Downloads ───────────────────────────────────────────────────────────────
+
+/// A provider archive holding a single stand-in binary.
+fn provider_zip() -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file::<_, ()>(
+        "terraform-provider-widget_v1.0.0_x5",
+        zip::write::SimpleFileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(b"#!/bin/sh\n").unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+/// Serve a one-version `acme/widget` registry and its archive. While
+/// `truncate` is set the archive is cut short, as by a dropped connection.
+async fn mock_download_registry(truncate: Arc<AtomicBool>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let archive = provider_zip();
+    let platform = Platform::current();
+    let versions = serde_json::json!({
+        "versions": [{
+            "version": "1.0.0",
+            "protocols": ["5.0"],
+            "platforms": [{ "os": platform.os, "arch": platform.arch }],
+        }]
+    })
+    .to_string();
+    let download = serde_json::json!({
+        "os": platform.os,
+        "arch": platform.arch,
+        "filename": "terraform-provider-widget_1.0.0.zip",
+        "download_url": format!("{}/archive.zip", base),
+        "shasum": shasum(&archive),
+        "protocols": ["5.0"],
+    })
+    .to_string();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("");
+            let body: &[u8] = if path == "/v1/providers/acme/widget/versions" {
+                versions.as_bytes()
+            } else if path.starts_with("/v1/providers/acme/widget/1.0.0/download/") {
+                download.as_bytes()
+            } else if path == "/archive.zip" && truncate.load(Ordering::SeqCst) {
+                &archive[..archive.len() / 2]
+            } else if path == "/archive.zip" {
+                &archive
+            } else {
+                b"{}"
+            };
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = socket.write_all(header.as_bytes()).await;
+            let _ = socket.write_all(body).await;
+        }
+    });
+    base
+}
+
+#[tokio::test]
+async fn test_download_extracts_provider_and_retries_cleanly() {
+    let truncate = Arc::new(AtomicBool::new(true));
+    let base = mock_download_registry(truncate.clone()).await;
+    let cache = tempfile::TempDir::new().unwrap();
+    let pm = ProviderManager::with_registry(cache.path().to_path_buf(), &base)
+        .with_progress(MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+    let version_dir = cache.path().join("registry.terraform.io/acme/widget/1.0.0");
+
+    // A failed download leaves nothing behind to be mistaken for a cached provider
+    let err = pm
+        .ensure_provider("acme/widget", "1.0.0")
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("Checksum mismatch"),
+        "{:#}",
+        err
+    );
+    assert!(!version_dir.exists());
+
+    truncate.store(false, Ordering::SeqCst);
+    let binary = pm.ensure_provider("acme/widget", "1.0.0").await.unwrap();
+    assert_eq!(
+        binary,
+        version_dir.join("terraform-provider-widget_v1.0.0_x5")
+    );
+    assert!(binary.is_file());
+    assert!(!version_dir
+        .join("terraform-provider-widget_1.0.0.zip")
+        .exists());
+}
+
+#[test]
+fn test_source_host() {
+    assert_eq!(
+        RegistryClient::source_host("registry.example.com/acme/widget"),
+        Some("registry.example.com")
+    );
+    assert_eq!(RegistryClient::source_host("hashicorp/aws"), None);
+}
+
+#[test]
+fn test_verify_shasum() {
+    // sha256("hello")
+    let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    assert!(verify_shasum(b"hello", digest).is_ok());
+    assert!(verify_shasum(b"hello", &digest.to_uppercase()).is_ok());
+    let err = verify_shasum(b"tampered", digest).unwrap_err().to_string();
+    assert!(err.contains("Checksum mismatch"), "{}", err);
+}
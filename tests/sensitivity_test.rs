@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use oxid::executor::engine::{eval_expression, sensitive_attributes, EvalContext};
+use oxid::executor::sensitivity::Sensitivity;
+use oxid::hcl::parser::parse_hcl;
+use oxid::state::models::ResourceState;
+use serde_json::json;
+
+fn db_state(address: &str) -> ResourceState {
+    let mut state = ResourceState::new("ws", "aws_db_instance", "main", address);
+    state.sensitive_attrs = vec!["password".to_string()];
+    state
+}
+
+/// Names of the outputs the analysis marks sensitive, sorted.
+fn sensitive_outputs(hcl: &str, resources: &[ResourceState]) -> Vec<String> {
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let sensitivity = Sensitivity::new(&ws, resources);
+    let mut names: Vec<String> = ws
+        .outputs
+        .iter()
+        .filter(|o| sensitivity.is_sensitive(&o.value))
+        .map(|o| o.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_sensitive_attribute_propagates_into_outputs() {
+    let hcl = r#"
+resource "aws_db_instance" "main" {
+  engine = "postgres"
+}
+
+output "password" {
+  value = aws_db_instance.main.password
+}
+
+output "dsn" {
+  value = "postgres://admin:${aws_db_instance.main.password}@${aws_db_instance.main.address}"
+}
+
+output "whole" {
+  value = aws_db_instance.main
+}
+
+output "address" {
+  value = aws_db_instance.main.address
+}
+"#;
+    assert_eq!(
+        sensitive_outputs(hcl, &[db_state("aws_db_instance.main")]),
+        ["dsn", "password", "whole"]
+    );
+}
+
+#[test]
+fn test_sensitive_attribute_of_an_instance() {
+    let hcl = r#"
+output "first" {
+  value = aws_db_instance.main[0].password
+}
+
+output "all" {
+  value = [for db in aws_db_instance.main : db.address]
+}
+
+output "endpoint" {
+  value = aws_db_instance.main[0].address
+}
+"#;
+    assert_eq!(
+        sensitive_outputs(hcl, &[db_state("aws_db_instance.main[0]")]),
+        ["all", "first"]
+    );
+}
+
+#[test]
+fn test_sensitive_variables_propagate_through_locals() {
+    let hcl = r#"
+variable "token" {
+  sensitive = true
+}
+
+variable "region" {
+  default = "us-east-1"
+}
+
+locals {
+  header = "Bearer ${var.token}"
+  region = upper(var.region)
+}
+
+output "header" {
+  value = local.header
+}
+
+output "region" {
+  value = local.region
+}
+"#;
+    assert_eq!(sensitive_outputs(hcl, &[]), ["header"]);
+}
+
+#[test]
+fn test_sensitive_and_nonsensitive_functions() {
+    let hcl = r#"
+variable "token" {
+  sensitive = true
+}
+
+output "forced" {
+  value = sensitive("not a secret")
+}
+
+output "cleared" {
+  value = nonsensitive(length(var.token))
+}
+"#;
+    assert_eq!(sensitive_outputs(hcl, &[]), ["forced"]);
+
+    // Both pass their argument through unchanged
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let ctx = EvalContext::plan_only(HashMap::new());
+    let forced = ws.outputs.iter().find(|o| o.name == "forced").unwrap();
+    assert_eq!(
+        eval_expression(&forced.value, &ctx).unwrap(),
+        json!("not a secret")
+    );
+}
+
+#[test]
+fn test_sensitive_attributes_from_schema() {
+    let schema = json!({
+        "version": 0,
+        "block": {
+            "attributes": [
+                { "name": "address", "sensitive": false },
+                { "name": "password", "sensitive": true },
+                { "name": "engine" }
+            ]
+        }
+    });
+    assert_eq!(sensitive_attributes(&schema), ["password"]);
+    assert!(sensitive_attributes(&json!({})).is_empty());
+}
@@ -0,0 +1,1015 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::FutureExt;
+use oxid::dag::resource_graph::{build_resource_dag, DagNode};
+use oxid::dag::walker::NodeExecutor;
+use oxid::executor::engine::{
+    build_provider_map, build_variable_defaults, record_node_results, NodeOutputs, RecordAction,
+};
+use oxid::hcl::parser::parse_hcl;
+use oxid::output::formatter::resource_list_json;
+use oxid::state::backend::StateBackend;
+use oxid::state::lock::{acquire_workspace_lock, WORKSPACE_LOCK_ADDRESS};
+use oxid::state::models::{ResourceFilter, ResourceResult, ResourceSort, ResourceState};
+use oxid::state::query::{execute_query, stream_query, QueryFormat};
+use oxid::state::sqlite::SqliteBackend;
+use rusqlite::Connection;
+use tempfile::TempDir;
+
+/// Bytes that would be mangled by any text round-trip.
+const PRIVATE: &[u8] = &[0x00, 0xff, 0x7b, 0x22, 0x0a, 0xc3, 0x28, 0x00];
+
+async fn backend_with_workspace() -> (SqliteBackend, String) {
+    let backend = SqliteBackend::open_memory().unwrap();
+    backend.initialize().await.unwrap();
+    let ws_id = backend.create_workspace("default").await.unwrap();
+    (backend, ws_id)
+}
+
+// ─── Private Data ────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_private_data_round_trip() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    let mut resource = ResourceState::new(&ws_id, "aws_instance", "web", "aws_instance.web");
+    resource.private_data = PRIVATE.to_vec();
+    backend.upsert_resource(&resource).await.unwrap();
+
+    let stored = backend
+        .get_resource(&ws_id, "aws_instance.web")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.private_data, PRIVATE);
+
+    let listed = backend
+        .list_resources(&ws_id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(listed[0].private_data, PRIVATE);
+
+    // Updates replace the private data
+    resource.private_data = vec![1, 2, 3];
+    backend.upsert_resource(&resource).await.unwrap();
+    let stored = backend
+        .get_resource(&ws_id, "aws_instance.web")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.private_data, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_resource_state_json_encodes_private_as_base64() {
+    let mut resource = ResourceState::new("ws", "aws_instance", "web", "aws_instance.web");
+    resource.private_data = PRIVATE.to_vec();
+    let json = serde_json::to_value(&resource).unwrap();
+    assert_eq!(json["private_data"], "AP97IgrDKAA=");
+    let decoded: ResourceState = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded.private_data, PRIVATE);
+}
+
+#[tokio::test]
+async fn test_import_tfstate_keeps_private() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    let tfstate = r#"{
+  "version": 4,
+  "resources": [{
+    "mode": "managed",
+    "type": "null_resource",
+    "name": "a",
+    "provider": "provider[\"registry.terraform.io/hashicorp/null\"]",
+    "instances": [{
+      "schema_version": 0,
+      "attributes": {"id": "1"},
+      "private": "AP97IgrDKAA="
+    }]
+  }]
+}"#;
+    backend.import_tfstate(&ws_id, tfstate).await.unwrap();
+    let stored = backend
+        .get_resource(&ws_id, "null_resource.a")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.private_data, PRIVATE);
+}
+
+// ─── Import ──────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_import_tfstate_records_dependencies() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    let tfstate = r#"{
+  "version": 4,
+  "resources": [
+    {
+      "mode": "managed",
+      "type": "aws_vpc",
+      "name": "main",
+      "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+      "instances": [{"attributes": {"id": "vpc-1"}}]
+    },
+    {
+      "mode": "managed",
+      "type": "aws_subnet",
+      "name": "private",
+      "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+      "instances": [
+        {"index_key": "a", "attributes": {"id": "subnet-a"}, "dependencies": ["aws_vpc.main"]},
+        {"index_key": "b", "attributes": {"id": "subnet-b"}, "dependencies": ["aws_vpc.main"]}
+      ]
+    },
+    {
+      "mode": "managed",
+      "type": "aws_instance",
+      "name": "web",
+      "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+      "instances": [{
+        "attributes": {"id": "i-1"},
+        "dependencies": ["aws_subnet.private", "aws_security_group.gone"]
+      }]
+    }
+  ]
+}"#;
+    let result = backend.import_tfstate(&ws_id, tfstate).await.unwrap();
+    assert_eq!(result.imported, 4);
+    assert_eq!(
+        result.warnings,
+        vec!["aws_instance.web depends on aws_security_group.gone, which is not in state"]
+    );
+
+    let id = |address: &'static str| {
+        let backend = &backend;
+        let ws_id = &ws_id;
+        async move {
+            backend
+                .get_resource(ws_id, address)
+                .await
+                .unwrap()
+                .unwrap()
+                .id
+        }
+    };
+    let vpc = id("aws_vpc.main").await;
+    let subnet0 = id("aws_subnet.private[\"a\"]").await;
+    let subnet1 = id("aws_subnet.private[\"b\"]").await;
+    let web = id("aws_instance.web").await;
+
+    assert_eq!(
+        backend.get_dependencies(&subnet0).await.unwrap(),
+        vec![vpc.clone()]
+    );
+    let mut web_deps = backend.get_dependencies(&web).await.unwrap();
+    web_deps.sort();
+    let mut subnets = vec![subnet0.clone(), subnet1.clone()];
+    subnets.sort();
+    assert_eq!(web_deps, subnets);
+
+    let mut vpc_dependents = backend.get_dependents(&vpc).await.unwrap();
+    vpc_dependents.sort();
+    assert_eq!(vpc_dependents, subnets);
+    assert!(backend.get_dependencies(&vpc).await.unwrap().is_empty());
+}
+
+// ─── Serial ──────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_every_state_write_bumps_the_workspace_serial() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    let serial = || async {
+        backend
+            .get_workspace("default")
+            .await
+            .unwrap()
+            .unwrap()
+            .serial
+    };
+    assert_eq!(serial().await, 0);
+
+    let resource = ResourceState::new(&ws_id, "null_resource", "a", "null_resource.a");
+    backend.upsert_resource(&resource).await.unwrap();
+    assert_eq!(serial().await, 1);
+    backend.upsert_resource(&resource).await.unwrap();
+    assert_eq!(serial().await, 2);
+    backend
+        .set_output(&ws_id, "", "id", "\"a\"", false)
+        .await
+        .unwrap();
+    assert_eq!(serial().await, 3);
+    backend
+        .delete_resource(&ws_id, "null_resource.a")
+        .await
+        .unwrap();
+    assert_eq!(serial().await, 4);
+
+    // Reads leave it alone
+    backend
+        .list_resources(&ws_id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(serial().await, 4);
+}
+
+// ─── Migrations ──────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_initialize_migrates_v1_database() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("oxid.db");
+    {
+        // Version 1 layout of the resources table, without private_data
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE schema_version (version INTEGER PRIMARY KEY, applied_at TEXT NOT NULL, description TEXT);
+             INSERT INTO schema_version VALUES (1, '2024-01-01T00:00:00Z', 'Initial schema');
+             CREATE TABLE workspaces (id TEXT PRIMARY KEY, name TEXT NOT NULL UNIQUE,
+                 created_at TEXT NOT NULL, updated_at TEXT NOT NULL);
+             INSERT INTO workspaces VALUES ('ws1', 'default', 'x', 'x');
+             CREATE TABLE resources (
+                 id TEXT PRIMARY KEY, workspace_id TEXT NOT NULL, module_path TEXT NOT NULL DEFAULT '',
+                 resource_type TEXT NOT NULL, resource_name TEXT NOT NULL,
+                 resource_mode TEXT NOT NULL DEFAULT 'managed', provider_source TEXT NOT NULL DEFAULT '',
+                 index_key TEXT, address TEXT NOT NULL, status TEXT NOT NULL DEFAULT 'planned',
+                 attributes_json TEXT NOT NULL DEFAULT '{}', sensitive_attrs TEXT NOT NULL DEFAULT '[]',
+                 schema_version INTEGER DEFAULT 0, created_at TEXT NOT NULL, updated_at TEXT NOT NULL,
+                 UNIQUE(workspace_id, address));
+             INSERT INTO resources (id, workspace_id, resource_type, resource_name, address,
+                 created_at, updated_at)
+             VALUES ('r1', 'ws1', 'null_resource', 'a', 'null_resource.a', 'x', 'x');",
+        )
+        .unwrap();
+    }
+
+    let backend = SqliteBackend::open(&path.to_string_lossy()).unwrap();
+    backend.initialize().await.unwrap();
+
+    let existing = backend
+        .get_resource("ws1", "null_resource.a")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(existing.private_data.is_empty());
+
+    let mut updated = existing.clone();
+    updated.private_data = PRIVATE.to_vec();
+    backend.upsert_resource(&updated).await.unwrap();
+    let stored = backend
+        .get_resource("ws1", "null_resource.a")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.private_data, PRIVATE);
+    let ws = backend.get_workspace("default").await.unwrap().unwrap();
+    assert_eq!(ws.serial, 1);
+
+    // Re-initializing an up-to-date database is a no-op
+    backend.initialize().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_initialize_adds_run_resources_output_column() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("oxid.db");
+    {
+        // Version 2 layout of run_resources, without output
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE schema_version (version INTEGER PRIMARY KEY, applied_at TEXT NOT NULL, description TEXT);
+             INSERT INTO schema_version VALUES (2, '2024-01-01T00:00:00Z', 'Add resources.private_data');
+             CREATE TABLE run_resources (
+                 run_id TEXT NOT NULL, resource_address TEXT NOT NULL, action TEXT NOT NULL,
+                 status TEXT NOT NULL DEFAULT 'pending', started_at TEXT, completed_at TEXT,
+                 error_message TEXT, diff_json TEXT,
+                 PRIMARY KEY (run_id, resource_address));
+             INSERT INTO run_resources (run_id, resource_address, action, status)
+             VALUES ('run1', 'null_resource.a', 'create', 'succeeded');",
+        )
+        .unwrap();
+    }
+
+    let backend = SqliteBackend::open(&path.to_string_lossy()).unwrap();
+    backend.initialize().await.unwrap();
+
+    let results = backend.list_run_resources("run1").await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].output.is_none());
+}
+
+// ─── Pagination ──────────────────────────────────────────────────────────────
+
+async fn backend_with_resources() -> (SqliteBackend, String) {
+    let (backend, ws_id) = backend_with_workspace().await;
+    for (rtype, name) in [
+        ("aws_vpc", "main"),
+        ("aws_subnet", "a"),
+        ("aws_subnet", "b"),
+        ("aws_instance", "web"),
+        ("aws_subnet", "c"),
+    ] {
+        let address = format!("{}.{}", rtype, name);
+        let resource = ResourceState::new(&ws_id, rtype, name, &address);
+        backend.upsert_resource(&resource).await.unwrap();
+    }
+    (backend, ws_id)
+}
+
+fn addresses(resources: &[ResourceState]) -> Vec<&str> {
+    resources.iter().map(|r| r.address.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_list_resources_limit_and_offset() {
+    let (backend, ws_id) = backend_with_resources().await;
+
+    let page = |limit, offset| ResourceFilter {
+        limit,
+        offset,
+        ..Default::default()
+    };
+
+    let first = backend
+        .list_resources(&ws_id, &page(Some(2), None))
+        .await
+        .unwrap();
+    assert_eq!(addresses(&first), ["aws_instance.web", "aws_subnet.a"]);
+
+    let second = backend
+        .list_resources(&ws_id, &page(Some(2), Some(2)))
+        .await
+        .unwrap();
+    assert_eq!(addresses(&second), ["aws_subnet.b", "aws_subnet.c"]);
+
+    let rest = backend
+        .list_resources(&ws_id, &page(None, Some(4)))
+        .await
+        .unwrap();
+    assert_eq!(addresses(&rest), ["aws_vpc.main"]);
+
+    let past_end = backend
+        .list_resources(&ws_id, &page(Some(10), Some(5)))
+        .await
+        .unwrap();
+    assert!(past_end.is_empty());
+}
+
+#[tokio::test]
+async fn test_list_resources_sort_by_type() {
+    let (backend, ws_id) = backend_with_resources().await;
+    let filter = ResourceFilter {
+        sort: ResourceSort::ResourceType,
+        ..Default::default()
+    };
+    let listed = backend.list_resources(&ws_id, &filter).await.unwrap();
+    let types: Vec<&str> = listed.iter().map(|r| r.resource_type.as_str()).collect();
+    assert_eq!(
+        types,
+        [
+            "aws_instance",
+            "aws_subnet",
+            "aws_subnet",
+            "aws_subnet",
+            "aws_vpc"
+        ]
+    );
+    // Ties are broken by address
+    assert_eq!(listed[1].address, "aws_subnet.a");
+    assert_eq!(listed[3].address, "aws_subnet.c");
+}
+
+#[tokio::test]
+async fn test_list_resources_pagination_respects_filters() {
+    let (backend, ws_id) = backend_with_resources().await;
+    let filter = ResourceFilter {
+        resource_type: Some("aws_subnet".to_string()),
+        limit: Some(1),
+        offset: Some(1),
+        ..Default::default()
+    };
+    let listed = backend.list_resources(&ws_id, &filter).await.unwrap();
+    assert_eq!(addresses(&listed), ["aws_subnet.b"]);
+}
+
+#[tokio::test]
+async fn test_state_list_json_follows_filters() {
+    let (backend, ws_id) = backend_with_resources().await;
+    let filter = ResourceFilter::parse("type=aws_subnet, status=planned");
+    assert_eq!(filter.resource_type.as_deref(), Some("aws_subnet"));
+    let listed = backend.list_resources(&ws_id, &filter).await.unwrap();
+    assert_eq!(listed.len(), 3);
+
+    let json = resource_list_json(&listed);
+    let items = json.as_array().unwrap();
+    assert_eq!(items.len(), listed.len());
+    assert_eq!(items[0]["address"], "aws_subnet.a");
+    assert_eq!(items[0]["resource_type"], "aws_subnet");
+    assert_eq!(items[0]["resource_mode"], "managed");
+    assert!(items[0].get("updated_at").is_some());
+    assert!(items[0].get("attributes_json").is_none());
+
+    let none = backend
+        .list_resources(&ws_id, &ResourceFilter::parse("status=tainted"))
+        .await
+        .unwrap();
+    assert_eq!(resource_list_json(&none), serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_list_resources_by_module_includes_nested_modules() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    for (module, name) in [
+        ("", "root"),
+        ("module.network", "vpc"),
+        ("module.network.module.subnets", "subnet"),
+        (r#"module.network["east"]"#, "east"),
+        ("module.network_extra", "extra"),
+    ] {
+        let address = format!(
+            "{}{}aws_vpc.{}",
+            module,
+            if module.is_empty() { "" } else { "." },
+            name
+        );
+        let mut resource = ResourceState::new(&ws_id, "aws_vpc", name, &address);
+        resource.module_path = module.to_string();
+        backend.upsert_resource(&resource).await.unwrap();
+    }
+
+    let filter = ResourceFilter {
+        module_path: Some("module.network".to_string()),
+        ..Default::default()
+    };
+    let listed = backend.list_resources(&ws_id, &filter).await.unwrap();
+    assert_eq!(
+        addresses(&listed),
+        [
+            "module.network.aws_vpc.vpc",
+            "module.network.module.subnets.aws_vpc.subnet",
+            r#"module.network["east"].aws_vpc.east"#,
+        ]
+    );
+
+    // The `module=` filter term narrows the same way
+    let filter = ResourceFilter::parse("module=module.network.module.subnets");
+    let listed = backend.list_resources(&ws_id, &filter).await.unwrap();
+    assert_eq!(
+        addresses(&listed),
+        ["module.network.module.subnets.aws_vpc.subnet"]
+    );
+}
+
+#[tokio::test]
+async fn test_state_rm_glob_removes_every_instance() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    for address in [
+        "aws_instance.web[0]",
+        "aws_instance.web[1]",
+        "aws_instance.web_lb",
+        "aws_instance.webXlb",
+        "aws_instance.api",
+    ] {
+        let resource = ResourceState::new(&ws_id, "aws_instance", "web", address);
+        backend.upsert_resource(&resource).await.unwrap();
+    }
+
+    // `_` in the glob is literal, unlike in a raw LIKE pattern
+    let lb = backend
+        .list_resources(&ws_id, &ResourceFilter::address_glob("aws_instance.web_*"))
+        .await
+        .unwrap();
+    assert_eq!(addresses(&lb), ["aws_instance.web_lb"]);
+
+    assert!(ResourceFilter::is_glob("aws_instance.web[*]"));
+    assert!(!ResourceFilter::is_glob("aws_instance.web[0]"));
+    let instances = backend
+        .list_resources(&ws_id, &ResourceFilter::address_glob("aws_instance.web[*]"))
+        .await
+        .unwrap();
+    assert_eq!(
+        addresses(&instances),
+        ["aws_instance.web[0]", "aws_instance.web[1]"]
+    );
+    for resource in &instances {
+        backend
+            .delete_resource(&ws_id, &resource.address)
+            .await
+            .unwrap();
+    }
+
+    let left = backend
+        .list_resources(&ws_id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        addresses(&left),
+        [
+            "aws_instance.api",
+            "aws_instance.webXlb",
+            "aws_instance.web_lb"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_filter_can_leave_out_deposed_objects() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    let web = ResourceState::new(&ws_id, "aws_instance", "web", "aws_instance.web");
+    let mut deposed =
+        ResourceState::new(&ws_id, "aws_instance", "web", "aws_instance.web (deposed)");
+    deposed.status = "deposed".to_string();
+    backend.upsert_resource(&web).await.unwrap();
+    backend.upsert_resource(&deposed).await.unwrap();
+
+    let all = backend
+        .list_resources(&ws_id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        addresses(&all),
+        ["aws_instance.web", "aws_instance.web (deposed)"]
+    );
+    let filter = ResourceFilter {
+        exclude_deposed: true,
+        ..Default::default()
+    };
+    let listed = backend.list_resources(&ws_id, &filter).await.unwrap();
+    assert_eq!(addresses(&listed), ["aws_instance.web"]);
+}
+
+#[test]
+fn test_resource_sort_parse() {
+    assert_eq!(
+        "updated_at".parse::<ResourceSort>().unwrap(),
+        ResourceSort::UpdatedAt
+    );
+    assert_eq!(
+        "resource_type".parse::<ResourceSort>().unwrap(),
+        ResourceSort::ResourceType
+    );
+    assert!("name".parse::<ResourceSort>().is_err());
+}
+
+// ─── Run history ─────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_node_results_land_in_run_resources() {
+    let backend = SqliteBackend::open_memory().unwrap();
+    backend.initialize().await.unwrap();
+    let ws_id = backend.create_workspace("default").await.unwrap();
+    let backend: Arc<dyn StateBackend> = Arc::new(backend);
+    let run_id = backend.start_run(&ws_id, "apply", 2).await.unwrap();
+
+    let hcl = r#"
+resource "null_resource" "ok" {}
+resource "null_resource" "broken" {}
+output "x" { value = 1 }
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    let outputs: NodeOutputs = Arc::new(dashmap::DashMap::new());
+    let captured = Arc::clone(&outputs);
+    let inner: NodeExecutor = Box::new(move |_idx, node: DagNode| {
+        let captured = Arc::clone(&captured);
+        async move {
+            if node.address() == "null_resource.broken" {
+                anyhow::bail!("provider said no");
+            }
+            if matches!(node, DagNode::Resource { .. }) {
+                captured.insert(node.address().to_string(), "hello\n".to_string());
+            }
+            Ok(None)
+        }
+        .boxed()
+    });
+    let actions = HashMap::from([
+        ("null_resource.ok".to_string(), "create".to_string()),
+        ("null_resource.broken".to_string(), "update".to_string()),
+    ]);
+    let record_action: RecordAction = Arc::new(move |node: &DagNode| match node {
+        DagNode::Resource { address, .. } => {
+            Some((actions[address].clone(), Some("{}".to_string())))
+        }
+        _ => None,
+    });
+    let executor = record_node_results(
+        inner,
+        Arc::clone(&backend),
+        &run_id,
+        record_action,
+        Arc::clone(&outputs),
+    );
+
+    for idx in graph.node_indices() {
+        let _ = executor(idx, graph[idx].clone()).await;
+    }
+
+    let rows = backend
+        .query_raw("SELECT resource_address, action, status, error_message, started_at, completed_at FROM run_resources ORDER BY resource_address", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2, "outputs are not recorded: {:?}", rows);
+    assert_eq!(rows[0]["resource_address"], "null_resource.broken");
+    assert_eq!(rows[0]["action"], "update");
+    assert_eq!(rows[0]["status"], "failed");
+    assert_eq!(rows[0]["error_message"], "provider said no");
+    assert_eq!(rows[1]["resource_address"], "null_resource.ok");
+    assert_eq!(rows[1]["status"], "succeeded");
+    assert!(rows[1]["error_message"].is_null());
+    assert!(rows[1]["started_at"].is_string() && rows[1]["completed_at"].is_string());
+    assert!(outputs.is_empty(), "recorded output is drained");
+
+    let history = backend
+        .list_resource_history(&ws_id, "null_resource.ok", 10)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].run_id, run_id);
+    assert_eq!(history[0].operation, "apply");
+    assert_eq!(history[0].result.action, "create");
+    assert_eq!(history[0].result.output.as_deref(), Some("hello\n"));
+}
+
+#[tokio::test]
+async fn test_resource_history_spans_runs() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    for (operation, action) in [
+        ("apply", "create"),
+        ("apply", "update"),
+        ("destroy", "delete"),
+    ] {
+        let run_id = backend.start_run(&ws_id, operation, 1).await.unwrap();
+        let result = ResourceResult {
+            address: "aws_vpc.main".to_string(),
+            action: action.to_string(),
+            status: "succeeded".to_string(),
+            started_at: Some(chrono::Utc::now().to_rfc3339()),
+            completed_at: None,
+            error_message: None,
+            diff_json: None,
+            output: None,
+        };
+        backend
+            .record_resource_result(&run_id, &result)
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    let history = backend
+        .list_resource_history(&ws_id, "aws_vpc.main", 10)
+        .await
+        .unwrap();
+    let actions: Vec<&str> = history.iter().map(|h| h.result.action.as_str()).collect();
+    assert_eq!(actions, ["delete", "update", "create"]);
+    assert_eq!(history[0].operation, "destroy");
+
+    let limited = backend
+        .list_resource_history(&ws_id, "aws_vpc.main", 1)
+        .await
+        .unwrap();
+    assert_eq!(limited.len(), 1);
+    assert!(backend
+        .list_resource_history(&ws_id, "aws_vpc.other", 10)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_list_run_resources_orders_skipped_last() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    let run_id = backend.start_run(&ws_id, "apply", 3).await.unwrap();
+    let result = |address: &str, status: &str, started_at: Option<&str>| ResourceResult {
+        address: address.to_string(),
+        action: "create".to_string(),
+        status: status.to_string(),
+        started_at: started_at.map(str::to_string),
+        completed_at: None,
+        error_message: None,
+        diff_json: None,
+        output: None,
+    };
+    for r in [
+        result("c.skipped", "cancelled", None),
+        result("b.second", "succeeded", Some("2026-10-01T12:00:02+00:00")),
+        result("a.first", "failed", Some("2026-10-01T12:00:01+00:00")),
+    ] {
+        backend.record_resource_result(&run_id, &r).await.unwrap();
+    }
+
+    let results = backend.list_run_resources(&run_id).await.unwrap();
+    let addresses: Vec<&str> = results.iter().map(|r| r.address.as_str()).collect();
+    assert_eq!(addresses, ["a.first", "b.second", "c.skipped"]);
+    assert!(backend
+        .list_run_resources("no-such-run")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+// ─── Workspace lock ──────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_concurrent_workspace_lock_fails_second_caller() {
+    // Two processes opening the same state file
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("oxid.db");
+    let first = SqliteBackend::open(path.to_str().unwrap()).unwrap();
+    first.initialize().await.unwrap();
+    let ws_id = first.create_workspace("default").await.unwrap();
+    let second = SqliteBackend::open(path.to_str().unwrap()).unwrap();
+
+    let (a, b) = tokio::join!(
+        acquire_workspace_lock(&first, &ws_id, "apply"),
+        acquire_workspace_lock(&second, &ws_id, "destroy"),
+    );
+    let (held, err) = match (a, b) {
+        (Ok(lock), Err(e)) | (Err(e), Ok(lock)) => (lock, e.to_string()),
+        (a, b) => panic!("expected exactly one lock holder, got {:?} / {:?}", a, b),
+    };
+    assert!(err.contains("State is locked"), "{}", err);
+    assert!(err.contains(&held.lock_id), "{}", err);
+    assert!(err.contains(&held.locked_by), "{}", err);
+    assert!(
+        err.contains(&format!("Operation: {}", held.operation)),
+        "{}",
+        err
+    );
+    assert!(err.contains(&held.locked_at), "{}", err);
+
+    // Released locks can be taken again
+    first.release_lock(&held.lock_id).await.unwrap();
+    let lock = acquire_workspace_lock(&second, &ws_id, "apply")
+        .await
+        .unwrap();
+    assert_eq!(lock.resource_address, WORKSPACE_LOCK_ADDRESS);
+    assert_eq!(lock.operation, "apply");
+}
+
+#[tokio::test]
+async fn test_force_unlock_releases_workspace_lock() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    acquire_workspace_lock(&backend, &ws_id, "apply")
+        .await
+        .unwrap();
+    assert!(acquire_workspace_lock(&backend, &ws_id, "apply")
+        .await
+        .is_err());
+
+    backend
+        .force_unlock(WORKSPACE_LOCK_ADDRESS, &ws_id)
+        .await
+        .unwrap();
+    assert!(backend
+        .is_locked(WORKSPACE_LOCK_ADDRESS, &ws_id)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(acquire_workspace_lock(&backend, &ws_id, "apply")
+        .await
+        .is_ok());
+}
+
+// ─── Query ───────────────────────────────────────────────────────────────────
+
+async fn resource_count(backend: &SqliteBackend, ws_id: &str) -> usize {
+    backend
+        .list_resources(ws_id, &ResourceFilter::default())
+        .await
+        .unwrap()
+        .len()
+}
+
+#[tokio::test]
+async fn test_query_rejects_non_select() {
+    let (backend, ws_id) = backend_with_resources().await;
+    for sql in [
+        "DELETE FROM resources",
+        "  update resources SET status = 'x'",
+        "DROP TABLE resources",
+        "SELECT 1; DELETE FROM resources",
+    ] {
+        let err = execute_query(&backend, sql, &[], &[], QueryFormat::Json)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Only SELECT") || err.to_string().contains("single statement"),
+            "{}: {}",
+            sql,
+            err
+        );
+    }
+    // Writes hidden behind a WITH clause are caught by SQLite itself
+    let err = execute_query(
+        &backend,
+        "WITH doomed AS (SELECT address FROM resources) DELETE FROM resources WHERE address IN doomed",
+        &[],
+        &[],
+        QueryFormat::Json,
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{}", err);
+    assert_eq!(resource_count(&backend, &ws_id).await, 5);
+}
+
+#[tokio::test]
+async fn test_query_semicolon_inside_string_is_allowed() {
+    let (backend, _) = backend_with_resources().await;
+    let out = execute_query(
+        &backend,
+        "SELECT address, 'a;b' AS note FROM resources WHERE address = 'aws_vpc.main';",
+        &[],
+        &[],
+        QueryFormat::Json,
+    )
+    .await
+    .unwrap();
+    let rows: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+        rows,
+        serde_json::json!([{ "address": "aws_vpc.main", "note": "a;b" }])
+    );
+}
+
+#[tokio::test]
+async fn test_parameterized_query() {
+    let (backend, _) = backend_with_resources().await;
+    let out = execute_query(
+        &backend,
+        "WITH subnets AS (SELECT address FROM resources WHERE resource_type = ?) \
+         SELECT address FROM subnets WHERE address != ? ORDER BY address",
+        &["aws_subnet".to_string(), "aws_subnet.b".to_string()],
+        &[],
+        QueryFormat::Json,
+    )
+    .await
+    .unwrap();
+    let rows: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+        rows,
+        serde_json::json!([{ "address": "aws_subnet.a" }, { "address": "aws_subnet.c" }])
+    );
+
+    let err = execute_query(
+        &backend,
+        "SELECT address FROM resources WHERE resource_type = ?",
+        &[],
+        &[],
+        QueryFormat::Json,
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("1 parameter placeholder(s) but 0"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_query_select_projects_nested_attributes() {
+    let (backend, ws_id) = backend_with_workspace().await;
+    let mut vpc = ResourceState::new(&ws_id, "aws_vpc", "main", "aws_vpc.main");
+    vpc.attributes_json = serde_json::json!({
+        "id": "vpc-123",
+        "cidr_block": "10.0.0.0/16",
+        "tags": { "Name": "main", "Team": "infra" },
+        "ipv6_cidrs": ["2600::/56"],
+    })
+    .to_string();
+    backend.upsert_resource(&vpc).await.unwrap();
+
+    let select: Vec<String> = [
+        "address",
+        "attributes.id",
+        "attributes.tags.Name",
+        "attributes.ipv6_cidrs.0",
+        "attributes.missing",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let sql = "SELECT address, attributes_json AS attributes FROM resources";
+    let out = execute_query(&backend, sql, &[], &select, QueryFormat::Json)
+        .await
+        .unwrap();
+    let rows: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+        rows,
+        serde_json::json!([{
+            "address": "aws_vpc.main",
+            "attributes.id": "vpc-123",
+            "attributes.tags.Name": "main",
+            "attributes.ipv6_cidrs.0": "2600::/56",
+            "attributes.missing": null,
+        }])
+    );
+
+    // Streaming applies the same projection
+    let mut out = Vec::new();
+    stream_query(&backend, sql, &[], &select[..1], &mut out)
+        .await
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "{\"address\":\"aws_vpc.main\"}\n"
+    );
+
+    let err = execute_query(
+        &backend,
+        sql,
+        &[],
+        &["attributes..id".to_string()],
+        QueryFormat::Json,
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("Invalid attribute path"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_jsonl_streams_one_line_per_row() {
+    let (backend, _) = backend_with_resources().await;
+    let mut out = Vec::new();
+    let count = stream_query(
+        &backend,
+        "SELECT address, resource_type FROM resources ORDER BY address",
+        &[],
+        &[],
+        &mut out,
+    )
+    .await
+    .unwrap();
+    assert_eq!(count, 5);
+
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.ends_with('\n'));
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 5);
+    for line in &lines {
+        let row: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert!(row["address"].is_string(), "{}", line);
+    }
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(lines[0]).unwrap(),
+        serde_json::json!({ "address": "aws_instance.web", "resource_type": "aws_instance" })
+    );
+
+    // An empty result writes nothing rather than a "No results." banner
+    let mut out = Vec::new();
+    let count = stream_query(
+        &backend,
+        "SELECT address FROM resources WHERE resource_type = ?",
+        &["aws_lambda_function".to_string()],
+        &[],
+        &mut out,
+    )
+    .await
+    .unwrap();
+    assert_eq!(count, 0);
+    assert!(out.is_empty());
+}
+
+#[tokio::test]
+async fn test_jsonl_stops_when_writer_fails() {
+    struct ClosedAfter(usize);
+    impl std::io::Write for ClosedAfter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf == b"\n" {
+                if self.0 == 0 {
+                    return Err(std::io::ErrorKind::BrokenPipe.into());
+                }
+                self.0 -= 1;
+            }
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let (backend, _) = backend_with_resources().await;
+    let err = stream_query(
+        &backend,
+        "SELECT address FROM resources",
+        &[],
+        &[],
+        &mut ClosedAfter(2),
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("Broken pipe") || err.to_string().contains("broken pipe"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,203 @@
+use oxid::state::address::{import_addresses, plan_move, ResourceAddress};
+use oxid::state::backend::StateBackend;
+use oxid::state::models::{ResourceFilter, ResourceState};
+use oxid::state::sqlite::SqliteBackend;
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+fn resource(address: &str) -> ResourceState {
+    let parsed = ResourceAddress::parse(address).unwrap();
+    let mut r = ResourceState::new("ws", &parsed.resource_type, &parsed.name, address);
+    r.index_key = parsed.index_key;
+    r.module_path = parsed.module_path;
+    r
+}
+
+// ─── Address parsing ─────────────────────────────────────────────────────────
+
+#[test]
+fn test_parse_resource_addresses() {
+    let a = ResourceAddress::parse("aws_instance.web[0]").unwrap();
+    assert_eq!(a.resource_type, "aws_instance");
+    assert_eq!(a.name, "web");
+    assert_eq!(a.index_key.as_deref(), Some("0"));
+    assert_eq!(a.base(), "aws_instance.web");
+
+    let a = ResourceAddress::parse("module.net.module.sub.aws_subnet.private[\"us-east-1.a\"]")
+        .unwrap();
+    assert_eq!(a.module_path, "module.net.module.sub");
+    assert_eq!(a.index_key.as_deref(), Some("us-east-1.a"));
+    assert_eq!(
+        a.to_string(),
+        "module.net.module.sub.aws_subnet.private[\"us-east-1.a\"]"
+    );
+
+    let a = ResourceAddress::parse("data.aws_ami.ubuntu").unwrap();
+    assert_eq!(a.mode, "data");
+    assert_eq!(a.to_string(), "data.aws_ami.ubuntu");
+
+    assert!(ResourceAddress::parse("aws_instance").is_err());
+    assert!(ResourceAddress::parse("aws_instance.web[abc]").is_err());
+    assert!(ResourceAddress::parse("a.b.c.d").is_err());
+}
+
+// ─── Import naming ───────────────────────────────────────────────────────────
+
+#[test]
+fn test_extra_imported_objects_get_config_names() {
+    let target = ResourceAddress::parse("module.net.aws_security_group.web").unwrap();
+    let types = [
+        "aws_security_group_rule",
+        "aws_security_group",
+        "aws_security_group_rule",
+    ];
+    let taken =
+        std::collections::HashSet::from(["module.net.aws_security_group_rule.web_1".to_string()]);
+    let addresses: Vec<String> = import_addresses(&target, &types, &taken)
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    assert_eq!(
+        addresses,
+        [
+            "module.net.aws_security_group_rule.web_2",
+            "module.net.aws_security_group.web",
+            "module.net.aws_security_group_rule.web_3",
+        ]
+    );
+
+    // Each extra name works as a resource block and a reference in config
+    let hcl = r#"
+resource "aws_security_group_rule" "web_2" {}
+
+output "rule" {
+  value = aws_security_group_rule.web_2.id
+}
+"#;
+    let ws = oxid::hcl::parser::parse_hcl(hcl, std::path::Path::new("main.tf")).unwrap();
+    assert_eq!(ws.resources[0].name, "web_2");
+    match &ws.outputs[0].value {
+        oxid::config::types::Expression::Reference(parts) => {
+            assert_eq!(parts, &["aws_security_group_rule", "web_2", "id"]);
+        }
+        other => panic!("expected a reference, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_imported_object_keeps_the_requested_index() {
+    let target = ResourceAddress::parse("aws_instance.web[2]").unwrap();
+    let addresses = import_addresses(&target, &["aws_instance"], &Default::default());
+    assert_eq!(addresses[0].to_string(), "aws_instance.web[2]");
+}
+
+// ─── Move planning ───────────────────────────────────────────────────────────
+
+#[test]
+fn test_move_indexed_instance() {
+    let state = vec![
+        resource("aws_instance.web[0]"),
+        resource("aws_instance.web[1]"),
+    ];
+    let moves = plan_move(&state, "aws_instance.web[0]", "aws_instance.app[0]").unwrap();
+    assert_eq!(moves.len(), 1);
+    let (from, moved) = &moves[0];
+    assert_eq!(from, "aws_instance.web[0]");
+    assert_eq!(moved.address, "aws_instance.app[0]");
+    assert_eq!(moved.resource_name, "app");
+    assert_eq!(moved.index_key.as_deref(), Some("0"));
+    assert_eq!(moved.id, state[0].id, "id must be preserved");
+
+    // Dropping the index
+    let moves = plan_move(&state, "aws_instance.web[1]", "aws_instance.single").unwrap();
+    assert_eq!(moves[0].1.address, "aws_instance.single");
+    assert_eq!(moves[0].1.index_key, None);
+}
+
+#[test]
+fn test_move_whole_base_address() {
+    let state = vec![
+        resource("aws_subnet.private[\"a\"]"),
+        resource("aws_subnet.private[\"b\"]"),
+        resource("aws_subnet.privateish"),
+    ];
+    let mut moves = plan_move(&state, "aws_subnet.private", "module.net.aws_subnet.this").unwrap();
+    moves.sort_by(|a, b| a.0.cmp(&b.0));
+    let addresses: Vec<&str> = moves.iter().map(|(_, m)| m.address.as_str()).collect();
+    assert_eq!(
+        addresses,
+        [
+            "module.net.aws_subnet.this[\"a\"]",
+            "module.net.aws_subnet.this[\"b\"]"
+        ]
+    );
+    assert!(moves.iter().all(|(_, m)| m.module_path == "module.net"));
+
+    let err = plan_move(&state, "aws_subnet.private", "aws_subnet.this[0]").unwrap_err();
+    assert!(err.to_string().contains("Cannot move all instances"));
+}
+
+#[test]
+fn test_move_validation() {
+    let state = vec![
+        resource("aws_instance.web[0]"),
+        resource("aws_instance.app[0]"),
+    ];
+
+    let err = plan_move(&state, "aws_instance.web[0]", "aws_eip.web[0]").unwrap_err();
+    assert!(err.to_string().contains("resource types differ"));
+
+    let err = plan_move(&state, "aws_instance.web[0]", "aws_instance.app[0]").unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+
+    let err = plan_move(&state, "aws_instance.web[5]", "aws_instance.x[5]").unwrap_err();
+    assert!(err.to_string().contains("not found"));
+}
+
+// ─── SQLite ──────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_move_resource_keeps_id_and_dependencies() {
+    let backend = SqliteBackend::open_memory().unwrap();
+    backend.initialize().await.unwrap();
+    let ws_id = backend.create_workspace("default").await.unwrap();
+
+    let mut vpc = resource("aws_vpc.main");
+    vpc.workspace_id = ws_id.clone();
+    let mut web = resource("aws_instance.web[0]");
+    web.workspace_id = ws_id.clone();
+    backend.upsert_resource(&vpc).await.unwrap();
+    backend.upsert_resource(&web).await.unwrap();
+    backend
+        .set_dependencies(&web.id, &[(vpc.id.clone(), "implicit".to_string())])
+        .await
+        .unwrap();
+
+    let state = backend
+        .list_resources(&ws_id, &ResourceFilter::default())
+        .await
+        .unwrap();
+    for (from, moved) in plan_move(&state, "aws_instance.web", "aws_instance.app").unwrap() {
+        backend.move_resource(&ws_id, &from, &moved).await.unwrap();
+    }
+
+    assert!(backend
+        .get_resource(&ws_id, "aws_instance.web[0]")
+        .await
+        .unwrap()
+        .is_none());
+    let moved = backend
+        .get_resource(&ws_id, "aws_instance.app[0]")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(moved.id, web.id);
+    assert_eq!(moved.resource_name, "app");
+    assert_eq!(moved.index_key.as_deref(), Some("0"));
+    assert_eq!(backend.get_dependencies(&moved.id).await.unwrap(), [vpc.id]);
+
+    assert!(backend
+        .move_resource(&ws_id, "aws_instance.gone", &moved)
+        .await
+        .is_err());
+}
@@ -602,3 +602,54 @@ fn test_json_only_directory_mode_detection() {
     let ws = load_workspace(dir.path()).unwrap();
     assert_eq!(ws.resources.len(), 1);
 }
+
+// ─── Mixed-format fixture directory ──────────────────────────────────────────
+
+#[test]
+fn test_mixed_fixture_merges_both_formats() {
+    let ws = load_workspace(Path::new("tests/fixtures/mixed")).unwrap();
+
+    let mut resources: Vec<String> = ws
+        .resources
+        .iter()
+        .map(|r| format!("{}.{}", r.resource_type, r.name))
+        .collect();
+    resources.sort();
+    assert_eq!(
+        resources,
+        [
+            "null_resource.app",
+            "null_resource.main",
+            "null_resource.network"
+        ]
+    );
+    assert_eq!(ws.data_sources.len(), 1);
+    assert_eq!(ws.data_sources[0].name, "zones");
+    assert_eq!(ws.outputs.len(), 1);
+    assert!(ws
+        .terraform_settings
+        .as_ref()
+        .unwrap()
+        .required_providers
+        .contains_key("null"));
+
+    // terraform.tfvars applies to variables declared in either format
+    let replicas = ws.variables.iter().find(|v| v.name == "replicas").unwrap();
+    assert!(matches!(
+        replicas.default,
+        Some(Expression::Literal(Value::Int(3)))
+    ));
+    let prefix = ws.variables.iter().find(|v| v.name == "prefix").unwrap();
+    assert!(matches!(
+        &prefix.default,
+        Some(Expression::Literal(Value::String(s))) if s == "dev"
+    ));
+}
+
+#[test]
+fn test_mixed_files_are_read_in_one_lexical_order() {
+    // app.tf.json < main.tf < network.tf.json, regardless of extension
+    let ws = parse_directory(Path::new("tests/fixtures/mixed")).unwrap();
+    let names: Vec<&str> = ws.resources.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["app", "main", "network"]);
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use oxid::config::loader::load_workspace;
+use oxid::dag::validation::{diagnose, validation_report};
+use oxid::executor::engine::build_variable_defaults;
+use serde_json::json;
+
+/// The `oxid validate --json` report for a fixture under `tests/fixtures/validate`.
+fn report(fixture: &str) -> serde_json::Value {
+    let ws = load_workspace(Path::new(&format!("tests/fixtures/validate/{}", fixture))).unwrap();
+    let diagnostics = diagnose(&ws, &build_variable_defaults(&ws));
+    validation_report(&diagnostics)
+}
+
+#[test]
+fn test_valid_configuration_report() {
+    assert_eq!(
+        report("valid"),
+        json!({
+            "valid": true,
+            "error_count": 0,
+            "warning_count": 0,
+            "diagnostics": [],
+        })
+    );
+}
+
+#[test]
+fn test_invalid_configuration_report() {
+    let report = report("invalid");
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["error_count"], 3);
+    assert_eq!(report["warning_count"], 1);
+
+    let diagnostics = report["diagnostics"].as_array().unwrap();
+    for d in diagnostics {
+        let keys: Vec<&str> = d.as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys, ["address", "detail", "severity", "source", "summary"]);
+    }
+    let find = |summary: &str| {
+        diagnostics
+            .iter()
+            .find(|d| d["summary"] == summary)
+            .unwrap_or_else(|| panic!("no \"{}\" diagnostic in {:#}", summary, report))
+    };
+
+    let depends_on = find("Unknown depends_on reference");
+    assert_eq!(depends_on["severity"], "warning");
+    assert_eq!(depends_on["address"], "aws_eip.ip");
+    assert!(depends_on["detail"]
+        .as_str()
+        .unwrap()
+        .contains("aws_vpc.missing"));
+
+    let instance_key = find("Missing resource instance key");
+    assert_eq!(instance_key["severity"], "error");
+    assert_eq!(instance_key["address"], "aws_eip.ip");
+    assert_eq!(
+        instance_key["source"],
+        "tests/fixtures/validate/invalid/main.tf:18:1"
+    );
+
+    let required = find("No value for required variable");
+    assert_eq!(required["address"], "var.region");
+    assert_eq!(required["source"], serde_json::Value::Null);
+
+    let invalid = find("Invalid value for variable");
+    assert_eq!(invalid["address"], "var.env");
+    assert_eq!(invalid["detail"], "env must be dev or prod.");
+}
@@ -0,0 +1,487 @@
+use std::path::Path;
+
+use oxid::config::types::WorkspaceConfig;
+use oxid::config::types::{Expression, Value};
+use oxid::dag::validation::validate_variables;
+use oxid::executor::engine::build_variable_defaults;
+use oxid::hcl::parse_directory;
+use oxid::hcl::parse_directory_with_overrides;
+use oxid::hcl::parser::parse_hcl;
+use oxid::hcl::variables::{
+    check_required_variables, coerce_value, parse_var_assignment, VarArg, VarType,
+    VariableOverrides,
+};
+use serde_json::json;
+use tempfile::TempDir;
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+/// Parse HCL and return the messages of all failing variable validations.
+fn validation_messages(hcl: &str) -> Vec<String> {
+    let ws = parse_hcl(hcl, Path::new("test.tf")).unwrap();
+    validate_variables(&ws, &build_variable_defaults(&ws))
+        .into_iter()
+        .map(|e| e.message)
+        .collect()
+}
+
+/// Write a main.tf (and optional terraform.tfvars) to a temp dir and parse it.
+fn parse_with_tfvars(main_tf: &str, tfvars: Option<&str>) -> anyhow::Result<WorkspaceConfig> {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.tf"), main_tf).unwrap();
+    if let Some(tfvars) = tfvars {
+        std::fs::write(dir.path().join("terraform.tfvars"), tfvars).unwrap();
+    }
+    parse_directory(dir.path())
+}
+
+// ─── Variable validation ─────────────────────────────────────────────────────
+
+#[test]
+fn test_regex_validation_passes() {
+    let messages = validation_messages(
+        r#"
+variable "region" {
+  default = "us-east-1"
+  validation {
+    condition     = can(regex("^[a-z]+-[a-z]+-[0-9]$", var.region))
+    error_message = "region must look like us-east-1."
+  }
+}
+"#,
+    );
+    assert!(messages.is_empty(), "unexpected errors: {:?}", messages);
+}
+
+#[test]
+fn test_regex_validation_fails() {
+    let messages = validation_messages(
+        r#"
+variable "region" {
+  default = "US_EAST"
+  validation {
+    condition     = can(regex("^[a-z]+-[a-z]+-[0-9]$", var.region))
+    error_message = "region must look like us-east-1."
+  }
+}
+"#,
+    );
+    assert_eq!(messages, vec!["region must look like us-east-1."]);
+}
+
+#[test]
+fn test_length_validation_passes() {
+    let messages = validation_messages(
+        r#"
+variable "name" {
+  default = "web-server"
+  validation {
+    condition     = length(var.name) >= 3 && length(var.name) <= 16
+    error_message = "name must be between 3 and 16 characters."
+  }
+}
+"#,
+    );
+    assert!(messages.is_empty(), "unexpected errors: {:?}", messages);
+}
+
+#[test]
+fn test_mixed_operator_validation() {
+    let config = |name: &str| {
+        format!(
+            r#"
+variable "name" {{
+  default = "{}"
+  validation {{
+    condition     = length(var.name) >= 3 && length(var.name) <= 8 || var.name == "x"
+    error_message = "name must be 3 to 8 characters, or x."
+  }}
+}}
+"#,
+            name
+        )
+    };
+    assert!(validation_messages(&config("web")).is_empty());
+    assert!(validation_messages(&config("x")).is_empty());
+    assert_eq!(
+        validation_messages(&config("web-server")),
+        vec!["name must be 3 to 8 characters, or x."]
+    );
+}
+
+#[test]
+fn test_length_validation_fails() {
+    let messages = validation_messages(
+        r#"
+variable "name" {
+  default = "ab"
+  validation {
+    condition     = length(var.name) >= 3
+    error_message = "name must be at least 3 characters."
+  }
+}
+"#,
+    );
+    assert_eq!(messages, vec!["name must be at least 3 characters."]);
+}
+
+#[test]
+fn test_validation_reports_every_failing_rule() {
+    let messages = validation_messages(
+        r#"
+variable "name" {
+  default = "AB"
+  validation {
+    condition     = length(var.name) >= 3
+    error_message = "too short"
+  }
+  validation {
+    condition     = lower(var.name) == var.name
+    error_message = "must be lowercase"
+  }
+}
+"#,
+    );
+    assert_eq!(messages, vec!["too short", "must be lowercase"]);
+}
+
+#[test]
+fn test_validation_must_reference_only_the_variable() {
+    let messages = validation_messages(
+        r#"
+variable "min" {
+  default = 3
+}
+variable "name" {
+  default = "web"
+  validation {
+    condition     = length(var.name) >= var.min
+    error_message = "too short"
+  }
+}
+"#,
+    );
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains("can only refer to the variable itself"));
+    assert!(messages[0].contains("var.min"));
+}
+
+#[test]
+fn test_validation_skipped_without_value() {
+    let messages = validation_messages(
+        r#"
+variable "name" {
+  validation {
+    condition     = length(var.name) >= 3
+    error_message = "too short"
+  }
+}
+"#,
+    );
+    assert!(messages.is_empty());
+}
+
+#[test]
+fn test_non_bool_validation_condition_fails() {
+    let messages = validation_messages(
+        r#"
+variable "name" {
+  default = "web"
+  validation {
+    condition     = length(var.name)
+    error_message = "too short"
+  }
+}
+"#,
+    );
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains("must be a bool, got 3"));
+}
+
+// ─── Required variables ──────────────────────────────────────────────────────
+
+#[test]
+fn test_missing_required_variable() {
+    let ws = parse_with_tfvars(
+        r#"
+variable "region" {
+  type = string
+}
+variable "env" {
+  default = "dev"
+}
+"#,
+        None,
+    )
+    .unwrap();
+    let err = check_required_variables(&ws).unwrap_err();
+    assert_eq!(err.to_string(), "No value for required variable region");
+}
+
+#[test]
+fn test_required_variable_set_from_tfvars() {
+    let ws = parse_with_tfvars(
+        "variable \"region\" {\n  type = string\n}\n",
+        Some("region = \"eu-west-1\"\n"),
+    )
+    .unwrap();
+    assert!(check_required_variables(&ws).is_ok());
+    assert_eq!(build_variable_defaults(&ws)["region"], json!("eu-west-1"));
+}
+
+// ─── Type coercion ───────────────────────────────────────────────────────────
+
+#[test]
+fn test_parse_type_constraints() {
+    assert_eq!(
+        VarType::parse("list(string)").unwrap(),
+        VarType::List(Box::new(VarType::String))
+    );
+    assert_eq!(
+        VarType::parse("map(object({ name = string, port = optional(number, 80) }))").unwrap(),
+        VarType::Map(Box::new(VarType::Object(vec![
+            ("name".to_string(), VarType::String, false),
+            ("port".to_string(), VarType::Number, true),
+        ])))
+    );
+    assert!(VarType::parse("list(strng)").is_err());
+}
+
+#[test]
+fn test_coerce_primitives() {
+    assert_eq!(
+        coerce_value(json!("5"), &VarType::Number).unwrap(),
+        json!(5)
+    );
+    assert_eq!(
+        coerce_value(json!("2.5"), &VarType::Number).unwrap(),
+        json!(2.5)
+    );
+    assert_eq!(
+        coerce_value(json!(42), &VarType::String).unwrap(),
+        json!("42")
+    );
+    assert_eq!(
+        coerce_value(json!(true), &VarType::String).unwrap(),
+        json!("true")
+    );
+    assert_eq!(
+        coerce_value(json!("false"), &VarType::Bool).unwrap(),
+        json!(false)
+    );
+    assert!(coerce_value(json!("abc"), &VarType::Number).is_err());
+    assert!(coerce_value(json!("yes"), &VarType::Bool).is_err());
+}
+
+#[test]
+fn test_coerce_wraps_scalar_into_list() {
+    let list_of_strings = VarType::List(Box::new(VarType::String));
+    assert_eq!(
+        coerce_value(json!("a"), &list_of_strings).unwrap(),
+        json!(["a"])
+    );
+    assert_eq!(
+        coerce_value(json!([1, "b"]), &list_of_strings).unwrap(),
+        json!(["1", "b"])
+    );
+    // HCL list syntax (as passed through TF_VAR_) is parsed rather than wrapped
+    assert_eq!(
+        coerce_value(json!(r#"["a", "b"]"#), &list_of_strings).unwrap(),
+        json!(["a", "b"])
+    );
+}
+
+#[test]
+fn test_coerce_object_attributes() {
+    let ty = VarType::parse("object({ name = string, port = optional(number) })").unwrap();
+    assert_eq!(
+        coerce_value(json!({"name": "web", "extra": 1}), &ty).unwrap(),
+        json!({"name": "web", "port": null})
+    );
+    let err = coerce_value(json!({"port": 80}), &ty).unwrap_err();
+    assert!(err.to_string().contains("attribute \"name\" is required"));
+}
+
+#[test]
+fn test_tfvars_values_are_coerced_on_load() {
+    let ws = parse_with_tfvars(
+        r#"
+variable "replicas" {
+  type = number
+}
+variable "zones" {
+  type = list(string)
+}
+"#,
+        Some("replicas = \"3\"\nzones = \"us-east-1a\"\n"),
+    )
+    .unwrap();
+    let values = build_variable_defaults(&ws);
+    assert_eq!(values["replicas"], json!(3));
+    assert_eq!(values["zones"], json!(["us-east-1a"]));
+}
+
+#[test]
+fn test_invalid_tfvars_value_fails_load() {
+    let err = parse_with_tfvars(
+        "variable \"replicas\" {\n  type = number\n}\n",
+        Some("replicas = \"three\"\n"),
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Invalid value for variable \"replicas\""));
+}
+
+// ─── Command-line variables ──────────────────────────────────────────────────
+
+#[test]
+fn test_parse_var_assignment_literals() {
+    let literal = |arg: &str| match parse_var_assignment(arg).unwrap() {
+        (_, Expression::Literal(v)) => v,
+        (_, other) => panic!("expected literal for {}, got {:?}", arg, other),
+    };
+    assert!(matches!(literal("n=5"), Value::Int(5)));
+    assert!(matches!(literal("b=true"), Value::Bool(true)));
+    assert!(matches!(literal(r#"s="quoted value""#), Value::String(ref s) if s == "quoted value"));
+    assert!(matches!(literal("s=prod"), Value::String(ref s) if s == "prod"));
+    assert!(matches!(literal("s=a=b"), Value::String(ref s) if s == "a=b"));
+    assert!(matches!(literal(r#"l=["a", "b"]"#), Value::List(ref items) if items.len() == 2));
+    assert!(matches!(literal(r#"m={ k = "v" }"#), Value::Map(ref entries) if entries.len() == 1));
+    assert!(parse_var_assignment("novalue").is_err());
+}
+
+#[test]
+fn test_cli_var_overrides_tfvars() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("main.tf"),
+        "variable \"env\" {\n  default = \"dev\"\n}\nvariable \"replicas\" {\n  type = number\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("terraform.tfvars"),
+        "env = \"staging\"\nreplicas = 2\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("custom.tfvars"), "replicas = 4\n").unwrap();
+
+    let var_file = dir.path().join("custom.tfvars").display().to_string();
+    let overrides = VariableOverrides::from_args(&[
+        VarArg::Var("env=prod".to_string()),
+        VarArg::File(var_file.clone()),
+    ])
+    .unwrap();
+    let ws = parse_directory_with_overrides(dir.path(), &overrides).unwrap();
+    let values = build_variable_defaults(&ws);
+    assert_eq!(values["env"], json!("prod"));
+    assert_eq!(values["replicas"], json!(4));
+
+    // A later --var wins over an earlier --var-file, and values are coerced
+    // to the declared type
+    let overrides = VariableOverrides::from_args(&[
+        VarArg::File(var_file.clone()),
+        VarArg::Var("replicas=\"6\"".to_string()),
+    ])
+    .unwrap();
+    let ws = parse_directory_with_overrides(dir.path(), &overrides).unwrap();
+    assert_eq!(build_variable_defaults(&ws)["replicas"], json!(6));
+
+    // ...and a later --var-file wins over an earlier --var, as in Terraform
+    let overrides = VariableOverrides::from_args(&[
+        VarArg::Var("replicas=\"6\"".to_string()),
+        VarArg::File(var_file),
+    ])
+    .unwrap();
+    let ws = parse_directory_with_overrides(dir.path(), &overrides).unwrap();
+    assert_eq!(build_variable_defaults(&ws)["replicas"], json!(4));
+}
+
+#[test]
+fn test_missing_var_file_fails() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.tf"), "variable \"env\" {}\n").unwrap();
+    let overrides =
+        VariableOverrides::from_args(&[VarArg::File("does-not-exist.tfvars".to_string())]).unwrap();
+    let err = parse_directory_with_overrides(dir.path(), &overrides).unwrap_err();
+    assert!(format!("{:#}", err).contains("does-not-exist.tfvars"));
+}
+
+#[test]
+fn test_selected_workspace_tfvars_override_defaults() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("main.tf"),
+        "variable \"env\" {\n  default = \"dev\"\n}\nvariable \"size\" {\n  default = \"small\"\n}\nvariable \"zone\" {\n  default = \"a\"\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("common.auto.tfvars"), "size = \"medium\"\n").unwrap();
+    std::fs::write(dir.path().join("default.tfvars"), "env = \"default\"\n").unwrap();
+    std::fs::write(
+        dir.path().join("prod.tfvars"),
+        "env = \"prod\"\nsize = \"large\"\nzone = \"b\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("workspaces")).unwrap();
+    std::fs::write(dir.path().join("workspaces/prod.tfvars"), "zone = \"c\"\n").unwrap();
+
+    let values = |overrides: VariableOverrides| {
+        build_variable_defaults(&parse_directory_with_overrides(dir.path(), &overrides).unwrap())
+    };
+
+    let prod = values(VariableOverrides::default().with_workspace("prod"));
+    assert_eq!(prod["env"], json!("prod"));
+    assert_eq!(prod["size"], json!("large"));
+    assert_eq!(prod["zone"], json!("c"));
+
+    let default = values(VariableOverrides::default().with_workspace("default"));
+    assert_eq!(default["env"], json!("default"));
+    assert_eq!(default["size"], json!("medium"));
+    assert_eq!(default["zone"], json!("a"));
+
+    // Without a selected workspace neither file is read
+    let none = values(VariableOverrides::default());
+    assert_eq!(none["env"], json!("dev"));
+
+    // Command-line values still win
+    let overrides = VariableOverrides::from_args(&[VarArg::Var("size=tiny".to_string())])
+        .unwrap()
+        .with_workspace("prod");
+    assert_eq!(values(overrides)["size"], json!("tiny"));
+}
+
+// ─── JSON tfvars ─────────────────────────────────────────────────────────────
+
+#[test]
+fn test_tfvars_json_files_override_defaults() {
+    let ws = parse_directory(Path::new("tests/fixtures/tfvars-json")).unwrap();
+    let values = build_variable_defaults(&ws);
+
+    assert_eq!(values["region"], json!("eu-west-1"));
+    assert_eq!(values["tags"], json!({"team": "infra", "env": "dev"}));
+    // terraform.tfvars.json is read after terraform.tfvars
+    assert_eq!(values["size"], json!("large"));
+    // *.auto.tfvars.json sort with *.auto.tfvars by file name
+    assert_eq!(values["zone"], json!("c"));
+    assert_eq!(values["instance_count"], json!(3));
+}
+
+#[test]
+fn test_var_file_accepts_tfvars_json() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("main.tf"),
+        "variable \"size\" {\n  default = \"small\"\n}\n",
+    )
+    .unwrap();
+    let var_file = dir.path().join("prod.tfvars.json");
+    std::fs::write(&var_file, r#"{"size": "xlarge"}"#).unwrap();
+
+    let overrides =
+        VariableOverrides::from_args(&[VarArg::File(var_file.display().to_string())]).unwrap();
+    let ws = parse_directory_with_overrides(dir.path(), &overrides).unwrap();
+    assert_eq!(build_variable_defaults(&ws)["size"], json!("xlarge"));
+
+    std::fs::write(&var_file, r#"["not", "an", "object"]"#).unwrap();
+    assert!(parse_directory_with_overrides(dir.path(), &overrides).is_err());
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::FutureExt;
+use oxid::dag::resource_graph::build_resource_dag;
+use oxid::dag::walker::{DagWalker, NodeExecutor, NodeStatus, WalkMode, CANCELLED};
+use oxid::executor::engine::{
+    build_provider_limits, build_provider_map, build_variable_defaults, emit_apply_events,
+    replace_failure, ApplyEvent, ApplySummary, ResourceAction,
+};
+use oxid::hcl::parser::parse_hcl;
+use tokio_util::sync::CancellationToken;
+
+// ─── Helper ──────────────────────────────────────────────────────────────────
+
+/// Executor that records the peak number of nodes running at the same time.
+fn concurrency_probe(peak: Arc<AtomicUsize>) -> NodeExecutor {
+    let running = Arc::new(AtomicUsize::new(0));
+    Box::new(move |_idx, _node| {
+        let running = Arc::clone(&running);
+        let peak = Arc::clone(&peak);
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(None)
+        }
+        .boxed()
+    })
+}
+
+// ─── Per-provider parallelism ────────────────────────────────────────────────
+
+#[test]
+fn test_parse_provider_max_parallel() {
+    let hcl = r#"
+provider "aws" {
+  region       = "us-east-1"
+  max_parallel = 4
+}
+provider "null" {}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let aws = ws.providers.iter().find(|p| p.name == "aws").unwrap();
+    assert_eq!(aws.max_parallel, Some(4));
+    assert!(!aws.config.contains_key("max_parallel"));
+
+    let limits = build_provider_limits(&ws);
+    assert_eq!(limits.get("hashicorp/aws"), Some(&4));
+    assert!(!limits.contains_key("hashicorp/null"));
+}
+
+#[test]
+fn test_parse_provider_max_parallel_rejects_zero() {
+    let hcl = r#"
+provider "aws" {
+  max_parallel = 0
+}
+"#;
+    assert!(parse_hcl(hcl, Path::new("main.tf")).is_err());
+}
+
+#[tokio::test]
+async fn test_provider_limit_of_one_serializes_resources() {
+    let hcl = r#"
+provider "null" {
+  max_parallel = 1
+}
+provider "random" {}
+
+resource "null_resource" "a" {}
+resource "null_resource" "b" {}
+resource "null_resource" "c" {}
+resource "random_pet" "a" {}
+resource "random_pet" "b" {}
+resource "random_pet" "c" {}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, node_map) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    // Limited provider: never more than one node in flight
+    let null_only = {
+        let mut g = graph.clone();
+        g.retain_nodes(|g, idx| g[idx].address().starts_with("null_resource."));
+        g
+    };
+    let peak = Arc::new(AtomicUsize::new(0));
+    let results = DagWalker::new(10)
+        .with_provider_limits(build_provider_limits(&ws))
+        .walk(
+            &null_only,
+            Arc::new(concurrency_probe(Arc::clone(&peak))),
+            WalkMode::Apply,
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|r| r.status == NodeStatus::Succeeded));
+    assert_eq!(peak.load(Ordering::SeqCst), 1);
+    // Each result is timed and attributed to its provider
+    for result in &results {
+        assert!(result.duration >= std::time::Duration::from_millis(50));
+        assert_eq!(result.provider.as_deref(), Some("hashicorp/null"));
+    }
+
+    // Unlimited provider in the same walk still uses the global parallelism
+    let peak = Arc::new(AtomicUsize::new(0));
+    let results = DagWalker::new(10)
+        .with_provider_limits(build_provider_limits(&ws))
+        .walk(
+            &graph,
+            Arc::new(concurrency_probe(Arc::clone(&peak))),
+            WalkMode::Apply,
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), node_map.len());
+    assert!(peak.load(Ordering::SeqCst) > 1);
+}
+
+// ─── Cancellation ────────────────────────────────────────────────────────────
+
+/// Executor that cancels `token` from inside the first node it runs, then
+/// lets that node finish. Records every address it executes.
+fn cancel_on_first_node(
+    token: CancellationToken,
+    started: Arc<std::sync::Mutex<Vec<String>>>,
+) -> NodeExecutor {
+    Box::new(move |_idx, node| {
+        let token = token.clone();
+        let started = Arc::clone(&started);
+        async move {
+            started.lock().unwrap().push(node.address().to_string());
+            token.cancel();
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            Ok(None)
+        }
+        .boxed()
+    })
+}
+
+fn status_of<'a>(results: &'a [oxid::dag::walker::NodeResult], address: &str) -> &'a NodeStatus {
+    &results
+        .iter()
+        .find(|r| r.address == address)
+        .unwrap()
+        .status
+}
+
+#[tokio::test]
+async fn test_cancellation_stops_dependents_from_starting() {
+    let hcl = r#"
+resource "null_resource" "a" {}
+resource "null_resource" "b" {
+  depends_on = [null_resource.a]
+}
+resource "null_resource" "c" {
+  depends_on = [null_resource.b]
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    let token = CancellationToken::new();
+    let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let results = DagWalker::new(10)
+        .with_cancellation(token.clone())
+        .walk(
+            &graph,
+            Arc::new(cancel_on_first_node(token, Arc::clone(&started))),
+            WalkMode::Apply,
+        )
+        .await
+        .unwrap();
+
+    // The in-flight node finished; nothing after it started
+    assert_eq!(*started.lock().unwrap(), vec!["null_resource.a"]);
+    assert_eq!(
+        status_of(&results, "null_resource.a"),
+        &NodeStatus::Succeeded
+    );
+    for address in ["null_resource.b", "null_resource.c"] {
+        assert_eq!(
+            status_of(&results, address),
+            &NodeStatus::Skipped(CANCELLED.to_string())
+        );
+    }
+    assert_eq!(results.len(), 3);
+}
+
+#[tokio::test]
+async fn test_cancellation_skips_nodes_queued_for_a_slot() {
+    let hcl = r#"
+resource "null_resource" "a" {}
+resource "null_resource" "b" {}
+resource "null_resource" "c" {}
+resource "null_resource" "d" {}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    // One slot: the first node runs, the others wait on the semaphore
+    let token = CancellationToken::new();
+    let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let results = DagWalker::new(1)
+        .with_cancellation(token.clone())
+        .walk(
+            &graph,
+            Arc::new(cancel_on_first_node(token, Arc::clone(&started))),
+            WalkMode::Apply,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(started.lock().unwrap().len(), 1);
+    assert_eq!(results.len(), 4);
+    assert_eq!(
+        results
+            .iter()
+            .filter(|r| r.status == NodeStatus::Skipped(CANCELLED.to_string()))
+            .count(),
+        3
+    );
+}
+
+// ─── Failed replacements ─────────────────────────────────────────────────────
+
+/// Executor that fails the replacement create for `address` after its prior
+/// object was destroyed (`destroyed`), or before anything was destroyed.
+fn failing_replace(address: &'static str, destroyed: bool) -> NodeExecutor {
+    Box::new(move |_idx, node| {
+        let is_target = node.address() == address;
+        async move {
+            if is_target {
+                let err = anyhow::anyhow!("InvalidAMIID.NotFound");
+                return Err(replace_failure(err, address, destroyed));
+            }
+            Ok(None)
+        }
+        .boxed()
+    })
+}
+
+#[tokio::test]
+async fn test_create_failure_after_destroy_is_reported_distinctly() {
+    let hcl = r#"
+resource "aws_instance" "web" {}
+resource "aws_eip" "ip" {
+  instance = aws_instance.web.id
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    let results = DagWalker::new(10)
+        .walk(
+            &graph,
+            Arc::new(failing_replace("aws_instance.web", true)),
+            WalkMode::Apply,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        status_of(&results, "aws_instance.web"),
+        &NodeStatus::ReplaceFailedAfterDestroy("InvalidAMIID.NotFound".to_string())
+    );
+    assert!(status_of(&results, "aws_instance.web").is_failure());
+    // Dependents are skipped as for any failure
+    assert!(matches!(
+        status_of(&results, "aws_eip.ip"),
+        NodeStatus::Skipped(_)
+    ));
+
+    colored::control::set_override(false);
+    let summary = ApplySummary {
+        results,
+        added: 1,
+        changed: 0,
+        destroyed: 0,
+        failed: 1,
+        skipped: 1,
+        elapsed_secs: 3,
+        is_destroy: false,
+    };
+    assert_eq!(summary.destroyed_not_recreated(), ["aws_instance.web"]);
+    let text = summary.to_string();
+    assert!(
+        text.contains(
+            "Warning: 1 resource(s) were destroyed but failed to recreate: aws_instance.web"
+        ),
+        "{}",
+        text
+    );
+
+    // Nothing destroyed yet (e.g. create_before_destroy): an ordinary failure
+    let results = DagWalker::new(10)
+        .walk(
+            &graph,
+            Arc::new(failing_replace("aws_instance.web", false)),
+            WalkMode::Apply,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        status_of(&results, "aws_instance.web"),
+        &NodeStatus::Failed("InvalidAMIID.NotFound".to_string())
+    );
+}
+
+// ─── Provider dependencies ───────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_provider_is_configured_after_the_resource_it_references() {
+    let hcl = r#"
+provider "kubernetes" {
+  host = aws_eks_cluster.main.endpoint
+}
+
+resource "aws_eks_cluster" "main" {}
+resource "kubernetes_namespace" "app" {}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = Arc::clone(&order);
+    let executor: NodeExecutor = Box::new(move |_idx, node| {
+        seen.lock().unwrap().push(node.address().to_string());
+        async { Ok(None) }.boxed()
+    });
+    let results = DagWalker::new(10)
+        .walk(&graph, Arc::new(executor), WalkMode::Apply)
+        .await
+        .unwrap();
+    assert!(results.iter().all(|r| r.status == NodeStatus::Succeeded));
+    assert_eq!(
+        *order.lock().unwrap(),
+        [
+            "aws_eks_cluster.main",
+            "provider.kubernetes",
+            "kubernetes_namespace.app"
+        ]
+    );
+}
+
+// ─── Apply events ────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_apply_events_complete_once_per_resource() {
+    let hcl = r#"
+resource "null_resource" "a" {}
+resource "null_resource" "b" {
+  depends_on = [null_resource.a]
+}
+resource "null_resource" "broken" {}
+
+data "null_data_source" "c" {}
+
+output "a_id" {
+  value = null_resource.a.id
+}
+"#;
+    let ws = parse_hcl(hcl, Path::new("main.tf")).unwrap();
+    let (graph, _) =
+        build_resource_dag(&ws, &build_provider_map(&ws), &build_variable_defaults(&ws)).unwrap();
+
+    let executor: NodeExecutor = Box::new(|_idx, node| {
+        let address = node.address().to_string();
+        async move {
+            if address == "null_resource.broken" {
+                anyhow::bail!("provider said no");
+            }
+            Ok(None)
+        }
+        .boxed()
+    });
+    let actions = HashMap::from([
+        ("null_resource.a".to_string(), ResourceAction::Create),
+        ("null_resource.b".to_string(), ResourceAction::Update),
+    ]);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let executor = emit_apply_events(executor, tx, actions);
+    DagWalker::new(4)
+        .walk(&graph, Arc::new(executor), WalkMode::Apply)
+        .await
+        .unwrap();
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+    let mut completed: Vec<(String, ResourceAction)> = events
+        .iter()
+        .filter_map(|e| match e {
+            ApplyEvent::ResourceCompleted {
+                address, action, ..
+            } => Some((address.clone(), action.clone())),
+            _ => None,
+        })
+        .collect();
+    completed.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        completed,
+        [
+            ("data.null_data_source.c".to_string(), ResourceAction::Read),
+            ("null_resource.a".to_string(), ResourceAction::Create),
+            ("null_resource.b".to_string(), ResourceAction::Update),
+        ]
+    );
+
+    let failed: Vec<&ApplyEvent> = events
+        .iter()
+        .filter(|e| matches!(e, ApplyEvent::ResourceFailed { .. }))
+        .collect();
+    assert_eq!(
+        failed,
+        [&ApplyEvent::ResourceFailed {
+            address: "null_resource.broken".to_string(),
+            error: "provider said no".to_string(),
+        }]
+    );
+
+    // Every resource is announced before it completes; outputs aren't resources
+    let started = |address: &str| {
+        events.iter().position(
+            |e| matches!(e, ApplyEvent::ResourceStarted { address: a, .. } if a == address),
+        )
+    };
+    let finished = |address: &str| {
+        events.iter().position(
+            |e| matches!(e, ApplyEvent::ResourceCompleted { address: a, .. } if a == address),
+        )
+    };
+    assert!(started("null_resource.b").unwrap() < finished("null_resource.b").unwrap());
+    assert!(finished("null_resource.a").unwrap() < started("null_resource.b").unwrap());
+    assert_eq!(events.len(), 8);
+
+    let json = serde_json::to_value(&events[events.len() - 1]).unwrap();
+    assert!(json.get("event").is_some(), "{}", json);
+}